@@ -0,0 +1,119 @@
+//! Times the per-bin queries on a database with tens of thousands of requests, first
+//! with the migration-managed indexes and then with them dropped.
+//!
+//! Run with `cargo bench --bench indexes`.
+
+use rustbin::models::LoggedRequest;
+use rustbin::storage::{sqlite::MIGRATOR, NewBin, PageRequest, RequestFilter, SqliteStorage, Storage};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+const LARGE_BIN_REQUESTS: usize = 50_000;
+const OTHER_BINS: usize = 20;
+const OTHER_BIN_REQUESTS: usize = 1_000;
+const ITERATIONS: u32 = 50;
+
+const INDEXES: &[&str] = &["requests_bin_id", "requests_bin_timestamp", "requests_timestamp", "bins_last_updated"];
+
+fn request(i: usize) -> LoggedRequest {
+    LoggedRequest {
+        method: if i.is_multiple_of(2) { "POST" } else { "GET" }.to_string(),
+        headers: r#"[["content-type","application/json"]]"#.to_string(),
+        body: Some(format!(r#"{{"sequence": {}}}"#, i)),
+        body_encoding: Some("utf8".to_string()),
+        content_encoding: None,
+        timestamp: format!("2024-01-01T00:{:02}:{:02}+00:00", (i / 60) % 60, i % 60),
+        request_id: Uuid::new_v4(),
+        body_format: Some("json".to_string()),
+        content_type_mismatch: false,
+        query_string: None,
+        query_params: None,
+        source_ip: None,
+        source_port: None,
+        path: Some("/".to_string()),
+        signature_valid: None,
+        fields: Default::default(),
+        pinned: false,
+        chaos: None,
+    }
+}
+
+async fn fill_bin(storage: &SqliteStorage, bin_id: &str, count: usize) {
+    storage
+        .create_bin(NewBin { id: bin_id.to_string(), last_updated: "2024-01-01T00:00:00+00:00".to_string(), ..Default::default() })
+        .await
+        .unwrap();
+    let requests: Vec<_> = (0..count).map(request).collect();
+    for chunk in requests.chunks(1_000) {
+        storage.insert_requests(bin_id, chunk, i64::MAX, "2024-01-01T00:00:00+00:00").await.unwrap();
+    }
+}
+
+async fn time<F, Fut>(mut run: F) -> Duration
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        run().await;
+    }
+    start.elapsed() / ITERATIONS
+}
+
+async fn measure(storage: &SqliteStorage, large_bin: &str, small_bin: &str) -> Vec<(&'static str, Duration)> {
+    let page = PageRequest { after: None, limit: 50, offset: 0 };
+    let recent = RequestFilter { since: Some("2024-01-01T00:59:00+00:00".to_string()), ..Default::default() };
+    let mut results = Vec::new();
+
+    results.push(("inspect small bin", time(|| async {
+        storage.list_requests(small_bin, &RequestFilter::default(), page).await.unwrap();
+    }).await));
+    results.push(("inspect large bin since", time(|| async {
+        storage.list_requests(large_bin, &recent, page).await.unwrap();
+    }).await));
+    results.push(("capture with FIFO trim", time(|| async {
+        storage.insert_requests(large_bin, &[request(0)], LARGE_BIN_REQUESTS as i64, "2024-01-02T00:00:00+00:00").await.unwrap();
+    }).await));
+    results.push(("find inactive bins", time(|| async {
+        storage.inactive_bins("2023-12-31T00:00:00+00:00", None).await.unwrap();
+    }).await));
+    results
+}
+
+#[tokio::main]
+async fn main() {
+    let dir = tempfile::tempdir().unwrap();
+    let options = SqliteConnectOptions::new().filename(dir.path().join("bench.db")).create_if_missing(true);
+    let pool = SqlitePoolOptions::new().max_connections(1).connect_with(options).await.unwrap();
+    MIGRATOR.run(&pool).await.unwrap();
+    let storage = SqliteStorage::new(pool.clone());
+
+    let mut small_bin = String::new();
+    for _ in 0..OTHER_BINS {
+        small_bin = Uuid::new_v4().to_string();
+        fill_bin(&storage, &small_bin, OTHER_BIN_REQUESTS).await;
+    }
+    let large_bin = Uuid::new_v4().to_string();
+    fill_bin(&storage, &large_bin, LARGE_BIN_REQUESTS).await;
+    let total = LARGE_BIN_REQUESTS + OTHER_BINS * OTHER_BIN_REQUESTS;
+    println!("{} requests, largest bin holds {}, mean of {} runs\n", total, LARGE_BIN_REQUESTS, ITERATIONS);
+
+    let indexed = measure(&storage, &large_bin, &small_bin).await;
+    for index in INDEXES {
+        sqlx::query(&format!("DROP INDEX {}", index)).execute(&pool).await.unwrap();
+    }
+    let unindexed = measure(&storage, &large_bin, &small_bin).await;
+
+    println!("{:<26}{:>14}{:>14}{:>10}", "query", "indexed", "no indexes", "speedup");
+    for ((name, with), (_, without)) in indexed.iter().zip(&unindexed) {
+        println!(
+            "{:<26}{:>14?}{:>14?}{:>9.1}x",
+            name,
+            with,
+            without,
+            without.as_secs_f64() / with.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,6 @@
+// Rebuild when a migration or a web UI file is added or edited, since `sqlx::migrate!`
+// and `RustEmbed` embed them
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+    println!("cargo:rerun-if-changed=ui");
+}
@@ -1,3 +1,7 @@
+-- Bins and the requests captured in them, as the old hand-applied schema.sql created
+-- them. `IF NOT EXISTS` lets those databases adopt the migration history; the columns
+-- added since then come in 0033.
+
 CREATE TABLE IF NOT EXISTS bins (
     id TEXT UNIQUE PRIMARY KEY,
     last_updated TEXT
@@ -0,0 +1,26 @@
+-- Full-text index over request bodies, kept in sync with the requests table by triggers
+CREATE VIRTUAL TABLE IF NOT EXISTS requests_fts USING fts5(
+    body,
+    content='requests',
+    content_rowid='id'
+);
+
+CREATE TRIGGER IF NOT EXISTS requests_fts_insert AFTER INSERT ON requests BEGIN
+    INSERT INTO requests_fts(rowid, body)
+    VALUES (new.id, new.body);
+END;
+
+CREATE TRIGGER IF NOT EXISTS requests_fts_delete AFTER DELETE ON requests BEGIN
+    INSERT INTO requests_fts(requests_fts, rowid, body)
+    VALUES ('delete', old.id, old.body);
+END;
+
+CREATE TRIGGER IF NOT EXISTS requests_fts_update AFTER UPDATE OF body ON requests BEGIN
+    INSERT INTO requests_fts(requests_fts, rowid, body)
+    VALUES ('delete', old.id, old.body);
+    INSERT INTO requests_fts(rowid, body)
+    VALUES (new.id, new.body);
+END;
+
+-- Index requests stored before the full-text index existed
+INSERT INTO requests_fts(requests_fts) VALUES ('rebuild');
@@ -0,0 +1,2 @@
+-- Key of a body kept in the blob store instead of the `body` column
+ALTER TABLE requests ADD COLUMN body_ref TEXT;
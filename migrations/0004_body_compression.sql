@@ -0,0 +1,24 @@
+-- Codec of a compressed `body` ('zstd'); NULL when the body is stored as text
+ALTER TABLE requests ADD COLUMN body_compression TEXT;
+
+-- Compressed bodies are binary, so keep them out of the full-text index
+DROP TRIGGER IF EXISTS requests_fts_insert;
+DROP TRIGGER IF EXISTS requests_fts_delete;
+DROP TRIGGER IF EXISTS requests_fts_update;
+
+CREATE TRIGGER requests_fts_insert AFTER INSERT ON requests BEGIN
+    INSERT INTO requests_fts(rowid, body)
+    SELECT new.id, new.body WHERE new.body_compression IS NULL;
+END;
+
+CREATE TRIGGER requests_fts_delete AFTER DELETE ON requests BEGIN
+    INSERT INTO requests_fts(requests_fts, rowid, body)
+    SELECT 'delete', old.id, old.body WHERE old.body_compression IS NULL;
+END;
+
+CREATE TRIGGER requests_fts_update AFTER UPDATE OF body, body_compression ON requests BEGIN
+    INSERT INTO requests_fts(requests_fts, rowid, body)
+    SELECT 'delete', old.id, old.body WHERE old.body_compression IS NULL;
+    INSERT INTO requests_fts(rowid, body)
+    SELECT new.id, new.body WHERE new.body_compression IS NULL;
+END;
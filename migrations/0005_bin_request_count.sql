@@ -0,0 +1,16 @@
+-- Requests are always looked up per bin, oldest or newest first
+CREATE INDEX IF NOT EXISTS requests_bin_id ON requests(bin_id, id);
+
+-- Number of requests in each bin, kept current by triggers so captures don't have
+-- to count the bin's rows to enforce `max_requests_per_bin`
+ALTER TABLE bins ADD COLUMN request_count INTEGER NOT NULL DEFAULT 0;
+
+UPDATE bins SET request_count = (SELECT COUNT(*) FROM requests WHERE requests.bin_id = bins.id);
+
+CREATE TRIGGER requests_count_insert AFTER INSERT ON requests BEGIN
+    UPDATE bins SET request_count = request_count + 1 WHERE id = new.bin_id;
+END;
+
+CREATE TRIGGER requests_count_delete AFTER DELETE ON requests BEGIN
+    UPDATE bins SET request_count = request_count - 1 WHERE id = old.bin_id;
+END;
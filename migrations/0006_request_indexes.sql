@@ -0,0 +1,9 @@
+-- Inspect filters a bin by capture time; admin search and stats filter every bin by it
+CREATE INDEX IF NOT EXISTS requests_bin_timestamp ON requests(bin_id, timestamp);
+CREATE INDEX IF NOT EXISTS requests_timestamp ON requests(timestamp);
+
+-- Cleanup looks for bins idle since a cutoff; the admin bin list sorts by recency
+CREATE INDEX IF NOT EXISTS bins_last_updated ON bins(last_updated);
+
+-- The FIFO trim (`SELECT id ... WHERE bin_id = ? ORDER BY id LIMIT ?`) is answered
+-- from requests_bin_id (0005) alone: `id` is the rowid, so that index already covers it.
@@ -0,0 +1,26 @@
+-- URLs every request captured by a bin is mirrored to
+CREATE TABLE IF NOT EXISTS forwards (
+    id TEXT PRIMARY KEY,
+    bin_id TEXT NOT NULL,
+    url TEXT NOT NULL,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (bin_id) REFERENCES bins(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS forwards_bin_id ON forwards(bin_id, created_at);
+
+-- Outcome of each attempt to mirror a request; `request_id` is not a foreign key
+-- because the request may be trimmed from its bin long before the record goes
+CREATE TABLE IF NOT EXISTS forward_deliveries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    forward_id TEXT NOT NULL,
+    request_id TEXT NOT NULL,
+    status TEXT NOT NULL,
+    status_code INTEGER,
+    error TEXT,
+    attempted_at TEXT NOT NULL,
+    FOREIGN KEY (forward_id) REFERENCES forwards(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS forward_deliveries_forward_id ON forward_deliveries(forward_id, id);
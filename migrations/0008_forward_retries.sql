@@ -0,0 +1,7 @@
+-- A delivery row now covers every attempt at mirroring one request to one forward.
+-- `next_attempt_at` is set while the delivery is pending a retry.
+ALTER TABLE forward_deliveries ADD COLUMN attempts INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE forward_deliveries ADD COLUMN next_attempt_at TEXT;
+
+CREATE INDEX IF NOT EXISTS forward_deliveries_pending
+    ON forward_deliveries(next_attempt_at) WHERE next_attempt_at IS NOT NULL;
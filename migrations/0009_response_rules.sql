@@ -0,0 +1,13 @@
+-- Conditional responses of a bin, evaluated in `position` order. `response` holds the
+-- same JSON as `bins.response`.
+CREATE TABLE IF NOT EXISTS response_rules (
+    id TEXT PRIMARY KEY,
+    bin_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    method TEXT,
+    path TEXT,
+    response TEXT NOT NULL,
+    FOREIGN KEY (bin_id) REFERENCES bins(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS response_rules_bin_id ON response_rules(bin_id, position);
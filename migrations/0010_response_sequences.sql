@@ -0,0 +1,9 @@
+-- Responses a bin hands out one after another. `responses` is a JSON array of custom
+-- responses and `position` counts how many have been served.
+CREATE TABLE IF NOT EXISTS response_sequences (
+    bin_id TEXT PRIMARY KEY,
+    mode TEXT NOT NULL,
+    responses TEXT NOT NULL,
+    position INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY (bin_id) REFERENCES bins(id) ON DELETE CASCADE
+);
@@ -0,0 +1,12 @@
+-- Shared secret a bin's captures are signed with. `header` names the signature
+-- header for the generic `hmac` provider.
+CREATE TABLE IF NOT EXISTS signature_verifications (
+    bin_id TEXT PRIMARY KEY,
+    provider TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    header TEXT,
+    FOREIGN KEY (bin_id) REFERENCES bins(id) ON DELETE CASCADE
+);
+
+-- Outcome of the check; NULL when the bin had no verification configured
+ALTER TABLE requests ADD COLUMN signature_valid INTEGER;
@@ -0,0 +1,8 @@
+-- Per-bin redaction applied before captures are stored. Both columns hold JSON arrays
+-- of strings.
+CREATE TABLE IF NOT EXISTS redaction_rules (
+    bin_id TEXT PRIMARY KEY,
+    headers TEXT NOT NULL,
+    body_patterns TEXT NOT NULL,
+    FOREIGN KEY (bin_id) REFERENCES bins(id) ON DELETE CASCADE
+);
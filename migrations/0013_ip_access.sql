@@ -0,0 +1,22 @@
+-- Source networks a bin accepts captures from. `allow` and `deny` hold JSON arrays of
+-- addresses or CIDR ranges.
+CREATE TABLE IF NOT EXISTS ip_access_rules (
+    bin_id TEXT PRIMARY KEY,
+    allow TEXT NOT NULL,
+    deny TEXT NOT NULL,
+    log_rejected INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY (bin_id) REFERENCES bins(id) ON DELETE CASCADE
+);
+
+-- Captures turned away by those rules, kept apart from the bin's requests
+CREATE TABLE IF NOT EXISTS rejected_requests (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    bin_id TEXT NOT NULL,
+    source_ip TEXT NOT NULL,
+    method TEXT NOT NULL,
+    path TEXT NOT NULL,
+    timestamp TEXT NOT NULL,
+    FOREIGN KEY (bin_id) REFERENCES bins(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_rejected_requests_bin_id ON rejected_requests (bin_id, id);
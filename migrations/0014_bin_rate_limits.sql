@@ -0,0 +1,8 @@
+-- Per-bin capture rate overriding `[rate_limiting] per_bin_requests_per_second`.
+-- A rate of 0 leaves the bin unlimited.
+CREATE TABLE IF NOT EXISTS bin_rate_limits (
+    bin_id TEXT PRIMARY KEY,
+    requests_per_second REAL NOT NULL,
+    burst_size INTEGER NOT NULL,
+    FOREIGN KEY (bin_id) REFERENCES bins(id) ON DELETE CASCADE
+);
@@ -0,0 +1,11 @@
+-- Chat webhook a bin posts a summary of matching captures to.
+-- `methods` is a JSON array; empty matches every method.
+CREATE TABLE IF NOT EXISTS bin_notifications (
+    bin_id TEXT PRIMARY KEY,
+    service TEXT NOT NULL,
+    webhook_url TEXT NOT NULL,
+    methods TEXT NOT NULL,
+    path_prefix TEXT,
+    failed_signatures_only INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY (bin_id) REFERENCES bins(id) ON DELETE CASCADE
+);
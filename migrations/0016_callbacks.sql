@@ -0,0 +1,13 @@
+-- URLs a bin sends signed event notifications to.
+-- `events` is a JSON array; empty receives every event.
+CREATE TABLE IF NOT EXISTS callbacks (
+    id TEXT PRIMARY KEY,
+    bin_id TEXT NOT NULL,
+    url TEXT NOT NULL,
+    events TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (bin_id) REFERENCES bins(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS callbacks_bin_id ON callbacks(bin_id, created_at);
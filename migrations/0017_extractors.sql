@@ -0,0 +1,19 @@
+-- Named JSONPath expressions evaluated against each captured body, as a JSON array
+-- of {"name", "path"} objects
+CREATE TABLE IF NOT EXISTS bin_extractors (
+    bin_id TEXT PRIMARY KEY,
+    extractors TEXT NOT NULL,
+    FOREIGN KEY (bin_id) REFERENCES bins(id) ON DELETE CASCADE
+);
+
+-- Values the extractors found, one row per request and extractor. They go with the
+-- request when it is trimmed, cleared or deleted.
+CREATE TABLE IF NOT EXISTS request_fields (
+    request_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (request_id, name),
+    FOREIGN KEY (request_id) REFERENCES requests(request_id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS request_fields_name_value ON request_fields(name, value);
@@ -0,0 +1,5 @@
+-- Pinned requests are never trimmed to make room for new ones, and a bin holding any
+-- does not expire.
+ALTER TABLE requests ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS requests_pinned ON requests(bin_id) WHERE pinned;
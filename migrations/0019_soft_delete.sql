@@ -0,0 +1,5 @@
+-- Deleted bins are kept, hidden, until the cleanup task purges them, so they can be
+-- restored in the meantime. NULL for live bins.
+ALTER TABLE bins ADD COLUMN deleted_at TEXT;
+
+CREATE INDEX IF NOT EXISTS bins_deleted_at ON bins(deleted_at) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,9 @@
+-- Per-bin overrides of `[limits] max_requests_per_bin`, `max_body_size` and
+-- `[cleanup] bin_expiry_hours`. NULL keeps the server-wide value.
+CREATE TABLE IF NOT EXISTS bin_limits (
+    bin_id TEXT PRIMARY KEY,
+    max_requests INTEGER,
+    max_body_size INTEGER,
+    expiry_hours INTEGER,
+    FOREIGN KEY (bin_id) REFERENCES bins(id) ON DELETE CASCADE
+);
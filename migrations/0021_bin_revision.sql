@@ -0,0 +1,18 @@
+-- Bumped whenever a bin's requests change, so inspect can answer `If-None-Match`
+-- without reading them. The request count triggers from 0005 bump it in the same update.
+ALTER TABLE bins ADD COLUMN revision INTEGER NOT NULL DEFAULT 0;
+
+DROP TRIGGER IF EXISTS requests_count_insert;
+CREATE TRIGGER requests_count_insert AFTER INSERT ON requests BEGIN
+    UPDATE bins SET request_count = request_count + 1, revision = revision + 1 WHERE id = new.bin_id;
+END;
+
+DROP TRIGGER IF EXISTS requests_count_delete;
+CREATE TRIGGER requests_count_delete AFTER DELETE ON requests BEGIN
+    UPDATE bins SET request_count = request_count - 1, revision = revision + 1 WHERE id = old.bin_id;
+END;
+
+CREATE TRIGGER requests_revision_pinned AFTER UPDATE OF pinned ON requests
+WHEN old.pinned <> new.pinned BEGIN
+    UPDATE bins SET revision = revision + 1 WHERE id = new.bin_id;
+END;
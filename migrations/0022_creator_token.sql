@@ -0,0 +1,5 @@
+-- Secret shared by the bins one client created, so they can be listed again with
+-- GET /my/bins after their ids are lost. NULL for bins created before this existed.
+ALTER TABLE bins ADD COLUMN creator_token TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_bins_creator_token ON bins(creator_token) WHERE creator_token IS NOT NULL;
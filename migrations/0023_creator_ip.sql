@@ -0,0 +1,5 @@
+-- Client address a bin was created from, so `[limits] max_bins_per_ip` can count them.
+-- NULL for bins created before this existed.
+ALTER TABLE bins ADD COLUMN creator_ip TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_bins_creator_ip ON bins(creator_ip) WHERE creator_ip IS NOT NULL;
@@ -0,0 +1,8 @@
+-- What the forward target answered on a delivery's latest attempt, so targets can be
+-- compared on the same captured request. `response_headers` holds `[name, value]` pairs.
+ALTER TABLE forward_deliveries ADD COLUMN latency_ms INTEGER;
+ALTER TABLE forward_deliveries ADD COLUMN response_headers TEXT;
+ALTER TABLE forward_deliveries ADD COLUMN response_body TEXT;
+ALTER TABLE forward_deliveries ADD COLUMN response_body_encoding TEXT;
+
+CREATE INDEX IF NOT EXISTS forward_deliveries_request_id ON forward_deliveries(request_id);
@@ -0,0 +1,20 @@
+-- Replays of captured requests and what their target answered, listed with the request's
+-- forward deliveries. Like `forward_deliveries`, `request_id` is not a foreign key so the
+-- record outlives the request being trimmed.
+CREATE TABLE IF NOT EXISTS replay_deliveries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    bin_id TEXT NOT NULL,
+    request_id TEXT NOT NULL,
+    url TEXT NOT NULL,
+    status TEXT NOT NULL,
+    status_code INTEGER,
+    error TEXT,
+    attempted_at TEXT NOT NULL,
+    latency_ms INTEGER,
+    response_headers TEXT,
+    response_body TEXT,
+    response_body_encoding TEXT,
+    FOREIGN KEY (bin_id) REFERENCES bins(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS replay_deliveries_request_id ON replay_deliveries(bin_id, request_id);
@@ -0,0 +1,3 @@
+-- `replay_deliveries` also holds deliveries reported by `rustbin tunnel` clients, which
+-- send captures on to a local server themselves
+ALTER TABLE replay_deliveries ADD COLUMN kind TEXT NOT NULL DEFAULT 'replay';
@@ -0,0 +1,3 @@
+-- Status a paused bin answers captures with, without storing them. NULL while the bin
+-- captures.
+ALTER TABLE bins ADD COLUMN paused_status INTEGER;
@@ -0,0 +1,8 @@
+-- Store only some captures of a chatty bin: every Nth one, or a share of them picked at
+-- random. Both NULL while the bin stores everything.
+ALTER TABLE bins ADD COLUMN sample_every INTEGER;
+ALTER TABLE bins ADD COLUMN sample_percent REAL;
+-- Captures seen since sampling was last set up, to pick every Nth one
+ALTER TABLE bins ADD COLUMN sample_seen INTEGER NOT NULL DEFAULT 0;
+-- Captures answered but not stored because of sampling
+ALTER TABLE bins ADD COLUMN sampled_out_count INTEGER NOT NULL DEFAULT 0;
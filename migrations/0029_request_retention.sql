@@ -0,0 +1,3 @@
+-- Per-bin override of `[cleanup] request_retention_hours`; 0 keeps requests until the
+-- bin is trimmed or expires. NULL keeps the server-wide value.
+ALTER TABLE bin_limits ADD COLUMN retention_hours INTEGER;
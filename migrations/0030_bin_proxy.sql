@@ -0,0 +1,3 @@
+-- Upstream a recording bin passes every capture on to, answering with its response.
+-- NULL for bins that answer captures themselves.
+ALTER TABLE bins ADD COLUMN proxy_upstream TEXT;
@@ -0,0 +1,4 @@
+-- Failures a bin injects into its answers, as JSON; NULL for bins that behave
+ALTER TABLE bins ADD COLUMN chaos TEXT;
+-- The failure injected into a capture's answer: "error", "drop" or "slow"
+ALTER TABLE requests ADD COLUMN chaos TEXT;
@@ -0,0 +1,2 @@
+-- Rhai script run on every capture; NULL for bins without one
+ALTER TABLE bins ADD COLUMN script TEXT;
@@ -0,0 +1,22 @@
+-- Columns added to schema.sql before migrations existed, so databases created from the
+-- original schema.sql get them too
+
+-- Custom response template, as JSON
+ALTER TABLE bins ADD COLUMN response TEXT;
+ALTER TABLE bins ADD COLUMN name TEXT;
+ALTER TABLE bins ADD COLUMN description TEXT;
+-- JSON array of strings
+ALTER TABLE bins ADD COLUMN tags TEXT;
+-- Bearer token of a private bin
+ALTER TABLE bins ADD COLUMN token TEXT;
+
+ALTER TABLE requests ADD COLUMN body_format TEXT;
+ALTER TABLE requests ADD COLUMN content_type_mismatch INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE requests ADD COLUMN query_string TEXT;
+-- JSON object of the parsed query string
+ALTER TABLE requests ADD COLUMN query_params TEXT;
+ALTER TABLE requests ADD COLUMN source_ip TEXT;
+ALTER TABLE requests ADD COLUMN source_port INTEGER;
+ALTER TABLE requests ADD COLUMN path TEXT;
+ALTER TABLE requests ADD COLUMN body_encoding TEXT;
+ALTER TABLE requests ADD COLUMN content_encoding TEXT;
@@ -0,0 +1,22 @@
+-- The full-text index is written by the capture transaction with each body as it was
+-- captured, so compressed and offloaded bodies are searchable too. Nothing can be read
+-- back from a contentless index, and contentless_delete lets rows leave it by rowid
+-- alone, without the body they were indexed with.
+DROP TRIGGER IF EXISTS requests_fts_insert;
+DROP TRIGGER IF EXISTS requests_fts_delete;
+DROP TRIGGER IF EXISTS requests_fts_update;
+DROP TABLE IF EXISTS requests_fts;
+
+CREATE VIRTUAL TABLE requests_fts USING fts5(
+    body,
+    content='',
+    contentless_delete=1
+);
+
+CREATE TRIGGER requests_fts_delete AFTER DELETE ON requests BEGIN
+    DELETE FROM requests_fts WHERE rowid = old.id;
+END;
+
+-- Bodies kept as text can be indexed here; compressed ones are indexed on startup
+INSERT INTO requests_fts(rowid, body)
+SELECT id, body FROM requests WHERE body_compression IS NULL AND body IS NOT NULL;
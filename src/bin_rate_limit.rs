@@ -0,0 +1,168 @@
+//! Per-bin token buckets, so a single sender can't fill a bin within its per-IP allowance.
+
+use dashmap::DashMap;
+use std::time::{Duration, Instant};
+
+use crate::config::RateLimitingConfig;
+use crate::models::BinRateLimit;
+
+/// Buckets idle for this long are dropped by [`BinRateLimiter::prune`] once full again
+const IDLE_BUCKET_TTL: Duration = Duration::from_secs(60);
+
+/// Token buckets of the bins that received captures recently. Each bucket remembers the
+/// limit it was created with, so a bin's override is only loaded when its bucket is.
+pub struct BinRateLimiter {
+    default: Option<BinRateLimit>,
+    buckets: DashMap<String, Bucket>,
+}
+
+struct Bucket {
+    /// `None` for unlimited bins
+    limit: Option<BinRateLimit>,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant) {
+        if let Some(limit) = self.limit {
+            let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+            self.tokens = (self.tokens + elapsed * limit.requests_per_second).min(capacity(limit));
+        }
+        self.updated = now;
+    }
+
+    /// Idle long enough to be dropped without losing anything
+    fn is_stale(&mut self, now: Instant) -> bool {
+        if now.saturating_duration_since(self.updated) < IDLE_BUCKET_TTL {
+            return false;
+        }
+        self.refill(now);
+        self.limit.is_none_or(|limit| self.tokens >= capacity(limit))
+    }
+}
+
+/// Burst plus the one capture the sustained rate always allows
+fn capacity(limit: BinRateLimit) -> f64 {
+    f64::from(limit.burst_size).max(1.0)
+}
+
+/// `None` for limits that don't limit anything
+fn effective(limit: BinRateLimit) -> Option<BinRateLimit> {
+    (limit.requests_per_second > 0.0).then_some(limit)
+}
+
+impl BinRateLimiter {
+    pub fn new(config: &RateLimitingConfig) -> Self {
+        BinRateLimiter {
+            default: effective(BinRateLimit {
+                requests_per_second: config.per_bin_requests_per_second,
+                burst_size: config.per_bin_burst_size,
+            }),
+            buckets: DashMap::new(),
+        }
+    }
+
+    /// Whether the bin has a bucket
+    #[cfg(test)]
+    pub fn is_tracked(&self, bin_id: &str) -> bool {
+        self.buckets.contains_key(bin_id)
+    }
+
+    /// Take one token from the bin's bucket, creating a full one with `limit` (or the
+    /// configured default when the bin has no override) if there is none. Returns how
+    /// long to wait when the bucket is empty.
+    pub fn check(&self, bin_id: &str, limit: Option<BinRateLimit>) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut bucket = self.buckets.entry(bin_id.to_string()).or_insert_with(|| {
+            let limit = limit.map_or(self.default, effective);
+            Bucket { limit, tokens: limit.map_or(0.0, capacity), updated: now }
+        });
+        bucket.refill(now);
+        let Some(limit) = bucket.limit else {
+            return Ok(());
+        };
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / limit.requests_per_second))
+        }
+    }
+
+    /// Drop the bin's bucket, e.g. after its override changed
+    pub fn forget(&self, bin_id: &str) {
+        self.buckets.remove(bin_id);
+    }
+
+    /// Drop buckets that are full again and saw no captures for a while
+    pub fn prune(&self) {
+        let now = Instant::now();
+        self.buckets.retain(|_, bucket| !bucket.is_stale(now));
+    }
+
+    pub fn len(&self) -> usize {
+        self.buckets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RustbinConfig;
+
+    fn limiter(requests_per_second: f64, burst_size: u32) -> BinRateLimiter {
+        BinRateLimiter::new(&RateLimitingConfig {
+            requests_per_second: 2,
+            burst_size: 5,
+            cleanup_interval_seconds: 60,
+            per_bin_requests_per_second: requests_per_second,
+            per_bin_burst_size: burst_size,
+            ..RustbinConfig::default().rate_limiting
+        })
+    }
+
+    #[test]
+    fn allows_the_burst_then_asks_to_wait() {
+        let limiter = limiter(2.0, 3);
+        for _ in 0..3 {
+            assert!(limiter.check("bin", None).is_ok());
+        }
+        let wait = limiter.check("bin", None).unwrap_err();
+        assert!(wait > Duration::from_millis(400) && wait <= Duration::from_millis(500), "{:?}", wait);
+        // Other bins have their own bucket
+        assert!(limiter.check("other", None).is_ok());
+    }
+
+    #[test]
+    fn overrides_replace_the_default() {
+        let limiter = limiter(0.0, 20);
+        for _ in 0..100 {
+            assert!(limiter.check("open", None).is_ok());
+        }
+        let strict = BinRateLimit { requests_per_second: 0.1, burst_size: 1 };
+        assert!(limiter.check("strict", Some(strict)).is_ok());
+        assert!(limiter.check("strict", None).unwrap_err() > Duration::from_secs(9));
+
+        // Forgetting the bucket picks up a changed override
+        limiter.forget("strict");
+        let unlimited = BinRateLimit { requests_per_second: 0.0, burst_size: 0 };
+        assert!(limiter.check("strict", Some(unlimited)).is_ok());
+        assert!(limiter.check("strict", None).is_ok());
+    }
+
+    #[test]
+    fn prunes_idle_full_buckets() {
+        let limiter = limiter(1000.0, 5);
+        limiter.check("idle", None).unwrap();
+        limiter.check("busy", None).unwrap();
+        limiter.buckets.get_mut("idle").unwrap().updated -= IDLE_BUCKET_TTL;
+        limiter.prune();
+        assert!(!limiter.is_tracked("idle"));
+        assert!(limiter.is_tracked("busy"));
+    }
+}
@@ -0,0 +1,136 @@
+//! Short-lived cache of bin existence and settings so captures don't need a SELECT per request.
+
+use dashmap::DashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::storage::BinSettings;
+
+/// Entries kept at most; misses for unknown ids stop being cached beyond this
+const MAX_ENTRIES: usize = 10_000;
+
+/// Whether a bin exists and, once a capture has loaded them, its settings, remembered for
+/// `ttl`. Entries are dropped when this process deletes a bin and settings when it changes
+/// them, so the TTL only bounds staleness from other processes sharing the database.
+pub struct BinCache {
+    ttl: Duration,
+    entries: DashMap<String, Entry>,
+}
+
+struct Entry {
+    exists: bool,
+    settings: Option<Arc<BinSettings>>,
+    expires_at: Instant,
+}
+
+impl BinCache {
+    /// A zero `ttl` disables caching
+    pub fn new(ttl: Duration) -> Self {
+        BinCache { ttl, entries: DashMap::new() }
+    }
+
+    pub fn get(&self, bin_id: &str) -> Option<bool> {
+        let exists = self.entries.get(bin_id).filter(|entry| entry.expires_at > Instant::now()).map(|entry| entry.exists);
+        if exists.is_none() {
+            self.expire(bin_id);
+        }
+        exists
+    }
+
+    pub fn insert(&self, bin_id: &str, exists: bool) {
+        self.put(bin_id, exists, None);
+    }
+
+    /// The settings of an existing bin, if they were loaded within `ttl`
+    pub fn settings(&self, bin_id: &str) -> Option<Arc<BinSettings>> {
+        let settings = self.entries.get(bin_id).filter(|entry| entry.expires_at > Instant::now()).map(|entry| entry.settings.clone());
+        if settings.is_none() {
+            self.expire(bin_id);
+        }
+        settings.flatten()
+    }
+
+    /// Remember that the bin exists with these settings
+    pub fn insert_settings(&self, bin_id: &str, settings: Arc<BinSettings>) {
+        self.put(bin_id, true, Some(settings));
+    }
+
+    /// Forget the bin's settings after a change, keeping whether it exists
+    pub fn invalidate_settings(&self, bin_id: &str) {
+        if let Some(mut entry) = self.entries.get_mut(bin_id) {
+            entry.settings = None;
+        }
+    }
+
+    pub fn remove(&self, bin_id: &str) {
+        self.entries.remove(bin_id);
+    }
+
+    fn put(&self, bin_id: &str, exists: bool, settings: Option<Arc<BinSettings>>) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let now = Instant::now();
+        if self.entries.len() >= MAX_ENTRIES {
+            self.entries.retain(|_, entry| entry.expires_at > now);
+            if self.entries.len() >= MAX_ENTRIES {
+                return;
+            }
+        }
+        self.entries.insert(bin_id.to_string(), Entry { exists, settings, expires_at: now + self.ttl });
+    }
+
+    fn expire(&self, bin_id: &str) {
+        self.entries.remove_if(bin_id, |_, entry| entry.expires_at <= Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remembers_until_removed_or_expired() {
+        let cache = BinCache::new(Duration::from_secs(60));
+        assert_eq!(cache.get("bin"), None);
+        cache.insert("bin", true);
+        cache.insert("missing", false);
+        assert_eq!(cache.get("bin"), Some(true));
+        assert_eq!(cache.get("missing"), Some(false));
+        cache.remove("bin");
+        assert_eq!(cache.get("bin"), None);
+
+        let expired = BinCache::new(Duration::from_nanos(1));
+        expired.insert("bin", true);
+        std::thread::sleep(Duration::from_millis(1));
+        assert_eq!(expired.get("bin"), None);
+    }
+
+    #[test]
+    fn keeps_settings_until_invalidated() {
+        let cache = BinCache::new(Duration::from_secs(60));
+        assert!(cache.settings("bin").is_none());
+        cache.insert("bin", true);
+        assert!(cache.settings("bin").is_none());
+
+        cache.insert_settings("bin", Arc::new(BinSettings { has_sequence: true, ..Default::default() }));
+        assert!(cache.settings("bin").unwrap().has_sequence);
+        cache.invalidate_settings("bin");
+        assert!(cache.settings("bin").is_none());
+        assert_eq!(cache.get("bin"), Some(true));
+
+        let expired = BinCache::new(Duration::from_nanos(1));
+        expired.insert_settings("bin", Arc::new(BinSettings::default()));
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(expired.settings("bin").is_none());
+    }
+
+    #[test]
+    fn zero_ttl_disables_caching() {
+        let cache = BinCache::new(Duration::ZERO);
+        cache.insert("bin", true);
+        cache.insert_settings("bin", Arc::new(BinSettings::default()));
+        assert_eq!(cache.get("bin"), None);
+        assert!(cache.settings("bin").is_none());
+    }
+}
@@ -0,0 +1,249 @@
+//! Persisting captured requests, either inline or through a batched background writer.
+
+use arc_swap::ArcSwap;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::Utc;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tracing::{error, info};
+
+use crate::config::LimitsConfig;
+use crate::models::{BinLimits, LoggedRequest};
+use crate::storage::{InsertOutcome, Storage, StorageResult};
+use crate::webhooks::{BinEvent, WebhookDispatcher};
+
+/// The server-wide limits with the bin's own overrides applied. Falls back to the
+/// server-wide limits when the bin's can't be loaded.
+pub async fn bin_limits(storage: &dyn Storage, limits: &LimitsConfig, bin_id: &str) -> LimitsConfig {
+    let overrides = storage.bin_limits(bin_id).await.unwrap_or_else(|err| {
+        error!(%bin_id, %err, "Failed to load bin limits");
+        None
+    });
+    with_overrides(limits, overrides.as_ref())
+}
+
+/// The server-wide limits with a bin's overrides, if it has any, applied
+pub fn with_overrides(limits: &LimitsConfig, overrides: Option<&BinLimits>) -> LimitsConfig {
+    let mut limits = limits.clone();
+    if let Some(overrides) = overrides {
+        limits.max_requests_per_bin = overrides.max_requests.unwrap_or(limits.max_requests_per_bin);
+        limits.max_body_size = overrides.max_body_size.unwrap_or(limits.max_body_size);
+    }
+    limits
+}
+
+/// Store captured requests, trimming the bin to `max_requests` and bumping
+/// `last_updated` in the same transaction
+pub async fn store_requests(
+    storage: &dyn Storage,
+    webhooks: &WebhookDispatcher,
+    bin_id: &str,
+    requests: &[LoggedRequest],
+    max_requests: i64,
+) -> StorageResult<InsertOutcome> {
+    let outcome = storage
+        .insert_requests(bin_id, requests, max_requests, &Utc::now().to_rfc3339())
+        .await?;
+
+    // Only the insert that takes the bin to its limit reports it, however far past it goes
+    let before = outcome.request_count - requests.len() as i64;
+    if before < max_requests && outcome.request_count >= max_requests {
+        webhooks.emit(
+            BinEvent::LimitReached,
+            bin_id,
+            serde_json::json!({ "max_requests_per_bin": max_requests }),
+        );
+    }
+
+    if outcome.trimmed > 0 {
+        info!(%bin_id, rows_deleted = outcome.trimmed, "Cleaned up old requests to maintain limit");
+    }
+    Ok(outcome)
+}
+
+/// Where the store stands against `[cleanup] max_storage_bytes`, as of the cleanup
+/// task's last pass
+#[derive(Debug, Default)]
+pub struct StorageQuota {
+    full: AtomicBool,
+    evicted: AtomicU64,
+}
+
+impl StorageQuota {
+    /// Whether captures are refused until space is freed
+    pub fn is_full(&self) -> bool {
+        self.full.load(Ordering::Relaxed)
+    }
+
+    pub fn set_full(&self, full: bool) {
+        self.full.store(full, Ordering::Relaxed);
+    }
+
+    /// Requests evicted to stay under the cap since the server started
+    pub fn evicted(&self) -> u64 {
+        self.evicted.load(Ordering::Relaxed)
+    }
+
+    pub fn record_evictions(&self, count: u64) {
+        self.evicted.fetch_add(count, Ordering::Relaxed);
+    }
+}
+
+/// The queue is at capacity; the writer can't keep up
+#[derive(Debug)]
+pub struct QueueFull;
+
+/// In-process queue of captures written in batches by a background task
+/// (`[limits] async_writes`). Queued captures are lost if the process dies
+/// before they are flushed.
+pub struct CaptureQueue {
+    sender: mpsc::Sender<(String, LoggedRequest)>,
+}
+
+impl CaptureQueue {
+    /// Spawn the writer task; must be called from within a Tokio runtime. Queue and batch
+    /// sizes are fixed from here on, while `max_requests_per_bin` and the bins' own limits
+    /// are read on every flush.
+    pub fn start(storage: Arc<dyn Storage>, webhooks: Arc<WebhookDispatcher>, limits: Arc<ArcSwap<LimitsConfig>>) -> Self {
+        let current = limits.load_full();
+        let (sender, receiver) = mpsc::channel(current.write_queue_capacity.max(1));
+        let writer = BatchWriter {
+            storage,
+            webhooks,
+            limits,
+            batch_size: current.write_batch_size.max(1),
+            flush_interval: Duration::from_millis(current.write_flush_interval_ms),
+        };
+        tokio::spawn(writer.run(receiver));
+        CaptureQueue { sender }
+    }
+
+    /// Whether the writer task is still draining the queue
+    pub fn is_alive(&self) -> bool {
+        !self.sender.is_closed()
+    }
+
+    /// Captures waiting to be written, and how many fit
+    pub fn depth(&self) -> (usize, usize) {
+        let capacity = self.sender.max_capacity();
+        (capacity - self.sender.capacity(), capacity)
+    }
+
+    pub fn push(&self, bin_id: &str, request: LoggedRequest) -> Result<(), QueueFull> {
+        self.sender
+            .try_send((bin_id.to_string(), request))
+            .map_err(|_| QueueFull)
+    }
+}
+
+struct BatchWriter {
+    storage: Arc<dyn Storage>,
+    webhooks: Arc<WebhookDispatcher>,
+    limits: Arc<ArcSwap<LimitsConfig>>,
+    batch_size: usize,
+    flush_interval: Duration,
+}
+
+impl BatchWriter {
+    async fn run(self, mut receiver: mpsc::Receiver<(String, LoggedRequest)>) {
+        let mut batch = Vec::with_capacity(self.batch_size);
+        // Wait for the first capture, then give the batch up to `flush_interval` to fill
+        while let Some(first) = receiver.recv().await {
+            batch.push(first);
+            let deadline = Instant::now() + self.flush_interval;
+            while batch.len() < self.batch_size {
+                match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                    Ok(Some(capture)) => batch.push(capture),
+                    Ok(None) | Err(_) => break,
+                }
+            }
+            self.flush(std::mem::take(&mut batch)).await;
+        }
+    }
+
+    /// One transaction per bin, keeping each bin's captures in arrival order
+    async fn flush(&self, batch: Vec<(String, LoggedRequest)>) {
+        let mut order = Vec::new();
+        let mut by_bin: HashMap<String, Vec<LoggedRequest>> = HashMap::new();
+        for (bin_id, request) in batch {
+            if !by_bin.contains_key(&bin_id) {
+                order.push(bin_id.clone());
+            }
+            by_bin.entry(bin_id).or_default().push(request);
+        }
+
+        let limits = self.limits.load_full();
+        for bin_id in order {
+            let requests = by_bin.remove(&bin_id).unwrap_or_default();
+            let max_requests = bin_limits(self.storage.as_ref(), &limits, &bin_id).await.max_requests_per_bin;
+            match store_requests(self.storage.as_ref(), &self.webhooks, &bin_id, &requests, max_requests).await {
+                Ok(_) => info!(%bin_id, count = requests.len(), "Flushed queued requests"),
+                Err(err) => error!(%bin_id, %err, dropped = requests.len(), "Failed to write queued requests"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{WebhookEndpointConfig, WebhooksConfig};
+    use crate::storage::{MemoryStorage, NewBin};
+    use axum::{extract::State, http::StatusCode, routing::post, Router};
+    use std::sync::Mutex;
+
+    fn request() -> LoggedRequest {
+        serde_json::from_value(serde_json::json!({
+            "method": "POST",
+            "headers": "[]",
+            "timestamp": Utc::now().to_rfc3339(),
+            "request_id": uuid::Uuid::new_v4(),
+            "content_type_mismatch": false,
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_limit_reached_fires_when_a_batch_crosses_the_limit() {
+        type Received = Arc<Mutex<Vec<String>>>;
+        async fn receive(State(received): State<Received>, body: String) -> StatusCode {
+            received.lock().unwrap().push(body);
+            StatusCode::OK
+        }
+        let received: Received = Arc::new(Mutex::new(Vec::new()));
+        let app = Router::new().route("/hook", post(receive)).with_state(received.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let webhooks = WebhookDispatcher::new(&WebhooksConfig {
+            endpoints: vec![WebhookEndpointConfig {
+                url: format!("http://{}/hook", addr),
+                secret: None,
+                events: vec!["bin.limit_reached".to_string()],
+            }],
+            max_attempts: 1,
+            retry_backoff_ms: 10,
+            timeout_seconds: 5,
+        });
+
+        let storage = MemoryStorage::new();
+        storage.create_bin(NewBin { id: "bin-1".to_string(), ..Default::default() }).await.unwrap();
+        let batch = |count| (0..count).map(|_| request()).collect::<Vec<_>>();
+        store_requests(&storage, &webhooks, "bin-1", &batch(3), 5).await.unwrap();
+        // From 3 to 7, trimmed back to 5
+        let outcome = store_requests(&storage, &webhooks, "bin-1", &batch(4), 5).await.unwrap();
+        assert_eq!((outcome.request_count, outcome.trimmed), (7, 2));
+        // Already at the limit
+        store_requests(&storage, &webhooks, "bin-1", &batch(2), 5).await.unwrap();
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        let payload: serde_json::Value = serde_json::from_str(&received[0]).unwrap();
+        assert_eq!(payload["event"], "bin.limit_reached");
+        assert_eq!(payload["data"]["max_requests_per_bin"], 5);
+    }
+}
@@ -0,0 +1,104 @@
+//! Failures injected into a bin's answers for resilience testing, picked per capture from
+//! the bin's chaos settings.
+
+use axum::body::{Body, Bytes};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::handlers::ApiError;
+use crate::models::BinChaos;
+
+/// What the bin's chaos settings do to one capture's answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChaosAction {
+    Error,
+    Drop,
+    Slow,
+}
+
+impl ChaosAction {
+    /// Stored with the capture as its `chaos`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ChaosAction::Error => "error",
+            ChaosAction::Drop => "drop",
+            ChaosAction::Slow => "slow",
+        }
+    }
+
+    /// Pick the action for a capture, if any. Request ids are random v4 UUIDs; the low 53
+    /// bits of their second half make a fair draw in [0, 100), independent of sampling's.
+    pub fn pick(chaos: &BinChaos, request_id: Uuid) -> Option<Self> {
+        let draw = (request_id.as_u64_pair().1 & ((1u64 << 53) - 1)) as f64 / (1u64 << 53) as f64 * 100.0;
+        let mut bound = 0.0;
+        for (percent, action) in [
+            (chaos.error_percent, ChaosAction::Error),
+            (chaos.drop_percent, ChaosAction::Drop),
+            (chaos.slow_percent, ChaosAction::Slow),
+        ] {
+            bound += percent;
+            if draw < bound {
+                return Some(action);
+            }
+        }
+        None
+    }
+
+    /// Turn the capture's usual answer into the failing one
+    pub async fn apply(self, chaos: &BinChaos, response: Response) -> Response {
+        match self {
+            ChaosAction::Error => {
+                ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "chaos_error", "Failure injected by the bin's chaos settings")
+                    .into_response()
+            }
+            ChaosAction::Drop => dropped_connection(),
+            ChaosAction::Slow => {
+                tokio::time::sleep(Duration::from_millis(chaos.delay_ms)).await;
+                response
+            }
+        }
+    }
+}
+
+/// A response whose body fails at once, so the server closes the connection instead of
+/// completing the answer
+fn dropped_connection() -> Response {
+    let body = futures::stream::once(async { Err::<Bytes, _>(std::io::Error::other("connection dropped by chaos")) });
+    Response::new(Body::from_stream(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chaos(error_percent: f64, drop_percent: f64, slow_percent: f64) -> BinChaos {
+        BinChaos { error_percent, drop_percent, slow_percent, delay_ms: 0 }
+    }
+
+    #[test]
+    fn picks_actions_in_proportion() {
+        let chaos = chaos(20.0, 30.0, 10.0);
+        let mut counts = [0i32; 4];
+        for _ in 0..10_000 {
+            let index = match ChaosAction::pick(&chaos, Uuid::new_v4()) {
+                Some(ChaosAction::Error) => 0,
+                Some(ChaosAction::Drop) => 1,
+                Some(ChaosAction::Slow) => 2,
+                None => 3,
+            };
+            counts[index] += 1;
+        }
+        for (count, expected) in counts.into_iter().zip([2_000, 3_000, 1_000, 4_000]) {
+            assert!((count - expected).abs() < 300, "{:?}", counts);
+        }
+    }
+
+    #[test]
+    fn full_or_empty_shares_are_certain() {
+        assert_eq!(ChaosAction::pick(&chaos(100.0, 0.0, 0.0), Uuid::new_v4()), Some(ChaosAction::Error));
+        assert_eq!(ChaosAction::pick(&chaos(0.0, 0.0, 100.0), Uuid::new_v4()), Some(ChaosAction::Slow));
+        assert_eq!(ChaosAction::pick(&chaos(0.0, 0.0, 0.0), Uuid::new_v4()), None);
+    }
+}
@@ -0,0 +1,199 @@
+//! Finding the client behind trusted reverse proxies.
+
+use axum::extract::ConnectInfo;
+use axum::http::{header::FORWARDED, HeaderMap, Request};
+use ipnet::IpNet;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use tower_governor::key_extractor::KeyExtractor;
+use tower_governor::GovernorError;
+
+use crate::config::ForwardedHeader;
+use crate::ip_access::parse_network;
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// The `[server] trusted_proxies` networks, whose `forwarded_header` is believed
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies {
+    networks: Vec<IpNet>,
+    header: ForwardedHeader,
+}
+
+impl TrustedProxies {
+    /// Parse every entry, naming the first one that is neither an address nor a CIDR range
+    pub fn new(entries: &[String], header: ForwardedHeader) -> Result<Self, String> {
+        Ok(TrustedProxies {
+            networks: entries.iter().map(|entry| parse_network(entry)).collect::<Result<_, _>>()?,
+            header,
+        })
+    }
+
+    fn trusts(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.networks.iter().any(|network| network.contains(&ip))
+    }
+
+    /// The address of the client as reported by the trusted proxies in front of `peer`.
+    /// Hops are followed from the nearest one while they are trusted, so a client can't
+    /// pick its address by sending the headers itself. Only the configured header is
+    /// read: proxies append to their own and pass the other one on as the client sent it.
+    pub fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.trusts(peer) {
+            return peer;
+        }
+        let hops = match self.header {
+            ForwardedHeader::XForwardedFor => x_forwarded_for(headers),
+            ForwardedHeader::Forwarded => forwarded_for(headers),
+        };
+        let Some(hops) = hops else {
+            return peer;
+        };
+
+        let mut client = peer;
+        for hop in hops.iter().rev() {
+            if !self.trusts(client) {
+                break;
+            }
+            // `unknown`, obfuscated names and junk end the chain at the proxy that sent them
+            match hop {
+                Some(ip) => client = *ip,
+                None => break,
+            }
+        }
+        client.to_canonical()
+    }
+}
+
+/// `X-Forwarded-For` entries, client first, across all of the headers
+fn x_forwarded_for(headers: &HeaderMap) -> Option<Vec<Option<IpAddr>>> {
+    let values = headers.get_all(X_FORWARDED_FOR);
+    let mut hops = Vec::new();
+    for value in values.iter() {
+        let value = value.to_str().ok()?;
+        hops.extend(value.split(',').map(|hop| parse_hop(hop.trim())));
+    }
+    (!hops.is_empty()).then_some(hops)
+}
+
+/// The `for` parameters of `Forwarded` elements (RFC 7239), client first
+fn forwarded_for(headers: &HeaderMap) -> Option<Vec<Option<IpAddr>>> {
+    let values = headers.get_all(FORWARDED);
+    let mut hops = Vec::new();
+    for value in values.iter() {
+        let value = value.to_str().ok()?;
+        for element in value.split(',') {
+            let node = element.split(';').find_map(|pair| {
+                let (name, value) = pair.trim().split_once('=')?;
+                name.trim().eq_ignore_ascii_case("for").then(|| value.trim())
+            });
+            // Elements without `for` describe a hop we know nothing about
+            hops.push(node.and_then(|node| parse_hop(node.trim_matches('"'))));
+        }
+    }
+    (!hops.is_empty()).then_some(hops)
+}
+
+/// An address, optionally with a port (`192.0.2.1:80`, `[2001:db8::1]:80`)
+fn parse_hop(hop: &str) -> Option<IpAddr> {
+    hop.parse::<IpAddr>()
+        .ok()
+        .or_else(|| hop.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+        .or_else(|| hop.strip_prefix('[')?.strip_suffix(']')?.parse().ok())
+}
+
+/// Rate limits clients instead of the proxies in front of them
+#[derive(Debug, Clone)]
+pub struct ClientIpKeyExtractor {
+    proxies: Arc<TrustedProxies>,
+}
+
+impl ClientIpKeyExtractor {
+    pub fn new(proxies: Arc<TrustedProxies>) -> Self {
+        ClientIpKeyExtractor { proxies }
+    }
+}
+
+impl KeyExtractor for ClientIpKeyExtractor {
+    type Key = IpAddr;
+
+    fn extract<T>(&self, req: &Request<T>) -> Result<Self::Key, GovernorError> {
+        let ConnectInfo(peer) = req
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .ok_or(GovernorError::UnableToExtractKey)?;
+        Ok(self.proxies.client_ip(peer.ip(), req.headers()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn proxies(entries: &[&str]) -> TrustedProxies {
+        proxies_reading(entries, ForwardedHeader::XForwardedFor)
+    }
+
+    fn proxies_reading(entries: &[&str], header: ForwardedHeader) -> TrustedProxies {
+        TrustedProxies::new(&entries.iter().map(|entry| entry.to_string()).collect::<Vec<_>>(), header).unwrap()
+    }
+
+    fn ip(addr: &str) -> IpAddr {
+        addr.parse().unwrap()
+    }
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn ignores_headers_from_untrusted_peers() {
+        let proxies = proxies(&["10.0.0.0/8"]);
+        let spoofed = headers(&[("x-forwarded-for", "192.0.2.1")]);
+        assert_eq!(proxies.client_ip(ip("203.0.113.7"), &spoofed), ip("203.0.113.7"));
+        assert_eq!(TrustedProxies::default().client_ip(ip("10.0.0.1"), &spoofed), ip("10.0.0.1"));
+    }
+
+    #[test]
+    fn follows_trusted_hops_only() {
+        let proxies = proxies(&["10.0.0.0/8", "::1"]);
+        // The client put 198.51.100.1 in the header itself; the first untrusted hop wins
+        let chain = headers(&[("x-forwarded-for", "198.51.100.1, 192.0.2.1"), ("x-forwarded-for", "10.0.0.2")]);
+        assert_eq!(proxies.client_ip(ip("10.0.0.1"), &chain), ip("192.0.2.1"));
+        assert_eq!(proxies.client_ip(ip("::1"), &headers(&[])), ip("::1"));
+        // Everything trusted: the farthest hop is the client
+        assert_eq!(proxies.client_ip(ip("10.0.0.1"), &headers(&[("x-forwarded-for", "10.9.9.9")])), ip("10.9.9.9"));
+        // Junk stops at the proxy that sent it
+        assert_eq!(proxies.client_ip(ip("10.0.0.1"), &headers(&[("x-forwarded-for", "192.0.2.1, nonsense")])), ip("10.0.0.1"));
+        assert_eq!(proxies.client_ip(ip("::ffff:10.0.0.1"), &headers(&[("x-forwarded-for", "192.0.2.1:4711")])), ip("192.0.2.1"));
+    }
+
+    #[test]
+    fn reads_only_the_configured_header() {
+        // nginx appends to X-Forwarded-For and passes the client's own Forwarded through
+        let spoofed = headers(&[("forwarded", "for=192.0.2.60"), ("x-forwarded-for", "198.51.100.1")]);
+        assert_eq!(proxies(&["10.0.0.0/8"]).client_ip(ip("10.0.0.1"), &spoofed), ip("198.51.100.1"));
+        let only_forwarded = headers(&[("forwarded", "for=192.0.2.60")]);
+        assert_eq!(proxies(&["10.0.0.0/8"]).client_ip(ip("10.0.0.1"), &only_forwarded), ip("10.0.0.1"));
+
+        let proxies = proxies_reading(&["10.0.0.0/8"], ForwardedHeader::Forwarded);
+        let both = headers(&[
+            ("forwarded", r#"for=192.0.2.60;proto=https, For="[2001:db8:cafe::17]:4711""#),
+            ("x-forwarded-for", "198.51.100.1"),
+        ]);
+        assert_eq!(proxies.client_ip(ip("10.0.0.1"), &both), ip("2001:db8:cafe::17"));
+        let hidden = headers(&[("forwarded", "for=192.0.2.60, for=_hidden")]);
+        assert_eq!(proxies.client_ip(ip("10.0.0.1"), &hidden), ip("10.0.0.1"));
+        assert_eq!(proxies.client_ip(ip("10.0.0.1"), &headers(&[("x-forwarded-for", "198.51.100.1")])), ip("10.0.0.1"));
+    }
+
+    #[test]
+    fn rejects_invalid_entries() {
+        assert!(TrustedProxies::new(&["proxy.internal".to_string()], ForwardedHeader::XForwardedFor).is_err());
+    }
+}
@@ -1,6 +1,15 @@
+use figment::{
+    providers::{Env, Serialized},
+    Figment,
+};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
+use crate::utils::schedule::{Schedule, ScheduleError};
+
+/// Environment variables starting with this override the configuration file
+pub const ENV_PREFIX: &str = "RUSTBIN_";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RustbinConfig {
     pub server: ServerConfig,
@@ -9,6 +18,34 @@ pub struct RustbinConfig {
     pub limits: LimitsConfig,
     pub cleanup: CleanupConfig,
     pub logging: LoggingConfig,
+    #[serde(default)]
+    pub webhooks: WebhooksConfig,
+    #[serde(default)]
+    pub admin: AdminConfig,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    #[serde(default)]
+    pub blobs: BlobsConfig,
+    #[serde(default)]
+    pub compression: CompressionConfig,
+    #[serde(default)]
+    pub replay: ReplayConfig,
+    #[serde(default)]
+    pub forwarding: ForwardingConfig,
+    #[serde(default)]
+    pub outbound: OutboundConfig,
+    #[serde(default)]
+    pub websocket: WebSocketConfig,
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+    #[serde(default)]
+    pub smtp: SmtpConfig,
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    #[serde(default)]
+    pub scripts: ScriptsConfig,
+    #[serde(default)]
+    pub plugins: PluginsConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,14 +54,100 @@ pub struct ServerConfig {
     pub host: String,
     /// Server port (default: 3000)
     pub port: u16,
+    /// Reverse proxies (addresses or CIDR ranges) whose `forwarded_header` names the
+    /// client, for rate limiting and stored captures (default: none)
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
+    /// The header the trusted proxies name the client in; the other one is ignored, as
+    /// proxies pass it on from the client untouched (default: "x-forwarded-for")
+    #[serde(default)]
+    pub forwarded_header: ForwardedHeader,
+    /// Serve HTTPS with this certificate instead of plain HTTP
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    /// Compress API responses with gzip or brotli when the client accepts it. Captures are
+    /// never compressed, so senders get their bin's response as configured (default: true)
+    #[serde(default = "default_compress_responses")]
+    pub compress_responses: bool,
+    /// Give up on request bodies not fully received this many seconds after the headers,
+    /// answering 408; 0 waits forever (default: 30)
+    #[serde(default = "default_request_read_timeout_seconds")]
+    pub request_read_timeout_seconds: u64,
+    /// Answer `HEAD /bin/{id}` with whether the bin exists and its request count, without
+    /// capturing it. Off, HEAD requests are captured like any other (default: false)
+    #[serde(default)]
+    pub head_probes: bool,
+    /// Serve every route under this path, e.g. "/hooks", for reverse proxies that forward
+    /// a sub-path without rewriting it (default: none)
+    #[serde(default)]
+    pub base_path: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ForwardedHeader {
+    #[default]
+    XForwardedFor,
+    /// RFC 7239 `Forwarded`
+    Forwarded,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsConfig {
+    /// PEM file with the certificate chain, leaf first
+    pub cert_path: String,
+    /// PEM file with the private key (PKCS#8, PKCS#1 or SEC1)
+    pub key_path: String,
+    /// Check both files this often and load them again when they changed, e.g. after a
+    /// certificate renewal; 0 disables reloading (default: 0)
+    #[serde(default)]
+    pub reload_interval_seconds: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DatabaseConfig {
-    /// Database URL (e.g., "sqlite://rustbin.db")
+    /// Database URL (e.g., "sqlite://rustbin.db", or "memory://" for the in-memory store)
     pub url: String,
     /// Maximum number of database connections (default: 5)
     pub max_connections: u32,
+    /// SQLite journal mode (default: "wal")
+    #[serde(default = "default_journal_mode")]
+    pub journal_mode: JournalMode,
+    /// SQLite `synchronous` level (default: "normal")
+    #[serde(default = "default_synchronous")]
+    pub synchronous: Synchronous,
+    /// How long a connection waits for a lock before failing with `database is locked`
+    /// (default: 5000)
+    #[serde(default = "default_busy_timeout_ms")]
+    pub busy_timeout_ms: u64,
+    /// How long captures trust a cached "bin exists" answer and the bin's settings; 0
+    /// reads the database every time (default: 30)
+    #[serde(default = "default_bin_cache_ttl_seconds")]
+    pub bin_cache_ttl_seconds: u64,
+    /// Cron expression (UTC) for the incremental vacuum and ANALYZE run; empty disables
+    /// it (default: "0 3 * * *")
+    #[serde(default = "default_maintenance_schedule")]
+    pub maintenance_schedule: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalMode {
+    Delete,
+    Truncate,
+    Persist,
+    Memory,
+    Wal,
+    Off,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Synchronous {
+    Off,
+    Normal,
+    Full,
+    Extra,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,6 +158,66 @@ pub struct RateLimitingConfig {
     pub burst_size: u32,
     /// Interval in seconds for rate limit cleanup (default: 60)
     pub cleanup_interval_seconds: u64,
+    /// Captures each bin accepts per second from all senders together; 0 disables the
+    /// per-bin limit unless a bin sets its own (default: 0)
+    #[serde(default)]
+    pub per_bin_requests_per_second: f64,
+    /// Captures a bin accepts in a burst before `per_bin_requests_per_second` applies
+    /// (default: 20)
+    #[serde(default = "default_per_bin_burst_size")]
+    pub per_bin_burst_size: u32,
+    /// Where per-IP limits are tracked: "memory" for each process on its own, or "redis"
+    /// to share them between instances (default: "memory")
+    #[serde(default)]
+    pub backend: RateLimitBackend,
+    /// Server used by the redis backend
+    #[serde(default)]
+    pub redis: Option<RedisConfig>,
+    /// Client addresses or CIDR ranges the per-IP limits never apply to, e.g. a
+    /// monitoring host or a CI runner. Per-bin limits still do.
+    #[serde(default)]
+    pub exempt: Vec<String>,
+    /// Budget of its own for `POST /create`; without it creating bins draws from the
+    /// default one
+    #[serde(default)]
+    pub create: Option<RateLimitPolicy>,
+    /// Budget of its own for captures sent to `/bin/{id}` and below
+    #[serde(default)]
+    pub capture: Option<RateLimitPolicy>,
+}
+
+impl RateLimitingConfig {
+    /// The budget of every route without one of its own
+    pub fn default_policy(&self) -> RateLimitPolicy {
+        RateLimitPolicy { requests_per_second: self.requests_per_second, burst_size: self.burst_size }
+    }
+}
+
+/// A per-IP budget, read like the `[rate_limiting]` fields of the same name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimitPolicy {
+    pub requests_per_second: u32,
+    pub burst_size: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitBackend {
+    #[default]
+    Memory,
+    Redis,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedisConfig {
+    /// e.g. "redis://:password@redis.internal:6379/0"
+    pub url: String,
+    /// Prepended to every key rustbin writes (default: "rustbin:")
+    #[serde(default = "default_redis_key_prefix")]
+    pub key_prefix: String,
+    /// Give up on a command after this many milliseconds (default: 250)
+    #[serde(default = "default_redis_timeout_ms")]
+    pub timeout_ms: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,6 +228,37 @@ pub struct LimitsConfig {
     pub max_body_size: usize,
     /// Maximum headers size in bytes (default: 1048576 = 1MB)
     pub max_headers_size: usize,
+    /// Maximum size of a body after gzip/deflate/br decompression (default: 10485760 = 10MB)
+    #[serde(default = "default_max_decompressed_body_size")]
+    pub max_decompressed_body_size: usize,
+    /// Answer captures with 202 right away and store them in batches from a background
+    /// writer (default: false)
+    #[serde(default)]
+    pub async_writes: bool,
+    /// Most captures written in one batch (default: 100)
+    #[serde(default = "default_write_batch_size")]
+    pub write_batch_size: usize,
+    /// How long the writer waits for a batch to fill up, in milliseconds (default: 50)
+    #[serde(default = "default_write_flush_interval_ms")]
+    pub write_flush_interval_ms: u64,
+    /// Captures waiting to be written before new ones get 503 (default: 10000)
+    #[serde(default = "default_write_queue_capacity")]
+    pub write_queue_capacity: usize,
+    /// Most requests a bin may keep through `PUT /bin/{id}/limits` (default: 1000)
+    #[serde(default = "default_bin_max_requests")]
+    pub bin_max_requests: i64,
+    /// Largest body a bin may accept through `PUT /bin/{id}/limits` (default: 10485760 = 10MB)
+    #[serde(default = "default_bin_max_body_size")]
+    pub bin_max_body_size: usize,
+    /// Longest expiry in hours a bin may set through `PUT /bin/{id}/limits` (default: 168)
+    #[serde(default = "default_bin_max_expiry_hours")]
+    pub bin_max_expiry_hours: i64,
+    /// Live bins a single client address may have created; 0 for no limit (default: 0)
+    #[serde(default)]
+    pub max_bins_per_ip: i64,
+    /// Live bins a single `X-Creator-Token` may have created; 0 for no limit (default: 0)
+    #[serde(default)]
+    pub max_bins_per_creator: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,6 +267,40 @@ pub struct CleanupConfig {
     pub bin_expiry_hours: i64,
     /// Cleanup task interval in seconds (default: 60)
     pub cleanup_interval_seconds: u64,
+    /// Minutes before expiry at which a `bin.expiring` event is sent (default: 10)
+    #[serde(default = "default_expiry_warning_minutes")]
+    pub expiry_warning_minutes: i64,
+    /// Hours a deleted bin can still be restored before it is purged; 0 deletes bins
+    /// outright (default: 24)
+    #[serde(default = "default_deleted_bin_retention_hours")]
+    pub deleted_bin_retention_hours: i64,
+    /// Directory expired bins are written to as gzipped NDJSON before deletion (default: none)
+    #[serde(default)]
+    pub archive_directory: Option<String>,
+    /// Days to keep archive files; 0 keeps them forever (default: 30)
+    #[serde(default = "default_archive_retention_days")]
+    pub archive_retention_days: u64,
+    /// Size in bytes the stored requests may take up, checked on every pass; 0 leaves
+    /// it unbounded (default: 0)
+    #[serde(default)]
+    pub max_storage_bytes: u64,
+    /// What happens once `max_storage_bytes` is reached (default: "evict")
+    #[serde(default)]
+    pub storage_full_policy: StorageFullPolicy,
+    /// Hours a request is kept, even in a bin that is still capturing; 0 keeps requests
+    /// until the bin is trimmed or expires (default: 0)
+    #[serde(default)]
+    pub request_retention_hours: i64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageFullPolicy {
+    /// Delete the oldest unpinned requests across all bins until back under the cap
+    #[default]
+    Evict,
+    /// Answer new captures with 507 until expiry or deletions free enough space
+    Reject,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,12 +309,432 @@ pub struct LoggingConfig {
     pub filter: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhooksConfig {
+    /// Callback endpoints notified of bin lifecycle events (default: none)
+    pub endpoints: Vec<WebhookEndpointConfig>,
+    /// Delivery attempts per event before giving up (default: 3)
+    pub max_attempts: u32,
+    /// Delay before the first retry in milliseconds, doubled on each attempt (default: 1000)
+    pub retry_backoff_ms: u64,
+    /// Timeout for a single delivery attempt in seconds (default: 10)
+    pub timeout_seconds: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdminConfig {
+    /// Bearer token required by the /admin endpoints; the admin API is disabled when unset
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplayConfig {
+    /// Allow re-sending and forwarding captured requests to other URLs (default: true)
+    pub enabled: bool,
+    /// Give up on the replay target after this many seconds (default: 10)
+    pub timeout_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ForwardingConfig {
+    /// Delivery attempts per captured request and forward before giving up (default: 5)
+    pub max_attempts: u32,
+    /// Delay before the first retry in milliseconds, doubled on each attempt (default: 1000)
+    pub retry_backoff_ms: u64,
+    /// How often the retry task looks for due deliveries, in milliseconds (default: 1000)
+    pub retry_interval_ms: u64,
+    /// Bytes of each target's response body kept with the delivery (default: 65536)
+    pub max_response_body_bytes: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutboundConfig {
+    /// Loopback, private or link-local networks (addresses or CIDR ranges) that proxy
+    /// upstreams, forwards, replays, callbacks and chat notifications may still target;
+    /// all other internal addresses are refused (default: none)
+    pub allowed_networks: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebSocketConfig {
+    /// Stored requests sent to a client when it connects, before live ones (default: 10)
+    pub history: i64,
+    /// How often idle clients are pinged in milliseconds; 0 disables pings (default: 30000)
+    pub ping_interval_ms: u64,
+    /// Close connections that don't answer a ping within this many milliseconds (default: 10000)
+    pub pong_timeout_ms: u64,
+    /// How messages reach clients connected to other instances: "local" for none, or
+    /// "redis" to relay them through Redis pub/sub (default: "local")
+    pub fanout: FanoutBackend,
+    /// Server used by the redis fanout
+    pub redis: Option<RedisConfig>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FanoutBackend {
+    #[default]
+    Local,
+    Redis,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactionConfig {
+    /// Headers whose values are masked in every bin, matched case-insensitively (default: none)
+    pub headers: Vec<String>,
+    /// Regexes masked in every text body; with a capture group only the first group is
+    /// masked (default: none)
+    pub body_patterns: Vec<String>,
+    /// What redacted values are replaced with (default: "[REDACTED]")
+    pub mask: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    /// Let bins post a summary of new captures to Slack or Discord (default: true)
+    pub enabled: bool,
+    /// Give up on the chat webhook after this many seconds (default: 10)
+    pub timeout_seconds: u64,
+    /// Address this server is reached at, e.g. "https://rustb.in", used to link messages
+    /// to the bin (default: none)
+    pub public_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScriptsConfig {
+    /// Let bins answer captures with a Rhai script (default: true)
+    pub enabled: bool,
+    /// Stop a script after this many operations, so a runaway loop can't hold a worker
+    /// (default: 100000)
+    pub max_operations: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginsConfig {
+    /// WASM modules every HTTP capture passes through, in order, before it is stored
+    /// (default: none)
+    pub modules: Vec<PluginModule>,
+    /// Fuel each plugin gets per capture; one that runs out is skipped (default: 10000000)
+    pub fuel: u64,
+    /// Largest memory a plugin may grow to, in bytes (default: 67108864)
+    pub max_memory_bytes: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginModule {
+    /// Names the plugin in logs
+    pub name: String,
+    /// A `.wasm` module, or `.wat` text
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmtpConfig {
+    /// Accept mail for `<bin_id>@<domain>` and store it in the bin (default: false)
+    pub enabled: bool,
+    /// SMTP bind address (default: "0.0.0.0")
+    pub host: String,
+    /// SMTP port (default: 2525)
+    pub port: u16,
+    /// Domain recipients must be at; empty accepts any domain (default: "localhost")
+    pub domain: String,
+    /// Largest message accepted in bytes (default: 10485760)
+    pub max_message_size: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuthConfig {
+    /// Keys accepted in `X-Api-Key` for creating/deleting bins and the admin API;
+    /// management endpoints are open when empty
+    pub api_keys: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BlobsConfig {
+    /// Bodies larger than this many bytes are stored outside the database; 0 keeps
+    /// every body in its row (default: 0)
+    pub threshold_bytes: usize,
+    /// Where offloaded bodies go: "local" or "s3" (default: "local")
+    pub backend: BlobBackend,
+    /// Directory used by the local backend (default: "data/blobs")
+    pub directory: String,
+    /// Bucket used by the s3 backend
+    pub s3: Option<S3Config>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompressionConfig {
+    /// Store large bodies zstd-compressed in the database (default: false)
+    pub enabled: bool,
+    /// zstd level, 1 (fastest) to 22 (smallest) (default: 3)
+    pub level: i32,
+    /// Bodies shorter than this many bytes are stored as-is (default: 1024)
+    pub min_bytes: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlobBackend {
+    Local,
+    S3,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct S3Config {
+    /// Base URL of the S3-compatible service, e.g. "https://s3.eu-west-1.amazonaws.com"
+    pub endpoint: String,
+    pub bucket: String,
+    /// Prepended to every object key (default: "")
+    #[serde(default)]
+    pub prefix: String,
+    /// Region used to sign requests (default: "us-east-1")
+    #[serde(default = "default_s3_region")]
+    pub region: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEndpointConfig {
+    /// URL that receives the event as a JSON POST
+    pub url: String,
+    /// Shared secret used to sign payloads with HMAC-SHA256 (optional)
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Events to deliver, e.g. ["bin.created", "bin.expired"] (default: all)
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+fn default_compress_responses() -> bool {
+    true
+}
+
+fn default_request_read_timeout_seconds() -> u64 {
+    30
+}
+
+fn default_max_decompressed_body_size() -> usize {
+    10 * 1024 * 1024
+}
+
+fn default_journal_mode() -> JournalMode {
+    JournalMode::Wal
+}
+
+fn default_synchronous() -> Synchronous {
+    Synchronous::Normal
+}
+
+fn default_busy_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_bin_cache_ttl_seconds() -> u64 {
+    30
+}
+
+fn default_maintenance_schedule() -> String {
+    "0 3 * * *".to_string()
+}
+
+fn default_write_batch_size() -> usize {
+    100
+}
+
+fn default_write_flush_interval_ms() -> u64 {
+    50
+}
+
+fn default_write_queue_capacity() -> usize {
+    10_000
+}
+
+fn default_bin_max_requests() -> i64 {
+    1000
+}
+
+fn default_bin_max_body_size() -> usize {
+    10 * 1024 * 1024
+}
+
+fn default_bin_max_expiry_hours() -> i64 {
+    168
+}
+
+fn default_per_bin_burst_size() -> u32 {
+    20
+}
+
+fn default_redis_key_prefix() -> String {
+    "rustbin:".to_string()
+}
+
+fn default_redis_timeout_ms() -> u64 {
+    250
+}
+
+fn default_expiry_warning_minutes() -> i64 {
+    10
+}
+
+fn default_deleted_bin_retention_hours() -> i64 {
+    24
+}
+
+fn default_archive_retention_days() -> u64 {
+    30
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
+}
+
+impl Default for BlobsConfig {
+    fn default() -> Self {
+        Self {
+            threshold_bytes: 0,
+            backend: BlobBackend::Local,
+            directory: "data/blobs".to_string(),
+            s3: None,
+        }
+    }
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            timeout_seconds: 10,
+        }
+    }
+}
+
+impl Default for ForwardingConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            retry_backoff_ms: 1000,
+            retry_interval_ms: 1000,
+            max_response_body_bytes: 65536,
+        }
+    }
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self {
+            history: 10,
+            ping_interval_ms: 30_000,
+            pong_timeout_ms: 10_000,
+            fanout: FanoutBackend::Local,
+            redis: None,
+        }
+    }
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            headers: Vec::new(),
+            body_patterns: Vec::new(),
+            mask: "[REDACTED]".to_string(),
+        }
+    }
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            timeout_seconds: 10,
+            public_url: None,
+        }
+    }
+}
+
+impl Default for ScriptsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_operations: 100_000,
+        }
+    }
+}
+
+impl Default for PluginsConfig {
+    fn default() -> Self {
+        Self {
+            modules: Vec::new(),
+            fuel: 10_000_000,
+            max_memory_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+impl Default for SmtpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "0.0.0.0".to_string(),
+            port: 2525,
+            domain: "localhost".to_string(),
+            max_message_size: 10 * 1024 * 1024,
+        }
+    }
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            level: 3,
+            min_bytes: 1024,
+        }
+    }
+}
+
+impl Default for WebhooksConfig {
+    fn default() -> Self {
+        Self {
+            endpoints: Vec::new(),
+            max_attempts: 3,
+            retry_backoff_ms: 1000,
+            timeout_seconds: 10,
+        }
+    }
+}
+
 impl Default for LimitsConfig {
     fn default() -> Self {
         Self {
             max_requests_per_bin: 100,
             max_body_size: 1024 * 1024, // 1MB
             max_headers_size: 1024 * 1024, // 1MB
+            max_decompressed_body_size: default_max_decompressed_body_size(),
+            async_writes: false,
+            write_batch_size: default_write_batch_size(),
+            write_flush_interval_ms: default_write_flush_interval_ms(),
+            write_queue_capacity: default_write_queue_capacity(),
+            bin_max_requests: default_bin_max_requests(),
+            bin_max_body_size: default_bin_max_body_size(),
+            bin_max_expiry_hours: default_bin_max_expiry_hours(),
+            max_bins_per_ip: 0,
+            max_bins_per_creator: 0,
         }
     }
 }
@@ -77,32 +745,90 @@ impl Default for RustbinConfig {
             server: ServerConfig {
                 host: "0.0.0.0".to_string(),
                 port: 3000,
+                trusted_proxies: Vec::new(),
+                forwarded_header: ForwardedHeader::XForwardedFor,
+                tls: None,
+                compress_responses: true,
+                request_read_timeout_seconds: default_request_read_timeout_seconds(),
+                head_probes: false,
+                base_path: String::new(),
             },
             database: DatabaseConfig {
                 url: "sqlite://rustbin.db".to_string(),
                 max_connections: 5,
+                journal_mode: default_journal_mode(),
+                synchronous: default_synchronous(),
+                busy_timeout_ms: default_busy_timeout_ms(),
+                bin_cache_ttl_seconds: default_bin_cache_ttl_seconds(),
+                maintenance_schedule: default_maintenance_schedule(),
             },
             rate_limiting: RateLimitingConfig {
                 requests_per_second: 2,
                 burst_size: 5,
                 cleanup_interval_seconds: 60,
+                per_bin_requests_per_second: 0.0,
+                per_bin_burst_size: default_per_bin_burst_size(),
+                backend: RateLimitBackend::Memory,
+                redis: None,
+                exempt: Vec::new(),
+                create: None,
+                capture: None,
             },
-            limits: LimitsConfig {
-                max_requests_per_bin: 100,
-                max_body_size: 1024 * 1024, // 1MB
-                max_headers_size: 1024 * 1024, // 1MB
-            },
+            limits: LimitsConfig::default(),
             cleanup: CleanupConfig {
                 bin_expiry_hours: 1,
                 cleanup_interval_seconds: 60,
+                expiry_warning_minutes: default_expiry_warning_minutes(),
+                deleted_bin_retention_hours: default_deleted_bin_retention_hours(),
+                archive_directory: None,
+                archive_retention_days: default_archive_retention_days(),
+                max_storage_bytes: 0,
+                storage_full_policy: StorageFullPolicy::Evict,
+                request_retention_hours: 0,
             },
             logging: LoggingConfig {
                 filter: "rustbin=info,tower_http=warn,sqlx=warn,hyper=warn".to_string(),
             },
+            webhooks: WebhooksConfig::default(),
+            admin: AdminConfig::default(),
+            auth: AuthConfig::default(),
+            blobs: BlobsConfig::default(),
+            compression: CompressionConfig::default(),
+            replay: ReplayConfig::default(),
+            forwarding: ForwardingConfig::default(),
+            outbound: OutboundConfig::default(),
+            websocket: WebSocketConfig::default(),
+            redaction: RedactionConfig::default(),
+            smtp: SmtpConfig::default(),
+            notifications: NotificationsConfig::default(),
+            scripts: ScriptsConfig::default(),
+            plugins: PluginsConfig::default(),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// `base_path` with one leading slash and none trailing, or empty to serve at the root
+    pub fn base_path(&self) -> String {
+        let path = self.base_path.trim().trim_matches('/');
+        if path.is_empty() {
+            String::new()
+        } else {
+            format!("/{}", path)
         }
     }
 }
 
+impl DatabaseConfig {
+    /// The parsed `maintenance_schedule`, or `None` when maintenance is turned off
+    pub fn maintenance_schedule(&self) -> Result<Option<Schedule>, ScheduleError> {
+        if self.maintenance_schedule.trim().is_empty() {
+            return Ok(None);
+        }
+        self.maintenance_schedule.parse().map(Some)
+    }
+}
+
 impl RustbinConfig {
     /// Load configuration from a TOML file
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
@@ -111,9 +837,10 @@ impl RustbinConfig {
         Ok(config)
     }
 
-    /// Load configuration from a TOML file, falling back to defaults if file doesn't exist
+    /// Load configuration from a TOML file, falling back to defaults if file doesn't exist.
+    /// Environment overrides apply either way, see [`RustbinConfig::load`].
     pub fn from_file_or_default<P: AsRef<Path>>(path: P) -> Self {
-        match Self::from_file(path.as_ref()) {
+        match Self::load(path.as_ref()) {
             Ok(config) => {
                 tracing::info!("Loaded configuration from {}", path.as_ref().display());
                 config
@@ -124,11 +851,28 @@ impl RustbinConfig {
                     path.as_ref().display(),
                     err
                 );
-                Self::default()
+                Self::default().with_env_overrides().unwrap_or_default()
             }
         }
     }
 
+    /// Load configuration from the TOML file if it exists, else the defaults, and apply
+    /// environment overrides on top. `RUSTBIN_SERVER__PORT=8080` sets `[server] port`;
+    /// `__` separates nested keys, so `RUSTBIN_SERVER__TLS__CERT_PATH` sets
+    /// `[server.tls] cert_path`. Values are read like TOML, e.g. `RUSTBIN_AUTH__API_KEYS=[a,b]`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let config = if path.as_ref().exists() { Self::from_file(path)? } else { Self::default() };
+        Ok(config.with_env_overrides()?)
+    }
+
+    /// This configuration with the `RUSTBIN_` environment variables applied
+    pub fn with_env_overrides(self) -> Result<Self, Box<figment::Error>> {
+        Figment::from(Serialized::defaults(self))
+            .merge(Env::prefixed(ENV_PREFIX).split("__"))
+            .extract()
+            .map_err(Box::new)
+    }
+
     /// Save the current configuration to a TOML file
     pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn std::error::Error>> {
         let content = toml::to_string_pretty(self)?;
@@ -158,15 +902,48 @@ mod tests {
         let config = RustbinConfig::default();
         assert_eq!(config.server.host, "0.0.0.0");
         assert_eq!(config.server.port, 3000);
+        assert!(config.server.trusted_proxies.is_empty());
+        assert!(config.server.tls.is_none());
         assert_eq!(config.database.url, "sqlite://rustbin.db");
         assert_eq!(config.database.max_connections, 5);
+        assert_eq!(config.database.journal_mode, JournalMode::Wal);
+        assert_eq!(config.database.synchronous, Synchronous::Normal);
+        assert_eq!(config.database.busy_timeout_ms, 5000);
+        assert_eq!(config.database.bin_cache_ttl_seconds, 30);
+        assert_eq!(config.database.maintenance_schedule, "0 3 * * *");
         assert_eq!(config.rate_limiting.requests_per_second, 2);
         assert_eq!(config.rate_limiting.burst_size, 5);
+        assert_eq!(config.rate_limiting.per_bin_requests_per_second, 0.0);
+        assert_eq!(config.rate_limiting.per_bin_burst_size, 20);
+        assert_eq!(config.rate_limiting.backend, RateLimitBackend::Memory);
+        assert!(config.rate_limiting.redis.is_none());
         assert_eq!(config.limits.max_requests_per_bin, 100);
         assert_eq!(config.limits.max_body_size, 1024 * 1024);
         assert_eq!(config.limits.max_headers_size, 1024 * 1024);
+        assert_eq!(config.limits.max_decompressed_body_size, 10 * 1024 * 1024);
+        assert!(!config.limits.async_writes);
         assert_eq!(config.cleanup.bin_expiry_hours, 1);
         assert_eq!(config.cleanup.cleanup_interval_seconds, 60);
+        assert_eq!(config.cleanup.expiry_warning_minutes, 10);
+        assert!(config.webhooks.endpoints.is_empty());
+        assert_eq!(config.blobs.backend, BlobBackend::Local);
+        assert!(!config.compression.enabled);
+        assert!(config.replay.enabled);
+        assert_eq!(config.forwarding.max_attempts, 5);
+        assert_eq!(config.forwarding.retry_backoff_ms, 1000);
+        assert_eq!(config.websocket.history, 10);
+        assert_eq!(config.websocket.ping_interval_ms, 30_000);
+        assert_eq!(config.websocket.fanout, FanoutBackend::Local);
+        assert!(config.redaction.headers.is_empty());
+        assert_eq!(config.redaction.mask, "[REDACTED]");
+        assert!(!config.smtp.enabled);
+        assert_eq!(config.smtp.port, 2525);
+        assert_eq!(config.smtp.domain, "localhost");
+        assert!(config.notifications.enabled);
+        assert_eq!(config.notifications.timeout_seconds, 10);
+        assert!(config.notifications.public_url.is_none());
+        assert_eq!(config.webhooks.max_attempts, 3);
+        assert!(config.admin.token.is_none());
     }
 
     #[test]
@@ -185,8 +962,125 @@ mod tests {
     }
 
     #[test]
+    fn test_bundled_config_parses() {
+        let config: RustbinConfig = toml::from_str(include_str!("../rustbin.toml")).unwrap();
+        assert!(config.webhooks.endpoints.is_empty());
+        assert_eq!(config.blobs.backend, BlobBackend::Local);
+    }
+
+    #[test]
+    fn test_webhook_endpoints_round_trip() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config_path = temp_file.path();
+
+        let mut original_config = RustbinConfig::default();
+        original_config.webhooks.endpoints.push(WebhookEndpointConfig {
+            url: "http://localhost:9000/hooks".to_string(),
+            secret: Some("s3cret".to_string()),
+            events: vec!["bin.created".to_string()],
+        });
+        original_config.save_to_file(config_path).unwrap();
+
+        let loaded_config = RustbinConfig::from_file(config_path).unwrap();
+        assert_eq!(loaded_config.webhooks.endpoints.len(), 1);
+        assert_eq!(loaded_config.webhooks.endpoints[0].url, "http://localhost:9000/hooks");
+        assert_eq!(loaded_config.webhooks.endpoints[0].secret.as_deref(), Some("s3cret"));
+    }
+
+    #[test]
+    fn test_server_tls_round_trip() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config_path = temp_file.path();
+
+        let mut original_config = RustbinConfig::default();
+        original_config.server.tls = Some(TlsConfig {
+            cert_path: "/etc/rustbin/cert.pem".to_string(),
+            key_path: "/etc/rustbin/key.pem".to_string(),
+            reload_interval_seconds: 3600,
+        });
+        original_config.save_to_file(config_path).unwrap();
+
+        let loaded_config = RustbinConfig::from_file(config_path).unwrap();
+        let tls = loaded_config.server.tls.unwrap();
+        assert_eq!(tls.cert_path, "/etc/rustbin/cert.pem");
+        assert_eq!(tls.key_path, "/etc/rustbin/key.pem");
+        assert_eq!(tls.reload_interval_seconds, 3600);
+    }
+
+    #[test]
+    fn test_base_path_is_normalized() {
+        let mut server = RustbinConfig::default().server;
+        for (configured, expected) in [("", ""), ("/", ""), ("/hooks", "/hooks"), ("hooks/", "/hooks"), (" /a/b/ ", "/a/b")] {
+            server.base_path = configured.to_string();
+            assert_eq!(server.base_path(), expected, "{:?}", configured);
+        }
+    }
+
+    #[test]
+    fn test_redis_rate_limiting_defaults() {
+        let config: RateLimitingConfig = toml::from_str(
+            "requests_per_second = 2\nburst_size = 5\ncleanup_interval_seconds = 60\nbackend = \"redis\"\n\
+             [redis]\nurl = \"redis://localhost\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.backend, RateLimitBackend::Redis);
+        let redis = config.redis.unwrap();
+        assert_eq!(redis.url, "redis://localhost");
+        assert_eq!(redis.key_prefix, "rustbin:");
+        assert_eq!(redis.timeout_ms, 250);
+    }
+
+    #[test]
+    #[allow(clippy::result_large_err)] // `Jail` closures return `figment::Error`
+    fn test_env_overrides_file() {
+        figment::Jail::expect_with(|jail| {
+            let mut file_config = RustbinConfig::default();
+            file_config.server.host = "127.0.0.1".to_string();
+            file_config.server.port = 4000;
+            file_config.database.url = "sqlite://file.db".to_string();
+            file_config.save_to_file("rustbin.toml").unwrap();
+            jail.set_env("RUSTBIN_SERVER__PORT", "8080");
+            jail.set_env("RUSTBIN_LIMITS__ASYNC_WRITES", "true");
+            jail.set_env("RUSTBIN_AUTH__API_KEYS", "[one, two]");
+            jail.set_env("RUSTBIN_SERVER__TLS__CERT_PATH", "/certs/cert.pem");
+            jail.set_env("RUSTBIN_SERVER__TLS__KEY_PATH", "/certs/key.pem");
+            // Without the prefix, nothing changes
+            jail.set_env("SERVER__HOST", "10.0.0.1");
+
+            let config = RustbinConfig::load("rustbin.toml").unwrap();
+            assert_eq!(config.server.host, "127.0.0.1");
+            assert_eq!(config.server.port, 8080);
+            assert_eq!(config.database.url, "sqlite://file.db");
+            assert!(config.limits.async_writes);
+            assert_eq!(config.auth.api_keys, ["one", "two"]);
+            assert_eq!(config.server.tls.unwrap().key_path, "/certs/key.pem");
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[allow(clippy::result_large_err)] // `Jail` closures return `figment::Error`
+    fn test_env_overrides_without_file() {
+        figment::Jail::expect_with(|jail| {
+            jail.set_env("RUSTBIN_DATABASE__URL", "memory://");
+            let config = RustbinConfig::load("missing.toml").unwrap();
+            assert_eq!(config.database.url, "memory://");
+            assert_eq!(config.server.port, 3000);
+
+            jail.set_env("RUSTBIN_SERVER__PORT", "not a port");
+            assert!(RustbinConfig::load("missing.toml").is_err());
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[allow(clippy::result_large_err)] // `Jail` closures return `figment::Error`
     fn test_from_file_or_default_with_missing_file() {
-        let config = RustbinConfig::from_file_or_default("nonexistent.toml");
-        assert_eq!(config.server.port, 3000); // Should use defaults
+        // In a jail so the environment set by other tests can't leak in
+        figment::Jail::expect_with(|_| {
+            let config = RustbinConfig::from_file_or_default("nonexistent.toml");
+            assert_eq!(config.server.port, 3000); // Should use defaults
+            Ok(())
+        });
     }
 }
\ No newline at end of file
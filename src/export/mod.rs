@@ -0,0 +1,83 @@
+pub mod atom;
+pub mod csv;
+pub mod curl;
+pub mod html;
+pub mod import;
+pub mod postman;
+pub mod snippet;
+
+use crate::models::LoggedRequest;
+use crate::utils::headers::{parse_stored_headers, stored_header};
+
+/// Bin export formats accepted by `GET /bin/:id/export?format=...`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Curl,
+    Postman,
+    Ndjson,
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn parse(format: &str) -> Option<Self> {
+        match format.to_ascii_lowercase().as_str() {
+            "curl" => Some(ExportFormat::Curl),
+            "postman" => Some(ExportFormat::Postman),
+            "ndjson" => Some(ExportFormat::Ndjson),
+            "csv" => Some(ExportFormat::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// One JSON object per line, for piping into tools like `jq`.
+pub fn to_ndjson_line(request: &LoggedRequest) -> String {
+    let mut line = serde_json::to_string(request).unwrap_or_else(|_| "{}".to_string());
+    line.push('\n');
+    line
+}
+
+/// Headers describing the original connection or transfer rather than the request itself.
+/// Bodies are stored decompressed, so `Content-Encoding` no longer applies either.
+const SKIPPED_HEADERS: &[&str] = &[
+    "host",
+    "content-length",
+    "connection",
+    "keep-alive",
+    "transfer-encoding",
+    "content-encoding",
+    "upgrade",
+];
+
+/// Headers worth sending again when a captured request is replayed elsewhere.
+pub fn replayable_headers(request: &LoggedRequest) -> Vec<(String, String)> {
+    parse_stored_headers(&request.headers)
+        .into_iter()
+        .filter(|(name, _)| !SKIPPED_HEADERS.contains(&name.to_ascii_lowercase().as_str()))
+        .collect()
+}
+
+/// URL to re-send a captured request to: `target` followed by the captured sub-path and
+/// query string
+pub fn replay_url(request: &LoggedRequest, target: &str) -> String {
+    let base = target.trim_end_matches('/');
+    let mut url = format!("{}{}", base, request.path.as_deref().unwrap_or(""));
+    if let Some(query) = request.query_string.as_deref().filter(|q| !q.is_empty()) {
+        url.push('?');
+        url.push_str(query);
+    }
+    url
+}
+
+/// Like [`replay_url`], defaulting to the bin URL the request was originally sent to, with
+/// the bin served at `bin_path`
+pub fn export_url(request: &LoggedRequest, bin_path: &str, target: Option<&str>) -> String {
+    match target {
+        Some(target) => replay_url(request, target),
+        None => {
+            let host = stored_header(&request.headers, "host").unwrap_or_else(|| "localhost".to_string());
+            let scheme = stored_header(&request.headers, "x-forwarded-proto").unwrap_or_else(|| "http".to_string());
+            replay_url(request, &format!("{}://{}{}", scheme, host, bin_path))
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use serde_json::{json, Value};
+
+use crate::models::LoggedRequest;
+use crate::utils::body::ENCODING_BASE64;
+
+use super::{export_url, replayable_headers};
+
+pub const POSTMAN_SCHEMA: &str = "https://schema.getpostman.com/json/collection/v2.1.0/collection.json";
+
+/// Build a Postman Collection v2.1 with one item per captured request, oldest first.
+pub fn to_postman_collection(
+    bin_id: &str,
+    bin_path: &str,
+    name: Option<&str>,
+    requests: &[LoggedRequest],
+    target: Option<&str>,
+) -> Value {
+    let items: Vec<Value> = requests
+        .iter()
+        .map(|request| postman_item(request, &export_url(request, bin_path, target)))
+        .collect();
+
+    json!({
+        "info": {
+            "_postman_id": bin_id,
+            "name": name.map(str::to_string).unwrap_or_else(|| format!("rustbin {}", bin_id)),
+            "schema": POSTMAN_SCHEMA,
+        },
+        "item": items,
+    })
+}
+
+fn postman_item(request: &LoggedRequest, url: &str) -> Value {
+    let headers: Vec<Value> = replayable_headers(request)
+        .into_iter()
+        .map(|(key, value)| json!({ "key": key, "value": value }))
+        .collect();
+
+    let mut item = json!({
+        "name": format!("{} {} ({})", request.method, request.path.as_deref().unwrap_or("/"), request.timestamp),
+        "request": {
+            "method": request.method,
+            "header": headers,
+            "url": url,
+        },
+    });
+
+    let body = request.body.as_deref().unwrap_or("");
+    if !body.is_empty() {
+        let language = match request.body_format.as_deref() {
+            Some("json") => "json",
+            Some("xml") => "xml",
+            _ => "text",
+        };
+        item["request"]["body"] = json!({
+            "mode": "raw",
+            "raw": body,
+            "options": { "raw": { "language": language } },
+        });
+        // Postman can't hold raw bytes inline
+        if request.body_encoding.as_deref() == Some(ENCODING_BASE64) {
+            item["request"]["description"] = json!("Binary body, base64 encoded. Decode it before sending.");
+        }
+    }
+    item
+}
@@ -0,0 +1,247 @@
+use crate::models::LoggedRequest;
+use crate::utils::body::ENCODING_BASE64;
+
+use super::replayable_headers;
+
+/// Languages accepted by `GET /bin/:id/request/:rid/snippet?lang=...`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnippetLanguage {
+    /// Python with `requests`
+    Python,
+    /// Node.js 18+ with the built-in `fetch`
+    Node,
+    /// Rust with `reqwest` on tokio
+    Rust,
+    /// Go with `net/http`
+    Go,
+}
+
+impl SnippetLanguage {
+    pub const ALL: [SnippetLanguage; 4] = [SnippetLanguage::Python, SnippetLanguage::Node, SnippetLanguage::Rust, SnippetLanguage::Go];
+
+    pub fn parse(lang: &str) -> Option<Self> {
+        match lang.to_ascii_lowercase().as_str() {
+            "python" | "py" => Some(SnippetLanguage::Python),
+            "node" | "js" | "javascript" => Some(SnippetLanguage::Node),
+            "rust" | "rs" => Some(SnippetLanguage::Rust),
+            "go" | "golang" => Some(SnippetLanguage::Go),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SnippetLanguage::Python => "python",
+            SnippetLanguage::Node => "node",
+            SnippetLanguage::Rust => "rust",
+            SnippetLanguage::Go => "go",
+        }
+    }
+}
+
+/// The captured body, if any, and whether it is stored base64 encoded
+fn captured_body(request: &LoggedRequest) -> Option<(&str, bool)> {
+    let body = request.body.as_deref().filter(|body| !body.is_empty())?;
+    Some((body, request.body_encoding.as_deref() == Some(ENCODING_BASE64)))
+}
+
+/// A double-quoted string literal valid in Python, JavaScript and Go
+fn quote(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+/// Rebuild a captured request as a standalone program sending it to `url` and printing
+/// the answer. Binary bodies are embedded as base64 and decoded before sending.
+pub fn to_snippet(lang: SnippetLanguage, request: &LoggedRequest, url: &str) -> String {
+    match lang {
+        SnippetLanguage::Python => python(request, url),
+        SnippetLanguage::Node => node(request, url),
+        SnippetLanguage::Rust => rust(request, url),
+        SnippetLanguage::Go => go(request, url),
+    }
+}
+
+fn python(request: &LoggedRequest, url: &str) -> String {
+    // A dict holds one value per name, so repeated headers are folded into a list
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for (name, value) in replayable_headers(request) {
+        match headers.iter_mut().find(|(existing, _)| existing.eq_ignore_ascii_case(&name)) {
+            Some((_, existing)) => *existing = format!("{}, {}", existing, value),
+            None => headers.push((name, value)),
+        }
+    }
+    let body = captured_body(request);
+
+    let mut code = String::new();
+    if matches!(body, Some((_, true))) {
+        code.push_str("import base64\n");
+    }
+    code.push_str("import requests\n\nresponse = requests.request(\n");
+    code.push_str(&format!("    {},\n    {},\n", quote(&request.method), quote(url)));
+    code.push_str("    headers={\n");
+    for (name, value) in &headers {
+        code.push_str(&format!("        {}: {},\n", quote(name), quote(value)));
+    }
+    code.push_str("    },\n");
+    match body {
+        Some((body, true)) => code.push_str(&format!("    data=base64.b64decode({}),\n", quote(body))),
+        Some((body, false)) => code.push_str(&format!("    data={}.encode(),\n", quote(body))),
+        None => {}
+    }
+    code.push_str("    allow_redirects=False,\n)\nprint(response.status_code)\nprint(response.text)\n");
+    code
+}
+
+fn node(request: &LoggedRequest, url: &str) -> String {
+    let mut code = String::from("// Node.js 18+, run as an ES module (e.g. snippet.mjs)\n");
+    code.push_str(&format!("const response = await fetch({}, {{\n", quote(url)));
+    code.push_str(&format!("  method: {},\n  headers: [\n", quote(&request.method)));
+    for (name, value) in replayable_headers(request) {
+        code.push_str(&format!("    [{}, {}],\n", quote(&name), quote(&value)));
+    }
+    code.push_str("  ],\n");
+    // fetch refuses a body on GET and HEAD
+    match captured_body(request).filter(|_| !matches!(request.method.as_str(), "GET" | "HEAD")) {
+        Some((body, true)) => code.push_str(&format!("  body: Buffer.from({}, \"base64\"),\n", quote(body))),
+        Some((body, false)) => code.push_str(&format!("  body: {},\n", quote(body))),
+        None => {}
+    }
+    code.push_str("  redirect: \"manual\",\n});\nconsole.log(response.status);\nconsole.log(await response.text());\n");
+    code
+}
+
+fn rust(request: &LoggedRequest, url: &str) -> String {
+    let body = captured_body(request);
+    let method = match request.method.as_str() {
+        standard @ ("GET" | "POST" | "PUT" | "PATCH" | "DELETE" | "HEAD" | "OPTIONS" | "TRACE") => format!("reqwest::Method::{}", standard),
+        other => format!("reqwest::Method::from_bytes(b{:?})?", other),
+    };
+
+    let mut code = String::from("// Cargo.toml: reqwest = \"0.12\", tokio = { version = \"1\", features = [\"full\"] }");
+    if matches!(body, Some((_, true))) {
+        code.push_str(", base64 = \"0.22\"\n");
+        code.push_str("use base64::Engine;\n");
+    } else {
+        code.push('\n');
+    }
+    code.push_str("\n#[tokio::main]\nasync fn main() -> Result<(), Box<dyn std::error::Error>> {\n");
+    code.push_str("    let client = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none()).build()?;\n");
+    code.push_str(&format!("    let response = client\n        .request({}, {:?})\n", method, url));
+    for (name, value) in replayable_headers(request) {
+        code.push_str(&format!("        .header({:?}, {:?})\n", name, value));
+    }
+    match body {
+        Some((body, true)) => code.push_str(&format!("        .body(base64::engine::general_purpose::STANDARD.decode({:?})?)\n", body)),
+        Some((body, false)) => code.push_str(&format!("        .body({:?})\n", body)),
+        None => {}
+    }
+    code.push_str("        .send()\n        .await?;\n");
+    code.push_str("    println!(\"{}\", response.status());\n    println!(\"{}\", response.text().await?);\n    Ok(())\n}\n");
+    code
+}
+
+fn go(request: &LoggedRequest, url: &str) -> String {
+    let body = captured_body(request);
+    let mut imports = vec!["fmt", "io", "net/http"];
+    let mut setup = String::new();
+    let reader = match body {
+        Some((body, true)) => {
+            imports.extend(["bytes", "encoding/base64"]);
+            setup.push_str(&format!("\tpayload, err := base64.StdEncoding.DecodeString({})\n\tif err != nil {{\n\t\tpanic(err)\n\t}}\n", quote(body)));
+            "bytes.NewReader(payload)".to_string()
+        }
+        Some((body, false)) => {
+            imports.push("strings");
+            format!("strings.NewReader({})", quote(body))
+        }
+        None => "nil".to_string(),
+    };
+    imports.sort_unstable();
+
+    let mut code = String::from("package main\n\nimport (\n");
+    for import in imports {
+        code.push_str(&format!("\t\"{}\"\n", import));
+    }
+    code.push_str(")\n\nfunc main() {\n");
+    code.push_str(&setup);
+    code.push_str(&format!("\treq, err := http.NewRequest({}, {}, {})\n", quote(&request.method), quote(url), reader));
+    code.push_str("\tif err != nil {\n\t\tpanic(err)\n\t}\n");
+    for (name, value) in replayable_headers(request) {
+        code.push_str(&format!("\treq.Header.Add({}, {})\n", quote(&name), quote(&value)));
+    }
+    code.push_str(
+        "\tclient := &http.Client{CheckRedirect: func(*http.Request, []*http.Request) error { return http.ErrUseLastResponse }}\n\
+         \tresp, err := client.Do(req)\n\tif err != nil {\n\t\tpanic(err)\n\t}\n\tdefer resp.Body.Close()\n\
+         \tout, err := io.ReadAll(resp.Body)\n\tif err != nil {\n\t\tpanic(err)\n\t}\n\
+         \tfmt.Println(resp.StatusCode)\n\tfmt.Println(string(out))\n}\n",
+    );
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, headers: &str, body: &str, encoding: &str) -> LoggedRequest {
+        serde_json::from_value(serde_json::json!({
+            "method": method,
+            "headers": headers,
+            "body": body,
+            "body_encoding": encoding,
+            "timestamp": "2024-01-01T00:00:00+00:00",
+            "request_id": uuid::Uuid::nil(),
+            "content_type_mismatch": false,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn renders_each_language_with_escaped_values() {
+        let req = request(
+            "POST",
+            r#"[["host","rustb.in"],["content-type","application/json"],["x-tag","a"],["x-tag","b"]]"#,
+            "{\"quote\": \"\\\"\"}\n",
+            "utf8",
+        );
+        let url = "http://localhost:8080/hook";
+
+        let python = to_snippet(SnippetLanguage::Python, &req, url);
+        assert!(python.starts_with("import requests\n"));
+        assert!(python.contains("\"x-tag\": \"a, b\","));
+        assert!(python.contains(r#"data="{\"quote\": \"\\\"\"}\n".encode(),"#));
+        assert!(!python.contains("rustb.in"));
+
+        let node = to_snippet(SnippetLanguage::Node, &req, url);
+        assert!(node.contains("[\"x-tag\", \"a\"],\n    [\"x-tag\", \"b\"],"));
+        assert!(node.contains(r#"body: "{\"quote\": \"\\\"\"}\n","#));
+
+        let rust = to_snippet(SnippetLanguage::Rust, &req, url);
+        assert!(rust.contains(".request(reqwest::Method::POST, \"http://localhost:8080/hook\")"));
+        assert!(rust.contains(r#".body("{\"quote\": \"\\\"\"}\n")"#));
+
+        let go = to_snippet(SnippetLanguage::Go, &req, url);
+        assert!(go.contains("\t\"strings\"\n"));
+        assert!(go.contains(r#"http.NewRequest("POST", "http://localhost:8080/hook", strings.NewReader("{\"quote\": \"\\\"\"}\n"))"#));
+        assert!(go.contains("req.Header.Add(\"x-tag\", \"b\")"));
+
+        for lang in SnippetLanguage::ALL {
+            assert_eq!(SnippetLanguage::parse(lang.as_str()), Some(lang));
+        }
+        assert_eq!(SnippetLanguage::parse("ruby"), None);
+    }
+
+    #[test]
+    fn decodes_binary_bodies_and_skips_empty_ones() {
+        let binary = request("PUT", "[]", "AAEC", "base64");
+        assert!(to_snippet(SnippetLanguage::Python, &binary, "http://x").contains("data=base64.b64decode(\"AAEC\")"));
+        assert!(to_snippet(SnippetLanguage::Node, &binary, "http://x").contains("Buffer.from(\"AAEC\", \"base64\")"));
+        assert!(to_snippet(SnippetLanguage::Rust, &binary, "http://x").contains("STANDARD.decode(\"AAEC\")?"));
+        let go = to_snippet(SnippetLanguage::Go, &binary, "http://x");
+        assert!(go.contains("\t\"encoding/base64\"\n") && go.contains("bytes.NewReader(payload)") && !go.contains("strings"));
+
+        let empty = request("REPORT", "[]", "", "utf8");
+        assert!(!to_snippet(SnippetLanguage::Python, &empty, "http://x").contains("data="));
+        assert!(to_snippet(SnippetLanguage::Rust, &empty, "http://x").contains("reqwest::Method::from_bytes(b\"REPORT\")?"));
+        assert!(to_snippet(SnippetLanguage::Go, &empty, "http://x").contains("\"http://x\", nil)"));
+    }
+}
@@ -0,0 +1,215 @@
+//! Per-bin extractors: named values pulled out of JSON bodies at capture time with a
+//! small JSONPath subset, stored next to the request and filterable in `inspect`.
+
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::models::Extractor;
+
+/// Most extractors a bin can have
+pub const MAX_EXTRACTORS: usize = 20;
+
+/// Longest extracted value kept; longer values are cut at a character boundary
+const MAX_VALUE_CHARS: usize = 256;
+
+const MAX_NAME_LEN: usize = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Key(String),
+    /// Negative indexes count from the end
+    Index(i64),
+}
+
+/// A parsed path: `$` followed by `.key`, `['key']`, `["key"]` or `[index]` steps.
+/// Wildcards, slices and filters are not supported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPath(Vec<Step>);
+
+impl JsonPath {
+    pub fn parse(path: &str) -> Result<Self, String> {
+        let rest = path.trim().strip_prefix('$').ok_or_else(|| format!("JSONPath `{}` must start with `$`", path))?;
+        let unsupported = || format!("Unsupported JSONPath `{}`: use `.key`, `['key']` or `[index]` steps", path);
+        let mut chars = rest.chars().peekable();
+        let mut steps = Vec::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '.' => {
+                    let mut key = String::new();
+                    while let Some(&c) = chars.peek() {
+                        if c == '.' || c == '[' {
+                            break;
+                        }
+                        key.push(c);
+                        chars.next();
+                    }
+                    if key.is_empty() || key == "*" {
+                        return Err(unsupported());
+                    }
+                    steps.push(Step::Key(key));
+                }
+                '[' => match chars.peek() {
+                    Some(&quote) if quote == '\'' || quote == '"' => {
+                        chars.next();
+                        let mut key = String::new();
+                        loop {
+                            match chars.next() {
+                                Some('\\') => key.push(chars.next().ok_or_else(unsupported)?),
+                                Some(c) if c == quote => break,
+                                Some(c) => key.push(c),
+                                None => return Err(unsupported()),
+                            }
+                        }
+                        if chars.next() != Some(']') {
+                            return Err(unsupported());
+                        }
+                        steps.push(Step::Key(key));
+                    }
+                    _ => {
+                        let mut index = String::new();
+                        loop {
+                            match chars.next() {
+                                Some(']') => break,
+                                Some(c) => index.push(c),
+                                None => return Err(unsupported()),
+                            }
+                        }
+                        steps.push(Step::Index(index.trim().parse().map_err(|_| unsupported())?));
+                    }
+                },
+                _ => return Err(unsupported()),
+            }
+        }
+        Ok(JsonPath(steps))
+    }
+
+    pub fn select<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        self.0.iter().try_fold(value, |value, step| match (step, value) {
+            (Step::Key(key), Value::Object(object)) => object.get(key),
+            (Step::Index(index), Value::Array(items)) => {
+                let index = if *index < 0 { items.len().checked_sub(index.unsigned_abs() as usize)? } else { *index as usize };
+                items.get(index)
+            }
+            _ => None,
+        })
+    }
+}
+
+/// Check names and paths before they are stored
+pub fn validate_extractors(extractors: &[Extractor]) -> Result<(), String> {
+    if extractors.len() > MAX_EXTRACTORS {
+        return Err(format!("A bin can have at most {} extractors", MAX_EXTRACTORS));
+    }
+    let mut names = HashSet::new();
+    for extractor in extractors {
+        let name = extractor.name.as_str();
+        if name.is_empty() || name.len() > MAX_NAME_LEN || !name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')) {
+            return Err(format!(
+                "Extractor name `{}` must be 1 to {} letters, digits, `_`, `-` or `.`",
+                name, MAX_NAME_LEN
+            ));
+        }
+        if !names.insert(name) {
+            return Err(format!("Extractor name `{}` is used twice", name));
+        }
+        JsonPath::parse(&extractor.path)?;
+    }
+    Ok(())
+}
+
+/// A bin's extractors, parsed once per capture
+#[derive(Debug, Default)]
+pub struct Extractors(Vec<(String, JsonPath)>);
+
+impl Extractors {
+    /// Extractors whose path no longer parses are skipped
+    pub fn new(extractors: &[Extractor]) -> Self {
+        Extractors(
+            extractors
+                .iter()
+                .filter_map(|extractor| JsonPath::parse(&extractor.path).ok().map(|path| (extractor.name.clone(), path)))
+                .collect(),
+        )
+    }
+
+    /// The values found in a JSON body. Strings are kept as they are, other values as
+    /// JSON; `null` and missing values are left out.
+    pub fn extract(&self, body: &str) -> BTreeMap<String, String> {
+        if self.0.is_empty() {
+            return BTreeMap::new();
+        }
+        let Ok(json) = serde_json::from_str::<Value>(body) else {
+            return BTreeMap::new();
+        };
+        self.0
+            .iter()
+            .filter_map(|(name, path)| {
+                let value = match path.select(&json)? {
+                    Value::Null => return None,
+                    Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                };
+                Some((name.clone(), value.chars().take(MAX_VALUE_CHARS).collect()))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn extractor(name: &str, path: &str) -> Extractor {
+        Extractor { name: name.to_string(), path: path.to_string() }
+    }
+
+    #[test]
+    fn parses_and_selects_paths() {
+        let body = json!({ "data": { "object": { "id": "ord_1", "items": [{ "sku": "a" }, { "sku": "b" }] } }, "odd key": 7 });
+        let select = |path: &str| JsonPath::parse(path).unwrap().select(&body).cloned();
+        assert_eq!(select("$.data.object.id"), Some(json!("ord_1")));
+        assert_eq!(select("$.data.object.items[1].sku"), Some(json!("b")));
+        assert_eq!(select("$.data.object.items[-2]['sku']"), Some(json!("a")));
+        assert_eq!(select("$[\"odd key\"]"), Some(json!(7)));
+        assert_eq!(select("$"), Some(body.clone()));
+        assert_eq!(select("$.data.missing.id"), None);
+        assert_eq!(select("$.data.object.items[5]"), None);
+
+        for path in ["data.id", "$.", "$.items[*]", "$..id", "$[1:2]", "$[1", "$['open"] {
+            assert!(JsonPath::parse(path).is_err(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn extracts_values_as_strings() {
+        let extractors = Extractors::new(&[
+            extractor("order_id", "$.order.id"),
+            extractor("amount", "$.order.amount"),
+            extractor("paid", "$.order.paid"),
+            extractor("note", "$.order.note"),
+            extractor("missing", "$.nope"),
+        ]);
+        let fields = extractors.extract(r#"{"order": {"id": "123", "amount": 9.5, "paid": true, "note": null}}"#);
+        assert_eq!(
+            fields.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("amount".to_string(), "9.5".to_string()),
+                ("order_id".to_string(), "123".to_string()),
+                ("paid".to_string(), "true".to_string()),
+            ]
+        );
+        assert!(extractors.extract("not json").is_empty());
+    }
+
+    #[test]
+    fn validates_extractors() {
+        assert!(validate_extractors(&[extractor("order_id", "$.id"), extractor("event.type", "$.type")]).is_ok());
+        assert!(validate_extractors(&[extractor("", "$.id")]).is_err());
+        assert!(validate_extractors(&[extractor("order id", "$.id")]).is_err());
+        assert!(validate_extractors(&[extractor("id", "$.id"), extractor("id", "$.other")]).is_err());
+        assert!(validate_extractors(&[extractor("id", "id")]).is_err());
+        let many: Vec<_> = (0..=MAX_EXTRACTORS).map(|i| extractor(&format!("f{}", i), "$.id")).collect();
+        assert!(validate_extractors(&many).is_err());
+    }
+}
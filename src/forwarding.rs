@@ -0,0 +1,180 @@
+//! Mirroring captured requests to the forward targets attached to their bin.
+
+use chrono::{Duration, Utc};
+use futures::future::join_all;
+use std::sync::Arc;
+use std::time::Instant;
+use uuid::Uuid;
+use tracing::{error, info, warn};
+
+use crate::config::{ForwardingConfig, ReplayConfig};
+use crate::export::replay_url;
+use crate::models::{Forward, ForwardDelivery, LoggedRequest, ReplayResponse};
+use crate::replay::{truncate_body, Replayer};
+use crate::storage::{DueDelivery, Storage};
+
+pub const DELIVERED: &str = "delivered";
+pub const PENDING: &str = "pending";
+pub const FAILED: &str = "failed";
+
+/// Due deliveries retried per pass
+const RETRY_BATCH: i64 = 100;
+
+/// Sends each capture to all of the bin's enabled forwards at once, in the background, and
+/// records what every target answered. Failed deliveries are left pending for [`Forwarder::retry_due`]
+/// until `max_attempts` is used up. Does nothing when replay is disabled.
+pub struct Forwarder {
+    storage: Arc<dyn Storage>,
+    replayer: Arc<Replayer>,
+    enabled: bool,
+    max_attempts: u32,
+    retry_backoff_ms: u64,
+    max_response_body_bytes: usize,
+}
+
+impl Forwarder {
+    pub fn new(storage: Arc<dyn Storage>, replayer: Arc<Replayer>, replay: &ReplayConfig, config: &ForwardingConfig) -> Self {
+        Forwarder {
+            storage,
+            replayer,
+            enabled: replay.enabled,
+            max_attempts: config.max_attempts.max(1),
+            retry_backoff_ms: config.retry_backoff_ms,
+            max_response_body_bytes: config.max_response_body_bytes,
+        }
+    }
+
+    /// Queue delivery of `request` to the bin's enabled `forwards`. Never blocks the caller.
+    pub fn mirror(self: &Arc<Self>, bin_id: &str, forwards: &[Forward], request: LoggedRequest) {
+        let forwards: Vec<Forward> = forwards.iter().filter(|forward| forward.enabled).cloned().collect();
+        if !self.enabled || forwards.is_empty() {
+            return;
+        }
+        let forwarder = self.clone();
+        let bin_id = bin_id.to_string();
+        tokio::spawn(async move {
+            let deliveries = forwards.into_iter().map(|forward| {
+                let (forwarder, request, bin_id) = (&forwarder, &request, &bin_id);
+                async move {
+                    let url = replay_url(request, &forward.url);
+                    let delivery = forwarder.attempt(request, &url, 0, 1).await;
+                    if let Err(err) = forwarder.storage.record_delivery(&forward.forward_id, &delivery).await {
+                        error!(%bin_id, forward_id = %forward.forward_id, %err, "Failed to record forward delivery");
+                    }
+                }
+            });
+            join_all(deliveries).await;
+        });
+    }
+
+    /// Make the next attempt for every pending delivery that is due. Returns how many were attempted.
+    pub async fn retry_due(&self) -> usize {
+        if !self.enabled {
+            return 0;
+        }
+        let due = match self.storage.due_deliveries(&Utc::now().to_rfc3339(), RETRY_BATCH).await {
+            Ok(due) => due,
+            Err(err) => {
+                error!(%err, "Failed to load due forward deliveries");
+                return 0;
+            }
+        };
+
+        let count = due.len();
+        for DueDelivery { bin_id, forward_id, url, delivery } in due {
+            let updated = match self.storage.get_request(&bin_id, delivery.request_id).await {
+                Ok(Some(request)) => {
+                    let url = replay_url(&request, &url);
+                    self.attempt(&request, &url, delivery.delivery_id, delivery.attempts + 1).await
+                }
+                // Trimmed or deleted since the first attempt; there is nothing left to send
+                Ok(None) => ForwardDelivery {
+                    status: FAILED.to_string(),
+                    error: Some("request is no longer stored".to_string()),
+                    next_attempt_at: None,
+                    ..delivery
+                },
+                Err(err) => {
+                    error!(%bin_id, %forward_id, %err, "Failed to load request for forward retry");
+                    continue;
+                }
+            };
+            if let Err(err) = self.storage.update_delivery(&updated).await {
+                error!(%bin_id, %forward_id, %err, "Failed to record forward delivery");
+            }
+        }
+        count
+    }
+
+    /// Attempt number `attempts` of a delivery, scheduling another one if any are left
+    async fn attempt(&self, request: &LoggedRequest, url: &str, delivery_id: i64, attempts: u32) -> ForwardDelivery {
+        let now = Utc::now();
+        let mut delivery = ForwardDelivery {
+            delivery_id,
+            request_id: request.request_id,
+            status: DELIVERED.to_string(),
+            status_code: None,
+            error: None,
+            attempts,
+            attempted_at: now.to_rfc3339(),
+            next_attempt_at: None,
+            latency_ms: None,
+            response: None,
+        };
+
+        let started = Instant::now();
+        let result = self.replayer.deliver(request, url, self.max_response_body_bytes).await;
+        let error = match result {
+            Ok(response) => {
+                let code = response.status;
+                delivery.status_code = Some(code);
+                delivery.latency_ms = Some(started.elapsed().as_millis() as u64);
+                delivery.response = Some(response);
+                if (200..300).contains(&code) {
+                    info!(%url, request_id = %request.request_id, status = code, attempts, "Request forwarded");
+                    return delivery;
+                }
+                format!("target answered with status {}", code)
+            }
+            Err(err) => err.to_string(),
+        };
+
+        if attempts < self.max_attempts {
+            warn!(%url, request_id = %request.request_id, attempts, %error, "Forward failed, will retry");
+            delivery.status = PENDING.to_string();
+            delivery.next_attempt_at = Some((now + self.backoff(attempts)).to_rfc3339());
+        } else {
+            warn!(%url, request_id = %request.request_id, attempts, %error, "Giving up on forward");
+            delivery.status = FAILED.to_string();
+        }
+        delivery.error = Some(error);
+        delivery
+    }
+
+    /// A delivery of `request_id` made outside the forwards, by a replay or a tunnel client,
+    /// ready to be recorded. The response body is cut like a forward's.
+    pub fn outside_delivery(&self, request_id: Uuid, outcome: Result<&ReplayResponse, String>, latency_ms: Option<u64>) -> ForwardDelivery {
+        let (status, response, error) = match outcome {
+            Ok(response) => (DELIVERED, Some(truncate_body(response, self.max_response_body_bytes)), None),
+            Err(err) => (FAILED, None, Some(err)),
+        };
+        ForwardDelivery {
+            delivery_id: 0,
+            request_id,
+            status: status.to_string(),
+            status_code: response.as_ref().map(|response| response.status),
+            error,
+            attempts: 1,
+            attempted_at: Utc::now().to_rfc3339(),
+            next_attempt_at: None,
+            latency_ms: latency_ms.filter(|_| response.is_some()),
+            response,
+        }
+    }
+
+    /// Delay after attempt number `attempts`, doubling from `retry_backoff_ms`
+    fn backoff(&self, attempts: u32) -> Duration {
+        let factor = 1u64.checked_shl(attempts.saturating_sub(1)).unwrap_or(u64::MAX);
+        Duration::milliseconds(self.retry_backoff_ms.saturating_mul(factor).min(i64::MAX as u64) as i64)
+    }
+}
@@ -0,0 +1,300 @@
+//! GraphQL API over bins and their captured requests (`/graphql`), so a client can pick
+//! fields and filters in one query instead of combining REST endpoints.
+
+use async_graphql::{Context, EmptyMutation, Error, ErrorExtensions, Json, Object, Result, Schema, SimpleObject, Subscription, ID};
+use futures::Stream;
+use tokio::sync::broadcast;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::handlers::{optional_timestamp_param, ApiError, parse_header_filter, validate_bin_id, DEFAULT_INSPECT_LIMIT, MAX_INSPECT_LIMIT};
+use crate::models::{BinInfo, RequestNotification, WsEvent, WsMessage};
+use crate::state::AppState;
+use crate::storage::{PageRequest, RequestFilter, StorageError};
+use crate::utils::token::constant_time_eq;
+use crate::websocket::Listener;
+
+pub type RustbinSchema = Schema<QueryRoot, EmptyMutation, SubscriptionRoot>;
+
+/// Deepest query accepted; the schema itself nests three levels
+const MAX_QUERY_DEPTH: usize = 8;
+
+/// The token the client presented, checked against each private bin it asks for
+pub struct BinToken(pub Option<String>);
+
+pub fn schema(state: AppState) -> RustbinSchema {
+    Schema::build(QueryRoot, EmptyMutation, SubscriptionRoot)
+        .data(state)
+        .limit_depth(MAX_QUERY_DEPTH)
+        .finish()
+}
+
+/// A REST error as a GraphQL one, keeping its code in the extensions
+fn graphql_error(error: ApiError) -> Error {
+    let code = error.code;
+    Error::new(error.detail).extend_with(|_, extensions| extensions.set("code", code))
+}
+
+/// Whether the bin exists, failing when it is private and the client didn't present its
+/// token, so private bins behave as they do over REST
+async fn authorize(ctx: &Context<'_>, id: &str) -> Result<bool> {
+    validate_bin_id(id).map_err(graphql_error)?;
+    let state = ctx.data::<AppState>()?;
+    let expected = match state.storage.bin_token(id).await {
+        Ok(Some(Some(token))) => token,
+        Ok(Some(None)) => return Ok(true),
+        Ok(None) => return Ok(false),
+        Err(err) => {
+            error!(%id, %err, "Failed to look up bin token");
+            return Err(Error::new("Failed to check bin access"));
+        }
+    };
+    match ctx.data_opt::<BinToken>().and_then(|token| token.0.as_deref()) {
+        Some(token) if constant_time_eq(token, &expected) => Ok(true),
+        _ => Err(Error::new("Invalid or missing bin token")),
+    }
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// A bin by id, or `null` when there is no such bin
+    async fn bin(&self, ctx: &Context<'_>, id: ID) -> Result<Option<Bin>> {
+        if !authorize(ctx, &id).await? {
+            return Ok(None);
+        }
+        let state = ctx.data::<AppState>()?;
+        state.storage.bin_info(&id).await.map(|info| info.map(Bin)).map_err(|err| {
+            error!(id = %id.as_str(), %err, "Failed to fetch bin info");
+            Error::new("Failed to fetch bin info")
+        })
+    }
+}
+
+pub struct Bin(BinInfo);
+
+#[Object]
+impl Bin {
+    async fn id(&self) -> ID {
+        ID(self.0.bin_id.clone())
+    }
+
+    async fn private(&self) -> bool {
+        self.0.private
+    }
+
+    async fn name(&self) -> Option<&str> {
+        self.0.name.as_deref()
+    }
+
+    async fn description(&self) -> Option<&str> {
+        self.0.description.as_deref()
+    }
+
+    async fn tags(&self) -> &[String] {
+        &self.0.tags
+    }
+
+    /// Whether captures are turned away rather than stored
+    async fn paused(&self) -> bool {
+        self.0.paused
+    }
+
+    /// Captures answered but not stored because of the bin's sampling
+    async fn sampled_out(&self) -> i64 {
+        self.0.sampled_out
+    }
+
+    /// When the bin last captured a request or was updated, RFC 3339
+    async fn last_updated(&self) -> Option<&str> {
+        self.0.last_updated.as_deref()
+    }
+
+    /// Captured requests, oldest first, with the same filters as `/bin/{id}/inspect`.
+    /// `since` and `until` take RFC 3339 timestamps or relative ones like `-15m`, and
+    /// `header` is `name` or `name: value`.
+    #[allow(clippy::too_many_arguments)]
+    async fn requests(
+        &self,
+        ctx: &Context<'_>,
+        method: Option<String>,
+        since: Option<String>,
+        until: Option<String>,
+        header: Option<String>,
+        after: Option<ID>,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> Result<RequestPage> {
+        let (header_name, header_value) = match header.as_deref() {
+            Some(raw) => {
+                let (name, value) = parse_header_filter(raw).map_err(graphql_error)?;
+                (Some(name), value)
+            }
+            None => (None, None),
+        };
+        let filter = RequestFilter {
+            method: method.map(|method| method.to_ascii_uppercase()),
+            since: optional_timestamp_param("since", since.as_deref()).map_err(graphql_error)?,
+            until: optional_timestamp_param("until", until.as_deref()).map_err(graphql_error)?,
+            header_name,
+            header_value,
+            field_name: None,
+            field_value: None,
+        };
+        let page = PageRequest {
+            after: after.as_deref().map(|after| validate_bin_id(after)).transpose().map_err(graphql_error)?,
+            limit: limit.unwrap_or(DEFAULT_INSPECT_LIMIT).clamp(1, MAX_INSPECT_LIMIT),
+            offset: offset.unwrap_or(0).max(0),
+        };
+
+        let state = ctx.data::<AppState>()?;
+        match state.storage.list_requests(&self.0.bin_id, &filter, page).await {
+            Ok((requests, total)) => Ok(RequestPage {
+                total,
+                nodes: requests.into_iter().map(|request| LoggedRequest(request.into())).collect(),
+            }),
+            Err(StorageError::UnknownCursor) => Err(Error::new("Unknown `after` for this bin")),
+            Err(err) => {
+                error!(id = %self.0.bin_id, %err, "Failed to fetch logged requests");
+                Err(Error::new("Failed to fetch logged requests"))
+            }
+        }
+    }
+
+    /// One captured request, or `null` when the bin has no such request
+    async fn request(&self, ctx: &Context<'_>, id: ID) -> Result<Option<LoggedRequest>> {
+        let request_id = Uuid::parse_str(&id).map_err(|_| Error::new("Invalid request id"))?;
+        let state = ctx.data::<AppState>()?;
+        match state.storage.get_request(&self.0.bin_id, request_id).await {
+            Ok(request) => Ok(request.map(|request| LoggedRequest(request.into()))),
+            Err(err) => {
+                error!(id = %self.0.bin_id, %request_id, %err, "Failed to fetch request");
+                Err(Error::new("Failed to fetch request"))
+            }
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct RequestPage {
+    /// Requests matching the filters, across all pages
+    total: i64,
+    nodes: Vec<LoggedRequest>,
+}
+
+#[derive(SimpleObject)]
+pub struct Header {
+    name: String,
+    value: String,
+}
+
+/// A captured request, with headers and query parameters decoded like on the WebSocket
+pub struct LoggedRequest(RequestNotification);
+
+#[Object]
+impl LoggedRequest {
+    async fn id(&self) -> ID {
+        ID(self.0.request_id.to_string())
+    }
+
+    async fn method(&self) -> &str {
+        &self.0.method
+    }
+
+    /// Path below the bin, `/` for the bin itself
+    async fn path(&self) -> &str {
+        self.0.path.as_deref().unwrap_or("/")
+    }
+
+    /// In the order they were received
+    async fn headers(&self) -> Vec<Header> {
+        self.0.headers.iter().map(|(name, value)| Header { name: name.clone(), value: value.clone() }).collect()
+    }
+
+    /// The first value of a header, matched case-insensitively
+    async fn header(&self, name: String) -> Option<&str> {
+        self.0.headers.iter().find(|(header, _)| header.eq_ignore_ascii_case(&name)).map(|(_, value)| value.as_str())
+    }
+
+    async fn body(&self) -> Option<&str> {
+        self.0.body.as_deref()
+    }
+
+    /// "utf8" when `body` is the text as sent, "base64" for binary payloads
+    async fn body_encoding(&self) -> Option<&str> {
+        self.0.body_encoding.as_deref()
+    }
+
+    async fn content_encoding(&self) -> Option<&str> {
+        self.0.content_encoding.as_deref()
+    }
+
+    async fn body_format(&self) -> Option<&str> {
+        self.0.body_format.as_deref()
+    }
+
+    async fn content_type_mismatch(&self) -> bool {
+        self.0.content_type_mismatch
+    }
+
+    async fn query_string(&self) -> Option<&str> {
+        self.0.query_string.as_deref()
+    }
+
+    async fn query_params(&self) -> Option<Json<&serde_json::Value>> {
+        self.0.query_params.as_ref().map(Json)
+    }
+
+    async fn source_ip(&self) -> Option<&str> {
+        self.0.source_ip.as_deref()
+    }
+
+    async fn source_port(&self) -> Option<u16> {
+        self.0.source_port
+    }
+
+    /// Whether the bin's signature verification accepted the request; `null` when not checked
+    async fn signature_valid(&self) -> Option<bool> {
+        self.0.signature_valid
+    }
+
+    /// Failure the bin's chaos settings injected into the answer: "error", "drop" or "slow"
+    async fn chaos(&self) -> Option<&str> {
+        self.0.chaos.as_deref()
+    }
+
+    async fn timestamp(&self) -> &str {
+        &self.0.timestamp
+    }
+}
+
+pub struct SubscriptionRoot;
+
+#[Subscription]
+impl SubscriptionRoot {
+    /// Requests the bin captures from now on; ends when the bin is deleted
+    async fn request_logged(&self, ctx: &Context<'_>, bin_id: ID) -> Result<impl Stream<Item = LoggedRequest> + use<>> {
+        if !authorize(ctx, &bin_id).await? {
+            return Err(Error::new("Bin not found"));
+        }
+        let state = ctx.data::<AppState>()?;
+        // Joins the channel WebSocket clients use, so captures reach both the same way
+        let listener = Listener::subscribe(state, &bin_id);
+
+        Ok(futures::stream::unfold(listener, |mut listener| async move {
+            loop {
+                let message = match listener.recv().await {
+                    Ok(message) => message,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                };
+                match serde_json::from_str::<WsMessage>(&message).map(|message| message.event) {
+                    Ok(WsEvent::Request(request)) => return Some((LoggedRequest(*request), listener)),
+                    Ok(WsEvent::BinDeleted { .. }) => return None,
+                    _ => continue,
+                }
+            }
+        }))
+    }
+}
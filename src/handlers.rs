@@ -1,27 +1,58 @@
 use axum::{
-    body::Body,
-    extract::{ConnectInfo, Path, Query, State},
-    http::{header, HeaderValue, Request, StatusCode},
+    body::{Body, Bytes},
+    extract::{rejection::JsonRejection, ConnectInfo, Path, Query, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, Request, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
-use chrono::Utc;
-use http_body_util::BodyExt;
-use sqlx::query;
-use std::{collections::HashMap, net::SocketAddr};
+use chrono::{DateTime, Duration, Utc};
+use futures::StreamExt;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
 use crate::{
-    models::{BinResponse, LoggedRequest, PingQuery, PingResponse},
+    capture,
+    diff,
+    replay::{parse_target, ReplayError},
+    models::{AdminBinsQuery, AdminSearchQuery, AdminStats, AdminStorage, AdminStorageQuery, BinChaos, BinInfo, BinMetadataUpdate, BinLimits, BinNotifications, BinPause, BinProxy, BinRateLimit, BinResponse, BinSampling, BinScript, BinSearchQuery, Callback, CaptureResponse, ClearQuery, CreateBinRequest, CreateCallback, CreatedCallback, CreateForward, CreateResponseRule, CreatorBinSummary, CustomResponse, DiffQuery, ExportQuery, Extractor, FeedQuery, Forward, ForwardUpdate, HealthResponse, InspectQuery, LoggedRequest, PingQuery, PingResponse, PollQuery, ProblemDetails, ReadinessChecks, ReadinessResponse, CheckResult, IpAccessRules, RedactionRules, RejectedRequest, ReplayOverrides, ReplayRequest, ReplayResponse, ReportedDelivery, RequestDelivery, ResponseRule, ResponseSequence, SignatureProvider, SignatureVerification, SimulateQuery, SnippetQuery, WsEvent, WsMessage},
     state::AppState,
 };
+use crate::export::{
+    atom::to_atom_feed,
+    csv::{to_csv_row, CSV_HEADER},
+    curl::to_curl,
+    html::to_html_page,
+    import::{parse_import, ImportedRequest},
+    postman::to_postman_collection,
+    snippet::{to_snippet, SnippetLanguage},
+    export_url, replay_url, to_ndjson_line, ExportFormat,
+};
+use crate::chaos::ChaosAction;
+use crate::extractors::{validate_extractors, Extractors};
+use crate::health::TaskStatus;
+use crate::ip_access::{parse_network, IpMatcher};
+use crate::plugins::PluginCapture;
+use crate::redaction::Redactor;
+use crate::routes::body_limit::BodyReadError;
+use crate::rules::select_rule;
+use crate::samples::{self, SampleProvider};
+use crate::scripts::MAX_SCRIPT_LENGTH;
+use crate::signatures;
+use crate::storage::{BinSettings, InsertOutcome, NewBin, PageRequest, RequestFilter, StorageError, KIND_PROXY, KIND_REPLAY, KIND_TUNNEL, MAX_RESPONSE_RULES, MAX_SEQUENCE_RESPONSES};
+use crate::utils::body::{decode_body, encode_body, ENCODING_UTF8};
+use crate::utils::decompress::{decompress_body, DecompressError};
+use crate::utils::headers::{headers_to_json, parse_stored_headers, preferred_media_type, stored_header};
+use crate::utils::sniff::{is_content_type_mismatch, sniff_body, BodyFormat};
+use crate::utils::template::{render_template, validate_template};
+use crate::utils::token::generate_token;
+use crate::websocket::{broadcast, Listener};
 use crate::utils::uuid::validate_uuid;
-
-#[cfg(test)]
-use std::sync::Arc;
-#[cfg(test)]
-use dashmap::DashMap;
+use crate::webhooks::callbacks::is_callback_event;
+use crate::webhooks::BinEvent;
 
 // Note: These constants are now configured via rustbin.toml
 // They remain here for backwards compatibility with tests
@@ -30,79 +61,423 @@ pub const MAX_HEADERS_SIZE: usize = 1024 * 1024; // 1MB
 #[cfg(test)]
 pub const MAX_BODY_SIZE: usize = 1024 * 1024; // 1MB
 
+pub(crate) const DEFAULT_INSPECT_LIMIT: i64 = 100;
+pub(crate) const MAX_INSPECT_LIMIT: i64 = 1000;
+const DEFAULT_POLL_TIMEOUT_SECONDS: u64 = 30;
+const MAX_POLL_TIMEOUT_SECONDS: u64 = 60;
+/// Response header carrying the number of requests stored in a bin
+pub const TOTAL_COUNT_HEADER: &str = "x-total-count";
+/// Response header carrying the id a capture was stored under
+pub const REQUEST_ID_HEADER: &str = "x-rustbin-request-id";
+/// Response header carrying how many requests a bin holds, on captures and HEAD probes
+pub const REQUEST_COUNT_HEADER: &str = "x-rustbin-request-count";
+/// Request header marking a capture made by `POST /bin/:id/simulate`, naming the sample
+pub const SIMULATED_HEADER: &str = "x-rustbin-simulated";
+/// Request header naming the client that creates bins, for `GET /my/bins`
+pub const CREATOR_TOKEN_HEADER: &str = "x-creator-token";
+
+const MAX_BIN_NAME_LENGTH: usize = 100;
+const MAX_BIN_DESCRIPTION_LENGTH: usize = 1000;
+const MAX_BIN_TAGS: usize = 20;
+const MAX_BIN_TAG_LENGTH: usize = 50;
+const MAX_CREATOR_TOKEN_LENGTH: usize = 256;
+
+const DEFAULT_ADMIN_BINS_LIMIT: i64 = 50;
+const MAX_ADMIN_BINS_LIMIT: i64 = 500;
+const DEFAULT_STORAGE_TOP_BINS: i64 = 20;
+
+/// Representations of `inspect`, chosen by `Accept`; the first is the default
+const INSPECT_MEDIA_TYPES: [&str; 3] = ["application/json", "text/html", "text/csv"];
+const DEFAULT_FEED_ENTRIES: i64 = 20;
+const MAX_FEED_ENTRIES: i64 = 100;
+
+const DEFAULT_SEARCH_LIMIT: i64 = 50;
+const MAX_SEARCH_LIMIT: i64 = 500;
+const DEFAULT_SEARCH_WINDOW_HOURS: i64 = 24;
+
+/// Longest a capture may ask to be delayed with `_delay`
+const MAX_RESPONSE_DELAY_MS: u64 = 30_000;
+
+/// An error answered as an RFC 7807 `application/problem+json` document. `code` is the
+/// stable, machine-readable part; `detail` may change wording between releases.
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub detail: String,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &'static str, detail: impl Into<String>) -> Self {
+        Self { status, code, detail: detail.into() }
+    }
+
+    /// Replace the generic code derived from the status with a more specific one
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = code;
+        self
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.detail)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let problem = ProblemDetails {
+            problem_type: "about:blank".to_string(),
+            title: self.status.canonical_reason().unwrap_or("Error").to_string(),
+            status: self.status.as_u16(),
+            detail: self.detail,
+            code: self.code.to_string(),
+        };
+        (self.status, [(header::CONTENT_TYPE, "application/problem+json")], Json(problem)).into_response()
+    }
+}
+
 // Common error response helpers
-fn internal_error(message: String) -> (StatusCode, String) {
-    (StatusCode::INTERNAL_SERVER_ERROR, message)
+pub(crate) fn internal_error(message: String) -> ApiError {
+    ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", message)
+}
+
+pub(crate) fn not_found_error(message: String) -> ApiError {
+    ApiError::new(StatusCode::NOT_FOUND, "not_found", message)
+}
+
+pub(crate) fn bad_request_error(message: String) -> ApiError {
+    ApiError::new(StatusCode::BAD_REQUEST, "bad_request", message)
 }
 
-fn not_found_error(message: String) -> (StatusCode, String) {
-    (StatusCode::NOT_FOUND, message)
+pub(crate) fn payload_too_large_error(message: String) -> ApiError {
+    ApiError::new(StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large", message)
 }
 
-fn bad_request_error(message: String) -> (StatusCode, String) {
-    (StatusCode::BAD_REQUEST, message)
+pub(crate) fn forbidden_error(message: String) -> ApiError {
+    ApiError::new(StatusCode::FORBIDDEN, "forbidden", message)
 }
 
-fn payload_too_large_error(message: String) -> (StatusCode, String) {
-    (StatusCode::PAYLOAD_TOO_LARGE, message)
+pub(crate) fn unauthorized_error(message: String) -> ApiError {
+    ApiError::new(StatusCode::UNAUTHORIZED, "unauthorized", message)
 }
 
 // Validation helpers
-fn validate_bin_id(id: &str) -> Result<Uuid, (StatusCode, String)> {
-    validate_uuid(id).map_err(|e| bad_request_error(e))
+pub(crate) fn validate_bin_id(id: &str) -> Result<Uuid, ApiError> {
+    validate_uuid(id).map_err(|message| bad_request_error(message).with_code("invalid_id"))
 }
 
-async fn check_bin_exists(state: &AppState, id: &str) -> Result<(), (StatusCode, String)> {
-    let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM bins WHERE id = ?")
-        .bind(id)
-        .fetch_one(&state.db)
-        .await
-        .map_err(|err| {
-            error!(%id, %err, "Failed to check bin existence");
-            internal_error("Failed to check bin existence".to_string())
-        })?;
+pub(crate) async fn check_bin_exists(state: &AppState, id: &str) -> Result<(), ApiError> {
+    let exists = match state.bin_cache.get(id) {
+        Some(exists) => exists,
+        None => {
+            let exists = state.storage.bin_exists(id).await.map_err(|err| {
+                error!(%id, %err, "Failed to check bin existence");
+                internal_error("Failed to check bin existence".to_string())
+            })?;
+            state.bin_cache.insert(id, exists);
+            exists
+        }
+    };
 
-    if count == 0 {
+    if !exists {
         warn!(%id, "Attempted to access non-existent bin");
-        return Err(not_found_error("Bin not found".to_string()));
+        return Err(not_found_error("Bin not found".to_string()).with_code("bin_not_found"));
     }
     Ok(())
 }
 
+/// Everything a capture into the bin depends on, from the cache when it is warm. Not
+/// found for unknown bins, like [`check_bin_exists`].
+pub(crate) async fn load_bin_settings(state: &AppState, id: &str) -> Result<Arc<BinSettings>, ApiError> {
+    if let Some(settings) = state.bin_cache.settings(id) {
+        return Ok(settings);
+    }
+    let settings = match state.bin_cache.get(id) {
+        Some(false) => None,
+        _ => state.storage.bin_settings(id).await.map_err(|err| {
+            error!(%id, %err, "Failed to load bin settings");
+            internal_error("Failed to load bin settings".to_string())
+        })?,
+    };
+    match settings {
+        Some(settings) => {
+            let settings = Arc::new(settings);
+            state.bin_cache.insert_settings(id, settings.clone());
+            Ok(settings)
+        }
+        None => {
+            state.bin_cache.insert(id, false);
+            warn!(%id, "Attempted to access non-existent bin");
+            Err(not_found_error("Bin not found".to_string()).with_code("bin_not_found"))
+        }
+    }
+}
+
 // Request processing helpers
 #[derive(Debug)]
 struct ProcessedRequest {
     method: String,
     headers_json: String,
     body: String,
+    body_encoding: &'static str,
+    content_encoding: Option<String>,
     request_id: Uuid,
+    body_format: BodyFormat,
+    content_type_mismatch: bool,
+    query_string: Option<String>,
+    query_params: Option<String>,
+    source_ip: Option<String>,
+    source_port: Option<u16>,
+    path: String,
+    timestamp: String,
+    signature_valid: Option<bool>,
+    fields: BTreeMap<String, String>,
+    chaos: Option<ChaosAction>,
+}
+
+impl ProcessedRequest {
+    /// The row handed to storage
+    fn to_logged_request(&self) -> LoggedRequest {
+        LoggedRequest {
+            method: self.method.clone(),
+            headers: self.headers_json.clone(),
+            body: Some(self.body.clone()),
+            body_encoding: Some(self.body_encoding.to_string()),
+            content_encoding: self.content_encoding.clone(),
+            timestamp: self.timestamp.clone(),
+            request_id: self.request_id,
+            body_format: Some(self.body_format.as_str().to_string()),
+            content_type_mismatch: self.content_type_mismatch,
+            query_string: self.query_string.clone(),
+            query_params: self.query_params.clone(),
+            source_ip: self.source_ip.clone(),
+            source_port: self.source_port,
+            path: Some(self.path.clone()),
+            signature_valid: self.signature_valid,
+            fields: self.fields.clone(),
+            pinned: false,
+            chaos: self.chaos.map(|action| action.as_str().to_string()),
+        }
+    }
+
+    /// The capture as `[plugins]` see it
+    fn to_plugin_capture(&self, bin_id: &str) -> PluginCapture {
+        PluginCapture {
+            bin_id: bin_id.to_string(),
+            request_id: self.request_id,
+            method: self.method.clone(),
+            path: self.path.clone(),
+            query_string: self.query_string.clone(),
+            headers: parse_stored_headers(&self.headers_json),
+            body: (self.body_encoding == ENCODING_UTF8).then(|| self.body.clone()),
+            source_ip: self.source_ip.clone(),
+        }
+    }
+
+    /// Take the plugins' changes, checking the body against its `Content-Type` again
+    fn apply_plugin_capture(&mut self, capture: PluginCapture) {
+        self.path = capture.path;
+        self.query_params = capture.query_string.as_deref().map(parse_query_params);
+        self.query_string = capture.query_string;
+        self.headers_json = serde_json::to_string(&capture.headers).unwrap_or_else(|_| "[]".to_string());
+        if let Some(body) = capture.body {
+            self.body_format = sniff_body(body.as_bytes());
+            self.body = body;
+        }
+        let content_type = stored_header(&self.headers_json, "content-type");
+        self.content_type_mismatch = is_content_type_mismatch(content_type.as_deref(), self.body_format);
+    }
+
+    /// Run the bin's extractors over a text body
+    fn extract(&mut self, extractors: &Extractors) {
+        if self.body_encoding == ENCODING_UTF8 {
+            self.fields = extractors.extract(&self.body);
+        }
+    }
+
+    /// Mask sensitive header values and body matches in place. Binary bodies are left alone.
+    fn redact(&mut self, redactor: &Redactor) {
+        if redactor.is_empty() {
+            return;
+        }
+        let mut headers = parse_stored_headers(&self.headers_json);
+        if redactor.redact_headers(&mut headers) {
+            self.headers_json = serde_json::to_string(&headers).unwrap_or_else(|_| "[]".to_string());
+        }
+        if self.body_encoding == ENCODING_UTF8
+            && let Cow::Owned(body) = redactor.redact_body(&self.body)
+        {
+            self.body = body;
+        }
+    }
+}
+
+/// Reject captures from sources outside the bin's allow list or inside its deny list,
+/// recording them when the bin asks for it
+async fn check_ip_access(state: &AppState, id: &str, rules: Option<&IpAccessRules>, method: &str, path: &str, client: IpAddr) -> Result<(), ApiError> {
+    let Some(rules) = rules else {
+        return Ok(());
+    };
+    match IpMatcher::new(rules) {
+        Ok(matcher) if matcher.permits(client) => return Ok(()),
+        Ok(_) => {}
+        Err(err) => {
+            warn!(%id, %err, "Ignoring invalid IP access rules");
+            return Ok(());
+        }
+    }
+
+    warn!(%id, %client, %method, "Rejected capture from a source the bin does not accept");
+    if rules.log_rejected {
+        let rejected = RejectedRequest {
+            source_ip: client.to_canonical().to_string(),
+            method: method.to_string(),
+            path: path.to_string(),
+            timestamp: Utc::now().to_rfc3339(),
+        };
+        if let Err(err) = state.storage.record_rejected_request(id, &rejected).await {
+            error!(%id, %err, "Failed to record rejected capture");
+        }
+    }
+    Err(forbidden_error("Source address not allowed".to_string()).with_code("source_not_allowed"))
+}
+
+/// Answer a capture the bin's bucket had no token for, saying when to try again
+fn bin_rate_limited(wait: std::time::Duration) -> Response {
+    let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+    let error = ApiError::new(StatusCode::TOO_MANY_REQUESTS, "rate_limited", "Bin rate limit exceeded");
+    ([(header::RETRY_AFTER, retry_after.to_string())], error).into_response()
+}
+
+/// Answer captures into a paused bin with its status, without reading or storing them
+pub(crate) fn check_bin_paused(settings: &BinSettings) -> Result<(), ApiError> {
+    match settings.pause {
+        Some(pause) => {
+            let status = StatusCode::from_u16(pause.status).unwrap_or(StatusCode::SERVICE_UNAVAILABLE);
+            Err(ApiError::new(status, "bin_paused", "Bin is paused and not capturing requests"))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Pass the capture through the `[plugins]`, applying their changes. `false` when one of
+/// them dropped it.
+async fn run_plugins(state: &AppState, id: &str, request: &mut ProcessedRequest) -> bool {
+    if state.plugins.is_empty() {
+        return true;
+    }
+    let plugins = state.plugins.clone();
+    let capture = request.to_plugin_capture(id);
+    match tokio::task::spawn_blocking(move || plugins.run(capture)).await {
+        Ok(Some(capture)) => {
+            request.apply_plugin_capture(capture);
+            true
+        }
+        Ok(None) => false,
+        Err(err) => {
+            error!(%id, %err, "Plugins panicked, storing the capture as it is");
+            true
+        }
+    }
+}
+
+/// Whether a sampling bin stores this capture, counting it as sampled out when it doesn't
+async fn sampled_in(state: &AppState, id: &str, sampling: Option<BinSampling>, request_id: Uuid) -> bool {
+    let Some(sampling) = sampling else {
+        return true;
+    };
+    let keep = match (sampling.every, sampling.percent) {
+        (Some(every), _) => match state.storage.next_sample(id).await {
+            Ok(seen) => (seen - 1).rem_euclid(i64::from(every.max(1))) == 0,
+            Err(err) => {
+                error!(%id, %err, "Failed to count sampled capture");
+                true
+            }
+        },
+        // Request ids are random v4 UUIDs; their leading 53 bits make a fair draw in [0, 1)
+        (None, Some(percent)) => (request_id.as_u64_pair().0 >> 11) as f64 / (1u64 << 53) as f64 * 100.0 < percent,
+        (None, None) => true,
+    };
+    if !keep && let Err(err) = state.storage.record_sampled_out(id).await {
+        error!(%id, %err, "Failed to count sampled out capture");
+    }
+    keep
+}
+
+/// Refuse new requests while the store is over `[cleanup] max_storage_bytes` and the
+/// policy is to reject rather than evict
+fn check_storage_quota(state: &AppState) -> Result<(), ApiError> {
+    if state.storage_quota.is_full() {
+        warn!("Storage is full, refusing capture");
+        return Err(ApiError::new(StatusCode::INSUFFICIENT_STORAGE, "storage_full", "Storage is full, try again later"));
+    }
+    Ok(())
+}
+
+/// The global redaction rules extended with the bin's own
+pub(crate) fn bin_redactor<'a>(state: &'a AppState, id: &str, rules: Option<&RedactionRules>) -> Cow<'a, Redactor> {
+    match rules.map(|rules| state.redactor.with_rules(rules)) {
+        Some(Ok(redactor)) => Cow::Owned(redactor),
+        Some(Err(err)) => {
+            warn!(%id, %err, "Ignoring invalid redaction rules");
+            Cow::Borrowed(state.redactor.as_ref())
+        }
+        None => Cow::Borrowed(state.redactor.as_ref()),
+    }
+}
+
+/// Decode a raw query string into a JSON object. Repeated keys become arrays.
+fn parse_query_params(query: &str) -> String {
+    let pairs: Vec<(String, String)> = serde_urlencoded::from_str(query).unwrap_or_default();
+    let mut params = serde_json::Map::new();
+    for (key, value) in pairs {
+        match params.get_mut(&key) {
+            Some(serde_json::Value::Array(values)) => values.push(value.into()),
+            Some(existing) => *existing = serde_json::json!([existing.take(), value]),
+            None => {
+                params.insert(key, value.into());
+            }
+        }
+    }
+    serde_json::Value::Object(params).to_string()
 }
 
 async fn process_request_data(
     req: Request<Body>,
     id: &str,
+    path: String,
     addr: &SocketAddr,
+    client: IpAddr,
     limits: &crate::config::LimitsConfig,
-) -> Result<ProcessedRequest, (StatusCode, String)> {
+    signature: Option<&SignatureVerification>,
+) -> Result<ProcessedRequest, ApiError> {
     let (parts, body) = req.into_parts();
     let method = parts.method;
     let headers = parts.headers;
+    let query_string = parts.uri.query().map(str::to_string);
 
-    let body_bytes = body.collect().await.unwrap().to_bytes();
-    let body_str = String::from_utf8_lossy(&body_bytes).to_string();
-    
-    // Validate body size
-    if body_bytes.len() > limits.max_body_size {
-        warn!(%id, %addr, body_size = body_bytes.len(), max_allowed = limits.max_body_size, "Request body too large, rejecting");
-        return Err(payload_too_large_error("Request body exceeds size limit".to_string()));
+    let mut body_bytes = read_body(body, &headers, id, addr, limits.max_body_size).await?;
+
+    // Transparently decode compressed bodies; undecodable ones are stored as sent
+    let content_encoding = headers
+        .get(header::CONTENT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    if let Some(encoding) = content_encoding.as_deref() {
+        match decompress_body(encoding, &body_bytes, limits.max_decompressed_body_size) {
+            Ok(decoded) => body_bytes = decoded,
+            Err(DecompressError::TooLarge) => {
+                warn!(%id, %addr, %encoding, max_allowed = limits.max_decompressed_body_size, "Decompressed body too large, rejecting");
+                return Err(payload_too_large_error("Decompressed request body exceeds size limit".to_string()));
+            }
+            Err(err) => warn!(%id, %addr, %encoding, %err, "Could not decompress body, storing it as sent"),
+        }
     }
+    let (body_str, body_encoding) = encode_body(&body_bytes);
 
-    let headers_json = serde_json::to_string(
-        &headers
-            .iter()
-            .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
-            .collect::<HashMap<_, _>>(),
-    ).unwrap_or_else(|_| "{}".to_string());
+    let headers_json = headers_to_json(&headers);
 
     // Validate headers size
     if headers_json.len() > limits.max_headers_size {
@@ -110,53 +485,152 @@ async fn process_request_data(
         return Err(payload_too_large_error("Request headers exceed size limit".to_string()));
     }
 
+    // Compare the declared Content-Type with what the body actually looks like
+    let body_format = sniff_body(&body_bytes);
+    let content_type = headers.get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok());
+    let content_type_mismatch = is_content_type_mismatch(content_type, body_format);
+    if content_type_mismatch {
+        warn!(%id, %addr, content_type = content_type.unwrap_or(""), detected = body_format.as_str(), "Content-Type does not match body");
+    }
+
+    let signature_valid = signature.map(|signature| signatures::verify(signature, &headers, &body_bytes));
+    if signature_valid == Some(false) {
+        warn!(%id, %addr, provider = signature.map(|s| s.provider.as_str()).unwrap_or(""), "Request signature is invalid");
+    }
+
     Ok(ProcessedRequest {
         method: method.to_string(),
         headers_json,
         body: body_str,
+        body_encoding,
+        content_encoding,
         request_id: Uuid::new_v4(),
+        body_format,
+        content_type_mismatch,
+        query_params: query_string.as_deref().map(parse_query_params),
+        query_string,
+        source_ip: Some(client.to_string()),
+        // The peer's port says nothing about a client behind a proxy
+        source_port: (client == addr.ip()).then_some(addr.port()),
+        path,
+        timestamp: Utc::now().to_rfc3339(),
+        signature_valid,
+        fields: BTreeMap::new(),
+        chaos: None,
     })
 }
 
-async fn enforce_request_limit(state: &AppState, bin_id: &str) -> Result<(), sqlx::Error> {
-    let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM requests WHERE bin_id = ?")
-        .bind(bin_id)
-        .fetch_one(&state.db)
-        .await?;
+/// Read the body while counting, giving up as soon as it passes `max_size` instead of
+/// buffering all of it first. A `Content-Length` over the limit is rejected unread.
+async fn read_body(body: Body, headers: &HeaderMap, id: &str, addr: &SocketAddr, max_size: usize) -> Result<Vec<u8>, ApiError> {
+    let too_large = |body_size: usize| {
+        warn!(%id, %addr, body_size, max_allowed = max_size, "Request body too large, rejecting");
+        payload_too_large_error("Request body exceeds size limit".to_string())
+    };
+    let declared = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    if let Some(declared) = declared
+        && declared > max_size
+    {
+        return Err(too_large(declared));
+    }
 
-    if count > state.limits.max_requests_per_bin {
-        let excess = count - state.limits.max_requests_per_bin;
-        let deleted = query(
-            "DELETE FROM requests WHERE bin_id = ? AND id IN (
-                SELECT id FROM requests WHERE bin_id = ? ORDER BY id ASC LIMIT ?
-            )"
-        )
-        .bind(bin_id)
-        .bind(bin_id)
-        .bind(excess)
-        .execute(&state.db)
-        .await?;
+    let mut body_bytes = Vec::with_capacity(declared.unwrap_or(0));
+    let mut chunks = body.into_data_stream();
+    while let Some(chunk) = chunks.next().await {
+        let chunk = chunk.map_err(|err| body_read_error(id, addr, &err))?;
+        if body_bytes.len() + chunk.len() > max_size {
+            return Err(too_large(body_bytes.len() + chunk.len()));
+        }
+        body_bytes.extend_from_slice(&chunk);
+    }
+    Ok(body_bytes)
+}
+
+/// Answer a body that couldn't be read: 408 or 413 when `limit_request_body` cut it short
+fn body_read_error(id: &str, addr: &SocketAddr, err: &axum::Error) -> ApiError {
+    match BodyReadError::from_body_error(err) {
+        Some(BodyReadError::TimedOut) => {
+            warn!(%id, %addr, "Request body not received in time, rejecting");
+            ApiError::new(StatusCode::REQUEST_TIMEOUT, "request_timeout", "Request body was not received in time")
+        }
+        Some(BodyReadError::TooLarge) => {
+            warn!(%id, %addr, "Request body too large, rejecting");
+            payload_too_large_error("Request body exceeds size limit".to_string())
+        }
+        None => {
+            warn!(%id, %addr, %err, "Failed to read request body");
+            bad_request_error("Failed to read request body".to_string())
+        }
+    }
+}
 
-        info!(%bin_id, rows_deleted = deleted.rows_affected(), "Cleaned up old requests to maintain limit");
+/// Prepare an imported request for storage the same way a live capture would be.
+/// Timestamps are kept when they are valid RFC 3339, otherwise the import time is used.
+fn process_imported_request(imported: ImportedRequest, limits: &crate::config::LimitsConfig) -> Result<ProcessedRequest, ApiError> {
+    if imported.body.len() > limits.max_body_size {
+        return Err(payload_too_large_error("Imported request body exceeds size limit".to_string()));
     }
-    Ok(())
+    let headers_json = serde_json::to_string(&imported.headers).unwrap_or_else(|_| "[]".to_string());
+    if headers_json.len() > limits.max_headers_size {
+        return Err(payload_too_large_error("Imported request headers exceed size limit".to_string()));
+    }
+
+    let (body, body_encoding) = encode_body(&imported.body);
+    let body_format = sniff_body(&imported.body);
+    let content_type = imported
+        .headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+        .map(|(_, value)| value.as_str());
+    let timestamp = imported
+        .timestamp
+        .as_deref()
+        .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+        .map(|ts| ts.with_timezone(&Utc).to_rfc3339())
+        .unwrap_or_else(|| Utc::now().to_rfc3339());
+
+    Ok(ProcessedRequest {
+        method: imported.method,
+        headers_json,
+        body,
+        body_encoding,
+        content_encoding: imported.content_encoding,
+        request_id: Uuid::new_v4(),
+        body_format,
+        content_type_mismatch: is_content_type_mismatch(content_type, body_format),
+        query_params: imported.query_string.as_deref().map(parse_query_params),
+        query_string: imported.query_string,
+        source_ip: imported.source_ip,
+        source_port: imported.source_port,
+        path: imported.path,
+        timestamp,
+        signature_valid: None,
+        fields: BTreeMap::new(),
+        chaos: None,
+    })
+}
+
+async fn store_requests(state: &AppState, bin_id: &str, requests: &[LoggedRequest], max_requests: i64) -> Result<InsertOutcome, StorageError> {
+    capture::store_requests(state.storage.as_ref(), &state.webhooks, bin_id, requests, max_requests).await
+}
+
+/// The server-wide limits with the bin's own overrides applied
+fn limits_for(state: &AppState, settings: &BinSettings) -> crate::config::LimitsConfig {
+    capture::with_overrides(&state.limits.load(), settings.limits.as_ref())
 }
 
 async fn send_websocket_notification(state: &AppState, bin_id: &str, request_data: &ProcessedRequest) {
-    if let Some(sender) = state.bin_channels.get(bin_id) {
-        let payload = serde_json::json!({
-            "method": request_data.method,
-            "headers": request_data.headers_json,
-            "body": request_data.body,
-            "timestamp": Utc::now().to_rfc3339(),
-            "request_id": request_data.request_id,
-        });
-        let _ = sender.send(payload.to_string());
+    // Other instances may have listeners when this one has none
+    if state.bin_channels.contains_key(bin_id) || state.fanout.is_some() {
+        broadcast(state, bin_id, WsEvent::Request(Box::new(request_data.to_logged_request().into())));
     }
 }
 
 // Helper function to add CORS headers to any response
-fn add_cors_headers(mut response: Response) -> Response {
+pub(crate) fn add_cors_headers(mut response: Response) -> Response {
     let headers = response.headers_mut();
     
     // Allow all origins
@@ -177,6 +651,12 @@ fn add_cors_headers(mut response: Response) -> Response {
         HeaderValue::from_static("*"),
     );
     
+    // Let browsers read our custom response headers (e.g. X-Total-Count)
+    headers.insert(
+        header::ACCESS_CONTROL_EXPOSE_HEADERS,
+        HeaderValue::from_static("*"),
+    );
+    
     // Cache preflight for 1 day
     headers.insert(
         header::ACCESS_CONTROL_MAX_AGE,
@@ -191,250 +671,4424 @@ pub async fn options_handler() -> Response {
     add_cors_headers(Response::new(Body::empty()))
 }
 
+/// Trim metadata and check it against the size limits. Empty strings become `None`.
+fn normalize_metadata(update: BinMetadataUpdate) -> Result<BinMetadataUpdate, ApiError> {
+    fn clean(value: Option<String>, field: &str, max_len: usize) -> Result<Option<String>, ApiError> {
+        match value.map(|v| v.trim().to_string()) {
+            Some(v) if v.chars().count() > max_len => {
+                Err(bad_request_error(format!("`{}` must be at most {} characters", field, max_len)))
+            }
+            other => Ok(other),
+        }
+    }
+
+    let tags = match update.tags {
+        Some(tags) => {
+            let mut cleaned: Vec<String> = Vec::new();
+            for tag in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+                if tag.chars().count() > MAX_BIN_TAG_LENGTH {
+                    return Err(bad_request_error(format!("Tags must be at most {} characters", MAX_BIN_TAG_LENGTH)));
+                }
+                if !cleaned.iter().any(|t| t == tag) {
+                    cleaned.push(tag.to_string());
+                }
+            }
+            if cleaned.len() > MAX_BIN_TAGS {
+                return Err(bad_request_error(format!("A bin can have at most {} tags", MAX_BIN_TAGS)));
+            }
+            Some(cleaned)
+        }
+        None => None,
+    };
+
+    Ok(BinMetadataUpdate {
+        name: clean(update.name, "name", MAX_BIN_NAME_LENGTH)?,
+        description: clean(update.description, "description", MAX_BIN_DESCRIPTION_LENGTH)?,
+        tags,
+    })
+}
+
+/// Parse an optional JSON body; an empty body means all defaults.
+fn parse_optional_json<T: serde::de::DeserializeOwned + Default>(body: &Bytes) -> Result<T, ApiError> {
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Ok(T::default());
+    }
+    serde_json::from_slice(body)
+        .map_err(|err| bad_request_error(format!("Invalid bin metadata: {}", err)))
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().filter(|v| !v.is_empty())
+}
+
+/// The `X-Creator-Token` sent, if any; blank counts as none
+fn creator_token(headers: &HeaderMap) -> Result<Option<String>, ApiError> {
+    let Some(value) = headers.get(CREATOR_TOKEN_HEADER) else {
+        return Ok(None);
+    };
+    let token = value
+        .to_str()
+        .map_err(|_| bad_request_error("X-Creator-Token must be printable ASCII".to_string()))?
+        .trim();
+    if token.len() > MAX_CREATOR_TOKEN_LENGTH {
+        return Err(bad_request_error(format!("X-Creator-Token must be at most {} characters", MAX_CREATOR_TOKEN_LENGTH)));
+    }
+    Ok((!token.is_empty()).then(|| token.to_string()))
+}
+
+/// Refuse another bin to a client that already has `[limits] max_bins_per_ip` live bins,
+/// or whose creator token already has `max_bins_per_creator`
+async fn check_bin_quota(state: &AppState, client: &str, creator_token: Option<&str>) -> Result<(), ApiError> {
+    let limits = state.limits.load();
+    if limits.max_bins_per_ip <= 0 && limits.max_bins_per_creator <= 0 {
+        return Ok(());
+    }
+    let (by_ip, by_creator) = state.storage.count_creator_bins(client, creator_token).await.map_err(|err| {
+        error!(%client, %err, "Failed to count the client's bins");
+        internal_error("Failed to check bin quota".to_string())
+    })?;
+
+    let exceeded = if limits.max_bins_per_ip > 0 && by_ip >= limits.max_bins_per_ip {
+        Some(("address", limits.max_bins_per_ip))
+    } else if limits.max_bins_per_creator > 0 && by_creator >= limits.max_bins_per_creator {
+        Some(("creator token", limits.max_bins_per_creator))
+    } else {
+        None
+    };
+    match exceeded {
+        Some((owner, max)) => {
+            warn!(%client, owner, max, "Bin quota reached, refusing to create bin");
+            let detail = format!("This {} already has {} live bins; delete some or wait for them to expire", owner, max);
+            Err(ApiError::new(StatusCode::TOO_MANY_REQUESTS, "bin_quota_exceeded", detail))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Create a bin. It is recorded under the `X-Creator-Token` sent, or under a new creator
+/// token returned in the answer, so `GET /my/bins` can list it again later.
+#[utoipa::path(
+    post,
+    path = "/create",
+    tag = "bins",
+    request_body(content = Option<CreateBinRequest>, description = "Optional metadata; `private` makes the bin require a token"),
+    params(("X-Creator-Token" = Option<String>, Header, description = "Creator token from an earlier bin")),
+    responses(
+        (status = 200, description = "Bin created", body = BinResponse),
+        (status = 400, description = "Invalid metadata or creator token"),
+        (status = 401, description = "Missing or invalid API key"),
+        (status = 429, description = "The client already has as many live bins as it may"),
+    ),
+    security((), ("api_key" = []))
+)]
 pub async fn create_bin(
     State(state): State<AppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    body: Bytes,
 ) -> Result<impl IntoResponse, impl IntoResponse> {
+    let request: CreateBinRequest = parse_optional_json(&body).map_err(|e| add_cors_headers(e.into_response()))?;
+    let metadata = normalize_metadata(request.metadata).map_err(|e| add_cors_headers(e.into_response()))?;
+    let supplied_creator_token = creator_token(&headers).map_err(|e| add_cors_headers(e.into_response()))?;
+    let client = state.trusted_proxies.client_ip(addr.ip(), &headers).to_canonical().to_string();
+    check_bin_quota(&state, &client, supplied_creator_token.as_deref())
+        .await
+        .map_err(|e| add_cors_headers(e.into_response()))?;
     let id = Uuid::new_v4().to_string();
     let now = Utc::now().to_rfc3339();
+    let token = request.private.then(generate_token);
+    // Only a freshly issued creator token is sent back
+    let new_creator_token = supplied_creator_token.is_none().then(generate_token);
 
-    info!(%id, %addr, "Creating new bin");
+    info!(%id, %addr, private = request.private, "Creating new bin");
 
-    let result = query("INSERT INTO bins (id, last_updated) VALUES (?, ?)")
-        .bind(&id)
-        .bind(&now)
-        .execute(&state.db)
-        .await;
+    let result = state.storage.create_bin(NewBin {
+        id: id.clone(),
+        last_updated: now.clone(),
+        name: non_empty(&metadata.name).map(str::to_string),
+        description: non_empty(&metadata.description).map(str::to_string),
+        tags: metadata.tags,
+        token: token.clone(),
+        creator_token: supplied_creator_token.or_else(|| new_creator_token.clone()),
+        creator_ip: Some(client),
+    }).await;
 
     match result {
         Ok(_) => {
-            let response = Json(BinResponse { bin_id: id.to_string() }).into_response();
+            state.webhooks.emit(BinEvent::Created, &id, serde_json::json!({ "created_at": now }));
+            state.hooks.on_bin_created(&id);
+            let response = Json(BinResponse { bin_id: id.to_string(), token, creator_token: new_creator_token }).into_response();
             Ok(add_cors_headers(response))
         },
         Err(err) => {
             error!(%id, %addr, %err, "Failed to create bin");
-            let response = (StatusCode::INTERNAL_SERVER_ERROR, "Failed to insert bin").into_response();
+            let response = internal_error("Failed to insert bin".to_string()).into_response();
             Err(add_cors_headers(response))
         }
     }
 }
 
-async fn update_last_updated(state: &AppState, id: &str) -> Result<(), sqlx::Error> {
-    let now = Utc::now().to_rfc3339();
-    query("UPDATE bins SET last_updated = ? WHERE id = ?")
-        .bind(&now)
-        .bind(id)
-        .execute(&state.db)
-        .await?;
-    Ok(())
+async fn fetch_bin_info(state: &AppState, id: &str) -> Result<BinInfo, StorageError> {
+    state
+        .storage
+        .bin_info(id)
+        .await?
+        .ok_or_else(|| StorageError::Backend(format!("bin {} disappeared", id)))
 }
-    
-async fn store_request_in_db(
-    state: &AppState,
-    bin_id: &str,
-    request_data: &ProcessedRequest,
-) -> Result<(), sqlx::Error> {
-    query(
-        "INSERT INTO requests (bin_id, request_id, method, headers, body, timestamp) VALUES (?, ?, ?, ?, ?, ?)"
-    )
-    .bind(bin_id)
-    .bind(&request_data.request_id)
-    .bind(&request_data.method)
-    .bind(&request_data.headers_json)
-    .bind(&request_data.body)
-    .bind(Utc::now().to_rfc3339())
-    .execute(&state.db)
-    .await?;
-    Ok(())
+
+/// Show a bin's name, description and tags
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/meta",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "Bin metadata", body = BinInfo),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn get_bin_info(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    match fetch_bin_info(&state, &id).await {
+        Ok(info) => Ok(add_cors_headers(Json(info).into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to fetch bin metadata");
+            Err(add_cors_headers(internal_error("Failed to fetch bin metadata".to_string()).into_response()))
+        }
+    }
+}
+
+/// Update any of a bin's name, description and tags
+#[utoipa::path(
+    patch,
+    path = "/bin/{id}/meta",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    request_body = BinMetadataUpdate,
+    responses(
+        (status = 200, description = "Updated bin metadata", body = BinInfo),
+        (status = 400, description = "Invalid metadata"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn update_bin_info(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    body: Bytes,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+    let update = parse_optional_json(&body)
+        .and_then(normalize_metadata)
+        .map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let result = state.storage.update_bin_metadata(&id, &update).await;
+
+    match result {
+        Ok(_) => match fetch_bin_info(&state, &id).await {
+            Ok(info) => {
+                info!(%id, %addr, "Bin metadata updated");
+                Ok(add_cors_headers(Json(info).into_response()))
+            }
+            Err(err) => {
+                error!(%id, %addr, %err, "Failed to fetch bin metadata");
+                Err(add_cors_headers(internal_error("Failed to fetch bin metadata".to_string()).into_response()))
+            }
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to update bin metadata");
+            Err(add_cors_headers(internal_error("Failed to update bin metadata".to_string()).into_response()))
+        }
+    }
 }
 
+async fn update_last_updated(state: &AppState, id: &str) -> Result<(), StorageError> {
+    state.storage.touch_bin(id, &Utc::now().to_rfc3339()).await
+}
+
+/// Capture a request sent to a bin. Every HTTP method is accepted.
+#[utoipa::path(
+    post,
+    path = "/bin/{id}",
+    tag = "capture",
+    params(("id" = String, Path, description = "Bin id")),
+    request_body(content = String, description = "Any payload", content_type = "*/*"),
+    responses(
+        (status = 200, description = "Request logged, or the bin's custom response; `X-Rustbin-Request-Count` holds the bin's request count", body = CaptureResponse),
+        (status = 202, description = "Request queued for a background write", body = CaptureResponse),
+        (status = 404, description = "Bin not found"),
+        (status = 413, description = "Body or headers too large"),
+    )
+)]
 pub async fn log_request(
     State(state): State<AppState>,
     Path(id): Path<String>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     req: Request<Body>,
 ) -> Result<impl IntoResponse, impl IntoResponse> {
+    capture_request(state, id, "/".to_string(), addr, req).await
+}
+
+/// Capture requests sent to any sub-path below a bin, e.g. `/bin/:id/github/push`.
+/// Every HTTP method is accepted.
+#[utoipa::path(
+    post,
+    path = "/bin/{id}/{path}",
+    tag = "capture",
+    params(("id" = String, Path, description = "Bin id"), ("path" = String, Path, description = "Any sub-path")),
+    request_body(content = String, description = "Any payload", content_type = "*/*"),
+    responses(
+        (status = 200, description = "Request logged, or the bin's custom response", body = CaptureResponse),
+        (status = 202, description = "Request queued for a background write", body = CaptureResponse),
+        (status = 404, description = "Bin not found"),
+        (status = 413, description = "Body or headers too large"),
+    )
+)]
+pub async fn log_request_with_path(
+    State(state): State<AppState>,
+    Path((id, path)): Path<(String, String)>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    req: Request<Body>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    capture_request(state, id, format!("/{}", path), addr, req).await
+}
+
+async fn capture_request(
+    state: AppState,
+    id: String,
+    path: String,
+    addr: SocketAddr,
+    req: Request<Body>,
+) -> Result<Response, Response> {
     // Validate input
     validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
     
-    // Check if bin exists
-    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
-    
+    // Check if bin exists, loading everything else the capture needs along the way
+    let settings = load_bin_settings(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_paused(&settings).map_err(|e| add_cors_headers(e.into_response()))?;
+
+    // Turn away sources the bin doesn't accept before reading the body
+    let client = state.trusted_proxies.client_ip(addr.ip(), req.headers());
+    check_ip_access(&state, &id, settings.ip_access.as_ref(), req.method().as_str(), &path, client)
+        .await
+        .map_err(|e| add_cors_headers(e.into_response()))?;
+
+    // The bin's override only sets up its bucket, the first time the bin is seen
+    state.bin_limiter.check(&id, settings.rate_limit).map_err(|wait| add_cors_headers(bin_rate_limited(wait)))?;
+    // Probes go through the same pause, access and rate checks as the captures they stand in for
+    if state.head_probes && req.method() == Method::HEAD {
+        return probe_bin(&state, &id).await;
+    }
+    check_storage_quota(&state).map_err(|e| add_cors_headers(e.into_response()))?;
+    let requested = requested_response(&path, req.uri().query()).map_err(|e| add_cors_headers(e.into_response()))?;
+
+    // A proxied capture is answered with the upstream's response, which a queued capture's
+    // 202 is sent without waiting for
+    let proxy = settings.proxy.as_ref().filter(|_| state.capture_queue.is_none());
+    // Chaos is picked before the capture is queued, so queued captures fail their 202 too
+    let chaos = settings.chaos;
+
     // Process request data (headers, body, validation)
-    let request_data = process_request_data(req, &id, &addr, &state.limits).await.map_err(|e| add_cors_headers(e.into_response()))?;
-    
+    let limits = limits_for(&state, &settings);
+    let mut request_data = process_request_data(req, &id, path, &addr, client, &limits, settings.signature.as_ref())
+        .await
+        .map_err(|e| add_cors_headers(e.into_response()))?;
+
+    // Redact after the signature check, which needs the body as sent, and before the
+    // capture goes anywhere else. Queued captures are redacted too.
+    let redactor = bin_redactor(&state, &id, settings.redaction.as_ref());
+    request_data.redact(&redactor);
+    // Plugins see the redacted capture, and may rewrite it or keep it out of storage
+    let kept = run_plugins(&state, &id, &mut request_data).await;
+    request_data.extract(&Extractors::new(&settings.extractors));
+    request_data.chaos = chaos.as_ref().and_then(|chaos| ChaosAction::pick(chaos, request_data.request_id));
+    let requested = RequestedResponse { chaos: chaos.zip(request_data.chaos), ..requested };
+
+    // Captures a plugin dropped or the bin's sampling left out are answered as usual, and
+    // go nowhere else
+    let skipped = if !kept {
+        Some("dropped by a plugin")
+    } else if !sampled_in(&state, &id, settings.sampling, request_data.request_id).await {
+        Some("sampled out")
+    } else {
+        None
+    };
+    if let Some(reason) = skipped {
+        info!(%id, %addr, method = %request_data.method, reason, "Request not stored");
+        let response = match (&state.capture_queue, proxy) {
+            (Some(_), _) => (StatusCode::ACCEPTED, capture_response(&id, &request_data)).into_response(),
+            (None, Some(proxy)) => proxy_capture(&state, &id, proxy, &request_data.to_logged_request(), false).await,
+            (None, None) => respond_to_capture(&state, &id, &settings, &request_data).await,
+        };
+        return Ok(add_cors_headers(requested.apply(response).await));
+    }
+
+    // Hand the request to the background writer when captures are queued
+    let logged = request_data.to_logged_request();
+    if let Some(queue) = &state.capture_queue {
+        return match queue.push(&id, logged.clone()) {
+            Ok(()) => {
+                info!(%id, %addr, method = %request_data.method, "Request queued");
+                state.notifier.notify(&id, settings.notifications.as_ref(), &logged);
+                state.callbacks.request_captured(&settings.callbacks, &id, &logged);
+                state.hooks.on_request_logged(&id, &logged);
+                state.forwarder.mirror(&id, &settings.forwards, logged);
+                send_websocket_notification(&state, &id, &request_data).await;
+                let response = (StatusCode::ACCEPTED, capture_response(&id, &request_data)).into_response();
+                let response = requested.apply(response).await;
+                Ok(add_cors_headers(with_request_id(response, request_data.request_id)))
+            }
+            Err(_) => {
+                warn!(%id, %addr, "Capture queue is full");
+                let response = ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "capture_queue_full", "Capture queue is full, try again later").into_response();
+                Err(add_cors_headers(response))
+            }
+        };
+    }
+
     // Store request in database
-    match store_request_in_db(&state, &id, &request_data).await {
-        Ok(_) => {
+    match store_requests(&state, &id, std::slice::from_ref(&logged), limits.max_requests_per_bin).await {
+        Ok(outcome) => {
             info!(%id, %addr, method = %request_data.method, 
                   headers = %request_data.headers_json, body = %request_data.body, 
                   "Request logged");
-            
-            // Clean up old requests if needed
-            if let Err(err) = enforce_request_limit(&state, &id).await {
-                error!(%id, %err, "Failed to clean up old requests");
-            }
-            
-            // Update bin timestamp
-            update_last_updated(&state, &id).await.ok();
+            state.notifier.notify(&id, settings.notifications.as_ref(), &logged);
+            state.callbacks.request_captured(&settings.callbacks, &id, &logged);
+            state.hooks.on_request_logged(&id, &logged);
+            state.forwarder.mirror(&id, &settings.forwards, logged);
             
             // Send websocket notification
             send_websocket_notification(&state, &id, &request_data).await;
             
-            // Return response with CORS headers
-            let response = "Request logged".to_string().into_response();
-            Ok(add_cors_headers(response))
+            let response = match proxy {
+                Some(proxy) => proxy_capture(&state, &id, proxy, &request_data.to_logged_request(), true).await,
+                None => respond_to_capture(&state, &id, &settings, &request_data).await,
+            };
+            let mut response = with_request_id(response, request_data.request_id);
+            let stored = outcome.request_count - outcome.trimmed as i64;
+            response.headers_mut().insert(REQUEST_COUNT_HEADER, HeaderValue::from(stored));
+            Ok(add_cors_headers(requested.apply(response).await))
         },
         Err(err) => {
             error!(%id, %addr, %err, "DB error");
-            let response = (StatusCode::NOT_FOUND, "Bin not found or error logging request").into_response();
+            let response = internal_error("Failed to log request".to_string()).into_response();
             Err(add_cors_headers(response))
         }
     }
 }
 
-pub async fn inspect_bin(
-    State(state): State<AppState>,
-    Path(id): Path<String>,
-    ConnectInfo(addr): ConnectInfo<SocketAddr>,
-) -> Result<impl IntoResponse, impl IntoResponse> {
-    // Validate input and check bin existence
-    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
-    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+/// Status and delay a capture asks for itself, so one bin can serve many client test cases,
+/// and the failure the bin's chaos settings picked for it
+#[derive(Debug, Default, PartialEq)]
+struct RequestedResponse {
+    status: Option<StatusCode>,
+    delay: std::time::Duration,
+    chaos: Option<(BinChaos, ChaosAction)>,
+}
 
-    // Fetch the requests for this bin
-    let rows = sqlx::query_as::<_, LoggedRequest>(
-        r#"
-        SELECT 
-            method, 
-            headers, 
-            body, 
-            timestamp,
-            request_id
-        FROM requests
-        WHERE bin_id = ?
-        ORDER BY id
-        "#
-    )
-    .bind(&id)
-    .fetch_all(&state.db)
-    .await;
+impl RequestedResponse {
+    /// Hold the answer back for the delay, then give it the requested status, unless the
+    /// chaos settings fail it
+    async fn apply(&self, mut response: Response) -> Response {
+        if !self.delay.is_zero() {
+            tokio::time::sleep(self.delay).await;
+        }
+        if let Some(status) = self.status {
+            *response.status_mut() = status;
+        }
+        match &self.chaos {
+            Some((chaos, action)) => action.apply(chaos, response).await,
+            None => response,
+        }
+    }
+}
+
+/// Read `/respond/{status}` at the start of the capture path, or `_status` and `_delay`
+/// (milliseconds) from the query. The query's status wins over the path's.
+fn requested_response(path: &str, query: Option<&str>) -> Result<RequestedResponse, ApiError> {
+    let parse_status = |value: &str| {
+        value
+            .parse::<u16>()
+            .ok()
+            .filter(|status| (200..=599).contains(status))
+            .and_then(|status| StatusCode::from_u16(status).ok())
+    };
+
+    // Other paths under `respond/` are captured like any other
+    let mut requested = RequestedResponse::default();
+    let mut segments = path.trim_start_matches('/').split('/');
+    if segments.next() == Some("respond") {
+        requested.status = segments.next().and_then(parse_status);
+    }
+
+    let pairs: Vec<(String, String)> = serde_urlencoded::from_str(query.unwrap_or_default()).unwrap_or_default();
+    for (key, value) in pairs {
+        match key.as_str() {
+            "_status" => {
+                let status = parse_status(&value).ok_or_else(|| bad_request_error(format!("Invalid requested status `{}`", value)))?;
+                requested.status = Some(status);
+            }
+            "_delay" => {
+                let delay = value
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|delay| *delay <= MAX_RESPONSE_DELAY_MS)
+                    .ok_or_else(|| bad_request_error(format!("_delay must be between 0 and {} milliseconds", MAX_RESPONSE_DELAY_MS)))?;
+                requested.delay = std::time::Duration::from_millis(delay);
+            }
+            _ => {}
+        }
+    }
+    Ok(requested)
+}
+
+/// Answer `HEAD /bin/:id` with the bin's request count, without capturing anything
+async fn probe_bin(state: &AppState, id: &str) -> Result<Response, Response> {
+    match state.storage.bin_request_count(id).await {
+        Ok(Some(count)) => Ok(add_cors_headers(([(REQUEST_COUNT_HEADER, HeaderValue::from(count))], StatusCode::OK).into_response())),
+        Ok(None) => Err(add_cors_headers(not_found_error("Bin not found".to_string()).into_response())),
+        Err(err) => {
+            error!(%id, %err, "Failed to count bin requests");
+            Err(add_cors_headers(internal_error("Failed to count bin requests".to_string()).into_response()))
+        }
+    }
+}
+
+/// Answer with the bin's custom response if one is configured
+async fn respond_to_capture(state: &AppState, id: &str, settings: &BinSettings, request: &ProcessedRequest) -> Response {
+    if let Some(response) = run_bin_script(state, id, settings.script.as_ref(), request).await {
+        return response;
+    }
+    match load_response_for(state, id, settings, request).await {
+        Ok(Some(custom)) => render_custom_response(&custom, id, request),
+        Ok(None) => capture_response(id, request).into_response(),
+        Err(err) => {
+            error!(%id, %err, "Failed to load custom response");
+            capture_response(id, request).into_response()
+        }
+    }
+}
+
+/// Run the bin's script on a capture and broadcast its events. `None` when the bin has no
+/// script or the script left the answer to the bin.
+async fn run_bin_script(state: &AppState, id: &str, script: Option<&BinScript>, request: &ProcessedRequest) -> Option<Response> {
+    if !state.scripts.enabled() {
+        return None;
+    }
+    let source = script?.source.clone();
+    let engine = state.scripts.clone();
+    let context = template_context(id, request);
+    let outcome = tokio::task::spawn_blocking(move || engine.run(&source, &context))
+        .await
+        .unwrap_or_else(|err| Err(err.to_string()));
+    let outcome = match outcome {
+        Ok(outcome) => outcome,
+        Err(err) => {
+            warn!(%id, request_id = %request.request_id, %err, "Bin script failed");
+            return Some(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "script_failed", err).into_response());
+        }
+    };
+    for event in outcome.events {
+        broadcast(state, id, WsEvent::ScriptEvent { request_id: request.request_id, name: event.name, data: event.data });
+    }
+    outcome.response.map(|answer| {
+        let status = StatusCode::from_u16(answer.status).unwrap_or(StatusCode::OK);
+        let mut response = (status, answer.body).into_response();
+        for (name, value) in &answer.headers {
+            match (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value)) {
+                (Ok(name), Ok(value)) => {
+                    response.headers_mut().insert(name, value);
+                }
+                _ => warn!(%id, header = %name, "Skipping script response header that is not valid"),
+            }
+        }
+        response
+    })
+}
+
+/// Send a capture on to the bin's upstream and answer with its response, recording it as
+/// a delivery of the stored request when `record` is set
+async fn proxy_capture(state: &AppState, id: &str, proxy: &BinProxy, request: &LoggedRequest, record: bool) -> Response {
+    let url = replay_url(request, &proxy.upstream);
+    let started = std::time::Instant::now();
+    let outcome = state.replayer.replay(request, &url, &ReplayOverrides::default()).await;
+    if record {
+        let latency_ms = started.elapsed().as_millis() as u64;
+        let delivery = state.forwarder.outside_delivery(request.request_id, outcome.as_ref().map_err(ToString::to_string), Some(latency_ms));
+        if let Err(err) = state.storage.record_replay(id, KIND_PROXY, &url, &delivery).await {
+            error!(%id, request_id = %request.request_id, %err, "Failed to record proxied response");
+        }
+    }
+    match outcome {
+        Ok(upstream) => relay_response(&upstream),
+        Err(err) => {
+            warn!(%id, %url, %err, "Proxying capture failed");
+            ApiError::new(StatusCode::BAD_GATEWAY, "proxy_failed", err.to_string()).into_response()
+        }
+    }
+}
+
+/// An upstream's response as ours, without the headers describing its own connection
+fn relay_response(upstream: &ReplayResponse) -> Response {
+    let status = StatusCode::from_u16(upstream.status).unwrap_or(StatusCode::BAD_GATEWAY);
+    let body = decode_body(&upstream.body, Some(&upstream.body_encoding)).unwrap_or_default();
+    let mut response = (status, body).into_response();
+    for (name, value) in &upstream.headers {
+        if matches!(name.to_ascii_lowercase().as_str(), "connection" | "keep-alive" | "transfer-encoding" | "content-length") {
+            continue;
+        }
+        if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value)) {
+            response.headers_mut().append(name, value);
+        }
+    }
+    response
+}
+
+/// The default answer to a capture
+fn capture_response(bin_id: &str, request: &ProcessedRequest) -> Json<CaptureResponse> {
+    Json(CaptureResponse {
+        request_id: request.request_id,
+        bin_id: bin_id.to_string(),
+        timestamp: request.timestamp.clone(),
+    })
+}
+
+/// Every answer to a capture names the stored request, custom responses included
+fn with_request_id(mut response: Response, request_id: Uuid) -> Response {
+    response.headers_mut().insert(REQUEST_ID_HEADER, HeaderValue::from_str(&request_id.to_string()).unwrap());
+    response
+}
+
+async fn load_custom_response(state: &AppState, id: &str) -> Result<Option<CustomResponse>, StorageError> {
+    state.storage.custom_response(id).await
+}
+
+/// The next response of the bin's sequence, else the first response rule matching the
+/// request, else the bin's custom response
+async fn load_response_for(state: &AppState, id: &str, settings: &BinSettings, request: &ProcessedRequest) -> Result<Option<CustomResponse>, StorageError> {
+    if settings.has_sequence && let Some(response) = state.storage.next_sequence_response(id).await? {
+        return Ok(Some(response));
+    }
+    if let Some(rule) = select_rule(&settings.rules, &request.method, &request.path) {
+        return Ok(Some(rule.response.clone()));
+    }
+    Ok(settings.response.clone())
+}
+
+/// Variables available to response templates
+fn template_context(bin_id: &str, request: &ProcessedRequest) -> serde_json::Value {
+    // Repeated headers are folded into a single comma separated value
+    let mut headers = serde_json::Map::new();
+    for (name, value) in parse_stored_headers(&request.headers_json) {
+        match headers.get_mut(&name) {
+            Some(serde_json::Value::String(existing)) => {
+                existing.push_str(", ");
+                existing.push_str(&value);
+            }
+            _ => {
+                headers.insert(name, value.into());
+            }
+        }
+    }
+
+    let query = request
+        .query_params
+        .as_deref()
+        .and_then(|params| serde_json::from_str(params).ok())
+        .unwrap_or_else(|| serde_json::json!({}));
+    let text = (request.body_encoding == ENCODING_UTF8).then_some(request.body.as_str());
+    let json = text.and_then(|text| serde_json::from_str::<serde_json::Value>(text).ok());
+    let form = text
+        .filter(|_| request.body_format == BodyFormat::Form)
+        .and_then(|text| serde_json::from_str::<serde_json::Value>(&parse_query_params(text)).ok());
+
+    serde_json::json!({
+        "bin_id": bin_id,
+        "request_id": request.request_id,
+        "method": request.method,
+        "path": request.path,
+        "query": query,
+        "query_string": request.query_string,
+        "headers": headers,
+        "body": {
+            "text": text,
+            "json": json,
+            "form": form,
+        },
+        "source_ip": request.source_ip,
+    })
+}
+
+fn render_custom_response(custom: &CustomResponse, bin_id: &str, request: &ProcessedRequest) -> Response {
+    let context = template_context(bin_id, request);
+    let body = match render_template(&custom.body, &context) {
+        Ok(body) => body,
+        Err(err) => {
+            error!(%bin_id, %err, "Failed to render custom response body");
+            return internal_error("Failed to render custom response".to_string()).into_response();
+        }
+    };
+
+    let status = StatusCode::from_u16(custom.status).unwrap_or(StatusCode::OK);
+    let mut response = (status, body).into_response();
+    for (name, template) in &custom.headers {
+        let rendered = render_template(template, &context).ok();
+        match (HeaderName::from_bytes(name.as_bytes()), rendered.and_then(|v| HeaderValue::from_str(&v).ok())) {
+            (Ok(name), Some(value)) => {
+                response.headers_mut().insert(name, value);
+            }
+            _ => warn!(%bin_id, header = %name, "Skipping custom response header that did not render to a valid value"),
+        }
+    }
+    response
+}
+
+fn validate_custom_response(custom: &CustomResponse) -> Result<(), ApiError> {
+    StatusCode::from_u16(custom.status)
+        .ok()
+        .filter(|status| (200..=599).contains(&status.as_u16()))
+        .ok_or_else(|| bad_request_error(format!("Invalid response status {}", custom.status)))?;
+    for (name, value) in &custom.headers {
+        HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| bad_request_error(format!("Invalid response header name `{}`", name)))?;
+        validate_template(value)
+            .map_err(|err| bad_request_error(format!("Invalid template for header `{}`: {}", name, err)))?;
+    }
+    validate_template(&custom.body)
+        .map_err(|err| bad_request_error(format!("Invalid body template: {}", err)))?;
+    Ok(())
+}
+
+/// Show the custom response configured for a bin
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/response",
+    tag = "responses",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "Configured custom response", body = CustomResponse),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found or no custom response"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn get_bin_response(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    match load_custom_response(&state, &id).await {
+        Ok(Some(custom)) => Ok(add_cors_headers(Json(custom).into_response())),
+        Ok(None) => Err(add_cors_headers(not_found_error("No custom response configured".to_string()).into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to load custom response");
+            Err(add_cors_headers(internal_error("Failed to load custom response".to_string()).into_response()))
+        }
+    }
+}
+
+/// Configure the (templated) response a bin sends back to captured requests
+#[utoipa::path(
+    put,
+    path = "/bin/{id}/response",
+    tag = "responses",
+    params(("id" = String, Path, description = "Bin id")),
+    request_body = CustomResponse,
+    responses(
+        (status = 200, description = "Custom response stored", body = CustomResponse),
+        (status = 400, description = "Invalid status, header or template"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn set_bin_response(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    payload: Result<Json<CustomResponse>, JsonRejection>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let Json(custom) = payload
+        .map_err(|rejection| add_cors_headers(bad_request_error(rejection.body_text()).into_response()))?;
+    validate_custom_response(&custom).map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let result = state.storage.set_custom_response(&id, Some(&custom)).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(_) => {
+            info!(%id, %addr, status = custom.status, "Custom response configured");
+            Ok(add_cors_headers(Json(custom).into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to store custom response");
+            Err(add_cors_headers(internal_error("Failed to store custom response".to_string()).into_response()))
+        }
+    }
+}
+
+/// Go back to the default JSON response
+#[utoipa::path(
+    delete,
+    path = "/bin/{id}/response",
+    tag = "responses",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "Custom response removed"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn delete_bin_response(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let result = state.storage.set_custom_response(&id, None).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(_) => {
+            info!(%id, %addr, "Custom response removed");
+            Ok(add_cors_headers("Custom response removed".to_string().into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to remove custom response");
+            Err(add_cors_headers(internal_error("Failed to remove custom response".to_string()).into_response()))
+        }
+    }
+}
+
+/// Show a bin's response sequence and how far it has got
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/response/sequence",
+    tag = "responses",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "Configured sequence", body = ResponseSequence),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found or no sequence"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn get_response_sequence(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    match state.storage.response_sequence(&id).await {
+        Ok(Some(sequence)) => Ok(add_cors_headers(Json(sequence).into_response())),
+        Ok(None) => Err(add_cors_headers(not_found_error("No response sequence configured".to_string()).into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to load response sequence");
+            Err(add_cors_headers(internal_error("Failed to load response sequence".to_string()).into_response()))
+        }
+    }
+}
+
+/// Answer consecutive captures with the given responses in order, starting from the first
+#[utoipa::path(
+    put,
+    path = "/bin/{id}/response/sequence",
+    tag = "responses",
+    params(("id" = String, Path, description = "Bin id")),
+    request_body = ResponseSequence,
+    responses(
+        (status = 200, description = "Sequence stored", body = ResponseSequence),
+        (status = 400, description = "Invalid or empty sequence"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn set_response_sequence(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    payload: Result<Json<ResponseSequence>, JsonRejection>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let Json(mut sequence) = payload
+        .map_err(|rejection| add_cors_headers(bad_request_error(rejection.body_text()).into_response()))?;
+    if sequence.responses.is_empty() || sequence.responses.len() > MAX_SEQUENCE_RESPONSES {
+        let message = format!("A response sequence needs 1 to {} responses", MAX_SEQUENCE_RESPONSES);
+        return Err(add_cors_headers(bad_request_error(message).into_response()));
+    }
+    for response in &sequence.responses {
+        validate_custom_response(response).map_err(|e| add_cors_headers(e.into_response()))?;
+    }
+    sequence.position = 0;
+
+    let result = state.storage.set_response_sequence(&id, Some(&sequence)).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, mode = sequence.mode.as_str(), count = sequence.responses.len(), "Response sequence configured");
+            Ok(add_cors_headers(Json(sequence).into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to store response sequence");
+            Err(add_cors_headers(internal_error("Failed to store response sequence".to_string()).into_response()))
+        }
+    }
+}
+
+/// Remove a bin's response sequence
+#[utoipa::path(
+    delete,
+    path = "/bin/{id}/response/sequence",
+    tag = "responses",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "Sequence removed"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn delete_response_sequence(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let result = state.storage.set_response_sequence(&id, None).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, "Response sequence removed");
+            Ok(add_cors_headers("Response sequence removed".to_string().into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to remove response sequence");
+            Err(add_cors_headers(internal_error("Failed to remove response sequence".to_string()).into_response()))
+        }
+    }
+}
+
+/// Show how a bin's captures are verified; the secret is never returned
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/signature",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "Configured verification, without the secret", body = SignatureVerification),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found or no verification"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn get_signature_verification(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    match state.storage.signature_verification(&id).await {
+        Ok(Some(verification)) => Ok(add_cors_headers(Json(verification).into_response())),
+        Ok(None) => Err(add_cors_headers(not_found_error("No signature verification configured".to_string()).into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to load signature verification");
+            Err(add_cors_headers(internal_error("Failed to load signature verification".to_string()).into_response()))
+        }
+    }
+}
+
+/// Check the signature of every capture against a shared secret and record the outcome
+/// as `signature_valid`
+#[utoipa::path(
+    put,
+    path = "/bin/{id}/signature",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    request_body = SignatureVerification,
+    responses(
+        (status = 200, description = "Verification stored", body = SignatureVerification),
+        (status = 400, description = "Empty secret or invalid header name"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn set_signature_verification(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    payload: Result<Json<SignatureVerification>, JsonRejection>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let Json(mut verification) = payload
+        .map_err(|rejection| add_cors_headers(bad_request_error(rejection.body_text()).into_response()))?;
+    if verification.secret.is_empty() {
+        return Err(add_cors_headers(bad_request_error("Signature secret must not be empty".to_string()).into_response()));
+    }
+    verification.header = verification.header.filter(|header| !header.is_empty());
+    if let Some(header) = &verification.header {
+        if verification.provider != SignatureProvider::Hmac {
+            let message = format!("A signature header can only be set for the `{}` provider", SignatureProvider::Hmac.as_str());
+            return Err(add_cors_headers(bad_request_error(message).into_response()));
+        }
+        HeaderName::from_bytes(header.as_bytes())
+            .map_err(|_| add_cors_headers(bad_request_error(format!("Invalid header name `{}`", header)).into_response()))?;
+    }
+
+    let result = state.storage.set_signature_verification(&id, Some(&verification)).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, provider = verification.provider.as_str(), "Signature verification configured");
+            Ok(add_cors_headers(Json(verification).into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to store signature verification");
+            Err(add_cors_headers(internal_error("Failed to store signature verification".to_string()).into_response()))
+        }
+    }
+}
+
+/// Stop verifying signatures; later captures have no `signature_valid`
+#[utoipa::path(
+    delete,
+    path = "/bin/{id}/signature",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "Verification removed"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn delete_signature_verification(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let result = state.storage.set_signature_verification(&id, None).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, "Signature verification removed");
+            Ok(add_cors_headers("Signature verification removed".to_string().into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to remove signature verification");
+            Err(add_cors_headers(internal_error("Failed to remove signature verification".to_string()).into_response()))
+        }
+    }
+}
+
+/// A bin's own redaction rules, applied on top of the global `[redaction]` ones
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/redaction",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "The bin's redaction rules", body = RedactionRules),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found or no redaction rules"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn get_redaction_rules(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    match state.storage.redaction_rules(&id).await {
+        Ok(Some(rules)) => Ok(add_cors_headers(Json(rules).into_response())),
+        Ok(None) => Err(add_cors_headers(not_found_error("No redaction rules configured".to_string()).into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to load redaction rules");
+            Err(add_cors_headers(internal_error("Failed to load redaction rules".to_string()).into_response()))
+        }
+    }
+}
+
+/// Mask these headers and body patterns in every later capture before it is stored.
+/// Requests captured earlier are left as they are.
+#[utoipa::path(
+    put,
+    path = "/bin/{id}/redaction",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    request_body = RedactionRules,
+    responses(
+        (status = 200, description = "Redaction rules stored", body = RedactionRules),
+        (status = 400, description = "Invalid header name or body pattern"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn set_redaction_rules(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    payload: Result<Json<RedactionRules>, JsonRejection>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let Json(rules) = payload
+        .map_err(|rejection| add_cors_headers(bad_request_error(rejection.body_text()).into_response()))?;
+    for header in &rules.headers {
+        HeaderName::from_bytes(header.as_bytes())
+            .map_err(|_| add_cors_headers(bad_request_error(format!("Invalid header name `{}`", header)).into_response()))?;
+    }
+    for pattern in &rules.body_patterns {
+        regex::Regex::new(pattern).map_err(|err| {
+            add_cors_headers(bad_request_error(format!("Invalid body pattern `{}`: {}", pattern, err)).into_response())
+        })?;
+    }
+
+    let result = state.storage.set_redaction_rules(&id, Some(&rules)).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, headers = rules.headers.len(), body_patterns = rules.body_patterns.len(), "Redaction rules configured");
+            Ok(add_cors_headers(Json(rules).into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to store redaction rules");
+            Err(add_cors_headers(internal_error("Failed to store redaction rules".to_string()).into_response()))
+        }
+    }
+}
+
+/// Remove the bin's own redaction rules; the global ones still apply
+#[utoipa::path(
+    delete,
+    path = "/bin/{id}/redaction",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "Redaction rules removed"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn delete_redaction_rules(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let result = state.storage.set_redaction_rules(&id, None).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, "Redaction rules removed");
+            Ok(add_cors_headers("Redaction rules removed".to_string().into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to remove redaction rules");
+            Err(add_cors_headers(internal_error("Failed to remove redaction rules".to_string()).into_response()))
+        }
+    }
+}
+
+/// Which source addresses may capture into the bin
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/access",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "The bin's IP access rules", body = IpAccessRules),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found or no IP access rules"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn get_ip_access_rules(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    match state.storage.ip_access_rules(&id).await {
+        Ok(Some(rules)) => Ok(add_cors_headers(Json(rules).into_response())),
+        Ok(None) => Err(add_cors_headers(not_found_error("No IP access rules configured".to_string()).into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to load IP access rules");
+            Err(add_cors_headers(internal_error("Failed to load IP access rules".to_string()).into_response()))
+        }
+    }
+}
+
+/// Only accept captures from allowed sources that aren't denied; others get a 403.
+/// The rest of the bin's API is not restricted.
+#[utoipa::path(
+    put,
+    path = "/bin/{id}/access",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    request_body = IpAccessRules,
+    responses(
+        (status = 200, description = "IP access rules stored", body = IpAccessRules),
+        (status = 400, description = "An entry is not an address or CIDR range"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn set_ip_access_rules(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    payload: Result<Json<IpAccessRules>, JsonRejection>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let Json(mut rules) = payload
+        .map_err(|rejection| add_cors_headers(bad_request_error(rejection.body_text()).into_response()))?;
+    // Store the entries normalised, so `10.1.2.3/8` reads back as `10.0.0.0/8`
+    for entries in [&mut rules.allow, &mut rules.deny] {
+        for entry in entries.iter_mut() {
+            let network = parse_network(entry).map_err(|err| add_cors_headers(bad_request_error(err).into_response()))?;
+            *entry = network.trunc().to_string();
+        }
+    }
+
+    let result = state.storage.set_ip_access_rules(&id, Some(&rules)).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, allow = rules.allow.len(), deny = rules.deny.len(), "IP access rules configured");
+            Ok(add_cors_headers(Json(rules).into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to store IP access rules");
+            Err(add_cors_headers(internal_error("Failed to store IP access rules".to_string()).into_response()))
+        }
+    }
+}
+
+/// Accept captures from every source again. Rejected captures already recorded are kept.
+#[utoipa::path(
+    delete,
+    path = "/bin/{id}/access",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "IP access rules removed"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn delete_ip_access_rules(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let result = state.storage.set_ip_access_rules(&id, None).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, "IP access rules removed");
+            Ok(add_cors_headers("IP access rules removed".to_string().into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to remove IP access rules");
+            Err(add_cors_headers(internal_error("Failed to remove IP access rules".to_string()).into_response()))
+        }
+    }
+}
+
+/// Captures turned away by the bin's IP access rules while `log_rejected` was on, newest first
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/access/rejected",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "Rejected captures", body = Vec<RejectedRequest>),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn list_rejected_requests(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    match state.storage.rejected_requests(&id).await {
+        Ok(rejected) => Ok(add_cors_headers(Json(rejected).into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to load rejected captures");
+            Err(add_cors_headers(internal_error("Failed to load rejected captures".to_string()).into_response()))
+        }
+    }
+}
+
+/// How a paused bin answers captures
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/pause",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "The bin is paused", body = BinPause),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found or capturing"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn get_bin_pause(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    match state.storage.bin_pause(&id).await {
+        Ok(Some(pause)) => Ok(add_cors_headers(Json(pause).into_response())),
+        Ok(None) => Err(add_cors_headers(not_found_error("Bin is not paused".to_string()).with_code("bin_not_paused").into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to load bin pause");
+            Err(add_cors_headers(internal_error("Failed to load bin pause".to_string()).into_response()))
+        }
+    }
+}
+
+/// Stop capturing: until the bin is resumed, captures are answered with `status`
+/// (503 by default) and nothing is stored. The history and the bin URL stay as they are.
+#[utoipa::path(
+    put,
+    path = "/bin/{id}/pause",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    request_body = BinPause,
+    responses(
+        (status = 200, description = "Bin paused", body = BinPause),
+        (status = 400, description = "Status outside 200 to 599"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn pause_bin(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    payload: Result<Json<BinPause>, JsonRejection>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    // An empty body pauses with the default status
+    let pause = match payload {
+        Ok(Json(pause)) => pause,
+        Err(JsonRejection::MissingJsonContentType(_)) => BinPause::default(),
+        Err(rejection) => return Err(add_cors_headers(bad_request_error(rejection.body_text()).into_response())),
+    };
+    if !(200..=599).contains(&pause.status) {
+        return Err(add_cors_headers(bad_request_error("status must be between 200 and 599".to_string()).into_response()));
+    }
+
+    let result = state.storage.set_bin_pause(&id, Some(&pause)).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, status = pause.status, "Bin paused");
+            Ok(add_cors_headers(Json(pause).into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to pause bin");
+            Err(add_cors_headers(internal_error("Failed to pause bin".to_string()).into_response()))
+        }
+    }
+}
+
+/// Capture requests again
+#[utoipa::path(
+    delete,
+    path = "/bin/{id}/pause",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "Bin capturing again"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn resume_bin(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let result = state.storage.set_bin_pause(&id, None).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, "Bin resumed");
+            Ok(add_cors_headers("Bin resumed".to_string().into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to resume bin");
+            Err(add_cors_headers(internal_error("Failed to resume bin".to_string()).into_response()))
+        }
+    }
+}
+
+/// Which captures the bin stores
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/sampling",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "The bin's sampling", body = BinSampling),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found or storing every capture"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn get_bin_sampling(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    match state.storage.bin_sampling(&id).await {
+        Ok(Some(sampling)) => Ok(add_cors_headers(Json(sampling).into_response())),
+        Ok(None) => Err(add_cors_headers(not_found_error("Bin is not sampling".to_string()).with_code("bin_not_sampling").into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to load bin sampling");
+            Err(add_cors_headers(internal_error("Failed to load bin sampling".to_string()).into_response()))
+        }
+    }
+}
+
+/// Store only some captures, e.g. during a load test: every Nth one with `every`, or a
+/// share of them picked at random with `percent`. The others are answered as usual and
+/// counted in the bin's `sampled_out`, but not stored, forwarded or broadcast.
+#[utoipa::path(
+    put,
+    path = "/bin/{id}/sampling",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    request_body = BinSampling,
+    responses(
+        (status = 200, description = "Sampling configured", body = BinSampling),
+        (status = 400, description = "Neither or both of every and percent, or out of range"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn set_bin_sampling(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    payload: Result<Json<BinSampling>, JsonRejection>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let Json(sampling) = payload
+        .map_err(|rejection| add_cors_headers(bad_request_error(rejection.body_text()).into_response()))?;
+    let invalid = match (sampling.every, sampling.percent) {
+        (Some(_), Some(_)) | (None, None) => Some("Set either every or percent"),
+        (Some(0), None) => Some("every must be at least 1"),
+        (None, Some(percent)) if !(percent > 0.0 && percent <= 100.0) => Some("percent must be above 0 and at most 100"),
+        _ => None,
+    };
+    if let Some(detail) = invalid {
+        return Err(add_cors_headers(bad_request_error(detail.to_string()).into_response()));
+    }
+
+    let result = state.storage.set_bin_sampling(&id, Some(&sampling)).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, every = ?sampling.every, percent = ?sampling.percent, "Bin sampling configured");
+            Ok(add_cors_headers(Json(sampling).into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to store bin sampling");
+            Err(add_cors_headers(internal_error("Failed to store bin sampling".to_string()).into_response()))
+        }
+    }
+}
+
+/// Store every capture again
+#[utoipa::path(
+    delete,
+    path = "/bin/{id}/sampling",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "Bin storing every capture"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn delete_bin_sampling(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let result = state.storage.set_bin_sampling(&id, None).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, "Bin sampling removed");
+            Ok(add_cors_headers("Bin sampling removed".to_string().into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to remove bin sampling");
+            Err(add_cors_headers(internal_error("Failed to remove bin sampling".to_string()).into_response()))
+        }
+    }
+}
+
+/// Failures the bin injects into its answers
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/chaos",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "The bin's chaos settings", body = BinChaos),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found or not injecting failures"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn get_bin_chaos(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    match state.storage.bin_chaos(&id).await {
+        Ok(Some(chaos)) => Ok(add_cors_headers(Json(chaos).into_response())),
+        Ok(None) => Err(add_cors_headers(not_found_error("Bin has no chaos settings".to_string()).with_code("bin_not_chaotic").into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to load bin chaos settings");
+            Err(add_cors_headers(internal_error("Failed to load bin chaos settings".to_string()).into_response()))
+        }
+    }
+}
+
+/// Make the bin fail some of its answers, to see how senders cope: answer 500, drop the
+/// connection, or answer late. Captures are stored either way, with the failure injected
+/// into their answer as `chaos`.
+#[utoipa::path(
+    put,
+    path = "/bin/{id}/chaos",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    request_body = BinChaos,
+    responses(
+        (status = 200, description = "Chaos configured", body = BinChaos),
+        (status = 400, description = "Shares out of range or above 100 together, or delay too long"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn set_bin_chaos(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    payload: Result<Json<BinChaos>, JsonRejection>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let Json(chaos) = payload
+        .map_err(|rejection| add_cors_headers(bad_request_error(rejection.body_text()).into_response()))?;
+    let shares = [chaos.error_percent, chaos.drop_percent, chaos.slow_percent];
+    let invalid = if !shares.iter().all(|percent| (0.0..=100.0).contains(percent)) {
+        Some("Percentages must be between 0 and 100".to_string())
+    } else if shares.iter().sum::<f64>() > 100.0 {
+        Some("Percentages must add up to at most 100".to_string())
+    } else if chaos.delay_ms > MAX_RESPONSE_DELAY_MS {
+        Some(format!("delay_ms must be at most {}", MAX_RESPONSE_DELAY_MS))
+    } else {
+        None
+    };
+    if let Some(detail) = invalid {
+        return Err(add_cors_headers(bad_request_error(detail).into_response()));
+    }
+
+    let result = state.storage.set_bin_chaos(&id, Some(&chaos)).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, error = chaos.error_percent, drop = chaos.drop_percent, slow = chaos.slow_percent, "Bin chaos configured");
+            Ok(add_cors_headers(Json(chaos).into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to store bin chaos settings");
+            Err(add_cors_headers(internal_error("Failed to store bin chaos settings".to_string()).into_response()))
+        }
+    }
+}
+
+/// Stop injecting failures
+#[utoipa::path(
+    delete,
+    path = "/bin/{id}/chaos",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "Bin answering normally"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn delete_bin_chaos(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let result = state.storage.set_bin_chaos(&id, None).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, "Bin chaos removed");
+            Ok(add_cors_headers("Bin chaos removed".to_string().into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to remove bin chaos settings");
+            Err(add_cors_headers(internal_error("Failed to remove bin chaos settings".to_string()).into_response()))
+        }
+    }
+}
+
+/// Where the bin proxies its captures
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/proxy",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "The bin's upstream", body = BinProxy),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found or not proxying"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn get_bin_proxy(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    match state.storage.bin_proxy(&id).await {
+        Ok(Some(proxy)) => Ok(add_cors_headers(Json(proxy).into_response())),
+        Ok(None) => Err(add_cors_headers(not_found_error("Bin is not proxying".to_string()).with_code("bin_not_proxying").into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to load bin proxy");
+            Err(add_cors_headers(internal_error("Failed to load bin proxy".to_string()).into_response()))
+        }
+    }
+}
+
+/// Record traffic to a live service: every capture is stored as usual, then sent on to
+/// the upstream, and its sender gets the upstream's response. The response is recorded
+/// as a "proxy" delivery of the request.
+#[utoipa::path(
+    put,
+    path = "/bin/{id}/proxy",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    request_body = BinProxy,
+    responses(
+        (status = 200, description = "Proxy configured", body = BinProxy),
+        (status = 400, description = "Invalid upstream URL"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 403, description = "Forwarding is disabled"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn set_bin_proxy(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    payload: Result<Json<BinProxy>, JsonRejection>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    check_forwarding_enabled(&state).map_err(|e| add_cors_headers(e.into_response()))?;
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let Json(proxy) = payload
+        .map_err(|rejection| add_cors_headers(bad_request_error(rejection.body_text()).into_response()))?;
+    validate_target(&state, &proxy.upstream).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let result = state.storage.set_bin_proxy(&id, Some(&proxy)).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, upstream = %proxy.upstream, "Bin proxy configured");
+            Ok(add_cors_headers(Json(proxy).into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to store bin proxy");
+            Err(add_cors_headers(internal_error("Failed to store bin proxy".to_string()).into_response()))
+        }
+    }
+}
+
+/// Answer captures from the bin again
+#[utoipa::path(
+    delete,
+    path = "/bin/{id}/proxy",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "Bin answering captures itself"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn delete_bin_proxy(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let result = state.storage.set_bin_proxy(&id, None).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, "Bin proxy removed");
+            Ok(add_cors_headers("Bin proxy removed".to_string().into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to remove bin proxy");
+            Err(add_cors_headers(internal_error("Failed to remove bin proxy".to_string()).into_response()))
+        }
+    }
+}
+
+/// The script the bin runs on every capture
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/script",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "The bin's script", body = BinScript),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found or without a script"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn get_bin_script(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    match state.storage.bin_script(&id).await {
+        Ok(Some(script)) => Ok(add_cors_headers(Json(script).into_response())),
+        Ok(None) => Err(add_cors_headers(not_found_error("Bin has no script".to_string()).with_code("bin_not_scripted").into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to load bin script");
+            Err(add_cors_headers(internal_error("Failed to load bin script".to_string()).into_response()))
+        }
+    }
+}
+
+/// Answer captures with a Rhai script. It sees the capture as `request` and evaluates to
+/// nothing to leave the answer to the bin, a string body, or a map with `status`,
+/// `headers`, `body` and `events`, each event sent to the bin's WebSocket listeners.
+#[utoipa::path(
+    put,
+    path = "/bin/{id}/script",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    request_body = BinScript,
+    responses(
+        (status = 200, description = "Script set", body = BinScript),
+        (status = 400, description = "Script too long or does not compile"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 403, description = "Scripts are disabled"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn set_bin_script(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    payload: Result<Json<BinScript>, JsonRejection>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    if !state.scripts.enabled() {
+        let response = forbidden_error("Scripts are disabled on this server".to_string()).with_code("scripts_disabled").into_response();
+        return Err(add_cors_headers(response));
+    }
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let Json(script) = payload
+        .map_err(|rejection| add_cors_headers(bad_request_error(rejection.body_text()).into_response()))?;
+    if script.source.len() > MAX_SCRIPT_LENGTH {
+        let message = format!("Script is longer than {} bytes", MAX_SCRIPT_LENGTH);
+        return Err(add_cors_headers(bad_request_error(message).into_response()));
+    }
+    state
+        .scripts
+        .compile(&script.source)
+        .map_err(|err| add_cors_headers(bad_request_error(format!("Invalid script: {}", err)).with_code("invalid_script").into_response()))?;
+
+    let result = state.storage.set_bin_script(&id, Some(&script)).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, length = script.source.len(), "Bin script set");
+            Ok(add_cors_headers(Json(script).into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to store bin script");
+            Err(add_cors_headers(internal_error("Failed to store bin script".to_string()).into_response()))
+        }
+    }
+}
+
+/// Stop running the bin's script
+#[utoipa::path(
+    delete,
+    path = "/bin/{id}/script",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "Script removed"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn delete_bin_script(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let result = state.storage.set_bin_script(&id, None).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, "Bin script removed");
+            Ok(add_cors_headers("Bin script removed".to_string().into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to remove bin script");
+            Err(add_cors_headers(internal_error("Failed to remove bin script".to_string()).into_response()))
+        }
+    }
+}
+
+/// The bin's own capture rate limit
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/rate-limit",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "The bin's rate limit override", body = BinRateLimit),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found or using the configured default"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn get_bin_rate_limit(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    match state.storage.bin_rate_limit(&id).await {
+        Ok(Some(limit)) => Ok(add_cors_headers(Json(limit).into_response())),
+        Ok(None) => Err(add_cors_headers(not_found_error("No rate limit override configured".to_string()).into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to load bin rate limit");
+            Err(add_cors_headers(internal_error("Failed to load bin rate limit".to_string()).into_response()))
+        }
+    }
+}
+
+/// Replace the configured per-bin rate limit for this bin. Captures past the limit get a
+/// 429 with `Retry-After`; a rate of 0 lifts the limit.
+#[utoipa::path(
+    put,
+    path = "/bin/{id}/rate-limit",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    request_body = BinRateLimit,
+    responses(
+        (status = 200, description = "Rate limit override stored", body = BinRateLimit),
+        (status = 400, description = "Negative or non-finite rate"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn set_bin_rate_limit(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    payload: Result<Json<BinRateLimit>, JsonRejection>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let Json(limit) = payload
+        .map_err(|rejection| add_cors_headers(bad_request_error(rejection.body_text()).into_response()))?;
+    if !limit.requests_per_second.is_finite() || limit.requests_per_second < 0.0 {
+        return Err(add_cors_headers(
+            bad_request_error("requests_per_second must be a non-negative number".to_string()).into_response(),
+        ));
+    }
+
+    let result = state.storage.set_bin_rate_limit(&id, Some(&limit)).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            // The bucket holds the old limit
+            state.bin_limiter.forget(&id);
+            info!(%id, %addr, requests_per_second = limit.requests_per_second, burst_size = limit.burst_size, "Bin rate limit configured");
+            Ok(add_cors_headers(Json(limit).into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to store bin rate limit");
+            Err(add_cors_headers(internal_error("Failed to store bin rate limit".to_string()).into_response()))
+        }
+    }
+}
+
+/// Go back to the configured per-bin rate limit
+#[utoipa::path(
+    delete,
+    path = "/bin/{id}/rate-limit",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "Rate limit override removed"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn delete_bin_rate_limit(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let result = state.storage.set_bin_rate_limit(&id, None).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            state.bin_limiter.forget(&id);
+            info!(%id, %addr, "Bin rate limit override removed");
+            Ok(add_cors_headers("Rate limit override removed".to_string().into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to remove bin rate limit");
+            Err(add_cors_headers(internal_error("Failed to remove bin rate limit".to_string()).into_response()))
+        }
+    }
+}
+
+/// The bin's own limits
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/limits",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "The bin's limit overrides", body = BinLimits),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found or using the server-wide limits"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn get_bin_limits(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    match state.storage.bin_limits(&id).await {
+        Ok(Some(limits)) => Ok(add_cors_headers(Json(limits).into_response())),
+        Ok(None) => Err(add_cors_headers(not_found_error("No limit overrides configured".to_string()).into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to load bin limits");
+            Err(add_cors_headers(internal_error("Failed to load bin limits".to_string()).into_response()))
+        }
+    }
+}
+
+/// A bin may go as far as `[limits] bin_max_*`, or the server-wide value when that is higher
+fn validate_bin_limits(limits: &BinLimits, config: &crate::config::LimitsConfig, expiry_hours: i64) -> Result<(), ApiError> {
+    if let Some(max_requests) = limits.max_requests {
+        let ceiling = config.bin_max_requests.max(config.max_requests_per_bin);
+        if !(1..=ceiling).contains(&max_requests) {
+            return Err(bad_request_error(format!("max_requests must be between 1 and {}", ceiling)));
+        }
+    }
+    if let Some(max_body_size) = limits.max_body_size {
+        let ceiling = config.bin_max_body_size.max(config.max_body_size);
+        if max_body_size > ceiling {
+            return Err(bad_request_error(format!("max_body_size must be at most {}", ceiling)));
+        }
+    }
+    if let Some(hours) = limits.expiry_hours {
+        let ceiling = config.bin_max_expiry_hours.max(expiry_hours);
+        if !(1..=ceiling).contains(&hours) {
+            return Err(bad_request_error(format!("expiry_hours must be between 1 and {}", ceiling)));
+        }
+    }
+    if limits.retention_hours.is_some_and(|hours| hours < 0) {
+        return Err(bad_request_error("retention_hours must be 0 or more".to_string()));
+    }
+    Ok(())
+}
+
+/// Replace the bin's limit overrides. Omitted limits keep the server-wide value.
+#[utoipa::path(
+    put,
+    path = "/bin/{id}/limits",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    request_body = BinLimits,
+    responses(
+        (status = 200, description = "Limit overrides stored", body = BinLimits),
+        (status = 400, description = "A limit beyond what the server allows"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn set_bin_limits(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    payload: Result<Json<BinLimits>, JsonRejection>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let Json(limits) = payload
+        .map_err(|rejection| add_cors_headers(bad_request_error(rejection.body_text()).into_response()))?;
+    validate_bin_limits(&limits, &state.limits.load(), state.cleanup.load().bin_expiry_hours)
+        .map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let result = state.storage.set_bin_limits(&id, Some(&limits)).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, max_requests = ?limits.max_requests, max_body_size = ?limits.max_body_size, expiry_hours = ?limits.expiry_hours, retention_hours = ?limits.retention_hours, "Bin limits configured");
+            Ok(add_cors_headers(Json(limits).into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to store bin limits");
+            Err(add_cors_headers(internal_error("Failed to store bin limits".to_string()).into_response()))
+        }
+    }
+}
+
+/// Go back to the server-wide limits
+#[utoipa::path(
+    delete,
+    path = "/bin/{id}/limits",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "Limit overrides removed"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn delete_bin_limits(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let result = state.storage.set_bin_limits(&id, None).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, "Bin limit overrides removed");
+            Ok(add_cors_headers("Limit overrides removed".to_string().into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to remove bin limits");
+            Err(add_cors_headers(internal_error("Failed to remove bin limits".to_string()).into_response()))
+        }
+    }
+}
+
+/// Where the bin posts new captures, and which ones
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/notifications",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "The bin's chat notifications", body = BinNotifications),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found or no notifications configured"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn get_bin_notifications(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    match state.storage.bin_notifications(&id).await {
+        Ok(Some(notifications)) => Ok(add_cors_headers(Json(notifications).into_response())),
+        Ok(None) => Err(add_cors_headers(not_found_error("No notifications configured".to_string()).into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to load bin notifications");
+            Err(add_cors_headers(internal_error("Failed to load bin notifications".to_string()).into_response()))
+        }
+    }
+}
+
+/// Post a summary of each matching capture to a Slack or Discord webhook
+#[utoipa::path(
+    put,
+    path = "/bin/{id}/notifications",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    request_body = BinNotifications,
+    responses(
+        (status = 200, description = "Notifications stored", body = BinNotifications),
+        (status = 400, description = "Invalid webhook URL or method"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 403, description = "Notifications are disabled"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn set_bin_notifications(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    payload: Result<Json<BinNotifications>, JsonRejection>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+    if !state.notifier.is_enabled() {
+        let response = forbidden_error("Notifications are disabled on this server".to_string()).with_code("notifications_disabled").into_response();
+        return Err(add_cors_headers(response));
+    }
+
+    let Json(mut notifications) = payload
+        .map_err(|rejection| add_cors_headers(bad_request_error(rejection.body_text()).into_response()))?;
+    validate_target(&state, &notifications.webhook_url).await.map_err(|e| add_cors_headers(e.into_response()))?;
+    for method in &mut notifications.methods {
+        Method::from_bytes(method.as_bytes())
+            .map_err(|_| add_cors_headers(bad_request_error(format!("Invalid method `{}`", method)).into_response()))?;
+        method.make_ascii_uppercase();
+    }
+    notifications.path_prefix = notifications.path_prefix.filter(|prefix| !prefix.is_empty());
+
+    let result = state.storage.set_bin_notifications(&id, Some(&notifications)).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, service = notifications.service.as_str(), "Bin notifications configured");
+            Ok(add_cors_headers(Json(notifications).into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to store bin notifications");
+            Err(add_cors_headers(internal_error("Failed to store bin notifications".to_string()).into_response()))
+        }
+    }
+}
+
+/// Stop posting captures to chat
+#[utoipa::path(
+    delete,
+    path = "/bin/{id}/notifications",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "Notifications removed"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn delete_bin_notifications(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let result = state.storage.set_bin_notifications(&id, None).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, "Bin notifications removed");
+            Ok(add_cors_headers("Notifications removed".to_string().into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to remove bin notifications");
+            Err(add_cors_headers(internal_error("Failed to remove bin notifications".to_string()).into_response()))
+        }
+    }
+}
+
+/// The bin's extractors, in the order they were given
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/extractors",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "The bin's extractors, empty when it has none", body = [Extractor]),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn get_extractors(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    match state.storage.extractors(&id).await {
+        Ok(extractors) => Ok(add_cors_headers(Json(extractors).into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to load extractors");
+            Err(add_cors_headers(internal_error("Failed to load extractors".to_string()).into_response()))
+        }
+    }
+}
+
+/// Replace the bin's extractors. Later captures get the new fields; requests already
+/// captured keep the ones they had.
+#[utoipa::path(
+    put,
+    path = "/bin/{id}/extractors",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    request_body = [Extractor],
+    responses(
+        (status = 200, description = "Extractors stored", body = [Extractor]),
+        (status = 400, description = "Invalid name or path, or too many extractors"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn set_extractors(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    payload: Result<Json<Vec<Extractor>>, JsonRejection>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let Json(extractors) = payload
+        .map_err(|rejection| add_cors_headers(bad_request_error(rejection.body_text()).into_response()))?;
+    validate_extractors(&extractors).map_err(|message| add_cors_headers(bad_request_error(message).into_response()))?;
+
+    let result = state.storage.set_extractors(&id, &extractors).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, count = extractors.len(), "Extractors replaced");
+            Ok(add_cors_headers(Json(extractors).into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to store extractors");
+            Err(add_cors_headers(internal_error("Failed to store extractors".to_string()).into_response()))
+        }
+    }
+}
+
+/// Check a rule's conditions and response, normalising the method to upper case.
+/// Empty conditions are dropped so they match anything.
+fn build_response_rule(rule_id: String, create: CreateResponseRule) -> Result<ResponseRule, ApiError> {
+    let method = match create.method.filter(|method| !method.is_empty()) {
+        Some(method) => {
+            Method::from_bytes(method.as_bytes())
+                .map_err(|_| bad_request_error(format!("Invalid rule method `{}`", method)))?;
+            Some(method.to_ascii_uppercase())
+        }
+        None => None,
+    };
+    let path = create.path.filter(|path| !path.is_empty());
+    if let Some(path) = path.as_ref().filter(|path| !path.starts_with('/') && !path.starts_with('*')) {
+        return Err(bad_request_error(format!("Rule path `{}` must start with `/` or `*`", path)));
+    }
+    validate_custom_response(&create.response)?;
+    Ok(ResponseRule { rule_id, method, path, response: create.response })
+}
+
+/// List a bin's response rules in the order they are evaluated
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/rules",
+    tag = "responses",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "Rules in evaluation order", body = [ResponseRule]),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn list_response_rules(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    match state.storage.response_rules(&id).await {
+        Ok(rules) => Ok(add_cors_headers(Json(rules).into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to load response rules");
+            Err(add_cors_headers(internal_error("Failed to load response rules".to_string()).into_response()))
+        }
+    }
+}
+
+/// Add a response rule, evaluated after the bin's existing rules
+#[utoipa::path(
+    post,
+    path = "/bin/{id}/rules",
+    tag = "responses",
+    params(("id" = String, Path, description = "Bin id")),
+    request_body = CreateResponseRule,
+    responses(
+        (status = 201, description = "Rule created", body = ResponseRule),
+        (status = 400, description = "Invalid condition or response, or too many rules"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn create_response_rule(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    payload: Result<Json<CreateResponseRule>, JsonRejection>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let Json(create) = payload
+        .map_err(|rejection| add_cors_headers(bad_request_error(rejection.body_text()).into_response()))?;
+    let rule = build_response_rule(Uuid::new_v4().to_string(), create).map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let result = match state.storage.response_rules(&id).await {
+        Ok(rules) if rules.len() >= MAX_RESPONSE_RULES => {
+            let message = format!("A bin can have at most {} response rules", MAX_RESPONSE_RULES);
+            return Err(add_cors_headers(bad_request_error(message).into_response()));
+        }
+        Ok(_) => state.storage.add_response_rule(&id, &rule).await,
+        Err(err) => Err(err),
+    };
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, rule_id = %rule.rule_id, "Response rule created");
+            Ok(add_cors_headers((StatusCode::CREATED, Json(rule)).into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to store response rule");
+            Err(add_cors_headers(internal_error("Failed to store response rule".to_string()).into_response()))
+        }
+    }
+}
+
+/// Replace all of a bin's response rules; the new rules are evaluated in the given order
+#[utoipa::path(
+    put,
+    path = "/bin/{id}/rules",
+    tag = "responses",
+    params(("id" = String, Path, description = "Bin id")),
+    request_body = [CreateResponseRule],
+    responses(
+        (status = 200, description = "Rules stored", body = [ResponseRule]),
+        (status = 400, description = "Invalid condition or response, or too many rules"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn replace_response_rules(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    payload: Result<Json<Vec<CreateResponseRule>>, JsonRejection>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let Json(creates) = payload
+        .map_err(|rejection| add_cors_headers(bad_request_error(rejection.body_text()).into_response()))?;
+    if creates.len() > MAX_RESPONSE_RULES {
+        let message = format!("A bin can have at most {} response rules", MAX_RESPONSE_RULES);
+        return Err(add_cors_headers(bad_request_error(message).into_response()));
+    }
+    let rules = creates
+        .into_iter()
+        .map(|create| build_response_rule(Uuid::new_v4().to_string(), create))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let result = state.storage.set_response_rules(&id, &rules).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, count = rules.len(), "Response rules replaced");
+            Ok(add_cors_headers(Json(rules).into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to store response rules");
+            Err(add_cors_headers(internal_error("Failed to store response rules".to_string()).into_response()))
+        }
+    }
+}
+
+/// Change a response rule's conditions or response, keeping its place in the order
+#[utoipa::path(
+    put,
+    path = "/bin/{id}/rules/{rule_id}",
+    tag = "responses",
+    params(("id" = String, Path, description = "Bin id"), ("rule_id" = String, Path, description = "Rule id")),
+    request_body = CreateResponseRule,
+    responses(
+        (status = 200, description = "Rule updated", body = ResponseRule),
+        (status = 400, description = "Invalid condition or response"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Rule not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn update_response_rule(
+    State(state): State<AppState>,
+    Path((id, rule_id)): Path<(String, String)>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    payload: Result<Json<CreateResponseRule>, JsonRejection>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    validate_bin_id(&rule_id).map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let Json(create) = payload
+        .map_err(|rejection| add_cors_headers(bad_request_error(rejection.body_text()).into_response()))?;
+    let rule = build_response_rule(rule_id, create).map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let result = state.storage.update_response_rule(&id, &rule).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(true) => {
+            info!(%id, %addr, rule_id = %rule.rule_id, "Response rule updated");
+            Ok(add_cors_headers(Json(rule).into_response()))
+        },
+        Ok(false) => Err(add_cors_headers(not_found_error("Rule not found".to_string()).into_response())),
+        Err(err) => {
+            error!(%id, %addr, rule_id = %rule.rule_id, %err, "Failed to update response rule");
+            Err(add_cors_headers(internal_error("Failed to update response rule".to_string()).into_response()))
+        }
+    }
+}
+
+/// Remove a response rule
+#[utoipa::path(
+    delete,
+    path = "/bin/{id}/rules/{rule_id}",
+    tag = "responses",
+    params(("id" = String, Path, description = "Bin id"), ("rule_id" = String, Path, description = "Rule id")),
+    responses(
+        (status = 200, description = "Rule deleted"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Rule not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn delete_response_rule(
+    State(state): State<AppState>,
+    Path((id, rule_id)): Path<(String, String)>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    validate_bin_id(&rule_id).map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let result = state.storage.delete_response_rule(&id, &rule_id).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(true) => {
+            info!(%id, %addr, %rule_id, "Response rule deleted");
+            Ok(add_cors_headers("Rule deleted".to_string().into_response()))
+        },
+        Ok(false) => Err(add_cors_headers(not_found_error("Rule not found".to_string()).into_response())),
+        Err(err) => {
+            error!(%id, %addr, %rule_id, %err, "Failed to delete response rule");
+            Err(add_cors_headers(internal_error("Failed to delete response rule".to_string()).into_response()))
+        }
+    }
+}
+
+/// A bin's captured requests, as JSON, or as an HTML table or CSV when `Accept` prefers
+/// `text/html` (as browsers do) or `text/csv`
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/inspect",
+    tag = "requests",
+    params(("id" = String, Path, description = "Bin id"), InspectQuery),
+    responses(
+        (status = 200, description = "Captured requests, oldest first. `X-Total-Count` holds the number matching the filters",
+            content(("application/json" = [LoggedRequest]), ("text/html" = String), ("text/csv" = String))),
+        (status = 304, description = "`If-None-Match` names the current `ETag`; nothing changed"),
+        (status = 400, description = "Invalid filter or cursor"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn inspect_bin(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<InspectQuery>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    // Validate input and check bin existence
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let limit = params.limit.unwrap_or(DEFAULT_INSPECT_LIMIT).clamp(1, MAX_INSPECT_LIMIT);
+    let offset = params.offset.unwrap_or(0).max(0);
+
+    let after = params
+        .after_id
+        .as_deref()
+        .map(validate_bin_id)
+        .transpose()
+        .map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let method = params.method.as_deref().map(str::to_ascii_uppercase);
+    let since = optional_timestamp_param("since", params.since.as_deref())
+        .map_err(|e| add_cors_headers(e.into_response()))?;
+    let until = optional_timestamp_param("until", params.until.as_deref())
+        .map_err(|e| add_cors_headers(e.into_response()))?;
+    let (header_name, header_value) = match params.header.as_deref() {
+        Some(raw) => {
+            let (name, value) = parse_header_filter(raw).map_err(|e| add_cors_headers(e.into_response()))?;
+            (Some(name), value)
+        }
+        None => (None, None),
+    };
+
+    if params.value.is_some() && params.field.is_none() {
+        return Err(add_cors_headers(bad_request_error("`value` needs a `field` to compare with".to_string()).into_response()));
+    }
+
+    let filter = RequestFilter { method, since, until, header_name, header_value, field_name: params.field, field_value: params.value };
+    let page = PageRequest { after, limit, offset };
+
+    // Read before the requests, so a capture in between only costs the poller a refetch
+    let validators = match state.storage.bin_revision(&id).await {
+        Ok(revision) => revision.map(|(revision, last_updated)| cache_validators(revision, last_updated.as_deref())),
+        Err(err) => {
+            warn!(%id, %err, "Failed to load bin revision, answering without an ETag");
+            None
+        }
+    };
+    if let Some(validators) = &validators
+        && validators.get(header::ETAG).is_some_and(|etag| etag_matches(&headers, etag))
+    {
+        return Ok(add_cors_headers((StatusCode::NOT_MODIFIED, validators.clone()).into_response()));
+    }
+
+    // Fetch the matching requests for this bin
+    match state.storage.list_requests(&id, &filter, page).await {
+        Ok((data, total)) => {
+            info!(%id, %addr, request_count = data.len(), total, "Successfully fetched bin requests");
+            let media_type = preferred_media_type(&headers, &INSPECT_MEDIA_TYPES).unwrap_or(INSPECT_MEDIA_TYPES[0]);
+            let body = match media_type {
+                "text/html" => ([(header::CONTENT_TYPE, "text/html; charset=utf-8")], to_html_page(&id, &data, total)).into_response(),
+                "text/csv" => {
+                    let csv: String = std::iter::once(CSV_HEADER.to_string()).chain(data.iter().map(to_csv_row)).collect();
+                    ([(header::CONTENT_TYPE, "text/csv; charset=utf-8")], csv).into_response()
+                }
+                _ => Json(data).into_response(),
+            };
+            let mut response = (validators.unwrap_or_default(), body).into_response();
+            response.headers_mut().insert(TOTAL_COUNT_HEADER, HeaderValue::from(total));
+            response.headers_mut().insert(header::VARY, HeaderValue::from_static("accept"));
+            Ok(add_cors_headers(response))
+        },
+        Err(StorageError::UnknownCursor) => {
+            Err(add_cors_headers(bad_request_error("Unknown `after_id` for this bin".to_string()).into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to fetch logged requests");
+            let response = internal_error("Failed to fetch logged requests".to_string()).into_response();
+            Err(add_cors_headers(response))
+        }
+    }
+}
+
+/// A bin's most recent requests as an Atom feed, for feed readers and chat integrations
+/// that watch low-volume webhooks. Private bins take their token as `?token=`.
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/feed.atom",
+    tag = "requests",
+    params(("id" = String, Path, description = "Bin id"), FeedQuery),
+    responses(
+        (status = 200, description = "Atom feed of the newest requests, newest first", content_type = "application/atom+xml", body = String),
+        (status = 304, description = "`If-None-Match` names the current `ETag`; nothing changed"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn bin_feed(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<FeedQuery>,
+    headers: HeaderMap,
+) -> Result<Response, Response> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let limit = params.limit.unwrap_or(DEFAULT_FEED_ENTRIES).clamp(1, MAX_FEED_ENTRIES);
+    let info = fetch_bin_info(&state, &id).await.map_err(|err| {
+        error!(%id, %addr, %err, "Failed to load bin for its feed");
+        add_cors_headers(internal_error("Failed to load bin".to_string()).into_response())
+    })?;
+    let validators = match state.storage.bin_revision(&id).await {
+        Ok(revision) => revision.map(|(revision, last_updated)| cache_validators(revision, last_updated.as_deref())),
+        Err(err) => {
+            warn!(%id, %err, "Failed to load bin revision, answering without an ETag");
+            None
+        }
+    };
+    if let Some(validators) = &validators
+        && validators.get(header::ETAG).is_some_and(|etag| etag_matches(&headers, etag))
+    {
+        return Ok(add_cors_headers((StatusCode::NOT_MODIFIED, validators.clone()).into_response()));
+    }
+
+    let mut requests = state.storage.recent_requests(&id, limit).await.map_err(|err| {
+        error!(%id, %addr, %err, "Failed to fetch requests for feed");
+        add_cors_headers(internal_error("Failed to fetch logged requests".to_string()).into_response())
+    })?;
+    requests.reverse();
+
+    let updated = requests
+        .first()
+        .map(|request| request.timestamp.clone())
+        .or(info.last_updated.clone())
+        .unwrap_or_else(|| Utc::now().to_rfc3339());
+    let feed = to_atom_feed(&id, info.name.as_deref(), &base_url(&state, &headers), &requests, &updated);
+    info!(%id, %addr, entries = requests.len(), "Serving bin feed");
+    let response = (
+        validators.unwrap_or_default(),
+        [(header::CONTENT_TYPE, "application/atom+xml; charset=utf-8")],
+        feed,
+    );
+    Ok(add_cors_headers(response.into_response()))
+}
+
+/// Where clients reach this server: `[notifications] public_url` when set, otherwise
+/// taken from the request's `Host` and `X-Forwarded-Proto` and the base path
+fn base_url(state: &AppState, headers: &HeaderMap) -> String {
+    if let Some(public_url) = state.notifier.public_url() {
+        return public_url.to_string();
+    }
+    let host = headers.get(header::HOST).and_then(|value| value.to_str().ok()).unwrap_or("localhost");
+    let scheme = headers.get("x-forwarded-proto").and_then(|value| value.to_str().ok()).unwrap_or("http");
+    format!("{}://{}{}", scheme, host, state.base_path)
+}
+
+/// Wait for requests captured after `after`, for clients that can't hold a WebSocket open.
+/// Answers at once when there already are some, else with the first ones to arrive, or
+/// an empty list once `timeout` runs out.
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/poll",
+    tag = "requests",
+    params(("id" = String, Path, description = "Bin id"), PollQuery),
+    responses(
+        (status = 200, description = "New requests, oldest first; empty when none arrived in time", body = [LoggedRequest]),
+        (status = 400, description = "Unknown `after`"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found, or deleted while waiting"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn poll_bin(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<PollQuery>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+    let after = params
+        .after
+        .as_deref()
+        .map(validate_bin_id)
+        .transpose()
+        .map_err(|e| add_cors_headers(e.into_response()))?;
+    let timeout = params.timeout.unwrap_or(DEFAULT_POLL_TIMEOUT_SECONDS).min(MAX_POLL_TIMEOUT_SECONDS);
+
+    // Subscribed before reading the bin, so nothing captured in between is missed
+    let mut listener = Listener::subscribe(&state, &id);
+    if let Some(after) = after {
+        let page = PageRequest { after: Some(after), limit: MAX_INSPECT_LIMIT, offset: 0 };
+        match state.storage.list_requests(&id, &RequestFilter::default(), page).await {
+            Ok((requests, _)) if !requests.is_empty() => return Ok(add_cors_headers(Json(requests).into_response())),
+            Ok(_) => {}
+            Err(StorageError::UnknownCursor) => {
+                return Err(add_cors_headers(bad_request_error("Unknown `after` for this bin".to_string()).into_response()));
+            }
+            Err(err) => {
+                error!(%id, %addr, %err, "Failed to fetch logged requests");
+                return Err(add_cors_headers(internal_error("Failed to fetch logged requests".to_string()).into_response()));
+            }
+        }
+    }
+
+    // Captures are announced once stored, except queued ones, which the next poll finds
+    let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(timeout);
+    let mut requests = Vec::new();
+    while requests.is_empty() {
+        let message = match tokio::time::timeout_at(deadline, listener.recv()).await {
+            Ok(Ok(message)) => message,
+            Ok(Err(tokio::sync::broadcast::error::RecvError::Lagged(_))) => continue,
+            Ok(Err(tokio::sync::broadcast::error::RecvError::Closed)) | Err(_) => break,
+        };
+        match serde_json::from_str::<WsMessage>(&message).map(|message| message.event) {
+            Ok(WsEvent::Request(request)) => {
+                if let Ok(Some(stored)) = state.storage.get_request(&id, request.request_id).await {
+                    requests.push(stored);
+                }
+            }
+            Ok(WsEvent::BinDeleted { .. }) => {
+                return Err(add_cors_headers(not_found_error("Bin not found".to_string()).with_code("bin_not_found").into_response()));
+            }
+            _ => {}
+        }
+    }
+    info!(%id, %addr, request_count = requests.len(), "Poll answered");
+    Ok(add_cors_headers(Json(requests).into_response()))
+}
+
+/// Inspect's `ETag`, from the bin's revision, and `Last-Modified`, from its last capture
+fn cache_validators(revision: i64, last_updated: Option<&str>) -> HeaderMap {
+    let mut validators = HeaderMap::new();
+    validators.insert(header::ETAG, HeaderValue::from_str(&format!("W/\"{}\"", revision)).unwrap());
+    if let Some(last_updated) = last_updated.and_then(|ts| DateTime::parse_from_rfc3339(ts).ok()) {
+        let http_date = last_updated.with_timezone(&Utc).format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+        validators.insert(header::LAST_MODIFIED, HeaderValue::from_str(&http_date).unwrap());
+    }
+    validators
+}
+
+/// Whether `If-None-Match` names `etag`, using the weak comparison
+fn etag_matches(headers: &HeaderMap, etag: &HeaderValue) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let etag = opaque(etag.to_str().unwrap_or_default());
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|tag| tag.trim() == "*" || opaque(tag) == etag)
+}
+
+/// Return the original bytes of a captured body with its original Content-Type
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/request/{rid}/body",
+    tag = "requests",
+    params(("id" = String, Path, description = "Bin id"), ("rid" = String, Path, description = "Request id")),
+    responses(
+        (status = 200, description = "Original body bytes with the captured Content-Type"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Request not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn get_request_body(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path((id, request_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    let request_uuid = validate_bin_id(&request_id).map_err(|e| add_cors_headers(e.into_response()))?;
+
+    match state.storage.get_request(&id, request_uuid).await {
+        Ok(Some(request)) => {
+            let bytes = decode_body(request.body.as_deref().unwrap_or(""), request.body_encoding.as_deref()).map_err(|err| {
+                error!(%id, %request_id, %err, "Stored body is not valid base64");
+                add_cors_headers(internal_error("Failed to decode stored body".to_string()).into_response())
+            })?;
+            let content_type = stored_header(&request.headers, "content-type")
+                .unwrap_or_else(|| "application/octet-stream".to_string());
+
+            info!(%id, %addr, %request_id, size = bytes.len(), "Returning raw request body");
+            let response = ([(header::CONTENT_TYPE, content_type)], bytes).into_response();
+            Ok(add_cors_headers(response))
+        },
+        Ok(None) => Err(add_cors_headers(not_found_error("Request not found".to_string()).with_code("request_not_found").into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to fetch request body");
+            Err(add_cors_headers(internal_error("Failed to fetch request body".to_string()).into_response()))
+        }
+    }
+}
+
+/// Compare two requests captured by a bin: method, path, query string, headers and body
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/diff",
+    tag = "requests",
+    params(("id" = String, Path, description = "Bin id"), DiffQuery),
+    responses(
+        (status = 200, description = "What changed from one request to the other", body = RequestDiff),
+        (status = 400, description = "Invalid request id"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin or request not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn diff_requests(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<DiffQuery>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+    let from_id = validate_bin_id(&params.from).map_err(|e| add_cors_headers(e.into_response()))?;
+    let to_id = validate_bin_id(&params.to).map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let mut requests = Vec::with_capacity(2);
+    for request_id in [from_id, to_id] {
+        match state.storage.get_request(&id, request_id).await {
+            Ok(Some(request)) => requests.push(request),
+            Ok(None) => {
+                let message = format!("Request {} not found in this bin", request_id);
+                return Err(add_cors_headers(not_found_error(message).with_code("request_not_found").into_response()));
+            }
+            Err(err) => {
+                error!(%id, %addr, %request_id, %err, "Failed to fetch request");
+                return Err(add_cors_headers(internal_error("Failed to fetch request".to_string()).into_response()));
+            }
+        }
+    }
+
+    info!(%id, %addr, from = %from_id, to = %to_id, "Diffing requests");
+    Ok(add_cors_headers(Json(diff::diff_requests(&requests[0], &requests[1])).into_response()))
+}
+
+/// Export every request in a bin, oldest first
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/export",
+    tag = "export",
+    params(("id" = String, Path, description = "Bin id"), ExportQuery),
+    responses(
+        (status = 200, description = "curl script, Postman collection, NDJSON or CSV"),
+        (status = 400, description = "Unsupported format"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn export_bin(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<ExportQuery>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let format_name = params.format.as_deref().unwrap_or("curl");
+    let Some(format) = ExportFormat::parse(format_name) else {
+        return Err(add_cors_headers(bad_request_error(format!("Unsupported export format `{}`", format_name)).into_response()));
+    };
+    info!(%id, %addr, format = format_name, "Exporting bin");
+
+    let response = match format {
+        // Line oriented formats are streamed straight from the database
+        ExportFormat::Ndjson => {
+            let body = stream_export(&state, id, None, to_ndjson_line);
+            ([(header::CONTENT_TYPE, "application/x-ndjson")], body).into_response()
+        }
+        ExportFormat::Csv => {
+            let disposition = format!("attachment; filename=\"rustbin-{}.csv\"", id);
+            let body = stream_export(&state, id, Some(CSV_HEADER), to_csv_row);
+            (
+                [(header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()), (header::CONTENT_DISPOSITION, disposition)],
+                body,
+            ).into_response()
+        }
+        ExportFormat::Curl => {
+            let requests = fetch_bin_requests(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+            let commands: Vec<String> = requests
+                .iter()
+                .map(|request| to_curl(request, &export_url(request, &state.bin_path(&id), params.target.as_deref())))
+                .collect();
+            ([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], commands.join("\n\n") + "\n").into_response()
+        }
+        ExportFormat::Postman => {
+            let requests = fetch_bin_requests(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+            let name = fetch_bin_info(&state, &id).await.ok().and_then(|info| info.name);
+            let collection = to_postman_collection(&id, &state.bin_path(&id), name.as_deref(), &requests, params.target.as_deref());
+            let disposition = format!("attachment; filename=\"rustbin-{}.postman_collection.json\"", id);
+            ([(header::CONTENT_DISPOSITION, disposition)], Json(collection)).into_response()
+        }
+    };
+    Ok(add_cors_headers(response))
+}
+
+/// Load requests from an NDJSON export or a HAR file into a bin, with fresh request ids.
+/// All requests are stored or none are; the bin's request limit applies afterwards.
+#[utoipa::path(
+    post,
+    path = "/bin/{id}/import",
+    tag = "export",
+    params(("id" = String, Path, description = "Bin id")),
+    request_body(content = String, description = "NDJSON export or HAR file"),
+    responses(
+        (status = 200, description = "Number of imported requests, as `{\"imported\": n}`"),
+        (status = 400, description = "Unreadable import"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+        (status = 413, description = "An imported request is too large"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn import_requests(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    body: Bytes,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    let settings = load_bin_settings(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    check_storage_quota(&state).map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let imported = parse_import(&body)
+        .map_err(|err| add_cors_headers(bad_request_error(format!("Invalid import: {}", err)).into_response()))?;
+    let limits = limits_for(&state, &settings);
+    let mut requests = imported
+        .into_iter()
+        .map(|request| process_imported_request(request, &limits))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| add_cors_headers(e.into_response()))?;
+    let redactor = bin_redactor(&state, &id, settings.redaction.as_ref());
+    let extractors = Extractors::new(&settings.extractors);
+    for request in &mut requests {
+        request.redact(&redactor);
+        request.extract(&extractors);
+    }
+
+    let rows: Vec<LoggedRequest> = requests.iter().map(ProcessedRequest::to_logged_request).collect();
+    match store_requests(&state, &id, &rows, limits.max_requests_per_bin).await {
+        Ok(_) => {
+            info!(%id, %addr, imported = requests.len(), "Imported requests");
+            for row in &rows {
+                state.hooks.on_request_logged(&id, row);
+            }
+            let response = Json(serde_json::json!({ "imported": requests.len() })).into_response();
+            Ok(add_cors_headers(response))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to import requests");
+            Err(add_cors_headers(internal_error("Failed to import requests".to_string()).into_response()))
+        }
+    }
+}
+
+/// Capture a made-up delivery from a well-known webhook provider as though the provider
+/// had sent it, so integrations can be tried out without setting the provider up. The
+/// sample is signed with the bin's secret when the bin verifies signatures, goes through
+/// the same checks, storage and notifications as any capture and is answered like one.
+#[utoipa::path(
+    post,
+    path = "/bin/{id}/simulate",
+    tag = "capture",
+    params(("id" = String, Path, description = "Bin id"), SimulateQuery),
+    responses(
+        (status = 200, description = "Sample logged, or the bin's custom response", body = CaptureResponse),
+        (status = 202, description = "Sample queued for a background write", body = CaptureResponse),
+        (status = 400, description = "Unknown sample provider"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn simulate_request(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<SimulateQuery>,
+) -> Result<Response, Response> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    let settings = load_bin_settings(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let provider_name = params.provider.as_deref().unwrap_or("generic");
+    let Some(provider) = SampleProvider::parse(provider_name) else {
+        return Err(add_cors_headers(bad_request_error(format!("Unsupported sample provider `{}`", provider_name)).into_response()));
+    };
+    let now = Utc::now();
+    let sample = samples::sample(provider, now);
+
+    let mut builder = Request::builder().method(Method::POST).uri("/").header(SIMULATED_HEADER, provider.as_str());
+    for (name, value) in &sample.headers {
+        builder = builder.header(*name, value);
+    }
+    if let Some(verification) = &settings.signature {
+        for (name, value) in signatures::sign(verification, sample.body.as_bytes(), now.timestamp()) {
+            builder = builder.header(name, value);
+        }
+    }
+    let req = builder.body(Body::from(sample.body)).map_err(|err| {
+        error!(%id, %err, "Failed to build sample request");
+        add_cors_headers(internal_error("Failed to build sample request".to_string()).into_response())
+    })?;
+
+    info!(%id, %addr, provider = provider.as_str(), "Simulating webhook delivery");
+    capture_request(state, id, "/".to_string(), addr, req).await
+}
+
+/// Every request in a bin, oldest first
+async fn fetch_bin_requests(state: &AppState, id: &str) -> Result<Vec<LoggedRequest>, ApiError> {
+    state
+        .storage
+        .stream_requests(id.to_string())
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .map_err(|err| {
+            error!(%id, %err, "Failed to fetch bin requests");
+            internal_error("Failed to fetch logged requests".to_string())
+        })
+}
+
+/// Stream a bin's requests, oldest first, rendering each one as it is read so large
+/// bins are never held in memory.
+fn stream_export(state: &AppState, id: String, preamble: Option<&'static str>, render: fn(&LoggedRequest) -> String) -> Body {
+    let preamble = futures::stream::iter(preamble.map(|preamble| Ok(preamble.to_string())));
+    let rows = state.storage.stream_requests(id.clone()).map(move |row| {
+        row.map(|request| render(&request)).map_err(|err| {
+            error!(%id, %err, "Failed to stream bin export");
+            std::io::Error::other(err)
+        })
+    });
+    Body::from_stream(preamble.chain(rows))
+}
+
+/// A single captured request as a `curl` command line
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/request/{rid}/curl",
+    tag = "export",
+    params(("id" = String, Path, description = "Bin id"), ("rid" = String, Path, description = "Request id"), ExportQuery),
+    responses(
+        (status = 200, description = "curl command line", content_type = "text/plain"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Request not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn get_request_curl(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path((id, request_id)): Path<(String, String)>,
+    Query(params): Query<ExportQuery>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    let request_uuid = validate_bin_id(&request_id).map_err(|e| add_cors_headers(e.into_response()))?;
+
+    match state.storage.get_request(&id, request_uuid).await {
+        Ok(Some(request)) => {
+            info!(%id, %addr, %request_id, "Exporting request as curl");
+            let command = to_curl(&request, &export_url(&request, &state.bin_path(&id), params.target.as_deref()));
+            let response = ([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], command + "\n").into_response();
+            Ok(add_cors_headers(response))
+        },
+        Ok(None) => Err(add_cors_headers(not_found_error("Request not found".to_string()).with_code("request_not_found").into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to fetch request");
+            Err(add_cors_headers(internal_error("Failed to fetch request".to_string()).into_response()))
+        }
+    }
+}
+
+/// A single captured request as a program in another language, for reproducing it in tests
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/request/{rid}/snippet",
+    tag = "export",
+    params(("id" = String, Path, description = "Bin id"), ("rid" = String, Path, description = "Request id"), SnippetQuery),
+    responses(
+        (status = 200, description = "Source code sending the request", content_type = "text/plain"),
+        (status = 400, description = "Missing or unknown language"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Request not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn get_request_snippet(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path((id, request_id)): Path<(String, String)>,
+    Query(params): Query<SnippetQuery>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    let request_uuid = validate_bin_id(&request_id).map_err(|e| add_cors_headers(e.into_response()))?;
+    let lang = params.lang.as_deref().and_then(SnippetLanguage::parse).ok_or_else(|| {
+        let supported: Vec<&str> = SnippetLanguage::ALL.iter().map(SnippetLanguage::as_str).collect();
+        add_cors_headers(bad_request_error(format!("lang must be one of: {}", supported.join(", "))).into_response())
+    })?;
+
+    match state.storage.get_request(&id, request_uuid).await {
+        Ok(Some(request)) => {
+            info!(%id, %addr, %request_id, lang = lang.as_str(), "Exporting request as a snippet");
+            let code = to_snippet(lang, &request, &export_url(&request, &state.bin_path(&id), params.target.as_deref()));
+            let response = ([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], code).into_response();
+            Ok(add_cors_headers(response))
+        },
+        Ok(None) => Err(add_cors_headers(not_found_error("Request not found".to_string()).with_code("request_not_found").into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to fetch request");
+            Err(add_cors_headers(internal_error("Failed to fetch request".to_string()).into_response()))
+        }
+    }
+}
+
+/// Re-send a captured request to another URL, with any overrides applied, and return what
+/// it answered
+#[utoipa::path(
+    post,
+    path = "/bin/{id}/request/{rid}/replay",
+    tag = "requests",
+    params(("id" = String, Path, description = "Bin id"), ("rid" = String, Path, description = "Request id")),
+    request_body = ReplayRequest,
+    responses(
+        (status = 200, description = "The target's response", body = ReplayResponse),
+        (status = 400, description = "Invalid target URL or override"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 403, description = "Replay is disabled"),
+        (status = 404, description = "Request not found"),
+        (status = 502, description = "Target unreachable or its response too large"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn replay_request(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path((id, request_id)): Path<(String, String)>,
+    payload: Result<Json<ReplayRequest>, JsonRejection>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    if !state.replay.enabled {
+        let response = forbidden_error("Replay is disabled on this server".to_string()).with_code("replay_disabled").into_response();
+        return Err(add_cors_headers(response));
+    }
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    let request_uuid = validate_bin_id(&request_id).map_err(|e| add_cors_headers(e.into_response()))?;
+    let Json(replay) = payload
+        .map_err(|rejection| add_cors_headers(bad_request_error(rejection.body_text()).into_response()))?;
+
+    let request = match state.storage.get_request(&id, request_uuid).await {
+        Ok(Some(request)) => request,
+        Ok(None) => return Err(add_cors_headers(not_found_error("Request not found".to_string()).with_code("request_not_found").into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to fetch request");
+            return Err(add_cors_headers(internal_error("Failed to fetch request".to_string()).into_response()));
+        }
+    };
+
+    let url = replay_url(&request, &replay.target);
+    let started = std::time::Instant::now();
+    let outcome = state.replayer.replay(&request, &url, &replay.overrides).await;
+    // Only replays that reached the target are worth listing with the request
+    if !matches!(outcome, Err(ReplayError::InvalidTarget(_) | ReplayError::InvalidRequest(_))) {
+        let latency_ms = started.elapsed().as_millis() as u64;
+        let delivery = state.forwarder.outside_delivery(request_uuid, outcome.as_ref().map_err(ToString::to_string), Some(latency_ms));
+        if let Err(err) = state.storage.record_replay(&id, KIND_REPLAY, &url, &delivery).await {
+            error!(%id, %request_id, %err, "Failed to record replay");
+        }
+    }
+    match outcome {
+        Ok(upstream) => {
+            info!(%id, %addr, %request_id, %url, status = upstream.status, "Replayed request");
+            Ok(add_cors_headers(Json(upstream).into_response()))
+        },
+        Err(err) => {
+            warn!(%id, %addr, %request_id, %url, %err, "Replay failed");
+            let error = match err {
+                ReplayError::InvalidTarget(_) | ReplayError::InvalidRequest(_) => bad_request_error(err.to_string()),
+                ReplayError::Upstream(_) | ReplayError::ResponseTooLarge => {
+                    ApiError::new(StatusCode::BAD_GATEWAY, "replay_failed", err.to_string())
+                }
+            };
+            Err(add_cors_headers(error.into_response()))
+        }
+    }
+}
+
+/// Keep a request when the bin trims old requests, and keep its bin from expiring
+#[utoipa::path(
+    post,
+    path = "/bin/{id}/request/{rid}/pin",
+    tag = "requests",
+    params(("id" = String, Path, description = "Bin id"), ("rid" = String, Path, description = "Request id")),
+    responses(
+        (status = 200, description = "Request pinned"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Request not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn pin_request(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path((id, request_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    set_request_pinned(&state, addr, &id, &request_id, true).await
+}
+
+/// Let a pinned request be trimmed like any other again
+#[utoipa::path(
+    delete,
+    path = "/bin/{id}/request/{rid}/pin",
+    tag = "requests",
+    params(("id" = String, Path, description = "Bin id"), ("rid" = String, Path, description = "Request id")),
+    responses(
+        (status = 200, description = "Request unpinned"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Request not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn unpin_request(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path((id, request_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    set_request_pinned(&state, addr, &id, &request_id, false).await
+}
+
+async fn set_request_pinned(state: &AppState, addr: SocketAddr, id: &str, request_id: &str, pinned: bool) -> Result<Response, Response> {
+    validate_bin_id(id).map_err(|e| add_cors_headers(e.into_response()))?;
+    let request_uuid = validate_bin_id(request_id).map_err(|e| add_cors_headers(e.into_response()))?;
+
+    match state.storage.set_request_pinned(id, request_uuid, pinned).await {
+        Ok(true) => {
+            info!(%id, %addr, %request_id, pinned, "Request pin changed");
+            let message = if pinned { "Request pinned" } else { "Request unpinned" };
+            Ok(add_cors_headers(message.into_response()))
+        },
+        Ok(false) => Err(add_cors_headers(not_found_error("Request not found".to_string()).with_code("request_not_found").into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to pin request");
+            Err(add_cors_headers(internal_error("Failed to update request".to_string()).into_response()))
+        }
+    }
+}
+
+/// Answer 403 when forwarding is switched off with `[replay] enabled = false`
+fn check_forwarding_enabled(state: &AppState) -> Result<(), ApiError> {
+    if state.replay.enabled {
+        Ok(())
+    } else {
+        Err(forbidden_error("Forwarding is disabled on this server".to_string()).with_code("forwarding_disabled"))
+    }
+}
+
+fn validate_forward_url(url: &str) -> Result<(), ApiError> {
+    parse_target(url).map(|_| ()).map_err(|err| bad_request_error(err.to_string()))
+}
+
+/// A URL the bin will send to: [`validate_forward_url`], and not at an internal address
+/// outside `[outbound] allowed_networks`
+async fn validate_target(state: &AppState, url: &str) -> Result<(), ApiError> {
+    let target = parse_target(url).map_err(|err| bad_request_error(err.to_string()))?;
+    state.outbound.check(&target).await.map_err(|err| bad_request_error(err).with_code("target_not_allowed"))
+}
+
+/// Mirror every request captured by a bin to another URL
+#[utoipa::path(
+    post,
+    path = "/bin/{id}/forwards",
+    tag = "forwards",
+    params(("id" = String, Path, description = "Bin id")),
+    request_body = CreateForward,
+    responses(
+        (status = 201, description = "Forward created", body = Forward),
+        (status = 400, description = "Invalid URL"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 403, description = "Forwarding is disabled"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn create_forward(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    payload: Result<Json<CreateForward>, JsonRejection>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    check_forwarding_enabled(&state).map_err(|e| add_cors_headers(e.into_response()))?;
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let Json(create) = payload
+        .map_err(|rejection| add_cors_headers(bad_request_error(rejection.body_text()).into_response()))?;
+    validate_target(&state, &create.url).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let forward = Forward {
+        forward_id: Uuid::new_v4().to_string(),
+        url: create.url,
+        enabled: create.enabled,
+        created_at: Utc::now().to_rfc3339(),
+        last_delivery: None,
+    };
+    let result = state.storage.create_forward(&id, &forward).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, forward_id = %forward.forward_id, url = %forward.url, "Forward created");
+            Ok(add_cors_headers((StatusCode::CREATED, Json(forward)).into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to create forward");
+            Err(add_cors_headers(internal_error("Failed to create forward".to_string()).into_response()))
+        }
+    }
+}
+
+/// A bin's forwards with the outcome of their latest delivery
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/forwards",
+    tag = "forwards",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "Forwards in creation order", body = [Forward]),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn list_forwards(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    match state.storage.list_forwards(&id).await {
+        Ok(forwards) => Ok(add_cors_headers(Json(forwards).into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to list forwards");
+            Err(add_cors_headers(internal_error("Failed to list forwards".to_string()).into_response()))
+        }
+    }
+}
+
+/// Change a forward's URL or switch it on or off
+#[utoipa::path(
+    patch,
+    path = "/bin/{id}/forwards/{fid}",
+    tag = "forwards",
+    params(("id" = String, Path, description = "Bin id"), ("fid" = String, Path, description = "Forward id")),
+    request_body = ForwardUpdate,
+    responses(
+        (status = 200, description = "Updated forward", body = Forward),
+        (status = 400, description = "Invalid URL"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 403, description = "Forwarding is disabled"),
+        (status = 404, description = "Forward not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn update_forward(
+    State(state): State<AppState>,
+    Path((id, forward_id)): Path<(String, String)>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    payload: Result<Json<ForwardUpdate>, JsonRejection>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    check_forwarding_enabled(&state).map_err(|e| add_cors_headers(e.into_response()))?;
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    validate_bin_id(&forward_id).map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let Json(update) = payload
+        .map_err(|rejection| add_cors_headers(bad_request_error(rejection.body_text()).into_response()))?;
+    if let Some(url) = &update.url {
+        validate_target(&state, url).await.map_err(|e| add_cors_headers(e.into_response()))?;
+    }
+
+    let updated = match state.storage.update_forward(&id, &forward_id, &update).await {
+        Ok(true) => state.storage.get_forward(&id, &forward_id).await,
+        Ok(false) => Ok(None),
+        Err(err) => Err(err),
+    };
+    state.bin_cache.invalidate_settings(&id);
+    match updated {
+        Ok(Some(forward)) => {
+            info!(%id, %addr, %forward_id, url = %forward.url, enabled = forward.enabled, "Forward updated");
+            Ok(add_cors_headers(Json(forward).into_response()))
+        },
+        Ok(None) => Err(add_cors_headers(not_found_error("Forward not found".to_string()).into_response())),
+        Err(err) => {
+            error!(%id, %addr, %forward_id, %err, "Failed to update forward");
+            Err(add_cors_headers(internal_error("Failed to update forward".to_string()).into_response()))
+        }
+    }
+}
+
+/// Every delivery of a forward with its attempts and current status, newest first
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/forwards/{fid}/deliveries",
+    tag = "forwards",
+    params(("id" = String, Path, description = "Bin id"), ("fid" = String, Path, description = "Forward id")),
+    responses(
+        (status = 200, description = "Deliveries, newest first", body = [ForwardDelivery]),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Forward not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn list_forward_deliveries(
+    State(state): State<AppState>,
+    Path((id, forward_id)): Path<(String, String)>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    validate_bin_id(&forward_id).map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let deliveries = match state.storage.get_forward(&id, &forward_id).await {
+        Ok(Some(_)) => state.storage.list_deliveries(&forward_id).await,
+        Ok(None) => return Err(add_cors_headers(not_found_error("Forward not found".to_string()).into_response())),
+        Err(err) => Err(err),
+    };
+    match deliveries {
+        Ok(deliveries) => Ok(add_cors_headers(Json(deliveries).into_response())),
+        Err(err) => {
+            error!(%id, %addr, %forward_id, %err, "Failed to list forward deliveries");
+            Err(add_cors_headers(internal_error("Failed to list forward deliveries".to_string()).into_response()))
+        }
+    }
+}
+
+/// Every time a captured request was forwarded or replayed, with what the target answered
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/request/{rid}/deliveries",
+    tag = "requests",
+    params(("id" = String, Path, description = "Bin id"), ("rid" = String, Path, description = "Request id")),
+    responses(
+        (status = 200, description = "Forward deliveries in the order the forwards were created, then replays oldest first", body = [RequestDelivery]),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn list_request_deliveries(
+    State(state): State<AppState>,
+    Path((id, request_id)): Path<(String, String)>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    let request_uuid = validate_bin_id(&request_id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    // Deliveries are kept after the request is trimmed, so its absence isn't an error
+    match state.storage.request_deliveries(&id, request_uuid).await {
+        Ok(deliveries) => Ok(add_cors_headers(Json(deliveries).into_response())),
+        Err(err) => {
+            error!(%id, %addr, %request_id, %err, "Failed to list request deliveries");
+            Err(add_cors_headers(internal_error("Failed to list request deliveries".to_string()).into_response()))
+        }
+    }
+}
+
+/// Record that a captured request was delivered somewhere by a client, such as
+/// `rustbin tunnel` passing it on to a local server, with what that server answered
+#[utoipa::path(
+    post,
+    path = "/bin/{id}/request/{rid}/deliveries",
+    tag = "requests",
+    params(("id" = String, Path, description = "Bin id"), ("rid" = String, Path, description = "Request id")),
+    request_body = ReportedDelivery,
+    responses(
+        (status = 201, description = "Delivery recorded", body = RequestDelivery),
+        (status = 400, description = "Invalid URL or body"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Request not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn report_request_delivery(
+    State(state): State<AppState>,
+    Path((id, request_id)): Path<(String, String)>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    payload: Result<Json<ReportedDelivery>, JsonRejection>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    let request_uuid = validate_bin_id(&request_id).map_err(|e| add_cors_headers(e.into_response()))?;
+    let Json(report) = payload
+        .map_err(|rejection| add_cors_headers(bad_request_error(rejection.body_text()).into_response()))?;
+    validate_forward_url(&report.url).map_err(|e| add_cors_headers(e.into_response()))?;
+
+    match state.storage.get_request(&id, request_uuid).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(add_cors_headers(not_found_error("Request not found".to_string()).with_code("request_not_found").into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to fetch request");
+            return Err(add_cors_headers(internal_error("Failed to fetch request".to_string()).into_response()));
+        }
+    }
+
+    let outcome = report.response.as_ref().ok_or_else(|| report.error.clone().unwrap_or_else(|| "no response".to_string()));
+    let mut delivery = state.forwarder.outside_delivery(request_uuid, outcome, report.latency_ms);
+    match state.storage.record_replay(&id, KIND_TUNNEL, &report.url, &delivery).await {
+        Ok(delivery_id) => {
+            info!(%id, %addr, %request_id, url = %report.url, status = ?delivery.status_code, "Delivery reported");
+            delivery.delivery_id = delivery_id;
+            let recorded = RequestDelivery { kind: KIND_TUNNEL.to_string(), forward_id: None, url: report.url, delivery };
+            Ok(add_cors_headers((StatusCode::CREATED, Json(recorded)).into_response()))
+        }
+        Err(err) => {
+            error!(%id, %addr, %request_id, %err, "Failed to record reported delivery");
+            Err(add_cors_headers(internal_error("Failed to record delivery".to_string()).into_response()))
+        }
+    }
+}
+
+/// Stop mirroring requests to a forward and drop its delivery history
+#[utoipa::path(
+    delete,
+    path = "/bin/{id}/forwards/{fid}",
+    tag = "forwards",
+    params(("id" = String, Path, description = "Bin id"), ("fid" = String, Path, description = "Forward id")),
+    responses(
+        (status = 200, description = "Forward deleted"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Forward not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn delete_forward(
+    State(state): State<AppState>,
+    Path((id, forward_id)): Path<(String, String)>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    validate_bin_id(&forward_id).map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let result = state.storage.delete_forward(&id, &forward_id).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(true) => {
+            info!(%id, %addr, %forward_id, "Forward deleted");
+            Ok(add_cors_headers("Forward deleted".to_string().into_response()))
+        },
+        Ok(false) => Err(add_cors_headers(not_found_error("Forward not found".to_string()).into_response())),
+        Err(err) => {
+            error!(%id, %addr, %forward_id, %err, "Failed to delete forward");
+            Err(add_cors_headers(internal_error("Failed to delete forward".to_string()).into_response()))
+        }
+    }
+}
+
+/// Register a URL that receives the bin's events, signed with the callback's secret
+#[utoipa::path(
+    post,
+    path = "/bin/{id}/callbacks",
+    tag = "callbacks",
+    params(("id" = String, Path, description = "Bin id")),
+    request_body = CreateCallback,
+    responses(
+        (status = 201, description = "Callback created; the only response that includes the secret", body = CreatedCallback),
+        (status = 400, description = "Invalid URL or unknown event"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn create_callback(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    payload: Result<Json<CreateCallback>, JsonRejection>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let Json(create) = payload
+        .map_err(|rejection| add_cors_headers(bad_request_error(rejection.body_text()).into_response()))?;
+    validate_target(&state, &create.url).await.map_err(|e| add_cors_headers(e.into_response()))?;
+    if let Some(event) = create.events.iter().find(|event| !is_callback_event(event)) {
+        let message = format!("Unknown event `{}`, expected request.captured, bin.expiring or bin.deleted", event);
+        return Err(add_cors_headers(bad_request_error(message).into_response()));
+    }
+    let secret = match create.secret {
+        Some(secret) if secret.is_empty() => {
+            return Err(add_cors_headers(bad_request_error("Callback secret cannot be empty".to_string()).into_response()));
+        }
+        Some(secret) => secret,
+        None => generate_token(),
+    };
+
+    let callback = Callback {
+        callback_id: Uuid::new_v4().to_string(),
+        url: create.url,
+        events: create.events,
+        secret,
+        created_at: Utc::now().to_rfc3339(),
+    };
+    let result = state.storage.create_callback(&id, &callback).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(()) => {
+            info!(%id, %addr, callback_id = %callback.callback_id, url = %callback.url, "Callback created");
+            let created = CreatedCallback {
+                callback_id: callback.callback_id,
+                url: callback.url,
+                events: callback.events,
+                secret: callback.secret,
+                created_at: callback.created_at,
+            };
+            Ok(add_cors_headers((StatusCode::CREATED, Json(created)).into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to create callback");
+            Err(add_cors_headers(internal_error("Failed to create callback".to_string()).into_response()))
+        }
+    }
+}
+
+/// A bin's callbacks, without their secrets
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/callbacks",
+    tag = "callbacks",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "Callbacks in creation order", body = [Callback]),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn list_callbacks(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    match state.storage.list_callbacks(&id).await {
+        Ok(callbacks) => Ok(add_cors_headers(Json(callbacks).into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to list callbacks");
+            Err(add_cors_headers(internal_error("Failed to list callbacks".to_string()).into_response()))
+        }
+    }
+}
+
+/// Stop sending the bin's events to a callback
+#[utoipa::path(
+    delete,
+    path = "/bin/{id}/callbacks/{cid}",
+    tag = "callbacks",
+    params(("id" = String, Path, description = "Bin id"), ("cid" = String, Path, description = "Callback id")),
+    responses(
+        (status = 200, description = "Callback deleted"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Callback not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn delete_callback(
+    State(state): State<AppState>,
+    Path((id, callback_id)): Path<(String, String)>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    validate_bin_id(&callback_id).map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let result = state.storage.delete_callback(&id, &callback_id).await;
+    state.bin_cache.invalidate_settings(&id);
+    match result {
+        Ok(true) => {
+            info!(%id, %addr, %callback_id, "Callback deleted");
+            Ok(add_cors_headers("Callback deleted".to_string().into_response()))
+        },
+        Ok(false) => Err(add_cors_headers(not_found_error("Callback not found".to_string()).into_response())),
+        Err(err) => {
+            error!(%id, %addr, %callback_id, %err, "Failed to delete callback");
+            Err(add_cors_headers(internal_error("Failed to delete callback".to_string()).into_response()))
+        }
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/delete/{id}",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "Bin deleted"),
+        (status = 401, description = "Missing or invalid API key or bin token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("api_key" = []), ("bin_token" = []))
+)]
+pub async fn delete_bin(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    let uuid = validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+
+    // The callbacks are deleted with the bin, right away or when it is purged
+    let callbacks = state.callbacks.load(&id).await;
+    let result = if state.cleanup.load().deleted_bin_retention_hours > 0 {
+        state.storage.soft_delete_bin(&uuid.to_string(), &Utc::now().to_rfc3339()).await
+    } else {
+        state.storage.delete_bin(&uuid.to_string()).await
+    };
+    state.bin_cache.remove(&id);
+    match result {
+        Ok(deleted) => {
+            if !deleted {
+                let response = not_found_error("Bin not found".to_string()).with_code("bin_not_found").into_response();
+                return Err(add_cors_headers(response));
+            }
+            info!(%id, %addr, "Bin deleted");
+            update_last_updated(&state, &id).await.ok();
+            broadcast(&state, &id, WsEvent::BinDeleted { expired: false });
+            state.callbacks.send_to(&callbacks, BinEvent::Deleted, &id, serde_json::json!({ "expired": false }));
+            state.hooks.on_bin_deleted(&id, false);
+            let response = "Bin deleted".to_string().into_response();
+            Ok(add_cors_headers(response))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "DB error");
+            let response = internal_error("Failed to delete bin".to_string()).into_response();
+            Err(add_cors_headers(response))     
+        }
+    }
+}
+
+/// Undo `DELETE /delete/{id}` while the bin is still within `deleted_bin_retention_hours`
+#[utoipa::path(
+    post,
+    path = "/bin/{id}/restore",
+    tag = "bins",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "Bin restored"),
+        (status = 401, description = "Missing or invalid API key or bin token"),
+        (status = 404, description = "No deleted bin with this id"),
+    ),
+    security((), ("api_key" = []), ("bin_token" = []))
+)]
+pub async fn restore_bin(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+
+    // Restoring counts as activity, so the bin doesn't expire straight away
+    let result = state.storage.restore_bin(&id, &Utc::now().to_rfc3339()).await;
+    state.bin_cache.remove(&id);
+    match result {
+        Ok(true) => {
+            info!(%id, %addr, "Bin restored");
+            Ok(add_cors_headers("Bin restored".to_string().into_response()))
+        },
+        Ok(false) => Err(add_cors_headers(not_found_error("No deleted bin with this id".to_string()).into_response())),
+        Err(err) => {
+            error!(%id, %addr, %err, "Failed to restore bin");
+            Err(add_cors_headers(internal_error("Failed to restore bin".to_string()).into_response()))
+        }
+    }
+}
+
+/// Delete one request of the bin
+#[utoipa::path(
+    delete,
+    path = "/bin/{id}/request/{rid}",
+    tag = "requests",
+    params(("id" = String, Path, description = "Bin id"), ("rid" = String, Path, description = "Request id")),
+    responses(
+        (status = 200, description = "Request deleted"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found, or the request isn't in it"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn delete_bin_request(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path((id, request_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    let uuid = validate_bin_id(&request_id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    // Request ids are unique across bins, so once the bin has it, deleting by id is safe
+    match state.storage.get_request(&id, uuid).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(add_cors_headers(not_found_error("Request not found".to_string()).with_code("request_not_found").into_response())),
+        Err(err) => {
+            error!(%id, %request_id, %addr, %err, "Failed to load request");
+            return Err(add_cors_headers(internal_error("Failed to delete request".to_string()).into_response()));
+        }
+    }
+
+    match state.storage.delete_request(uuid).await {
+        Ok(Some(_)) => {
+            info!(%id, %request_id, %addr, "Request deleted");
+            update_last_updated(&state, &id).await.ok();
+            broadcast(&state, &id, WsEvent::RequestDeleted { request_id: uuid });
+            Ok(add_cors_headers("Request deleted".to_string().into_response()))
+        },
+        Ok(None) => Err(add_cors_headers(not_found_error("Request not found".to_string()).with_code("request_not_found").into_response())),
+        Err(err) => {
+            error!(%id, %request_id, %addr, %err, "Failed to delete request");
+            Err(add_cors_headers(internal_error("Failed to delete request".to_string()).into_response()))
+        }
+    }
+}
+
+/// Deprecated: use `DELETE /bin/{id}/request/{rid}`, which checks the request belongs to
+/// the bin. Answers carry `Deprecation` and a `Link` to the scoped route.
+#[utoipa::path(
+    delete,
+    path = "/request/{id}",
+    tag = "requests",
+    params(("id" = String, Path, description = "Request id")),
+    responses(
+        (status = 200, description = "Request deleted"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Request not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn delete_request(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    let uuid = validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+
+    match state.storage.delete_request(uuid).await {
+        Ok(bin_id) => {
+            let Some(bin_id) = bin_id else {
+                let response = not_found_error("Request not found".to_string()).with_code("request_not_found").into_response();
+                return Err(add_cors_headers(response));
+            };
+            warn!(%id, %addr, %bin_id, "Request deleted through deprecated DELETE /request/:id");
+            update_last_updated(&state, &bin_id).await.ok();
+            broadcast(&state, &bin_id, WsEvent::RequestDeleted { request_id: uuid });
+            let successor = format!("<{}/request/{}>; rel=\"successor-version\"", state.bin_path(&bin_id), uuid);
+            let response = (
+                [(HeaderName::from_static("deprecation"), "true".to_string()), (header::LINK, successor)],
+                "Request deleted",
+            )
+                .into_response();
+            Ok(add_cors_headers(response))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "DB error");
+            let response = internal_error("Failed to delete request".to_string()).into_response();
+            Err(add_cors_headers(response))     
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/ping",
+    tag = "health",
+    params(PingQuery),
+    responses((status = 200, description = "Service is up", body = PingResponse))
+)]
+pub async fn ping(Query(query): Query<PingQuery>) -> impl IntoResponse {
+    let message = query.message.unwrap_or_else(|| "pong".to_string());
+
+    let response = Json(PingResponse {
+        ok: true,
+        message,
+    }).into_response();
+    
+    add_cors_headers(response)
+}
+
+/// Liveness probe; never touches the database
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "health",
+    responses((status = 200, description = "Process is up", body = HealthResponse))
+)]
+pub async fn healthz() -> impl IntoResponse {
+    add_cors_headers(Json(HealthResponse { status: "ok".to_string() }).into_response())
+}
+
+/// Readiness probe: checks the database, the capture writer and the cleanup task
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "health",
+    responses(
+        (status = 200, description = "Ready to serve traffic", body = ReadinessResponse),
+        (status = 503, description = "A dependency is down", body = ReadinessResponse),
+    )
+)]
+pub async fn readyz(State(state): State<AppState>) -> impl IntoResponse {
+    let mut database = match state.storage.ping().await {
+        Ok(()) => check_result(CHECK_OK, None),
+        Err(err) => {
+            error!(%err, "Readiness check failed to reach the database");
+            check_result(CHECK_DOWN, Some(err.to_string()))
+        }
+    };
+    if let Some(pool) = state.storage.pool_status() {
+        database.details.insert("pool_size".to_string(), pool.size.into());
+        database.details.insert("pool_idle".to_string(), pool.idle.into());
+        database.details.insert("pool_max_connections".to_string(), pool.max_connections.into());
+    }
+
+    let writer_queue = match &state.capture_queue {
+        None => check_result(CHECK_DISABLED, None),
+        Some(queue) => {
+            let mut result = if queue.is_alive() {
+                check_result(CHECK_OK, None)
+            } else {
+                check_result(CHECK_DOWN, Some("capture writer has stopped".to_string()))
+            };
+            let (depth, capacity) = queue.depth();
+            result.details.insert("depth".to_string(), depth as u64);
+            result.details.insert("capacity".to_string(), capacity as u64);
+            result
+        }
+    };
+
+    let cleanup_task = match state.cleanup_heartbeat.status() {
+        TaskStatus::NotStarted => check_result(CHECK_DISABLED, None),
+        TaskStatus::Alive => check_result(CHECK_OK, None),
+        TaskStatus::Stalled => check_result(CHECK_DOWN, Some("cleanup task has not run recently".to_string())),
+    };
+
+    let checks = ReadinessChecks { database, writer_queue, cleanup_task };
+    let ready = [&checks.database, &checks.writer_queue, &checks.cleanup_task]
+        .iter()
+        .all(|check| check.status != CHECK_DOWN);
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    add_cors_headers((status, Json(ReadinessResponse { ready, checks })).into_response())
+}
+
+const CHECK_OK: &str = "ok";
+const CHECK_DOWN: &str = "down";
+const CHECK_DISABLED: &str = "disabled";
+
+fn check_result(status: &str, error: Option<String>) -> CheckResult {
+    CheckResult { status: status.to_string(), error, details: Default::default() }
+}
+
+/// Remove a bin's requests: all of them, or with `older_than` and `keep_last` only the
+/// old unpinned ones
+#[utoipa::path(
+    delete,
+    path = "/bin/{id}/clear",
+    tag = "requests",
+    params(("id" = String, Path, description = "Bin id"), ClearQuery),
+    responses(
+        (status = 200, description = "Requests removed from the bin"),
+        (status = 400, description = "Invalid `older_than` or `keep_last`"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn clear_bin_requests(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(id): Path<String>,
+    Query(params): Query<ClearQuery>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    let _uuid = validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    
+    // Check if bin exists
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    let before = params
+        .older_than
+        .as_deref()
+        .map(|raw| parse_age_param("older_than", raw).map(|age| Utc::now().checked_sub_signed(age).unwrap_or(DateTime::<Utc>::MIN_UTC).to_rfc3339()))
+        .transpose()
+        .map_err(|e| add_cors_headers(e.into_response()))?;
+    if params.keep_last.is_some_and(|keep| keep < 0) {
+        return Err(add_cors_headers(bad_request_error("keep_last must not be negative".to_string()).into_response()));
+    }
+
+    let cleared = match (&before, params.keep_last) {
+        (None, None) => state.storage.clear_bin(&id).await,
+        (before, keep_last) => state.storage.prune_bin(&id, before.as_deref(), keep_last).await,
+    };
+    match cleared {
+        Ok(deleted_count) => {
+            info!(%id, %addr, deleted_count, older_than = ?params.older_than, keep_last = ?params.keep_last, "Cleared requests from bin");
+            update_last_updated(&state, &id).await.ok();
+            broadcast(&state, &id, WsEvent::RequestsCleared { count: deleted_count });
+            
+            let response = format!("Cleared {} requests from bin", deleted_count).into_response();
+            Ok(add_cors_headers(response))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "DB error while clearing bin requests");
+            let response = internal_error("Failed to clear bin requests".to_string()).into_response();
+            Err(add_cors_headers(response))     
+        }
+    }
+}
+
+/// An age like `90s`, `15m`, `24h` or `7d`
+fn parse_age_param(name: &str, raw: &str) -> Result<Duration, ApiError> {
+    let invalid = || bad_request_error(format!("Invalid `{}`, expected a number followed by s, m, h or d, e.g. 24h", name));
+    let split = raw.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let amount: i64 = raw[..split].parse().map_err(|_| invalid())?;
+    let age = match &raw[split..] {
+        "s" => Duration::try_seconds(amount),
+        "m" => Duration::try_minutes(amount),
+        "h" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        _ => None,
+    };
+    age.ok_or_else(invalid)
+}
+
+fn parse_timestamp_param(name: &str, value: Option<&str>, default: DateTime<Utc>) -> Result<String, ApiError> {
+    match value {
+        Some(raw) => DateTime::parse_from_rfc3339(raw)
+            .map(|ts| ts.with_timezone(&Utc).to_rfc3339())
+            .map_err(|_| bad_request_error(format!("Invalid `{}` timestamp, expected RFC 3339", name))),
+        None => Ok(default.to_rfc3339()),
+    }
+}
+
+pub(crate) fn optional_timestamp_param(name: &str, value: Option<&str>) -> Result<Option<String>, ApiError> {
+    value
+        .map(|raw| parse_timestamp_param(name, Some(raw), Utc::now()))
+        .transpose()
+}
+
+/// Split a `name:value` header filter; a bare `name` only checks that the header is present.
+pub(crate) fn parse_header_filter(raw: &str) -> Result<(String, Option<String>), ApiError> {
+    let (name, value) = match raw.split_once(':') {
+        Some((name, value)) => (name, Some(value.trim().to_string())),
+        None => (raw, None),
+    };
+    let name = name.trim().to_ascii_lowercase();
+    if name.is_empty() {
+        return Err(bad_request_error("Invalid `header` filter, expected `name` or `name:value`".to_string()));
+    }
+    Ok((name, value))
+}
+
+/// Full-text search over the bodies captured by a single bin, newest first
+#[utoipa::path(
+    get,
+    path = "/bin/{id}/search",
+    tag = "requests",
+    params(("id" = String, Path, description = "Bin id"), BinSearchQuery),
+    responses(
+        (status = 200, description = "Matching requests, newest first", body = [LoggedRequest]),
+        (status = 400, description = "Empty query"),
+        (status = 401, description = "Private bin and missing or invalid token"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security((), ("bin_token" = []))
+)]
+pub async fn search_bin(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<BinSearchQuery>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
+    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
+
+    if params.q.trim().is_empty() {
+        return Err(add_cors_headers(bad_request_error("Search query `q` must not be empty".to_string()).into_response()));
+    }
+    let limit = params.limit.unwrap_or(DEFAULT_SEARCH_LIMIT).clamp(1, MAX_SEARCH_LIMIT);
+
+    let rows = state.storage.search_bin(&id, &params.q, limit).await;
+
+    match rows {
+        Ok(data) => {
+            info!(%id, %addr, query = %params.q, result_count = data.len(), "Bin search completed");
+            Ok(add_cors_headers(Json(data).into_response()))
+        },
+        Err(err) => {
+            error!(%id, %addr, %err, "Bin search failed");
+            Err(add_cors_headers(internal_error("Failed to search requests".to_string()).into_response()))
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/search",
+    tag = "admin",
+    params(AdminSearchQuery),
+    responses(
+        (status = 200, description = "Matching requests across all bins, newest first", body = [AdminSearchResult]),
+        (status = 400, description = "Empty query or invalid time range"),
+        (status = 401, description = "Missing or invalid admin token or API key"),
+        (status = 403, description = "Admin API disabled"),
+    ),
+    security(("admin_token" = []))
+)]
+pub async fn admin_search(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<AdminSearchQuery>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    if params.q.trim().is_empty() {
+        return Err(add_cors_headers(bad_request_error("Search query `q` must not be empty".to_string()).into_response()));
+    }
+
+    let now = Utc::now();
+    let since = parse_timestamp_param("since", params.since.as_deref(), now - Duration::hours(DEFAULT_SEARCH_WINDOW_HOURS))
+        .map_err(|e| add_cors_headers(e.into_response()))?;
+    let until = parse_timestamp_param("until", params.until.as_deref(), now)
+        .map_err(|e| add_cors_headers(e.into_response()))?;
+    let limit = params.limit.unwrap_or(DEFAULT_SEARCH_LIMIT).clamp(1, MAX_SEARCH_LIMIT);
+
+    let rows = state.storage.search_all(&params.q, &since, &until, limit).await;
 
     match rows {
         Ok(data) => {
-            info!(%id, %addr, request_count = data.len(), "Successfully fetched bin requests");
-            let response = Json(data).into_response();
-            Ok(add_cors_headers(response))
+            info!(%addr, query = %params.q, result_count = data.len(), "Admin search completed");
+            Ok(add_cors_headers(Json(data).into_response()))
         },
         Err(err) => {
-            error!(%id, %addr, %err, "Failed to fetch logged requests");
-            let response = (StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch logged requests").into_response();
-            Err(add_cors_headers(response))
+            error!(%addr, %err, "Admin search failed");
+            Err(add_cors_headers(internal_error("Failed to search requests".to_string()).into_response()))
         }
     }
 }
 
-pub async fn delete_bin(
+/// The bins created with the `X-Creator-Token` sent, most recently active first, so a
+/// client that lost a bin's id can find it again
+#[utoipa::path(
+    get,
+    path = "/my/bins",
+    tag = "bins",
+    responses(
+        (status = 200, description = "Live bins created with the token", body = [CreatorBinSummary]),
+        (status = 401, description = "Missing creator token"),
+    ),
+    security(("creator_token" = []))
+)]
+pub async fn list_creator_bins(
     State(state): State<AppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    Path(id): Path<String>,
+    headers: HeaderMap,
 ) -> Result<impl IntoResponse, impl IntoResponse> {
-    let uuid = validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
-
-    let result = query("DELETE FROM bins WHERE id = ?")
-        .bind(uuid.to_string())
-        .execute(&state.db)
-        .await;
+    let Some(creator_token) = creator_token(&headers).map_err(|e| add_cors_headers(e.into_response()))? else {
+        return Err(add_cors_headers(unauthorized_error("Missing X-Creator-Token".to_string()).into_response()));
+    };
 
-    match result {
-        Ok(res) => {
-            if res.rows_affected() == 0 {
-                let response = (StatusCode::NOT_FOUND, "Bin not found").into_response();
-                return Err(add_cors_headers(response));
-            }
-            info!(%id, %addr, "Bin deleted");
-            update_last_updated(&state, &id).await.ok();
-            let response = "Bin deleted".to_string().into_response();
-            Ok(add_cors_headers(response))
+    match state.storage.creator_bins(&creator_token).await {
+        Ok(bins) => {
+            let default_expiry = state.cleanup.load().bin_expiry_hours;
+            let bins: Vec<CreatorBinSummary> = bins
+                .into_iter()
+                .map(|bin| {
+                    let expires_at = bin
+                        .summary
+                        .last_updated
+                        .as_deref()
+                        .filter(|_| !bin.pinned)
+                        .and_then(|last_updated| DateTime::parse_from_rfc3339(last_updated).ok())
+                        .map(|last_updated| (last_updated + Duration::hours(bin.expiry_hours.unwrap_or(default_expiry))).with_timezone(&Utc).to_rfc3339());
+                    CreatorBinSummary {
+                        bin_id: bin.summary.bin_id,
+                        name: bin.summary.name,
+                        private: bin.summary.private,
+                        last_updated: bin.summary.last_updated,
+                        request_count: bin.summary.request_count,
+                        expires_at,
+                    }
+                })
+                .collect();
+            info!(%addr, bin_count = bins.len(), "Listed creator's bins");
+            Ok(add_cors_headers(Json(bins).into_response()))
         },
         Err(err) => {
-            error!(%id, %addr, %err, "DB error");
-            let response = (StatusCode::NOT_FOUND, "Bin not found or error deleting Bin").into_response();
-            Err(add_cors_headers(response))     
+            error!(%addr, %err, "Failed to list creator's bins");
+            Err(add_cors_headers(internal_error("Failed to list bins".to_string()).into_response()))
         }
     }
 }
 
-pub async fn delete_request(
+/// List every bin with its request count, most recently active first
+#[utoipa::path(
+    get,
+    path = "/admin/bins",
+    tag = "admin",
+    params(AdminBinsQuery),
+    responses(
+        (status = 200, description = "Bins, most recently active first. `X-Total-Count` holds the number of bins", body = [AdminBinSummary]),
+        (status = 401, description = "Missing or invalid admin token or API key"),
+        (status = 403, description = "Admin API disabled"),
+    ),
+    security(("admin_token" = []))
+)]
+pub async fn admin_list_bins(
     State(state): State<AppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    Path(id): Path<String>,
+    Query(params): Query<AdminBinsQuery>,
 ) -> Result<impl IntoResponse, impl IntoResponse> {
-    let uuid = validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
-
-    let result = query("DELETE FROM requests WHERE request_id = ?")
-        .bind(uuid)
-        .execute(&state.db)
-        .await;
+    let limit = params.limit.unwrap_or(DEFAULT_ADMIN_BINS_LIMIT).clamp(1, MAX_ADMIN_BINS_LIMIT);
+    let offset = params.offset.unwrap_or(0).max(0);
 
-    match result {
-        Ok(res) => {
-            if res.rows_affected() == 0 {
-                let response = (StatusCode::NOT_FOUND, "Request not found").into_response();
-                return Err(add_cors_headers(response));
-            }
-            info!(%id, %addr, "Request deleted");
-            update_last_updated(&state, &id).await.ok();
-            let response = "Request deleted".to_string().into_response();
+    match state.storage.list_bins(limit, offset).await {
+        Ok((data, total)) => {
+            info!(%addr, bin_count = data.len(), total, "Admin listed bins");
+            let mut response = Json(data).into_response();
+            response.headers_mut().insert(TOTAL_COUNT_HEADER, HeaderValue::from(total));
             Ok(add_cors_headers(response))
         },
         Err(err) => {
-            error!(%id, %addr, %err, "DB error");
-            let response = (StatusCode::NOT_FOUND, "Request not found or error deleting request").into_response();
-            Err(add_cors_headers(response))     
+            error!(%addr, %err, "Failed to list bins");
+            Err(add_cors_headers(internal_error("Failed to list bins".to_string()).into_response()))
         }
     }
 }
 
-pub async fn ping(Query(query): Query<PingQuery>) -> impl IntoResponse {
-    let message = query.message.unwrap_or_else(|| "pong".to_string());
-
-    let response = Json(PingResponse {
-        ok: true,
-        message,
-    }).into_response();
-    
-    add_cors_headers(response)
+/// Delete any bin, private ones included
+#[utoipa::path(
+    delete,
+    path = "/admin/bins/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Bin id")),
+    responses(
+        (status = 200, description = "Bin deleted"),
+        (status = 401, description = "Missing or invalid admin token or API key"),
+        (status = 403, description = "Admin API disabled"),
+        (status = 404, description = "Bin not found"),
+    ),
+    security(("admin_token" = []))
+)]
+pub async fn admin_delete_bin(
+    state: State<AppState>,
+    addr: ConnectInfo<SocketAddr>,
+    id: Path<String>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    delete_bin(state, addr, id).await
 }
 
-pub async fn clear_bin_requests(
+/// Instance wide counters for operators
+#[utoipa::path(
+    get,
+    path = "/admin/stats",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Instance statistics", body = AdminStats),
+        (status = 401, description = "Missing or invalid admin token or API key"),
+        (status = 403, description = "Admin API disabled"),
+    ),
+    security(("admin_token" = []))
+)]
+pub async fn admin_stats(
     State(state): State<AppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    Path(id): Path<String>,
 ) -> Result<impl IntoResponse, impl IntoResponse> {
-    let _uuid = validate_bin_id(&id).map_err(|e| add_cors_headers(e.into_response()))?;
-    
-    // Check if bin exists
-    check_bin_exists(&state, &id).await.map_err(|e| add_cors_headers(e.into_response()))?;
-
-    let result = query("DELETE FROM requests WHERE bin_id = ?")
-        .bind(&id)
-        .execute(&state.db)
-        .await;
+    let since = (Utc::now() - Duration::hours(24)).to_rfc3339();
+    match state.storage.stats(&since).await {
+        Ok(stats) => {
+            let watched_bin_count = state
+                .bin_channels
+                .iter()
+                .filter(|entry| entry.value().receiver_count() > 0)
+                .count();
+            info!(%addr, "Admin fetched stats");
+            Ok(add_cors_headers(Json(AdminStats {
+                bin_count: stats.bin_count,
+                private_bin_count: stats.private_bin_count,
+                request_count: stats.request_count,
+                requests_last_24h: stats.recent_request_count,
+                sampled_out_requests: stats.sampled_out_count,
+                watched_bin_count,
+                database_size_bytes: stats.size_bytes,
+                storage_full: state.storage_quota.is_full(),
+                evicted_requests: state.storage_quota.evicted(),
+            }).into_response()))
+        },
+        Err(err) => {
+            error!(%addr, %err, "Failed to compute stats");
+            Err(add_cors_headers(internal_error("Failed to compute stats".to_string()).into_response()))
+        }
+    }
+}
 
-    match result {
-        Ok(res) => {
-            let deleted_count = res.rows_affected();
-            info!(%id, %addr, deleted_count, "Cleared all requests from bin");
-            update_last_updated(&state, &id).await.ok();
-            
-            let response = format!("Cleared {} requests from bin", deleted_count).into_response();
-            Ok(add_cors_headers(response))
+/// Where the disk space goes: database pages, the bins holding the most bytes and the
+/// blob store, to find what is filling the disk before it is full
+#[utoipa::path(
+    get,
+    path = "/admin/storage",
+    tag = "admin",
+    params(AdminStorageQuery),
+    responses(
+        (status = 200, description = "Storage usage", body = AdminStorage),
+        (status = 401, description = "Missing or invalid admin token or API key"),
+        (status = 403, description = "Admin API disabled"),
+    ),
+    security(("admin_token" = []))
+)]
+pub async fn admin_storage(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<AdminStorageQuery>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    let top = params.top.unwrap_or(DEFAULT_STORAGE_TOP_BINS).clamp(1, MAX_ADMIN_BINS_LIMIT);
+    match state.storage.storage_report(top).await {
+        Ok(report) => {
+            info!(%addr, "Admin fetched storage usage");
+            Ok(add_cors_headers(Json(AdminStorage {
+                database_size_bytes: report.size_bytes,
+                page_size: report.page_size,
+                page_count: report.page_count,
+                free_page_count: report.free_page_count,
+                bins: report.bins,
+                blobs: report.blobs,
+            }).into_response()))
         },
         Err(err) => {
-            error!(%id, %addr, %err, "DB error while clearing bin requests");
-            let response = (StatusCode::INTERNAL_SERVER_ERROR, "Failed to clear bin requests").into_response();
-            Err(add_cors_headers(response))     
+            error!(%addr, %err, "Failed to measure storage usage");
+            Err(add_cors_headers(internal_error("Failed to measure storage usage".to_string()).into_response()))
         }
     }
 }
@@ -460,29 +5114,9 @@ mod tests {
             .await
             .unwrap();
 
-        sqlx::query("CREATE TABLE bins (id TEXT UNIQUE PRIMARY KEY, last_updated TEXT NOT NULL);")
-            .execute(&pool)
-            .await
-            .unwrap();
-
-        sqlx::query("CREATE TABLE requests (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            bin_id TEXT,
-            request_id TEXT UNIQUE NOT NULL,
-            method TEXT,
-            headers TEXT,
-            body TEXT,
-            timestamp TEXT
-        );")
-        .execute(&pool)
-        .await
-        .unwrap();
+        crate::storage::sqlite::MIGRATOR.run(&pool).await.unwrap();
 
-        AppState {
-            db: pool,
-            bin_channels: Arc::new(DashMap::new()),
-            limits: crate::config::LimitsConfig::default(),
-        }
+        AppState::from_pool(pool, &crate::config::RustbinConfig::default())
     }
 
     fn test_addr() -> SocketAddr {
@@ -507,7 +5141,7 @@ mod tests {
     async fn test_create_bin() {
         let state = setup_test_db().await;
         let addr = test_addr();
-        let result = create_bin(State(state), ConnectInfo(addr)).await;
+        let result = create_bin(State(state), ConnectInfo(addr), HeaderMap::new(), Bytes::new()).await;
         assert!(result.is_ok());
         let resp = result.ok().unwrap();
         let bin_response: BinResponse = response_json(resp).await;
@@ -520,7 +5154,7 @@ mod tests {
         let addr = test_addr();
         // Create a bin first
         let bin_id = {
-            let result = create_bin(State(state.clone()), ConnectInfo(addr)).await;
+            let result = create_bin(State(state.clone()), ConnectInfo(addr), HeaderMap::new(), Bytes::new()).await;
             assert!(result.is_ok());
             let resp = result.ok().unwrap();
             let bin_response: BinResponse = response_json(resp).await;
@@ -546,6 +5180,8 @@ mod tests {
             State(state.clone()),
             Path(bin_id.clone()),
             ConnectInfo(addr),
+            Query(InspectQuery::default()),
+            HeaderMap::new(),
         )
         .await;
         assert!(result.is_ok());
@@ -554,6 +5190,35 @@ mod tests {
         assert_eq!(requests.len(), 1);
         assert_eq!(requests[0].method, "POST");
         assert_eq!(requests[0].body.as_deref(), Some("test body"));
+        assert_eq!(requests[0].source_ip.as_deref(), Some("0.0.0.0"));
+        assert_eq!(requests[0].source_port, Some(8080));
+    }
+
+    #[tokio::test]
+    async fn test_source_address_is_stored_and_broadcast() {
+        let state = setup_test_db().await;
+        let addr = SocketAddr::from(([0, 0, 0, 0, 0, 0, 0, 1], 54321));
+        let result = create_bin(State(state.clone()), ConnectInfo(addr), HeaderMap::new(), Bytes::new()).await;
+        let bin_id = response_json::<BinResponse>(result.ok().unwrap()).await.bin_id;
+        let (sender, mut receiver) = tokio::sync::broadcast::channel(10);
+        state.bin_channels.insert(bin_id.clone(), sender);
+
+        let req = Request::builder().method(Method::POST).uri("/").body(Body::from("from v6")).unwrap();
+        assert!(log_request(State(state.clone()), Path(bin_id.clone()), ConnectInfo(addr), req).await.is_ok());
+
+        let message: serde_json::Value = serde_json::from_str(&receiver.try_recv().unwrap()).unwrap();
+        assert_eq!(message["data"]["source_ip"], "::1");
+        assert_eq!(message["data"]["source_port"], 54321);
+        let result = inspect_bin(
+            State(state.clone()),
+            Path(bin_id.clone()),
+            ConnectInfo(addr),
+            Query(InspectQuery::default()),
+            HeaderMap::new(),
+        )
+        .await;
+        let requests: Vec<LoggedRequest> = response_json(result.ok().unwrap()).await;
+        assert_eq!((requests[0].source_ip.as_deref(), requests[0].source_port), (Some("::1"), Some(54321)));
     }
 
     #[tokio::test]
@@ -561,7 +5226,7 @@ mod tests {
         let state = setup_test_db().await;
         let addr = test_addr();
         let bin_id = {
-            let result = create_bin(State(state.clone()), ConnectInfo(addr)).await;
+            let result = create_bin(State(state.clone()), ConnectInfo(addr), HeaderMap::new(), Bytes::new()).await;
             assert!(result.is_ok());
             let resp = result.ok().unwrap();
             let bin_response: BinResponse = response_json(resp).await;
@@ -593,7 +5258,7 @@ mod tests {
         let state = setup_test_db().await;
         let addr = test_addr();
         let bin_id = {
-            let result = create_bin(State(state.clone()), ConnectInfo(addr)).await;
+            let result = create_bin(State(state.clone()), ConnectInfo(addr), HeaderMap::new(), Bytes::new()).await;
             assert!(result.is_ok());
             let resp = result.ok().unwrap();
             let bin_response: BinResponse = response_json(resp).await;
@@ -619,6 +5284,8 @@ mod tests {
             State(state.clone()),
             Path(bin_id.clone()),
             ConnectInfo(addr),
+            Query(InspectQuery::default()),
+            HeaderMap::new(),
         )
         .await;
         assert!(result.is_ok());
@@ -668,7 +5335,7 @@ mod tests {
         
         // Create a bin first
         let bin_id = {
-            let result = create_bin(State(state.clone()), ConnectInfo(addr)).await;
+            let result = create_bin(State(state.clone()), ConnectInfo(addr), HeaderMap::new(), Bytes::new()).await;
             assert!(result.is_ok());
             let resp = result.ok().unwrap();
             let bin_response: BinResponse = response_json(resp).await;
@@ -696,6 +5363,32 @@ mod tests {
         assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
     }
 
+    #[tokio::test]
+    async fn test_log_request_stops_reading_once_body_is_too_large() {
+        let state = setup_test_db().await;
+        let addr = test_addr();
+        let resp = create_bin(State(state.clone()), ConnectInfo(addr), HeaderMap::new(), Bytes::new()).await.ok().unwrap();
+        let bin_id = response_json::<BinResponse>(resp).await.bin_id;
+
+        // No Content-Length, and a body that would never end if read to the end
+        let oversized = Bytes::from("x".repeat(MAX_BODY_SIZE + 1));
+        let chunks = futures::stream::once(async move { Ok::<_, std::io::Error>(oversized) })
+            .chain(futures::stream::pending());
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("/")
+            .body(Body::from_stream(chunks))
+            .unwrap();
+
+        let log_result = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            log_request(State(state.clone()), Path(bin_id), ConnectInfo(addr), req),
+        )
+        .await
+        .expect("the body should be rejected without reading it to the end");
+        assert_eq!(log_result.err().unwrap().into_response().status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
     #[tokio::test]
     async fn test_log_request_headers_size_limit() {
         let state = setup_test_db().await;
@@ -703,7 +5396,7 @@ mod tests {
         
         // Create a bin first
         let bin_id = {
-            let result = create_bin(State(state.clone()), ConnectInfo(addr)).await;
+            let result = create_bin(State(state.clone()), ConnectInfo(addr), HeaderMap::new(), Bytes::new()).await;
             assert!(result.is_ok());
             let resp = result.ok().unwrap();
             let bin_response: BinResponse = response_json(resp).await;
@@ -741,7 +5434,7 @@ mod tests {
         
         // Create a bin first
         let bin_id = {
-            let result = create_bin(State(state.clone()), ConnectInfo(addr)).await;
+            let result = create_bin(State(state.clone()), ConnectInfo(addr), HeaderMap::new(), Bytes::new()).await;
             assert!(result.is_ok());
             let resp = result.ok().unwrap();
             let bin_response: BinResponse = response_json(resp).await;
@@ -772,6 +5465,8 @@ mod tests {
             State(state.clone()),
             Path(bin_id.clone()),
             ConnectInfo(addr),
+            Query(InspectQuery::default()),
+            HeaderMap::new(),
         )
         .await;
         assert!(result.is_ok());
@@ -801,6 +5496,8 @@ mod tests {
             State(state.clone()),
             Path(fake_bin_id),
             ConnectInfo(addr),
+            Query(InspectQuery::default()),
+            HeaderMap::new(),
         )
         .await;
         
@@ -818,6 +5515,8 @@ mod tests {
             State(state.clone()),
             Path("not-a-uuid".to_string()),
             ConnectInfo(addr),
+            Query(InspectQuery::default()),
+            HeaderMap::new(),
         )
         .await;
         
@@ -858,7 +5557,7 @@ mod tests {
         
         // Create a bin first
         let bin_id = {
-            let result = create_bin(State(state.clone()), ConnectInfo(addr)).await;
+            let result = create_bin(State(state.clone()), ConnectInfo(addr), HeaderMap::new(), Bytes::new()).await;
             assert!(result.is_ok());
             let resp = result.ok().unwrap();
             let bin_response: BinResponse = response_json(resp).await;
@@ -907,7 +5606,7 @@ mod tests {
         
         // Create a bin first
         let bin_id = {
-            let result = create_bin(State(state.clone()), ConnectInfo(addr)).await;
+            let result = create_bin(State(state.clone()), ConnectInfo(addr), HeaderMap::new(), Bytes::new()).await;
             assert!(result.is_ok());
             let resp = result.ok().unwrap();
             let bin_response: BinResponse = response_json(resp).await;
@@ -937,6 +5636,8 @@ mod tests {
             State(state.clone()),
             Path(bin_id.clone()),
             ConnectInfo(addr),
+            Query(InspectQuery::default()),
+            HeaderMap::new(),
         )
         .await;
         assert!(result.is_ok());
@@ -947,9 +5648,9 @@ mod tests {
         assert_eq!(requests[0].method, "OPTIONS");
         
         // Verify CORS headers are captured
-        let headers: serde_json::Value = serde_json::from_str(&requests[0].headers).unwrap();
-        assert!(headers.get("access-control-request-method").is_some());
-        assert!(headers.get("access-control-request-headers").is_some());
+        let headers: Vec<(String, String)> = serde_json::from_str(&requests[0].headers).unwrap();
+        assert!(headers.iter().any(|(k, _)| k == "access-control-request-method"));
+        assert!(headers.iter().any(|(k, _)| k == "access-control-request-headers"));
     }
 
     #[tokio::test]
@@ -959,7 +5660,7 @@ mod tests {
         
         // Create a bin first
         let bin_id = {
-            let result = create_bin(State(state.clone()), ConnectInfo(addr)).await;
+            let result = create_bin(State(state.clone()), ConnectInfo(addr), HeaderMap::new(), Bytes::new()).await;
             assert!(result.is_ok());
             let resp = result.ok().unwrap();
             let bin_response: BinResponse = response_json(resp).await;
@@ -992,6 +5693,8 @@ mod tests {
             State(state.clone()),
             Path(bin_id.clone()),
             ConnectInfo(addr),
+            Query(InspectQuery::default()),
+            HeaderMap::new(),
         )
         .await;
         assert!(result.is_ok());
@@ -1001,8 +5704,8 @@ mod tests {
         assert_eq!(requests.len(), 5);
         
         // Requests should be ordered by ID (chronological order)
-        for i in 0..5 {
-            assert_eq!(requests[i].body.as_deref().unwrap(), format!("request_{}", i));
+        for (i, request) in requests.iter().enumerate() {
+            assert_eq!(request.body.as_deref().unwrap(), format!("request_{}", i));
         }
     }
 
@@ -1013,7 +5716,7 @@ mod tests {
         
         // Create a bin first
         let bin_id = {
-            let result = create_bin(State(state.clone()), ConnectInfo(addr)).await;
+            let result = create_bin(State(state.clone()), ConnectInfo(addr), HeaderMap::new(), Bytes::new()).await;
             assert!(result.is_ok());
             let resp = result.ok().unwrap();
             let bin_response: BinResponse = response_json(resp).await;
@@ -1044,6 +5747,8 @@ mod tests {
             State(state.clone()),
             Path(bin_id.clone()),
             ConnectInfo(addr),
+            Query(InspectQuery::default()),
+            HeaderMap::new(),
         )
         .await;
         assert!(result.is_ok());
@@ -1056,6 +5761,7 @@ mod tests {
             State(state.clone()),
             ConnectInfo(addr),
             Path(bin_id.clone()),
+            Query(ClearQuery::default()),
         )
         .await;
         assert!(result.is_ok());
@@ -1068,6 +5774,8 @@ mod tests {
             State(state.clone()),
             Path(bin_id.clone()),
             ConnectInfo(addr),
+            Query(InspectQuery::default()),
+            HeaderMap::new(),
         )
         .await;
         assert!(result.is_ok());
@@ -1087,6 +5795,7 @@ mod tests {
             State(state.clone()),
             ConnectInfo(addr),
             Path(fake_bin_id),
+            Query(ClearQuery::default()),
         )
         .await;
         
@@ -1102,7 +5811,7 @@ mod tests {
         
         // Create a bin first
         let bin_id = {
-            let result = create_bin(State(state.clone()), ConnectInfo(addr)).await;
+            let result = create_bin(State(state.clone()), ConnectInfo(addr), HeaderMap::new(), Bytes::new()).await;
             assert!(result.is_ok());
             let resp = result.ok().unwrap();
             let bin_response: BinResponse = response_json(resp).await;
@@ -1132,6 +5841,8 @@ mod tests {
             State(state.clone()),
             Path(bin_id.clone()),
             ConnectInfo(addr),
+            Query(InspectQuery::default()),
+            HeaderMap::new(),
         )
         .await;
         assert!(result.is_ok());
@@ -1154,6 +5865,8 @@ mod tests {
             State(state.clone()),
             Path(bin_id.clone()),
             ConnectInfo(addr),
+            Query(InspectQuery::default()),
+            HeaderMap::new(),
         )
         .await;
         assert!(result.is_ok());
@@ -1161,4 +5874,291 @@ mod tests {
         let requests: Vec<LoggedRequest> = response_json(resp).await;
         assert_eq!(requests.len(), 2);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_content_type_mismatch_detection() {
+        let state = setup_test_db().await;
+        let addr = test_addr();
+
+        // Create a bin first
+        let bin_id = {
+            let result = create_bin(State(state.clone()), ConnectInfo(addr), HeaderMap::new(), Bytes::new()).await;
+            assert!(result.is_ok());
+            let resp = result.ok().unwrap();
+            let bin_response: BinResponse = response_json(resp).await;
+            bin_response.bin_id
+        };
+
+        // A form-encoded body sent with a JSON content type
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("/")
+            .header("content-type", "application/json")
+            .body(Body::from("order_id=123&status=paid"))
+            .unwrap();
+        let log_result = log_request(State(state.clone()), Path(bin_id.clone()), ConnectInfo(addr), req).await;
+        assert!(log_result.is_ok());
+
+        // A JSON body that matches its content type
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("/")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"order_id": 123}"#))
+            .unwrap();
+        let log_result = log_request(State(state.clone()), Path(bin_id.clone()), ConnectInfo(addr), req).await;
+        assert!(log_result.is_ok());
+
+        let result = inspect_bin(State(state.clone()), Path(bin_id.clone()), ConnectInfo(addr), Query(InspectQuery::default()), HeaderMap::new()).await;
+        let requests: Vec<LoggedRequest> = response_json(result.ok().unwrap()).await;
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].body_format.as_deref(), Some("form"));
+        assert!(requests[0].content_type_mismatch);
+        assert_eq!(requests[1].body_format.as_deref(), Some("json"));
+        assert!(!requests[1].content_type_mismatch);
+    }
+
+    #[tokio::test]
+    async fn test_query_string_is_captured() {
+        let state = setup_test_db().await;
+        let addr = test_addr();
+
+        // Create a bin first
+        let bin_id = {
+            let result = create_bin(State(state.clone()), ConnectInfo(addr), HeaderMap::new(), Bytes::new()).await;
+            assert!(result.is_ok());
+            let resp = result.ok().unwrap();
+            let bin_response: BinResponse = response_json(resp).await;
+            bin_response.bin_id
+        };
+
+        let req = Request::builder()
+            .method(Method::GET)
+            .uri("/?event=push&tag=a&tag=b&name=hello%20world")
+            .body(Body::empty())
+            .unwrap();
+        let log_result = log_request(State(state.clone()), Path(bin_id.clone()), ConnectInfo(addr), req).await;
+        assert!(log_result.is_ok());
+
+        let result = inspect_bin(State(state.clone()), Path(bin_id.clone()), ConnectInfo(addr), Query(InspectQuery::default()), HeaderMap::new()).await;
+        let requests: Vec<LoggedRequest> = response_json(result.ok().unwrap()).await;
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].query_string.as_deref(), Some("event=push&tag=a&tag=b&name=hello%20world"));
+
+        let params: serde_json::Value = serde_json::from_str(requests[0].query_params.as_deref().unwrap()).unwrap();
+        assert_eq!(params["event"], "push");
+        assert_eq!(params["tag"], serde_json::json!(["a", "b"]));
+        assert_eq!(params["name"], "hello world");
+    }
+
+    #[tokio::test]
+    async fn test_binary_body_round_trip() {
+        let state = setup_test_db().await;
+        let addr = test_addr();
+
+        // Create a bin first
+        let bin_id = {
+            let result = create_bin(State(state.clone()), ConnectInfo(addr), HeaderMap::new(), Bytes::new()).await;
+            assert!(result.is_ok());
+            let resp = result.ok().unwrap();
+            let bin_response: BinResponse = response_json(resp).await;
+            bin_response.bin_id
+        };
+
+        // Not valid UTF-8, e.g. a protobuf payload
+        let payload: Vec<u8> = vec![0x08, 0x96, 0x01, 0xff, 0xfe, 0x00, 0x12];
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("/")
+            .header("content-type", "application/x-protobuf")
+            .body(Body::from(payload.clone()))
+            .unwrap();
+        let log_result = log_request(State(state.clone()), Path(bin_id.clone()), ConnectInfo(addr), req).await;
+        assert!(log_result.is_ok());
+
+        let result = inspect_bin(State(state.clone()), Path(bin_id.clone()), ConnectInfo(addr), Query(InspectQuery::default()), HeaderMap::new()).await;
+        let requests: Vec<LoggedRequest> = response_json(result.ok().unwrap()).await;
+        assert_eq!(requests[0].body_encoding.as_deref(), Some("base64"));
+
+        let result = get_request_body(
+            State(state.clone()),
+            ConnectInfo(addr),
+            Path((bin_id.clone(), requests[0].request_id.to_string())),
+        )
+        .await;
+        let response = result.ok().unwrap().into_response();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/x-protobuf");
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(bytes.to_vec(), payload);
+    }
+
+    #[tokio::test]
+    async fn test_gzip_body_is_decompressed() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let state = setup_test_db().await;
+        let mut limits = crate::config::LimitsConfig::clone(&state.limits.load());
+        limits.max_decompressed_body_size = 1024;
+        state.limits.store(Arc::new(limits));
+        let addr = test_addr();
+
+        // Create a bin first
+        let bin_id = {
+            let result = create_bin(State(state.clone()), ConnectInfo(addr), HeaderMap::new(), Bytes::new()).await;
+            assert!(result.is_ok());
+            let resp = result.ok().unwrap();
+            let bin_response: BinResponse = response_json(resp).await;
+            bin_response.bin_id
+        };
+
+        let gzip = |data: &[u8]| {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap()
+        };
+
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("/")
+            .header("content-type", "application/json")
+            .header("content-encoding", "gzip")
+            .body(Body::from(gzip(br#"{"event": "push"}"#)))
+            .unwrap();
+        let log_result = log_request(State(state.clone()), Path(bin_id.clone()), ConnectInfo(addr), req).await;
+        assert!(log_result.is_ok());
+
+        let result = inspect_bin(State(state.clone()), Path(bin_id.clone()), ConnectInfo(addr), Query(InspectQuery::default()), HeaderMap::new()).await;
+        let requests: Vec<LoggedRequest> = response_json(result.ok().unwrap()).await;
+        assert_eq!(requests[0].body.as_deref(), Some(r#"{"event": "push"}"#));
+        assert_eq!(requests[0].content_encoding.as_deref(), Some("gzip"));
+        assert!(!requests[0].content_type_mismatch);
+
+        // A small compressed body that inflates past the limit is rejected
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("/")
+            .header("content-encoding", "gzip")
+            .body(Body::from(gzip(&[b'a'; 4096])))
+            .unwrap();
+        let log_result = log_request(State(state.clone()), Path(bin_id.clone()), ConnectInfo(addr), req).await;
+        let response = log_result.err().unwrap().into_response();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_inspect_pagination() {
+        let state = setup_test_db().await;
+        let addr = test_addr();
+
+        // Create a bin first
+        let bin_id = {
+            let result = create_bin(State(state.clone()), ConnectInfo(addr), HeaderMap::new(), Bytes::new()).await;
+            assert!(result.is_ok());
+            let resp = result.ok().unwrap();
+            let bin_response: BinResponse = response_json(resp).await;
+            bin_response.bin_id
+        };
+
+        for i in 0..5 {
+            let req = Request::builder()
+                .method(Method::POST)
+                .uri("/")
+                .body(Body::from(format!("request_{}", i)))
+                .unwrap();
+            let log_result = log_request(State(state.clone()), Path(bin_id.clone()), ConnectInfo(addr), req).await;
+            assert!(log_result.is_ok());
+        }
+
+        // limit/offset
+        let query = InspectQuery { limit: Some(2), offset: Some(1), after_id: None, ..Default::default() };
+        let result = inspect_bin(State(state.clone()), Path(bin_id.clone()), ConnectInfo(addr), Query(query), HeaderMap::new()).await;
+        let response = result.ok().unwrap().into_response();
+        assert_eq!(response.headers()[TOTAL_COUNT_HEADER], "5");
+        let requests: Vec<LoggedRequest> = response_json(response).await;
+        let bodies: Vec<_> = requests.iter().map(|r| r.body.clone().unwrap()).collect();
+        assert_eq!(bodies, vec!["request_1", "request_2"]);
+
+        // Cursor continues after the last request of the previous page
+        let query = InspectQuery { limit: Some(10), offset: None, after_id: Some(requests[1].request_id.to_string()), ..Default::default() };
+        let result = inspect_bin(State(state.clone()), Path(bin_id.clone()), ConnectInfo(addr), Query(query), HeaderMap::new()).await;
+        let requests: Vec<LoggedRequest> = response_json(result.ok().unwrap()).await;
+        let bodies: Vec<_> = requests.iter().map(|r| r.body.clone().unwrap()).collect();
+        assert_eq!(bodies, vec!["request_3", "request_4"]);
+
+        // Unknown cursor is rejected
+        let query = InspectQuery { limit: None, offset: None, after_id: Some(Uuid::new_v4().to_string()), ..Default::default() };
+        let result = inspect_bin(State(state.clone()), Path(bin_id.clone()), ConnectInfo(addr), Query(query), HeaderMap::new()).await;
+        assert_eq!(result.err().unwrap().into_response().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_inspect_filters() {
+        let state = setup_test_db().await;
+        let addr = test_addr();
+
+        // Create a bin first
+        let bin_id = {
+            let result = create_bin(State(state.clone()), ConnectInfo(addr), HeaderMap::new(), Bytes::new()).await;
+            assert!(result.is_ok());
+            let resp = result.ok().unwrap();
+            let bin_response: BinResponse = response_json(resp).await;
+            bin_response.bin_id
+        };
+
+        let requests = [
+            (Method::POST, "push"),
+            (Method::POST, "issues"),
+            (Method::GET, "push"),
+        ];
+        for (method, event) in requests {
+            let req = Request::builder()
+                .method(method)
+                .uri("/")
+                .header("X-GitHub-Event", event)
+                .body(Body::empty())
+                .unwrap();
+            let log_result = log_request(State(state.clone()), Path(bin_id.clone()), ConnectInfo(addr), req).await;
+            assert!(log_result.is_ok());
+        }
+
+        async fn inspect(state: &AppState, bin_id: &str, query: InspectQuery) -> (String, Vec<LoggedRequest>) {
+            let result = inspect_bin(State(state.clone()), Path(bin_id.to_string()), ConnectInfo(test_addr()), Query(query), HeaderMap::new()).await;
+            let response = result.ok().unwrap().into_response();
+            let total = response.headers()[TOTAL_COUNT_HEADER].to_str().unwrap().to_string();
+            (total, response_json(response).await)
+        }
+
+        let (total, requests) = inspect(&state, &bin_id, InspectQuery { method: Some("post".to_string()), ..Default::default() }).await;
+        assert_eq!(total, "2");
+        assert!(requests.iter().all(|r| r.method == "POST"));
+
+        let (_, requests) = inspect(&state, &bin_id, InspectQuery { header: Some("x-github-event:push".to_string()), ..Default::default() }).await;
+        assert_eq!(requests.len(), 2);
+
+        let (_, requests) = inspect(&state, &bin_id, InspectQuery {
+            method: Some("POST".to_string()),
+            header: Some("X-GitHub-Event: push".to_string()),
+            ..Default::default()
+        }).await;
+        assert_eq!(requests.len(), 1);
+
+        let (_, requests) = inspect(&state, &bin_id, InspectQuery { header: Some("x-missing".to_string()), ..Default::default() }).await;
+        assert!(requests.is_empty());
+
+        let (_, requests) = inspect(&state, &bin_id, InspectQuery { since: Some("2999-01-01T00:00:00Z".to_string()), ..Default::default() }).await;
+        assert!(requests.is_empty());
+
+        let (_, requests) = inspect(&state, &bin_id, InspectQuery { until: Some("2999-01-01T00:00:00Z".to_string()), ..Default::default() }).await;
+        assert_eq!(requests.len(), 3);
+
+        let result = inspect_bin(
+            State(state.clone()),
+            Path(bin_id.clone()),
+            ConnectInfo(addr),
+            Query(InspectQuery { since: Some("yesterday".to_string()), ..Default::default() }),
+            HeaderMap::new(),
+        ).await;
+        assert_eq!(result.err().unwrap().into_response().status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,68 @@
+//! Liveness tracking for background tasks, reported by `/readyz`.
+
+use chrono::Utc;
+use std::sync::atomic::{AtomicI64, Ordering};
+
+/// How a background task is doing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskStatus {
+    /// The task was never started, e.g. in tests or when embedding the router
+    NotStarted,
+    Alive,
+    /// No beat for longer than the task's grace period
+    Stalled,
+}
+
+/// Updated by a periodic task after every pass. Missing a few passes means the task
+/// died or is stuck.
+#[derive(Debug, Default)]
+pub struct Heartbeat {
+    /// Milliseconds without a beat before the task counts as stalled; 0 until started
+    grace_ms: AtomicI64,
+    last_beat_ms: AtomicI64,
+}
+
+impl Heartbeat {
+    /// Start expecting a beat every `interval_ms`
+    pub fn start(&self, interval_ms: u64) {
+        let grace = (interval_ms.saturating_mul(3)).max(60_000).min(i64::MAX as u64) as i64;
+        self.last_beat_ms.store(Utc::now().timestamp_millis(), Ordering::Relaxed);
+        self.grace_ms.store(grace, Ordering::Relaxed);
+    }
+
+    pub fn beat(&self) {
+        self.last_beat_ms.store(Utc::now().timestamp_millis(), Ordering::Relaxed);
+    }
+
+    pub fn status(&self) -> TaskStatus {
+        let grace = self.grace_ms.load(Ordering::Relaxed);
+        if grace == 0 {
+            return TaskStatus::NotStarted;
+        }
+        let silent_for = Utc::now().timestamp_millis() - self.last_beat_ms.load(Ordering::Relaxed);
+        if silent_for > grace {
+            TaskStatus::Stalled
+        } else {
+            TaskStatus::Alive
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_stalled_tasks() {
+        let heartbeat = Heartbeat::default();
+        assert_eq!(heartbeat.status(), TaskStatus::NotStarted);
+
+        heartbeat.start(1_000);
+        assert_eq!(heartbeat.status(), TaskStatus::Alive);
+
+        heartbeat.last_beat_ms.store(Utc::now().timestamp_millis() - 61_000, Ordering::Relaxed);
+        assert_eq!(heartbeat.status(), TaskStatus::Stalled);
+        heartbeat.beat();
+        assert_eq!(heartbeat.status(), TaskStatus::Alive);
+    }
+}
@@ -0,0 +1,25 @@
+//! Hooks for applications embedding rustbin, to attach their own behavior (metrics,
+//! syncing to another system, auditing) to bin and capture events without patching the
+//! handlers. Register them with [`AppState::with_hooks`](crate::state::AppState::with_hooks)
+//! before building the routes.
+
+use crate::models::LoggedRequest;
+
+/// Called inline by the handlers after each event has happened, so implementations should
+/// return quickly and spawn anything slow. Every method does nothing by default.
+pub trait Hooks: Send + Sync {
+    /// A bin was created through the API
+    fn on_bin_created(&self, _bin_id: &str) {}
+
+    /// A capture was stored, or queued for a background write with `[limits] async_writes`;
+    /// email bins' messages and imported requests included
+    fn on_request_logged(&self, _bin_id: &str, _request: &LoggedRequest) {}
+
+    /// A bin was deleted through the API, or `expired` for inactivity
+    fn on_bin_deleted(&self, _bin_id: &str, _expired: bool) {}
+}
+
+/// The hooks of a server that has none registered
+pub struct NoHooks;
+
+impl Hooks for NoHooks {}
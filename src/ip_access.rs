@@ -0,0 +1,85 @@
+//! Deciding which source addresses may capture into a bin.
+
+use ipnet::IpNet;
+use std::net::IpAddr;
+
+use crate::models::IpAccessRules;
+
+/// A bin's parsed allow and deny lists
+#[derive(Debug, Clone, Default)]
+pub struct IpMatcher {
+    allow: Vec<IpNet>,
+    deny: Vec<IpNet>,
+}
+
+impl IpMatcher {
+    /// Parse every entry, naming the first one that is neither an address nor a CIDR range
+    pub fn new(rules: &IpAccessRules) -> Result<Self, String> {
+        Ok(IpMatcher {
+            allow: rules.allow.iter().map(|entry| parse_network(entry)).collect::<Result<_, _>>()?,
+            deny: rules.deny.iter().map(|entry| parse_network(entry)).collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Denied sources are always turned away; with an allow list, so is everything else
+    pub fn permits(&self, ip: IpAddr) -> bool {
+        // IPv4 clients of a dual-stack listener show up as `::ffff:a.b.c.d`
+        let ip = ip.to_canonical();
+        if self.deny.iter().any(|network| network.contains(&ip)) {
+            return false;
+        }
+        self.allow.is_empty() || self.allow.iter().any(|network| network.contains(&ip))
+    }
+}
+
+/// An address (as a single host network) or a CIDR range
+pub fn parse_network(entry: &str) -> Result<IpNet, String> {
+    let entry = entry.trim();
+    entry
+        .parse::<IpNet>()
+        .or_else(|_| entry.parse::<IpAddr>().map(IpNet::from))
+        .map_err(|_| format!("`{}` is not an IP address or CIDR range", entry))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matcher(allow: &[&str], deny: &[&str]) -> IpMatcher {
+        IpMatcher::new(&IpAccessRules {
+            allow: allow.iter().map(|entry| entry.to_string()).collect(),
+            deny: deny.iter().map(|entry| entry.to_string()).collect(),
+            log_rejected: false,
+        })
+        .unwrap()
+    }
+
+    fn ip(addr: &str) -> IpAddr {
+        addr.parse().unwrap()
+    }
+
+    #[test]
+    fn empty_rules_permit_everyone() {
+        assert!(matcher(&[], &[]).permits(ip("203.0.113.7")));
+        assert!(matcher(&[], &[]).permits(ip("2001:db8::1")));
+    }
+
+    #[test]
+    fn allow_list_limits_sources_and_deny_wins() {
+        let matcher = matcher(&["10.0.0.0/8", "2001:db8::/32", "192.0.2.1"], &["10.0.0.13"]);
+        assert!(matcher.permits(ip("10.1.2.3")));
+        assert!(matcher.permits(ip("2001:db8::42")));
+        assert!(matcher.permits(ip("192.0.2.1")));
+        assert!(!matcher.permits(ip("192.0.2.2")));
+        assert!(!matcher.permits(ip("10.0.0.13")));
+        assert!(matcher.permits(ip("::ffff:10.9.9.9")));
+    }
+
+    #[test]
+    fn rejects_malformed_entries() {
+        assert!(parse_network(" 127.0.0.1 ").is_ok());
+        assert!(parse_network("10.0.0.0/33").is_err());
+        let rules = IpAccessRules { allow: vec!["localhost".to_string()], ..Default::default() };
+        assert_eq!(IpMatcher::new(&rules).unwrap_err(), "`localhost` is not an IP address or CIDR range");
+    }
+}
@@ -1,9 +1,39 @@
+pub mod bin_rate_limit;
+pub mod cache;
+pub mod capture;
+pub mod chaos;
+pub mod client_ip;
 pub mod config;
+pub mod diff;
+pub mod export;
+pub mod extractors;
+pub mod forwarding;
+pub mod graphql;
 pub mod handlers;
+pub mod ip_access;
+pub mod health;
+pub mod hooks;
 pub mod state;
+pub mod storage;
 pub mod models;
+pub mod notifications;
+pub mod openapi;
+pub mod outbound;
+pub mod plugins;
+pub mod rate_limit;
+pub mod redaction;
+pub mod redis;
+pub mod redis_rate_limit;
+pub mod replay;
 pub mod routes;
+pub mod rules;
+pub mod samples;
+pub mod scripts;
+pub mod signatures;
+pub mod smtp;
+pub mod tunnel;
 pub mod utils;
+pub mod webhooks;
 pub mod websocket;
 
 // Re-export commonly used items for convenience
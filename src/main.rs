@@ -1,22 +1,79 @@
+mod bin_rate_limit;
+mod cache;
+mod capture;
+mod chaos;
+mod client_ip;
 mod config;
+mod diff;
+mod export;
+mod extractors;
+mod forwarding;
+mod graphql;
 mod handlers;
+mod health;
+mod hooks;
+mod ip_access;
 mod models;
+mod notifications;
+mod openapi;
+mod outbound;
+mod plugins;
+mod rate_limit;
+mod redaction;
+mod redis;
+mod redis_rate_limit;
+mod replay;
 mod routes;
+mod rules;
+mod samples;
+mod scripts;
+mod signatures;
+mod smtp;
 mod state;
+mod storage;
 mod tasks;
+mod tunnel;
 mod utils;
+mod webhooks;
 mod websocket;
 
+use axum::middleware;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use tower_http::compression::CompressionLayer;
 use tower_http::trace::{TraceLayer, DefaultMakeSpan, DefaultOnResponse};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
-use tower_governor::{governor::GovernorConfigBuilder, GovernorLayer};
+use tracing_subscriber::{layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter};
 
-use config::RustbinConfig;
+use config::{RateLimitBackend, RustbinConfig};
+use rate_limit::RateLimits;
+
+/// `rustbin tunnel`: relay a bin's captures to a local server instead of serving
+async fn run_tunnel(args: &[String]) {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .init();
+    let options = match tunnel::TunnelOptions::parse(args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n{}", err, tunnel::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if let Err(err) = tunnel::run(options).await {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
 
 #[tokio::main]
 async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|command| command == "tunnel") {
+        run_tunnel(&args[1..]).await;
+        return;
+    }
+
     // Load configuration (creates default config file if it doesn't exist)
     const CONFIG_PATH: &str = "rustbin.toml";
     if let Err(err) = RustbinConfig::create_default_config_if_missing(CONFIG_PATH) {
@@ -24,49 +81,66 @@ async fn main() {
     }
     
     let config = RustbinConfig::from_file_or_default(CONFIG_PATH);
+
+    // Both rustls and reqwest are built with ring only; make it the process-wide provider
+    let _ = rustls::crypto::ring::default_provider().install_default();
     
-    // Initialize logging with config
+    // Initialize logging with config. RUST_LOG wins over the file, also on reload.
+    let env_filter = EnvFilter::try_from_default_env().ok();
+    let file_filter = env_filter.is_none();
+    let (filter, filter_handle) = reload::Layer::new(env_filter.unwrap_or_else(|| EnvFilter::new(&config.logging.filter)));
     tracing_subscriber::registry()
-        .with(EnvFilter::try_from_default_env()
-            .unwrap_or_else(|_| EnvFilter::new(&config.logging.filter)))
+        .with(filter)
         .with(tracing_subscriber::fmt::layer())
         .init();
 
     tracing::info!("Starting rustbin with configuration from {}", CONFIG_PATH);
 
-    let app_state = state::AppState::new(&config.database, &config.limits).await.expect("Failed to init DB");
-    tasks::cleanup::start_cleanup_task(
-        app_state.db.clone(), 
-        app_state.bin_channels.clone(),
-        &config.cleanup
-    ).await;
+    let app_state = state::AppState::new(&config).await.expect("Failed to init DB");
+    tasks::cleanup::start_cleanup_task(app_state.clone()).await;
+    tasks::reload::start_config_reload_task(CONFIG_PATH, app_state.clone(), file_filter.then_some(filter_handle)).await;
+    tasks::forwards::start_forward_retry_task(app_state.clone(), &config.forwarding).await;
+    tasks::maintenance::start_maintenance_task(app_state.clone(), &config.database).await;
 
-    let governor_conf = Arc::new(
-       GovernorConfigBuilder::default()
-           .per_second(config.rate_limiting.requests_per_second.into())
-           .burst_size(config.rate_limiting.burst_size.into())
-           .finish()
-           .unwrap(),
-   );
-    tasks::limit::start_rate_limit_cleanup(&governor_conf, &config.rate_limiting).await;
+    let rate_limits = Arc::new(
+        RateLimits::new(&config.rate_limiting, app_state.trusted_proxies.clone())
+            .unwrap_or_else(|err| panic!("Invalid [rate_limiting]: {}", err))
+            .with_base_path(&app_state.base_path),
+    );
+    if config.rate_limiting.backend == RateLimitBackend::Redis {
+        tracing::info!("Sharing rate limits through Redis");
+    }
+    tasks::limit::start_rate_limit_cleanup(&rate_limits, &config.rate_limiting).await;
+    tasks::limit::start_bin_rate_limit_cleanup(app_state.bin_limiter.clone(), &config.rate_limiting).await;
 
     let trace = TraceLayer::new_for_http()
         .make_span_with(DefaultMakeSpan::new().include_headers(true))
         .on_response(DefaultOnResponse::new().include_headers(true));
 
     // Create rate-limited routes (everything except WebSocket)
-    let rate_limited_routes = routes::bin::bin_routes(app_state.clone())
-        .merge(routes::health::health_routes())
-        .layer(GovernorLayer {
-            config: governor_conf,
-        });
+    let mut api_routes = routes::bin::bin_routes(app_state.clone())
+        .merge(routes::health::health_routes(app_state.clone()))
+        .merge(routes::docs::docs_routes(&app_state.base_path))
+        .merge(routes::graphql::graphql_routes(app_state.clone()))
+        .merge(routes::admin::admin_routes(app_state.clone()))
+        .merge(routes::ui::ui_routes(&app_state.base_path));
+    if config.server.compress_responses {
+        api_routes = api_routes.layer(CompressionLayer::new());
+    }
+    // Captures are merged after the compression layer so it never wraps them
+    let limited_routes = api_routes
+        .merge(routes::bin::capture_routes(app_state.clone()))
+        .layer(middleware::from_fn_with_state(app_state.clone(), routes::body_limit::limit_request_body));
+    // A route layer, so the limiter can tell creates and captures from the other routes
+    let rate_limited_routes = limited_routes
+        .route_layer(middleware::from_fn_with_state(rate_limits, rate_limit::limit_requests));
     
     // Create WebSocket routes without rate limiting
-    let websocket_routes = routes::bin::websocket_routes(app_state.clone());
+    let websocket_routes = routes::bin::websocket_routes(app_state.clone())
+        .merge(routes::graphql::graphql_websocket_routes(app_state.clone()));
     
     // Combine all routes
-    let app = rate_limited_routes
-        .merge(websocket_routes)
+    let app = routes::under_base_path(&app_state.base_path, rate_limited_routes.merge(websocket_routes))
         .layer(trace);
 
     let addr = SocketAddr::from((
@@ -74,10 +148,34 @@ async fn main() {
             .unwrap_or_else(|_| [0, 0, 0, 0].into()),
         config.server.port
     ));
-    tracing::info!("Listening on http://{}", addr);
+    let service = app.into_make_service_with_connect_info::<SocketAddr>();
 
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
-        .await
-        .unwrap();
+    if config.smtp.enabled {
+        let smtp_addr = SocketAddr::from((
+            config.smtp.host.parse::<std::net::IpAddr>()
+                .unwrap_or_else(|_| [0, 0, 0, 0].into()),
+            config.smtp.port
+        ));
+        let listener = tokio::net::TcpListener::bind(smtp_addr)
+            .await
+            .unwrap_or_else(|err| panic!("Failed to bind SMTP listener on {}: {}", smtp_addr, err));
+        tracing::info!("Accepting mail for <bin_id>@{} on {}", config.smtp.domain, smtp_addr);
+        tokio::spawn(smtp::serve(listener, app_state.clone(), config.smtp.clone()));
+    }
+
+    match &config.server.tls {
+        Some(tls_config) => {
+            let tls = tasks::tls::load_tls_config(tls_config)
+                .await
+                .unwrap_or_else(|err| panic!("Failed to load TLS certificate {}: {}", tls_config.cert_path, err));
+            tasks::tls::start_tls_reload_task(tls.clone(), tls_config).await;
+            tracing::info!("Listening on https://{}", addr);
+            axum_server::bind_rustls(addr, tls).serve(service).await.unwrap();
+        }
+        None => {
+            tracing::info!("Listening on http://{}", addr);
+            let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+            axum::serve(listener, service).await.unwrap();
+        }
+    }
 }
@@ -1,26 +1,1009 @@
 use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
-#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+/// (De)serializes a column holding JSON text as the value it holds
+mod json_text {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use serde_json::Value;
+
+    pub fn serialize<S: Serializer>(text: &Option<String>, serializer: S) -> Result<S::Ok, S::Error> {
+        text.as_deref()
+            .map(|text| serde_json::from_str::<Value>(text).unwrap_or_else(|_| Value::String(text.to_string())))
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+        Ok(Option::<Value>::deserialize(deserializer)?.map(|value| match value {
+            // Older clients sent the text itself
+            Value::String(text) => text,
+            value => value.to_string(),
+        }))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow, ToSchema)]
 pub struct LoggedRequest {
    pub method: String,
+   /// JSON list of `[name, value]` pairs in the order they were received
    pub headers: String,
    pub body: Option<String>,
+   /// "utf8" when `body` is the text as sent, "base64" for binary payloads
+   pub body_encoding: Option<String>,
+   /// `Content-Encoding` the body was sent with before it was decompressed
+   pub content_encoding: Option<String>,
    pub timestamp: String,
    pub request_id: Uuid,
+   pub body_format: Option<String>,
+   pub content_type_mismatch: bool,
+   pub query_string: Option<String>,
+   /// Query parameters by name, stored as JSON text and served as an object
+   #[serde(default, with = "json_text")]
+   #[schema(value_type = Option<Object>)]
+   pub query_params: Option<String>,
+   pub source_ip: Option<String>,
+   pub source_port: Option<u16>,
+   pub path: Option<String>,
+   /// Whether the bin's signature verification accepted the request; `None` when not checked
+   #[serde(default)]
+   #[sqlx(default)]
+   pub signature_valid: Option<bool>,
+   /// Pinned requests are kept when the bin trims old requests, and keep the bin from expiring
+   #[serde(default)]
+   #[sqlx(default)]
+   pub pinned: bool,
+   /// Values the bin's extractors found in the body when it was captured, by extractor name
+   #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+   #[sqlx(skip)]
+   pub fields: BTreeMap<String, String>,
+   /// Failure the bin's chaos settings injected into the answer: "error", "drop" or "slow"
+   #[serde(default, skip_serializing_if = "Option::is_none")]
+   #[sqlx(default)]
+   pub chaos: Option<String>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct BinResponse {
     pub bin_id: String,
+    /// Secret needed to read or manage a private bin; only returned once, at creation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Secret for listing this client's bins with `GET /my/bins`; only returned when
+    /// the request didn't send one in `X-Creator-Token`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creator_token: Option<String>,
+}
+
+/// Answer to a capture, unless the bin has a custom response
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CaptureResponse {
+    /// Id of the stored request, also sent as `X-Rustbin-Request-Id`
+    pub request_id: Uuid,
+    pub bin_id: String,
+    /// When the request was captured (RFC 3339)
+    pub timestamp: String,
+}
+
+/// Body of every error response, an RFC 7807 `application/problem+json` document
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ProblemDetails {
+    /// Always "about:blank"; `code` tells errors apart
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    /// Reason phrase of the status code
+    pub title: String,
+    pub status: u16,
+    /// What went wrong, for people
+    pub detail: String,
+    /// What went wrong, for programs, e.g. "bin_not_found"
+    pub code: String,
+}
+
+/// Labels that help tell bins apart. Accepted by `POST /create` and `PATCH /bin/:id/meta`;
+/// on update, omitted fields are left alone and an empty `name`/`description` clears it.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, ToSchema)]
+pub struct BinMetadataUpdate {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+/// Body accepted by `POST /create`
+#[derive(Debug, Serialize, Deserialize, Clone, Default, ToSchema)]
+pub struct CreateBinRequest {
+    #[serde(flatten)]
+    pub metadata: BinMetadataUpdate,
+    /// Require a token to inspect, search, clear or delete the bin
+    #[serde(default)]
+    pub private: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct BinInfo {
+    pub bin_id: String,
+    #[serde(default)]
+    pub private: bool,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+    pub last_updated: Option<String>,
+    /// Whether the bin is turning captures away, see `PUT /bin/:id/pause`
+    #[serde(default)]
+    pub paused: bool,
+    /// Captures answered but not stored because of the bin's sampling, see
+    /// `PUT /bin/:id/sampling`
+    #[serde(default)]
+    pub sampled_out: i64,
+}
+
+/// Response a bin sends back to the requests it captures. Header values and the
+/// body are Handlebars templates rendered against the captured request, e.g.
+/// `{{method}}`, `{{headers.x-request-id}}` or `{{body.json.order_id}}`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct CustomResponse {
+    #[serde(default = "default_custom_response_status")]
+    pub status: u16,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body: String,
+}
+
+fn default_custom_response_status() -> u16 {
+    200
+}
+
+/// A response sent only to captured requests that match its conditions. A bin answers
+/// with its first matching rule, then falls back to its custom response.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct ResponseRule {
+    pub rule_id: String,
+    /// HTTP method to match; any method when absent
+    pub method: Option<String>,
+    /// Sub-path below the bin to match, e.g. `/orders/*`; `*` matches any run of
+    /// characters. Any path when absent.
+    pub path: Option<String>,
+    pub response: CustomResponse,
+}
+
+/// Body accepted when creating or replacing a response rule
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateResponseRule {
+    pub method: Option<String>,
+    pub path: Option<String>,
+    pub response: CustomResponse,
+}
+
+/// What a response sequence does once every response has been served
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SequenceMode {
+    /// Start over from the first response
+    Rotate,
+    /// Stop; later requests get the bin's rules or custom response
+    Once,
+}
+
+impl SequenceMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SequenceMode::Rotate => "rotate",
+            SequenceMode::Once => "once",
+        }
+    }
+}
+
+/// Responses served in order to consecutive captures, e.g. 500, 500, then 200.
+/// Takes precedence over response rules and the custom response while it lasts.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct ResponseSequence {
+    pub mode: SequenceMode,
+    pub responses: Vec<CustomResponse>,
+    /// How many responses have been served; ignored when a sequence is stored
+    #[serde(default)]
+    pub position: u64,
+}
+
+/// Header names and body patterns masked before a bin's captures are stored, on top of
+/// the global `[redaction]` settings
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, ToSchema)]
+pub struct RedactionRules {
+    /// Header names, matched case-insensitively
+    #[serde(default)]
+    pub headers: Vec<String>,
+    /// Regexes over text bodies; with a capture group only the first group is masked
+    #[serde(default)]
+    pub body_patterns: Vec<String>,
+}
+
+/// Source addresses a bin accepts captures from. Entries are addresses or CIDR ranges.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, ToSchema)]
+pub struct IpAccessRules {
+    /// When not empty, only these sources may capture
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Always turned away, even when also allowed
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Keep a record of turned away captures, see `GET /bin/:id/access/rejected`
+    #[serde(default)]
+    pub log_rejected: bool,
+}
+
+/// A capture turned away by a bin's IP access rules
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, sqlx::FromRow, ToSchema)]
+pub struct RejectedRequest {
+    pub source_ip: String,
+    pub method: String,
+    pub path: String,
+    pub timestamp: String,
+}
+
+/// What a paused bin answers captures with while it stores nothing
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+pub struct BinPause {
+    /// Status code sent to senders, 200 to 599
+    #[serde(default = "default_pause_status")]
+    pub status: u16,
+}
+
+fn default_pause_status() -> u16 {
+    503
+}
+
+impl Default for BinPause {
+    fn default() -> Self {
+        BinPause { status: default_pause_status() }
+    }
+}
+
+/// Which captures a sampling bin stores; set either `every` or `percent`. The others are
+/// answered as usual but not stored, forwarded or broadcast.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, ToSchema)]
+pub struct BinSampling {
+    /// Store the first capture and every Nth one after it
+    #[serde(default)]
+    pub every: Option<u32>,
+    /// Store this share of captures, picked at random, above 0 and up to 100
+    #[serde(default)]
+    pub percent: Option<f64>,
+}
+
+/// Upstream a recording bin proxies its captures to
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct BinProxy {
+    /// Base URL; the captured sub-path and query string are appended
+    pub upstream: String,
+}
+
+/// Rhai script a bin runs on every capture, see the README's "Scripted responses"
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct BinScript {
+    /// Evaluates to nothing to leave the answer to the bin, a string body, or a map with
+    /// `status`, `headers`, `body` and `events`
+    pub source: String,
+}
+
+/// Failures a bin injects into its answers, for resilience testing. Each share is a
+/// percentage of captures, and together they are at most 100.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, ToSchema)]
+pub struct BinChaos {
+    /// Answer 500 instead of the bin's response
+    #[serde(default)]
+    pub error_percent: f64,
+    /// Close the connection without answering
+    #[serde(default)]
+    pub drop_percent: f64,
+    /// Answer as usual, but only after `delay_ms`
+    #[serde(default)]
+    pub slow_percent: f64,
+    /// How long slow answers are held back, at most 30000 (default: 30000)
+    #[serde(default = "default_chaos_delay_ms")]
+    pub delay_ms: u64,
+}
+
+fn default_chaos_delay_ms() -> u64 {
+    30_000
+}
+
+/// How fast a bin accepts captures, from all senders together
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, ToSchema)]
+pub struct BinRateLimit {
+    /// Sustained captures per second; 0 leaves the bin unlimited
+    pub requests_per_second: f64,
+    /// Captures accepted in a burst on top of the sustained rate
+    pub burst_size: u32,
+}
+
+/// A bin's own limits; omitted ones keep the server-wide value
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, ToSchema)]
+pub struct BinLimits {
+    /// Requests kept before the oldest unpinned ones are dropped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_requests: Option<i64>,
+    /// Largest request body accepted, in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_body_size: Option<usize>,
+    /// Hours without captures before the bin expires
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expiry_hours: Option<i64>,
+    /// Hours each unpinned request is kept; 0 keeps them until trimmed or expired
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention_hours: Option<i64>,
+}
+
+/// Chat services a bin can post new captures to
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatService {
+    /// Slack incoming webhook, or anything accepting `{"text": ...}`
+    Slack,
+    /// Discord channel webhook
+    Discord,
+}
+
+impl ChatService {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChatService::Slack => "slack",
+            ChatService::Discord => "discord",
+        }
+    }
+}
+
+/// Where a bin posts a summary of each capture, and which captures are worth one
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct BinNotifications {
+    pub service: ChatService,
+    /// Incoming webhook URL of the Slack app or Discord channel
+    pub webhook_url: String,
+    /// Only captures with these methods; empty for any
+    #[serde(default)]
+    pub methods: Vec<String>,
+    /// Only captures whose path below the bin starts with this
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_prefix: Option<String>,
+    /// Only captures that failed the bin's signature verification
+    #[serde(default)]
+    pub failed_signatures_only: bool,
+}
+
+/// Webhook senders whose signatures can be checked on capture
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureProvider {
+    /// `X-Hub-Signature-256: sha256=<hex>`
+    Github,
+    /// `Stripe-Signature: t=<timestamp>,v1=<hex>`
+    Stripe,
+    /// `X-Slack-Signature: v0=<hex>` with `X-Slack-Request-Timestamp`
+    Slack,
+    /// HMAC-SHA256 of the body, hex or base64, in a configurable header
+    Hmac,
+}
+
+impl SignatureProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignatureProvider::Github => "github",
+            SignatureProvider::Stripe => "stripe",
+            SignatureProvider::Slack => "slack",
+            SignatureProvider::Hmac => "hmac",
+        }
+    }
+}
+
+/// How a bin's captures are signed. Every capture is then stored with `signature_valid`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct SignatureVerification {
+    pub provider: SignatureProvider,
+    /// Shared secret; never returned
+    #[serde(skip_serializing)]
+    pub secret: String,
+    /// Signature header for the `hmac` provider (default: `X-Signature`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header: Option<String>,
+}
+
+/// A URL every request captured by a bin is mirrored to
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct Forward {
+    pub forward_id: String,
+    /// Base URL; the captured sub-path and query string are appended
+    pub url: String,
+    pub enabled: bool,
+    pub created_at: String,
+    /// Outcome of the most recent delivery, if there was one
+    pub last_delivery: Option<ForwardDelivery>,
+}
+
+/// Mirroring of one captured request to a forward target, across all its attempts
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct ForwardDelivery {
+    pub delivery_id: i64,
+    pub request_id: Uuid,
+    /// "delivered" after a 2xx answer, "pending" while a retry is scheduled and
+    /// "failed" once the attempts are used up
+    pub status: String,
+    /// Status code the target last answered with, if it answered
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    pub attempts: u32,
+    /// When the latest attempt was made
+    pub attempted_at: String,
+    /// When the next attempt is due, while pending
+    pub next_attempt_at: Option<String>,
+    /// Milliseconds the latest attempt took, until the response was read
+    pub latency_ms: Option<u64>,
+    /// What the target answered on the latest attempt, with the body cut to
+    /// `[forwarding] max_response_body_bytes`
+    pub response: Option<ReplayResponse>,
+}
+
+/// Body accepted by `POST /bin/:id/request/:rid/deliveries`, sent by `rustbin tunnel`
+/// once it has passed a capture on to a local server
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReportedDelivery {
+    /// Where the request was sent
+    pub url: String,
+    /// What the server answered; absent when it couldn't be reached
+    pub response: Option<ReplayResponse>,
+    /// Why there is no response
+    pub error: Option<String>,
+    pub latency_ms: Option<u64>,
+}
+
+/// One time a captured request was sent on, by a forward, a replay or a tunnel client,
+/// with what the target answered
+#[derive(Debug, Serialize, Clone, ToSchema)]
+pub struct RequestDelivery {
+    /// "forward", "replay", "tunnel" or "proxy"
+    pub kind: String,
+    /// The forward that made the delivery; absent for replays
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forward_id: Option<String>,
+    pub url: String,
+    #[serde(flatten)]
+    pub delivery: ForwardDelivery,
+}
+
+/// Body accepted by `POST /bin/:id/forwards`
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateForward {
+    pub url: String,
+    #[serde(default = "default_forward_enabled")]
+    pub enabled: bool,
+}
+
+fn default_forward_enabled() -> bool {
+    true
+}
+
+/// Body accepted by `PATCH /bin/:id/forwards/:fid`; omitted fields are left alone
+#[derive(Debug, Deserialize, Default, ToSchema)]
+pub struct ForwardUpdate {
+    pub url: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+/// Names a value pulled out of JSON bodies when they are captured, e.g. `order_id` at
+/// `$.data.object.id`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct Extractor {
+    pub name: String,
+    /// JSONPath of the value: `$` followed by `.key`, `['key']` or `[index]` steps
+    pub path: String,
+}
+
+/// A URL that receives a bin's `request.captured`, `bin.expiring` and `bin.deleted` events
+#[derive(Debug, Serialize, Clone, ToSchema)]
+pub struct Callback {
+    pub callback_id: String,
+    pub url: String,
+    /// Events the callback receives; empty receives all of them
+    pub events: Vec<String>,
+    /// Key for the `x-rustbin-signature` HMAC, only shown when the callback is created
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub created_at: String,
+}
+
+/// Body accepted by `POST /bin/:id/callbacks`
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateCallback {
+    pub url: String,
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// Generated when omitted
+    pub secret: Option<String>,
 }
 
-#[derive(Serialize, Deserialize)]
+/// Answer to `POST /bin/:id/callbacks`, the only time the secret is returned
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedCallback {
+    pub callback_id: String,
+    pub url: String,
+    pub events: Vec<String>,
+    pub secret: String,
+    pub created_at: String,
+}
+
+/// Where to re-send a captured request, and what to change in it first
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ReplayRequest {
+    /// Base URL; the captured sub-path and query string are appended
+    pub target: String,
+    #[serde(flatten)]
+    pub overrides: ReplayOverrides,
+}
+
+/// Changes applied on top of a stored request before it is replayed; everything left out
+/// is sent as captured
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct ReplayOverrides {
+    /// Method to send instead of the captured one
+    pub method: Option<String>,
+    pub headers: Option<HeaderOverrides>,
+    /// Text body to send instead of the captured one
+    pub body: Option<String>,
+}
+
+/// Header changes for a replay, matched without regard to case
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct HeaderOverrides {
+    /// Headers to send, replacing every captured value of the same name
+    #[serde(default)]
+    pub set: BTreeMap<String, String>,
+    /// Captured headers to leave out
+    #[serde(default)]
+    pub remove: Vec<String>,
+}
+
+/// What the replay target answered
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct ReplayResponse {
+    pub status: u16,
+    /// `[name, value]` pairs in the order they were received
+    #[schema(value_type = Vec<Vec<String>>)]
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    /// "utf8" or "base64", as for captured bodies
+    pub body_encoding: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct PingResponse {
     pub ok: bool,
     pub message: String,
 }
 
-#[derive(Deserialize)]
+/// Liveness probe answer; the process is up and serving
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct HealthResponse {
+    pub status: String,
+}
+
+/// Readiness probe answer. `ready` is false when any check is `down`.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ReadinessResponse {
+    pub ready: bool,
+    pub checks: ReadinessChecks,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ReadinessChecks {
+    /// Runs `SELECT 1` and reports connection pool usage
+    pub database: CheckResult,
+    /// Background writer used with `[limits] async_writes`
+    pub writer_queue: CheckResult,
+    pub cleanup_task: CheckResult,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct CheckResult {
+    /// "ok", "down" or "disabled"
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Numbers describing the component, e.g. pool or queue usage
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub details: BTreeMap<String, u64>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct PingQuery {
     pub message: Option<String>,
 }
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AdminSearchQuery {
+    pub q: String,
+    /// RFC 3339 lower bound on the capture timestamp (default: 24 hours ago)
+    pub since: Option<String>,
+    /// RFC 3339 upper bound on the capture timestamp (default: now)
+    pub until: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow, ToSchema)]
+pub struct AdminSearchResult {
+    pub bin_id: String,
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub request: LoggedRequest,
+}
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    /// Export format (default: "curl")
+    pub format: Option<String>,
+    /// Base URL to aim exported requests at instead of the bin they were sent to
+    pub target: Option<String>,
+}
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SnippetQuery {
+    /// Language to render: "python", "node", "rust" or "go"
+    pub lang: Option<String>,
+    /// Base URL to aim the snippet at instead of the bin the request was sent to
+    pub target: Option<String>,
+}
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SimulateQuery {
+    /// Sample to send: "github.push", "stripe.invoice.paid" or "generic" (default: "generic")
+    pub provider: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DiffQuery {
+    /// Request to compare from, usually the one that worked
+    pub from: String,
+    /// Request to compare to
+    pub to: String,
+}
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AdminStorageQuery {
+    /// Number of bins to list, largest first (default: 20, max: 500)
+    pub top: Option<i64>,
+}
+
+/// A bin's share of the store in `GET /admin/storage`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+pub struct BinStorageUsage {
+    pub bin_id: String,
+    pub request_count: i64,
+    /// Bytes of headers, query parameters and bodies held in the database, compressed
+    /// bodies at their compressed size
+    pub bytes: i64,
+    /// Bodies offloaded to the blob store
+    pub blob_count: i64,
+}
+
+/// Request bodies offloaded to `[blobs]`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+pub struct BlobStorageUsage {
+    /// Whether large bodies are offloaded at all
+    pub enabled: bool,
+    pub count: i64,
+    /// Total size of the blobs; `null` when the store can't tell cheaply, as with S3
+    pub bytes: Option<u64>,
+}
+
+/// Where the disk space goes
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct AdminStorage {
+    /// Size of the database file, or of the stored requests for in-memory storage
+    pub database_size_bytes: i64,
+    pub page_size: i64,
+    pub page_count: i64,
+    /// Unused pages, given back to the filesystem by the maintenance vacuum
+    pub free_page_count: i64,
+    /// Bins holding the most bytes, largest first
+    pub bins: Vec<BinStorageUsage>,
+    pub blobs: BlobStorageUsage,
+}
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AdminBinsQuery {
+    /// Maximum number of bins to return (default: 50, max: 500)
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow, ToSchema)]
+pub struct AdminBinSummary {
+    pub bin_id: String,
+    pub name: Option<String>,
+    pub private: bool,
+    pub last_updated: Option<String>,
+    pub request_count: i64,
+}
+
+/// A bin in `GET /my/bins`
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct CreatorBinSummary {
+    pub bin_id: String,
+    pub name: Option<String>,
+    pub private: bool,
+    pub last_updated: Option<String>,
+    pub request_count: i64,
+    /// When the bin is deleted unless it captures something first (RFC 3339); `null`
+    /// while it holds pinned requests
+    pub expires_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct AdminStats {
+    pub bin_count: i64,
+    pub private_bin_count: i64,
+    pub request_count: i64,
+    pub requests_last_24h: i64,
+    /// Captures answered but not stored because of bin sampling
+    pub sampled_out_requests: i64,
+    /// Bins with at least one live WebSocket subscriber
+    pub watched_bin_count: usize,
+    pub database_size_bytes: i64,
+    /// Captures are refused because `[cleanup] max_storage_bytes` was reached
+    pub storage_full: bool,
+    /// Requests evicted to stay under `max_storage_bytes` since the server started
+    pub evicted_requests: u64,
+}
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct InspectQuery {
+    /// Maximum number of requests to return (default: 100, max: 1000)
+    pub limit: Option<i64>,
+    /// Number of requests to skip
+    pub offset: Option<i64>,
+    /// Only return requests captured after this request_id
+    pub after_id: Option<String>,
+    /// Only return requests with this HTTP method
+    pub method: Option<String>,
+    /// Only return requests captured at or after this RFC 3339 timestamp
+    pub since: Option<String>,
+    /// Only return requests captured at or before this RFC 3339 timestamp
+    pub until: Option<String>,
+    /// Only return requests carrying this header, as `name` or `name:value`
+    pub header: Option<String>,
+    /// Only return requests where this extractor found a value
+    pub field: Option<String>,
+    /// With `field`, only return requests where the extracted value is exactly this
+    pub value: Option<String>,
+}
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FeedQuery {
+    /// Number of recent requests in the feed (default: 20, max: 100)
+    pub limit: Option<i64>,
+}
+
+/// Without either parameter `DELETE /bin/:id/clear` removes every request, pinned ones
+/// included; with them only unpinned requests are pruned
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ClearQuery {
+    /// Only remove requests older than this, e.g. "90s", "15m", "24h" or "7d"
+    pub older_than: Option<String>,
+    /// Always keep this many of the newest requests
+    pub keep_last: Option<i64>,
+}
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PollQuery {
+    /// Only return requests captured after this request_id; without it, wait for the next one
+    pub after: Option<String>,
+    /// Seconds to wait for a new request (default: 30, max: 60)
+    pub timeout: Option<u64>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BinSearchQuery {
+    /// Words or phrases that must all appear in the request body
+    pub q: String,
+    /// Maximum number of results (default: 50, max: 500)
+    pub limit: Option<i64>,
+}
+
+/// Version of the WebSocket message format, sent with every message
+pub const WS_MESSAGE_VERSION: u32 = 1;
+
+/// Everything sent to WebSocket clients, as `{"type": ..., "version": 1, "data": ...}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WsMessage {
+    pub version: u32,
+    #[serde(flatten)]
+    pub event: WsEvent,
+}
+
+impl WsMessage {
+    pub fn new(event: WsEvent) -> Self {
+        WsMessage { version: WS_MESSAGE_VERSION, event }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum WsEvent {
+    /// A request was captured, or is being replayed from history on connect
+    Request(Box<RequestNotification>),
+    /// One request was deleted
+    RequestDeleted { request_id: Uuid },
+    /// Every request of the bin was deleted
+    RequestsCleared { count: u64 },
+    /// The bin will be deleted for inactivity unless it receives a request first
+    BinExpiring { expires_at: Option<String> },
+    /// The bin is gone, either deleted or `expired`; the server closes the connection next
+    BinDeleted { expired: bool },
+    /// The bin's script emitted an event while answering a capture
+    ScriptEvent { request_id: Uuid, name: String, data: serde_json::Value },
+    /// The client sent something the server could not use
+    Error { message: String },
+}
+
+/// What changed between two captured requests of a bin, from `GET /bin/:id/diff`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RequestDiff {
+    pub from: Uuid,
+    pub to: Uuid,
+    /// Present only when the method differs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<ValueChange>,
+    /// Present only when the path below the bin differs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<ValueChange>,
+    /// Present only when the query string differs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_string: Option<ValueChange>,
+    /// Headers that differ, by lower-cased name; repeated headers are compared as a
+    /// comma separated list
+    pub headers: Vec<HeaderChange>,
+    pub body: BodyDiff,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Change {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A value on each side; `null` when the side doesn't have it
+#[derive(Debug, Serialize, PartialEq, ToSchema)]
+pub struct ValueChange {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Serialize, PartialEq, ToSchema)]
+pub struct HeaderChange {
+    pub name: String,
+    pub change: Change,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// Body differences: by JSON path when both bodies are JSON, by line for other text,
+/// and only whether they match for binary bodies
+#[derive(Debug, Serialize, PartialEq, ToSchema)]
+#[serde(tag = "format", rename_all = "lowercase")]
+pub enum BodyDiff {
+    Json { changes: Vec<JsonChange> },
+    Text { changes: Vec<LineChange> },
+    Binary { identical: bool, from_size: usize, to_size: usize },
+}
+
+#[derive(Debug, Serialize, PartialEq, ToSchema)]
+pub struct JsonChange {
+    /// JSONPath of the value, e.g. `$.data.items[0].id`
+    pub path: String,
+    pub change: Change,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub from: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub to: Option<serde_json::Value>,
+}
+
+/// A line only one side has
+#[derive(Debug, Serialize, PartialEq, ToSchema)]
+pub struct LineChange {
+    /// `removed` lines are numbered in the `from` body, `added` ones in the `to` body
+    pub change: Change,
+    pub line: usize,
+    pub text: String,
+}
+
+/// A captured request as pushed to WebSocket clients. Unlike [`LoggedRequest`],
+/// headers and query parameters are JSON values rather than encoded strings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestNotification {
+    pub request_id: Uuid,
+    pub method: String,
+    pub path: Option<String>,
+    /// `[name, value]` pairs in the order they were received
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+    pub body_encoding: Option<String>,
+    pub content_encoding: Option<String>,
+    pub body_format: Option<String>,
+    pub content_type_mismatch: bool,
+    pub query_string: Option<String>,
+    pub query_params: Option<serde_json::Value>,
+    pub source_ip: Option<String>,
+    pub source_port: Option<u16>,
+    pub signature_valid: Option<bool>,
+    pub timestamp: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chaos: Option<String>,
+}
+
+impl From<RequestNotification> for LoggedRequest {
+    fn from(notification: RequestNotification) -> Self {
+        LoggedRequest {
+            request_id: notification.request_id,
+            headers: serde_json::to_string(&notification.headers).unwrap_or_else(|_| "[]".to_string()),
+            query_params: notification.query_params.map(|params| params.to_string()),
+            method: notification.method,
+            path: notification.path,
+            body: notification.body,
+            body_encoding: notification.body_encoding,
+            content_encoding: notification.content_encoding,
+            body_format: notification.body_format,
+            content_type_mismatch: notification.content_type_mismatch,
+            query_string: notification.query_string,
+            source_ip: notification.source_ip,
+            source_port: notification.source_port,
+            signature_valid: notification.signature_valid,
+            pinned: false,
+            timestamp: notification.timestamp,
+            fields: notification.fields,
+            chaos: notification.chaos,
+        }
+    }
+}
+
+impl From<LoggedRequest> for RequestNotification {
+    fn from(request: LoggedRequest) -> Self {
+        RequestNotification {
+            request_id: request.request_id,
+            headers: crate::utils::headers::parse_stored_headers(&request.headers),
+            query_params: request.query_params.as_deref().and_then(|params| serde_json::from_str(params).ok()),
+            method: request.method,
+            path: request.path,
+            body: request.body,
+            body_encoding: request.body_encoding,
+            content_encoding: request.content_encoding,
+            body_format: request.body_format,
+            content_type_mismatch: request.content_type_mismatch,
+            query_string: request.query_string,
+            source_ip: request.source_ip,
+            source_port: request.source_port,
+            signature_valid: request.signature_valid,
+            timestamp: request.timestamp,
+            fields: request.fields,
+            chaos: request.chaos,
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! Posting a summary of captures to the Slack or Discord webhook configured on their bin.
+
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::config::NotificationsConfig;
+use crate::models::{BinNotifications, ChatService, LoggedRequest};
+use crate::outbound::OutboundGuard;
+use crate::replay::parse_target;
+use crate::utils::body::ENCODING_UTF8;
+use crate::utils::headers::parse_stored_headers;
+
+/// Characters of a text body quoted in the message
+const BODY_PREVIEW_CHARS: usize = 500;
+
+/// Posts captures to their bin's chat webhook in the background.
+/// Does nothing when `[notifications]` is disabled.
+pub struct Notifier {
+    client: reqwest::Client,
+    guard: Arc<OutboundGuard>,
+    enabled: bool,
+    public_url: Option<String>,
+}
+
+impl Notifier {
+    pub fn new(config: &NotificationsConfig, guard: Arc<OutboundGuard>) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_seconds))
+            .build()
+            .expect("Failed to build notification HTTP client");
+        Notifier {
+            client,
+            guard,
+            enabled: config.enabled,
+            public_url: config.public_url.as_deref().map(|url| url.trim_end_matches('/').to_string()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// `[notifications] public_url`, without a trailing slash
+    pub fn public_url(&self) -> Option<&str> {
+        self.public_url.as_deref()
+    }
+
+    /// Post `request` to the bin's chat webhook if it has one and the request passes its
+    /// filters. Never blocks the caller.
+    pub fn notify(self: &Arc<Self>, bin_id: &str, notifications: Option<&BinNotifications>, request: &LoggedRequest) {
+        let Some(notifications) = notifications.filter(|notifications| self.enabled && matches(notifications, request)) else {
+            return;
+        };
+        let notifier = self.clone();
+        let bin_id = bin_id.to_string();
+        let notifications = notifications.clone();
+        let request = request.clone();
+        tokio::spawn(async move {
+            let permitted = match parse_target(&notifications.webhook_url) {
+                Ok(url) => notifier.guard.check(&url).await,
+                Err(err) => Err(err.to_string()),
+            };
+            if let Err(err) = permitted {
+                warn!(%bin_id, request_id = %request.request_id, %err, "Refusing to send chat notification");
+                return;
+            }
+
+            let message = message(notifications.service, &bin_id, &request, notifier.public_url.as_deref());
+            match notifier.client.post(&notifications.webhook_url).json(&message).send().await {
+                Ok(response) if response.status().is_success() => {
+                    info!(%bin_id, request_id = %request.request_id, service = notifications.service.as_str(), "Chat notification sent");
+                }
+                Ok(response) => {
+                    warn!(%bin_id, request_id = %request.request_id, status = response.status().as_u16(), "Chat webhook rejected the notification");
+                }
+                Err(err) => warn!(%bin_id, request_id = %request.request_id, %err, "Failed to send chat notification"),
+            }
+        });
+    }
+}
+
+fn matches(notifications: &BinNotifications, request: &LoggedRequest) -> bool {
+    let method_ok = notifications.methods.is_empty()
+        || notifications.methods.iter().any(|method| method.eq_ignore_ascii_case(&request.method));
+    let path_ok = notifications
+        .path_prefix
+        .as_deref()
+        .is_none_or(|prefix| request.path.as_deref().unwrap_or("/").starts_with(prefix));
+    let signature_ok = !notifications.failed_signatures_only || request.signature_valid == Some(false);
+    method_ok && path_ok && signature_ok
+}
+
+/// The webhook payload: a few lines on what arrived, the start of a text body, and a
+/// link to the bin when the server knows its public address
+fn message(service: ChatService, bin_id: &str, request: &LoggedRequest, public_url: Option<&str>) -> serde_json::Value {
+    let bold = match service {
+        ChatService::Slack => "*",
+        ChatService::Discord => "**",
+    };
+    let mut text = format!(
+        "{bold}{} {}{bold} captured in bin `{}`",
+        request.method,
+        request.path.as_deref().unwrap_or("/"),
+        bin_id
+    );
+
+    let mut details = Vec::new();
+    if let Some(source_ip) = &request.source_ip {
+        details.push(format!("from {}", source_ip));
+    }
+    let headers = parse_stored_headers(&request.headers);
+    if let Some((_, content_type)) = headers.iter().find(|(name, _)| name.eq_ignore_ascii_case("content-type")) {
+        details.push(content_type.clone());
+    }
+    match request.signature_valid {
+        Some(true) => details.push("signature valid".to_string()),
+        Some(false) => details.push("signature invalid".to_string()),
+        None => {}
+    }
+    if !details.is_empty() {
+        text.push('\n');
+        text.push_str(&details.join(" · "));
+    }
+
+    let body = request.body.as_deref().unwrap_or("");
+    if !body.is_empty() {
+        if request.body_encoding.as_deref().is_none_or(|encoding| encoding == ENCODING_UTF8) {
+            let mut preview: String = body.chars().take(BODY_PREVIEW_CHARS).collect();
+            if preview.len() < body.len() {
+                preview.push('…');
+            }
+            // A backtick run would end the code block early
+            text.push_str(&format!("\n```\n{}\n```", preview.replace("```", "`\u{200b}``")));
+        } else {
+            text.push_str("\n(binary body)");
+        }
+    }
+
+    if let Some(public_url) = public_url {
+        let link = format!("{}/bin/{}/inspect", public_url, bin_id);
+        match service {
+            ChatService::Slack => text.push_str(&format!("\n<{}|Inspect the bin>", link)),
+            ChatService::Discord => text.push_str(&format!("\n[Inspect the bin]({})", link)),
+        }
+    }
+
+    match service {
+        ChatService::Slack => json!({ "text": text }),
+        ChatService::Discord => json!({ "content": text }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn request(method: &str, path: &str, signature_valid: Option<bool>) -> LoggedRequest {
+        LoggedRequest {
+            method: method.to_string(),
+            headers: r#"[["content-type","application/json"]]"#.to_string(),
+            body: Some(r#"{"event":"push"}"#.to_string()),
+            body_encoding: Some(ENCODING_UTF8.to_string()),
+            content_encoding: None,
+            timestamp: "2024-01-01T00:00:00Z".to_string(),
+            request_id: Uuid::new_v4(),
+            body_format: Some("json".to_string()),
+            content_type_mismatch: false,
+            query_string: None,
+            query_params: None,
+            source_ip: Some("203.0.113.5".to_string()),
+            source_port: None,
+            path: Some(path.to_string()),
+            signature_valid,
+            fields: Default::default(),
+            pinned: false,
+            chaos: None,
+        }
+    }
+
+    fn notifications() -> BinNotifications {
+        BinNotifications {
+            service: ChatService::Slack,
+            webhook_url: "https://hooks.slack.com/services/T/B/X".to_string(),
+            methods: Vec::new(),
+            path_prefix: None,
+            failed_signatures_only: false,
+        }
+    }
+
+    #[test]
+    fn filters_captures() {
+        assert!(matches(&notifications(), &request("GET", "/", None)));
+
+        let posts = BinNotifications { methods: vec!["post".to_string()], path_prefix: Some("/github".to_string()), ..notifications() };
+        assert!(matches(&posts, &request("POST", "/github/push", None)));
+        assert!(!matches(&posts, &request("GET", "/github/push", None)));
+        assert!(!matches(&posts, &request("POST", "/stripe", None)));
+
+        let failures = BinNotifications { failed_signatures_only: true, ..notifications() };
+        assert!(matches(&failures, &request("POST", "/", Some(false))));
+        assert!(!matches(&failures, &request("POST", "/", Some(true))));
+        assert!(!matches(&failures, &request("POST", "/", None)));
+    }
+
+    #[test]
+    fn formats_messages_per_service() {
+        let request = request("POST", "/github", Some(false));
+        let slack = message(ChatService::Slack, "bin-1", &request, Some("https://rustb.in"));
+        assert_eq!(
+            slack["text"],
+            "*POST /github* captured in bin `bin-1`\nfrom 203.0.113.5 · application/json · signature invalid\n\
+             ```\n{\"event\":\"push\"}\n```\n<https://rustb.in/bin/bin-1/inspect|Inspect the bin>"
+        );
+
+        let discord = message(ChatService::Discord, "bin-1", &request, None);
+        let content = discord["content"].as_str().unwrap();
+        assert!(content.starts_with("**POST /github** captured"), "{}", content);
+        assert!(!content.contains("Inspect"));
+    }
+}
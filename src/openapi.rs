@@ -0,0 +1,202 @@
+use utoipa::{
+    openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi,
+};
+
+use crate::{handlers, models};
+
+/// OpenAPI 3 description of the HTTP API, served at `/openapi.json`.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "rustbin", description = "Capture and inspect HTTP requests"),
+    paths(
+        handlers::create_bin,
+        handlers::log_request,
+        handlers::log_request_with_path,
+        handlers::inspect_bin,
+        handlers::poll_bin,
+        handlers::bin_feed,
+        handlers::search_bin,
+        handlers::get_bin_info,
+        handlers::update_bin_info,
+        handlers::get_bin_response,
+        handlers::set_bin_response,
+        handlers::delete_bin_response,
+        handlers::get_response_sequence,
+        handlers::set_response_sequence,
+        handlers::delete_response_sequence,
+        handlers::get_signature_verification,
+        handlers::set_signature_verification,
+        handlers::delete_signature_verification,
+        handlers::get_redaction_rules,
+        handlers::set_redaction_rules,
+        handlers::delete_redaction_rules,
+        handlers::get_ip_access_rules,
+        handlers::set_ip_access_rules,
+        handlers::delete_ip_access_rules,
+        handlers::list_rejected_requests,
+        handlers::get_bin_pause,
+        handlers::pause_bin,
+        handlers::resume_bin,
+        handlers::get_bin_sampling,
+        handlers::set_bin_sampling,
+        handlers::delete_bin_sampling,
+        handlers::get_bin_chaos,
+        handlers::set_bin_chaos,
+        handlers::delete_bin_chaos,
+        handlers::get_bin_proxy,
+        handlers::set_bin_proxy,
+        handlers::delete_bin_proxy,
+        handlers::get_bin_script,
+        handlers::set_bin_script,
+        handlers::delete_bin_script,
+        handlers::get_bin_rate_limit,
+        handlers::set_bin_rate_limit,
+        handlers::delete_bin_rate_limit,
+        handlers::get_bin_limits,
+        handlers::set_bin_limits,
+        handlers::delete_bin_limits,
+        handlers::get_bin_notifications,
+        handlers::set_bin_notifications,
+        handlers::delete_bin_notifications,
+        handlers::get_extractors,
+        handlers::set_extractors,
+        handlers::list_response_rules,
+        handlers::create_response_rule,
+        handlers::replace_response_rules,
+        handlers::update_response_rule,
+        handlers::delete_response_rule,
+        handlers::get_request_body,
+        handlers::get_request_curl,
+        handlers::get_request_snippet,
+        handlers::diff_requests,
+        handlers::replay_request,
+        handlers::pin_request,
+        handlers::unpin_request,
+        handlers::create_forward,
+        handlers::list_forwards,
+        handlers::update_forward,
+        handlers::delete_forward,
+        handlers::list_forward_deliveries,
+        handlers::list_request_deliveries,
+        handlers::report_request_delivery,
+        handlers::create_callback,
+        handlers::list_callbacks,
+        handlers::delete_callback,
+        handlers::export_bin,
+        handlers::import_requests,
+        handlers::simulate_request,
+        handlers::clear_bin_requests,
+        handlers::delete_bin,
+        handlers::restore_bin,
+        handlers::delete_bin_request,
+        handlers::delete_request,
+        handlers::admin_search,
+        handlers::list_creator_bins,
+        handlers::admin_list_bins,
+        handlers::admin_delete_bin,
+        handlers::admin_stats,
+        handlers::admin_storage,
+        handlers::ping,
+        handlers::healthz,
+        handlers::readyz,
+    ),
+    components(schemas(
+        models::LoggedRequest,
+        models::BinResponse,
+        models::CreateBinRequest,
+        models::BinMetadataUpdate,
+        models::BinInfo,
+        models::CustomResponse,
+        models::ResponseSequence,
+        models::SequenceMode,
+        models::ResponseRule,
+        models::CreateResponseRule,
+        models::SignatureVerification,
+        models::SignatureProvider,
+        models::RedactionRules,
+        models::IpAccessRules,
+        models::BinPause,
+        models::BinSampling,
+        models::BinProxy,
+        models::BinScript,
+        models::BinChaos,
+        models::BinRateLimit,
+        models::CaptureResponse,
+        models::ProblemDetails,
+        models::BinLimits,
+        models::BinNotifications,
+        models::ChatService,
+        models::Extractor,
+        models::RejectedRequest,
+        models::PingResponse,
+        models::HealthResponse,
+        models::ReadinessResponse,
+        models::ReadinessChecks,
+        models::CheckResult,
+        models::RequestDiff,
+        models::ValueChange,
+        models::HeaderChange,
+        models::BodyDiff,
+        models::JsonChange,
+        models::LineChange,
+        models::Change,
+        models::ReplayRequest,
+        models::ReplayOverrides,
+        models::HeaderOverrides,
+        models::ReplayResponse,
+        models::Forward,
+        models::ForwardDelivery,
+        models::RequestDelivery,
+        models::ReportedDelivery,
+        models::CreateForward,
+        models::ForwardUpdate,
+        models::Callback,
+        models::CreateCallback,
+        models::CreatedCallback,
+        models::AdminSearchResult,
+        models::AdminBinSummary,
+        models::CreatorBinSummary,
+        models::AdminStats,
+        models::AdminStorage,
+        models::BinStorageUsage,
+        models::BlobStorageUsage,
+    )),
+    modifiers(&SecuritySchemes),
+    tags(
+        (name = "capture", description = "Endpoints that record incoming requests"),
+        (name = "bins", description = "Creating, describing and deleting bins"),
+        (name = "requests", description = "Reading and removing captured requests"),
+        (name = "responses", description = "Custom responses sent back by a bin"),
+        (name = "forwards", description = "Mirroring captured requests to other URLs"),
+        (name = "callbacks", description = "Signed bin events sent to URLs registered on the bin"),
+        (name = "export", description = "Exporting and importing captured requests"),
+        (name = "admin", description = "Operator endpoints"),
+        (name = "health", description = "Liveness checks"),
+    )
+)]
+pub struct ApiDoc;
+
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bin_token",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).description(Some("Token of a private bin, also accepted as `?token=`")).build()),
+        );
+        components.add_security_scheme(
+            "admin_token",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).description(Some("`[admin] token` from rustbin.toml")).build()),
+        );
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-Api-Key"))),
+        );
+        components.add_security_scheme(
+            "creator_token",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-Creator-Token"))),
+        );
+    }
+}
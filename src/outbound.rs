@@ -0,0 +1,120 @@
+//! Keeping the URLs bins send to (proxy upstreams, forwards, replays, callbacks and chat
+//! notifications) out of the server's own network.
+
+use ipnet::IpNet;
+use reqwest::Url;
+use std::net::IpAddr;
+
+use crate::ip_access::parse_network;
+
+/// Loopback, private, link-local (cloud metadata included), shared, multicast and
+/// reserved ranges, none of which a bin has any business reaching
+const INTERNAL_NETWORKS: [&str; 16] = [
+    "0.0.0.0/8",
+    "10.0.0.0/8",
+    "100.64.0.0/10",
+    "127.0.0.0/8",
+    "169.254.0.0/16",
+    "172.16.0.0/12",
+    "192.0.0.0/24",
+    "192.168.0.0/16",
+    "198.18.0.0/15",
+    "224.0.0.0/4",
+    "240.0.0.0/4",
+    "::/128",
+    "::1/128",
+    "fc00::/7",
+    "fe80::/10",
+    "ff00::/8",
+];
+
+/// `[outbound] allowed_networks`, the internal addresses bins may send to anyway
+#[derive(Debug, Clone)]
+pub struct OutboundGuard {
+    internal: Vec<IpNet>,
+    allowed: Vec<IpNet>,
+}
+
+impl OutboundGuard {
+    /// Parse every entry, naming the first one that is neither an address nor a CIDR range
+    pub fn new(entries: &[String]) -> Result<Self, String> {
+        Ok(OutboundGuard {
+            internal: INTERNAL_NETWORKS.iter().map(|entry| parse_network(entry)).collect::<Result<_, _>>()?,
+            allowed: entries.iter().map(|entry| parse_network(entry)).collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Public addresses, and internal ones inside an allowed network
+    pub fn permits(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.allowed.iter().any(|network| network.contains(&ip)) || !self.internal.iter().any(|network| network.contains(&ip))
+    }
+
+    /// Fail unless every address `url`'s host resolves to is permitted. A host that
+    /// doesn't resolve passes, as nothing can be sent to it anyway. Hosts are resolved
+    /// again for the request itself, so this is checked before every one.
+    pub async fn check(&self, url: &Url) -> Result<(), String> {
+        let host = url.host_str().unwrap_or_default();
+        // IPv6 hosts keep their brackets
+        let addresses: Vec<IpAddr> = match host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+            Ok(ip) => vec![ip],
+            Err(_) => match tokio::net::lookup_host((host, url.port_or_known_default().unwrap_or(80))).await {
+                Ok(addresses) => addresses.map(|address| address.ip()).collect(),
+                Err(_) => return Ok(()),
+            },
+        };
+        match addresses.into_iter().find(|ip| !self.permits(*ip)) {
+            Some(ip) => Err(format!(
+                "`{}` is {}, an internal address; add it to [outbound] allowed_networks to send there",
+                host, ip
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for OutboundGuard {
+    fn default() -> Self {
+        OutboundGuard::new(&[]).expect("internal networks parse")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(addr: &str) -> IpAddr {
+        addr.parse().unwrap()
+    }
+
+    #[test]
+    fn permits_only_public_addresses_by_default() {
+        let guard = OutboundGuard::default();
+        for internal in ["127.0.0.1", "10.1.2.3", "172.20.0.1", "192.168.1.1", "169.254.169.254", "100.64.0.1", "0.0.0.0", "::1", "fd00::1", "fe80::1", "::ffff:127.0.0.1"] {
+            assert!(!guard.permits(ip(internal)), "{} should be internal", internal);
+        }
+        for public in ["93.184.216.34", "8.8.8.8", "172.32.0.1", "2606:4700::1111"] {
+            assert!(guard.permits(ip(public)), "{} should be public", public);
+        }
+    }
+
+    #[test]
+    fn allowed_networks_open_internal_ranges() {
+        let guard = OutboundGuard::new(&["127.0.0.0/8".to_string(), "10.0.0.5".to_string()]).unwrap();
+        assert!(guard.permits(ip("127.0.0.1")));
+        assert!(guard.permits(ip("10.0.0.5")));
+        assert!(!guard.permits(ip("10.0.0.6")));
+        assert!(OutboundGuard::new(&["intranet".to_string()]).is_err());
+    }
+
+    #[tokio::test]
+    async fn checks_literal_and_resolved_hosts() {
+        let guard = OutboundGuard::default();
+        let url = |target: &str| Url::parse(target).unwrap();
+        assert!(guard.check(&url("http://127.0.0.1:8080/hook")).await.is_err());
+        assert!(guard.check(&url("http://[::1]/hook")).await.is_err());
+        assert!(guard.check(&url("http://169.254.169.254/latest/meta-data/")).await.is_err());
+        assert!(guard.check(&url("http://localhost:8080/hook")).await.is_err());
+        assert!(guard.check(&url("https://93.184.216.34/hook")).await.is_ok());
+    }
+}
@@ -0,0 +1,224 @@
+//! WASM plugins from `[plugins]`: modules every HTTP capture passes through before it is
+//! stored, which can rewrite it (custom redaction, enrichment) or keep it out of storage.
+//!
+//! A plugin is a core WebAssembly module without imports that exports its `memory`,
+//! `alloc(len: i32) -> i32` and `transform(ptr: i32, len: i32) -> i64`. The capture is
+//! written as JSON into memory from `alloc`, and `transform` answers with a pointer in the
+//! high 32 bits and a length in the low ones to a JSON [`Verdict`]; a length of 0 leaves
+//! the capture as it is.
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use uuid::Uuid;
+use wasmtime::{Config, Engine, InstancePre, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use crate::config::PluginsConfig;
+
+/// A capture as plugins see it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginCapture {
+    pub bin_id: String,
+    pub request_id: Uuid,
+    pub method: String,
+    pub path: String,
+    pub query_string: Option<String>,
+    /// `[name, value]` pairs in the order they were received
+    pub headers: Vec<(String, String)>,
+    /// `None` for binary bodies, which plugins can't change
+    pub body: Option<String>,
+    pub source_ip: Option<String>,
+}
+
+/// What a plugin answers; every field it leaves out keeps the capture's value
+#[derive(Debug, Default, Deserialize)]
+pub struct Verdict {
+    /// Keep the capture out of storage; it is still answered as usual
+    #[serde(default)]
+    pub drop: bool,
+    pub path: Option<String>,
+    pub query_string: Option<String>,
+    pub headers: Option<Vec<(String, String)>>,
+    /// Ignored for binary bodies
+    pub body: Option<String>,
+}
+
+struct Plugin {
+    name: String,
+    pre: InstancePre<StoreLimits>,
+}
+
+/// The `[plugins]` modules, compiled once at startup
+pub struct Plugins {
+    plugins: Vec<Plugin>,
+    fuel: u64,
+    max_memory_bytes: usize,
+}
+
+impl Plugins {
+    /// Compile every configured module, failing on the first that can't be loaded or
+    /// doesn't export what plugins need
+    pub fn from_config(config: &PluginsConfig) -> Result<Self, String> {
+        let mut plugins = Vec::with_capacity(config.modules.len());
+        if !config.modules.is_empty() {
+            let mut wasm = Config::new();
+            wasm.consume_fuel(true);
+            let engine = Engine::new(&wasm).map_err(|err| err.to_string())?;
+            let linker = Linker::new(&engine);
+            for module in &config.modules {
+                let compiled = Module::from_file(&engine, &module.path)
+                    .map_err(|err| format!("plugin `{}` ({}): {}", module.name, module.path, err))?;
+                for export in ["memory", "alloc", "transform"] {
+                    if compiled.get_export(export).is_none() {
+                        return Err(format!("plugin `{}` does not export `{}`", module.name, export));
+                    }
+                }
+                let pre = linker
+                    .instantiate_pre(&compiled)
+                    .map_err(|err| format!("plugin `{}`: {}", module.name, err))?;
+                plugins.push(Plugin { name: module.name.clone(), pre });
+            }
+        }
+        Ok(Plugins { plugins, fuel: config.fuel, max_memory_bytes: config.max_memory_bytes })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    /// Pass the capture through every plugin in order, each seeing the previous one's
+    /// changes. `None` when one of them drops it. A plugin that fails, runs out of fuel
+    /// or answers something unreadable is skipped.
+    pub fn run(&self, mut capture: PluginCapture) -> Option<PluginCapture> {
+        for plugin in &self.plugins {
+            let verdict = serde_json::to_vec(&capture)
+                .map_err(wasmtime::Error::from)
+                .and_then(|input| self.call(plugin, &input))
+                .and_then(|output| match output.is_empty() {
+                    true => Ok(Verdict::default()),
+                    false => serde_json::from_slice::<Verdict>(&output).map_err(wasmtime::Error::from),
+                });
+            match verdict {
+                Ok(verdict) if verdict.drop => {
+                    info!(plugin = %plugin.name, bin_id = %capture.bin_id, request_id = %capture.request_id, "Capture dropped by plugin");
+                    return None;
+                }
+                Ok(verdict) => verdict.apply(&mut capture),
+                Err(err) => warn!(plugin = %plugin.name, bin_id = %capture.bin_id, %err, "Plugin failed, skipping it"),
+            }
+        }
+        Some(capture)
+    }
+
+    /// Run one plugin on a fresh instance and read back its answer
+    fn call(&self, plugin: &Plugin, input: &[u8]) -> wasmtime::Result<Vec<u8>> {
+        let limits = StoreLimitsBuilder::new().memory_size(self.max_memory_bytes).build();
+        let mut store = Store::new(plugin.pre.module().engine(), limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(self.fuel)?;
+
+        let instance = plugin.pre.instantiate(&mut store)?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| wasmtime::Error::msg("`memory` is not a memory"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+        let transform = instance.get_typed_func::<(i32, i32), i64>(&mut store, "transform")?;
+
+        let len = i32::try_from(input.len())?;
+        let ptr = alloc.call(&mut store, len)?;
+        memory.write(&mut store, ptr as u32 as usize, input)?;
+        let packed = transform.call(&mut store, (ptr, len))? as u64;
+        let mut output = vec![0; (packed & 0xffff_ffff) as usize];
+        memory.read(&store, (packed >> 32) as usize, &mut output)?;
+        Ok(output)
+    }
+}
+
+impl Verdict {
+    fn apply(self, capture: &mut PluginCapture) {
+        if let Some(path) = self.path {
+            capture.path = path;
+        }
+        if let Some(query_string) = self.query_string {
+            capture.query_string = Some(query_string).filter(|query| !query.is_empty());
+        }
+        if let Some(headers) = self.headers {
+            capture.headers = headers;
+        }
+        if let (Some(body), Some(_)) = (self.body, &capture.body) {
+            capture.body = Some(body);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PluginModule;
+
+    /// A module answering every capture with `verdict`, or spinning forever without one
+    fn module(dir: &tempfile::TempDir, name: &str, verdict: Option<&str>) -> PluginModule {
+        let transform = match verdict {
+            Some(verdict) => format!("(i64.or (i64.shl (i64.const 16) (i64.const 32)) (i64.const {}))", verdict.len()),
+            None => "(loop $spin (br $spin)) (unreachable)".to_string(),
+        };
+        let wat = format!(
+            r#"(module
+                (memory (export "memory") 1)
+                (data (i32.const 16) "{}")
+                (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+                (func (export "transform") (param i32 i32) (result i64) {}))"#,
+            verdict.unwrap_or("").replace('"', "\\\""),
+            transform
+        );
+        let path = dir.path().join(format!("{}.wat", name));
+        std::fs::write(&path, wat).unwrap();
+        PluginModule { name: name.to_string(), path: path.to_string_lossy().into_owned() }
+    }
+
+    fn sample_capture() -> PluginCapture {
+        PluginCapture {
+            bin_id: "bin".to_string(),
+            request_id: Uuid::new_v4(),
+            method: "POST".to_string(),
+            path: "/".to_string(),
+            query_string: None,
+            headers: vec![("content-type".to_string(), "text/plain".to_string())],
+            body: Some("ssn 123-45-6789".to_string()),
+            source_ip: None,
+        }
+    }
+
+    #[test]
+    fn plugins_rewrite_and_drop_captures_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let scrub = module(&dir, "scrub", Some(r#"{"body": "[scrubbed]", "headers": [["x-plugin", "scrub"]]}"#));
+        let keep = module(&dir, "keep", Some(""));
+        let spin = module(&dir, "spin", None);
+        let drop = module(&dir, "drop", Some(r#"{"drop": true}"#));
+
+        let config = PluginsConfig { modules: vec![scrub.clone(), keep, spin], ..PluginsConfig::default() };
+        let capture = Plugins::from_config(&config).unwrap().run(sample_capture()).unwrap();
+        assert_eq!(capture.body.as_deref(), Some("[scrubbed]"));
+        assert_eq!(capture.headers, [("x-plugin".to_string(), "scrub".to_string())]);
+
+        let config = PluginsConfig { modules: vec![drop, scrub], ..PluginsConfig::default() };
+        assert_eq!(Plugins::from_config(&config).unwrap().run(sample_capture()), None);
+    }
+
+    #[test]
+    fn modules_must_load_and_export_the_plugin_interface() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = PluginModule { name: "missing".to_string(), path: "no/such/plugin.wasm".to_string() };
+        let config = PluginsConfig { modules: vec![missing], ..PluginsConfig::default() };
+        assert!(Plugins::from_config(&config).is_err());
+
+        let path = dir.path().join("empty.wat");
+        std::fs::write(&path, "(module)").unwrap();
+        let empty = PluginModule { name: "empty".to_string(), path: path.to_string_lossy().into_owned() };
+        let config = PluginsConfig { modules: vec![empty], ..PluginsConfig::default() };
+        let Err(err) = Plugins::from_config(&config) else {
+            panic!("a module without the plugin exports should be rejected");
+        };
+        assert!(err.contains("memory"));
+    }
+}
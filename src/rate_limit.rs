@@ -0,0 +1,248 @@
+//! Per-IP rate limits: one budget for most routes, optional ones of their own for creating
+//! bins and for captures, and addresses exempt from all of them.
+
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::{HeaderMap, HeaderValue, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use governor::clock::{Clock, DefaultClock, QuantaInstant};
+use governor::middleware::NoOpMiddleware;
+use ipnet::IpNet;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tower_governor::governor::{GovernorConfig, GovernorConfigBuilder};
+use tower_governor::key_extractor::KeyExtractor;
+use tower_governor::GovernorError;
+
+use crate::client_ip::{ClientIpKeyExtractor, TrustedProxies};
+use crate::config::{RateLimitBackend, RateLimitPolicy, RateLimitingConfig};
+use crate::handlers::{internal_error, ApiError};
+use crate::ip_access::parse_network;
+use crate::redis_rate_limit::RedisRateLimiter;
+
+pub type LocalConfig = GovernorConfig<ClientIpKeyExtractor, NoOpMiddleware<QuantaInstant>>;
+
+/// Which budget a request draws from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteBudget {
+    Create,
+    Capture,
+    Default,
+}
+
+impl RouteBudget {
+    /// Pick the budget from the route the request matched, e.g. `/bin/:id/*path`
+    pub fn for_route(method: &Method, route: &str) -> Self {
+        match route {
+            "/create" if method == Method::POST => RouteBudget::Create,
+            "/bin/:id" | "/bin/:id/*path" => RouteBudget::Capture,
+            _ => RouteBudget::Default,
+        }
+    }
+}
+
+enum Limiter {
+    Local(Arc<LocalConfig>),
+    Redis(Box<RedisRateLimiter>),
+}
+
+impl Limiter {
+    async fn check(&self, key: IpAddr) -> Option<Duration> {
+        match self {
+            Limiter::Local(config) => config
+                .limiter()
+                .check_key(&key)
+                .err()
+                .map(|negative| negative.wait_time_from(DefaultClock::default().now())),
+            Limiter::Redis(limiter) => limiter.check(key).await,
+        }
+    }
+}
+
+pub struct RateLimits {
+    keys: ClientIpKeyExtractor,
+    exempt: Vec<IpNet>,
+    default: Limiter,
+    create: Option<Limiter>,
+    capture: Option<Limiter>,
+    /// Kept for the cleanup task, including the fallbacks of the Redis limiters
+    local: Vec<Arc<LocalConfig>>,
+    /// Prefix of the matched routes when served under `[server] base_path`
+    base_path: String,
+}
+
+impl RateLimits {
+    pub fn new(config: &RateLimitingConfig, proxies: Arc<TrustedProxies>) -> Result<Self, String> {
+        let keys = ClientIpKeyExtractor::new(proxies);
+        let mut local = Vec::new();
+        let mut limiter = |policy: &RateLimitPolicy, scope: Option<&str>| -> Result<Limiter, String> {
+            let conf = Arc::new(
+                GovernorConfigBuilder::default()
+                    .key_extractor(keys.clone())
+                    .per_second(policy.requests_per_second.into())
+                    .burst_size(policy.burst_size)
+                    .finish()
+                    .ok_or_else(|| "requests_per_second and burst_size must be above 0".to_string())?,
+            );
+            local.push(conf.clone());
+            match config.backend {
+                RateLimitBackend::Memory => Ok(Limiter::Local(conf)),
+                RateLimitBackend::Redis => {
+                    let redis = config
+                        .redis
+                        .as_ref()
+                        .ok_or_else(|| "backend = \"redis\" needs a [rate_limiting.redis] section".to_string())?;
+                    Ok(Limiter::Redis(Box::new(RedisRateLimiter::new(redis, policy, scope, conf)?)))
+                }
+            }
+        };
+
+        let default = limiter(&config.default_policy(), None)?;
+        let create = config.create.as_ref().map(|policy| limiter(policy, Some("create"))).transpose()?;
+        let capture = config.capture.as_ref().map(|policy| limiter(policy, Some("capture"))).transpose()?;
+        Ok(RateLimits {
+            keys,
+            exempt: config.exempt.iter().map(|entry| parse_network(entry)).collect::<Result<_, _>>()?,
+            default,
+            create,
+            capture,
+            local,
+            base_path: String::new(),
+        })
+    }
+
+    /// Budgets for routes served under `base_path`
+    pub fn with_base_path(mut self, base_path: &str) -> Self {
+        self.base_path = base_path.to_string();
+        self
+    }
+
+    fn budget(&self, method: &Method, route: &str) -> RouteBudget {
+        RouteBudget::for_route(method, route.strip_prefix(self.base_path.as_str()).unwrap_or(route))
+    }
+
+    /// The in-process limiters, whose stale keys have to be evicted now and then
+    pub fn local_configs(&self) -> &[Arc<LocalConfig>] {
+        &self.local
+    }
+
+    pub fn is_exempt(&self, ip: IpAddr) -> bool {
+        self.exempt.iter().any(|network| network.contains(&ip))
+    }
+
+    /// How long the client has to wait before the budget allows another request, if at all
+    pub async fn check(&self, budget: RouteBudget, key: IpAddr) -> Option<Duration> {
+        if self.is_exempt(key) {
+            return None;
+        }
+        let limiter = match budget {
+            RouteBudget::Create => self.create.as_ref(),
+            RouteBudget::Capture => self.capture.as_ref(),
+            RouteBudget::Default => None,
+        };
+        limiter.unwrap_or(&self.default).check(key).await
+    }
+}
+
+/// Route layer enforcing the per-IP limits, answering like `GovernorLayer` does. It needs
+/// the matched route, so it has to be added with `Router::route_layer`.
+pub async fn limit_requests(State(limits): State<Arc<RateLimits>>, req: Request, next: Next) -> Response {
+    let Ok(key) = limits.keys.extract(&req) else {
+        return rate_limit_error(GovernorError::UnableToExtractKey);
+    };
+    let budget = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(RouteBudget::Default, |route| limits.budget(req.method(), route.as_str()));
+    match limits.check(budget, key).await {
+        None => next.run(req).await,
+        Some(wait) => {
+            let wait_time = wait.as_secs_f64().ceil() as u64;
+            let mut headers = HeaderMap::new();
+            headers.insert("x-ratelimit-after", HeaderValue::from(wait_time));
+            headers.insert("retry-after", HeaderValue::from(wait_time));
+            rate_limit_error(GovernorError::TooManyRequests { wait_time, headers: Some(headers) })
+        }
+    }
+}
+
+/// Answer a request turned away by the rate limiter with a problem document, keeping the
+/// limiter's headers
+pub fn rate_limit_error(error: GovernorError) -> Response {
+    match error {
+        GovernorError::TooManyRequests { wait_time, headers } => {
+            let detail = format!("Too many requests, wait for {}s", wait_time);
+            (headers.unwrap_or_default(), ApiError::new(StatusCode::TOO_MANY_REQUESTS, "rate_limited", detail)).into_response()
+        }
+        GovernorError::UnableToExtractKey => internal_error("Unable to identify the client".to_string()).into_response(),
+        GovernorError::Other { code, msg, headers } => {
+            let detail = msg.unwrap_or_else(|| code.canonical_reason().unwrap_or("Error").to_string());
+            (headers.unwrap_or_default(), ApiError::new(code, "rate_limited", detail)).into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RustbinConfig;
+
+    fn limits(config: RateLimitingConfig) -> RateLimits {
+        RateLimits::new(&config, Arc::new(TrustedProxies::default())).unwrap()
+    }
+
+    #[test]
+    fn picks_the_budget_from_the_matched_route() {
+        assert_eq!(RouteBudget::for_route(&Method::POST, "/create"), RouteBudget::Create);
+        assert_eq!(RouteBudget::for_route(&Method::OPTIONS, "/create"), RouteBudget::Default);
+        assert_eq!(RouteBudget::for_route(&Method::PUT, "/bin/:id"), RouteBudget::Capture);
+        assert_eq!(RouteBudget::for_route(&Method::GET, "/bin/:id/*path"), RouteBudget::Capture);
+        assert_eq!(RouteBudget::for_route(&Method::GET, "/bin/:id/requests"), RouteBudget::Default);
+
+        let limits = limits(RustbinConfig::default().rate_limiting).with_base_path("/hooks");
+        assert_eq!(limits.budget(&Method::POST, "/hooks/create"), RouteBudget::Create);
+        assert_eq!(limits.budget(&Method::GET, "/hooks/bin/:id/*path"), RouteBudget::Capture);
+    }
+
+    #[tokio::test]
+    async fn routes_with_their_own_policy_keep_separate_buckets() {
+        let limits = limits(RateLimitingConfig {
+            requests_per_second: 60,
+            burst_size: 1,
+            capture: Some(RateLimitPolicy { requests_per_second: 60, burst_size: 2 }),
+            ..RustbinConfig::default().rate_limiting
+        });
+        let client = IpAddr::from([192, 0, 2, 1]);
+
+        assert_eq!(limits.check(RouteBudget::Default, client).await, None);
+        assert!(limits.check(RouteBudget::Default, client).await.is_some());
+        // Creating bins has no policy of its own, so it shares the spent default budget
+        assert!(limits.check(RouteBudget::Create, client).await.is_some());
+        assert_eq!(limits.check(RouteBudget::Capture, client).await, None);
+        assert_eq!(limits.check(RouteBudget::Capture, client).await, None);
+        assert!(limits.check(RouteBudget::Capture, client).await.is_some());
+        assert_eq!(limits.local_configs().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn exempt_clients_are_never_limited() {
+        let limits = limits(RateLimitingConfig {
+            requests_per_second: 60,
+            burst_size: 1,
+            exempt: vec!["10.0.0.0/8".to_string(), "2001:db8::1".to_string()],
+            ..RustbinConfig::default().rate_limiting
+        });
+
+        for _ in 0..5 {
+            assert_eq!(limits.check(RouteBudget::Default, IpAddr::from([10, 1, 2, 3])).await, None);
+            assert_eq!(limits.check(RouteBudget::Default, "2001:db8::1".parse().unwrap()).await, None);
+        }
+        let other = IpAddr::from([192, 0, 2, 1]);
+        assert_eq!(limits.check(RouteBudget::Default, other).await, None);
+        assert!(limits.check(RouteBudget::Default, other).await.is_some());
+
+        let invalid = RateLimitingConfig { exempt: vec!["not-an-ip".to_string()], ..RustbinConfig::default().rate_limiting };
+        assert!(RateLimits::new(&invalid, Arc::new(TrustedProxies::default())).is_err());
+    }
+}
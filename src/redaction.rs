@@ -0,0 +1,128 @@
+//! Masking credentials in captured requests before they are stored.
+
+use regex::Regex;
+use std::borrow::Cow;
+
+use crate::config::RedactionConfig;
+use crate::models::RedactionRules;
+
+/// Header names and compiled body patterns to mask, with the mask they get
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    /// Lower case
+    headers: Vec<String>,
+    patterns: Vec<Regex>,
+    mask: String,
+}
+
+impl Redactor {
+    /// The global `[redaction]` settings. Fails on an invalid body pattern.
+    pub fn from_config(config: &RedactionConfig) -> Result<Self, regex::Error> {
+        let redactor = Redactor { headers: Vec::new(), patterns: Vec::new(), mask: config.mask.clone() };
+        redactor.with_rules(&RedactionRules {
+            headers: config.headers.clone(),
+            body_patterns: config.body_patterns.clone(),
+        })
+    }
+
+    /// This redactor extended with a bin's own rules
+    pub fn with_rules(&self, rules: &RedactionRules) -> Result<Self, regex::Error> {
+        let mut redactor = self.clone();
+        redactor.headers.extend(rules.headers.iter().map(|name| name.to_ascii_lowercase()));
+        for pattern in &rules.body_patterns {
+            redactor.patterns.push(Regex::new(pattern)?);
+        }
+        Ok(redactor)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty() && self.patterns.is_empty()
+    }
+
+    /// Mask the values of redacted headers, keeping every pair in place
+    pub fn redact_headers(&self, headers: &mut [(String, String)]) -> bool {
+        let mut redacted = false;
+        for (name, value) in headers.iter_mut() {
+            if self.headers.iter().any(|header| name.eq_ignore_ascii_case(header)) {
+                value.clone_from(&self.mask);
+                redacted = true;
+            }
+        }
+        redacted
+    }
+
+    /// Mask every match of the body patterns. A pattern with capture groups only masks
+    /// its first group, so `"password":"([^"]*)"` keeps the key.
+    pub fn redact_body<'a>(&self, body: &'a str) -> Cow<'a, str> {
+        let mut body = Cow::Borrowed(body);
+        for pattern in &self.patterns {
+            if !pattern.is_match(&body) {
+                continue;
+            }
+            let masked = pattern.replace_all(&body, |captures: &regex::Captures| {
+                let whole = captures.get(0).expect("group 0 always matches");
+                match captures.get(1) {
+                    Some(group) => {
+                        let text = whole.as_str();
+                        let start = group.start() - whole.start();
+                        let end = group.end() - whole.start();
+                        format!("{}{}{}", &text[..start], self.mask, &text[end..])
+                    }
+                    None if pattern.captures_len() > 1 => whole.as_str().to_string(),
+                    None => self.mask.clone(),
+                }
+            });
+            body = Cow::Owned(masked.into_owned());
+        }
+        body
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redactor(headers: &[&str], patterns: &[&str]) -> Redactor {
+        Redactor::from_config(&RedactionConfig {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            body_patterns: patterns.iter().map(|p| p.to_string()).collect(),
+            mask: "***".to_string(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn masks_headers_case_insensitively() {
+        let redactor = redactor(&["Authorization"], &[]);
+        let mut headers = vec![
+            ("authorization".to_string(), "Bearer secret".to_string()),
+            ("content-type".to_string(), "text/plain".to_string()),
+        ];
+        assert!(redactor.redact_headers(&mut headers));
+        assert_eq!(headers[0].1, "***");
+        assert_eq!(headers[1].1, "text/plain");
+    }
+
+    #[test]
+    fn masks_whole_matches_or_the_first_group() {
+        let redactor = redactor(&[], &[r"sk_live_\w+", r#""password":"([^"]*)""#]);
+        let body = r#"{"key":"sk_live_abc123","password":"hunter2","user":"ann"}"#;
+        assert_eq!(redactor.redact_body(body), r#"{"key":"***","password":"***","user":"ann"}"#);
+        assert!(matches!(redactor.redact_body("nothing to hide"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn bin_rules_extend_the_global_ones() {
+        let global = redactor(&["cookie"], &[]);
+        assert!(!global.is_empty());
+        let rules = RedactionRules { headers: vec!["X-Api-Key".to_string()], body_patterns: vec!["\\d{16}".to_string()] };
+        let combined = global.with_rules(&rules).unwrap();
+        let mut headers = vec![("cookie".to_string(), "a".to_string()), ("x-api-key".to_string(), "b".to_string())];
+        combined.redact_headers(&mut headers);
+        assert!(headers.iter().all(|(_, value)| value == "***"));
+        assert_eq!(combined.redact_body("card 4242424242424242"), "card ***");
+
+        let invalid = RedactionRules { headers: vec![], body_patterns: vec!["(".to_string()] };
+        assert!(global.with_rules(&invalid).is_err());
+    }
+}
@@ -0,0 +1,141 @@
+//! Per-IP rate limits shared by every instance through Redis (`[rate_limiting] backend = "redis"`).
+
+use governor::clock::{Clock, DefaultClock};
+use redis::{Script, Value};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{error, info, warn};
+
+use crate::config::{RateLimitPolicy, RedisConfig};
+use crate::rate_limit::LocalConfig;
+use crate::redis::RedisClient;
+
+/// GCRA over a theoretical arrival time in milliseconds: the same quota as governor's,
+/// kept in one key per client that expires once the client is back to a full burst.
+/// Returns 0 when the request is allowed, otherwise how many milliseconds to wait.
+const GCRA_SCRIPT: &str = r#"
+local now = tonumber(ARGV[1])
+local interval = tonumber(ARGV[2])
+local burst = tonumber(ARGV[3])
+local tat = math.max(tonumber(redis.call('GET', KEYS[1]) or now), now)
+local allow_at = tat + interval - burst * interval
+if now < allow_at then
+    return allow_at - now
+end
+redis.call('SET', KEYS[1], tat + interval, 'PX', tat + interval - now)
+return 0
+"#;
+
+pub struct RedisRateLimiter {
+    client: RedisClient,
+    script: Script,
+    key_prefix: String,
+    /// Milliseconds between requests at the sustained rate
+    interval_ms: u64,
+    burst_size: u32,
+    /// Used while Redis can't be reached, so an outage doesn't lift the limits
+    local: Arc<LocalConfig>,
+    /// Logs the switch to and from `local` once instead of per request
+    degraded: AtomicBool,
+}
+
+impl RedisRateLimiter {
+    /// Limits under `{key_prefix}ratelimit:`, followed by `{scope}:` for route budgets
+    /// of their own so they don't share keys with the default one
+    pub fn new(
+        redis: &RedisConfig,
+        policy: &RateLimitPolicy,
+        scope: Option<&str>,
+        local: Arc<LocalConfig>,
+    ) -> Result<Self, String> {
+        let key_prefix = match scope {
+            Some(scope) => format!("{}ratelimit:{}:", redis.key_prefix, scope),
+            None => format!("{}ratelimit:", redis.key_prefix),
+        };
+        Ok(RedisRateLimiter {
+            client: RedisClient::new(redis)?,
+            script: Script::new(GCRA_SCRIPT),
+            key_prefix,
+            // Same quota as `GovernorConfigBuilder::per_second`, which takes the seconds
+            // between requests, so switching backends doesn't change the limits
+            interval_ms: u64::from(policy.requests_per_second.max(1)) * 1000,
+            burst_size: policy.burst_size.max(1),
+            local,
+            degraded: AtomicBool::new(false),
+        })
+    }
+
+    /// How long the client has to wait, if at all
+    pub async fn check(&self, key: IpAddr) -> Option<Duration> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis().to_string();
+        let reply = match self.client.connection().await {
+            Ok(mut connection) => {
+                self.script
+                    .key(format!("{}{}", self.key_prefix, key))
+                    .arg(now)
+                    .arg(self.interval_ms)
+                    .arg(self.burst_size)
+                    .invoke_async::<Value>(&mut connection)
+                    .await
+            }
+            Err(err) => Err(err),
+        };
+
+        match reply {
+            Ok(Value::Int(wait_ms)) => {
+                if self.degraded.swap(false, Ordering::Relaxed) {
+                    info!("Redis is back, sharing rate limits again");
+                }
+                (wait_ms > 0).then(|| Duration::from_millis(wait_ms as u64))
+            }
+            other => {
+                if !self.degraded.swap(true, Ordering::Relaxed) {
+                    match other {
+                        Ok(value) => error!(?value, "Unexpected reply from the Redis rate limiter, limiting per instance"),
+                        Err(err) => warn!(%err, "Redis is unreachable, limiting per instance until it is back"),
+                    }
+                }
+                self.local
+                    .limiter()
+                    .check_key(&key)
+                    .err()
+                    .map(|negative| negative.wait_time_from(DefaultClock::default().now()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client_ip::{ClientIpKeyExtractor, TrustedProxies};
+    use crate::config::RustbinConfig;
+    use tokio::net::TcpListener;
+    use tower_governor::governor::GovernorConfigBuilder;
+
+    #[tokio::test]
+    async fn falls_back_to_local_limits_without_redis() {
+        let address = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+        let redis = RedisConfig { url: format!("redis://{}", address), key_prefix: "rustbin:".to_string(), timeout_ms: 200 };
+        let keys = ClientIpKeyExtractor::new(Arc::new(TrustedProxies::default()));
+        let local = Arc::new(
+            GovernorConfigBuilder::default()
+                .key_extractor(keys.clone())
+                .per_second(60)
+                .burst_size(2)
+                .finish()
+                .unwrap(),
+        );
+        let limiter = RedisRateLimiter::new(&redis, &RustbinConfig::default().rate_limiting.default_policy(), None, local).unwrap();
+
+        let client = IpAddr::from([192, 0, 2, 1]);
+        assert_eq!(limiter.check(client).await, None);
+        assert_eq!(limiter.check(client).await, None);
+        assert!(limiter.check(client).await.is_some());
+        // Other clients have their own allowance
+        assert_eq!(limiter.check(IpAddr::from([192, 0, 2, 2])).await, None);
+        assert!(limiter.degraded.load(Ordering::Relaxed));
+    }
+}
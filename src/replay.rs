@@ -0,0 +1,209 @@
+//! Re-sending captured requests to another server.
+
+use reqwest::{header::{HeaderName, HeaderValue}, redirect::Policy, Method, Url};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::ReplayConfig;
+use crate::export::replayable_headers;
+use crate::models::{LoggedRequest, ReplayOverrides, ReplayResponse};
+use crate::outbound::OutboundGuard;
+use crate::utils::body::{decode_body, encode_body, ENCODING_BASE64};
+
+#[derive(Debug)]
+pub enum ReplayError {
+    /// The target isn't an absolute http(s) URL
+    InvalidTarget(String),
+    /// The stored request can't be rebuilt (bad method or body encoding), or an override
+    /// is not valid
+    InvalidRequest(String),
+    /// The target couldn't be reached or answered garbage
+    Upstream(String),
+    /// The target's response is larger than the allowed size
+    ResponseTooLarge,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::InvalidTarget(message) => write!(f, "invalid target: {}", message),
+            ReplayError::InvalidRequest(message) => write!(f, "cannot replay request: {}", message),
+            ReplayError::Upstream(message) => write!(f, "upstream request failed: {}", message),
+            ReplayError::ResponseTooLarge => write!(f, "upstream response exceeds size limit"),
+        }
+    }
+}
+
+/// Sends stored requests to arbitrary URLs. Redirects are returned to the caller
+/// rather than followed.
+pub struct Replayer {
+    client: reqwest::Client,
+    max_response_bytes: usize,
+    guard: Option<Arc<OutboundGuard>>,
+}
+
+impl Replayer {
+    pub fn new(config: &ReplayConfig, max_response_bytes: usize) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_seconds))
+            .redirect(Policy::none())
+            .build()
+            .expect("Failed to build replay HTTP client");
+        Replayer { client, max_response_bytes, guard: None }
+    }
+
+    /// Refuse targets `guard` doesn't permit, for URLs set by bin owners rather than
+    /// the one running the server
+    pub fn with_guard(mut self, guard: Arc<OutboundGuard>) -> Self {
+        self.guard = Some(guard);
+        self
+    }
+
+    /// Send `request` with its method, headers and body to `url`, changed by `overrides`
+    pub async fn replay(&self, request: &LoggedRequest, url: &str, overrides: &ReplayOverrides) -> Result<ReplayResponse, ReplayError> {
+        let response = self.send(request, url, overrides).await?;
+        read_response(response, self.max_response_bytes, false).await
+    }
+
+    /// Like [`Replayer::replay`] but a body over `max_body_bytes` is cut short rather than
+    /// failing the delivery, since the target already accepted the request
+    pub async fn deliver(&self, request: &LoggedRequest, url: &str, max_body_bytes: usize) -> Result<ReplayResponse, ReplayError> {
+        let response = self.send(request, url, &ReplayOverrides::default()).await?;
+        read_response(response, max_body_bytes, true).await
+    }
+
+    async fn send(&self, request: &LoggedRequest, url: &str, overrides: &ReplayOverrides) -> Result<reqwest::Response, ReplayError> {
+        let url = parse_target(url)?;
+        if let Some(guard) = &self.guard {
+            guard.check(&url).await.map_err(ReplayError::InvalidTarget)?;
+        }
+        let method = overrides.method.as_deref().unwrap_or(&request.method);
+        let method = Method::from_bytes(method.as_bytes())
+            .map_err(|_| ReplayError::InvalidRequest(format!("unsupported method `{}`", method)))?;
+        let body = match &overrides.body {
+            Some(body) => body.clone().into_bytes(),
+            None => decode_body(request.body.as_deref().unwrap_or(""), request.body_encoding.as_deref())
+                .map_err(|err| ReplayError::InvalidRequest(format!("stored body is not valid base64: {}", err)))?,
+        };
+
+        let mut outgoing = self.client.request(method, url);
+        for (name, value) in headers(request, overrides)? {
+            outgoing = outgoing.header(name, value);
+        }
+        outgoing
+            .body(body)
+            .send()
+            .await
+            .map_err(|err| ReplayError::Upstream(err.to_string()))
+    }
+}
+
+/// The stored request's replayable headers with `overrides` applied
+fn headers(request: &LoggedRequest, overrides: &ReplayOverrides) -> Result<Vec<(HeaderName, HeaderValue)>, ReplayError> {
+    let mut headers = Vec::new();
+    for (name, value) in replayable_headers(request) {
+        // Captured headers that don't parse can't be sent anyway
+        if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(&value)) {
+            headers.push((name, value));
+        }
+    }
+    let Some(changes) = &overrides.headers else {
+        return Ok(headers);
+    };
+    for removed in &changes.remove {
+        headers.retain(|(name, _)| !name.as_str().eq_ignore_ascii_case(removed));
+    }
+    for (name, value) in &changes.set {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| ReplayError::InvalidRequest(format!("invalid header name `{}`", name)))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|_| ReplayError::InvalidRequest(format!("invalid value for header `{}`", name)))?;
+        headers.retain(|(existing, _)| *existing != name);
+        headers.push((name, value));
+    }
+    Ok(headers)
+}
+
+/// Read the status, headers and up to `limit` bytes of body. Past the limit the body is
+/// either truncated (at a character boundary, when it is text) or an error.
+async fn read_response(mut response: reqwest::Response, limit: usize, truncate: bool) -> Result<ReplayResponse, ReplayError> {
+    let status = response.status().as_u16();
+    let headers = response
+        .headers()
+        .iter()
+        .map(|(name, value)| (name.to_string(), String::from_utf8_lossy(value.as_bytes()).into_owned()))
+        .collect();
+
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|err| ReplayError::Upstream(err.to_string()))? {
+        if bytes.len() + chunk.len() > limit {
+            if !truncate {
+                return Err(ReplayError::ResponseTooLarge);
+            }
+            bytes.extend_from_slice(&chunk[..limit - bytes.len()]);
+            // Don't let a split character turn a text body into base64
+            if let Err(err) = std::str::from_utf8(&bytes)
+                && err.error_len().is_none()
+            {
+                bytes.truncate(err.valid_up_to());
+            }
+            break;
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    let (body, body_encoding) = encode_body(&bytes);
+
+    Ok(ReplayResponse { status, headers, body, body_encoding: body_encoding.to_string() })
+}
+
+/// `response` with its body cut to at most `max_bytes` bytes of the original, keeping
+/// text on a character boundary
+pub fn truncate_body(response: &ReplayResponse, max_bytes: usize) -> ReplayResponse {
+    let mut body = response.body.clone();
+    if response.body_encoding == ENCODING_BASE64 {
+        body.truncate(max_bytes / 3 * 4);
+    } else if body.len() > max_bytes {
+        let end = (0..=max_bytes).rev().find(|index| body.is_char_boundary(*index)).unwrap_or(0);
+        body.truncate(end);
+    }
+    ReplayResponse { body, ..response.clone() }
+}
+
+/// Check that `target` is an absolute http(s) URL
+pub fn parse_target(target: &str) -> Result<Url, ReplayError> {
+    let url = Url::parse(target).map_err(|err| ReplayError::InvalidTarget(format!("`{}`: {}", target, err)))?;
+    match url.scheme() {
+        "http" | "https" => Ok(url),
+        scheme => Err(ReplayError::InvalidTarget(format!("unsupported scheme `{}`", scheme))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_only_http_targets() {
+        assert!(parse_target("http://localhost:8080/hook").is_ok());
+        assert!(parse_target("https://example.com").is_ok());
+        assert!(matches!(parse_target("file:///etc/passwd"), Err(ReplayError::InvalidTarget(_))));
+        assert!(matches!(parse_target("localhost:8080"), Err(ReplayError::InvalidTarget(_))));
+        assert!(matches!(parse_target("/relative"), Err(ReplayError::InvalidTarget(_))));
+    }
+
+    #[test]
+    fn truncates_bodies_without_splitting_them() {
+        let response = |body: &str, body_encoding: &str| ReplayResponse {
+            status: 200,
+            headers: Vec::new(),
+            body: body.to_string(),
+            body_encoding: body_encoding.to_string(),
+        };
+        assert_eq!(truncate_body(&response("héllo", "utf8"), 2).body, "h");
+        assert_eq!(truncate_body(&response("héllo", "utf8"), 3).body, "hé");
+        assert_eq!(truncate_body(&response("short", "utf8"), 100).body, "short");
+        // Six bytes of base64 are eight characters
+        assert_eq!(truncate_body(&response("AAECAwQFBgcICQ==", "base64"), 7).body, "AAECAwQF");
+    }
+}
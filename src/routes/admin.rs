@@ -0,0 +1,46 @@
+use axum::{
+    extract::{Request, State},
+    http::header,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{delete, get},
+    Router,
+};
+use tracing::warn;
+
+use crate::{handlers, state::AppState};
+use crate::routes::auth::require_api_key;
+use crate::utils::token::{bearer_token, constant_time_eq};
+
+pub fn admin_routes(app_state: AppState) -> Router {
+    Router::new()
+        .route("/admin/search", get(handlers::admin_search))
+        .route("/admin/bins", get(handlers::admin_list_bins))
+        .route("/admin/bins/:id", delete(handlers::admin_delete_bin))
+        .route("/admin/stats", get(handlers::admin_stats))
+        .route("/admin/storage", get(handlers::admin_storage))
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), require_admin_token))
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), require_api_key))
+        .with_state(app_state)
+}
+
+/// Reject requests that don't carry `Authorization: Bearer <admin.token>`.
+async fn require_admin_token(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let Some(expected) = state.admin.token.as_deref() else {
+        return handlers::forbidden_error("Admin API is disabled".to_string()).with_code("admin_disabled").into_response();
+    };
+
+    let provided = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(bearer_token);
+
+    match provided {
+        Some(token) if constant_time_eq(token, expected) => next.run(req).await,
+        _ => {
+            warn!(path = %req.uri().path(), "Rejected admin request with missing or invalid token");
+            handlers::unauthorized_error("Invalid or missing admin token".to_string()).into_response()
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::warn;
+
+use crate::{handlers, state::AppState};
+use crate::utils::token::constant_time_eq;
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Require one of the configured `[auth] api_keys` in `X-Api-Key`.
+/// Does nothing when no keys are configured.
+pub async fn require_api_key(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    if state.auth.api_keys.is_empty() {
+        return next.run(req).await;
+    }
+
+    let provided = req
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok());
+
+    match provided {
+        Some(key) if state.auth.api_keys.iter().any(|expected| constant_time_eq(key, expected)) => {
+            next.run(req).await
+        }
+        _ => {
+            warn!(path = %req.uri().path(), "Rejected request with missing or invalid API key");
+            let response = handlers::unauthorized_error("Invalid or missing API key".to_string()).into_response();
+            handlers::add_cors_headers(response)
+        }
+    }
+}
@@ -1,26 +1,268 @@
 use axum::{
-    routing::{get, post, delete, any, options},
+    extract::{Path, Request, State},
+    http::{header, HeaderMap, Method},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post, put, patch, delete, any, options},
     Router,
 };
+use std::collections::HashMap;
+use tracing::{error, warn};
+use uuid::Uuid;
+
 use crate::{handlers, state::AppState};
+use crate::routes::auth::require_api_key;
+use crate::storage::StorageResult;
+use crate::utils::token::{bearer_token, constant_time_eq};
 use crate::websocket::ws_handler;
 
 pub fn bin_routes(app_state: AppState) -> Router {
-    Router::new()
-        .route("/create", post(handlers::create_bin))
-        .route("/bin/:id", options(handlers::log_request))  // Explicit OPTIONS handler
-        .route("/bin/:id", any(handlers::log_request))      // All other methods
+    // Reading or managing a private bin requires its token. Scripts and the routes that
+    // make the server send requests elsewhere also need an API key when keys are set.
+    let protected = Router::new()
         .route("/bin/:id/inspect", get(handlers::inspect_bin))
+        .route("/bin/:id/poll", get(handlers::poll_bin))
+        .route("/bin/:id/feed.atom", get(handlers::bin_feed))
+        .route("/bin/:id/search", get(handlers::search_bin))
+        .route("/bin/:id/meta", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route("/bin/:id/meta", get(handlers::get_bin_info))
+        .route("/bin/:id/meta", patch(handlers::update_bin_info))
+        .route("/bin/:id/response", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route("/bin/:id/response", get(handlers::get_bin_response))
+        .route("/bin/:id/response", put(handlers::set_bin_response))
+        .route("/bin/:id/response", delete(handlers::delete_bin_response))
+        .route("/bin/:id/response/sequence", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route("/bin/:id/response/sequence", get(handlers::get_response_sequence))
+        .route("/bin/:id/response/sequence", put(handlers::set_response_sequence))
+        .route("/bin/:id/response/sequence", delete(handlers::delete_response_sequence))
+        .route("/bin/:id/signature", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route("/bin/:id/signature", get(handlers::get_signature_verification))
+        .route("/bin/:id/signature", put(handlers::set_signature_verification))
+        .route("/bin/:id/signature", delete(handlers::delete_signature_verification))
+        .route("/bin/:id/redaction", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route("/bin/:id/redaction", get(handlers::get_redaction_rules))
+        .route("/bin/:id/redaction", put(handlers::set_redaction_rules))
+        .route("/bin/:id/redaction", delete(handlers::delete_redaction_rules))
+        .route("/bin/:id/access", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route("/bin/:id/access", get(handlers::get_ip_access_rules))
+        .route("/bin/:id/access", put(handlers::set_ip_access_rules))
+        .route("/bin/:id/access", delete(handlers::delete_ip_access_rules))
+        .route("/bin/:id/access/rejected", get(handlers::list_rejected_requests))
+        .route("/bin/:id/pause", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route("/bin/:id/pause", get(handlers::get_bin_pause))
+        .route("/bin/:id/pause", put(handlers::pause_bin))
+        .route("/bin/:id/pause", delete(handlers::resume_bin))
+        .route("/bin/:id/sampling", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route("/bin/:id/sampling", get(handlers::get_bin_sampling))
+        .route("/bin/:id/sampling", put(handlers::set_bin_sampling))
+        .route("/bin/:id/sampling", delete(handlers::delete_bin_sampling))
+        .route("/bin/:id/chaos", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route("/bin/:id/chaos", get(handlers::get_bin_chaos))
+        .route("/bin/:id/chaos", put(handlers::set_bin_chaos))
+        .route("/bin/:id/chaos", delete(handlers::delete_bin_chaos))
+        .route("/bin/:id/proxy", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route(
+            "/bin/:id/proxy",
+            get(handlers::get_bin_proxy)
+                .put(handlers::set_bin_proxy)
+                .delete(handlers::delete_bin_proxy)
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), require_api_key)),
+        )
+        .route("/bin/:id/script", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route(
+            "/bin/:id/script",
+            get(handlers::get_bin_script)
+                .put(handlers::set_bin_script)
+                .delete(handlers::delete_bin_script)
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), require_api_key)),
+        )
+        .route("/bin/:id/rate-limit", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route("/bin/:id/rate-limit", get(handlers::get_bin_rate_limit))
+        .route("/bin/:id/rate-limit", put(handlers::set_bin_rate_limit))
+        .route("/bin/:id/rate-limit", delete(handlers::delete_bin_rate_limit))
+        .route("/bin/:id/limits", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route("/bin/:id/limits", get(handlers::get_bin_limits))
+        .route("/bin/:id/limits", put(handlers::set_bin_limits))
+        .route("/bin/:id/limits", delete(handlers::delete_bin_limits))
+        .route("/bin/:id/notifications", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route("/bin/:id/notifications", get(handlers::get_bin_notifications))
+        .route("/bin/:id/notifications", put(handlers::set_bin_notifications))
+        .route("/bin/:id/notifications", delete(handlers::delete_bin_notifications))
+        .route("/bin/:id/extractors", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route("/bin/:id/extractors", get(handlers::get_extractors))
+        .route("/bin/:id/extractors", put(handlers::set_extractors))
+        .route("/bin/:id/rules", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route("/bin/:id/rules", get(handlers::list_response_rules))
+        .route("/bin/:id/rules", post(handlers::create_response_rule))
+        .route("/bin/:id/rules", put(handlers::replace_response_rules))
+        .route("/bin/:id/rules/:rule_id", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route("/bin/:id/rules/:rule_id", put(handlers::update_response_rule))
+        .route("/bin/:id/rules/:rule_id", delete(handlers::delete_response_rule))
+        .route("/bin/:id/export", get(handlers::export_bin))
+        .route("/bin/:id/diff", get(handlers::diff_requests))
+        .route("/bin/:id/import", post(handlers::import_requests))
+        .route("/bin/:id/simulate", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route("/bin/:id/simulate", post(handlers::simulate_request))
+        .route("/bin/:id/request/:rid", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route("/bin/:id/request/:rid", delete(handlers::delete_bin_request))
+        .route("/bin/:id/request/:rid/body", get(handlers::get_request_body))
+        .route("/bin/:id/request/:rid/curl", get(handlers::get_request_curl))
+        .route("/bin/:id/request/:rid/snippet", get(handlers::get_request_snippet))
+        .route("/bin/:id/request/:rid/deliveries", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route("/bin/:id/request/:rid/deliveries", get(handlers::list_request_deliveries))
+        .route("/bin/:id/request/:rid/deliveries", post(handlers::report_request_delivery))
+        .route("/bin/:id/request/:rid/replay", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route("/bin/:id/request/:rid/replay", post(handlers::replay_request))
+        .route("/bin/:id/request/:rid/pin", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route("/bin/:id/request/:rid/pin", post(handlers::pin_request))
+        .route("/bin/:id/request/:rid/pin", delete(handlers::unpin_request))
+        .route("/bin/:id/forwards", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route(
+            "/bin/:id/forwards",
+            get(handlers::list_forwards)
+                .post(handlers::create_forward)
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), require_api_key)),
+        )
+        .route("/bin/:id/forwards/:fid", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route(
+            "/bin/:id/forwards/:fid",
+            patch(handlers::update_forward)
+                .delete(handlers::delete_forward)
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), require_api_key)),
+        )
+        .route(
+            "/bin/:id/forwards/:fid/deliveries",
+            get(handlers::list_forward_deliveries)
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), require_api_key)),
+        )
+        .route("/bin/:id/callbacks", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route(
+            "/bin/:id/callbacks",
+            get(handlers::list_callbacks)
+                .post(handlers::create_callback)
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), require_api_key)),
+        )
+        .route("/bin/:id/callbacks/:cid", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route(
+            "/bin/:id/callbacks/:cid",
+            delete(handlers::delete_callback)
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), require_api_key)),
+        )
         .route("/bin/:id/clear", options(handlers::options_handler))  // OPTIONS for CORS preflight
         .route("/bin/:id/clear", delete(handlers::clear_bin_requests))  // Clear all requests
-        .route("/delete/:id", delete(handlers::delete_bin))
+        .route(
+            "/delete/:id",
+            delete(handlers::delete_bin)
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), require_api_key)),
+        )
+        .route(
+            "/bin/:id/restore",
+            post(handlers::restore_bin)
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), require_api_key)),
+        )
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), require_bin_token));
+
+    // Deprecated in favour of DELETE /bin/:id/request/:rid
+    let request_routes = Router::new()
         .route("/request/:id", options(handlers::options_handler))  // OPTIONS for CORS preflight
         .route("/request/:id", delete(handlers::delete_request))
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), require_request_bin_token));
+
+    // Creating and deleting bins needs an API key when `[auth] api_keys` is configured
+    Router::new()
+        .route(
+            "/create",
+            post(handlers::create_bin)
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), require_api_key)),
+        )
+        .route("/my/bins", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route("/my/bins", get(handlers::list_creator_bins))
+        .merge(protected)
+        .merge(request_routes)
+        .with_state(app_state)
+}
+
+/// Captures, kept apart from the other bin routes so they skip response compression
+pub fn capture_routes(app_state: AppState) -> Router {
+    Router::new()
+        .route("/bin/:id", options(handlers::log_request))  // Explicit OPTIONS handler
+        .route("/bin/:id", any(handlers::log_request))      // All other methods
+        .route("/bin/:id/*path", any(handlers::log_request_with_path))  // Any sub-path below the bin
         .with_state(app_state)
 }
 
 pub fn websocket_routes(app_state: AppState) -> Router {
     Router::new()
         .route("/bin/:id/ws", get(ws_handler))
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), require_bin_token))
         .with_state(app_state)
 }
+
+/// Guard routes whose `:id` is a bin id.
+async fn require_bin_token(
+    State(state): State<AppState>,
+    Path(params): Path<HashMap<String, String>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let bin_id = params.get("id").cloned().unwrap_or_default();
+    let expected = state.storage.bin_token(&bin_id).await;
+    check_bin_token(expected, req, next).await
+}
+
+/// Guard routes whose `:id` is a request id, using the token of the bin it was captured in.
+async fn require_request_bin_token(
+    State(state): State<AppState>,
+    Path(request_id): Path<String>,
+    req: Request,
+    next: Next,
+) -> Response {
+    // Malformed ids are rejected by the handler itself
+    let expected = match Uuid::parse_str(&request_id) {
+        Ok(request_uuid) => state.storage.request_bin_token(request_uuid).await,
+        Err(_) => Ok(None),
+    };
+    check_bin_token(expected, req, next).await
+}
+
+/// Let the request through unless the bin is private and the caller didn't present its
+/// token as `Authorization: Bearer <token>` or `?token=<token>`. Unknown bins are left
+/// to the handler so it can answer 404.
+async fn check_bin_token(expected: StorageResult<Option<Option<String>>>, req: Request, next: Next) -> Response {
+    // CORS preflights never carry credentials
+    if req.method() == Method::OPTIONS {
+        return next.run(req).await;
+    }
+
+    let expected = match expected {
+        Ok(Some(Some(token))) => token,
+        Ok(_) => return next.run(req).await,
+        Err(err) => {
+            error!(path = %req.uri().path(), %err, "Failed to look up bin token");
+            let response = handlers::internal_error("Failed to check bin access".to_string()).into_response();
+            return handlers::add_cors_headers(response);
+        }
+    };
+
+    match presented_token(req.headers(), req.uri().query()) {
+        Some(token) if constant_time_eq(&token, &expected) => next.run(req).await,
+        _ => {
+            warn!(path = %req.uri().path(), "Rejected private bin request with missing or invalid token");
+            let response = handlers::unauthorized_error("Invalid or missing bin token".to_string()).into_response();
+            handlers::add_cors_headers(response)
+        }
+    }
+}
+
+/// The bin token sent as `Authorization: Bearer <token>` or `?token=<token>`
+pub(crate) fn presented_token(headers: &HeaderMap, query: Option<&str>) -> Option<String> {
+    let from_header = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(bearer_token)
+        .map(str::to_string);
+    let from_query = query
+        .and_then(|q| serde_urlencoded::from_str::<Vec<(String, String)>>(q).ok())
+        .and_then(|pairs| pairs.into_iter().find(|(key, _)| key == "token").map(|(_, value)| value));
+    from_header.or(from_query)
+}
@@ -0,0 +1,126 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    BoxError,
+};
+use futures::{stream, Stream, StreamExt};
+use std::fmt;
+use tokio::time::Instant;
+use tracing::warn;
+
+use crate::{handlers, state::AppState};
+
+/// Why a request body stopped being read before its end
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyReadError {
+    /// The client didn't finish sending it within `[server] request_read_timeout_seconds`
+    TimedOut,
+    /// It grew past the largest body any bin accepts
+    TooLarge,
+}
+
+impl fmt::Display for BodyReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BodyReadError::TimedOut => write!(f, "request body was not received in time"),
+            BodyReadError::TooLarge => write!(f, "request body exceeds size limit"),
+        }
+    }
+}
+
+impl std::error::Error for BodyReadError {}
+
+impl BodyReadError {
+    /// The reason a body read failed, when it was this middleware that cut it short
+    pub fn from_body_error(err: &axum::Error) -> Option<Self> {
+        let mut source: Option<&(dyn std::error::Error + 'static)> = Some(err);
+        while let Some(err) = source {
+            if let Some(read_error) = err.downcast_ref::<BodyReadError>() {
+                return Some(*read_error);
+            }
+            source = err.source();
+        }
+        None
+    }
+}
+
+/// Bound how long a client may take to send its body and how large it may get, so a
+/// slow or endless upload can't hold a connection and its memory forever. Bodies that
+/// announce a `Content-Length` over the limit are rejected before any of them is read.
+pub async fn limit_request_body(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let limits = state.limits.load();
+    // Bins may raise their own limit up to `bin_max_body_size`; handlers apply the exact one
+    let max_size = limits.max_body_size.max(limits.bin_max_body_size);
+    let declared = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    if declared.is_some_and(|declared| declared > max_size) {
+        warn!(path = %req.uri().path(), declared, max_allowed = max_size, "Rejected request with oversized Content-Length");
+        let error = handlers::payload_too_large_error("Request body exceeds size limit".to_string());
+        return handlers::add_cors_headers(error.into_response());
+    }
+
+    let deadline = (!state.read_timeout.is_zero()).then(|| Instant::now() + state.read_timeout);
+    let req = req.map(|body| Body::from_stream(guard_body(body, deadline, max_size)));
+    next.run(req).await
+}
+
+/// The body's chunks, ending with an error once `deadline` passes or `max_size` is exceeded
+fn guard_body(body: Body, deadline: Option<Instant>, max_size: usize) -> impl Stream<Item = Result<Bytes, BoxError>> {
+    let chunks = body.into_data_stream();
+    stream::unfold(Some((chunks, 0usize)), move |state| async move {
+        let (mut chunks, received) = state?;
+        let next = match deadline {
+            Some(deadline) => match tokio::time::timeout_at(deadline, chunks.next()).await {
+                Ok(next) => next,
+                Err(_) => return Some((Err(BodyReadError::TimedOut.into()), None)),
+            },
+            None => chunks.next().await,
+        };
+        match next? {
+            Ok(chunk) => {
+                let received = received + chunk.len();
+                if received > max_size {
+                    return Some((Err(BodyReadError::TooLarge.into()), None));
+                }
+                Some((Ok(chunk), Some((chunks, received))))
+            }
+            Err(err) => Some((Err(err.into()), None)),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::BodyExt;
+    use std::time::Duration;
+
+    async fn read(body: Body, deadline: Option<Instant>, max_size: usize) -> Result<Bytes, axum::Error> {
+        Body::from_stream(guard_body(body, deadline, max_size)).collect().await.map(|collected| collected.to_bytes())
+    }
+
+    #[tokio::test]
+    async fn passes_bodies_within_limits() {
+        let deadline = Some(Instant::now() + Duration::from_secs(5));
+        assert_eq!(read(Body::from("hello"), deadline, 5).await.unwrap(), "hello");
+    }
+
+    #[tokio::test]
+    async fn stops_at_the_size_limit() {
+        let err = read(Body::from("hello!"), None, 5).await.unwrap_err();
+        assert_eq!(BodyReadError::from_body_error(&err), Some(BodyReadError::TooLarge));
+    }
+
+    #[tokio::test]
+    async fn stops_a_body_that_never_ends() {
+        let endless = Body::from_stream(stream::pending::<Result<Bytes, BoxError>>());
+        let err = read(endless, Some(Instant::now() + Duration::from_millis(20)), 5).await.unwrap_err();
+        assert_eq!(BodyReadError::from_body_error(&err), Some(BodyReadError::TimedOut));
+    }
+}
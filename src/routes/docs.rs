@@ -0,0 +1,14 @@
+use axum::{routing::get, Json, Router};
+use utoipa::openapi::server::Server;
+use utoipa::OpenApi;
+
+use crate::openapi::ApiDoc;
+
+/// The API description, with the base path as its server when there is one
+pub fn docs_routes(base_path: &str) -> Router {
+    let mut doc = ApiDoc::openapi();
+    if !base_path.is_empty() {
+        doc.servers = Some(vec![Server::new(base_path)]);
+    }
+    Router::new().route("/openapi.json", get(move || std::future::ready(Json(doc.clone()))))
+}
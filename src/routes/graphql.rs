@@ -0,0 +1,98 @@
+use async_graphql::http::{GraphiQLSource, WebSocket as GraphQLWebSocket, WebSocketProtocols, WsMessage, ALL_WEBSOCKET_PROTOCOLS};
+use async_graphql::Data;
+use axum::{
+    extract::{rejection::JsonRejection, ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade}, RawQuery, State},
+    http::{header, HeaderMap},
+    response::{Html, IntoResponse, Response},
+    routing::{get, options},
+    Json, Router,
+};
+use futures::{future, SinkExt, StreamExt};
+
+use crate::graphql::{self, BinToken, RustbinSchema};
+use crate::handlers::{self, add_cors_headers};
+use crate::routes::bin::presented_token;
+use crate::state::AppState;
+
+pub fn graphql_routes(app_state: AppState) -> Router {
+    Router::new()
+        .route("/graphql", options(handlers::options_handler))  // OPTIONS for CORS preflight
+        .route("/graphql", get(graphiql).post(execute))
+        .with_state(graphql::schema(app_state))
+}
+
+/// Subscriptions, kept apart like the other WebSocket routes so rate limits don't apply
+pub fn graphql_websocket_routes(app_state: AppState) -> Router {
+    Router::new()
+        .route("/graphql/ws", get(subscribe))
+        .with_state(graphql::schema(app_state))
+}
+
+/// GraphiQL, to explore the schema from a browser
+async fn graphiql() -> Html<String> {
+    Html(GraphiQLSource::build().endpoint("/graphql").subscription_endpoint("/graphql/ws").finish())
+}
+
+async fn execute(
+    State(schema): State<RustbinSchema>,
+    headers: HeaderMap,
+    RawQuery(query): RawQuery,
+    payload: Result<Json<async_graphql::Request>, JsonRejection>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    let Json(request) = payload
+        .map_err(|rejection| add_cors_headers(handlers::bad_request_error(rejection.body_text()).into_response()))?;
+    let token = BinToken(presented_token(&headers, query.as_deref()));
+    let response = schema.execute(request.data(token)).await;
+    Ok::<_, Response>(add_cors_headers(Json(response).into_response()))
+}
+
+async fn subscribe(
+    State(schema): State<RustbinSchema>,
+    headers: HeaderMap,
+    RawQuery(query): RawQuery,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let protocol = headers
+        .get(header::SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|protocols| protocols.split(',').find_map(|protocol| protocol.trim().parse::<WebSocketProtocols>().ok()));
+    let Some(protocol) = protocol else {
+        let message = "Use the graphql-transport-ws or graphql-ws WebSocket subprotocol";
+        return handlers::bad_request_error(message.to_string()).into_response();
+    };
+    let token = presented_token(&headers, query.as_deref());
+    ws.protocols(ALL_WEBSOCKET_PROTOCOLS)
+        .on_upgrade(move |socket| serve_subscriptions(socket, schema, protocol, token))
+}
+
+async fn serve_subscriptions(socket: WebSocket, schema: RustbinSchema, protocol: WebSocketProtocols, token: Option<String>) {
+    let (mut outgoing, incoming) = socket.split();
+    let incoming = incoming
+        .take_while(|frame| future::ready(matches!(frame, Ok(frame) if !matches!(frame, Message::Close(_)))))
+        .filter_map(|frame| {
+            future::ready(match frame {
+                Ok(Message::Text(text)) => Some(text.into_bytes()),
+                Ok(Message::Binary(data)) => Some(data),
+                _ => None,
+            })
+        });
+
+    let messages = GraphQLWebSocket::new(schema, incoming, protocol).on_connection_init(move |payload| async move {
+        // Browsers can't set headers on WebSockets, so the token may come with `connection_init`
+        let token = payload.get("token").and_then(|token| token.as_str()).map(str::to_string).or(token);
+        let mut data = Data::default();
+        data.insert(BinToken(token));
+        Ok(data)
+    });
+    let mut messages = std::pin::pin!(messages);
+
+    while let Some(message) = messages.next().await {
+        let frame = match message {
+            WsMessage::Text(text) => Message::Text(text),
+            WsMessage::Close(code, reason) => Message::Close(Some(CloseFrame { code, reason: reason.into() })),
+        };
+        if outgoing.send(frame).await.is_err() {
+            break;
+        }
+    }
+}
@@ -1,6 +1,10 @@
 use axum::{routing::get, Router};
-use crate::handlers;
+use crate::{handlers, state::AppState};
 
-pub fn health_routes() -> Router {
-    Router::new().route("/ping", get(handlers::ping))
+pub fn health_routes(app_state: AppState) -> Router {
+    Router::new()
+        .route("/ping", get(handlers::ping))
+        .route("/healthz", get(handlers::healthz))
+        .route("/readyz", get(handlers::readyz))
+        .with_state(app_state)
 }
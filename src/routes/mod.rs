@@ -1,2 +1,22 @@
+pub mod admin;
+pub mod auth;
 pub mod bin;
+pub mod body_limit;
+pub mod docs;
+pub mod graphql;
 pub mod health;
+pub mod ui;
+
+use axum::{response::Redirect, routing::get, Router};
+
+/// Serve `app` under `[server] base_path`, or at the root when it's empty. The base path
+/// with a trailing slash redirects to the UI at the base path itself.
+pub fn under_base_path(base_path: &str, app: Router) -> Router {
+    if base_path.is_empty() {
+        return app;
+    }
+    let home = base_path.to_string();
+    Router::new()
+        .nest(base_path, app)
+        .route(&format!("{}/", base_path), get(move || std::future::ready(Redirect::permanent(&home))))
+}
@@ -0,0 +1,47 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use handlebars::html_escape;
+use rust_embed::RustEmbed;
+use std::sync::Arc;
+
+/// The web UI, compiled into the binary from `ui/`
+#[derive(RustEmbed)]
+#[folder = "ui/"]
+struct Assets;
+
+/// The UI's pages and the assets they load. Every page is the same document; the script
+/// picks the view from the URL.
+pub fn ui_routes(base_path: &str) -> Router {
+    Router::new()
+        .route("/", get(page))
+        .route("/ui/bin/:id", get(page))
+        .route("/ui/assets/*file", get(asset))
+        .with_state(Arc::from(base_path))
+}
+
+/// The page links to everything under the base path
+async fn page(State(base_path): State<Arc<str>>) -> Response {
+    match Assets::get("index.html") {
+        Some(file) => Html(String::from_utf8_lossy(&file.data).replace("%BASE_PATH%", &html_escape(&base_path))).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Assets are revalidated with their content hash, so a new build is picked up at once
+async fn asset(Path(file): Path<String>, headers: HeaderMap) -> Response {
+    let Some(asset) = Assets::get(&file) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let etag = format!("\"{}\"", hex::encode(asset.metadata.sha256_hash()));
+    let cache = [(header::ETAG, etag.clone()), (header::CACHE_CONTROL, "no-cache".to_string())];
+    if headers.get(header::IF_NONE_MATCH).is_some_and(|value| value.as_bytes() == etag.as_bytes()) {
+        return (StatusCode::NOT_MODIFIED, cache).into_response();
+    }
+    let content_type = asset.metadata.mimetype().to_string();
+    (cache, [(header::CONTENT_TYPE, content_type)], asset.data).into_response()
+}
@@ -0,0 +1,77 @@
+//! Choosing which response rule answers a captured request.
+
+use crate::models::ResponseRule;
+
+/// The first rule whose method and path both match
+pub fn select_rule<'a>(rules: &'a [ResponseRule], method: &str, path: &str) -> Option<&'a ResponseRule> {
+    rules.iter().find(|rule| {
+        rule.method.as_deref().is_none_or(|expected| expected.eq_ignore_ascii_case(method))
+            && rule.path.as_deref().is_none_or(|pattern| glob_match(pattern, path))
+    })
+}
+
+/// Match `text` against `pattern`, where `*` stands for any run of characters
+/// (including `/`) and everything else must match exactly
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // split always yields at least one part
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let middle: Vec<&str> = parts.collect();
+    let Some((last, middle)) = middle.split_last() else {
+        // No `*` at all
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CustomResponse;
+
+    fn rule(id: &str, method: Option<&str>, path: Option<&str>) -> ResponseRule {
+        ResponseRule {
+            rule_id: id.to_string(),
+            method: method.map(str::to_string),
+            path: path.map(str::to_string),
+            response: CustomResponse { status: 200, headers: Default::default(), body: String::new() },
+        }
+    }
+
+    #[test]
+    fn glob_matches_stars_anywhere() {
+        assert!(glob_match("/orders", "/orders"));
+        assert!(!glob_match("/orders", "/orders/7"));
+        assert!(glob_match("/orders/*", "/orders/7"));
+        assert!(glob_match("/orders/*", "/orders/7/items"));
+        assert!(!glob_match("/orders/*", "/order"));
+        assert!(glob_match("/orders/*/items", "/orders/7/items"));
+        assert!(!glob_match("/orders/*/items", "/orders/7/lines"));
+        assert!(glob_match("*.json", "/export/data.json"));
+        assert!(glob_match("/a*a", "/aa"));
+        assert!(!glob_match("/a*a", "/a"));
+        assert!(glob_match("*", "/"));
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let rules = vec![
+            rule("create", Some("POST"), Some("/orders/*")),
+            rule("orders", None, Some("/orders/*")),
+            rule("fallback", None, None),
+        ];
+        assert_eq!(select_rule(&rules, "POST", "/orders/7").unwrap().rule_id, "create");
+        assert_eq!(select_rule(&rules, "get", "/orders/7").unwrap().rule_id, "orders");
+        assert_eq!(select_rule(&rules, "POST", "/").unwrap().rule_id, "fallback");
+        assert!(select_rule(&rules[..2], "POST", "/").is_none());
+    }
+}
@@ -0,0 +1,208 @@
+//! Made-up webhook deliveries for `POST /bin/:id/simulate`, shaped like what the real
+//! providers send so integrations can be tried without setting the provider up.
+
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use uuid::Uuid;
+
+/// Payloads accepted by `POST /bin/:id/simulate?provider=...`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleProvider {
+    /// A GitHub `push` event with one commit
+    GithubPush,
+    /// A Stripe `invoice.paid` event
+    StripeInvoicePaid,
+    /// A small JSON event with no provider specifics
+    Generic,
+}
+
+impl SampleProvider {
+    pub fn parse(provider: &str) -> Option<Self> {
+        match provider.to_ascii_lowercase().as_str() {
+            "github.push" => Some(SampleProvider::GithubPush),
+            "stripe.invoice.paid" => Some(SampleProvider::StripeInvoicePaid),
+            "generic" => Some(SampleProvider::Generic),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SampleProvider::GithubPush => "github.push",
+            SampleProvider::StripeInvoicePaid => "stripe.invoice.paid",
+            SampleProvider::Generic => "generic",
+        }
+    }
+}
+
+/// A request as the provider would send it, minus any signature
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub headers: Vec<(&'static str, String)>,
+    pub body: String,
+}
+
+/// A fresh sample with new ids, dated `now`
+pub fn sample(provider: SampleProvider, now: DateTime<Utc>) -> Sample {
+    match provider {
+        SampleProvider::GithubPush => github_push(now),
+        SampleProvider::StripeInvoicePaid => stripe_invoice_paid(now),
+        SampleProvider::Generic => generic(now),
+    }
+}
+
+/// A random lowercase hex string of `len` characters
+fn hex_id(len: usize) -> String {
+    let mut id = String::new();
+    while id.len() < len {
+        id.push_str(&Uuid::new_v4().simple().to_string());
+    }
+    id.truncate(len);
+    id
+}
+
+/// Stripe style `<prefix>_<24 alphanumerics>` object id
+fn stripe_id(prefix: &str) -> String {
+    format!("{}_{}", prefix, &Uuid::new_v4().simple().to_string()[..24])
+}
+
+fn github_push(now: DateTime<Utc>) -> Sample {
+    let before = hex_id(40);
+    let after = hex_id(40);
+    let timestamp = now.to_rfc3339();
+    let commit = json!({
+        "id": after,
+        "tree_id": hex_id(40),
+        "distinct": true,
+        "message": "Fix typo in README",
+        "timestamp": timestamp,
+        "url": format!("https://github.com/octo-org/hello-world/commit/{}", after),
+        "author": { "name": "Mona Octocat", "email": "mona@example.com", "username": "octocat" },
+        "committer": { "name": "Mona Octocat", "email": "mona@example.com", "username": "octocat" },
+        "added": [],
+        "removed": [],
+        "modified": ["README.md"],
+    });
+    let body = json!({
+        "ref": "refs/heads/main",
+        "before": before,
+        "after": after,
+        "created": false,
+        "deleted": false,
+        "forced": false,
+        "compare": format!("https://github.com/octo-org/hello-world/compare/{}...{}", &before[..12], &after[..12]),
+        "commits": [commit],
+        "head_commit": commit,
+        "repository": {
+            "id": 1296269,
+            "name": "hello-world",
+            "full_name": "octo-org/hello-world",
+            "private": false,
+            "owner": { "login": "octo-org", "id": 6811672, "type": "Organization" },
+            "html_url": "https://github.com/octo-org/hello-world",
+            "default_branch": "main",
+            "pushed_at": now.timestamp(),
+        },
+        "pusher": { "name": "octocat", "email": "mona@example.com" },
+        "sender": { "login": "octocat", "id": 583231, "type": "User" },
+    });
+    Sample {
+        headers: vec![
+            ("content-type", "application/json".to_string()),
+            ("user-agent", format!("GitHub-Hookshot/{}", hex_id(7))),
+            ("x-github-event", "push".to_string()),
+            ("x-github-delivery", Uuid::new_v4().to_string()),
+            ("x-github-hook-id", "421337001".to_string()),
+            ("x-github-hook-installation-target-type", "repository".to_string()),
+            ("x-github-hook-installation-target-id", "1296269".to_string()),
+        ],
+        body: body.to_string(),
+    }
+}
+
+fn stripe_invoice_paid(now: DateTime<Utc>) -> Sample {
+    let created = now.timestamp();
+    let customer = stripe_id("cus");
+    let body = json!({
+        "id": stripe_id("evt"),
+        "object": "event",
+        "api_version": "2024-06-20",
+        "created": created,
+        "type": "invoice.paid",
+        "livemode": false,
+        "pending_webhooks": 1,
+        "request": { "id": null, "idempotency_key": null },
+        "data": {
+            "object": {
+                "id": stripe_id("in"),
+                "object": "invoice",
+                "account_country": "US",
+                "amount_due": 2000,
+                "amount_paid": 2000,
+                "amount_remaining": 0,
+                "billing_reason": "subscription_cycle",
+                "collection_method": "charge_automatically",
+                "created": created,
+                "currency": "usd",
+                "customer": customer,
+                "customer_email": "jenny.rosen@example.com",
+                "hosted_invoice_url": format!("https://invoice.stripe.com/i/acct_test/{}", hex_id(24)),
+                "livemode": false,
+                "number": format!("{}-0001", hex_id(8).to_ascii_uppercase()),
+                "paid": true,
+                "status": "paid",
+                "subscription": stripe_id("sub"),
+                "subtotal": 2000,
+                "total": 2000,
+                "period_start": created - 30 * 24 * 60 * 60,
+                "period_end": created,
+            }
+        },
+    });
+    Sample {
+        headers: vec![
+            ("content-type", "application/json; charset=utf-8".to_string()),
+            ("user-agent", "Stripe/1.0 (+https://stripe.com/docs/webhooks)".to_string()),
+            ("cache-control", "no-cache".to_string()),
+        ],
+        body: body.to_string(),
+    }
+}
+
+fn generic(now: DateTime<Utc>) -> Sample {
+    let body = json!({
+        "id": Uuid::new_v4(),
+        "event": "sample.created",
+        "timestamp": now.to_rfc3339(),
+        "data": {
+            "message": "Hello from rustbin",
+            "amount": 42,
+            "tags": ["sample", "simulated"],
+        },
+    });
+    Sample {
+        headers: vec![
+            ("content-type", "application/json".to_string()),
+            ("user-agent", "rustbin-simulator".to_string()),
+        ],
+        body: body.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_are_json_with_fresh_ids() {
+        for provider in [SampleProvider::GithubPush, SampleProvider::StripeInvoicePaid, SampleProvider::Generic] {
+            assert_eq!(SampleProvider::parse(provider.as_str()), Some(provider));
+            let first = sample(provider, Utc::now());
+            let second = sample(provider, Utc::now());
+            let body: serde_json::Value = serde_json::from_str(&first.body).unwrap();
+            assert!(body.is_object());
+            assert_ne!(first.body, second.body, "{} samples should not repeat", provider.as_str());
+        }
+        assert_eq!(SampleProvider::parse("github.pull_request"), None);
+    }
+}
@@ -0,0 +1,190 @@
+//! Bin scripts: Rhai code run on every capture, which can answer it in place of the bin's
+//! response and emit events to the bin's WebSocket listeners.
+
+use rhai::{Dynamic, Engine, Map, Scope};
+use tracing::info;
+
+use crate::config::ScriptsConfig;
+
+/// Longest script a bin accepts, in bytes
+pub const MAX_SCRIPT_LENGTH: usize = 64 * 1024;
+
+/// An answer a script gave to a capture
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+/// An event a script emitted
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptEvent {
+    pub name: String,
+    pub data: serde_json::Value,
+}
+
+/// What running a script on a capture produced
+#[derive(Debug, Default, PartialEq)]
+pub struct ScriptOutcome {
+    /// `None` when the script left the answer to the bin
+    pub response: Option<ScriptResponse>,
+    pub events: Vec<ScriptEvent>,
+}
+
+/// Compiles and runs bin scripts within `[scripts]` limits
+pub struct ScriptEngine {
+    engine: Engine,
+    enabled: bool,
+}
+
+impl ScriptEngine {
+    /// Strings a script builds, its answer's body included, are capped at `max_body_size`
+    pub fn new(config: &ScriptsConfig, max_body_size: usize) -> Self {
+        let mut engine = Engine::new();
+        engine.set_max_operations(config.max_operations);
+        engine.set_max_call_levels(32);
+        engine.set_max_expr_depths(64, 32);
+        engine.set_max_string_size(max_body_size);
+        engine.set_max_array_size(10_000);
+        engine.set_max_map_size(10_000);
+        engine.on_print(|text| info!(target: "rustbin::scripts", "{}", text));
+        engine.on_debug(|text, _, position| info!(target: "rustbin::scripts", %position, "{}", text));
+        ScriptEngine { engine, enabled: config.enabled }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Check that `source` is a valid script
+    pub fn compile(&self, source: &str) -> Result<(), String> {
+        self.engine.compile(source).map(|_| ()).map_err(|err| err.to_string())
+    }
+
+    /// Run `source` with the capture as `request`. The script answers with what it
+    /// evaluates to: nothing to leave the answer to the bin, a string for a `200` with that
+    /// body, or a map with `status`, `headers` and `body`. A map's `events` are emitted,
+    /// each a map with a `name` and `data`.
+    pub fn run(&self, source: &str, request: &serde_json::Value) -> Result<ScriptOutcome, String> {
+        let request = rhai::serde::to_dynamic(request).map_err(|err| err.to_string())?;
+        let mut scope = Scope::new();
+        scope.push_constant("request", request);
+        let result = self.engine.eval_with_scope::<Dynamic>(&mut scope, source).map_err(|err| err.to_string())?;
+        outcome(result)
+    }
+}
+
+fn outcome(result: Dynamic) -> Result<ScriptOutcome, String> {
+    if result.is_unit() {
+        return Ok(ScriptOutcome::default());
+    }
+    if result.is_string() {
+        let body = result.into_string().unwrap_or_default();
+        return Ok(ScriptOutcome { response: Some(ScriptResponse { status: 200, headers: Vec::new(), body }), events: Vec::new() });
+    }
+    let Some(mut map) = result.try_cast::<Map>() else {
+        return Err("a script must evaluate to nothing, a string or a map".to_string());
+    };
+
+    let events = match map.remove("events") {
+        Some(events) => events
+            .into_typed_array::<Map>()
+            .map_err(|_| "`events` must be an array of maps".to_string())?
+            .into_iter()
+            .map(event)
+            .collect::<Result<_, _>>()?,
+        None => Vec::new(),
+    };
+    if !["status", "headers", "body"].iter().any(|key| map.contains_key(*key)) {
+        return Ok(ScriptOutcome { response: None, events });
+    }
+
+    let status = match map.remove("status") {
+        Some(status) => status
+            .as_int()
+            .ok()
+            .and_then(|status| u16::try_from(status).ok())
+            .filter(|status| (200..=599).contains(status))
+            .ok_or_else(|| "`status` must be a number from 200 to 599".to_string())?,
+        None => 200,
+    };
+    let mut headers = Vec::new();
+    if let Some(values) = map.remove("headers") {
+        let values = values.try_cast::<Map>().ok_or_else(|| "`headers` must be a map".to_string())?;
+        headers.extend(values.into_iter().map(|(name, value)| (name.to_string(), value.to_string())));
+    }
+    let body = match map.remove("body") {
+        None => String::new(),
+        Some(body) if body.is_string() => body.into_string().unwrap_or_default(),
+        // Anything else is answered as JSON
+        Some(body) => {
+            if !headers.iter().any(|(name, _)| name.eq_ignore_ascii_case("content-type")) {
+                headers.push(("content-type".to_string(), "application/json".to_string()));
+            }
+            let value: serde_json::Value = rhai::serde::from_dynamic(&body).map_err(|err| err.to_string())?;
+            value.to_string()
+        }
+    };
+    Ok(ScriptOutcome { response: Some(ScriptResponse { status, headers, body }), events })
+}
+
+fn event(mut map: Map) -> Result<ScriptEvent, String> {
+    let name = map
+        .remove("name")
+        .and_then(|name| name.into_string().ok())
+        .ok_or_else(|| "every event needs a string `name`".to_string())?;
+    let data = match map.remove("data") {
+        Some(data) => rhai::serde::from_dynamic(&data).map_err(|err| err.to_string())?,
+        None => serde_json::Value::Null,
+    };
+    Ok(ScriptEvent { name, data })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn engine() -> ScriptEngine {
+        ScriptEngine::new(&ScriptsConfig::default(), 1024 * 1024)
+    }
+
+    #[test]
+    fn answers_with_what_the_script_evaluates_to() {
+        let request = json!({ "method": "POST", "body": { "json": { "amount": 42 } } });
+        let engine = engine();
+        assert_eq!(engine.run("()", &request).unwrap(), ScriptOutcome::default());
+        assert_eq!(engine.run("`got ${request.method}`", &request).unwrap().response.unwrap().body, "got POST");
+
+        let source = r#"
+            let amount = request.body.json.amount;
+            #{ status: if amount > 10 { 402 } else { 200 }, headers: #{ "x-amount": amount }, body: #{ amount: amount },
+               events: [#{ name: "charged", data: #{ amount: amount } }] }
+        "#;
+        let outcome = engine.run(source, &request).unwrap();
+        let response = outcome.response.unwrap();
+        assert_eq!(response.status, 402);
+        assert_eq!(response.body, r#"{"amount":42}"#);
+        assert!(response.headers.contains(&("x-amount".to_string(), "42".to_string())));
+        assert!(response.headers.contains(&("content-type".to_string(), "application/json".to_string())));
+        assert_eq!(outcome.events, [ScriptEvent { name: "charged".to_string(), data: json!({ "amount": 42 }) }]);
+
+        // Events alone leave the answer to the bin
+        let outcome = engine.run(r#"#{ events: [#{ name: "seen" }] }"#, &request).unwrap();
+        assert_eq!(outcome.response, None);
+        assert_eq!(outcome.events[0].data, serde_json::Value::Null);
+    }
+
+    #[test]
+    fn rejects_bad_scripts_and_results() {
+        let engine = engine();
+        assert!(engine.compile("let x = ;").is_err());
+        assert!(engine.compile("if request.method == \"GET\" { 1 }").is_ok());
+        assert!(engine.run("42", &json!({})).is_err());
+        assert!(engine.run("#{ status: 99 }", &json!({})).is_err());
+        assert!(engine.run("#{ events: [#{ data: 1 }] }", &json!({})).is_err());
+        // Endless scripts are stopped
+        assert!(engine.run("loop {}", &json!({})).is_err());
+    }
+}
@@ -0,0 +1,196 @@
+//! Checking webhook signatures of captured requests against a bin's shared secret.
+
+use axum::http::HeaderMap;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::models::{SignatureProvider, SignatureVerification};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Header checked by the `hmac` provider when none is configured
+pub const DEFAULT_HMAC_HEADER: &str = "x-signature";
+
+/// Whether `body` carries a valid signature. A missing or malformed signature header
+/// counts as invalid.
+pub fn verify(verification: &SignatureVerification, headers: &HeaderMap, body: &[u8]) -> bool {
+    let secret = verification.secret.as_bytes();
+    match verification.provider {
+        SignatureProvider::Github => header(headers, "x-hub-signature-256")
+            .and_then(|value| value.strip_prefix("sha256="))
+            .is_some_and(|signature| matches_hex(secret, &[body], signature)),
+        SignatureProvider::Stripe => header(headers, "stripe-signature").is_some_and(|value| verify_stripe(secret, value, body)),
+        SignatureProvider::Slack => {
+            let signature = header(headers, "x-slack-signature").and_then(|value| value.strip_prefix("v0="));
+            let timestamp = header(headers, "x-slack-request-timestamp");
+            match (signature, timestamp) {
+                (Some(signature), Some(timestamp)) => {
+                    matches_hex(secret, &[b"v0:", timestamp.as_bytes(), b":", body], signature)
+                }
+                _ => false,
+            }
+        }
+        SignatureProvider::Hmac => {
+            let name = verification.header.as_deref().unwrap_or(DEFAULT_HMAC_HEADER);
+            header(headers, name).is_some_and(|value| {
+                let signature = value.strip_prefix("sha256=").unwrap_or(value);
+                matches_hex(secret, &[body], signature) || matches_base64(secret, body, signature)
+            })
+        }
+    }
+}
+
+/// Headers that sign `body` the way `verification` expects, for requests rustbin makes
+/// up itself. `timestamp` is in Unix seconds, for the providers that sign one.
+pub fn sign(verification: &SignatureVerification, body: &[u8], timestamp: i64) -> Vec<(String, String)> {
+    let secret = verification.secret.as_bytes();
+    let timestamp = timestamp.to_string();
+    let hex_mac = |parts: &[&[u8]]| hex::encode(mac(secret, parts).finalize().into_bytes());
+    match verification.provider {
+        SignatureProvider::Github => vec![("x-hub-signature-256".to_string(), format!("sha256={}", hex_mac(&[body])))],
+        SignatureProvider::Stripe => {
+            let signature = hex_mac(&[timestamp.as_bytes(), b".", body]);
+            vec![("stripe-signature".to_string(), format!("t={},v1={}", timestamp, signature))]
+        }
+        SignatureProvider::Slack => {
+            let signature = hex_mac(&[b"v0:", timestamp.as_bytes(), b":", body]);
+            vec![
+                ("x-slack-signature".to_string(), format!("v0={}", signature)),
+                ("x-slack-request-timestamp".to_string(), timestamp),
+            ]
+        }
+        SignatureProvider::Hmac => {
+            let name = verification.header.as_deref().unwrap_or(DEFAULT_HMAC_HEADER);
+            vec![(name.to_ascii_lowercase(), hex_mac(&[body]))]
+        }
+    }
+}
+
+/// `t=<timestamp>,v1=<hex>[,v1=<hex>...]`, signing `"{t}.{body}"`. Any `v1` may match,
+/// as Stripe sends one per active secret while rolling them.
+fn verify_stripe(secret: &[u8], value: &str, body: &[u8]) -> bool {
+    let mut timestamp = None;
+    let mut signatures = Vec::new();
+    for part in value.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", t)) => timestamp = Some(t),
+            Some(("v1", signature)) => signatures.push(signature),
+            _ => {}
+        }
+    }
+    let Some(timestamp) = timestamp else {
+        return false;
+    };
+    signatures
+        .iter()
+        .any(|signature| matches_hex(secret, &[timestamp.as_bytes(), b".", body], signature))
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok()).map(str::trim)
+}
+
+fn mac(secret: &[u8], parts: &[&[u8]]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    for part in parts {
+        mac.update(part);
+    }
+    mac
+}
+
+/// Constant time comparison against a hex digest
+fn matches_hex(secret: &[u8], parts: &[&[u8]], signature: &str) -> bool {
+    hex::decode(signature).is_ok_and(|expected| mac(secret, parts).verify_slice(&expected).is_ok())
+}
+
+fn matches_base64(secret: &[u8], body: &[u8], signature: &str) -> bool {
+    STANDARD.decode(signature).is_ok_and(|expected| mac(secret, &[body]).verify_slice(&expected).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn verification(provider: SignatureProvider, secret: &str) -> SignatureVerification {
+        SignatureVerification { provider, secret: secret.to_string(), header: None }
+    }
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    fn hex_mac(secret: &str, message: &str) -> String {
+        hex::encode(mac(secret.as_bytes(), &[message.as_bytes()]).finalize().into_bytes())
+    }
+
+    #[test]
+    fn verifies_github_signatures() {
+        // Example from GitHub's "Validating webhook deliveries" guide
+        let github = verification(SignatureProvider::Github, "It's a Secret to Everybody");
+        let signed = headers(&[(
+            "x-hub-signature-256",
+            "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17",
+        )]);
+        assert!(verify(&github, &signed, b"Hello, World!"));
+        assert!(!verify(&github, &signed, b"Hello, World?"));
+        assert!(!verify(&github, &HeaderMap::new(), b"Hello, World!"));
+    }
+
+    #[test]
+    fn verifies_stripe_signatures_with_any_v1() {
+        let stripe = verification(SignatureProvider::Stripe, "whsec_test");
+        let body = r#"{"id":"evt_1"}"#;
+        let good = hex_mac("whsec_test", &format!("1700000000.{}", body));
+        let value = format!("t=1700000000,v1={},v1={}", "00".repeat(32), good);
+        assert!(verify(&stripe, &headers(&[("stripe-signature", &value)]), body.as_bytes()));
+
+        let wrong_time = format!("t=1700000001,v1={}", good);
+        assert!(!verify(&stripe, &headers(&[("stripe-signature", &wrong_time)]), body.as_bytes()));
+        assert!(!verify(&stripe, &headers(&[("stripe-signature", &format!("v1={}", good))]), body.as_bytes()));
+    }
+
+    #[test]
+    fn verifies_slack_signatures() {
+        let slack = verification(SignatureProvider::Slack, "slack-secret");
+        let body = "token=abc&team_id=T1";
+        let signature = format!("v0={}", hex_mac("slack-secret", &format!("v0:1531420618:{}", body)));
+        let signed = headers(&[("x-slack-signature", &signature), ("x-slack-request-timestamp", "1531420618")]);
+        assert!(verify(&slack, &signed, body.as_bytes()));
+        let missing_timestamp = headers(&[("x-slack-signature", &signature)]);
+        assert!(!verify(&slack, &missing_timestamp, body.as_bytes()));
+    }
+
+    #[test]
+    fn verifies_generic_hmac_in_hex_or_base64() {
+        let mut generic = verification(SignatureProvider::Hmac, "shared");
+        let digest = mac(b"shared", &[b"payload"]).finalize().into_bytes();
+        assert!(verify(&generic, &headers(&[("x-signature", &hex::encode(digest))]), b"payload"));
+        assert!(verify(&generic, &headers(&[("x-signature", &format!("sha256={}", hex::encode(digest)))]), b"payload"));
+
+        generic.header = Some("X-Shopify-Hmac-Sha256".to_string());
+        let signed = headers(&[("x-shopify-hmac-sha256", &STANDARD.encode(digest))]);
+        assert!(verify(&generic, &signed, b"payload"));
+        assert!(!verify(&generic, &signed, b"tampered"));
+    }
+
+    #[test]
+    fn signs_what_it_verifies() {
+        let mut custom = verification(SignatureProvider::Hmac, "shared");
+        custom.header = Some("X-Custom-Signature".to_string());
+        let providers = [SignatureProvider::Github, SignatureProvider::Stripe, SignatureProvider::Slack];
+        for verification in providers.into_iter().map(|provider| verification(provider, "shared")).chain([custom]) {
+            let mut signed = HeaderMap::new();
+            for (name, value) in sign(&verification, b"payload", 1_700_000_000) {
+                signed.append(axum::http::HeaderName::from_bytes(name.as_bytes()).unwrap(), HeaderValue::from_str(&value).unwrap());
+            }
+            assert!(verify(&verification, &signed, b"payload"), "{:?}", verification.provider);
+            assert!(!verify(&verification, &signed, b"tampered"), "{:?}", verification.provider);
+        }
+    }
+}
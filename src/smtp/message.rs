@@ -0,0 +1,352 @@
+//! Just enough RFC 5322 / MIME parsing to show a message: its headers, the first text
+//! and HTML parts, and what else was attached.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Serialize;
+
+/// Multipart messages nested deeper than this are listed as attachments
+const MAX_DEPTH: usize = 8;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ParsedMessage {
+    /// Unfolded, with encoded words decoded, in the order they appear
+    pub headers: Vec<(String, String)>,
+    pub text: Option<String>,
+    pub html: Option<String>,
+    pub attachments: Vec<Attachment>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Attachment {
+    pub filename: Option<String>,
+    pub content_type: String,
+    pub size: usize,
+}
+
+impl ParsedMessage {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        header(&self.headers, name)
+    }
+}
+
+pub fn parse_message(raw: &[u8]) -> ParsedMessage {
+    let mut message = ParsedMessage::default();
+    let (headers, body) = split_headers(raw);
+    let part = Part { headers: parse_headers(headers), body };
+    walk(&part, 0, &mut message);
+    message.headers = part.headers;
+    message
+}
+
+struct Part<'a> {
+    headers: Vec<(String, String)>,
+    body: &'a [u8],
+}
+
+fn walk(part: &Part, depth: usize, message: &mut ParsedMessage) {
+    let content_type = ContentType::of(&part.headers);
+    let attachment = header(&part.headers, "content-disposition")
+        .is_some_and(|disposition| disposition.trim_start().to_ascii_lowercase().starts_with("attachment"));
+
+    if content_type.mime.starts_with("multipart/") && depth < MAX_DEPTH
+        && let Some(boundary) = content_type.param("boundary")
+    {
+        for body in split_multipart(part.body, &boundary) {
+            let (headers, body) = split_headers(body);
+            walk(&Part { headers: parse_headers(headers), body }, depth + 1, message);
+        }
+        return;
+    }
+
+    let decoded = decode_transfer(part.body, header(&part.headers, "content-transfer-encoding"));
+    let slot = match content_type.mime.as_str() {
+        "text/plain" if !attachment => &mut message.text,
+        "text/html" if !attachment => &mut message.html,
+        _ => {
+            let filename = header(&part.headers, "content-disposition")
+                .and_then(|disposition| param(disposition, "filename"))
+                .or_else(|| content_type.param("name"));
+            message.attachments.push(Attachment { filename, content_type: content_type.mime, size: decoded.len() });
+            return;
+        }
+    };
+    // Only the first text and HTML parts are kept, usually alternatives of each other
+    if slot.is_none() {
+        *slot = Some(decode_charset(&decoded, content_type.param("charset").as_deref()));
+    }
+}
+
+/// Headers and body, split at the first empty line
+fn split_headers(raw: &[u8]) -> (&[u8], &[u8]) {
+    if raw.starts_with(b"\r\n") {
+        return (&[], &raw[2..]);
+    }
+    if raw.starts_with(b"\n") {
+        return (&[], &raw[1..]);
+    }
+    let crlf = find(raw, b"\r\n\r\n").map(|at| (at, at + 4));
+    let lf = find(raw, b"\n\n").map(|at| (at, at + 2));
+    match (crlf, lf) {
+        (Some(crlf), Some(lf)) => {
+            let (end, start) = if crlf.0 <= lf.0 { crlf } else { lf };
+            (&raw[..end], &raw[start..])
+        }
+        (Some((end, start)), None) | (None, Some((end, start))) => (&raw[..end], &raw[start..]),
+        (None, None) => (raw, &[]),
+    }
+}
+
+fn parse_headers(raw: &[u8]) -> Vec<(String, String)> {
+    let text = String::from_utf8_lossy(raw);
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in text.lines() {
+        // Folded lines continue the previous header
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    for (_, value) in headers.iter_mut() {
+        *value = decode_words(value);
+    }
+    headers
+}
+
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+}
+
+struct ContentType {
+    /// Lower case, `text/plain` when missing
+    mime: String,
+    raw: String,
+}
+
+impl ContentType {
+    fn of(headers: &[(String, String)]) -> Self {
+        let raw = header(headers, "content-type").unwrap_or("text/plain").to_string();
+        let mime = raw.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+        let mime = if mime.contains('/') { mime } else { "text/plain".to_string() };
+        ContentType { mime, raw }
+    }
+
+    fn param(&self, name: &str) -> Option<String> {
+        param(&self.raw, name)
+    }
+}
+
+/// A `; name=value` parameter of a structured header, unquoted
+fn param(value: &str, name: &str) -> Option<String> {
+    value.split(';').skip(1).find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        key.trim().eq_ignore_ascii_case(name).then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+/// The parts between `--boundary` lines, up to `--boundary--`
+fn split_multipart<'a>(body: &'a [u8], boundary: &str) -> Vec<&'a [u8]> {
+    let delimiter = format!("--{}", boundary);
+    let mut parts = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut offset = 0;
+    while offset < body.len() {
+        let end = find(&body[offset..], b"\n").map_or(body.len(), |at| offset + at + 1);
+        let line = trim_line_end(&body[offset..end]);
+        if line.starts_with(delimiter.as_bytes()) {
+            if let Some(start) = start {
+                // The line break before a delimiter belongs to the delimiter
+                parts.push(trim_line_end(&body[start..offset]));
+            }
+            if line[delimiter.len()..].starts_with(b"--") {
+                return parts;
+            }
+            start = Some(end);
+        }
+        offset = end;
+    }
+    // Unterminated: keep what was there
+    if let Some(start) = start {
+        parts.push(&body[start..]);
+    }
+    parts
+}
+
+fn trim_line_end(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+fn decode_transfer(body: &[u8], encoding: Option<&str>) -> Vec<u8> {
+    match encoding.map(|encoding| encoding.trim().to_ascii_lowercase()).as_deref() {
+        Some("base64") => {
+            let compact: Vec<u8> = body.iter().copied().filter(|byte| !byte.is_ascii_whitespace()).collect();
+            STANDARD.decode(compact).unwrap_or_else(|_| body.to_vec())
+        }
+        Some("quoted-printable") => decode_quoted_printable(body, false),
+        _ => body.to_vec(),
+    }
+}
+
+/// RFC 2045 quoted-printable; `header` also turns `_` into spaces (RFC 2047 `Q`)
+fn decode_quoted_printable(input: &[u8], header: bool) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len());
+    let mut index = 0;
+    while index < input.len() {
+        match input[index] {
+            b'=' => {
+                let rest = &input[index + 1..];
+                if rest.starts_with(b"\r\n") {
+                    index += 3;
+                } else if rest.starts_with(b"\n") {
+                    index += 2;
+                } else if let Some(byte) = rest.get(..2).and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()) {
+                    output.push(byte);
+                    index += 3;
+                } else {
+                    output.push(b'=');
+                    index += 1;
+                }
+            }
+            b'_' if header => {
+                output.push(b' ');
+                index += 1;
+            }
+            byte => {
+                output.push(byte);
+                index += 1;
+            }
+        }
+    }
+    output
+}
+
+/// Text in the declared charset. Anything but UTF-8 and Latin-1 is read as UTF-8.
+fn decode_charset(bytes: &[u8], charset: Option<&str>) -> String {
+    match charset.map(|charset| charset.to_ascii_lowercase()).as_deref() {
+        Some("iso-8859-1" | "latin1" | "windows-1252") => bytes.iter().map(|&byte| byte as char).collect(),
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+/// Decode RFC 2047 encoded words such as `=?UTF-8?B?w6k=?=`, dropping the whitespace
+/// between adjacent ones
+fn decode_words(value: &str) -> String {
+    if !value.contains("=?") {
+        return value.to_string();
+    }
+    let mut output = String::new();
+    let mut rest = value;
+    let mut after_word = false;
+    while let Some(start) = rest.find("=?") {
+        let Some(word) = encoded_word(&rest[start..]) else {
+            output.push_str(&rest[..start + 2]);
+            rest = &rest[start + 2..];
+            after_word = false;
+            continue;
+        };
+        let between = &rest[..start];
+        if !(after_word && between.trim().is_empty()) {
+            output.push_str(between);
+        }
+        output.push_str(&word.text);
+        rest = &rest[start + word.length..];
+        after_word = true;
+    }
+    output.push_str(rest);
+    output
+}
+
+struct EncodedWord {
+    text: String,
+    length: usize,
+}
+
+fn encoded_word(input: &str) -> Option<EncodedWord> {
+    let inner = input.strip_prefix("=?")?;
+    let (charset, inner) = inner.split_once('?')?;
+    let (encoding, inner) = inner.split_once('?')?;
+    let end = inner.find("?=")?;
+    let encoded = &inner[..end];
+    if encoded.contains(char::is_whitespace) {
+        return None;
+    }
+    let bytes = match encoding.to_ascii_uppercase().as_str() {
+        "B" => STANDARD.decode(encoded).ok()?,
+        "Q" => decode_quoted_printable(encoded.as_bytes(), true),
+        _ => return None,
+    };
+    Some(EncodedWord {
+        // Language tags (`UTF-8*en`) don't matter here
+        text: decode_charset(&bytes, charset.split('*').next()),
+        length: 2 + charset.len() + 1 + encoding.len() + 1 + end + 2,
+    })
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_plain_messages() {
+        let message = parse_message(b"From: a@example.com\r\nSubject: Hello\r\n  world\r\n\r\nHi there\r\n");
+        assert_eq!(message.header("subject"), Some("Hello world"));
+        assert_eq!(message.text.as_deref(), Some("Hi there\r\n"));
+        assert_eq!(message.html, None);
+        assert!(message.attachments.is_empty());
+    }
+
+    #[test]
+    fn parses_multipart_messages() {
+        let raw = concat!(
+            "Subject: =?UTF-8?B?Q2Fmw6k=?= =?UTF-8?Q?_ouvert?=\r\n",
+            "Content-Type: multipart/mixed; boundary=\"outer\"\r\n",
+            "\r\n",
+            "preamble\r\n",
+            "--outer\r\n",
+            "Content-Type: multipart/alternative; boundary=inner\r\n",
+            "\r\n",
+            "--inner\r\n",
+            "Content-Type: text/plain; charset=iso-8859-1\r\n",
+            "Content-Transfer-Encoding: quoted-printable\r\n",
+            "\r\n",
+            "caf=E9 soft=\r\nbreak\r\n",
+            "--inner\r\n",
+            "Content-Type: text/html\r\n",
+            "Content-Transfer-Encoding: base64\r\n",
+            "\r\n",
+            "PHA+aGk8L3A+\r\n",
+            "--inner--\r\n",
+            "--outer\r\n",
+            "Content-Type: application/pdf; name=\"invoice.pdf\"\r\n",
+            "Content-Disposition: attachment\r\n",
+            "Content-Transfer-Encoding: base64\r\n",
+            "\r\n",
+            "JVBERi0x\r\n",
+            "--outer--\r\n",
+        );
+        let message = parse_message(raw.as_bytes());
+        assert_eq!(message.header("Subject"), Some("Café ouvert"));
+        assert_eq!(message.text.as_deref(), Some("café softbreak"));
+        assert_eq!(message.html.as_deref(), Some("<p>hi</p>"));
+        assert_eq!(
+            message.attachments,
+            [Attachment { filename: Some("invoice.pdf".to_string()), content_type: "application/pdf".to_string(), size: 6 }]
+        );
+    }
+
+    #[test]
+    fn leaves_malformed_encoded_words_alone() {
+        assert_eq!(decode_words("=?utf-8?X?abc?= and =?broken"), "=?utf-8?X?abc?= and =?broken");
+        assert_eq!(decode_words("Re: =?utf-8?q?caf=C3=A9?= time"), "Re: café time");
+    }
+}
@@ -0,0 +1,361 @@
+//! An SMTP listener capturing mail sent to `<bin_id>@<domain>` into that bin, for testing
+//! transactional email (`[smtp] enabled = true`). It accepts mail without authentication
+//! or TLS and never relays anything.
+
+pub mod message;
+
+use axum::http::StatusCode;
+use chrono::Utc;
+use std::borrow::Cow;
+use std::io;
+use std::net::SocketAddr;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::{timeout, Duration};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::capture;
+use crate::config::SmtpConfig;
+use crate::extractors::Extractors;
+use crate::handlers::{bin_redactor, check_bin_paused, load_bin_settings};
+use crate::models::{LoggedRequest, WsEvent};
+use crate::state::AppState;
+use crate::storage::BinSettings;
+use crate::utils::body::ENCODING_UTF8;
+use crate::utils::sniff::BodyFormat;
+use crate::websocket::broadcast;
+use message::parse_message;
+
+/// Method of the requests mail is stored as
+pub const SMTP_METHOD: &str = "SMTP";
+
+/// Clients idle for longer are disconnected (RFC 5321 suggests at least 5 minutes)
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Longest command line accepted; RFC 5321 allows 512 bytes
+const MAX_COMMAND_LINE: u64 = 4096;
+
+/// Longest line of message data read at once; longer lines are split
+const MAX_DATA_LINE: u64 = 64 * 1024;
+
+const MAX_RECIPIENTS: usize = 100;
+
+/// Accept connections until the listener fails
+pub async fn serve(listener: TcpListener, state: AppState, config: SmtpConfig) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(err) => {
+                warn!(%err, "Failed to accept SMTP connection");
+                continue;
+            }
+        };
+        let session = Session::new(state.clone(), config.clone(), peer);
+        tokio::spawn(async move {
+            if let Err(err) = session.run(stream).await {
+                info!(%peer, %err, "SMTP session ended with an error");
+            }
+        });
+    }
+}
+
+struct Session {
+    state: AppState,
+    config: SmtpConfig,
+    peer: SocketAddr,
+    mail_from: Option<String>,
+    /// Bins of the accepted recipients, and the addresses as given
+    recipients: Vec<(String, String)>,
+}
+
+impl Session {
+    fn new(state: AppState, config: SmtpConfig, peer: SocketAddr) -> Self {
+        Session { state, config, peer, mail_from: None, recipients: Vec::new() }
+    }
+
+    async fn run(mut self, stream: TcpStream) -> io::Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        reply(&mut writer, &format!("220 {} rustbin ESMTP ready", self.config.domain)).await?;
+
+        loop {
+            let Some(line) = read_line(&mut reader, MAX_COMMAND_LINE).await? else {
+                return Ok(());
+            };
+            if !line.ends_with(b"\n") {
+                reply(&mut writer, "500 5.5.2 Line too long").await?;
+                return Ok(());
+            }
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end();
+            let (verb, argument) = line.split_once(' ').unwrap_or((line, ""));
+            match verb.to_ascii_uppercase().as_str() {
+                "HELO" => {
+                    self.reset();
+                    reply(&mut writer, &format!("250 {}", self.config.domain)).await?
+                }
+                "EHLO" => {
+                    self.reset();
+                    let greeting = format!(
+                        "250-{}\r\n250-SIZE {}\r\n250-8BITMIME\r\n250 SMTPUTF8",
+                        self.config.domain, self.config.max_message_size
+                    );
+                    reply(&mut writer, &greeting).await?
+                }
+                "MAIL" => {
+                    let response = self.mail(argument);
+                    reply(&mut writer, response).await?
+                }
+                "RCPT" => {
+                    let response = self.recipient(argument).await;
+                    reply(&mut writer, &response).await?
+                }
+                "DATA" => {
+                    if self.recipients.is_empty() {
+                        reply(&mut writer, "503 5.5.1 Need RCPT first").await?;
+                        continue;
+                    }
+                    reply(&mut writer, "354 End data with <CR><LF>.<CR><LF>").await?;
+                    let response = match read_data(&mut reader, self.config.max_message_size).await? {
+                        Some(data) => self.deliver(&data).await,
+                        None => "552 5.3.4 Message too big".to_string(),
+                    };
+                    self.reset();
+                    reply(&mut writer, &response).await?
+                }
+                "RSET" => {
+                    self.reset();
+                    reply(&mut writer, "250 2.0.0 OK").await?
+                }
+                "NOOP" => reply(&mut writer, "250 2.0.0 OK").await?,
+                "VRFY" => reply(&mut writer, "252 2.5.2 Cannot verify, send some mail").await?,
+                "QUIT" => {
+                    reply(&mut writer, "221 2.0.0 Bye").await?;
+                    return Ok(());
+                }
+                _ => reply(&mut writer, "502 5.5.2 Command not implemented").await?,
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.mail_from = None;
+        self.recipients.clear();
+    }
+
+    fn mail(&mut self, argument: &str) -> &'static str {
+        let Some((address, parameters)) = path_argument(argument, "FROM:") else {
+            return "501 5.5.4 Syntax: MAIL FROM:<address>";
+        };
+        let size = parameters
+            .split_whitespace()
+            .find_map(|parameter| parameter.get(..5).filter(|name| name.eq_ignore_ascii_case("SIZE=")).and(parameter.get(5..)))
+            .and_then(|size| size.parse::<usize>().ok());
+        if size.is_some_and(|size| size > self.config.max_message_size) {
+            return "552 5.3.4 Message too big";
+        }
+        self.reset();
+        self.mail_from = Some(address.to_string());
+        "250 2.1.0 OK"
+    }
+
+    async fn recipient(&mut self, argument: &str) -> String {
+        if self.mail_from.is_none() {
+            return "503 5.5.1 Need MAIL first".to_string();
+        }
+        let Some((address, _)) = path_argument(argument, "TO:") else {
+            return "501 5.5.4 Syntax: RCPT TO:<address>".to_string();
+        };
+        if self.recipients.len() >= MAX_RECIPIENTS {
+            return "452 4.5.3 Too many recipients".to_string();
+        }
+        let Some((bin_id, domain)) = address.rsplit_once('@') else {
+            return "550 5.1.1 No such bin".to_string();
+        };
+        if !self.config.domain.is_empty() && !domain.eq_ignore_ascii_case(&self.config.domain) {
+            return "550 5.7.1 Relaying denied".to_string();
+        }
+        let Ok(bin_id) = Uuid::parse_str(bin_id) else {
+            return "550 5.1.1 No such bin".to_string();
+        };
+        let bin_id = bin_id.to_string();
+        match load_bin_settings(&self.state, &bin_id).await {
+            Ok(settings) if check_bin_paused(&settings).is_err() => "450 4.2.1 Bin is paused".to_string(),
+            Ok(_) => {
+                if !self.recipients.iter().any(|(known, _)| *known == bin_id) {
+                    self.recipients.push((bin_id, address.to_string()));
+                }
+                "250 2.1.5 OK".to_string()
+            }
+            Err(error) if error.status == StatusCode::NOT_FOUND => "550 5.1.1 No such bin".to_string(),
+            Err(_) => "451 4.3.0 Temporary failure, try again later".to_string(),
+        }
+    }
+
+    /// Store the message in every recipient's bin
+    async fn deliver(&self, data: &[u8]) -> String {
+        let message = parse_message(data);
+        let timestamp = Utc::now().to_rfc3339();
+        let mut failed = false;
+
+        for (bin_id, _) in &self.recipients {
+            let settings = match load_bin_settings(&self.state, bin_id).await {
+                Ok(settings) => settings,
+                // Deleted since RCPT, or unreadable; either way the message isn't stored there
+                Err(_) => {
+                    failed = true;
+                    continue;
+                }
+            };
+            let redactor = bin_redactor(&self.state, bin_id, settings.redaction.as_ref());
+            let mut headers = message.headers.clone();
+            redactor.redact_headers(&mut headers);
+            let redact = |text: &Option<String>| text.as_deref().map(|text| redactor.redact_body(text).into_owned());
+            let body = serde_json::json!({
+                "from": self.mail_from,
+                "to": self.recipients.iter().map(|(_, address)| address).collect::<Vec<_>>(),
+                "text": redact(&message.text),
+                "html": redact(&message.html),
+                "attachments": message.attachments,
+            }).to_string();
+            let fields = Extractors::new(&settings.extractors).extract(&body);
+
+            let logged = LoggedRequest {
+                method: SMTP_METHOD.to_string(),
+                headers: serde_json::to_string(&headers).unwrap_or_else(|_| "[]".to_string()),
+                body: Some(body),
+                body_encoding: Some(ENCODING_UTF8.to_string()),
+                content_encoding: None,
+                timestamp: timestamp.clone(),
+                request_id: Uuid::new_v4(),
+                body_format: Some(BodyFormat::Json.as_str().to_string()),
+                content_type_mismatch: false,
+                query_string: None,
+                query_params: None,
+                source_ip: Some(self.peer.ip().to_string()),
+                source_port: Some(self.peer.port()),
+                path: Some("/".to_string()),
+                signature_valid: None,
+                fields,
+                pinned: false,
+                chaos: None,
+            };
+            if let Err(err) = self.store(bin_id, &settings, logged).await {
+                error!(%bin_id, peer = %self.peer, %err, "Failed to store mail");
+                failed = true;
+            }
+        }
+
+        if failed {
+            return "451 4.3.0 Failed to store the message, try again later".to_string();
+        }
+        info!(peer = %self.peer, recipients = self.recipients.len(), subject = message.header("subject").unwrap_or(""), "Mail captured");
+        "250 2.0.0 OK".to_string()
+    }
+
+    async fn store(&self, bin_id: &str, settings: &BinSettings, logged: LoggedRequest) -> Result<(), Cow<'static, str>> {
+        if self.state.storage_quota.is_full() {
+            return Err(Cow::Borrowed("storage is full"));
+        }
+        match &self.state.capture_queue {
+            Some(queue) => queue.push(bin_id, logged.clone()).map_err(|_| Cow::Borrowed("capture queue is full"))?,
+            None => {
+                capture::store_requests(
+                    self.state.storage.as_ref(),
+                    &self.state.webhooks,
+                    bin_id,
+                    std::slice::from_ref(&logged),
+                    capture::with_overrides(&self.state.limits.load(), settings.limits.as_ref()).max_requests_per_bin,
+                )
+                .await
+                .map_err(|err| Cow::Owned(err.to_string()))?;
+            }
+        }
+        self.state.notifier.notify(bin_id, settings.notifications.as_ref(), &logged);
+        self.state.callbacks.request_captured(&settings.callbacks, bin_id, &logged);
+        self.state.hooks.on_request_logged(bin_id, &logged);
+        if self.state.bin_channels.contains_key(bin_id) || self.state.fanout.is_some() {
+            broadcast(&self.state, bin_id, WsEvent::Request(Box::new(logged.into())));
+        }
+        Ok(())
+    }
+}
+
+/// The address in `FROM:<address> PARAMETERS`, and the parameters
+fn path_argument<'a>(argument: &'a str, prefix: &str) -> Option<(&'a str, &'a str)> {
+    let rest = argument.get(..prefix.len()).filter(|head| head.eq_ignore_ascii_case(prefix)).and(argument.get(prefix.len()..))?;
+    let rest = rest.trim_start().strip_prefix('<')?;
+    let (address, parameters) = rest.split_once('>')?;
+    Some((address, parameters.trim()))
+}
+
+async fn reply(writer: &mut OwnedWriteHalf, message: &str) -> io::Result<()> {
+    writer.write_all(format!("{}\r\n", message).as_bytes()).await
+}
+
+/// A line of at most `limit` bytes, without its end when it is longer; `None` at EOF
+async fn read_line<R: AsyncBufRead + Unpin>(reader: &mut R, limit: u64) -> io::Result<Option<Vec<u8>>> {
+    let mut line = Vec::new();
+    let read = timeout(COMMAND_TIMEOUT, reader.take(limit).read_until(b'\n', &mut line))
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "client idle for too long"))??;
+    Ok((read > 0).then_some(line))
+}
+
+/// The message up to the terminating `.` line, dot-unstuffed, or `None` when it was
+/// larger than `max_size` (it is still read to the end)
+async fn read_data<R: AsyncBufRead + Unpin>(reader: &mut R, max_size: usize) -> io::Result<Option<Vec<u8>>> {
+    let mut data = Vec::new();
+    let mut too_big = false;
+    let mut line_start = true;
+    loop {
+        let Some(line) = read_line(reader, MAX_DATA_LINE).await? else {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        };
+        let complete = line.ends_with(b"\n");
+        let mut line = line.as_slice();
+        if line_start {
+            if line == b".\r\n" || line == b".\n" {
+                return Ok((!too_big).then_some(data));
+            }
+            line = line.strip_prefix(b".").unwrap_or(line);
+        }
+        line_start = complete;
+        if data.len() + line.len() > max_size {
+            too_big = true;
+            data = Vec::new();
+        }
+        if !too_big {
+            data.extend_from_slice(line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_path_arguments() {
+        assert_eq!(path_argument("FROM:<a@example.com> SIZE=100", "FROM:"), Some(("a@example.com", "SIZE=100")));
+        assert_eq!(path_argument("to: <b@example.com>", "TO:"), Some(("b@example.com", "")));
+        assert_eq!(path_argument("FROM:<>", "FROM:"), Some(("", "")));
+        assert_eq!(path_argument("FROM:a@example.com", "FROM:"), None);
+        assert_eq!(path_argument("TO:<b@example.com>", "FROM:"), None);
+    }
+
+    #[tokio::test]
+    async fn reads_dot_stuffed_data() {
+        let data = &b"Subject: x\r\n\r\n..leading dot\r\nbody\r\n.\r\nQUIT\r\n"[..];
+        let mut reader = BufReader::new(data);
+        assert_eq!(read_data(&mut reader, 1024).await.unwrap().unwrap(), b"Subject: x\r\n\r\n.leading dot\r\nbody\r\n");
+        // The rest is left for the next command
+        assert_eq!(read_line(&mut reader, MAX_COMMAND_LINE).await.unwrap().unwrap(), b"QUIT\r\n");
+
+        let mut reader = BufReader::new(&b"0123456789\r\n.\r\nQUIT\r\n"[..]);
+        assert_eq!(read_data(&mut reader, 8).await.unwrap(), None);
+        assert_eq!(read_line(&mut reader, MAX_COMMAND_LINE).await.unwrap().unwrap(), b"QUIT\r\n");
+    }
+}
@@ -1,28 +1,240 @@
+use arc_swap::ArcSwap;
 use dashmap::DashMap;
 use std::sync::Arc;
 use tokio::sync::broadcast;
-use sqlx::{SqlitePool, sqlite::SqlitePoolOptions};
+use std::str::FromStr;
+use std::time::Duration;
+use sqlx::{SqlitePool, sqlite::{SqliteAutoVacuum, SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous}};
 
-use crate::config::{DatabaseConfig, LimitsConfig};
+use crate::bin_rate_limit::BinRateLimiter;
+use crate::cache::BinCache;
+use crate::capture::{CaptureQueue, StorageQuota};
+use crate::client_ip::TrustedProxies;
+use crate::outbound::OutboundGuard;
+use crate::config::{AdminConfig, AuthConfig, CleanupConfig, FanoutBackend, JournalMode, LimitsConfig, RedisConfig, ReplayConfig, RustbinConfig, Synchronous, WebSocketConfig};
+use crate::forwarding::Forwarder;
+use crate::notifications::Notifier;
+use crate::plugins::Plugins;
+use crate::health::Heartbeat;
+use crate::hooks::{Hooks, NoHooks};
+use crate::redaction::Redactor;
+use crate::redis::RedisClient;
+use crate::replay::Replayer;
+use crate::scripts::ScriptEngine;
+use crate::storage::{blob, sqlite::{self, MIGRATOR}, MemoryStorage, SqliteStorage, Storage, MEMORY_URL};
+use crate::webhooks::callbacks::CallbackDispatcher;
+use crate::webhooks::WebhookDispatcher;
+use crate::websocket::fanout::Fanout;
 
 #[derive(Clone)]
 pub struct AppState {
-    pub db: SqlitePool,
+    pub storage: Arc<dyn Storage>,
+    pub bin_cache: Arc<BinCache>,
+    /// Background writer for captures when `[limits] async_writes` is on
+    pub capture_queue: Option<Arc<CaptureQueue>>,
+    /// Updated by the cleanup task when `[cleanup] max_storage_bytes` is set
+    pub storage_quota: Arc<StorageQuota>,
     pub bin_channels: Arc<DashMap<String, broadcast::Sender<String>>>,
-    pub limits: LimitsConfig,
+    /// Beaten by the cleanup task after every pass
+    pub cleanup_heartbeat: Arc<Heartbeat>,
+    pub websocket: WebSocketConfig,
+    /// Relays WebSocket messages to other instances when `[websocket] fanout` is set
+    pub fanout: Option<Arc<Fanout>>,
+    /// Swapped when the configuration file is reloaded
+    pub limits: Arc<ArcSwap<LimitsConfig>>,
+    /// Read by the cleanup task before every pass; swapped on reload like `limits`
+    pub cleanup: Arc<ArcSwap<CleanupConfig>>,
+    pub webhooks: Arc<WebhookDispatcher>,
+    /// Signed events sent to the URLs registered on each bin
+    pub callbacks: Arc<CallbackDispatcher>,
+    pub replay: ReplayConfig,
+    pub replayer: Arc<Replayer>,
+    pub forwarder: Arc<Forwarder>,
+    pub notifier: Arc<Notifier>,
+    /// Runs bin scripts within the `[scripts]` limits
+    pub scripts: Arc<ScriptEngine>,
+    /// `[plugins]` modules captures pass through before they are stored
+    pub plugins: Arc<Plugins>,
+    /// Global `[redaction]` rules, extended per bin at capture time
+    pub redactor: Arc<Redactor>,
+    /// Per-bin token buckets, on top of the per-IP limit applied by the router
+    pub bin_limiter: Arc<BinRateLimiter>,
+    /// `[server] trusted_proxies`, shared with the rate limiter's key extractor
+    pub trusted_proxies: Arc<TrustedProxies>,
+    /// `[outbound] allowed_networks`, checked against every URL a bin sends to
+    pub outbound: Arc<OutboundGuard>,
+    /// `[server] request_read_timeout_seconds`; zero means no timeout
+    pub read_timeout: Duration,
+    /// `[server] head_probes`
+    pub head_probes: bool,
+    /// `[server] base_path`, normalized; empty when serving at the root
+    pub base_path: String,
+    pub admin: AdminConfig,
+    pub auth: AuthConfig,
+    /// An embedding application's hooks, see [`AppState::with_hooks`]
+    pub hooks: Arc<dyn Hooks>,
 }
 
 impl AppState {
-    pub async fn new(database_config: &DatabaseConfig, limits_config: &LimitsConfig) -> Result<Self, sqlx::Error> {
+    pub async fn new(config: &RustbinConfig) -> Result<Self, sqlx::Error> {
+        // Reject bad patterns with an error rather than the panic in `with_storage`
+        Redactor::from_config(&config.redaction).map_err(|err| sqlx::Error::Configuration(err.into()))?;
+        TrustedProxies::new(&config.server.trusted_proxies, config.server.forwarded_header).map_err(|err| sqlx::Error::Configuration(err.into()))?;
+        OutboundGuard::new(&config.outbound.allowed_networks).map_err(|err| sqlx::Error::Configuration(err.into()))?;
+        fanout_redis(config).map_err(|err| sqlx::Error::Configuration(err.into()))?;
+        config.database.maintenance_schedule().map_err(|err| sqlx::Error::Configuration(err.into()))?;
+        Plugins::from_config(&config.plugins).map_err(|err| sqlx::Error::Configuration(err.into()))?;
+
+        if config.database.url == MEMORY_URL {
+            return Ok(Self::with_storage(Arc::new(MemoryStorage::new()), config));
+        }
+
+        let options = SqliteConnectOptions::from_str(&config.database.url)?
+            .create_if_missing(true)
+            // Only takes effect on new databases; maintenance rebuilds older ones
+            .auto_vacuum(SqliteAutoVacuum::Incremental)
+            .journal_mode(journal_mode(config.database.journal_mode))
+            .synchronous(synchronous(config.database.synchronous))
+            .busy_timeout(Duration::from_millis(config.database.busy_timeout_ms));
         let pool = SqlitePoolOptions::new()
-            .max_connections(database_config.max_connections)
-            .connect(&database_config.url)
+            .max_connections(config.database.max_connections)
+            .connect_with(options)
             .await?;
 
-        Ok(AppState { 
-            db: pool, 
-            bin_channels: Arc::new(DashMap::new()),
-            limits: limits_config.clone(),
-        })
+        // Bring the schema up to date before anything touches it
+        MIGRATOR.run(&pool).await?;
+        tracing::info!("Database schema is up to date");
+        let indexed = sqlite::index_compressed_bodies(&pool).await?;
+        if indexed > 0 {
+            tracing::info!(indexed, "Added compressed bodies to the full-text index");
+        }
+
+        match blob::from_config(&config.blobs).map_err(|err| sqlx::Error::Configuration(err.into()))? {
+            Some(store) => {
+                tracing::info!(threshold_bytes = config.blobs.threshold_bytes, "Offloading large bodies to the blob store");
+                let storage = SqliteStorage::new(pool)
+                    .with_compression(&config.compression)
+                    .with_blobs(store, config.blobs.threshold_bytes);
+                Ok(Self::with_storage(Arc::new(storage), config))
+            }
+            None => Ok(Self::from_pool(pool, config)),
+        }
+    }
+
+    /// Build state around an existing pool (used by tests and embedders)
+    pub fn from_pool(pool: SqlitePool, config: &RustbinConfig) -> Self {
+        let storage = SqliteStorage::new(pool).with_compression(&config.compression);
+        Self::with_storage(Arc::new(storage), config)
+    }
+
+    /// Build state around any storage backend. Starts the capture writer when
+    /// `async_writes` is on and the WebSocket fanout when configured, so it must run
+    /// inside a Tokio runtime in those cases. Panics on invalid `[redaction]
+    /// body_patterns`, `[server] trusted_proxies`, `[outbound] allowed_networks`,
+    /// `[websocket]` fanout settings or `[plugins]` modules.
+    pub fn with_storage(storage: Arc<dyn Storage>, config: &RustbinConfig) -> Self {
+        let webhooks = Arc::new(WebhookDispatcher::new(&config.webhooks));
+        let limits = Arc::new(ArcSwap::from_pointee(config.limits.clone()));
+        let capture_queue = config.limits.async_writes.then(|| {
+            Arc::new(CaptureQueue::start(storage.clone(), webhooks.clone(), limits.clone()))
+        });
+        let outbound = Arc::new(OutboundGuard::new(&config.outbound.allowed_networks).expect("invalid [outbound] allowed_networks"));
+        let replayer = Arc::new(Replayer::new(&config.replay, config.limits.max_body_size).with_guard(outbound.clone()));
+        let forwarder = Arc::new(Forwarder::new(storage.clone(), replayer.clone(), &config.replay, &config.forwarding));
+        let notifier = Arc::new(Notifier::new(&config.notifications, outbound.clone()));
+        let callbacks = Arc::new(CallbackDispatcher::new(storage.clone(), webhooks.clone(), outbound.clone()));
+        let plugins = Plugins::from_config(&config.plugins).expect("invalid [plugins] modules");
+        let redactor = Redactor::from_config(&config.redaction).expect("invalid [redaction] body_patterns");
+        let trusted_proxies = TrustedProxies::new(&config.server.trusted_proxies, config.server.forwarded_header).expect("invalid [server] trusted_proxies");
+        let bin_channels = Arc::new(DashMap::new());
+        let fanout = fanout_redis(config)
+            .and_then(|redis| redis.map(|redis| Fanout::start(redis, Arc::clone(&bin_channels))).transpose())
+            .expect("invalid [websocket] fanout settings");
+        AppState {
+            storage,
+            bin_cache: Arc::new(BinCache::new(Duration::from_secs(config.database.bin_cache_ttl_seconds))),
+            bin_channels,
+            cleanup_heartbeat: Arc::new(Heartbeat::default()),
+            websocket: config.websocket.clone(),
+            fanout: fanout.map(Arc::new),
+            limits,
+            cleanup: Arc::new(ArcSwap::from_pointee(config.cleanup.clone())),
+            capture_queue,
+            storage_quota: Arc::new(StorageQuota::default()),
+            webhooks,
+            callbacks,
+            replay: config.replay.clone(),
+            replayer,
+            forwarder,
+            notifier,
+            scripts: Arc::new(ScriptEngine::new(&config.scripts, config.limits.max_body_size)),
+            plugins: Arc::new(plugins),
+            redactor: Arc::new(redactor),
+            bin_limiter: Arc::new(BinRateLimiter::new(&config.rate_limiting)),
+            trusted_proxies: Arc::new(trusted_proxies),
+            outbound,
+            read_timeout: Duration::from_secs(config.server.request_read_timeout_seconds),
+            head_probes: config.server.head_probes,
+            base_path: config.server.base_path(),
+            admin: config.admin.clone(),
+            auth: config.auth.clone(),
+            hooks: Arc::new(NoHooks),
+        }
+    }
+
+    /// Call `hooks` on bin and capture events, for applications embedding the routes
+    #[allow(dead_code)] // Only embedders register hooks; the server binary runs without
+    pub fn with_hooks(mut self, hooks: Arc<dyn Hooks>) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// The path a bin is served at, under the base path
+    pub fn bin_path(&self, bin_id: &str) -> String {
+        format!("{}/bin/{}", self.base_path, bin_id)
+    }
+
+    /// Apply the settings that can change while running: `[limits]` (except the capture
+    /// queue's sizing and `async_writes`) and `[cleanup]`. Everything else needs a restart.
+    pub fn reload(&self, config: &RustbinConfig) {
+        self.limits.store(Arc::new(config.limits.clone()));
+        self.cleanup.store(Arc::new(config.cleanup.clone()));
+    }
+}
+
+/// The `[websocket.redis]` section when messages are relayed through Redis
+fn fanout_redis(config: &RustbinConfig) -> Result<Option<&RedisConfig>, String> {
+    match config.websocket.fanout {
+        FanoutBackend::Local => Ok(None),
+        FanoutBackend::Redis => {
+            let redis = config
+                .websocket
+                .redis
+                .as_ref()
+                .ok_or_else(|| "[websocket] fanout = \"redis\" needs a [websocket.redis] section".to_string())?;
+            // Catch a bad URL at startup rather than in the background task
+            RedisClient::new(redis)?;
+            Ok(Some(redis))
+        }
+    }
+}
+
+fn journal_mode(mode: JournalMode) -> SqliteJournalMode {
+    match mode {
+        JournalMode::Delete => SqliteJournalMode::Delete,
+        JournalMode::Truncate => SqliteJournalMode::Truncate,
+        JournalMode::Persist => SqliteJournalMode::Persist,
+        JournalMode::Memory => SqliteJournalMode::Memory,
+        JournalMode::Wal => SqliteJournalMode::Wal,
+        JournalMode::Off => SqliteJournalMode::Off,
+    }
+}
+
+fn synchronous(level: Synchronous) -> SqliteSynchronous {
+    match level {
+        Synchronous::Off => SqliteSynchronous::Off,
+        Synchronous::Normal => SqliteSynchronous::Normal,
+        Synchronous::Full => SqliteSynchronous::Full,
+        Synchronous::Extra => SqliteSynchronous::Extra,
     }
 }
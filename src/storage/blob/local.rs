@@ -0,0 +1,89 @@
+use async_trait::async_trait;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use super::BlobStore;
+use crate::storage::{StorageError, StorageResult};
+
+/// Blobs kept as one file per key in a local directory
+pub struct LocalBlobStore {
+    directory: PathBuf,
+}
+
+impl LocalBlobStore {
+    pub fn new(directory: impl AsRef<Path>) -> Self {
+        LocalBlobStore { directory: directory.as_ref().to_path_buf() }
+    }
+
+    fn path(&self, key: &str) -> StorageResult<PathBuf> {
+        // Keys are generated by us, but never let one escape the directory
+        if key.is_empty() || key.contains(['/', '\\']) || key.starts_with('.') {
+            return Err(StorageError::Backend(format!("invalid blob key `{}`", key)));
+        }
+        Ok(self.directory.join(key))
+    }
+}
+
+fn io_error(key: &str, err: std::io::Error) -> StorageError {
+    StorageError::Backend(format!("blob {}: {}", key, err))
+}
+
+#[async_trait]
+impl BlobStore for LocalBlobStore {
+    async fn put(&self, key: &str, data: Vec<u8>) -> StorageResult<()> {
+        let path = self.path(key)?;
+        tokio::fs::create_dir_all(&self.directory).await.map_err(|err| io_error(key, err))?;
+        tokio::fs::write(path, data).await.map_err(|err| io_error(key, err))
+    }
+
+    async fn get(&self, key: &str) -> StorageResult<Vec<u8>> {
+        tokio::fs::read(self.path(key)?).await.map_err(|err| io_error(key, err))
+    }
+
+    async fn delete(&self, key: &str) -> StorageResult<()> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(io_error(key, err)),
+            _ => Ok(()),
+        }
+    }
+
+    async fn size(&self) -> StorageResult<Option<u64>> {
+        let directory = self.directory.display().to_string();
+        let mut entries = match tokio::fs::read_dir(&self.directory).await {
+            Ok(entries) => entries,
+            // Nothing was offloaded yet
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Some(0)),
+            Err(err) => return Err(io_error(&directory, err)),
+        };
+        let mut total = 0;
+        while let Some(entry) = entries.next_entry().await.map_err(|err| io_error(&directory, err))? {
+            if let Ok(metadata) = entry.metadata().await
+                && metadata.is_file()
+            {
+                total += metadata.len();
+            }
+        }
+        Ok(Some(total))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn round_trips_and_deletes_blobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = LocalBlobStore::new(dir.path().join("blobs"));
+        assert_eq!(store.size().await.unwrap(), Some(0));
+
+        store.put("abc", b"large body".to_vec()).await.unwrap();
+        assert_eq!(store.get("abc").await.unwrap(), b"large body");
+        assert_eq!(store.size().await.unwrap(), Some(10));
+
+        store.delete("abc").await.unwrap();
+        store.delete("abc").await.unwrap();
+        assert!(store.get("abc").await.is_err());
+        assert!(store.put("../escape", Vec::new()).await.is_err());
+    }
+}
@@ -0,0 +1,43 @@
+//! Stores for request bodies too large to keep in the database row.
+
+pub mod local;
+pub mod s3;
+
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use super::StorageResult;
+use crate::config::{BlobBackend, BlobsConfig};
+
+pub use local::LocalBlobStore;
+pub use s3::S3BlobStore;
+
+#[async_trait]
+pub trait BlobStore: Send + Sync {
+    async fn put(&self, key: &str, data: Vec<u8>) -> StorageResult<()>;
+    async fn get(&self, key: &str) -> StorageResult<Vec<u8>>;
+    /// Deleting a missing blob is not an error
+    async fn delete(&self, key: &str) -> StorageResult<()>;
+    /// Bytes taken up by all blobs, for stores that can tell without listing them remotely
+    async fn size(&self) -> StorageResult<Option<u64>> {
+        Ok(None)
+    }
+}
+
+/// The blob store configured in `[blobs]`, or `None` when offloading is disabled
+pub fn from_config(config: &BlobsConfig) -> Result<Option<Arc<dyn BlobStore>>, String> {
+    if config.threshold_bytes == 0 {
+        return Ok(None);
+    }
+    let store: Arc<dyn BlobStore> = match config.backend {
+        BlobBackend::Local => Arc::new(LocalBlobStore::new(&config.directory)),
+        BlobBackend::S3 => {
+            let s3 = config
+                .s3
+                .as_ref()
+                .ok_or_else(|| "[blobs] backend = \"s3\" needs a [blobs.s3] section".to_string())?;
+            Arc::new(S3BlobStore::new(s3)?)
+        }
+    };
+    Ok(Some(store))
+}
@@ -0,0 +1,155 @@
+use async_trait::async_trait;
+use aws_credential_types::Credentials;
+use aws_sigv4::http_request::{
+    sign, PayloadChecksumKind, PercentEncodingMode, SignableBody, SignableRequest, SigningSettings, UriPathNormalizationMode,
+};
+use aws_sigv4::sign::v4;
+use reqwest::{Method, StatusCode, Url};
+use std::time::SystemTime;
+
+use super::BlobStore;
+use crate::config::S3Config;
+use crate::storage::{StorageError, StorageResult};
+
+/// Blobs kept in an S3-compatible bucket (AWS, MinIO, R2, ...), addressed path-style
+/// as `{endpoint}/{bucket}/{prefix}{key}` and signed with AWS Signature Version 4.
+pub struct S3BlobStore {
+    client: reqwest::Client,
+    endpoint: Url,
+    bucket: String,
+    prefix: String,
+    region: String,
+    credentials: Credentials,
+}
+
+impl S3BlobStore {
+    pub fn new(config: &S3Config) -> Result<Self, String> {
+        let endpoint = Url::parse(&config.endpoint)
+            .map_err(|err| format!("Invalid S3 endpoint `{}`: {}", config.endpoint, err))?;
+        if config.bucket.is_empty() {
+            return Err("S3 bucket must not be empty".to_string());
+        }
+        Ok(S3BlobStore {
+            client: reqwest::Client::new(),
+            endpoint,
+            bucket: config.bucket.clone(),
+            prefix: config.prefix.clone(),
+            region: config.region.clone(),
+            credentials: Credentials::new(&config.access_key_id, &config.secret_access_key, None, None, "rustbin.toml"),
+        })
+    }
+
+    fn object_url(&self, key: &str) -> StorageResult<Url> {
+        let base = self.endpoint.as_str().trim_end_matches('/');
+        Url::parse(&format!("{}/{}/{}{}", base, self.bucket, self.prefix, key))
+            .map_err(|err| StorageError::Backend(format!("invalid S3 object URL: {}", err)))
+    }
+
+    async fn send(&self, method: Method, key: &str, body: Vec<u8>) -> StorageResult<reqwest::Response> {
+        let url = self.object_url(key)?;
+        let headers = self.signed_headers(method.as_str(), &url, &body, SystemTime::now())?;
+
+        let mut request = self.client.request(method, url);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        request
+            .body(body)
+            .send()
+            .await
+            .map_err(|err| StorageError::Backend(format!("S3 request for {} failed: {}", key, err)))
+    }
+
+    /// The `authorization`, `x-amz-date` and `x-amz-content-sha256` headers signing a
+    /// request's host, payload and date
+    fn signed_headers(&self, method: &str, url: &Url, body: &[u8], time: SystemTime) -> StorageResult<Vec<(&'static str, String)>> {
+        let signing_error = |err: &dyn std::fmt::Display| StorageError::Backend(format!("Failed to sign S3 request: {}", err));
+        let identity = self.credentials.clone().into();
+        // S3 signs object keys as they are sent, encoded once and not normalized
+        let mut settings = SigningSettings::default();
+        settings.payload_checksum_kind = PayloadChecksumKind::XAmzSha256;
+        settings.percent_encoding_mode = PercentEncodingMode::Single;
+        settings.uri_path_normalization_mode = UriPathNormalizationMode::Disabled;
+        let params = v4::SigningParams::builder()
+            .identity(&identity)
+            .region(&self.region)
+            .name("s3")
+            .time(time)
+            .settings(settings)
+            .build()
+            .map_err(|err| signing_error(&err))?
+            .into();
+
+        let request = SignableRequest::new(method, url.as_str(), std::iter::empty(), SignableBody::Bytes(body))
+            .map_err(|err| signing_error(&err))?;
+        let (instructions, _signature) = sign(request, &params).map_err(|err| signing_error(&err))?.into_parts();
+        let (headers, _params) = instructions.into_parts();
+        Ok(headers.into_iter().map(|header| (header.name(), header.value().to_string())).collect())
+    }
+}
+
+fn check_status(key: &str, response: &reqwest::Response) -> StorageResult<()> {
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(StorageError::Backend(format!("S3 answered {} for blob {}", response.status(), key)))
+    }
+}
+
+#[async_trait]
+impl BlobStore for S3BlobStore {
+    async fn put(&self, key: &str, data: Vec<u8>) -> StorageResult<()> {
+        let response = self.send(Method::PUT, key, data).await?;
+        check_status(key, &response)
+    }
+
+    async fn get(&self, key: &str) -> StorageResult<Vec<u8>> {
+        let response = self.send(Method::GET, key, Vec::new()).await?;
+        check_status(key, &response)?;
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|err| StorageError::Backend(format!("Failed to read blob {}: {}", key, err)))?;
+        Ok(bytes.to_vec())
+    }
+
+    async fn delete(&self, key: &str) -> StorageResult<()> {
+        let response = self.send(Method::DELETE, key, Vec::new()).await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(());
+        }
+        check_status(key, &response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn signs_path_style_requests() {
+        let store = S3BlobStore::new(&S3Config {
+            endpoint: "http://localhost:9000".to_string(),
+            bucket: "rustbin".to_string(),
+            prefix: "bodies/".to_string(),
+            region: "us-east-1".to_string(),
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "secret".to_string(),
+        })
+        .unwrap();
+        let url = store.object_url("abc").unwrap();
+        assert_eq!(url.as_str(), "http://localhost:9000/rustbin/bodies/abc");
+
+        // 2024-05-01T12:00:00Z
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_714_564_800);
+        let headers = store.signed_headers("GET", &url, b"", time).unwrap();
+        let header = |name: &str| headers.iter().find(|(header, _)| *header == name).map(|(_, value)| value.as_str());
+        assert_eq!(header("x-amz-date"), Some("20240501T120000Z"));
+        assert_eq!(header("x-amz-content-sha256"), Some("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"));
+        assert!(header("authorization").unwrap().starts_with(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20240501/us-east-1/s3/aws4_request, \
+             SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature="
+        ));
+    }
+}
@@ -0,0 +1,1294 @@
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use uuid::Uuid;
+
+use super::{
+    non_empty, BinSettings, CreatorBin, DueDelivery, InsertOutcome, KIND_FORWARD, MaintenanceOutcome, NewBin, PageRequest, PoolStatus, RequestFilter, Storage, StorageError, StorageReport, StorageResult, StorageStats,
+    MAX_DELIVERIES_PER_FORWARD, MAX_REJECTED_PER_BIN, MAX_REPLAYS_PER_BIN,
+};
+use crate::models::{
+    AdminBinSummary, AdminSearchResult, BinInfo, BinStorageUsage, BlobStorageUsage, Callback, Extractor, BinLimits, BinNotifications, BinChaos, BinPause, BinProxy, BinRateLimit, BinSampling, BinScript, BinMetadataUpdate, CustomResponse, Forward, ForwardDelivery, ForwardUpdate, RequestDelivery,
+    IpAccessRules, LoggedRequest, RedactionRules, RejectedRequest, ResponseRule, ResponseSequence, SequenceMode, SignatureVerification,
+};
+use crate::utils::headers::parse_stored_headers;
+
+/// Storage that lives and dies with the process. Searches behave like the SQLite
+/// backend's: bin search matches whole words, admin search is a substring match.
+#[derive(Default)]
+pub struct MemoryStorage {
+    inner: RwLock<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    bins: HashMap<String, StoredBin>,
+    /// Requests keyed by insertion order, like the SQLite row id
+    requests: BTreeMap<i64, StoredRequest>,
+    next_id: i64,
+    next_delivery_id: i64,
+}
+
+struct StoredBin {
+    last_updated: String,
+    name: Option<String>,
+    description: Option<String>,
+    tags: Option<Vec<String>>,
+    token: Option<String>,
+    creator_token: Option<String>,
+    creator_ip: Option<String>,
+    response: Option<CustomResponse>,
+    sequence: Option<ResponseSequence>,
+    signature: Option<SignatureVerification>,
+    redaction: Option<RedactionRules>,
+    ip_access: Option<IpAccessRules>,
+    rate_limit: Option<BinRateLimit>,
+    pause: Option<BinPause>,
+    sampling: Option<BinSampling>,
+    /// Captures seen since `sampling` was set
+    sample_seen: i64,
+    sampled_out: i64,
+    proxy: Option<BinProxy>,
+    script: Option<BinScript>,
+    chaos: Option<BinChaos>,
+    limits: Option<BinLimits>,
+    notifications: Option<BinNotifications>,
+    extractors: Vec<Extractor>,
+    /// Oldest first
+    rejected: VecDeque<RejectedRequest>,
+    /// Response rules in evaluation order
+    rules: Vec<ResponseRule>,
+    /// Forward targets in creation order; they go away with the bin
+    forwards: Vec<StoredForward>,
+    /// Callbacks in creation order
+    callbacks: Vec<Callback>,
+    /// Replays and tunnel deliveries of the bin's requests, oldest first
+    replays: VecDeque<StoredReplay>,
+    /// Set while the bin is soft-deleted
+    deleted_at: Option<String>,
+    /// Bumped whenever the bin's requests change
+    revision: i64,
+}
+
+struct StoredForward {
+    /// `last_delivery` is always `None`; it is filled from `deliveries` when read
+    forward: Forward,
+    /// Oldest first
+    deliveries: VecDeque<ForwardDelivery>,
+}
+
+impl StoredForward {
+    fn to_forward(&self) -> Forward {
+        Forward { last_delivery: self.deliveries.back().cloned(), ..self.forward.clone() }
+    }
+}
+
+struct StoredReplay {
+    kind: String,
+    url: String,
+    delivery: ForwardDelivery,
+}
+
+struct StoredRequest {
+    bin_id: String,
+    request: LoggedRequest,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Inner> {
+        // A panic while holding the lock can't leave the maps half updated
+        self.inner.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Inner> {
+        self.inner.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Inner {
+    /// Whether the bin exists and isn't soft-deleted
+    fn is_live(&self, bin_id: &str) -> bool {
+        self.bins.get(bin_id).is_some_and(|bin| bin.deleted_at.is_none())
+    }
+
+    fn bin_requests<'a>(&'a self, bin_id: &'a str) -> impl DoubleEndedIterator<Item = (&'a i64, &'a LoggedRequest)> + 'a {
+        self.requests
+            .iter()
+            .filter(move |(_, stored)| stored.bin_id == bin_id)
+            .map(|(row_id, stored)| (row_id, &stored.request))
+    }
+
+    /// Stored text only, as a rough equivalent of the SQLite file size
+    fn stored_size(&self) -> i64 {
+        self.requests
+            .values()
+            .map(|stored| stored.request.headers.len() + stored.request.body.as_ref().map_or(0, String::len))
+            .sum::<usize>() as i64
+    }
+
+    fn bump_revision(&mut self, bin_id: &str) {
+        if let Some(bin) = self.bins.get_mut(bin_id) {
+            bin.revision += 1;
+        }
+    }
+}
+
+fn matches_filter(request: &LoggedRequest, filter: &RequestFilter) -> bool {
+    if filter.method.as_ref().is_some_and(|method| *method != request.method)
+        || filter.since.as_ref().is_some_and(|since| request.timestamp < *since)
+        || filter.until.as_ref().is_some_and(|until| request.timestamp > *until)
+    {
+        return false;
+    }
+    if let Some(name) = &filter.field_name {
+        match request.fields.get(name) {
+            Some(value) if filter.field_value.as_ref().is_none_or(|expected| value == expected) => {}
+            _ => return false,
+        }
+    }
+    match &filter.header_name {
+        Some(name) => parse_stored_headers(&request.headers).iter().any(|(key, value)| {
+            key.to_ascii_lowercase() == *name && filter.header_value.as_ref().is_none_or(|expected| value == expected)
+        }),
+        None => true,
+    }
+}
+
+/// Lower-case words, split the way SQLite's default FTS5 tokenizer splits them
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Whether every whitespace separated term of `query` appears in `body` as a phrase
+fn matches_search(body: &str, query: &str) -> bool {
+    let body_words = words(body);
+    query.split_whitespace().all(|term| {
+        let phrase = words(term);
+        !phrase.is_empty() && body_words.windows(phrase.len()).any(|window| window == phrase.as_slice())
+    })
+}
+
+/// Substring match with the ASCII case folding of SQL `LIKE`
+fn contains_ignore_ascii_case(haystack: &str, needle: &str) -> bool {
+    haystack.to_ascii_lowercase().contains(&needle.to_ascii_lowercase())
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn bin_exists(&self, bin_id: &str) -> StorageResult<bool> {
+        Ok(self.read().bins.get(bin_id).is_some_and(|bin| bin.deleted_at.is_none()))
+    }
+
+    async fn bin_settings(&self, bin_id: &str) -> StorageResult<Option<BinSettings>> {
+        let inner = self.read();
+        let Some(bin) = inner.bins.get(bin_id).filter(|bin| bin.deleted_at.is_none()) else {
+            return Ok(None);
+        };
+        Ok(Some(BinSettings {
+            pause: bin.pause,
+            ip_access: bin.ip_access.clone(),
+            rate_limit: bin.rate_limit,
+            limits: bin.limits,
+            signature: bin.signature.clone(),
+            redaction: bin.redaction.clone(),
+            extractors: bin.extractors.clone(),
+            sampling: bin.sampling,
+            chaos: bin.chaos,
+            proxy: bin.proxy.clone(),
+            script: bin.script.clone(),
+            has_sequence: bin.sequence.is_some(),
+            rules: bin.rules.clone(),
+            response: bin.response.clone(),
+            notifications: bin.notifications.clone(),
+            forwards: bin.forwards.iter().map(|stored| stored.forward.clone()).collect(),
+            callbacks: bin.callbacks.clone(),
+        }))
+    }
+
+    async fn create_bin(&self, bin: NewBin) -> StorageResult<()> {
+        let mut inner = self.write();
+        if inner.bins.contains_key(&bin.id) {
+            return Err(StorageError::Backend(format!("bin {} already exists", bin.id)));
+        }
+        inner.bins.insert(bin.id, StoredBin {
+            last_updated: bin.last_updated,
+            name: bin.name,
+            description: bin.description,
+            tags: bin.tags,
+            token: bin.token,
+            creator_token: bin.creator_token,
+            creator_ip: bin.creator_ip,
+            response: None,
+            sequence: None,
+            signature: None,
+            redaction: None,
+            ip_access: None,
+            rate_limit: None,
+            pause: None,
+            sampling: None,
+            sample_seen: 0,
+            sampled_out: 0,
+            proxy: None,
+            script: None,
+            chaos: None,
+            limits: None,
+            notifications: None,
+            extractors: Vec::new(),
+            rejected: VecDeque::new(),
+            rules: Vec::new(),
+            forwards: Vec::new(),
+            callbacks: Vec::new(),
+            replays: VecDeque::new(),
+            deleted_at: None,
+            revision: 0,
+        });
+        Ok(())
+    }
+
+    async fn bin_info(&self, bin_id: &str) -> StorageResult<Option<BinInfo>> {
+        Ok(self.read().bins.get(bin_id).filter(|bin| bin.deleted_at.is_none()).map(|bin| BinInfo {
+            bin_id: bin_id.to_string(),
+            private: bin.token.is_some(),
+            name: bin.name.clone(),
+            description: bin.description.clone(),
+            tags: bin.tags.clone().unwrap_or_default(),
+            last_updated: Some(bin.last_updated.clone()),
+            paused: bin.pause.is_some(),
+            sampled_out: bin.sampled_out,
+        }))
+    }
+
+    async fn bin_request_count(&self, bin_id: &str) -> StorageResult<Option<i64>> {
+        let inner = self.read();
+        if inner.bins.get(bin_id).is_none_or(|bin| bin.deleted_at.is_some()) {
+            return Ok(None);
+        }
+        Ok(Some(inner.requests.values().filter(|stored| stored.bin_id == bin_id).count() as i64))
+    }
+
+    async fn bin_revision(&self, bin_id: &str) -> StorageResult<Option<(i64, Option<String>)>> {
+        Ok(self
+            .read()
+            .bins
+            .get(bin_id)
+            .filter(|bin| bin.deleted_at.is_none())
+            .map(|bin| (bin.revision, Some(bin.last_updated.clone()))))
+    }
+
+    async fn update_bin_metadata(&self, bin_id: &str, update: &BinMetadataUpdate) -> StorageResult<()> {
+        if let Some(bin) = self.write().bins.get_mut(bin_id) {
+            if update.name.is_some() {
+                bin.name = non_empty(&update.name).map(str::to_string);
+            }
+            if update.description.is_some() {
+                bin.description = non_empty(&update.description).map(str::to_string);
+            }
+            if update.tags.is_some() {
+                bin.tags = update.tags.clone();
+            }
+        }
+        Ok(())
+    }
+
+    async fn bin_token(&self, bin_id: &str) -> StorageResult<Option<Option<String>>> {
+        Ok(self.read().bins.get(bin_id).map(|bin| bin.token.clone()))
+    }
+
+    async fn request_bin_token(&self, request_id: Uuid) -> StorageResult<Option<Option<String>>> {
+        let inner = self.read();
+        Ok(inner
+            .requests
+            .values()
+            .find(|stored| stored.request.request_id == request_id)
+            .and_then(|stored| inner.bins.get(&stored.bin_id))
+            .map(|bin| bin.token.clone()))
+    }
+
+    async fn custom_response(&self, bin_id: &str) -> StorageResult<Option<CustomResponse>> {
+        Ok(self.read().bins.get(bin_id).and_then(|bin| bin.response.clone()))
+    }
+
+    async fn set_custom_response(&self, bin_id: &str, response: Option<&CustomResponse>) -> StorageResult<()> {
+        if let Some(bin) = self.write().bins.get_mut(bin_id) {
+            bin.response = response.cloned();
+        }
+        Ok(())
+    }
+
+    async fn signature_verification(&self, bin_id: &str) -> StorageResult<Option<SignatureVerification>> {
+        Ok(self.read().bins.get(bin_id).and_then(|bin| bin.signature.clone()))
+    }
+
+    async fn set_signature_verification(&self, bin_id: &str, verification: Option<&SignatureVerification>) -> StorageResult<()> {
+        if let Some(bin) = self.write().bins.get_mut(bin_id) {
+            bin.signature = verification.cloned();
+        }
+        Ok(())
+    }
+
+    async fn redaction_rules(&self, bin_id: &str) -> StorageResult<Option<RedactionRules>> {
+        Ok(self.read().bins.get(bin_id).and_then(|bin| bin.redaction.clone()))
+    }
+
+    async fn set_redaction_rules(&self, bin_id: &str, rules: Option<&RedactionRules>) -> StorageResult<()> {
+        if let Some(bin) = self.write().bins.get_mut(bin_id) {
+            bin.redaction = rules.cloned();
+        }
+        Ok(())
+    }
+
+    async fn ip_access_rules(&self, bin_id: &str) -> StorageResult<Option<IpAccessRules>> {
+        Ok(self.read().bins.get(bin_id).and_then(|bin| bin.ip_access.clone()))
+    }
+
+    async fn set_ip_access_rules(&self, bin_id: &str, rules: Option<&IpAccessRules>) -> StorageResult<()> {
+        if let Some(bin) = self.write().bins.get_mut(bin_id) {
+            bin.ip_access = rules.cloned();
+        }
+        Ok(())
+    }
+
+    async fn record_rejected_request(&self, bin_id: &str, rejected: &RejectedRequest) -> StorageResult<()> {
+        if let Some(bin) = self.write().bins.get_mut(bin_id) {
+            bin.rejected.push_back(rejected.clone());
+            while bin.rejected.len() as i64 > MAX_REJECTED_PER_BIN {
+                bin.rejected.pop_front();
+            }
+        }
+        Ok(())
+    }
+
+    async fn rejected_requests(&self, bin_id: &str) -> StorageResult<Vec<RejectedRequest>> {
+        Ok(self.read().bins.get(bin_id).map(|bin| bin.rejected.iter().rev().cloned().collect()).unwrap_or_default())
+    }
+
+    async fn bin_pause(&self, bin_id: &str) -> StorageResult<Option<BinPause>> {
+        Ok(self.read().bins.get(bin_id).filter(|bin| bin.deleted_at.is_none()).and_then(|bin| bin.pause))
+    }
+
+    async fn set_bin_pause(&self, bin_id: &str, pause: Option<&BinPause>) -> StorageResult<()> {
+        if let Some(bin) = self.write().bins.get_mut(bin_id) {
+            bin.pause = pause.copied();
+        }
+        Ok(())
+    }
+
+    async fn bin_sampling(&self, bin_id: &str) -> StorageResult<Option<BinSampling>> {
+        Ok(self.read().bins.get(bin_id).filter(|bin| bin.deleted_at.is_none()).and_then(|bin| bin.sampling))
+    }
+
+    async fn set_bin_sampling(&self, bin_id: &str, sampling: Option<&BinSampling>) -> StorageResult<()> {
+        if let Some(bin) = self.write().bins.get_mut(bin_id) {
+            bin.sampling = sampling.copied();
+            bin.sample_seen = 0;
+        }
+        Ok(())
+    }
+
+    async fn next_sample(&self, bin_id: &str) -> StorageResult<i64> {
+        Ok(self.write().bins.get_mut(bin_id).map_or(0, |bin| {
+            bin.sample_seen += 1;
+            bin.sample_seen
+        }))
+    }
+
+    async fn record_sampled_out(&self, bin_id: &str) -> StorageResult<()> {
+        if let Some(bin) = self.write().bins.get_mut(bin_id) {
+            bin.sampled_out += 1;
+        }
+        Ok(())
+    }
+
+    async fn bin_chaos(&self, bin_id: &str) -> StorageResult<Option<BinChaos>> {
+        Ok(self.read().bins.get(bin_id).filter(|bin| bin.deleted_at.is_none()).and_then(|bin| bin.chaos))
+    }
+
+    async fn set_bin_chaos(&self, bin_id: &str, chaos: Option<&BinChaos>) -> StorageResult<()> {
+        if let Some(bin) = self.write().bins.get_mut(bin_id) {
+            bin.chaos = chaos.copied();
+        }
+        Ok(())
+    }
+
+    async fn bin_proxy(&self, bin_id: &str) -> StorageResult<Option<BinProxy>> {
+        Ok(self.read().bins.get(bin_id).filter(|bin| bin.deleted_at.is_none()).and_then(|bin| bin.proxy.clone()))
+    }
+
+    async fn set_bin_proxy(&self, bin_id: &str, proxy: Option<&BinProxy>) -> StorageResult<()> {
+        if let Some(bin) = self.write().bins.get_mut(bin_id) {
+            bin.proxy = proxy.cloned();
+        }
+        Ok(())
+    }
+
+    async fn bin_script(&self, bin_id: &str) -> StorageResult<Option<BinScript>> {
+        Ok(self.read().bins.get(bin_id).filter(|bin| bin.deleted_at.is_none()).and_then(|bin| bin.script.clone()))
+    }
+
+    async fn set_bin_script(&self, bin_id: &str, script: Option<&BinScript>) -> StorageResult<()> {
+        if let Some(bin) = self.write().bins.get_mut(bin_id) {
+            bin.script = script.cloned();
+        }
+        Ok(())
+    }
+
+    async fn bin_rate_limit(&self, bin_id: &str) -> StorageResult<Option<BinRateLimit>> {
+        Ok(self.read().bins.get(bin_id).and_then(|bin| bin.rate_limit))
+    }
+
+    async fn set_bin_rate_limit(&self, bin_id: &str, limit: Option<&BinRateLimit>) -> StorageResult<()> {
+        if let Some(bin) = self.write().bins.get_mut(bin_id) {
+            bin.rate_limit = limit.copied();
+        }
+        Ok(())
+    }
+
+    async fn bin_limits(&self, bin_id: &str) -> StorageResult<Option<BinLimits>> {
+        Ok(self.read().bins.get(bin_id).and_then(|bin| bin.limits))
+    }
+
+    async fn set_bin_limits(&self, bin_id: &str, limits: Option<&BinLimits>) -> StorageResult<()> {
+        if let Some(bin) = self.write().bins.get_mut(bin_id) {
+            bin.limits = limits.copied();
+        }
+        Ok(())
+    }
+
+    async fn bin_expiry_overrides(&self) -> StorageResult<Vec<(String, i64)>> {
+        Ok(self
+            .read()
+            .bins
+            .iter()
+            .filter_map(|(id, bin)| Some((id.clone(), bin.limits?.expiry_hours?)))
+            .collect())
+    }
+
+    async fn bin_retention_overrides(&self) -> StorageResult<Vec<(String, i64)>> {
+        Ok(self
+            .read()
+            .bins
+            .iter()
+            .filter_map(|(id, bin)| Some((id.clone(), bin.limits?.retention_hours?)))
+            .collect())
+    }
+
+    async fn bins_with_requests_before(&self, before: &str) -> StorageResult<Vec<String>> {
+        let inner = self.read();
+        let bins: BTreeSet<&String> = inner
+            .requests
+            .values()
+            .filter(|stored| !stored.request.pinned && stored.request.timestamp.as_str() < before)
+            .map(|stored| &stored.bin_id)
+            .filter(|bin_id| inner.bins.get(*bin_id).is_some_and(|bin| bin.deleted_at.is_none()))
+            .collect();
+        Ok(bins.into_iter().cloned().collect())
+    }
+
+    async fn bin_notifications(&self, bin_id: &str) -> StorageResult<Option<BinNotifications>> {
+        Ok(self.read().bins.get(bin_id).and_then(|bin| bin.notifications.clone()))
+    }
+
+    async fn set_bin_notifications(&self, bin_id: &str, notifications: Option<&BinNotifications>) -> StorageResult<()> {
+        if let Some(bin) = self.write().bins.get_mut(bin_id) {
+            bin.notifications = notifications.cloned();
+        }
+        Ok(())
+    }
+
+    async fn extractors(&self, bin_id: &str) -> StorageResult<Vec<Extractor>> {
+        Ok(self.read().bins.get(bin_id).map(|bin| bin.extractors.clone()).unwrap_or_default())
+    }
+
+    async fn set_extractors(&self, bin_id: &str, extractors: &[Extractor]) -> StorageResult<()> {
+        if let Some(bin) = self.write().bins.get_mut(bin_id) {
+            bin.extractors = extractors.to_vec();
+        }
+        Ok(())
+    }
+
+    async fn response_sequence(&self, bin_id: &str) -> StorageResult<Option<ResponseSequence>> {
+        Ok(self.read().bins.get(bin_id).and_then(|bin| bin.sequence.clone()))
+    }
+
+    async fn set_response_sequence(&self, bin_id: &str, sequence: Option<&ResponseSequence>) -> StorageResult<()> {
+        if let Some(bin) = self.write().bins.get_mut(bin_id) {
+            bin.sequence = sequence.map(|sequence| ResponseSequence { position: 0, ..sequence.clone() });
+        }
+        Ok(())
+    }
+
+    async fn next_sequence_response(&self, bin_id: &str) -> StorageResult<Option<CustomResponse>> {
+        let mut inner = self.write();
+        let Some(sequence) = inner.bins.get_mut(bin_id).and_then(|bin| bin.sequence.as_mut()) else {
+            return Ok(None);
+        };
+        let len = sequence.responses.len() as u64;
+        if sequence.mode == SequenceMode::Once && sequence.position >= len {
+            return Ok(None);
+        }
+        let response = sequence.position.checked_rem(len).map(|index| sequence.responses[index as usize].clone());
+        sequence.position += 1;
+        Ok(response)
+    }
+
+    async fn response_rules(&self, bin_id: &str) -> StorageResult<Vec<ResponseRule>> {
+        Ok(self.read().bins.get(bin_id).map(|bin| bin.rules.clone()).unwrap_or_default())
+    }
+
+    async fn set_response_rules(&self, bin_id: &str, rules: &[ResponseRule]) -> StorageResult<()> {
+        if let Some(bin) = self.write().bins.get_mut(bin_id) {
+            bin.rules = rules.to_vec();
+        }
+        Ok(())
+    }
+
+    async fn add_response_rule(&self, bin_id: &str, rule: &ResponseRule) -> StorageResult<()> {
+        match self.write().bins.get_mut(bin_id) {
+            Some(bin) => {
+                bin.rules.push(rule.clone());
+                Ok(())
+            }
+            None => Err(StorageError::Backend(format!("bin {} does not exist", bin_id))),
+        }
+    }
+
+    async fn update_response_rule(&self, bin_id: &str, rule: &ResponseRule) -> StorageResult<bool> {
+        let mut inner = self.write();
+        let existing = inner
+            .bins
+            .get_mut(bin_id)
+            .and_then(|bin| bin.rules.iter_mut().find(|existing| existing.rule_id == rule.rule_id));
+        match existing {
+            Some(existing) => {
+                *existing = rule.clone();
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn delete_response_rule(&self, bin_id: &str, rule_id: &str) -> StorageResult<bool> {
+        let mut inner = self.write();
+        let Some(bin) = inner.bins.get_mut(bin_id) else {
+            return Ok(false);
+        };
+        let before = bin.rules.len();
+        bin.rules.retain(|rule| rule.rule_id != rule_id);
+        Ok(bin.rules.len() < before)
+    }
+
+    async fn touch_bin(&self, bin_id: &str, last_updated: &str) -> StorageResult<()> {
+        if let Some(bin) = self.write().bins.get_mut(bin_id) {
+            bin.last_updated = last_updated.to_string();
+        }
+        Ok(())
+    }
+
+    async fn delete_bin(&self, bin_id: &str) -> StorageResult<bool> {
+        let mut inner = self.write();
+        if inner.bins.remove(bin_id).is_none() {
+            return Ok(false);
+        }
+        inner.requests.retain(|_, stored| stored.bin_id != bin_id);
+        Ok(true)
+    }
+
+    async fn soft_delete_bin(&self, bin_id: &str, deleted_at: &str) -> StorageResult<bool> {
+        let mut inner = self.write();
+        match inner.bins.get_mut(bin_id) {
+            Some(bin) if bin.deleted_at.is_none() => {
+                bin.deleted_at = Some(deleted_at.to_string());
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn restore_bin(&self, bin_id: &str, last_updated: &str) -> StorageResult<bool> {
+        let mut inner = self.write();
+        match inner.bins.get_mut(bin_id) {
+            Some(bin) if bin.deleted_at.is_some() => {
+                bin.deleted_at = None;
+                bin.last_updated = last_updated.to_string();
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn deleted_bins(&self, before: &str) -> StorageResult<Vec<String>> {
+        Ok(self
+            .read()
+            .bins
+            .iter()
+            .filter(|(_, bin)| bin.deleted_at.as_deref().is_some_and(|deleted_at| deleted_at < before))
+            .map(|(id, _)| id.clone())
+            .collect())
+    }
+
+    async fn inactive_bins(&self, before: &str, since: Option<&str>) -> StorageResult<Vec<(String, String)>> {
+        let inner = self.read();
+        Ok(inner
+            .bins
+            .iter()
+            .filter(|(_, bin)| bin.deleted_at.is_none())
+            .filter(|(_, bin)| bin.last_updated.as_str() < before && since.is_none_or(|since| bin.last_updated.as_str() >= since))
+            .filter(|(id, _)| !inner.bin_requests(id).any(|(_, request)| request.pinned))
+            .map(|(id, bin)| (id.clone(), bin.last_updated.clone()))
+            .collect())
+    }
+
+    async fn list_bins(&self, limit: i64, offset: i64) -> StorageResult<(Vec<AdminBinSummary>, i64)> {
+        let inner = self.read();
+        let mut request_counts: HashMap<&str, i64> = HashMap::new();
+        for stored in inner.requests.values() {
+            *request_counts.entry(stored.bin_id.as_str()).or_default() += 1;
+        }
+
+        let mut bins: Vec<_> = inner.bins.iter().filter(|(_, bin)| bin.deleted_at.is_none()).collect();
+        bins.sort_by(|(a_id, a), (b_id, b)| b.last_updated.cmp(&a.last_updated).then_with(|| a_id.cmp(b_id)));
+        let page = bins
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .map(|(id, bin)| AdminBinSummary {
+                bin_id: id.clone(),
+                name: bin.name.clone(),
+                private: bin.token.is_some(),
+                last_updated: Some(bin.last_updated.clone()),
+                request_count: request_counts.get(id.as_str()).copied().unwrap_or(0),
+            })
+            .collect();
+        let total = inner.bins.values().filter(|bin| bin.deleted_at.is_none()).count() as i64;
+        Ok((page, total))
+    }
+
+    async fn creator_bins(&self, creator_token: &str) -> StorageResult<Vec<CreatorBin>> {
+        let inner = self.read();
+        let mut bins: Vec<_> = inner
+            .bins
+            .iter()
+            .filter(|(_, bin)| bin.deleted_at.is_none() && bin.creator_token.as_deref() == Some(creator_token))
+            .collect();
+        bins.sort_by(|(a_id, a), (b_id, b)| b.last_updated.cmp(&a.last_updated).then_with(|| a_id.cmp(b_id)));
+        Ok(bins
+            .into_iter()
+            .map(|(id, bin)| {
+                let requests = inner.requests.values().filter(|stored| stored.bin_id == *id);
+                let (request_count, pinned) = requests.fold((0, false), |(count, pinned), stored| (count + 1, pinned || stored.request.pinned));
+                CreatorBin {
+                    summary: AdminBinSummary {
+                        bin_id: id.clone(),
+                        name: bin.name.clone(),
+                        private: bin.token.is_some(),
+                        last_updated: Some(bin.last_updated.clone()),
+                        request_count,
+                    },
+                    expiry_hours: bin.limits.as_ref().and_then(|limits| limits.expiry_hours),
+                    pinned,
+                }
+            })
+            .collect())
+    }
+
+    async fn count_creator_bins(&self, creator_ip: &str, creator_token: Option<&str>) -> StorageResult<(i64, i64)> {
+        let inner = self.read();
+        let live = inner.bins.values().filter(|bin| bin.deleted_at.is_none());
+        Ok(live.fold((0, 0), |(by_ip, by_token), bin| {
+            (
+                by_ip + i64::from(bin.creator_ip.as_deref() == Some(creator_ip)),
+                by_token + i64::from(creator_token.is_some() && bin.creator_token.as_deref() == creator_token),
+            )
+        }))
+    }
+
+    async fn insert_requests(&self, bin_id: &str, requests: &[LoggedRequest], max_requests: i64, last_updated: &str) -> StorageResult<InsertOutcome> {
+        let mut inner = self.write();
+        if !inner.bins.contains_key(bin_id) {
+            return Err(StorageError::Backend(format!("bin {} does not exist", bin_id)));
+        }
+        for request in requests {
+            inner.next_id += 1;
+            let row_id = inner.next_id;
+            inner.requests.insert(row_id, StoredRequest {
+                bin_id: bin_id.to_string(),
+                request: request.clone(),
+            });
+        }
+
+        let request_count = inner.bin_requests(bin_id).count() as i64;
+        let excess = (request_count - max_requests).max(0) as usize;
+        let oldest: Vec<i64> = inner
+            .bin_requests(bin_id)
+            .filter(|(_, request)| !request.pinned)
+            .take(excess)
+            .map(|(row_id, _)| *row_id)
+            .collect();
+        for row_id in &oldest {
+            inner.requests.remove(row_id);
+        }
+        if let Some(bin) = inner.bins.get_mut(bin_id) {
+            bin.last_updated = last_updated.to_string();
+            if !requests.is_empty() {
+                bin.revision += 1;
+            }
+        }
+        Ok(InsertOutcome { request_count, trimmed: oldest.len() as u64 })
+    }
+
+    async fn list_requests(&self, bin_id: &str, filter: &RequestFilter, page: PageRequest) -> StorageResult<(Vec<LoggedRequest>, i64)> {
+        let inner = self.read();
+        let after_row_id = match page.after {
+            Some(after) => *inner
+                .bin_requests(bin_id)
+                .find(|(_, request)| request.request_id == after)
+                .ok_or(StorageError::UnknownCursor)?
+                .0,
+            None => 0,
+        };
+
+        let matching = || inner.bin_requests(bin_id).filter(|(_, request)| matches_filter(request, filter));
+        let total = matching().count() as i64;
+        let rows = matching()
+            .filter(|(row_id, _)| **row_id > after_row_id)
+            .skip(page.offset.max(0) as usize)
+            .take(page.limit.max(0) as usize)
+            .map(|(_, request)| request.clone())
+            .collect();
+        Ok((rows, total))
+    }
+
+    async fn recent_requests(&self, bin_id: &str, limit: i64) -> StorageResult<Vec<LoggedRequest>> {
+        let inner = self.read();
+        let mut recent: Vec<LoggedRequest> = inner
+            .bin_requests(bin_id)
+            .rev()
+            .take(limit.max(0) as usize)
+            .map(|(_, request)| request.clone())
+            .collect();
+        recent.reverse();
+        Ok(recent)
+    }
+
+    async fn get_request(&self, bin_id: &str, request_id: Uuid) -> StorageResult<Option<LoggedRequest>> {
+        Ok(self
+            .read()
+            .bin_requests(bin_id)
+            .find(|(_, request)| request.request_id == request_id)
+            .map(|(_, request)| request.clone()))
+    }
+
+    fn stream_requests(&self, bin_id: String) -> BoxStream<'static, StorageResult<LoggedRequest>> {
+        let requests: Vec<_> = self.read().bin_requests(&bin_id).map(|(_, request)| Ok(request.clone())).collect();
+        stream::iter(requests).boxed()
+    }
+
+    async fn search_bin(&self, bin_id: &str, query: &str, limit: i64) -> StorageResult<Vec<LoggedRequest>> {
+        Ok(self
+            .read()
+            .bin_requests(bin_id)
+            .rev()
+            .filter(|(_, request)| matches_search(request.body.as_deref().unwrap_or(""), query))
+            .take(limit.max(0) as usize)
+            .map(|(_, request)| request.clone())
+            .collect())
+    }
+
+    async fn search_all(&self, text: &str, since: &str, until: &str, limit: i64) -> StorageResult<Vec<AdminSearchResult>> {
+        let inner = self.read();
+        Ok(inner
+            .requests
+            .values()
+            .rev()
+            .filter(|stored| {
+                let request = &stored.request;
+                inner.is_live(&stored.bin_id)
+                    && request.timestamp.as_str() >= since
+                    && request.timestamp.as_str() <= until
+                    && (contains_ignore_ascii_case(request.body.as_deref().unwrap_or(""), text)
+                        || contains_ignore_ascii_case(&request.headers, text))
+            })
+            .take(limit.max(0) as usize)
+            .map(|stored| AdminSearchResult {
+                bin_id: stored.bin_id.clone(),
+                request: stored.request.clone(),
+            })
+            .collect())
+    }
+
+    async fn set_request_pinned(&self, bin_id: &str, request_id: Uuid, pinned: bool) -> StorageResult<bool> {
+        let mut inner = self.write();
+        let stored = inner
+            .requests
+            .values_mut()
+            .find(|stored| stored.bin_id == bin_id && stored.request.request_id == request_id);
+        let Some(stored) = stored else {
+            return Ok(false);
+        };
+        if stored.request.pinned != pinned {
+            stored.request.pinned = pinned;
+            inner.bump_revision(bin_id);
+        }
+        Ok(true)
+    }
+
+    async fn delete_request(&self, request_id: Uuid) -> StorageResult<Option<String>> {
+        let mut inner = self.write();
+        let row_id = inner
+            .requests
+            .iter()
+            .find(|(_, stored)| stored.request.request_id == request_id)
+            .map(|(row_id, _)| *row_id);
+        let bin_id = row_id.and_then(|row_id| inner.requests.remove(&row_id)).map(|stored| stored.bin_id);
+        if let Some(bin_id) = &bin_id {
+            inner.bump_revision(bin_id);
+        }
+        Ok(bin_id)
+    }
+
+    async fn clear_bin(&self, bin_id: &str) -> StorageResult<u64> {
+        let mut inner = self.write();
+        let before = inner.requests.len();
+        inner.requests.retain(|_, stored| stored.bin_id != bin_id);
+        let cleared = before - inner.requests.len();
+        if cleared > 0 {
+            inner.bump_revision(bin_id);
+        }
+        Ok(cleared as u64)
+    }
+
+    async fn prune_bin(&self, bin_id: &str, before: Option<&str>, keep_last: Option<i64>) -> StorageResult<u64> {
+        let mut inner = self.write();
+        let in_bin: Vec<(i64, bool, bool)> = inner
+            .requests
+            .iter()
+            .filter(|(_, stored)| stored.bin_id == bin_id)
+            .map(|(row_id, stored)| {
+                let old_enough = before.is_none_or(|before| stored.request.timestamp.as_str() < before);
+                (*row_id, stored.request.pinned, old_enough)
+            })
+            .collect();
+        let excess = keep_last.map_or(i64::MAX, |keep| in_bin.len() as i64 - keep).max(0) as usize;
+        let row_ids: Vec<i64> = in_bin
+            .into_iter()
+            .filter(|(_, pinned, old_enough)| !pinned && *old_enough)
+            .map(|(row_id, _, _)| row_id)
+            .take(excess)
+            .collect();
+        for row_id in &row_ids {
+            inner.requests.remove(row_id);
+        }
+        if !row_ids.is_empty() {
+            inner.bump_revision(bin_id);
+        }
+        Ok(row_ids.len() as u64)
+    }
+
+    async fn evict_oldest_requests(&self, count: i64) -> StorageResult<Vec<(String, Uuid)>> {
+        let mut inner = self.write();
+        let row_ids: Vec<i64> = inner
+            .requests
+            .iter()
+            .filter(|(_, stored)| !stored.request.pinned)
+            .map(|(row_id, _)| *row_id)
+            .take(count.max(0) as usize)
+            .collect();
+        let mut evicted = Vec::with_capacity(row_ids.len());
+        for row_id in row_ids {
+            if let Some(stored) = inner.requests.remove(&row_id) {
+                inner.bump_revision(&stored.bin_id);
+                evicted.push((stored.bin_id, stored.request.request_id));
+            }
+        }
+        Ok(evicted)
+    }
+
+    async fn ping(&self) -> StorageResult<()> {
+        Ok(())
+    }
+
+    fn pool_status(&self) -> Option<PoolStatus> {
+        None
+    }
+
+    async fn stats(&self, since: &str) -> StorageResult<StorageStats> {
+        let inner = self.read();
+        let bins = || inner.bins.values().filter(|bin| bin.deleted_at.is_none());
+        let requests = || inner.requests.values().filter(|stored| inner.is_live(&stored.bin_id));
+        Ok(StorageStats {
+            bin_count: bins().count() as i64,
+            private_bin_count: bins().filter(|bin| bin.token.is_some()).count() as i64,
+            request_count: requests().count() as i64,
+            recent_request_count: requests().filter(|stored| stored.request.timestamp.as_str() >= since).count() as i64,
+            sampled_out_count: bins().map(|bin| bin.sampled_out).sum(),
+            size_bytes: inner.stored_size(),
+        })
+    }
+
+    async fn storage_size(&self) -> StorageResult<i64> {
+        Ok(self.read().stored_size())
+    }
+
+    async fn storage_report(&self, top: i64) -> StorageResult<StorageReport> {
+        let inner = self.read();
+        let mut usage: HashMap<&str, BinStorageUsage> = HashMap::new();
+        for stored in inner.requests.values() {
+            let bin = usage.entry(&stored.bin_id).or_insert_with(|| BinStorageUsage {
+                bin_id: stored.bin_id.clone(),
+                request_count: 0,
+                bytes: 0,
+                blob_count: 0,
+            });
+            bin.request_count += 1;
+            bin.bytes += (stored.request.headers.len() + stored.request.body.as_ref().map_or(0, String::len)) as i64;
+        }
+        let mut bins: Vec<BinStorageUsage> = usage.into_values().collect();
+        bins.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.bin_id.cmp(&b.bin_id)));
+        bins.truncate(top.max(0) as usize);
+
+        Ok(StorageReport {
+            size_bytes: inner.stored_size(),
+            page_size: 0,
+            page_count: 0,
+            free_page_count: 0,
+            bins,
+            blobs: BlobStorageUsage { enabled: false, count: 0, bytes: None },
+        })
+    }
+
+    async fn run_maintenance(&self) -> StorageResult<MaintenanceOutcome> {
+        // Deleted requests are dropped right away; there is nothing to reclaim
+        Ok(MaintenanceOutcome::default())
+    }
+
+    async fn create_forward(&self, bin_id: &str, forward: &Forward) -> StorageResult<()> {
+        match self.write().bins.get_mut(bin_id) {
+            Some(bin) => {
+                let forward = Forward { last_delivery: None, ..forward.clone() };
+                bin.forwards.push(StoredForward { forward, deliveries: VecDeque::new() });
+                Ok(())
+            }
+            None => Err(StorageError::Backend(format!("bin {} does not exist", bin_id))),
+        }
+    }
+
+    async fn list_forwards(&self, bin_id: &str) -> StorageResult<Vec<Forward>> {
+        Ok(self
+            .read()
+            .bins
+            .get(bin_id)
+            .map(|bin| bin.forwards.iter().map(StoredForward::to_forward).collect())
+            .unwrap_or_default())
+    }
+
+    async fn get_forward(&self, bin_id: &str, forward_id: &str) -> StorageResult<Option<Forward>> {
+        Ok(self
+            .read()
+            .bins
+            .get(bin_id)
+            .and_then(|bin| bin.forwards.iter().find(|stored| stored.forward.forward_id == forward_id))
+            .map(StoredForward::to_forward))
+    }
+
+    async fn update_forward(&self, bin_id: &str, forward_id: &str, update: &ForwardUpdate) -> StorageResult<bool> {
+        let mut inner = self.write();
+        let Some(stored) = inner
+            .bins
+            .get_mut(bin_id)
+            .and_then(|bin| bin.forwards.iter_mut().find(|stored| stored.forward.forward_id == forward_id))
+        else {
+            return Ok(false);
+        };
+        if let Some(url) = &update.url {
+            stored.forward.url = url.clone();
+        }
+        if let Some(enabled) = update.enabled {
+            stored.forward.enabled = enabled;
+        }
+        Ok(true)
+    }
+
+    async fn delete_forward(&self, bin_id: &str, forward_id: &str) -> StorageResult<bool> {
+        let mut inner = self.write();
+        let Some(bin) = inner.bins.get_mut(bin_id) else {
+            return Ok(false);
+        };
+        let before = bin.forwards.len();
+        bin.forwards.retain(|stored| stored.forward.forward_id != forward_id);
+        Ok(bin.forwards.len() < before)
+    }
+
+    async fn record_delivery(&self, forward_id: &str, delivery: &ForwardDelivery) -> StorageResult<i64> {
+        let mut inner = self.write();
+        inner.next_delivery_id += 1;
+        let delivery_id = inner.next_delivery_id;
+        let stored = inner
+            .bins
+            .values_mut()
+            .flat_map(|bin| bin.forwards.iter_mut())
+            .find(|stored| stored.forward.forward_id == forward_id);
+        // Like the SQLite foreign key, but deliveries for a forward deleted mid-flight are just dropped
+        if let Some(stored) = stored {
+            stored.deliveries.push_back(ForwardDelivery { delivery_id, ..delivery.clone() });
+            while stored.deliveries.len() as i64 > MAX_DELIVERIES_PER_FORWARD {
+                stored.deliveries.pop_front();
+            }
+        }
+        Ok(delivery_id)
+    }
+
+    async fn update_delivery(&self, delivery: &ForwardDelivery) -> StorageResult<()> {
+        let mut inner = self.write();
+        let stored = inner
+            .bins
+            .values_mut()
+            .flat_map(|bin| bin.forwards.iter_mut())
+            .flat_map(|stored| stored.deliveries.iter_mut())
+            .find(|stored| stored.delivery_id == delivery.delivery_id);
+        if let Some(stored) = stored {
+            *stored = delivery.clone();
+        }
+        Ok(())
+    }
+
+    async fn list_deliveries(&self, forward_id: &str) -> StorageResult<Vec<ForwardDelivery>> {
+        Ok(self
+            .read()
+            .bins
+            .values()
+            .flat_map(|bin| bin.forwards.iter())
+            .find(|stored| stored.forward.forward_id == forward_id)
+            .map(|stored| stored.deliveries.iter().rev().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    async fn due_deliveries(&self, now: &str, limit: i64) -> StorageResult<Vec<DueDelivery>> {
+        let inner = self.read();
+        let mut due: Vec<DueDelivery> = inner
+            .bins
+            .iter()
+            .flat_map(|(bin_id, bin)| bin.forwards.iter().map(move |stored| (bin_id, stored)))
+            .filter(|(_, stored)| stored.forward.enabled)
+            .flat_map(|(bin_id, stored)| {
+                stored
+                    .deliveries
+                    .iter()
+                    .filter(|delivery| delivery.next_attempt_at.as_deref().is_some_and(|at| at <= now))
+                    .map(move |delivery| DueDelivery {
+                        bin_id: bin_id.clone(),
+                        forward_id: stored.forward.forward_id.clone(),
+                        url: stored.forward.url.clone(),
+                        delivery: delivery.clone(),
+                    })
+            })
+            .collect();
+        due.sort_by(|a, b| a.delivery.next_attempt_at.cmp(&b.delivery.next_attempt_at));
+        due.truncate(limit.max(0) as usize);
+        Ok(due)
+    }
+
+    async fn record_replay(&self, bin_id: &str, kind: &str, url: &str, delivery: &ForwardDelivery) -> StorageResult<i64> {
+        let mut inner = self.write();
+        inner.next_delivery_id += 1;
+        let delivery_id = inner.next_delivery_id;
+        if let Some(bin) = inner.bins.get_mut(bin_id) {
+            bin.replays.push_back(StoredReplay {
+                kind: kind.to_string(),
+                url: url.to_string(),
+                delivery: ForwardDelivery { delivery_id, ..delivery.clone() },
+            });
+            while bin.replays.len() as i64 > MAX_REPLAYS_PER_BIN {
+                bin.replays.pop_front();
+            }
+        }
+        Ok(delivery_id)
+    }
+
+    async fn request_deliveries(&self, bin_id: &str, request_id: Uuid) -> StorageResult<Vec<RequestDelivery>> {
+        let inner = self.read();
+        let Some(bin) = inner.bins.get(bin_id) else {
+            return Ok(Vec::new());
+        };
+        let forwarded = bin.forwards.iter().flat_map(|stored| {
+            stored.deliveries.iter().filter(|delivery| delivery.request_id == request_id).map(|delivery| RequestDelivery {
+                kind: KIND_FORWARD.to_string(),
+                forward_id: Some(stored.forward.forward_id.clone()),
+                url: stored.forward.url.clone(),
+                delivery: delivery.clone(),
+            })
+        });
+        let replayed = bin.replays.iter().filter(|replay| replay.delivery.request_id == request_id).map(|replay| RequestDelivery {
+            kind: replay.kind.clone(),
+            forward_id: None,
+            url: replay.url.clone(),
+            delivery: replay.delivery.clone(),
+        });
+        Ok(forwarded.chain(replayed).collect())
+    }
+
+    async fn create_callback(&self, bin_id: &str, callback: &Callback) -> StorageResult<()> {
+        match self.write().bins.get_mut(bin_id) {
+            Some(bin) => {
+                bin.callbacks.push(callback.clone());
+                Ok(())
+            }
+            None => Err(StorageError::Backend(format!("bin {} does not exist", bin_id))),
+        }
+    }
+
+    async fn list_callbacks(&self, bin_id: &str) -> StorageResult<Vec<Callback>> {
+        Ok(self.read().bins.get(bin_id).map(|bin| bin.callbacks.clone()).unwrap_or_default())
+    }
+
+    async fn delete_callback(&self, bin_id: &str, callback_id: &str) -> StorageResult<bool> {
+        let mut inner = self.write();
+        let Some(bin) = inner.bins.get_mut(bin_id) else {
+            return Ok(false);
+        };
+        let before = bin.callbacks.len();
+        bin.callbacks.retain(|callback| callback.callback_id != callback_id);
+        Ok(bin.callbacks.len() < before)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, headers: &str, body: &str, timestamp: &str) -> LoggedRequest {
+        LoggedRequest {
+            method: method.to_string(),
+            headers: headers.to_string(),
+            body: Some(body.to_string()),
+            body_encoding: Some("utf8".to_string()),
+            content_encoding: None,
+            timestamp: timestamp.to_string(),
+            request_id: Uuid::new_v4(),
+            body_format: None,
+            content_type_mismatch: false,
+            query_string: None,
+            query_params: None,
+            source_ip: None,
+            source_port: None,
+            path: Some("/".to_string()),
+            signature_valid: None,
+            fields: BTreeMap::new(),
+            pinned: false,
+            chaos: None,
+        }
+    }
+
+    async fn storage_with_bin(id: &str) -> MemoryStorage {
+        let storage = MemoryStorage::new();
+        storage
+            .create_bin(NewBin { id: id.to_string(), last_updated: "2024-01-01T00:00:00+00:00".to_string(), ..Default::default() })
+            .await
+            .unwrap();
+        storage
+    }
+
+    #[tokio::test]
+    async fn filters_and_pages_requests() {
+        let storage = storage_with_bin("bin").await;
+        let first = request("POST", r#"[["x-event","push"]]"#, "{}", "2024-01-01T00:00:01+00:00");
+        let second = request("GET", "[]", "", "2024-01-01T00:00:02+00:00");
+        let third = request("POST", r#"[["X-Event","ping"]]"#, "{}", "2024-01-01T00:00:03+00:00");
+        storage.insert_requests("bin", &[first.clone(), second, third.clone()], 10, "2024-01-01T00:00:03+00:00").await.unwrap();
+
+        let page = PageRequest { after: None, limit: 10, offset: 0 };
+        let posts = RequestFilter { method: Some("POST".to_string()), ..Default::default() };
+        let (rows, total) = storage.list_requests("bin", &posts, page).await.unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(rows.len(), 2);
+
+        let header = RequestFilter { header_name: Some("x-event".to_string()), header_value: Some("ping".to_string()), ..Default::default() };
+        let (rows, _) = storage.list_requests("bin", &header, page).await.unwrap();
+        assert_eq!(rows[0].request_id, third.request_id);
+
+        let after_first = PageRequest { after: Some(first.request_id), ..page };
+        let (rows, total) = storage.list_requests("bin", &RequestFilter::default(), after_first).await.unwrap();
+        assert_eq!((rows.len(), total), (2, 3));
+
+        let unknown = PageRequest { after: Some(Uuid::new_v4()), ..page };
+        assert!(matches!(
+            storage.list_requests("bin", &RequestFilter::default(), unknown).await,
+            Err(StorageError::UnknownCursor)
+        ));
+    }
+
+    #[tokio::test]
+    async fn trims_oldest_requests_and_cascades_deletes() {
+        let storage = storage_with_bin("bin").await;
+        let requests: Vec<_> = (0..3).map(|i| request("GET", "[]", &i.to_string(), "2024-01-01T00:00:00+00:00")).collect();
+        let outcome = storage.insert_requests("bin", &requests, 1, "2024-01-02T00:00:00+00:00").await.unwrap();
+
+        assert_eq!(outcome, InsertOutcome { request_count: 3, trimmed: 2 });
+        let remaining = storage.get_request("bin", requests[2].request_id).await.unwrap();
+        assert_eq!(remaining.unwrap().body.as_deref(), Some("2"));
+        let info = storage.bin_info("bin").await.unwrap().unwrap();
+        assert_eq!(info.last_updated.as_deref(), Some("2024-01-02T00:00:00+00:00"));
+
+        assert!(storage.delete_bin("bin").await.unwrap());
+        assert!(storage.read().requests.is_empty());
+        assert!(storage.insert_requests("bin", &requests, 10, "2024-01-02T00:00:00+00:00").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn keeps_newest_deliveries_and_finds_due_retries() {
+        let storage = storage_with_bin("bin").await;
+        let forward = Forward {
+            forward_id: "forward".to_string(),
+            url: "http://localhost:8080".to_string(),
+            enabled: true,
+            created_at: "2024-01-01T00:00:00+00:00".to_string(),
+            last_delivery: None,
+        };
+        storage.create_forward("bin", &forward).await.unwrap();
+        let mut ids = Vec::new();
+        for i in 0..=MAX_DELIVERIES_PER_FORWARD {
+            let delivery = ForwardDelivery {
+                delivery_id: 0,
+                request_id: Uuid::new_v4(),
+                status: "pending".to_string(),
+                status_code: None,
+                error: Some("connection refused".to_string()),
+                attempts: 1,
+                attempted_at: "2024-01-01T00:00:00+00:00".to_string(),
+                next_attempt_at: Some(format!("2024-01-01T00:{:02}:{:02}+00:00", i / 60, i % 60)),
+                latency_ms: None,
+                response: None,
+            };
+            ids.push(storage.record_delivery("forward", &delivery).await.unwrap());
+        }
+
+        let deliveries = storage.list_deliveries("forward").await.unwrap();
+        assert_eq!(deliveries.len() as i64, MAX_DELIVERIES_PER_FORWARD);
+        assert_eq!(deliveries[0].delivery_id, *ids.last().unwrap());
+        assert!(deliveries.iter().all(|delivery| delivery.delivery_id != ids[0]));
+
+        let due = storage.due_deliveries("2024-01-01T00:00:05+00:00", 2).await.unwrap();
+        assert_eq!(due.iter().map(|due| due.delivery.delivery_id).collect::<Vec<_>>(), vec![ids[1], ids[2]]);
+
+        let delivered = ForwardDelivery { status: "delivered".to_string(), next_attempt_at: None, ..due[0].delivery.clone() };
+        storage.update_delivery(&delivered).await.unwrap();
+        let due = storage.due_deliveries("2024-01-01T00:00:05+00:00", 10).await.unwrap();
+        assert_eq!(due.len(), 4);
+        storage.update_forward("bin", "forward", &ForwardUpdate { enabled: Some(false), ..Default::default() }).await.unwrap();
+        assert!(storage.due_deliveries("2024-01-01T00:00:05+00:00", 10).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn search_matches_words_and_phrases() {
+        assert!(matches_search(r#"{"order_id": "ord-123", "status": "Paid"}"#, "paid ord-123"));
+        assert!(!matches_search(r#"{"status": "unpaid"}"#, "paid"));
+        assert!(!matches_search("123-ord", "ord-123"));
+    }
+}
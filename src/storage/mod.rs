@@ -0,0 +1,405 @@
+//! Persistence for bins and the requests they capture.
+//!
+//! Handlers only talk to the [`Storage`] trait. [`SqliteStorage`] is the production
+//! backend; [`MemoryStorage`] keeps everything in process for tests and ephemeral
+//! deployments (`url = "memory://"`).
+
+pub mod blob;
+pub mod memory;
+pub mod sqlite;
+
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use std::fmt;
+use uuid::Uuid;
+
+use crate::models::{
+    AdminBinSummary, AdminSearchResult, BinInfo, BinStorageUsage, BlobStorageUsage, Callback, Extractor, BinLimits, BinNotifications, BinChaos, BinPause, BinProxy, BinRateLimit, BinSampling, BinScript, BinMetadataUpdate, CustomResponse, Forward, ForwardDelivery, ForwardUpdate,
+    IpAccessRules, LoggedRequest, RedactionRules, RejectedRequest, RequestDelivery, ResponseRule, ResponseSequence, SignatureVerification,
+};
+
+pub use memory::MemoryStorage;
+pub use sqlite::SqliteStorage;
+
+/// Database URL that selects the in-memory backend
+pub const MEMORY_URL: &str = "memory://";
+
+/// Response rules a bin may have
+pub const MAX_RESPONSE_RULES: usize = 100;
+
+/// Responses a bin's response sequence may hold
+pub const MAX_SEQUENCE_RESPONSES: usize = 100;
+
+/// Delivery records kept per forward target
+pub const MAX_DELIVERIES_PER_FORWARD: i64 = 100;
+
+/// Rejected captures kept per bin
+pub const MAX_REJECTED_PER_BIN: i64 = 100;
+
+/// Replay records kept per bin
+pub const MAX_REPLAYS_PER_BIN: i64 = 100;
+
+/// `RequestDelivery::kind` of deliveries made by a forward, by a replay, by a tunnel client
+/// and by a recording proxy
+pub const KIND_FORWARD: &str = "forward";
+pub const KIND_REPLAY: &str = "replay";
+pub const KIND_TUNNEL: &str = "tunnel";
+pub const KIND_PROXY: &str = "proxy";
+
+#[derive(Debug)]
+pub enum StorageError {
+    /// The `after_id` cursor does not name a request in the bin
+    UnknownCursor,
+    Backend(String),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::UnknownCursor => write!(f, "unknown cursor"),
+            StorageError::Backend(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<sqlx::Error> for StorageError {
+    fn from(err: sqlx::Error) -> Self {
+        StorageError::Backend(err.to_string())
+    }
+}
+
+pub type StorageResult<T> = Result<T, StorageError>;
+
+/// A bin as it is first stored. Empty metadata has already been turned into `None`.
+#[derive(Debug, Clone, Default)]
+pub struct NewBin {
+    pub id: String,
+    pub last_updated: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub token: Option<String>,
+    /// Token of the client that created the bin, for `GET /my/bins`
+    pub creator_token: Option<String>,
+    /// Address of the client that created the bin, for `[limits] max_bins_per_ip`
+    pub creator_ip: Option<String>,
+}
+
+/// Optional `inspect` filters; `None` disables a filter.
+#[derive(Debug, Clone, Default)]
+pub struct RequestFilter {
+    /// Upper-case HTTP method
+    pub method: Option<String>,
+    /// Inclusive RFC 3339 bounds on the capture timestamp
+    pub since: Option<String>,
+    pub until: Option<String>,
+    /// Lower-case header name, optionally with the exact value it must have
+    pub header_name: Option<String>,
+    pub header_value: Option<String>,
+    /// Extractor name, optionally with the exact value it must have extracted
+    pub field_name: Option<String>,
+    pub field_value: Option<String>,
+}
+
+/// Which slice of the filtered requests to return, oldest first
+#[derive(Debug, Clone, Copy)]
+pub struct PageRequest {
+    /// Only requests captured after this one
+    pub after: Option<Uuid>,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+/// What [`Storage::insert_requests`] did to the bin
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InsertOutcome {
+    /// Requests in the bin once the new ones were stored, before trimming
+    pub request_count: i64,
+    /// Oldest requests removed to stay within the limit
+    pub trimmed: u64,
+}
+
+/// A pending delivery with what is needed to attempt it again
+#[derive(Debug, Clone, PartialEq)]
+pub struct DueDelivery {
+    pub bin_id: String,
+    pub forward_id: String,
+    pub url: String,
+    pub delivery: ForwardDelivery,
+}
+
+/// A bin listed for the client that created it, with what its expiry depends on
+#[derive(Debug, Clone)]
+pub struct CreatorBin {
+    pub summary: AdminBinSummary,
+    /// The bin's own `expiry_hours`, when it overrides `[cleanup] bin_expiry_hours`
+    pub expiry_hours: Option<i64>,
+    /// Bins holding pinned requests never expire
+    pub pinned: bool,
+}
+
+/// What [`Storage::run_maintenance`] did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MaintenanceOutcome {
+    /// Pages the database file shrank by
+    pub reclaimed_pages: i64,
+    pub page_size: i64,
+    /// Whether the database had to be rebuilt to allow incremental vacuuming
+    pub rebuilt: bool,
+}
+
+/// Connections of a backend's pool
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolStatus {
+    /// Open connections, idle or in use
+    pub size: u32,
+    pub idle: u32,
+    pub max_connections: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StorageStats {
+    pub bin_count: i64,
+    pub private_bin_count: i64,
+    pub request_count: i64,
+    /// Requests captured at or after the `since` passed to [`Storage::stats`]
+    pub recent_request_count: i64,
+    /// Captures of sampling bins that weren't stored
+    pub sampled_out_count: i64,
+    pub size_bytes: i64,
+}
+
+/// Everything about a bin that its captures depend on, see [`Storage::bin_settings`]
+#[derive(Debug, Clone, Default)]
+pub struct BinSettings {
+    pub pause: Option<BinPause>,
+    pub ip_access: Option<IpAccessRules>,
+    pub rate_limit: Option<BinRateLimit>,
+    pub limits: Option<BinLimits>,
+    pub signature: Option<SignatureVerification>,
+    pub redaction: Option<RedactionRules>,
+    pub extractors: Vec<Extractor>,
+    pub sampling: Option<BinSampling>,
+    pub chaos: Option<BinChaos>,
+    pub proxy: Option<BinProxy>,
+    pub script: Option<BinScript>,
+    /// Whether the bin has a response sequence; its position is only known by taking
+    /// the next response
+    pub has_sequence: bool,
+    /// In evaluation order
+    pub rules: Vec<ResponseRule>,
+    pub response: Option<CustomResponse>,
+    pub notifications: Option<BinNotifications>,
+    /// In creation order, without their latest delivery
+    pub forwards: Vec<Forward>,
+    /// In creation order, with their secrets
+    pub callbacks: Vec<Callback>,
+}
+
+/// How the store's space is used, see [`Storage::storage_report`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageReport {
+    pub size_bytes: i64,
+    /// All 0 for backends without pages
+    pub page_size: i64,
+    pub page_count: i64,
+    pub free_page_count: i64,
+    /// Largest first
+    pub bins: Vec<BinStorageUsage>,
+    pub blobs: BlobStorageUsage,
+}
+
+/// Everything the server needs to persist. Timestamps are RFC 3339 strings in UTC,
+/// which sort chronologically. Soft-deleted bins don't exist as far as bin lookups and
+/// listings are concerned, except for [`Storage::bin_token`].
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn bin_exists(&self, bin_id: &str) -> StorageResult<bool>;
+    /// All of a bin's settings in one read, for captures; `None` for unknown bins
+    async fn bin_settings(&self, bin_id: &str) -> StorageResult<Option<BinSettings>>;
+    async fn create_bin(&self, bin: NewBin) -> StorageResult<()>;
+    async fn bin_info(&self, bin_id: &str) -> StorageResult<Option<BinInfo>>;
+    /// Requests stored in the bin, `None` for unknown bins
+    async fn bin_request_count(&self, bin_id: &str) -> StorageResult<Option<i64>>;
+    /// A number that changes whenever the bin's requests do (captured, deleted or pinned),
+    /// and the bin's `last_updated`
+    async fn bin_revision(&self, bin_id: &str) -> StorageResult<Option<(i64, Option<String>)>>;
+    /// Apply a metadata update; omitted fields are left alone and empty strings clear a field.
+    async fn update_bin_metadata(&self, bin_id: &str, update: &BinMetadataUpdate) -> StorageResult<()>;
+    /// `None` for unknown bins, `Some(None)` for public ones. Soft-deleted bins keep their
+    /// token so they can only be restored with it.
+    async fn bin_token(&self, bin_id: &str) -> StorageResult<Option<Option<String>>>;
+    /// Token of the bin a request was captured in, as for [`Storage::bin_token`]
+    async fn request_bin_token(&self, request_id: Uuid) -> StorageResult<Option<Option<String>>>;
+    async fn custom_response(&self, bin_id: &str) -> StorageResult<Option<CustomResponse>>;
+    async fn set_custom_response(&self, bin_id: &str, response: Option<&CustomResponse>) -> StorageResult<()>;
+    async fn response_sequence(&self, bin_id: &str) -> StorageResult<Option<ResponseSequence>>;
+    /// Store a sequence starting from its first response, or remove it with `None`
+    async fn set_response_sequence(&self, bin_id: &str, sequence: Option<&ResponseSequence>) -> StorageResult<()>;
+    /// Take the sequence's next response and advance it, atomically so concurrent
+    /// captures each get their own. `None` without a sequence or once a `once` sequence is used up.
+    async fn next_sequence_response(&self, bin_id: &str) -> StorageResult<Option<CustomResponse>>;
+    /// A bin's response rules in evaluation order
+    async fn response_rules(&self, bin_id: &str) -> StorageResult<Vec<ResponseRule>>;
+    /// Replace all of a bin's response rules, keeping the given order
+    async fn set_response_rules(&self, bin_id: &str, rules: &[ResponseRule]) -> StorageResult<()>;
+    /// Evaluate `rule` after the bin's existing rules
+    async fn add_response_rule(&self, bin_id: &str, rule: &ResponseRule) -> StorageResult<()>;
+    /// Replace the rule with `rule.rule_id` in place. Returns whether it existed.
+    async fn update_response_rule(&self, bin_id: &str, rule: &ResponseRule) -> StorageResult<bool>;
+    /// Returns whether the rule existed
+    async fn delete_response_rule(&self, bin_id: &str, rule_id: &str) -> StorageResult<bool>;
+    async fn signature_verification(&self, bin_id: &str) -> StorageResult<Option<SignatureVerification>>;
+    /// Store how the bin's captures are signed, or stop checking with `None`
+    async fn set_signature_verification(&self, bin_id: &str, verification: Option<&SignatureVerification>) -> StorageResult<()>;
+    async fn redaction_rules(&self, bin_id: &str) -> StorageResult<Option<RedactionRules>>;
+    /// Store the bin's own redaction rules, or remove them with `None`
+    async fn set_redaction_rules(&self, bin_id: &str, rules: Option<&RedactionRules>) -> StorageResult<()>;
+    async fn ip_access_rules(&self, bin_id: &str) -> StorageResult<Option<IpAccessRules>>;
+    /// Store which sources may capture into the bin, or let everyone in again with `None`
+    async fn set_ip_access_rules(&self, bin_id: &str, rules: Option<&IpAccessRules>) -> StorageResult<()>;
+    /// Only the newest [`MAX_REJECTED_PER_BIN`] rejections of a bin are kept
+    async fn record_rejected_request(&self, bin_id: &str, rejected: &RejectedRequest) -> StorageResult<()>;
+    /// A bin's rejected captures, newest first
+    async fn rejected_requests(&self, bin_id: &str) -> StorageResult<Vec<RejectedRequest>>;
+    /// The status a paused bin answers with; `None` while it captures
+    async fn bin_pause(&self, bin_id: &str) -> StorageResult<Option<BinPause>>;
+    /// Stop capturing into the bin, or resume with `None`
+    async fn set_bin_pause(&self, bin_id: &str, pause: Option<&BinPause>) -> StorageResult<()>;
+    /// Which captures the bin stores; `None` while it stores all of them
+    async fn bin_sampling(&self, bin_id: &str) -> StorageResult<Option<BinSampling>>;
+    /// Sample the bin's captures from now on, or store all of them again with `None`
+    async fn set_bin_sampling(&self, bin_id: &str, sampling: Option<&BinSampling>) -> StorageResult<()>;
+    /// Count a capture into a sampling bin, returning how many it has seen since the
+    /// sampling was set, this one included; 0 for unknown bins
+    async fn next_sample(&self, bin_id: &str) -> StorageResult<i64>;
+    /// Count a capture the bin's sampling didn't store
+    async fn record_sampled_out(&self, bin_id: &str) -> StorageResult<()>;
+    /// Failures the bin injects into its answers; `None` while it behaves
+    async fn bin_chaos(&self, bin_id: &str) -> StorageResult<Option<BinChaos>>;
+    /// Inject failures into the bin's answers from now on, or stop with `None`
+    async fn set_bin_chaos(&self, bin_id: &str, chaos: Option<&BinChaos>) -> StorageResult<()>;
+    /// Where the bin proxies its captures; `None` while it answers them itself
+    async fn bin_proxy(&self, bin_id: &str) -> StorageResult<Option<BinProxy>>;
+    /// Proxy the bin's captures to an upstream, or answer them again with `None`
+    async fn set_bin_proxy(&self, bin_id: &str, proxy: Option<&BinProxy>) -> StorageResult<()>;
+    /// The script the bin runs on every capture, if any
+    async fn bin_script(&self, bin_id: &str) -> StorageResult<Option<BinScript>>;
+    /// Run a script on the bin's captures from now on, or stop with `None`
+    async fn set_bin_script(&self, bin_id: &str, script: Option<&BinScript>) -> StorageResult<()>;
+    async fn bin_rate_limit(&self, bin_id: &str) -> StorageResult<Option<BinRateLimit>>;
+    /// Override the configured per-bin rate limit, or go back to it with `None`
+    async fn set_bin_rate_limit(&self, bin_id: &str, limit: Option<&BinRateLimit>) -> StorageResult<()>;
+    async fn bin_limits(&self, bin_id: &str) -> StorageResult<Option<BinLimits>>;
+    /// Override the server-wide limits for the bin, or go back to them with `None`
+    async fn set_bin_limits(&self, bin_id: &str, limits: Option<&BinLimits>) -> StorageResult<()>;
+    /// Ids and expiry hours of the bins that override `bin_expiry_hours`
+    async fn bin_expiry_overrides(&self) -> StorageResult<Vec<(String, i64)>>;
+    /// Ids and retention hours of the bins that override `request_retention_hours`
+    async fn bin_retention_overrides(&self) -> StorageResult<Vec<(String, i64)>>;
+    /// Bins holding unpinned requests captured before `before`
+    async fn bins_with_requests_before(&self, before: &str) -> StorageResult<Vec<String>>;
+    async fn bin_notifications(&self, bin_id: &str) -> StorageResult<Option<BinNotifications>>;
+    /// Post captures to chat from now on, or stop with `None`
+    async fn set_bin_notifications(&self, bin_id: &str, notifications: Option<&BinNotifications>) -> StorageResult<()>;
+    /// A bin's extractors, empty when it has none
+    async fn extractors(&self, bin_id: &str) -> StorageResult<Vec<Extractor>>;
+    /// Replace a bin's extractors; values already extracted are kept
+    async fn set_extractors(&self, bin_id: &str, extractors: &[Extractor]) -> StorageResult<()>;
+    async fn touch_bin(&self, bin_id: &str, last_updated: &str) -> StorageResult<()>;
+    /// Delete a bin and its requests. Returns whether the bin existed.
+    async fn delete_bin(&self, bin_id: &str) -> StorageResult<bool>;
+    /// Hide a bin until [`Storage::restore_bin`] or a purge with [`Storage::delete_bin`].
+    /// Returns whether a live bin existed.
+    async fn soft_delete_bin(&self, bin_id: &str, deleted_at: &str) -> StorageResult<bool>;
+    /// Bring back a soft-deleted bin with a fresh `last_updated`. Returns whether there was one.
+    async fn restore_bin(&self, bin_id: &str, last_updated: &str) -> StorageResult<bool>;
+    /// Ids of bins soft-deleted before `before`
+    async fn deleted_bins(&self, before: &str) -> StorageResult<Vec<String>>;
+    /// Ids and `last_updated` of bins last updated before `before` (and at or after `since`),
+    /// leaving out bins that hold pinned requests
+    async fn inactive_bins(&self, before: &str, since: Option<&str>) -> StorageResult<Vec<(String, String)>>;
+    async fn list_bins(&self, limit: i64, offset: i64) -> StorageResult<(Vec<AdminBinSummary>, i64)>;
+    /// Live bins created with `creator_token`, most recently active first
+    async fn creator_bins(&self, creator_token: &str) -> StorageResult<Vec<CreatorBin>>;
+    /// How many live bins were created from `creator_ip` and, when given, with `creator_token`
+    async fn count_creator_bins(&self, creator_ip: &str, creator_token: Option<&str>) -> StorageResult<(i64, i64)>;
+
+    /// Store requests in order, drop the oldest unpinned ones beyond `max_requests` and set
+    /// the bin's `last_updated`, all in one transaction.
+    async fn insert_requests(&self, bin_id: &str, requests: &[LoggedRequest], max_requests: i64, last_updated: &str) -> StorageResult<InsertOutcome>;
+    /// A page of matching requests plus the number of requests matching the filter
+    async fn list_requests(&self, bin_id: &str, filter: &RequestFilter, page: PageRequest) -> StorageResult<(Vec<LoggedRequest>, i64)>;
+    /// The newest `limit` requests of a bin, oldest first
+    async fn recent_requests(&self, bin_id: &str, limit: i64) -> StorageResult<Vec<LoggedRequest>>;
+    async fn get_request(&self, bin_id: &str, request_id: Uuid) -> StorageResult<Option<LoggedRequest>>;
+    /// Every request of a bin, oldest first, without loading them all at once
+    fn stream_requests(&self, bin_id: String) -> BoxStream<'static, StorageResult<LoggedRequest>>;
+    /// Requests whose body contains every whitespace separated term, newest first
+    async fn search_bin(&self, bin_id: &str, query: &str, limit: i64) -> StorageResult<Vec<LoggedRequest>>;
+    /// Requests in any bin whose body or headers contain `text` (ASCII case-insensitive), newest first
+    async fn search_all(&self, text: &str, since: &str, until: &str, limit: i64) -> StorageResult<Vec<AdminSearchResult>>;
+    /// Pin or unpin a request. Returns whether the bin has the request.
+    async fn set_request_pinned(&self, bin_id: &str, request_id: Uuid, pinned: bool) -> StorageResult<bool>;
+    /// Returns the bin the request was captured in, or `None` if it didn't exist
+    async fn delete_request(&self, request_id: Uuid) -> StorageResult<Option<String>>;
+    /// Delete every request of a bin, returning how many were removed
+    async fn clear_bin(&self, bin_id: &str) -> StorageResult<u64>;
+    /// Delete a bin's oldest unpinned requests, only those captured before `before` when
+    /// given and never the newest `keep_last`, returning how many were removed
+    async fn prune_bin(&self, bin_id: &str, before: Option<&str>, keep_last: Option<i64>) -> StorageResult<u64>;
+    /// Delete up to `count` of the oldest unpinned requests across all bins, returning
+    /// the bin and id of each
+    async fn evict_oldest_requests(&self, count: i64) -> StorageResult<Vec<(String, Uuid)>>;
+
+    async fn stats(&self, since: &str) -> StorageResult<StorageStats>;
+    /// Bytes currently in use, which `[cleanup] max_storage_bytes` is checked against
+    async fn storage_size(&self) -> StorageResult<i64>;
+    /// File and page sizes, the `top` bins holding the most bytes and blob store usage
+    async fn storage_report(&self, top: i64) -> StorageResult<StorageReport>;
+    /// Give space freed by deletions back to the filesystem and refresh query planner
+    /// statistics, for backends that keep either
+    async fn run_maintenance(&self) -> StorageResult<MaintenanceOutcome>;
+    /// Cheapest possible round trip, proving the backend answers
+    async fn ping(&self) -> StorageResult<()>;
+    /// Connection pool usage, for backends that have a pool
+    fn pool_status(&self) -> Option<PoolStatus>;
+
+    async fn create_forward(&self, bin_id: &str, forward: &Forward) -> StorageResult<()>;
+    /// A bin's forwards in the order they were created, with their latest delivery
+    async fn list_forwards(&self, bin_id: &str) -> StorageResult<Vec<Forward>>;
+    async fn get_forward(&self, bin_id: &str, forward_id: &str) -> StorageResult<Option<Forward>>;
+    /// Returns whether the forward existed
+    async fn update_forward(&self, bin_id: &str, forward_id: &str, update: &ForwardUpdate) -> StorageResult<bool>;
+    /// Returns whether the forward existed
+    async fn delete_forward(&self, bin_id: &str, forward_id: &str) -> StorageResult<bool>;
+    /// Store a new delivery, ignoring its `delivery_id`, and return the id it was given.
+    /// Only the newest [`MAX_DELIVERIES_PER_FORWARD`] deliveries of a forward are kept.
+    async fn record_delivery(&self, forward_id: &str, delivery: &ForwardDelivery) -> StorageResult<i64>;
+    /// Overwrite the outcome of the delivery with `delivery.delivery_id`
+    async fn update_delivery(&self, delivery: &ForwardDelivery) -> StorageResult<()>;
+    /// A forward's deliveries, newest first
+    async fn list_deliveries(&self, forward_id: &str) -> StorageResult<Vec<ForwardDelivery>>;
+    /// Pending deliveries of enabled forwards whose next attempt is due at `now`, soonest first
+    async fn due_deliveries(&self, now: &str, limit: i64) -> StorageResult<Vec<DueDelivery>>;
+    /// Store a replay (or tunnel delivery, by `kind`) of one of a bin's requests, ignoring
+    /// `delivery.delivery_id`, and return the id it was given. Only the newest
+    /// [`MAX_REPLAYS_PER_BIN`] of a bin are kept.
+    async fn record_replay(&self, bin_id: &str, kind: &str, url: &str, delivery: &ForwardDelivery) -> StorageResult<i64>;
+    /// Every delivery of one of a bin's requests: the forwards' in the order the forwards
+    /// were created, then replays and tunnel deliveries oldest first
+    async fn request_deliveries(&self, bin_id: &str, request_id: Uuid) -> StorageResult<Vec<RequestDelivery>>;
+
+    async fn create_callback(&self, bin_id: &str, callback: &Callback) -> StorageResult<()>;
+    /// A bin's callbacks in the order they were created, with their secrets
+    async fn list_callbacks(&self, bin_id: &str) -> StorageResult<Vec<Callback>>;
+    /// Returns whether the callback existed
+    async fn delete_callback(&self, bin_id: &str, callback_id: &str) -> StorageResult<bool>;
+}
+
+/// `None` for values that should be stored as NULL
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().filter(|v| !v.is_empty())
+}
@@ -0,0 +1,2363 @@
+use async_trait::async_trait;
+use futures::future::join_all;
+use futures::stream::{BoxStream, StreamExt};
+use sqlx::{migrate::Migrator, query, SqliteConnection, SqlitePool};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::blob::BlobStore;
+use super::{non_empty, BinSettings, CreatorBin, DueDelivery, InsertOutcome, KIND_FORWARD, MaintenanceOutcome, NewBin, MAX_DELIVERIES_PER_FORWARD, MAX_REJECTED_PER_BIN, MAX_REPLAYS_PER_BIN, PageRequest, PoolStatus, RequestFilter, Storage, StorageReport, StorageResult, StorageStats};
+use crate::config::CompressionConfig;
+use crate::utils::body::ENCODING_UTF8;
+use crate::models::{
+    AdminBinSummary, AdminSearchResult, BinInfo, BinStorageUsage, BlobStorageUsage, Callback, Extractor, BinLimits, BinNotifications, BinChaos, BinPause, BinProxy, BinRateLimit, BinSampling, BinScript, BinMetadataUpdate, ChatService, CustomResponse, Forward, ForwardDelivery, ForwardUpdate,
+    IpAccessRules, LoggedRequest, RedactionRules, RejectedRequest, ReplayResponse, RequestDelivery, ResponseRule, ResponseSequence, SequenceMode, SignatureProvider, SignatureVerification,
+};
+
+/// Columns selected into a `StoredRequest`
+const REQUEST_COLUMNS: &str = "method, headers, \
+    CASE WHEN body_compression IS NULL THEN body END AS body, timestamp, request_id, \
+    body_format, content_type_mismatch, query_string, query_params, source_ip, source_port, path, body_encoding, content_encoding, body_ref, signature_valid, pinned, chaos, \
+    CASE WHEN body_compression IS NOT NULL THEN body END AS compressed_body, body_compression";
+
+/// Optional inspect filters; `?N IS NULL` disables a filter when its parameter is absent.
+/// The `since` bound is written so it can use the `(bin_id, timestamp)` index.
+/// Header names are matched case-insensitively in both the pair and the legacy object layout.
+const INSPECT_FILTER: &str = "bin_id = ?1 \
+    AND (?2 IS NULL OR method = ?2) \
+    AND timestamp >= COALESCE(?3, '') \
+    AND (?4 IS NULL OR timestamp <= ?4) \
+    AND (?5 IS NULL OR EXISTS ( \
+        SELECT 1 FROM json_each(requests.headers) h \
+        WHERE lower(CASE h.type WHEN 'array' THEN json_extract(h.value, '$[0]') ELSE h.key END) = ?5 \
+          AND (?6 IS NULL OR (CASE h.type WHEN 'array' THEN json_extract(h.value, '$[1]') ELSE h.value END) = ?6))) \
+    AND (?7 IS NULL OR EXISTS ( \
+        SELECT 1 FROM request_fields f \
+        WHERE f.request_id = requests.request_id AND f.name = ?7 AND (?8 IS NULL OR f.value = ?8)))";
+
+/// Versioned schema migrations from `migrations/`, applied on startup
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// `body_compression` value of bodies stored as zstd frames
+const ZSTD: &str = "zstd";
+
+/// Extra attempts for a capture-path write that is still locked out after `busy_timeout`
+const BUSY_RETRIES: u32 = 3;
+const BUSY_RETRY_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Requests deleted per statement when pruning a bin, so captures aren't held up behind
+/// one long delete
+const PRUNE_BATCH: i64 = 500;
+
+/// Rows buffered between the database reader and a slow export client
+const EXPORT_STREAM_BUFFER: usize = 64;
+
+/// `PRAGMA auto_vacuum` value of databases that free pages on `incremental_vacuum`
+const AUTO_VACUUM_INCREMENTAL: i64 = 2;
+
+/// Storage backed by the `bins` and `requests` tables of a SQLite database
+#[derive(Clone)]
+pub struct SqliteStorage {
+    pool: SqlitePool,
+    blobs: Option<BodyBlobs>,
+    compression: Option<BodyCompression>,
+    /// Serialises capture transactions in process. SQLite allows a single writer anyway,
+    /// and queueing here is much cheaper than sleeping in its busy handler.
+    capture_lock: Arc<Mutex<()>>,
+}
+
+/// Where bodies above `threshold` bytes are kept instead of the `body` column
+#[derive(Clone)]
+struct BodyBlobs {
+    store: Arc<dyn BlobStore>,
+    threshold: usize,
+}
+
+/// zstd settings for bodies of at least `min_bytes` bytes
+#[derive(Clone, Copy)]
+struct BodyCompression {
+    level: i32,
+    min_bytes: usize,
+}
+
+/// A request row; `body` is NULL when the body lives in the blob store under `body_ref`
+/// or is stored compressed in `compressed_body`
+#[derive(sqlx::FromRow)]
+struct StoredRequest {
+    #[sqlx(flatten)]
+    request: LoggedRequest,
+    body_ref: Option<String>,
+    compressed_body: Option<Vec<u8>>,
+    body_compression: Option<String>,
+}
+
+fn parse_chat_service(service: &str) -> Option<ChatService> {
+    match service {
+        "slack" => Some(ChatService::Slack),
+        "discord" => Some(ChatService::Discord),
+        _ => None,
+    }
+}
+
+fn parse_signature_provider(provider: &str) -> Option<SignatureProvider> {
+    match provider {
+        "github" => Some(SignatureProvider::Github),
+        "stripe" => Some(SignatureProvider::Stripe),
+        "slack" => Some(SignatureProvider::Slack),
+        "hmac" => Some(SignatureProvider::Hmac),
+        _ => None,
+    }
+}
+
+fn parse_sequence_mode(mode: &str) -> Option<SequenceMode> {
+    match mode {
+        "rotate" => Some(SequenceMode::Rotate),
+        "once" => Some(SequenceMode::Once),
+        _ => None,
+    }
+}
+
+fn read_custom_response(bin_id: &str, json: &str) -> Option<CustomResponse> {
+    serde_json::from_str(json).map_err(|err| warn!(%bin_id, %err, "Ignoring unreadable custom response")).ok()
+}
+
+fn read_signature(bin_id: &str, provider: &str, secret: String, header: Option<String>) -> Option<SignatureVerification> {
+    match parse_signature_provider(provider) {
+        Some(provider) => Some(SignatureVerification { provider, secret, header }),
+        None => {
+            warn!(%bin_id, %provider, "Ignoring unknown signature provider");
+            None
+        }
+    }
+}
+
+fn read_redaction(bin_id: &str, headers: &str, body_patterns: &str) -> Option<RedactionRules> {
+    match (serde_json::from_str(headers), serde_json::from_str(body_patterns)) {
+        (Ok(headers), Ok(body_patterns)) => Some(RedactionRules { headers, body_patterns }),
+        _ => {
+            warn!(%bin_id, "Ignoring unreadable redaction rules");
+            None
+        }
+    }
+}
+
+fn read_ip_access(bin_id: &str, allow: &str, deny: &str, log_rejected: bool) -> Option<IpAccessRules> {
+    match (serde_json::from_str(allow), serde_json::from_str(deny)) {
+        (Ok(allow), Ok(deny)) => Some(IpAccessRules { allow, deny, log_rejected }),
+        _ => {
+            warn!(%bin_id, "Ignoring unreadable IP access rules");
+            None
+        }
+    }
+}
+
+fn read_chaos(bin_id: &str, json: &str) -> Option<BinChaos> {
+    serde_json::from_str(json).map_err(|err| warn!(%bin_id, %err, "Ignoring unreadable chaos settings")).ok()
+}
+
+fn read_extractors(bin_id: &str, json: &str) -> Vec<Extractor> {
+    serde_json::from_str(json)
+        .map_err(|err| warn!(%bin_id, %err, "Ignoring unreadable extractors"))
+        .unwrap_or_default()
+}
+
+fn read_notifications(bin_id: &str, service: &str, webhook_url: String, methods: &str, path_prefix: Option<String>, failed_signatures_only: bool) -> Option<BinNotifications> {
+    match (parse_chat_service(service), serde_json::from_str(methods)) {
+        (Some(service), Ok(methods)) => Some(BinNotifications { service, webhook_url, methods, path_prefix, failed_signatures_only }),
+        _ => {
+            warn!(%bin_id, %service, "Ignoring unreadable bin notifications");
+            None
+        }
+    }
+}
+
+fn read_callback(bin_id: &str, (callback_id, url, events, secret, created_at): (String, String, String, String, String)) -> Option<Callback> {
+    match serde_json::from_str(&events) {
+        Ok(events) => Some(Callback { callback_id, url, events, secret, created_at }),
+        Err(_) => {
+            warn!(%bin_id, %callback_id, "Ignoring unreadable callback");
+            None
+        }
+    }
+}
+
+#[derive(sqlx::FromRow, serde::Deserialize)]
+struct StoredRule {
+    id: String,
+    method: Option<String>,
+    path: Option<String>,
+    response: String,
+}
+
+impl StoredRule {
+    fn into_rule(self, bin_id: &str) -> Option<ResponseRule> {
+        match serde_json::from_str(&self.response) {
+            Ok(response) => Some(ResponseRule { rule_id: self.id, method: self.method, path: self.path, response }),
+            Err(err) => {
+                warn!(%bin_id, rule_id = %self.id, %err, "Ignoring unreadable response rule");
+                None
+            }
+        }
+    }
+}
+
+/// A bin's settings as [`BIN_SETTINGS_QUERY`] reads them. Settings kept in their own
+/// table are all NULL when the bin has none; lists are JSON arrays.
+#[derive(sqlx::FromRow)]
+struct StoredSettings {
+    paused_status: Option<u16>,
+    proxy_upstream: Option<String>,
+    chaos: Option<String>,
+    sample_every: Option<u32>,
+    sample_percent: Option<f64>,
+    script: Option<String>,
+    response: Option<String>,
+    access_allow: Option<String>,
+    access_deny: Option<String>,
+    access_log_rejected: Option<bool>,
+    requests_per_second: Option<f64>,
+    burst_size: Option<u32>,
+    limits_bin_id: Option<String>,
+    max_requests: Option<i64>,
+    max_body_size: Option<i64>,
+    expiry_hours: Option<i64>,
+    retention_hours: Option<i64>,
+    signature_provider: Option<String>,
+    signature_secret: Option<String>,
+    signature_header: Option<String>,
+    redaction_headers: Option<String>,
+    redaction_body_patterns: Option<String>,
+    extractors: Option<String>,
+    notification_service: Option<String>,
+    notification_webhook_url: Option<String>,
+    notification_methods: Option<String>,
+    notification_path_prefix: Option<String>,
+    notification_failed_signatures_only: Option<bool>,
+    has_sequence: bool,
+    rules: String,
+    forwards: String,
+    callbacks: String,
+}
+
+const BIN_SETTINGS_QUERY: &str = "SELECT b.paused_status, b.proxy_upstream, b.chaos, b.sample_every, b.sample_percent, b.script, b.response, \
+    a.allow AS access_allow, a.deny AS access_deny, a.log_rejected AS access_log_rejected, \
+    rl.requests_per_second, rl.burst_size, \
+    l.bin_id AS limits_bin_id, l.max_requests, l.max_body_size, l.expiry_hours, l.retention_hours, \
+    s.provider AS signature_provider, s.secret AS signature_secret, s.header AS signature_header, \
+    r.headers AS redaction_headers, r.body_patterns AS redaction_body_patterns, \
+    e.extractors, \
+    n.service AS notification_service, n.webhook_url AS notification_webhook_url, n.methods AS notification_methods, \
+    n.path_prefix AS notification_path_prefix, n.failed_signatures_only AS notification_failed_signatures_only, \
+    EXISTS (SELECT 1 FROM response_sequences WHERE bin_id = b.id) AS has_sequence, \
+    (SELECT json_group_array(json_object('id', id, 'method', method, 'path', path, 'response', response)) \
+        FROM (SELECT * FROM response_rules WHERE bin_id = b.id ORDER BY position)) AS rules, \
+    (SELECT json_group_array(json_array(id, url, enabled, created_at)) \
+        FROM (SELECT * FROM forwards WHERE bin_id = b.id ORDER BY created_at, id)) AS forwards, \
+    (SELECT json_group_array(json_array(id, url, events, secret, created_at)) \
+        FROM (SELECT * FROM callbacks WHERE bin_id = b.id ORDER BY created_at, id)) AS callbacks \
+    FROM bins b \
+    LEFT JOIN ip_access_rules a ON a.bin_id = b.id \
+    LEFT JOIN bin_rate_limits rl ON rl.bin_id = b.id \
+    LEFT JOIN bin_limits l ON l.bin_id = b.id \
+    LEFT JOIN signature_verifications s ON s.bin_id = b.id \
+    LEFT JOIN redaction_rules r ON r.bin_id = b.id \
+    LEFT JOIN bin_extractors e ON e.bin_id = b.id \
+    LEFT JOIN bin_notifications n ON n.bin_id = b.id \
+    WHERE b.id = ? AND b.deleted_at IS NULL";
+
+impl StoredSettings {
+    fn into_settings(self, bin_id: &str) -> BinSettings {
+        let list = |json: &str| -> Vec<serde_json::Value> { serde_json::from_str(json).unwrap_or_default() };
+        let rules = list(&self.rules)
+            .into_iter()
+            .filter_map(|rule| serde_json::from_value::<StoredRule>(rule).ok())
+            .filter_map(|rule| rule.into_rule(bin_id))
+            .collect();
+        let forwards = list(&self.forwards)
+            .into_iter()
+            .filter_map(|forward| serde_json::from_value::<(String, String, i64, String)>(forward).ok())
+            .map(|(forward_id, url, enabled, created_at)| Forward { forward_id, url, enabled: enabled != 0, created_at, last_delivery: None })
+            .collect();
+        let callbacks = list(&self.callbacks)
+            .into_iter()
+            .filter_map(|callback| serde_json::from_value(callback).ok())
+            .filter_map(|callback| read_callback(bin_id, callback))
+            .collect();
+        let limits = self.limits_bin_id.map(|_| BinLimits {
+                max_requests: self.max_requests,
+                max_body_size: self.max_body_size.map(|size| size as usize),
+                expiry_hours: self.expiry_hours,
+                retention_hours: self.retention_hours,
+            });
+
+        BinSettings {
+            pause: self.paused_status.map(|status| BinPause { status }),
+            ip_access: self
+                .access_allow
+                .zip(self.access_deny)
+                .and_then(|(allow, deny)| read_ip_access(bin_id, &allow, &deny, self.access_log_rejected.unwrap_or_default())),
+            rate_limit: self
+                .requests_per_second
+                .zip(self.burst_size)
+                .map(|(requests_per_second, burst_size)| BinRateLimit { requests_per_second, burst_size }),
+            limits,
+            signature: self
+                .signature_provider
+                .zip(self.signature_secret)
+                .and_then(|(provider, secret)| read_signature(bin_id, &provider, secret, self.signature_header)),
+            redaction: self
+                .redaction_headers
+                .zip(self.redaction_body_patterns)
+                .and_then(|(headers, body_patterns)| read_redaction(bin_id, &headers, &body_patterns)),
+            extractors: self.extractors.map(|json| read_extractors(bin_id, &json)).unwrap_or_default(),
+            sampling: (self.sample_every.is_some() || self.sample_percent.is_some())
+                .then_some(BinSampling { every: self.sample_every, percent: self.sample_percent }),
+            chaos: self.chaos.and_then(|json| read_chaos(bin_id, &json)),
+            proxy: self.proxy_upstream.map(|upstream| BinProxy { upstream }),
+            script: self.script.map(|source| BinScript { source }),
+            has_sequence: self.has_sequence,
+            rules,
+            response: self.response.and_then(|json| read_custom_response(bin_id, &json)),
+            notifications: match (self.notification_service, self.notification_webhook_url, self.notification_methods) {
+                (Some(service), Some(webhook_url), Some(methods)) => read_notifications(
+                    bin_id,
+                    &service,
+                    webhook_url,
+                    &methods,
+                    self.notification_path_prefix,
+                    self.notification_failed_signatures_only.unwrap_or_default(),
+                ),
+                _ => None,
+            },
+            forwards,
+            callbacks,
+        }
+    }
+}
+
+fn insert_rule<'q>(bin_id: &'q str, rule: &'q ResponseRule, position: i64) -> sqlx::query::Query<'q, sqlx::Sqlite, sqlx::sqlite::SqliteArguments<'q>> {
+    query("INSERT INTO response_rules (id, bin_id, position, method, path, response) VALUES (?, ?, ?, ?, ?, ?)")
+        .bind(&rule.rule_id)
+        .bind(bin_id)
+        .bind(position)
+        .bind(&rule.method)
+        .bind(&rule.path)
+        .bind(serde_json::to_string(&rule.response).unwrap_or_default())
+}
+
+/// A forward joined with its latest delivery, whose columns are NULL when there is none
+#[derive(sqlx::FromRow)]
+struct StoredForward {
+    id: String,
+    url: String,
+    enabled: bool,
+    created_at: String,
+    delivery_id: Option<i64>,
+    request_id: Option<Uuid>,
+    status: Option<String>,
+    status_code: Option<u16>,
+    error: Option<String>,
+    attempts: Option<u32>,
+    attempted_at: Option<String>,
+    next_attempt_at: Option<String>,
+    latency_ms: Option<i64>,
+    response_headers: Option<String>,
+    response_body: Option<String>,
+    response_body_encoding: Option<String>,
+}
+
+impl StoredForward {
+    fn into_forward(self) -> Forward {
+        let last_delivery = match (self.delivery_id, self.request_id, self.status, self.attempted_at) {
+            (Some(delivery_id), Some(request_id), Some(status), Some(attempted_at)) => Some(ForwardDelivery {
+                delivery_id,
+                request_id,
+                status,
+                status_code: self.status_code,
+                error: self.error,
+                attempts: self.attempts.unwrap_or(1),
+                attempted_at,
+                next_attempt_at: self.next_attempt_at,
+                latency_ms: self.latency_ms.map(|ms| ms as u64),
+                response: stored_response(self.status_code, self.response_headers, self.response_body, self.response_body_encoding),
+            }),
+            _ => None,
+        };
+        Forward { forward_id: self.id, url: self.url, enabled: self.enabled, created_at: self.created_at, last_delivery }
+    }
+}
+
+/// Selects `StoredForward` rows; callers add the WHERE clause
+const FORWARD_QUERY: &str = "SELECT f.id, f.url, f.enabled, f.created_at, \
+    d.id AS delivery_id, d.request_id, d.status, d.status_code, d.error, d.attempts, d.attempted_at, d.next_attempt_at, \
+    d.latency_ms, d.response_headers, d.response_body, d.response_body_encoding \
+    FROM forwards f \
+    LEFT JOIN forward_deliveries d ON d.id = (SELECT MAX(id) FROM forward_deliveries WHERE forward_id = f.id)";
+
+#[derive(sqlx::FromRow)]
+struct StoredDelivery {
+    id: i64,
+    request_id: Uuid,
+    status: String,
+    status_code: Option<u16>,
+    error: Option<String>,
+    attempts: u32,
+    attempted_at: String,
+    next_attempt_at: Option<String>,
+    latency_ms: Option<i64>,
+    response_headers: Option<String>,
+    response_body: Option<String>,
+    response_body_encoding: Option<String>,
+}
+
+impl From<StoredDelivery> for ForwardDelivery {
+    fn from(row: StoredDelivery) -> Self {
+        ForwardDelivery {
+            delivery_id: row.id,
+            request_id: row.request_id,
+            status: row.status,
+            status_code: row.status_code,
+            error: row.error,
+            attempts: row.attempts,
+            attempted_at: row.attempted_at,
+            next_attempt_at: row.next_attempt_at,
+            latency_ms: row.latency_ms.map(|ms| ms as u64),
+            response: stored_response(row.status_code, row.response_headers, row.response_body, row.response_body_encoding),
+        }
+    }
+}
+
+/// The target's answer to a delivery, stored when it answered at all
+fn stored_response(
+    status_code: Option<u16>,
+    headers: Option<String>,
+    body: Option<String>,
+    body_encoding: Option<String>,
+) -> Option<ReplayResponse> {
+    Some(ReplayResponse {
+        status: status_code?,
+        headers: serde_json::from_str(&headers?).unwrap_or_default(),
+        body: body.unwrap_or_default(),
+        body_encoding: body_encoding.unwrap_or_else(|| ENCODING_UTF8.to_string()),
+    })
+}
+
+/// Columns selected into a `StoredDelivery` from `forward_deliveries d`
+const DELIVERY_COLUMNS: &str = "d.id, d.request_id, d.status, d.status_code, d.error, d.attempts, d.attempted_at, d.next_attempt_at, \
+    d.latency_ms, d.response_headers, d.response_body, d.response_body_encoding";
+
+/// Columns selected into a `StoredDelivery` from `replay_deliveries d`, which only ever
+/// have the one attempt
+const REPLAY_COLUMNS: &str = "d.id, d.request_id, d.status, d.status_code, d.error, 1 AS attempts, d.attempted_at, \
+    NULL AS next_attempt_at, d.latency_ms, d.response_headers, d.response_body, d.response_body_encoding";
+
+#[derive(sqlx::FromRow)]
+struct StoredRequestDelivery {
+    kind: String,
+    forward_id: Option<String>,
+    url: String,
+    #[sqlx(flatten)]
+    delivery: StoredDelivery,
+}
+
+#[derive(sqlx::FromRow)]
+struct StoredDueDelivery {
+    bin_id: String,
+    forward_id: String,
+    url: String,
+    #[sqlx(flatten)]
+    delivery: StoredDelivery,
+}
+
+#[derive(sqlx::FromRow)]
+struct StoredSearchResult {
+    bin_id: String,
+    #[sqlx(flatten)]
+    stored: StoredRequest,
+}
+
+impl SqliteStorage {
+    pub fn new(pool: SqlitePool) -> Self {
+        SqliteStorage { pool, blobs: None, compression: None, capture_lock: Arc::new(Mutex::new(())) }
+    }
+
+    /// Store bodies of at least `min_bytes` bytes zstd-compressed when that makes them smaller
+    pub fn with_compression(mut self, config: &CompressionConfig) -> Self {
+        self.compression = config.enabled.then_some(BodyCompression {
+            level: config.level,
+            min_bytes: config.min_bytes,
+        });
+        self
+    }
+
+    /// Keep bodies longer than `threshold` bytes in `store`, with only a reference in the row
+    pub fn with_blobs(mut self, store: Arc<dyn BlobStore>, threshold: usize) -> Self {
+        self.blobs = Some(BodyBlobs { store, threshold });
+        self
+    }
+
+    /// Write a large body to the blob store, returning its key
+    async fn offload_body(&self, request: &LoggedRequest) -> StorageResult<Option<String>> {
+        let (Some(blobs), Some(body)) = (&self.blobs, &request.body) else {
+            return Ok(None);
+        };
+        if body.len() <= blobs.threshold {
+            return Ok(None);
+        }
+        let key = request.request_id.to_string();
+        blobs.store.put(&key, body.clone().into_bytes()).await?;
+        Ok(Some(key))
+    }
+
+    /// The compressed form of a body that stays in the row, if compressing pays off
+    fn compress_body(&self, body: &str) -> Option<Vec<u8>> {
+        let compression = self.compression?;
+        if body.len() < compression.min_bytes {
+            return None;
+        }
+        match zstd::encode_all(body.as_bytes(), compression.level) {
+            Ok(compressed) if compressed.len() < body.len() => Some(compressed),
+            Ok(_) => None,
+            Err(err) => {
+                warn!(%err, "Failed to compress body, storing it uncompressed");
+                None
+            }
+        }
+    }
+
+    async fn restore_all(&self, rows: Vec<StoredRequest>) -> StorageResult<Vec<LoggedRequest>> {
+        let mut requests = join_all(rows.into_iter().map(|row| restore_body(self.blobs.as_ref(), row))).await;
+        self.attach_fields(&mut requests).await?;
+        Ok(requests)
+    }
+
+    /// Fill in `fields` from `request_fields`
+    async fn attach_fields(&self, requests: &mut [LoggedRequest]) -> StorageResult<()> {
+        if requests.is_empty() {
+            return Ok(());
+        }
+        let sql = format!(
+            "SELECT request_id, name, value FROM request_fields WHERE request_id IN ({})",
+            vec!["?"; requests.len()].join(", ")
+        );
+        let mut select = sqlx::query_as::<_, (Uuid, String, String)>(&sql);
+        for request in requests.iter() {
+            select = select.bind(request.request_id);
+        }
+        let mut fields: HashMap<Uuid, BTreeMap<String, String>> = HashMap::new();
+        for (request_id, name, value) in select.fetch_all(&self.pool).await? {
+            fields.entry(request_id).or_default().insert(name, value);
+        }
+        for request in requests.iter_mut() {
+            if let Some(fields) = fields.remove(&request.request_id) {
+                request.fields = fields;
+            }
+        }
+        Ok(())
+    }
+
+    /// Blob keys of the rows matched by `sql`, which selects `body_ref` for a single bound value
+    async fn body_refs<'q, T>(&self, sql: &'q str, value: T) -> StorageResult<Vec<String>>
+    where
+        T: 'q + Send + sqlx::Encode<'q, sqlx::Sqlite> + sqlx::Type<sqlx::Sqlite>,
+    {
+        if self.blobs.is_none() {
+            return Ok(Vec::new());
+        }
+        Ok(sqlx::query_scalar::<_, String>(sql).bind(value).fetch_all(&self.pool).await?)
+    }
+
+    /// Best effort: a blob that can't be deleted only wastes space
+    async fn remove_blobs(&self, keys: Vec<String>) {
+        let Some(blobs) = &self.blobs else { return };
+        for key in keys {
+            if let Err(err) = blobs.store.delete(&key).await {
+                warn!(%key, %err, "Failed to delete offloaded body");
+            }
+        }
+    }
+}
+
+/// Put an offloaded or compressed body back into its request. A missing blob or corrupt
+/// frame leaves the body empty rather than failing the whole listing.
+async fn restore_body(blobs: Option<&BodyBlobs>, row: StoredRequest) -> LoggedRequest {
+    let mut request = row.request;
+    if let Some(compressed) = row.compressed_body {
+        match row.body_compression.as_deref() {
+            Some(ZSTD) => match zstd::decode_all(compressed.as_slice()) {
+                Ok(bytes) => request.body = Some(String::from_utf8_lossy(&bytes).into_owned()),
+                Err(err) => warn!(request_id = %request.request_id, %err, "Failed to decompress stored body"),
+            },
+            other => warn!(request_id = %request.request_id, compression = ?other, "Unknown body compression"),
+        }
+    }
+    if let Some(key) = row.body_ref {
+        match blobs {
+            Some(blobs) => match blobs.store.get(&key).await {
+                Ok(bytes) => request.body = Some(String::from_utf8_lossy(&bytes).into_owned()),
+                Err(err) => warn!(%key, %err, "Failed to load offloaded body"),
+            },
+            None => warn!(%key, "Body was offloaded but no blob store is configured"),
+        }
+    }
+    request
+}
+
+/// Whether SQLite gave up waiting for a lock (`SQLITE_BUSY`, `SQLITE_LOCKED` or one of
+/// their extended codes)
+fn is_busy(err: &sqlx::Error) -> bool {
+    match err {
+        sqlx::Error::Database(db) => db
+            .code()
+            .and_then(|code| code.parse::<i32>().ok())
+            .is_some_and(|code| matches!(code & 0xff, 5 | 6)),
+        _ => false,
+    }
+}
+
+/// Delete up to `limit` of a bin's oldest unpinned requests, only those captured before
+/// `before` when given. Returns how many went and, with `blob_refs`, the offloaded bodies
+/// they referenced.
+async fn delete_oldest(
+    conn: &mut SqliteConnection,
+    bin_id: &str,
+    before: Option<&str>,
+    limit: i64,
+    blob_refs: bool,
+) -> Result<(u64, Vec<String>), sqlx::Error> {
+    const OLDEST: &str = "SELECT id FROM requests WHERE bin_id = ?1 AND NOT pinned AND (?2 IS NULL OR timestamp < ?2) \
+                          ORDER BY id ASC LIMIT ?3";
+    let refs = if blob_refs {
+        sqlx::query_scalar::<_, String>(&format!("SELECT body_ref FROM requests WHERE id IN ({}) AND body_ref IS NOT NULL", OLDEST))
+            .bind(bin_id)
+            .bind(before)
+            .bind(limit)
+            .fetch_all(&mut *conn)
+            .await?
+    } else {
+        Vec::new()
+    };
+    let deleted = query(&format!("DELETE FROM requests WHERE id IN ({})", OLDEST))
+        .bind(bin_id)
+        .bind(before)
+        .bind(limit)
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+    Ok((deleted, refs))
+}
+
+/// Add compressed bodies stored before the capture transaction wrote the full-text index
+/// (0034_requests_fts_from_code.sql) to it. Returns how many were indexed.
+pub async fn index_compressed_bodies(pool: &SqlitePool) -> Result<u64, sqlx::Error> {
+    let mut indexed = 0;
+    let mut after = 0;
+    loop {
+        let rows = sqlx::query_as::<_, (i64, Vec<u8>)>(
+            "SELECT id, body FROM requests \
+             WHERE id > ? AND body_compression = ? AND id NOT IN (SELECT rowid FROM requests_fts) \
+             ORDER BY id LIMIT ?"
+        )
+        .bind(after)
+        .bind(ZSTD)
+        .bind(PRUNE_BATCH)
+        .fetch_all(pool)
+        .await?;
+        let Some((last, _)) = rows.last() else {
+            return Ok(indexed);
+        };
+        after = *last;
+        for (row_id, compressed) in rows {
+            match zstd::decode_all(compressed.as_slice()) {
+                Ok(bytes) => {
+                    query("INSERT INTO requests_fts (rowid, body) VALUES (?, ?)")
+                        .bind(row_id)
+                        .bind(String::from_utf8_lossy(&bytes))
+                        .execute(pool)
+                        .await?;
+                    indexed += 1;
+                }
+                Err(err) => warn!(row_id, %err, "Failed to decompress stored body for indexing"),
+            }
+        }
+    }
+}
+
+/// Run a write, retrying with a growing backoff while another connection holds the lock
+async fn retry_busy<T, F, Fut>(mut write: F) -> Result<T, sqlx::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, sqlx::Error>>,
+{
+    let mut attempt = 0;
+    loop {
+        match write().await {
+            Err(err) if attempt < BUSY_RETRIES && is_busy(&err) => {
+                attempt += 1;
+                warn!(attempt, %err, "Database is busy, retrying write");
+                tokio::time::sleep(BUSY_RETRY_BACKOFF * attempt).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Turn free text into an FTS5 query where every whitespace separated term must match.
+/// Terms are quoted so characters like `-` or `:` in IDs are not parsed as FTS5 syntax.
+fn fts_match_query(text: &str) -> String {
+    text.split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn escape_like(pattern: &str) -> String {
+    pattern.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    async fn bin_exists(&self, bin_id: &str) -> StorageResult<bool> {
+        let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM bins WHERE id = ? AND deleted_at IS NULL")
+            .bind(bin_id)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(count > 0)
+    }
+
+    async fn bin_settings(&self, bin_id: &str) -> StorageResult<Option<BinSettings>> {
+        let row = sqlx::query_as::<_, StoredSettings>(BIN_SETTINGS_QUERY)
+            .bind(bin_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.map(|row| row.into_settings(bin_id)))
+    }
+
+    async fn create_bin(&self, bin: NewBin) -> StorageResult<()> {
+        let tags = bin.tags.as_ref().map(|tags| serde_json::to_string(tags).unwrap_or_default());
+        query("INSERT INTO bins (id, last_updated, name, description, tags, token, creator_token, creator_ip) VALUES (?, ?, ?, ?, ?, ?, ?, ?)")
+            .bind(&bin.id)
+            .bind(&bin.last_updated)
+            .bind(&bin.name)
+            .bind(&bin.description)
+            .bind(&tags)
+            .bind(&bin.token)
+            .bind(&bin.creator_token)
+            .bind(&bin.creator_ip)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn bin_info(&self, bin_id: &str) -> StorageResult<Option<BinInfo>> {
+        let row = sqlx::query_as::<_, (Option<String>, Option<String>, Option<String>, Option<String>, bool, bool, i64)>(
+            "SELECT name, description, tags, last_updated, token IS NOT NULL, paused_status IS NOT NULL, sampled_out_count \
+             FROM bins WHERE id = ? AND deleted_at IS NULL"
+        )
+        .bind(bin_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|(name, description, tags, last_updated, private, paused, sampled_out)| BinInfo {
+            bin_id: bin_id.to_string(),
+            private,
+            name,
+            description,
+            tags: tags.and_then(|t| serde_json::from_str(&t).ok()).unwrap_or_default(),
+            last_updated,
+            paused,
+            sampled_out,
+        }))
+    }
+
+    async fn bin_request_count(&self, bin_id: &str) -> StorageResult<Option<i64>> {
+        Ok(sqlx::query_scalar::<_, i64>("SELECT request_count FROM bins WHERE id = ? AND deleted_at IS NULL")
+            .bind(bin_id)
+            .fetch_optional(&self.pool)
+            .await?)
+    }
+
+    async fn bin_revision(&self, bin_id: &str) -> StorageResult<Option<(i64, Option<String>)>> {
+        Ok(sqlx::query_as::<_, (i64, Option<String>)>(
+            "SELECT revision, last_updated FROM bins WHERE id = ? AND deleted_at IS NULL"
+        )
+        .bind(bin_id)
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    async fn update_bin_metadata(&self, bin_id: &str, update: &BinMetadataUpdate) -> StorageResult<()> {
+        let tags = update.tags.as_ref().map(|tags| serde_json::to_string(tags).unwrap_or_default());
+        query(
+            "UPDATE bins SET \
+                name = CASE WHEN ?1 THEN ?2 ELSE name END, \
+                description = CASE WHEN ?3 THEN ?4 ELSE description END, \
+                tags = CASE WHEN ?5 THEN ?6 ELSE tags END \
+             WHERE id = ?7"
+        )
+        .bind(update.name.is_some())
+        .bind(non_empty(&update.name))
+        .bind(update.description.is_some())
+        .bind(non_empty(&update.description))
+        .bind(tags.is_some())
+        .bind(&tags)
+        .bind(bin_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn bin_token(&self, bin_id: &str) -> StorageResult<Option<Option<String>>> {
+        Ok(sqlx::query_scalar::<_, Option<String>>("SELECT token FROM bins WHERE id = ?")
+            .bind(bin_id)
+            .fetch_optional(&self.pool)
+            .await?)
+    }
+
+    async fn request_bin_token(&self, request_id: Uuid) -> StorageResult<Option<Option<String>>> {
+        Ok(sqlx::query_scalar::<_, Option<String>>(
+            "SELECT bins.token FROM requests JOIN bins ON bins.id = requests.bin_id WHERE requests.request_id = ?"
+        )
+        .bind(request_id)
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    async fn custom_response(&self, bin_id: &str) -> StorageResult<Option<CustomResponse>> {
+        let stored = sqlx::query_scalar::<_, Option<String>>("SELECT response FROM bins WHERE id = ?")
+            .bind(bin_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .flatten();
+
+        Ok(stored.and_then(|json| read_custom_response(bin_id, &json)))
+    }
+
+    async fn set_custom_response(&self, bin_id: &str, response: Option<&CustomResponse>) -> StorageResult<()> {
+        let stored = response.map(|custom| serde_json::to_string(custom).unwrap_or_default());
+        query("UPDATE bins SET response = ? WHERE id = ?")
+            .bind(&stored)
+            .bind(bin_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn response_sequence(&self, bin_id: &str) -> StorageResult<Option<ResponseSequence>> {
+        let row = sqlx::query_as::<_, (String, String, i64)>(
+            "SELECT mode, responses, position FROM response_sequences WHERE bin_id = ?"
+        )
+        .bind(bin_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.and_then(|(mode, responses, position)| {
+            match (parse_sequence_mode(&mode), serde_json::from_str(&responses)) {
+                (Some(mode), Ok(responses)) => Some(ResponseSequence { mode, responses, position: position as u64 }),
+                _ => {
+                    warn!(%bin_id, "Ignoring unreadable response sequence");
+                    None
+                }
+            }
+        }))
+    }
+
+    async fn signature_verification(&self, bin_id: &str) -> StorageResult<Option<SignatureVerification>> {
+        let row = sqlx::query_as::<_, (String, String, Option<String>)>(
+            "SELECT provider, secret, header FROM signature_verifications WHERE bin_id = ?"
+        )
+        .bind(bin_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.and_then(|(provider, secret, header)| read_signature(bin_id, &provider, secret, header)))
+    }
+
+    async fn set_signature_verification(&self, bin_id: &str, verification: Option<&SignatureVerification>) -> StorageResult<()> {
+        match verification {
+            Some(verification) => {
+                query(
+                    "INSERT INTO signature_verifications (bin_id, provider, secret, header) VALUES (?, ?, ?, ?) \
+                     ON CONFLICT (bin_id) DO UPDATE SET provider = excluded.provider, secret = excluded.secret, header = excluded.header"
+                )
+                .bind(bin_id)
+                .bind(verification.provider.as_str())
+                .bind(&verification.secret)
+                .bind(&verification.header)
+                .execute(&self.pool)
+                .await?;
+            }
+            None => {
+                query("DELETE FROM signature_verifications WHERE bin_id = ?")
+                    .bind(bin_id)
+                    .execute(&self.pool)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn redaction_rules(&self, bin_id: &str) -> StorageResult<Option<RedactionRules>> {
+        let row = sqlx::query_as::<_, (String, String)>("SELECT headers, body_patterns FROM redaction_rules WHERE bin_id = ?")
+            .bind(bin_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.and_then(|(headers, body_patterns)| read_redaction(bin_id, &headers, &body_patterns)))
+    }
+
+    async fn set_redaction_rules(&self, bin_id: &str, rules: Option<&RedactionRules>) -> StorageResult<()> {
+        match rules {
+            Some(rules) => {
+                query(
+                    "INSERT INTO redaction_rules (bin_id, headers, body_patterns) VALUES (?, ?, ?) \
+                     ON CONFLICT (bin_id) DO UPDATE SET headers = excluded.headers, body_patterns = excluded.body_patterns"
+                )
+                .bind(bin_id)
+                .bind(serde_json::to_string(&rules.headers).unwrap_or_default())
+                .bind(serde_json::to_string(&rules.body_patterns).unwrap_or_default())
+                .execute(&self.pool)
+                .await?;
+            }
+            None => {
+                query("DELETE FROM redaction_rules WHERE bin_id = ?")
+                    .bind(bin_id)
+                    .execute(&self.pool)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn ip_access_rules(&self, bin_id: &str) -> StorageResult<Option<IpAccessRules>> {
+        let row = sqlx::query_as::<_, (String, String, bool)>("SELECT allow, deny, log_rejected FROM ip_access_rules WHERE bin_id = ?")
+            .bind(bin_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.and_then(|(allow, deny, log_rejected)| read_ip_access(bin_id, &allow, &deny, log_rejected)))
+    }
+
+    async fn set_ip_access_rules(&self, bin_id: &str, rules: Option<&IpAccessRules>) -> StorageResult<()> {
+        match rules {
+            Some(rules) => {
+                query(
+                    "INSERT INTO ip_access_rules (bin_id, allow, deny, log_rejected) VALUES (?, ?, ?, ?) \
+                     ON CONFLICT (bin_id) DO UPDATE SET allow = excluded.allow, deny = excluded.deny, log_rejected = excluded.log_rejected"
+                )
+                .bind(bin_id)
+                .bind(serde_json::to_string(&rules.allow).unwrap_or_default())
+                .bind(serde_json::to_string(&rules.deny).unwrap_or_default())
+                .bind(rules.log_rejected)
+                .execute(&self.pool)
+                .await?;
+            }
+            None => {
+                query("DELETE FROM ip_access_rules WHERE bin_id = ?")
+                    .bind(bin_id)
+                    .execute(&self.pool)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn record_rejected_request(&self, bin_id: &str, rejected: &RejectedRequest) -> StorageResult<()> {
+        let mut tx = self.pool.begin().await?;
+        query("INSERT INTO rejected_requests (bin_id, source_ip, method, path, timestamp) VALUES (?, ?, ?, ?, ?)")
+            .bind(bin_id)
+            .bind(&rejected.source_ip)
+            .bind(&rejected.method)
+            .bind(&rejected.path)
+            .bind(&rejected.timestamp)
+            .execute(&mut *tx)
+            .await?;
+        query(
+            "DELETE FROM rejected_requests WHERE bin_id = ?1 AND id NOT IN (
+                SELECT id FROM rejected_requests WHERE bin_id = ?1 ORDER BY id DESC LIMIT ?2
+            )"
+        )
+        .bind(bin_id)
+        .bind(MAX_REJECTED_PER_BIN)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn rejected_requests(&self, bin_id: &str) -> StorageResult<Vec<RejectedRequest>> {
+        Ok(sqlx::query_as::<_, RejectedRequest>(
+            "SELECT source_ip, method, path, timestamp FROM rejected_requests WHERE bin_id = ? ORDER BY id DESC"
+        )
+        .bind(bin_id)
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    async fn bin_pause(&self, bin_id: &str) -> StorageResult<Option<BinPause>> {
+        let status = sqlx::query_scalar::<_, Option<u16>>("SELECT paused_status FROM bins WHERE id = ? AND deleted_at IS NULL")
+            .bind(bin_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(status.flatten().map(|status| BinPause { status }))
+    }
+
+    async fn set_bin_pause(&self, bin_id: &str, pause: Option<&BinPause>) -> StorageResult<()> {
+        query("UPDATE bins SET paused_status = ? WHERE id = ?")
+            .bind(pause.map(|pause| pause.status))
+            .bind(bin_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn bin_sampling(&self, bin_id: &str) -> StorageResult<Option<BinSampling>> {
+        let row = sqlx::query_as::<_, (Option<u32>, Option<f64>)>(
+            "SELECT sample_every, sample_percent FROM bins WHERE id = ? AND deleted_at IS NULL"
+        )
+        .bind(bin_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.and_then(|(every, percent)| {
+            (every.is_some() || percent.is_some()).then_some(BinSampling { every, percent })
+        }))
+    }
+
+    async fn set_bin_sampling(&self, bin_id: &str, sampling: Option<&BinSampling>) -> StorageResult<()> {
+        query("UPDATE bins SET sample_every = ?, sample_percent = ?, sample_seen = 0 WHERE id = ?")
+            .bind(sampling.and_then(|sampling| sampling.every))
+            .bind(sampling.and_then(|sampling| sampling.percent))
+            .bind(bin_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn next_sample(&self, bin_id: &str) -> StorageResult<i64> {
+        let seen = sqlx::query_scalar::<_, i64>("UPDATE bins SET sample_seen = sample_seen + 1 WHERE id = ? RETURNING sample_seen")
+            .bind(bin_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(seen.unwrap_or(0))
+    }
+
+    async fn record_sampled_out(&self, bin_id: &str) -> StorageResult<()> {
+        query("UPDATE bins SET sampled_out_count = sampled_out_count + 1 WHERE id = ?")
+            .bind(bin_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn bin_chaos(&self, bin_id: &str) -> StorageResult<Option<BinChaos>> {
+        let stored = sqlx::query_scalar::<_, Option<String>>("SELECT chaos FROM bins WHERE id = ? AND deleted_at IS NULL")
+            .bind(bin_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .flatten();
+
+        Ok(stored.and_then(|json| read_chaos(bin_id, &json)))
+    }
+
+    async fn set_bin_chaos(&self, bin_id: &str, chaos: Option<&BinChaos>) -> StorageResult<()> {
+        let stored = chaos.map(|chaos| serde_json::to_string(chaos).unwrap_or_default());
+        query("UPDATE bins SET chaos = ? WHERE id = ?")
+            .bind(&stored)
+            .bind(bin_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn bin_proxy(&self, bin_id: &str) -> StorageResult<Option<BinProxy>> {
+        let upstream = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT proxy_upstream FROM bins WHERE id = ? AND deleted_at IS NULL"
+        )
+        .bind(bin_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(upstream.flatten().map(|upstream| BinProxy { upstream }))
+    }
+
+    async fn set_bin_proxy(&self, bin_id: &str, proxy: Option<&BinProxy>) -> StorageResult<()> {
+        query("UPDATE bins SET proxy_upstream = ? WHERE id = ?")
+            .bind(proxy.map(|proxy| proxy.upstream.as_str()))
+            .bind(bin_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn bin_script(&self, bin_id: &str) -> StorageResult<Option<BinScript>> {
+        let source = sqlx::query_scalar::<_, Option<String>>("SELECT script FROM bins WHERE id = ? AND deleted_at IS NULL")
+            .bind(bin_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(source.flatten().map(|source| BinScript { source }))
+    }
+
+    async fn set_bin_script(&self, bin_id: &str, script: Option<&BinScript>) -> StorageResult<()> {
+        query("UPDATE bins SET script = ? WHERE id = ?")
+            .bind(script.map(|script| script.source.as_str()))
+            .bind(bin_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn bin_rate_limit(&self, bin_id: &str) -> StorageResult<Option<BinRateLimit>> {
+        let row = sqlx::query_as::<_, (f64, u32)>("SELECT requests_per_second, burst_size FROM bin_rate_limits WHERE bin_id = ?")
+            .bind(bin_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|(requests_per_second, burst_size)| BinRateLimit { requests_per_second, burst_size }))
+    }
+
+    async fn set_bin_rate_limit(&self, bin_id: &str, limit: Option<&BinRateLimit>) -> StorageResult<()> {
+        match limit {
+            Some(limit) => {
+                query(
+                    "INSERT INTO bin_rate_limits (bin_id, requests_per_second, burst_size) VALUES (?, ?, ?) \
+                     ON CONFLICT (bin_id) DO UPDATE SET requests_per_second = excluded.requests_per_second, burst_size = excluded.burst_size"
+                )
+                .bind(bin_id)
+                .bind(limit.requests_per_second)
+                .bind(limit.burst_size)
+                .execute(&self.pool)
+                .await?;
+            }
+            None => {
+                query("DELETE FROM bin_rate_limits WHERE bin_id = ?")
+                    .bind(bin_id)
+                    .execute(&self.pool)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn bin_limits(&self, bin_id: &str) -> StorageResult<Option<BinLimits>> {
+        let row = sqlx::query_as::<_, (Option<i64>, Option<i64>, Option<i64>, Option<i64>)>(
+            "SELECT max_requests, max_body_size, expiry_hours, retention_hours FROM bin_limits WHERE bin_id = ?"
+        )
+        .bind(bin_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|(max_requests, max_body_size, expiry_hours, retention_hours)| BinLimits {
+            max_requests,
+            max_body_size: max_body_size.map(|size| size as usize),
+            expiry_hours,
+            retention_hours,
+        }))
+    }
+
+    async fn set_bin_limits(&self, bin_id: &str, limits: Option<&BinLimits>) -> StorageResult<()> {
+        match limits {
+            Some(limits) => {
+                query(
+                    "INSERT INTO bin_limits (bin_id, max_requests, max_body_size, expiry_hours, retention_hours) \
+                     VALUES (?, ?, ?, ?, ?) \
+                     ON CONFLICT (bin_id) DO UPDATE SET max_requests = excluded.max_requests, \
+                     max_body_size = excluded.max_body_size, expiry_hours = excluded.expiry_hours, \
+                     retention_hours = excluded.retention_hours"
+                )
+                .bind(bin_id)
+                .bind(limits.max_requests)
+                .bind(limits.max_body_size.map(|size| size as i64))
+                .bind(limits.expiry_hours)
+                .bind(limits.retention_hours)
+                .execute(&self.pool)
+                .await?;
+            }
+            None => {
+                query("DELETE FROM bin_limits WHERE bin_id = ?")
+                    .bind(bin_id)
+                    .execute(&self.pool)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn bin_expiry_overrides(&self) -> StorageResult<Vec<(String, i64)>> {
+        Ok(sqlx::query_as::<_, (String, i64)>("SELECT bin_id, expiry_hours FROM bin_limits WHERE expiry_hours IS NOT NULL")
+            .fetch_all(&self.pool)
+            .await?)
+    }
+
+    async fn bin_retention_overrides(&self) -> StorageResult<Vec<(String, i64)>> {
+        Ok(sqlx::query_as::<_, (String, i64)>("SELECT bin_id, retention_hours FROM bin_limits WHERE retention_hours IS NOT NULL")
+            .fetch_all(&self.pool)
+            .await?)
+    }
+
+    async fn bins_with_requests_before(&self, before: &str) -> StorageResult<Vec<String>> {
+        Ok(sqlx::query_scalar::<_, String>(
+            "SELECT DISTINCT requests.bin_id FROM requests JOIN bins ON bins.id = requests.bin_id \
+             WHERE requests.timestamp < ? AND NOT requests.pinned AND bins.deleted_at IS NULL"
+        )
+        .bind(before)
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    async fn bin_notifications(&self, bin_id: &str) -> StorageResult<Option<BinNotifications>> {
+        let row = sqlx::query_as::<_, (String, String, String, Option<String>, bool)>(
+            "SELECT service, webhook_url, methods, path_prefix, failed_signatures_only FROM bin_notifications WHERE bin_id = ?"
+        )
+        .bind(bin_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.and_then(|(service, webhook_url, methods, path_prefix, failed_signatures_only)| {
+            read_notifications(bin_id, &service, webhook_url, &methods, path_prefix, failed_signatures_only)
+        }))
+    }
+
+    async fn set_bin_notifications(&self, bin_id: &str, notifications: Option<&BinNotifications>) -> StorageResult<()> {
+        match notifications {
+            Some(notifications) => {
+                query(
+                    "INSERT INTO bin_notifications (bin_id, service, webhook_url, methods, path_prefix, failed_signatures_only) \
+                     VALUES (?, ?, ?, ?, ?, ?) \
+                     ON CONFLICT (bin_id) DO UPDATE SET service = excluded.service, webhook_url = excluded.webhook_url, \
+                     methods = excluded.methods, path_prefix = excluded.path_prefix, \
+                     failed_signatures_only = excluded.failed_signatures_only"
+                )
+                .bind(bin_id)
+                .bind(notifications.service.as_str())
+                .bind(&notifications.webhook_url)
+                .bind(serde_json::to_string(&notifications.methods).unwrap_or_default())
+                .bind(&notifications.path_prefix)
+                .bind(notifications.failed_signatures_only)
+                .execute(&self.pool)
+                .await?;
+            }
+            None => {
+                query("DELETE FROM bin_notifications WHERE bin_id = ?")
+                    .bind(bin_id)
+                    .execute(&self.pool)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn extractors(&self, bin_id: &str) -> StorageResult<Vec<Extractor>> {
+        let extractors = sqlx::query_scalar::<_, String>("SELECT extractors FROM bin_extractors WHERE bin_id = ?")
+            .bind(bin_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(extractors.map(|extractors| read_extractors(bin_id, &extractors)).unwrap_or_default())
+    }
+
+    async fn set_extractors(&self, bin_id: &str, extractors: &[Extractor]) -> StorageResult<()> {
+        if extractors.is_empty() {
+            query("DELETE FROM bin_extractors WHERE bin_id = ?")
+                .bind(bin_id)
+                .execute(&self.pool)
+                .await?;
+            return Ok(());
+        }
+        query(
+            "INSERT INTO bin_extractors (bin_id, extractors) VALUES (?, ?) \
+             ON CONFLICT (bin_id) DO UPDATE SET extractors = excluded.extractors"
+        )
+        .bind(bin_id)
+        .bind(serde_json::to_string(extractors).unwrap_or_default())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn set_response_sequence(&self, bin_id: &str, sequence: Option<&ResponseSequence>) -> StorageResult<()> {
+        match sequence {
+            Some(sequence) => {
+                query(
+                    "INSERT INTO response_sequences (bin_id, mode, responses, position) VALUES (?, ?, ?, 0) \
+                     ON CONFLICT (bin_id) DO UPDATE SET mode = excluded.mode, responses = excluded.responses, position = 0"
+                )
+                .bind(bin_id)
+                .bind(sequence.mode.as_str())
+                .bind(serde_json::to_string(&sequence.responses).unwrap_or_default())
+                .execute(&self.pool)
+                .await?;
+            }
+            None => {
+                query("DELETE FROM response_sequences WHERE bin_id = ?")
+                    .bind(bin_id)
+                    .execute(&self.pool)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn next_sequence_response(&self, bin_id: &str) -> StorageResult<Option<CustomResponse>> {
+        // Most bins have no sequence; don't take the write lock for them
+        let exists: Option<i64> = sqlx::query_scalar("SELECT 1 FROM response_sequences WHERE bin_id = ?")
+            .bind(bin_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        if exists.is_none() {
+            return Ok(None);
+        }
+
+        // A single statement, so concurrent captures never get the same position
+        let served = retry_busy(|| {
+            sqlx::query_as::<_, (i64, String)>(
+                "UPDATE response_sequences SET position = position + 1 \
+                 WHERE bin_id = ? AND (mode = 'rotate' OR position < json_array_length(responses)) \
+                 RETURNING position - 1, responses"
+            )
+            .bind(bin_id)
+            .fetch_optional(&self.pool)
+        })
+        .await?;
+
+        Ok(served.and_then(|(position, responses)| {
+            let responses: Vec<CustomResponse> = match serde_json::from_str(&responses) {
+                Ok(responses) => responses,
+                Err(err) => {
+                    warn!(%bin_id, %err, "Ignoring unreadable response sequence");
+                    return None;
+                }
+            };
+            let index = (position as usize).checked_rem(responses.len())?;
+            responses.into_iter().nth(index)
+        }))
+    }
+
+    async fn response_rules(&self, bin_id: &str) -> StorageResult<Vec<ResponseRule>> {
+        let rows = sqlx::query_as::<_, StoredRule>(
+            "SELECT id, method, path, response FROM response_rules WHERE bin_id = ? ORDER BY position"
+        )
+        .bind(bin_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().filter_map(|row| row.into_rule(bin_id)).collect())
+    }
+
+    async fn set_response_rules(&self, bin_id: &str, rules: &[ResponseRule]) -> StorageResult<()> {
+        let mut tx = self.pool.begin().await?;
+        query("DELETE FROM response_rules WHERE bin_id = ?")
+            .bind(bin_id)
+            .execute(&mut *tx)
+            .await?;
+        for (position, rule) in rules.iter().enumerate() {
+            insert_rule(bin_id, rule, position as i64).execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn add_response_rule(&self, bin_id: &str, rule: &ResponseRule) -> StorageResult<()> {
+        let mut tx = self.pool.begin().await?;
+        let position: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(position) + 1, 0) FROM response_rules WHERE bin_id = ?")
+            .bind(bin_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        insert_rule(bin_id, rule, position).execute(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn update_response_rule(&self, bin_id: &str, rule: &ResponseRule) -> StorageResult<bool> {
+        let result = query("UPDATE response_rules SET method = ?, path = ?, response = ? WHERE bin_id = ? AND id = ?")
+            .bind(&rule.method)
+            .bind(&rule.path)
+            .bind(serde_json::to_string(&rule.response).unwrap_or_default())
+            .bind(bin_id)
+            .bind(&rule.rule_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete_response_rule(&self, bin_id: &str, rule_id: &str) -> StorageResult<bool> {
+        let result = query("DELETE FROM response_rules WHERE bin_id = ? AND id = ?")
+            .bind(bin_id)
+            .bind(rule_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn touch_bin(&self, bin_id: &str, last_updated: &str) -> StorageResult<()> {
+        retry_busy(|| {
+            query("UPDATE bins SET last_updated = ? WHERE id = ?")
+                .bind(last_updated)
+                .bind(bin_id)
+                .execute(&self.pool)
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_bin(&self, bin_id: &str) -> StorageResult<bool> {
+        let refs = self.body_refs("SELECT body_ref FROM requests WHERE bin_id = ? AND body_ref IS NOT NULL", bin_id).await?;
+        let result = query("DELETE FROM bins WHERE id = ?")
+            .bind(bin_id)
+            .execute(&self.pool)
+            .await?;
+        self.remove_blobs(refs).await;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn soft_delete_bin(&self, bin_id: &str, deleted_at: &str) -> StorageResult<bool> {
+        let result = query("UPDATE bins SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL")
+            .bind(deleted_at)
+            .bind(bin_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn restore_bin(&self, bin_id: &str, last_updated: &str) -> StorageResult<bool> {
+        let result = query("UPDATE bins SET deleted_at = NULL, last_updated = ? WHERE id = ? AND deleted_at IS NOT NULL")
+            .bind(last_updated)
+            .bind(bin_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn deleted_bins(&self, before: &str) -> StorageResult<Vec<String>> {
+        Ok(sqlx::query_scalar::<_, String>("SELECT id FROM bins WHERE deleted_at < ?")
+            .bind(before)
+            .fetch_all(&self.pool)
+            .await?)
+    }
+
+    async fn inactive_bins(&self, before: &str, since: Option<&str>) -> StorageResult<Vec<(String, String)>> {
+        Ok(sqlx::query_as::<_, (String, String)>(
+            "SELECT id, last_updated FROM bins WHERE last_updated < ?1 AND (?2 IS NULL OR last_updated >= ?2)
+             AND deleted_at IS NULL AND NOT EXISTS (SELECT 1 FROM requests WHERE requests.bin_id = bins.id AND pinned)"
+        )
+        .bind(before)
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    async fn list_bins(&self, limit: i64, offset: i64) -> StorageResult<(Vec<AdminBinSummary>, i64)> {
+        let total = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM bins WHERE deleted_at IS NULL")
+            .fetch_one(&self.pool)
+            .await?;
+        let rows = sqlx::query_as::<_, AdminBinSummary>(
+            r#"
+            SELECT bins.id AS bin_id,
+                   bins.name,
+                   bins.token IS NOT NULL AS private,
+                   bins.last_updated,
+                   bins.request_count
+            FROM bins
+            WHERE bins.deleted_at IS NULL
+            ORDER BY bins.last_updated DESC, bins.id
+            LIMIT ? OFFSET ?
+            "#
+        )
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok((rows, total))
+    }
+
+    async fn creator_bins(&self, creator_token: &str) -> StorageResult<Vec<CreatorBin>> {
+        let rows = sqlx::query_as::<_, (String, Option<String>, bool, Option<String>, i64, Option<i64>, bool)>(
+            r#"
+            SELECT bins.id,
+                   bins.name,
+                   bins.token IS NOT NULL,
+                   bins.last_updated,
+                   bins.request_count,
+                   bin_limits.expiry_hours,
+                   EXISTS (SELECT 1 FROM requests WHERE requests.bin_id = bins.id AND pinned)
+            FROM bins
+            LEFT JOIN bin_limits ON bin_limits.bin_id = bins.id
+            WHERE bins.creator_token = ? AND bins.deleted_at IS NULL
+            ORDER BY bins.last_updated DESC, bins.id
+            "#
+        )
+        .bind(creator_token)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(bin_id, name, private, last_updated, request_count, expiry_hours, pinned)| CreatorBin {
+                summary: AdminBinSummary { bin_id, name, private, last_updated, request_count },
+                expiry_hours,
+                pinned,
+            })
+            .collect())
+    }
+
+    async fn count_creator_bins(&self, creator_ip: &str, creator_token: Option<&str>) -> StorageResult<(i64, i64)> {
+        Ok(sqlx::query_as::<_, (i64, i64)>(
+            "SELECT (SELECT COUNT(*) FROM bins WHERE creator_ip = ?1 AND deleted_at IS NULL),
+                    (SELECT COUNT(*) FROM bins WHERE creator_token = ?2 AND deleted_at IS NULL)"
+        )
+        .bind(creator_ip)
+        .bind(creator_token)
+        .fetch_one(&self.pool)
+        .await?)
+    }
+
+    async fn insert_requests(&self, bin_id: &str, requests: &[LoggedRequest], max_requests: i64, last_updated: &str) -> StorageResult<InsertOutcome> {
+        let mut body_refs = Vec::with_capacity(requests.len());
+        for request in requests {
+            match self.offload_body(request).await {
+                Ok(body_ref) => body_refs.push(body_ref),
+                Err(err) => {
+                    self.remove_blobs(body_refs.into_iter().flatten().collect()).await;
+                    return Err(err);
+                }
+            }
+        }
+
+        // Offloaded bodies aren't in the row, so there is nothing left to compress
+        let compressed: Vec<Option<Vec<u8>>> = requests
+            .iter()
+            .zip(&body_refs)
+            .map(|(request, body_ref)| match (body_ref, &request.body) {
+                (None, Some(body)) => self.compress_body(body),
+                _ => None,
+            })
+            .collect();
+
+        let stored = retry_busy(|| async {
+            let _capturing = self.capture_lock.lock().await;
+            let mut tx = self.pool.begin().await?;
+            for ((request, body_ref), compressed) in requests.iter().zip(&body_refs).zip(&compressed) {
+                let insert = query(
+                    "INSERT INTO requests (bin_id, request_id, method, headers, body, timestamp, body_format, content_type_mismatch, query_string, query_params, source_ip, source_port, path, body_encoding, content_encoding, body_ref, body_compression, signature_valid, chaos) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+                )
+                .bind(bin_id)
+                .bind(request.request_id)
+                .bind(&request.method)
+                .bind(&request.headers);
+                let compression = compressed.is_some().then_some(ZSTD);
+                let insert = match compressed {
+                    Some(bytes) => insert.bind(bytes.as_slice()),
+                    None => insert.bind(if body_ref.is_some() { None } else { request.body.as_deref() }),
+                };
+                let row_id = insert
+                .bind(&request.timestamp)
+                .bind(&request.body_format)
+                .bind(request.content_type_mismatch)
+                .bind(&request.query_string)
+                .bind(&request.query_params)
+                .bind(&request.source_ip)
+                .bind(request.source_port)
+                .bind(&request.path)
+                .bind(&request.body_encoding)
+                .bind(&request.content_encoding)
+                .bind(body_ref)
+                .bind(compression)
+                .bind(request.signature_valid)
+                .bind(&request.chaos)
+                .execute(&mut *tx)
+                .await?
+                .last_insert_rowid();
+                // Indexed as captured, whether the row keeps it compressed or offloaded
+                if let Some(body) = &request.body {
+                    query("INSERT INTO requests_fts (rowid, body) VALUES (?, ?)")
+                        .bind(row_id)
+                        .bind(body)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+                for (name, value) in &request.fields {
+                    query("INSERT INTO request_fields (request_id, name, value) VALUES (?, ?, ?)")
+                        .bind(request.request_id)
+                        .bind(name)
+                        .bind(value)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+            }
+
+            // The triggers in 0005_bin_request_count.sql already counted the new rows
+            let request_count = sqlx::query_scalar::<_, i64>(
+                "UPDATE bins SET last_updated = ? WHERE id = ? RETURNING request_count"
+            )
+            .bind(last_updated)
+            .bind(bin_id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+            let excess = request_count - max_requests;
+            let (trimmed, trimmed_refs) = if excess > 0 {
+                delete_oldest(&mut tx, bin_id, None, excess, self.blobs.is_some()).await?
+            } else {
+                (0, Vec::new())
+            };
+
+            tx.commit().await?;
+            Ok((InsertOutcome { request_count, trimmed }, trimmed_refs))
+        })
+        .await;
+
+        match stored {
+            Ok((outcome, trimmed_refs)) => {
+                self.remove_blobs(trimmed_refs).await;
+                Ok(outcome)
+            }
+            Err(err) => {
+                self.remove_blobs(body_refs.into_iter().flatten().collect()).await;
+                Err(err.into())
+            }
+        }
+    }
+
+    async fn list_requests(&self, bin_id: &str, filter: &RequestFilter, page: PageRequest) -> StorageResult<(Vec<LoggedRequest>, i64)> {
+        // Resolve the cursor to the internal row id so pages stay stable while new requests arrive
+        let after_row_id = match page.after {
+            Some(after) => sqlx::query_scalar::<_, i64>("SELECT id FROM requests WHERE bin_id = ? AND request_id = ?")
+                .bind(bin_id)
+                .bind(after)
+                .fetch_optional(&self.pool)
+                .await?
+                .ok_or(super::StorageError::UnknownCursor)?,
+            None => 0,
+        };
+
+        let total_sql = format!("SELECT COUNT(*) FROM requests WHERE {}", INSPECT_FILTER);
+        let total = sqlx::query_scalar::<_, i64>(&total_sql)
+            .bind(bin_id)
+            .bind(&filter.method)
+            .bind(&filter.since)
+            .bind(&filter.until)
+            .bind(&filter.header_name)
+            .bind(&filter.header_value)
+            .bind(&filter.field_name)
+            .bind(&filter.field_value)
+            .fetch_one(&self.pool)
+            .await?;
+
+        let sql = format!(
+            "SELECT {} FROM requests WHERE {} AND id > ?9 ORDER BY id LIMIT ?10 OFFSET ?11",
+            REQUEST_COLUMNS, INSPECT_FILTER
+        );
+        let rows = sqlx::query_as::<_, StoredRequest>(&sql)
+            .bind(bin_id)
+            .bind(&filter.method)
+            .bind(&filter.since)
+            .bind(&filter.until)
+            .bind(&filter.header_name)
+            .bind(&filter.header_value)
+            .bind(&filter.field_name)
+            .bind(&filter.field_value)
+            .bind(after_row_id)
+            .bind(page.limit)
+            .bind(page.offset)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok((self.restore_all(rows).await?, total))
+    }
+
+    async fn recent_requests(&self, bin_id: &str, limit: i64) -> StorageResult<Vec<LoggedRequest>> {
+        let sql = format!(
+            "SELECT * FROM (SELECT id, {} FROM requests WHERE bin_id = ? ORDER BY id DESC LIMIT ?) ORDER BY id",
+            REQUEST_COLUMNS
+        );
+        let rows = sqlx::query_as::<_, StoredRequest>(&sql)
+            .bind(bin_id)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+        self.restore_all(rows).await
+    }
+
+    async fn get_request(&self, bin_id: &str, request_id: Uuid) -> StorageResult<Option<LoggedRequest>> {
+        let sql = format!("SELECT {} FROM requests WHERE bin_id = ? AND request_id = ?", REQUEST_COLUMNS);
+        let row = sqlx::query_as::<_, StoredRequest>(&sql)
+            .bind(bin_id)
+            .bind(request_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        match row {
+            Some(row) => Ok(self.restore_all(vec![row]).await?.pop()),
+            None => Ok(None),
+        }
+    }
+
+    fn stream_requests(&self, bin_id: String) -> BoxStream<'static, StorageResult<LoggedRequest>> {
+        // The row stream borrows the pool, so it is read on its own task and handed
+        // over through a bounded channel
+        let (tx, rx) = tokio::sync::mpsc::channel(EXPORT_STREAM_BUFFER);
+        let pool = self.pool.clone();
+        let blobs = self.blobs.clone();
+
+        tokio::spawn(async move {
+            let sql = format!("SELECT {} FROM requests WHERE bin_id = ? ORDER BY id", REQUEST_COLUMNS);
+            let mut rows = sqlx::query_as::<_, StoredRequest>(&sql).bind(&bin_id).fetch(&pool);
+            while let Some(row) = rows.next().await {
+                let row = match row {
+                    Ok(row) => Ok(restore_body(blobs.as_ref(), row).await),
+                    Err(err) => Err(err.into()),
+                };
+                let failed = row.is_err();
+                // Stop when the reader goes away or the query fails
+                if tx.send(row).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        futures::stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|row| (row, rx))
+        })
+        .boxed()
+    }
+
+    async fn search_bin(&self, bin_id: &str, query: &str, limit: i64) -> StorageResult<Vec<LoggedRequest>> {
+        let sql = format!(
+            r#"
+            SELECT {}
+            FROM requests
+            WHERE bin_id = ?
+              AND id IN (SELECT rowid FROM requests_fts WHERE requests_fts MATCH ?)
+            ORDER BY id DESC
+            LIMIT ?
+            "#,
+            REQUEST_COLUMNS
+        );
+        let rows = sqlx::query_as::<_, StoredRequest>(&sql)
+            .bind(bin_id)
+            .bind(fts_match_query(query))
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+        self.restore_all(rows).await
+    }
+
+    async fn search_all(&self, text: &str, since: &str, until: &str, limit: i64) -> StorageResult<Vec<AdminSearchResult>> {
+        let sql = format!(
+            r#"
+            SELECT bin_id, {}
+            FROM requests
+            WHERE (CASE WHEN body_compression IS NULL AND body_ref IS NULL THEN body LIKE ?1 ESCAPE '\'
+                        -- Bodies that aren't text in the row are only in the full-text index
+                        ELSE id IN (SELECT rowid FROM requests_fts WHERE requests_fts MATCH ?5) END
+                   OR headers LIKE ?1 ESCAPE '\')
+              AND bin_id IN (SELECT id FROM bins WHERE deleted_at IS NULL)
+              AND timestamp >= ?2
+              AND timestamp <= ?3
+            ORDER BY id DESC
+            LIMIT ?4
+            "#,
+            REQUEST_COLUMNS
+        );
+        let rows = sqlx::query_as::<_, StoredSearchResult>(&sql)
+            .bind(format!("%{}%", escape_like(text)))
+            .bind(since)
+            .bind(until)
+            .bind(limit)
+            .bind(fts_match_query(text))
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(join_all(rows.into_iter().map(|row| async {
+            AdminSearchResult {
+                bin_id: row.bin_id,
+                request: restore_body(self.blobs.as_ref(), row.stored).await,
+            }
+        }))
+        .await)
+    }
+
+    async fn set_request_pinned(&self, bin_id: &str, request_id: Uuid, pinned: bool) -> StorageResult<bool> {
+        let result = query("UPDATE requests SET pinned = ? WHERE bin_id = ? AND request_id = ?")
+            .bind(pinned)
+            .bind(bin_id)
+            .bind(request_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete_request(&self, request_id: Uuid) -> StorageResult<Option<String>> {
+        let refs = self.body_refs("SELECT body_ref FROM requests WHERE request_id = ? AND body_ref IS NOT NULL", request_id).await?;
+        let bin_id = sqlx::query_scalar::<_, String>("DELETE FROM requests WHERE request_id = ? RETURNING bin_id")
+            .bind(request_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        self.remove_blobs(refs).await;
+        Ok(bin_id)
+    }
+
+    async fn clear_bin(&self, bin_id: &str) -> StorageResult<u64> {
+        let refs = self.body_refs("SELECT body_ref FROM requests WHERE bin_id = ? AND body_ref IS NOT NULL", bin_id).await?;
+        let result = query("DELETE FROM requests WHERE bin_id = ?")
+            .bind(bin_id)
+            .execute(&self.pool)
+            .await?;
+        self.remove_blobs(refs).await;
+        Ok(result.rows_affected())
+    }
+
+    async fn prune_bin(&self, bin_id: &str, before: Option<&str>, keep_last: Option<i64>) -> StorageResult<u64> {
+        let mut remaining = match keep_last {
+            Some(keep) => {
+                let count = sqlx::query_scalar::<_, i64>("SELECT request_count FROM bins WHERE id = ?")
+                    .bind(bin_id)
+                    .fetch_optional(&self.pool)
+                    .await?
+                    .unwrap_or_default();
+                count - keep
+            }
+            None => i64::MAX,
+        };
+
+        let mut pruned = 0;
+        while remaining > 0 {
+            let batch = remaining.min(PRUNE_BATCH);
+            let (deleted, refs) = retry_busy(|| async {
+                let _capturing = self.capture_lock.lock().await;
+                let mut conn = self.pool.acquire().await?;
+                delete_oldest(&mut conn, bin_id, before, batch, self.blobs.is_some()).await
+            })
+            .await?;
+            self.remove_blobs(refs).await;
+            pruned += deleted;
+            remaining -= deleted as i64;
+            if (deleted as i64) < batch {
+                break;
+            }
+        }
+        Ok(pruned)
+    }
+
+    async fn evict_oldest_requests(&self, count: i64) -> StorageResult<Vec<(String, Uuid)>> {
+        let refs = self
+            .body_refs(
+                "SELECT body_ref FROM (SELECT body_ref FROM requests WHERE NOT pinned ORDER BY id ASC LIMIT ?) WHERE body_ref IS NOT NULL",
+                count,
+            )
+            .await?;
+        let evicted = sqlx::query_as::<_, (String, Uuid)>(
+            r#"
+            DELETE FROM requests WHERE id IN (
+                SELECT id FROM requests WHERE NOT pinned ORDER BY id ASC LIMIT ?
+            )
+            RETURNING bin_id, request_id
+            "#
+        )
+        .bind(count)
+        .fetch_all(&self.pool)
+        .await?;
+        self.remove_blobs(refs).await;
+        Ok(evicted)
+    }
+
+    async fn ping(&self) -> StorageResult<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    fn pool_status(&self) -> Option<PoolStatus> {
+        Some(PoolStatus {
+            size: self.pool.size(),
+            idle: self.pool.num_idle() as u32,
+            max_connections: self.pool.options().get_max_connections(),
+        })
+    }
+
+    async fn stats(&self, since: &str) -> StorageResult<StorageStats> {
+        let (bin_count, private_bin_count, request_count, recent_request_count, sampled_out_count, size_bytes) =
+            sqlx::query_as::<_, (i64, i64, i64, i64, i64, i64)>(
+                r#"
+                SELECT (SELECT COUNT(*) FROM bins WHERE deleted_at IS NULL),
+                       (SELECT COUNT(*) FROM bins WHERE token IS NOT NULL AND deleted_at IS NULL),
+                       (SELECT COUNT(*) FROM requests JOIN bins ON bins.id = requests.bin_id WHERE bins.deleted_at IS NULL),
+                       (SELECT COUNT(*) FROM requests JOIN bins ON bins.id = requests.bin_id
+                        WHERE bins.deleted_at IS NULL AND requests.timestamp >= ?),
+                       (SELECT COALESCE(SUM(sampled_out_count), 0) FROM bins WHERE deleted_at IS NULL),
+                       (SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size())
+                "#
+            )
+            .bind(since)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(StorageStats {
+            bin_count,
+            private_bin_count,
+            request_count,
+            recent_request_count,
+            sampled_out_count,
+            size_bytes,
+        })
+    }
+
+    async fn storage_report(&self, top: i64) -> StorageResult<StorageReport> {
+        let (page_size, page_count, free_page_count) = sqlx::query_as::<_, (i64, i64, i64)>(
+            "SELECT page_size, page_count, freelist_count FROM pragma_page_size(), pragma_page_count(), pragma_freelist_count()"
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        let bins = sqlx::query_as::<_, (String, i64, i64, i64)>(
+            r#"
+            SELECT bin_id,
+                   COUNT(*),
+                   SUM(LENGTH(CAST(headers AS BLOB)) + COALESCE(LENGTH(CAST(body AS BLOB)), 0)
+                       + COALESCE(LENGTH(CAST(query_params AS BLOB)), 0)) AS bytes,
+                   COUNT(body_ref)
+            FROM requests
+            GROUP BY bin_id
+            ORDER BY bytes DESC
+            LIMIT ?
+            "#
+        )
+        .bind(top)
+        .fetch_all(&self.pool)
+        .await?;
+        let blob_count = sqlx::query_scalar::<_, i64>("SELECT COUNT(body_ref) FROM requests")
+            .fetch_one(&self.pool)
+            .await?;
+        let blob_bytes = match &self.blobs {
+            Some(blobs) => blobs.store.size().await?,
+            None => None,
+        };
+
+        Ok(StorageReport {
+            size_bytes: page_size * page_count,
+            page_size,
+            page_count,
+            free_page_count,
+            bins: bins
+                .into_iter()
+                .map(|(bin_id, request_count, bytes, blob_count)| BinStorageUsage { bin_id, request_count, bytes, blob_count })
+                .collect(),
+            blobs: BlobStorageUsage { enabled: self.blobs.is_some(), count: blob_count, bytes: blob_bytes },
+        })
+    }
+
+    async fn storage_size(&self) -> StorageResult<i64> {
+        // Free pages are reused by later writes, so they don't count
+        let size = sqlx::query_scalar::<_, i64>(
+            "SELECT (page_count - freelist_count) * page_size FROM pragma_page_count(), pragma_freelist_count(), pragma_page_size()"
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(size)
+    }
+
+    async fn run_maintenance(&self) -> StorageResult<MaintenanceOutcome> {
+        // Pragmas and VACUUM act on the connection they run on, so keep to one
+        let mut conn = self.pool.acquire().await?;
+        let page_count = "SELECT page_count FROM pragma_page_count()";
+        let before = sqlx::query_scalar::<_, i64>(page_count).fetch_one(&mut *conn).await?;
+        let page_size = sqlx::query_scalar::<_, i64>("SELECT page_size FROM pragma_page_size()").fetch_one(&mut *conn).await?;
+
+        // Databases created before `auto_vacuum` was set keep "none" until rebuilt
+        let auto_vacuum = sqlx::query_scalar::<_, i64>("SELECT auto_vacuum FROM pragma_auto_vacuum()").fetch_one(&mut *conn).await?;
+        let rebuilt = auto_vacuum != AUTO_VACUUM_INCREMENTAL;
+        if rebuilt {
+            query("PRAGMA auto_vacuum = INCREMENTAL").execute(&mut *conn).await?;
+            query("VACUUM").execute(&mut *conn).await?;
+        } else {
+            query("PRAGMA incremental_vacuum").execute(&mut *conn).await?;
+        }
+        query("ANALYZE").execute(&mut *conn).await?;
+
+        let after = sqlx::query_scalar::<_, i64>(page_count).fetch_one(&mut *conn).await?;
+        Ok(MaintenanceOutcome { reclaimed_pages: before - after, page_size, rebuilt })
+    }
+
+    async fn create_forward(&self, bin_id: &str, forward: &Forward) -> StorageResult<()> {
+        query("INSERT INTO forwards (id, bin_id, url, enabled, created_at) VALUES (?, ?, ?, ?, ?)")
+            .bind(&forward.forward_id)
+            .bind(bin_id)
+            .bind(&forward.url)
+            .bind(forward.enabled)
+            .bind(&forward.created_at)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn list_forwards(&self, bin_id: &str) -> StorageResult<Vec<Forward>> {
+        let sql = format!("{} WHERE f.bin_id = ? ORDER BY f.created_at, f.id", FORWARD_QUERY);
+        let rows = sqlx::query_as::<_, StoredForward>(&sql)
+            .bind(bin_id)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.into_iter().map(StoredForward::into_forward).collect())
+    }
+
+    async fn get_forward(&self, bin_id: &str, forward_id: &str) -> StorageResult<Option<Forward>> {
+        let sql = format!("{} WHERE f.bin_id = ? AND f.id = ?", FORWARD_QUERY);
+        let row = sqlx::query_as::<_, StoredForward>(&sql)
+            .bind(bin_id)
+            .bind(forward_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.map(StoredForward::into_forward))
+    }
+
+    async fn update_forward(&self, bin_id: &str, forward_id: &str, update: &ForwardUpdate) -> StorageResult<bool> {
+        let result = query(
+            "UPDATE forwards SET url = COALESCE(?1, url), enabled = COALESCE(?2, enabled) WHERE bin_id = ?3 AND id = ?4"
+        )
+        .bind(&update.url)
+        .bind(update.enabled)
+        .bind(bin_id)
+        .bind(forward_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete_forward(&self, bin_id: &str, forward_id: &str) -> StorageResult<bool> {
+        let result = query("DELETE FROM forwards WHERE bin_id = ? AND id = ?")
+            .bind(bin_id)
+            .bind(forward_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn record_delivery(&self, forward_id: &str, delivery: &ForwardDelivery) -> StorageResult<i64> {
+        let mut tx = self.pool.begin().await?;
+        let delivery_id: i64 = sqlx::query_scalar(
+            "INSERT INTO forward_deliveries \
+             (forward_id, request_id, status, status_code, error, attempts, attempted_at, next_attempt_at, \
+              latency_ms, response_headers, response_body, response_body_encoding) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) RETURNING id"
+        )
+        .bind(forward_id)
+        .bind(delivery.request_id)
+        .bind(&delivery.status)
+        .bind(delivery.status_code)
+        .bind(&delivery.error)
+        .bind(delivery.attempts)
+        .bind(&delivery.attempted_at)
+        .bind(&delivery.next_attempt_at)
+        .bind(delivery.latency_ms.map(|ms| ms as i64))
+        .bind(delivery.response.as_ref().map(|response| serde_json::to_string(&response.headers).unwrap_or_default()))
+        .bind(delivery.response.as_ref().map(|response| &response.body))
+        .bind(delivery.response.as_ref().map(|response| &response.body_encoding))
+        .fetch_one(&mut *tx)
+        .await?;
+        query(
+            "DELETE FROM forward_deliveries WHERE forward_id = ?1 AND id NOT IN (
+                SELECT id FROM forward_deliveries WHERE forward_id = ?1 ORDER BY id DESC LIMIT ?2
+            )"
+        )
+        .bind(forward_id)
+        .bind(MAX_DELIVERIES_PER_FORWARD)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(delivery_id)
+    }
+
+    async fn update_delivery(&self, delivery: &ForwardDelivery) -> StorageResult<()> {
+        query(
+            "UPDATE forward_deliveries SET status = ?, status_code = ?, error = ?, attempts = ?, attempted_at = ?, next_attempt_at = ?, \
+             latency_ms = ?, response_headers = ?, response_body = ?, response_body_encoding = ? \
+             WHERE id = ?"
+        )
+        .bind(&delivery.status)
+        .bind(delivery.status_code)
+        .bind(&delivery.error)
+        .bind(delivery.attempts)
+        .bind(&delivery.attempted_at)
+        .bind(&delivery.next_attempt_at)
+        .bind(delivery.latency_ms.map(|ms| ms as i64))
+        .bind(delivery.response.as_ref().map(|response| serde_json::to_string(&response.headers).unwrap_or_default()))
+        .bind(delivery.response.as_ref().map(|response| &response.body))
+        .bind(delivery.response.as_ref().map(|response| &response.body_encoding))
+        .bind(delivery.delivery_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn list_deliveries(&self, forward_id: &str) -> StorageResult<Vec<ForwardDelivery>> {
+        let sql = format!("SELECT {} FROM forward_deliveries d WHERE d.forward_id = ? ORDER BY d.id DESC", DELIVERY_COLUMNS);
+        let rows = sqlx::query_as::<_, StoredDelivery>(&sql)
+            .bind(forward_id)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.into_iter().map(ForwardDelivery::from).collect())
+    }
+
+    async fn due_deliveries(&self, now: &str, limit: i64) -> StorageResult<Vec<DueDelivery>> {
+        let sql = format!(
+            "SELECT f.bin_id, f.id AS forward_id, f.url, {} \
+             FROM forward_deliveries d JOIN forwards f ON f.id = d.forward_id \
+             WHERE d.next_attempt_at IS NOT NULL AND d.next_attempt_at <= ? AND f.enabled = 1 \
+             ORDER BY d.next_attempt_at LIMIT ?",
+            DELIVERY_COLUMNS
+        );
+        let rows = sqlx::query_as::<_, StoredDueDelivery>(&sql)
+            .bind(now)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| DueDelivery { bin_id: row.bin_id, forward_id: row.forward_id, url: row.url, delivery: row.delivery.into() })
+            .collect())
+    }
+
+    async fn record_replay(&self, bin_id: &str, kind: &str, url: &str, delivery: &ForwardDelivery) -> StorageResult<i64> {
+        let mut tx = self.pool.begin().await?;
+        let delivery_id: i64 = sqlx::query_scalar(
+            "INSERT INTO replay_deliveries \
+             (bin_id, kind, request_id, url, status, status_code, error, attempted_at, \
+              latency_ms, response_headers, response_body, response_body_encoding) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) RETURNING id"
+        )
+        .bind(bin_id)
+        .bind(kind)
+        .bind(delivery.request_id)
+        .bind(url)
+        .bind(&delivery.status)
+        .bind(delivery.status_code)
+        .bind(&delivery.error)
+        .bind(&delivery.attempted_at)
+        .bind(delivery.latency_ms.map(|ms| ms as i64))
+        .bind(delivery.response.as_ref().map(|response| serde_json::to_string(&response.headers).unwrap_or_default()))
+        .bind(delivery.response.as_ref().map(|response| &response.body))
+        .bind(delivery.response.as_ref().map(|response| &response.body_encoding))
+        .fetch_one(&mut *tx)
+        .await?;
+        query(
+            "DELETE FROM replay_deliveries WHERE bin_id = ?1 AND id NOT IN (
+                SELECT id FROM replay_deliveries WHERE bin_id = ?1 ORDER BY id DESC LIMIT ?2
+            )"
+        )
+        .bind(bin_id)
+        .bind(MAX_REPLAYS_PER_BIN)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(delivery_id)
+    }
+
+    async fn request_deliveries(&self, bin_id: &str, request_id: Uuid) -> StorageResult<Vec<RequestDelivery>> {
+        let forwarded = format!(
+            "SELECT '{}' AS kind, f.id AS forward_id, f.url, {} \
+             FROM forward_deliveries d JOIN forwards f ON f.id = d.forward_id \
+             WHERE f.bin_id = ? AND d.request_id = ? \
+             ORDER BY f.created_at, d.id",
+            KIND_FORWARD, DELIVERY_COLUMNS
+        );
+        let replayed = format!(
+            "SELECT d.kind, NULL AS forward_id, d.url, {} FROM replay_deliveries d \
+             WHERE d.bin_id = ? AND d.request_id = ? ORDER BY d.id",
+            REPLAY_COLUMNS
+        );
+        let mut deliveries = Vec::new();
+        for sql in [forwarded, replayed] {
+            let rows = sqlx::query_as::<_, StoredRequestDelivery>(&sql)
+                .bind(bin_id)
+                .bind(request_id)
+                .fetch_all(&self.pool)
+                .await?;
+            deliveries.extend(rows.into_iter().map(|row| RequestDelivery {
+                kind: row.kind,
+                forward_id: row.forward_id,
+                url: row.url,
+                delivery: row.delivery.into(),
+            }));
+        }
+        Ok(deliveries)
+    }
+
+    async fn create_callback(&self, bin_id: &str, callback: &Callback) -> StorageResult<()> {
+        query("INSERT INTO callbacks (id, bin_id, url, events, secret, created_at) VALUES (?, ?, ?, ?, ?, ?)")
+            .bind(&callback.callback_id)
+            .bind(bin_id)
+            .bind(&callback.url)
+            .bind(serde_json::to_string(&callback.events).unwrap_or_default())
+            .bind(&callback.secret)
+            .bind(&callback.created_at)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn list_callbacks(&self, bin_id: &str) -> StorageResult<Vec<Callback>> {
+        let rows = sqlx::query_as::<_, (String, String, String, String, String)>(
+            "SELECT id, url, events, secret, created_at FROM callbacks WHERE bin_id = ? ORDER BY created_at, id"
+        )
+        .bind(bin_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().filter_map(|row| read_callback(bin_id, row)).collect())
+    }
+
+    async fn delete_callback(&self, bin_id: &str, callback_id: &str) -> StorageResult<bool> {
+        let result = query("DELETE FROM callbacks WHERE bin_id = ? AND id = ?")
+            .bind(bin_id)
+            .bind(callback_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    fn request(body: &str) -> LoggedRequest {
+        serde_json::from_value(serde_json::json!({
+            "method": "POST",
+            "headers": "[]",
+            "body": body,
+            "timestamp": "2024-01-01T00:00:00+00:00",
+            "request_id": Uuid::new_v4(),
+            "content_type_mismatch": false,
+        }))
+        .unwrap()
+    }
+
+    async fn storage_with_bin(pool: SqlitePool, id: &str) -> SqliteStorage {
+        MIGRATOR.run(&pool).await.unwrap();
+        let storage = SqliteStorage::new(pool);
+        storage
+            .create_bin(NewBin { id: id.to_string(), last_updated: "2024-01-01T00:00:00+00:00".to_string(), ..Default::default() })
+            .await
+            .unwrap();
+        storage
+    }
+
+    #[tokio::test]
+    async fn concurrent_captures_store_trim_and_touch_together() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite://{}?mode=rwc", dir.path().join("rustbin.db").display());
+        let pool = SqlitePoolOptions::new().max_connections(5).connect(&url).await.unwrap();
+        let storage = Arc::new(storage_with_bin(pool, "bin").await);
+
+        let captures = (0..20).map(|i| {
+            let storage = storage.clone();
+            tokio::spawn(async move {
+                let last_updated = format!("2024-01-02T00:00:{:02}+00:00", i);
+                storage.insert_requests("bin", &[request(&i.to_string())], 5, &last_updated).await.unwrap()
+            })
+        });
+        let outcomes: Vec<InsertOutcome> = join_all(captures).await.into_iter().map(Result::unwrap).collect();
+        assert_eq!(outcomes.iter().map(|outcome| outcome.trimmed).sum::<u64>(), 15);
+
+        // Every capture saw the others' rows, so the bin never went past the limit plus one
+        assert!(outcomes.iter().all(|outcome| outcome.request_count <= 6));
+        assert_eq!(storage.bin_request_count("bin").await.unwrap(), Some(5));
+        let info = storage.bin_info("bin").await.unwrap().unwrap();
+        assert!(info.last_updated.unwrap().starts_with("2024-01-02"));
+    }
+
+    #[tokio::test]
+    async fn bin_settings_read_what_the_single_getters_do() {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await.unwrap();
+        let storage = storage_with_bin(pool, "bin").await;
+        let empty = storage.bin_settings("bin").await.unwrap().unwrap();
+        assert!(empty.pause.is_none() && empty.limits.is_none() && empty.notifications.is_none());
+        assert!(empty.rules.is_empty() && empty.forwards.is_empty() && empty.callbacks.is_empty() && !empty.has_sequence);
+        assert!(storage.bin_settings("missing").await.unwrap().is_none());
+
+        let response = |status| CustomResponse { status, headers: BTreeMap::new(), body: "ok".to_string() };
+        storage.set_bin_pause("bin", Some(&BinPause { status: 503 })).await.unwrap();
+        storage.set_ip_access_rules("bin", Some(&IpAccessRules { allow: vec!["10.0.0.0/8".to_string()], deny: Vec::new(), log_rejected: true })).await.unwrap();
+        storage.set_bin_rate_limit("bin", Some(&BinRateLimit { requests_per_second: 2.5, burst_size: 4 })).await.unwrap();
+        storage.set_bin_limits("bin", Some(&BinLimits::default())).await.unwrap();
+        storage.set_signature_verification("bin", Some(&SignatureVerification { provider: SignatureProvider::Github, secret: "s3cret".to_string(), header: None })).await.unwrap();
+        storage.set_redaction_rules("bin", Some(&RedactionRules { headers: vec!["authorization".to_string()], body_patterns: Vec::new() })).await.unwrap();
+        storage.set_extractors("bin", &[Extractor { name: "order".to_string(), path: "$.order".to_string() }]).await.unwrap();
+        storage.set_bin_sampling("bin", Some(&BinSampling { every: Some(3), percent: None })).await.unwrap();
+        storage.set_bin_chaos("bin", Some(&BinChaos { error_percent: 10.0, drop_percent: 0.0, slow_percent: 0.0, delay_ms: 100 })).await.unwrap();
+        storage.set_bin_proxy("bin", Some(&BinProxy { upstream: "https://example.com".to_string() })).await.unwrap();
+        storage.set_bin_script("bin", Some(&BinScript { source: "respond(204)".to_string() })).await.unwrap();
+        storage.set_response_sequence("bin", Some(&ResponseSequence { mode: SequenceMode::Rotate, responses: vec![response(201)], position: 0 })).await.unwrap();
+        for (rule_id, method) in [("first", Some("POST")), ("second", None)] {
+            let rule = ResponseRule { rule_id: rule_id.to_string(), method: method.map(str::to_string), path: None, response: response(202) };
+            storage.add_response_rule("bin", &rule).await.unwrap();
+        }
+        storage.set_custom_response("bin", Some(&response(200))).await.unwrap();
+        let notifications = BinNotifications { service: ChatService::Slack, webhook_url: "https://hooks.example.com".to_string(), methods: vec!["POST".to_string()], path_prefix: None, failed_signatures_only: true };
+        storage.set_bin_notifications("bin", Some(&notifications)).await.unwrap();
+        for (id, enabled) in [("a", true), ("b", false)] {
+            let forward = Forward { forward_id: id.to_string(), url: format!("https://example.com/{}", id), enabled, created_at: format!("2024-01-01T00:00:0{}Z", enabled as u8), last_delivery: None };
+            storage.create_forward("bin", &forward).await.unwrap();
+        }
+        let callback = Callback { callback_id: "cb".to_string(), url: "https://example.com/hook".to_string(), events: vec!["bin.deleted".to_string()], secret: "s3cret".to_string(), created_at: "2024-01-01T00:00:00Z".to_string() };
+        storage.create_callback("bin", &callback).await.unwrap();
+
+        let settings = storage.bin_settings("bin").await.unwrap().unwrap();
+        assert_eq!(settings.pause, storage.bin_pause("bin").await.unwrap());
+        assert_eq!(settings.ip_access, storage.ip_access_rules("bin").await.unwrap());
+        assert_eq!(settings.rate_limit, storage.bin_rate_limit("bin").await.unwrap());
+        assert_eq!(settings.limits, Some(BinLimits::default()));
+        assert_eq!(settings.signature, storage.signature_verification("bin").await.unwrap());
+        assert_eq!(settings.redaction, storage.redaction_rules("bin").await.unwrap());
+        assert_eq!(settings.extractors, storage.extractors("bin").await.unwrap());
+        assert_eq!(settings.sampling, storage.bin_sampling("bin").await.unwrap());
+        assert_eq!(settings.chaos, storage.bin_chaos("bin").await.unwrap());
+        assert_eq!(settings.proxy, storage.bin_proxy("bin").await.unwrap());
+        assert_eq!(settings.script, storage.bin_script("bin").await.unwrap());
+        assert!(settings.has_sequence);
+        assert_eq!(settings.rules, storage.response_rules("bin").await.unwrap());
+        assert_eq!(settings.response, storage.custom_response("bin").await.unwrap());
+        assert_eq!(settings.notifications, Some(notifications));
+        let forwards: Vec<_> = settings.forwards.iter().map(|forward| (forward.forward_id.as_str(), forward.enabled)).collect();
+        assert_eq!(forwards, [("b", false), ("a", true)]);
+        assert_eq!(format!("{:?}", settings.callbacks), format!("{:?}", storage.list_callbacks("bin").await.unwrap()));
+    }
+
+    #[tokio::test]
+    async fn captures_for_a_missing_bin_leave_nothing_behind() {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await.unwrap();
+        let storage = storage_with_bin(pool, "bin").await;
+        assert!(storage.insert_requests("missing", &[request("orphan")], 5, "2024-01-02T00:00:00+00:00").await.is_err());
+        let rows: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM requests").fetch_one(&storage.pool).await.unwrap();
+        assert_eq!(rows, 0);
+    }
+
+    #[tokio::test]
+    async fn compressed_bodies_are_searchable() {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await.unwrap();
+        let storage = storage_with_bin(pool, "bin")
+            .await
+            .with_compression(&CompressionConfig { enabled: true, level: 3, min_bytes: 16 });
+        let body = format!("{} invoice_7731 {}", "padding ".repeat(100), "padding ".repeat(100));
+        let request = request(&body);
+        storage.insert_requests("bin", std::slice::from_ref(&request), 10, "2024-01-02T00:00:00+00:00").await.unwrap();
+        let compression: Option<String> = sqlx::query_scalar("SELECT body_compression FROM requests").fetch_one(&storage.pool).await.unwrap();
+        assert_eq!(compression.as_deref(), Some(ZSTD));
+
+        let found = storage.search_bin("bin", "invoice_7731", 10).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].body.as_deref(), Some(body.as_str()));
+        let found = storage.search_all("invoice_7731", "2000-01-01T00:00:00+00:00", "2100-01-01T00:00:00+00:00", 10).await.unwrap();
+        assert_eq!(found.len(), 1);
+
+        // Bodies compressed before the index was written from code are added on startup
+        query("DELETE FROM requests_fts").execute(&storage.pool).await.unwrap();
+        assert!(storage.search_bin("bin", "invoice_7731", 10).await.unwrap().is_empty());
+        assert_eq!(index_compressed_bodies(&storage.pool).await.unwrap(), 1);
+        assert_eq!(index_compressed_bodies(&storage.pool).await.unwrap(), 0);
+        assert_eq!(storage.search_bin("bin", "invoice_7731", 10).await.unwrap().len(), 1);
+
+        // Deleted requests leave the index with their row
+        storage.delete_request(request.request_id).await.unwrap();
+        let indexed: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM requests_fts").fetch_one(&storage.pool).await.unwrap();
+        assert_eq!(indexed, 0);
+    }
+}
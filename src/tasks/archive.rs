@@ -0,0 +1,137 @@
+//! Writing expired bins to disk before the cleanup task deletes them
+//! (`[cleanup] archive_directory`), so an unexpected expiry doesn't lose evidence.
+
+use chrono::Utc;
+use flate2::{write::GzEncoder, Compression};
+use futures::StreamExt;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::warn;
+
+use crate::export::to_ndjson_line;
+use crate::storage::Storage;
+
+/// Suffix of archive files; nothing else in the directory is ever pruned
+const ARCHIVE_SUFFIX: &str = ".ndjson.gz";
+
+/// Write the bin's requests, oldest first and in the NDJSON export format, to
+/// `<bin_id>-<timestamp>.ndjson.gz`. Returns the file, or `None` when the bin is empty.
+pub async fn archive_bin(storage: &dyn Storage, directory: &Path, bin_id: &str) -> io::Result<Option<PathBuf>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    let mut archived = 0;
+    let mut requests = storage.stream_requests(bin_id.to_string());
+    while let Some(request) = requests.next().await {
+        let request = request.map_err(io::Error::other)?;
+        encoder.write_all(to_ndjson_line(&request).as_bytes())?;
+        archived += 1;
+    }
+    if archived == 0 {
+        return Ok(None);
+    }
+
+    tokio::fs::create_dir_all(directory).await?;
+    let name = format!("{}-{}{}", bin_id, Utc::now().format("%Y%m%dT%H%M%SZ"), ARCHIVE_SUFFIX);
+    let path = directory.join(&name);
+    // Renamed into place once complete, so a crash never leaves a truncated archive
+    let partial = directory.join(format!(".{}.partial", name));
+    tokio::fs::write(&partial, encoder.finish()?).await?;
+    tokio::fs::rename(&partial, &path).await?;
+    Ok(Some(path))
+}
+
+/// Delete archives last modified more than `retention_days` ago. Returns how many went.
+pub async fn prune_archives(directory: &Path, retention_days: u64) -> usize {
+    let Some(cutoff) = SystemTime::now().checked_sub(Duration::from_secs(retention_days * 24 * 60 * 60)) else {
+        return 0;
+    };
+    let mut entries = match tokio::fs::read_dir(directory).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return 0,
+        Err(err) => {
+            warn!(directory = %directory.display(), %err, "Failed to list bin archives");
+            return 0;
+        }
+    };
+
+    let mut pruned = 0;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if !entry.file_name().to_string_lossy().ends_with(ARCHIVE_SUFFIX) {
+            continue;
+        }
+        let modified = entry.metadata().await.and_then(|metadata| metadata.modified());
+        if modified.is_ok_and(|modified| modified < cutoff) {
+            match tokio::fs::remove_file(entry.path()).await {
+                Ok(()) => pruned += 1,
+                Err(err) => warn!(path = %entry.path().display(), %err, "Failed to delete old bin archive"),
+            }
+        }
+    }
+    pruned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::LoggedRequest;
+    use crate::storage::memory::MemoryStorage;
+    use crate::storage::NewBin;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    fn request(body: &str) -> LoggedRequest {
+        serde_json::from_value(serde_json::json!({
+            "method": "POST",
+            "headers": "[]",
+            "body": body,
+            "timestamp": Utc::now().to_rfc3339(),
+            "request_id": uuid::Uuid::new_v4(),
+            "content_type_mismatch": false,
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn archives_requests_as_gzipped_ndjson() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = MemoryStorage::new();
+        for bin_id in ["full", "empty"] {
+            let bin = NewBin { id: bin_id.to_string(), last_updated: Utc::now().to_rfc3339(), ..Default::default() };
+            storage.create_bin(bin).await.unwrap();
+        }
+        storage.insert_requests("full", &[request("first"), request("second")], 100, "").await.unwrap();
+
+        let path = archive_bin(&storage, dir.path(), "full").await.unwrap().unwrap();
+        assert!(path.file_name().unwrap().to_string_lossy().starts_with("full-"));
+        let mut ndjson = String::new();
+        GzDecoder::new(std::fs::File::open(&path).unwrap()).read_to_string(&mut ndjson).unwrap();
+        let bodies: Vec<_> = ndjson
+            .lines()
+            .map(|line| serde_json::from_str::<LoggedRequest>(line).unwrap().body.unwrap())
+            .collect();
+        assert_eq!(bodies, ["first", "second"]);
+
+        assert!(archive_bin(&storage, dir.path(), "empty").await.unwrap().is_none());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn prunes_only_old_archives() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("old-20240101T000000Z.ndjson.gz");
+        let recent = dir.path().join("recent-20240101T000000Z.ndjson.gz");
+        let unrelated = dir.path().join("notes.txt");
+        for path in [&old, &recent, &unrelated] {
+            std::fs::write(path, b"").unwrap();
+        }
+        let long_ago = SystemTime::now() - Duration::from_secs(10 * 24 * 60 * 60);
+        for path in [&old, &unrelated] {
+            std::fs::File::options().write(true).open(path).unwrap().set_modified(long_ago).unwrap();
+        }
+
+        assert_eq!(prune_archives(dir.path(), 7).await, 1);
+        assert!(!old.exists());
+        assert!(recent.exists() && unrelated.exists());
+        assert_eq!(prune_archives(&dir.path().join("missing"), 7).await, 0);
+    }
+}
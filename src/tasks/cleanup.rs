@@ -1,93 +1,391 @@
-use sqlx::SqlitePool;
-use chrono::{Utc, Duration};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 use tokio::time::{sleep, Duration as TokioDuration};
 use tracing::{info, warn};
-use dashmap::DashMap;
-use std::sync::Arc;
-use tokio::sync::broadcast;
 
-use crate::config::CleanupConfig;
+use super::archive::{archive_bin, prune_archives};
+use crate::config::{CleanupConfig, StorageFullPolicy};
+use crate::models::WsEvent;
+use crate::state::AppState;
+use crate::storage::StorageResult;
+use crate::webhooks::BinEvent;
+use crate::websocket::broadcast;
 
-pub async fn start_cleanup_task(
-    db: SqlitePool, 
-    bin_channels: Arc<DashMap<String, broadcast::Sender<String>>>,
-    config: &CleanupConfig,
-) {
-    let cleanup_config = config.clone();
+/// Result of a single cleanup pass.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct CleanupOutcome {
+    pub deleted: usize,
+    pub kept_alive: usize,
+    pub warned: usize,
+    /// Expired bins written to `archive_directory` before deletion
+    pub archived: usize,
+    /// Soft-deleted bins removed for good
+    pub purged: usize,
+    /// Requests deleted to get back under `max_storage_bytes`
+    pub evicted: usize,
+    /// Requests older than their bin's retention
+    pub expired_requests: usize,
+}
+
+/// Requests evicted per round trip to storage, and most rounds in one pass, so a store
+/// that doesn't shrink as expected can't keep the pass going forever
+const EVICTION_BATCH_SIZE: i64 = 100;
+const MAX_EVICTION_BATCHES: usize = 1000;
+
+/// Run cleanup passes forever. `state.cleanup` is read before every pass, so a reloaded
+/// configuration applies from the next pass on.
+pub async fn start_cleanup_task(state: AppState) {
+    let mut interval_seconds = state.cleanup.load().cleanup_interval_seconds;
+    state.cleanup_heartbeat.start(interval_seconds.saturating_mul(1000));
     tokio::spawn(async move {
+        let mut warned_bins = HashSet::new();
         loop {
-            let cutoff = Utc::now() - Duration::hours(cleanup_config.bin_expiry_hours);
-            
-            let expired_bins = match sqlx::query_as::<_, (String,)>(
-                "SELECT id FROM bins WHERE last_updated < ?"
-            )
-            .bind(cutoff)
-            .fetch_all(&db)
-            .await
-            {
-                Ok(bins) => bins,
-                Err(err) => {
-                    warn!("Failed to query expired bins: {:?}", err);
-                    sleep(TokioDuration::from_secs(cleanup_config.cleanup_interval_seconds)).await;
-                    continue;
+            let config = state.cleanup.load_full();
+            run_cleanup_pass(&state, &config, &mut warned_bins).await;
+            if config.cleanup_interval_seconds == interval_seconds {
+                state.cleanup_heartbeat.beat();
+            } else {
+                // Expect beats at the new pace
+                interval_seconds = config.cleanup_interval_seconds;
+                state.cleanup_heartbeat.start(interval_seconds.saturating_mul(1000));
+            }
+            sleep(TokioDuration::from_secs(interval_seconds)).await;
+        }
+    });
+}
+
+/// Delete expired bins, announce bins that are about to expire and purge bins deleted
+/// longer ago than `deleted_bin_retention_hours`.
+///
+/// `warned_bins` remembers which bins already received a `bin.expiring` event so
+/// each bin is only warned once per inactivity period.
+pub async fn run_cleanup_pass(
+    state: &AppState,
+    config: &CleanupConfig,
+    warned_bins: &mut HashSet<String>,
+) -> CleanupOutcome {
+    let mut outcome = CleanupOutcome::default();
+    let expiry = match BinExpiry::load(state, config).await {
+        Ok(expiry) => expiry,
+        Err(err) => {
+            warn!("Failed to query bin expiry overrides: {:?}", err);
+            return outcome;
+        }
+    };
+    let now = Utc::now();
+
+    if config.expiry_warning_minutes > 0 {
+        outcome.warned = warn_expiring_bins(state, config, &expiry, now, warned_bins).await;
+    }
+
+    let expired_bins = match state.storage.inactive_bins(&(now - expiry.shortest()).to_rfc3339(), None).await {
+        Ok(bins) => bins,
+        Err(err) => {
+            warn!("Failed to query expired bins: {:?}", err);
+            return outcome;
+        }
+    };
+
+    for (bin_id, last_updated) in expired_bins {
+        if expiry.expires_at(&bin_id, &last_updated).is_none_or(|expires_at| expires_at > now) {
+            continue;
+        }
+
+        // Check if there are active WebSocket connections for this bin
+        let has_active_connections = state
+            .bin_channels
+            .get(&bin_id)
+            .map(|sender| sender.receiver_count() > 0)
+            .unwrap_or(false);
+
+        if has_active_connections {
+            // Bin has active WebSocket connections, keep it alive
+            outcome.kept_alive += 1;
+            info!(%bin_id, "Keeping expired bin alive due to active WebSocket connections");
+            continue;
+        }
+
+        // An expired bin that can't be archived is kept until a later pass succeeds
+        if let Some(directory) = &config.archive_directory {
+            match archive_bin(state.storage.as_ref(), Path::new(directory), &bin_id).await {
+                Ok(Some(path)) => {
+                    outcome.archived += 1;
+                    info!(%bin_id, path = %path.display(), "Archived expired bin");
                 }
-            };
-
-            let mut deleted_count = 0;
-            let mut kept_alive_count = 0;
-
-            for (bin_id,) in expired_bins {
-                // Check if there are active WebSocket connections for this bin
-                let has_active_connections = bin_channels
-                    .get(&bin_id)
-                    .map(|sender| sender.receiver_count() > 0)
-                    .unwrap_or(false);
-
-                if has_active_connections {
-                    // Bin has active WebSocket connections, keep it alive
-                    kept_alive_count += 1;
-                    info!(%bin_id, "Keeping expired bin alive due to active WebSocket connections");
+                Ok(None) => {}
+                Err(err) => {
+                    warn!(%bin_id, %err, "Failed to archive expired bin, keeping it for now");
                     continue;
                 }
+            }
+        }
 
-                // No active connections, safe to delete
-                if let Err(err) = sqlx::query("DELETE FROM bins WHERE id = ?")
-                    .bind(&bin_id)
-                    .execute(&db)
-                    .await
-                {
-                    warn!(%bin_id, %err, "Failed to delete expired bin");
-                } else {
-                    deleted_count += 1;
-                    info!(%bin_id, "Deleted expired bin");
-                    
-                    // Clean up the channel entry if it exists
-                    bin_channels.remove(&bin_id);
-                }
+        // No active connections, safe to delete. The callbacks are deleted with the bin.
+        let callbacks = state.callbacks.load(&bin_id).await;
+        let result = state.storage.delete_bin(&bin_id).await;
+        state.bin_cache.remove(&bin_id);
+        if let Err(err) = result {
+            warn!(%bin_id, %err, "Failed to delete expired bin");
+        } else {
+            outcome.deleted += 1;
+            info!(%bin_id, "Deleted expired bin");
+
+            // Tell any late subscriber, then clean up the channel entry if it exists
+            broadcast(state, &bin_id, WsEvent::BinDeleted { expired: true });
+            state.bin_channels.remove(&bin_id);
+            warned_bins.remove(&bin_id);
+            state.webhooks.emit(BinEvent::Expired, &bin_id, serde_json::json!({}));
+            state.callbacks.send_to(&callbacks, BinEvent::Deleted, &bin_id, serde_json::json!({ "expired": true }));
+            state.hooks.on_bin_deleted(&bin_id, true);
+        }
+    }
+
+    outcome.purged = purge_deleted_bins(state, config).await;
+    outcome.expired_requests = delete_expired_requests(state, config, now).await;
+    if let Some(directory) = &config.archive_directory
+        && config.archive_retention_days > 0
+    {
+        let pruned = prune_archives(Path::new(directory), config.archive_retention_days).await;
+        if pruned > 0 {
+            info!(pruned, "Deleted old bin archives");
+        }
+    }
+
+    // Last, so the space freed by expired and purged bins counts
+    outcome.evicted = enforce_storage_cap(state, config).await;
+
+    if outcome.deleted > 0 || outcome.kept_alive > 0 || outcome.purged > 0 || outcome.evicted > 0 || outcome.expired_requests > 0 {
+        info!(
+            deleted = outcome.deleted,
+            kept_alive = outcome.kept_alive,
+            archived = outcome.archived,
+            purged = outcome.purged,
+            evicted = outcome.evicted,
+            expired_requests = outcome.expired_requests,
+            "Cleanup task completed"
+        );
+    }
+
+    outcome
+}
+
+async fn purge_deleted_bins(state: &AppState, config: &CleanupConfig) -> usize {
+    let cutoff = Utc::now() - Duration::hours(config.deleted_bin_retention_hours);
+    let deleted_bins = match state.storage.deleted_bins(&cutoff.to_rfc3339()).await {
+        Ok(bins) => bins,
+        Err(err) => {
+            warn!("Failed to query deleted bins: {:?}", err);
+            return 0;
+        }
+    };
+
+    let mut purged = 0;
+    for bin_id in deleted_bins {
+        match state.storage.delete_bin(&bin_id).await {
+            Ok(_) => {
+                purged += 1;
+                info!(%bin_id, "Purged deleted bin");
             }
+            Err(err) => warn!(%bin_id, %err, "Failed to purge deleted bin"),
+        }
+    }
+    purged
+}
+
+/// Delete unpinned requests older than `request_retention_hours`, or their bin's own
+/// retention, from bins that are still around. Returns how many were deleted.
+async fn delete_expired_requests(state: &AppState, config: &CleanupConfig, now: DateTime<Utc>) -> usize {
+    let overrides: HashMap<String, i64> = match state.storage.bin_retention_overrides().await {
+        Ok(overrides) => overrides.into_iter().collect(),
+        Err(err) => {
+            warn!(%err, "Failed to query request retention overrides");
+            return 0;
+        }
+    };
+    // No request is kept for less than the shortest retention, 0 meaning forever
+    let Some(shortest) = overrides
+        .values()
+        .chain([&config.request_retention_hours])
+        .copied()
+        .filter(|hours| *hours > 0)
+        .min()
+    else {
+        return 0;
+    };
+    let bins = match state.storage.bins_with_requests_before(&(now - Duration::hours(shortest)).to_rfc3339()).await {
+        Ok(bins) => bins,
+        Err(err) => {
+            warn!(%err, "Failed to query bins with old requests");
+            return 0;
+        }
+    };
 
-            if deleted_count > 0 || kept_alive_count > 0 {
-                info!(
-                    deleted = deleted_count, 
-                    kept_alive = kept_alive_count, 
-                    "Cleanup task completed"
-                );
+    let mut expired = 0;
+    for bin_id in bins {
+        let hours = overrides.get(&bin_id).copied().unwrap_or(config.request_retention_hours);
+        if hours <= 0 {
+            continue;
+        }
+        let cutoff = (now - Duration::hours(hours)).to_rfc3339();
+        match state.storage.prune_bin(&bin_id, Some(&cutoff), None).await {
+            Ok(0) => {}
+            Ok(count) => {
+                expired += count as usize;
+                info!(%bin_id, count, "Deleted requests past their retention");
+                broadcast(state, &bin_id, WsEvent::RequestsCleared { count });
             }
+            Err(err) => warn!(%bin_id, %err, "Failed to delete requests past their retention"),
+        }
+    }
+    expired
+}
 
-            sleep(TokioDuration::from_secs(cleanup_config.cleanup_interval_seconds)).await;
+/// Keep the store under `max_storage_bytes`: evict the oldest unpinned requests across
+/// all bins, or flag captures to be refused until space is freed. Returns how many
+/// requests were evicted.
+async fn enforce_storage_cap(state: &AppState, config: &CleanupConfig) -> usize {
+    if config.max_storage_bytes == 0 {
+        state.storage_quota.set_full(false);
+        return 0;
+    }
+    let cap = i64::try_from(config.max_storage_bytes).unwrap_or(i64::MAX);
+
+    let mut evicted = 0;
+    for _ in 0..MAX_EVICTION_BATCHES {
+        let size = match state.storage.storage_size().await {
+            Ok(size) => size,
+            Err(err) => {
+                warn!(%err, "Failed to measure storage size");
+                break;
+            }
+        };
+        if size <= cap {
+            if state.storage_quota.is_full() {
+                info!(size, cap, "Storage back under max_storage_bytes, accepting captures again");
+            }
+            state.storage_quota.set_full(false);
+            break;
         }
-    });
+        if config.storage_full_policy == StorageFullPolicy::Reject {
+            if !state.storage_quota.is_full() {
+                warn!(size, cap, "Storage reached max_storage_bytes, refusing captures");
+            }
+            state.storage_quota.set_full(true);
+            break;
+        }
+
+        let requests = match state.storage.evict_oldest_requests(EVICTION_BATCH_SIZE).await {
+            Ok(requests) => requests,
+            Err(err) => {
+                warn!(%err, "Failed to evict requests");
+                break;
+            }
+        };
+        if requests.is_empty() {
+            warn!(size, cap, "Storage over max_storage_bytes with only pinned requests left");
+            break;
+        }
+        evicted += requests.len();
+        for (bin_id, request_id) in requests {
+            broadcast(state, &bin_id, WsEvent::RequestDeleted { request_id });
+        }
+    }
+
+    if evicted > 0 {
+        state.storage_quota.record_evictions(evicted as u64);
+        info!(evicted, cap, "Evicted oldest requests to stay under max_storage_bytes");
+    }
+    evicted
+}
+
+/// `bin_expiry_hours`, and the bins that set their own
+struct BinExpiry {
+    default: Duration,
+    overrides: HashMap<String, Duration>,
+}
+
+impl BinExpiry {
+    async fn load(state: &AppState, config: &CleanupConfig) -> StorageResult<Self> {
+        let overrides = state.storage.bin_expiry_overrides().await?;
+        Ok(BinExpiry {
+            default: Duration::hours(config.bin_expiry_hours),
+            overrides: overrides.into_iter().map(|(bin_id, hours)| (bin_id, Duration::hours(hours))).collect(),
+        })
+    }
+
+    /// No bin expires sooner after its last update than this
+    fn shortest(&self) -> Duration {
+        self.overrides.values().copied().fold(self.default, Duration::min)
+    }
+
+    fn expires_at(&self, bin_id: &str, last_updated: &str) -> Option<DateTime<Utc>> {
+        let last_updated = DateTime::parse_from_rfc3339(last_updated).ok()?.with_timezone(&Utc);
+        Some(last_updated + self.overrides.get(bin_id).copied().unwrap_or(self.default))
+    }
+}
+
+async fn warn_expiring_bins(
+    state: &AppState,
+    config: &CleanupConfig,
+    expiry: &BinExpiry,
+    now: DateTime<Utc>,
+    warned_bins: &mut HashSet<String>,
+) -> usize {
+    let warning = Duration::minutes(config.expiry_warning_minutes);
+    let warning_cutoff = now - expiry.shortest() + warning;
+
+    let inactive_bins = match state.storage.inactive_bins(&warning_cutoff.to_rfc3339(), None).await {
+        Ok(bins) => bins,
+        Err(err) => {
+            warn!("Failed to query expiring bins: {:?}", err);
+            return 0;
+        }
+    };
+    let expiring_bins: Vec<(String, DateTime<Utc>)> = inactive_bins
+        .into_iter()
+        .filter_map(|(bin_id, last_updated)| {
+            let expires_at = expiry.expires_at(&bin_id, &last_updated)?;
+            (expires_at > now && expires_at <= now + warning).then_some((bin_id, expires_at))
+        })
+        .collect();
+
+    // Forget bins that were refreshed or removed since they were warned
+    warned_bins.retain(|id| expiring_bins.iter().any(|(bin_id, _)| bin_id == id));
+
+    let mut warned = 0;
+    for (bin_id, expires_at) in expiring_bins {
+        if !warned_bins.insert(bin_id.clone()) {
+            continue;
+        }
+
+        let expires_at = Some(expires_at.to_rfc3339());
+        info!(%bin_id, "Bin is about to expire");
+        broadcast(state, &bin_id, WsEvent::BinExpiring { expires_at: expires_at.clone() });
+        state.webhooks.emit(
+            BinEvent::Expiring,
+            &bin_id,
+            serde_json::json!({ "expires_at": expires_at }),
+        );
+        state.callbacks.emit(BinEvent::Expiring, &bin_id, serde_json::json!({ "expires_at": expires_at }));
+        warned += 1;
+    }
+    warned
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::RustbinConfig;
+    use crate::storage::{NewBin, PageRequest, RequestFilter};
     use sqlx::sqlite::SqlitePoolOptions;
-    use chrono::Utc;
+    use tokio::sync::broadcast;
 
-    #[tokio::test]
-    async fn test_cleanup_respects_active_websocket_connections() {
-        // Setup test database
+    async fn setup_state() -> AppState {
+        setup_state_with_config(&RustbinConfig::default()).await
+    }
+
+    async fn setup_state_with_config(config: &RustbinConfig) -> AppState {
         let pool = SqlitePoolOptions::new()
             .max_connections(1)
             .connect(":memory:")
@@ -95,113 +393,301 @@ mod tests {
             .unwrap();
 
         // Create tables
-        sqlx::query("CREATE TABLE bins (id TEXT UNIQUE PRIMARY KEY, last_updated TEXT NOT NULL);")
-            .execute(&pool)
+        crate::storage::sqlite::MIGRATOR.run(&pool).await.unwrap();
+
+        AppState::from_pool(pool, config)
+    }
+
+    async fn insert_bin(state: &AppState, id: &str, last_updated: DateTime<Utc>) {
+        state
+            .storage
+            .create_bin(NewBin { id: id.to_string(), last_updated: last_updated.to_rfc3339(), ..Default::default() })
             .await
             .unwrap();
+    }
 
-        sqlx::query("CREATE TABLE requests (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            bin_id TEXT,
-            request_id TEXT UNIQUE NOT NULL,
-            method TEXT,
-            headers TEXT,
-            body TEXT,
-            timestamp TEXT
-        );")
-        .execute(&pool)
-        .await
-        .unwrap();
+    async fn bin_exists(state: &AppState, id: &str) -> bool {
+        state.storage.bin_exists(id).await.unwrap()
+    }
 
-        let bin_channels = Arc::new(DashMap::new());
+    #[tokio::test]
+    async fn test_cleanup_respects_active_websocket_connections() {
+        let state = setup_state().await;
+        let config = RustbinConfig::default().cleanup;
 
         // Create two bins that are older than 1 hour
         let old_time = Utc::now() - Duration::hours(2);
         let bin_id_with_connection = "test-bin-with-ws";
         let bin_id_without_connection = "test-bin-without-ws";
-
-        // Insert both bins with old timestamps
-        sqlx::query("INSERT INTO bins (id, last_updated) VALUES (?, ?)")
-            .bind(bin_id_with_connection)
-            .bind(old_time.to_rfc3339())
-            .execute(&pool)
-            .await
-            .unwrap();
-
-        sqlx::query("INSERT INTO bins (id, last_updated) VALUES (?, ?)")
-            .bind(bin_id_without_connection)
-            .bind(old_time.to_rfc3339())
-            .execute(&pool)
-            .await
-            .unwrap();
+        insert_bin(&state, bin_id_with_connection, old_time).await;
+        insert_bin(&state, bin_id_without_connection, old_time).await;
 
         // Create a WebSocket connection for one bin (simulate active connection)
         let (tx, _rx): (broadcast::Sender<String>, broadcast::Receiver<String>) = broadcast::channel(100);
         let _rx_keepalive = tx.subscribe(); // Keep a receiver alive to simulate active connection
-        bin_channels.insert(bin_id_with_connection.to_string(), tx);
-
-        // Run the cleanup logic manually (extract the cleanup logic to a function for testing)
-        let cutoff = Utc::now() - Duration::hours(1);
-        
-        // Get expired bins
-        let expired_bins = sqlx::query_as::<_, (String,)>(
-            "SELECT id FROM bins WHERE last_updated < ?"
-        )
-        .bind(cutoff)
-        .fetch_all(&pool)
-        .await
-        .unwrap();
+        state.bin_channels.insert(bin_id_with_connection.to_string(), tx);
+
+        let outcome = run_cleanup_pass(&state, &config, &mut HashSet::new()).await;
 
-        let mut deleted_count = 0;
-        let mut kept_alive_count = 0;
+        // Verify that one bin was deleted and one was kept alive
+        assert_eq!(outcome.deleted, 1, "Should delete bin without WebSocket connections");
+        assert_eq!(outcome.kept_alive, 1, "Should keep bin with active WebSocket connections");
+
+        assert!(bin_exists(&state, bin_id_with_connection).await, "Bin with active WebSocket should still exist");
+        assert!(!bin_exists(&state, bin_id_without_connection).await, "Bin without WebSocket should be deleted");
+    }
+
+    #[tokio::test]
+    async fn test_expiring_bins_are_warned_once() {
+        let state = setup_state().await;
+        let config = RustbinConfig::default().cleanup;
+        let mut warned_bins = HashSet::new();
+
+        // Inside the 10 minute warning window, but not yet expired
+        insert_bin(&state, "expiring-bin", Utc::now() - Duration::minutes(55)).await;
+        insert_bin(&state, "fresh-bin", Utc::now()).await;
+        let (tx, mut rx) = broadcast::channel(10);
+        state.bin_channels.insert("expiring-bin".to_string(), tx);
+
+        let outcome = run_cleanup_pass(&state, &config, &mut warned_bins).await;
+        assert_eq!(outcome.warned, 1);
+        assert_eq!(outcome.deleted, 0);
+        assert!(warned_bins.contains("expiring-bin"));
+        let message: serde_json::Value = serde_json::from_str(&rx.try_recv().unwrap()).unwrap();
+        assert_eq!(message["type"], "bin_expiring");
+        assert!(message["data"]["expires_at"].is_string());
+
+        let outcome = run_cleanup_pass(&state, &config, &mut warned_bins).await;
+        assert_eq!(outcome.warned, 0, "Bin should only be warned once");
+    }
 
-        for (bin_id,) in expired_bins {
-            // Check if there are active WebSocket connections for this bin
-            let has_active_connections = bin_channels
-                .get(&bin_id)
-                .map(|sender| sender.receiver_count() > 0)
-                .unwrap_or(false);
+    #[tokio::test]
+    async fn test_expiry_warnings_reach_listeners_and_callbacks_once_per_inactivity() {
+        // The callback receiver listens on loopback
+        let mut state_config = RustbinConfig::default();
+        state_config.outbound.allowed_networks = vec!["127.0.0.0/8".to_string()];
+        let state = setup_state_with_config(&state_config).await;
+        let config = RustbinConfig::default().cleanup;
+        let mut warned_bins = HashSet::new();
 
-            if has_active_connections {
-                // Bin has active WebSocket connections, keep it alive
-                kept_alive_count += 1;
-                continue;
+        // A callback receiver recording the events it is sent
+        let (sender, mut events) = tokio::sync::mpsc::unbounded_channel();
+        let receive = move |headers: axum::http::HeaderMap, body: String| {
+            let sender = sender.clone();
+            async move {
+                let _ = sender.send((headers, body));
+                axum::http::StatusCode::OK
             }
+        };
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let receiver = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, axum::Router::new().fallback(receive)).await.unwrap() });
+
+        insert_bin(&state, "expiring-bin", Utc::now()).await;
+        let callback = crate::models::Callback {
+            callback_id: "expiry".to_string(),
+            url: format!("http://{}/hook", receiver),
+            events: vec!["bin.expiring".to_string()],
+            secret: "s3cret".to_string(),
+            created_at: Utc::now().to_rfc3339(),
+        };
+        state.storage.create_callback("expiring-bin", &callback).await.unwrap();
+        let (tx, mut rx) = broadcast::channel(10);
+        state.bin_channels.insert("expiring-bin".to_string(), tx);
 
-            // No active connections, safe to delete
-            if sqlx::query("DELETE FROM bins WHERE id = ?")
-                .bind(&bin_id)
-                .execute(&pool)
-                .await
-                .is_ok()
-            {
-                deleted_count += 1;
-                bin_channels.remove(&bin_id);
+        let idle_since = |minutes| (Utc::now() - Duration::minutes(minutes)).to_rfc3339();
+        for _ in 0..2 {
+            state.storage.insert_requests("expiring-bin", &[], 100, &idle_since(55)).await.unwrap();
+            for _ in 0..2 {
+                run_cleanup_pass(&state, &config, &mut warned_bins).await;
             }
+
+            let message: serde_json::Value = serde_json::from_str(&rx.try_recv().unwrap()).unwrap();
+            assert_eq!(message["type"], "bin_expiring");
+            let expires_at = message["data"]["expires_at"].as_str().unwrap();
+            let (headers, body) = tokio::time::timeout(std::time::Duration::from_secs(5), events.recv()).await.unwrap().unwrap();
+            assert_eq!(headers["x-rustbin-event"], "bin.expiring");
+            let payload: serde_json::Value = serde_json::from_str(&body).unwrap();
+            assert_eq!(payload["data"]["expires_at"], expires_at);
+            assert!(rx.try_recv().is_err(), "Bin should be warned once per inactivity period");
+
+            // Activity starts a new period, which is warned about again once it runs low
+            state.storage.insert_requests("expiring-bin", &[], 100, &idle_since(0)).await.unwrap();
+            run_cleanup_pass(&state, &config, &mut warned_bins).await;
+            assert!(rx.try_recv().is_err());
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        assert!(events.try_recv().is_err(), "Callbacks should be warned once per inactivity period");
+    }
+
+    #[tokio::test]
+    async fn test_bins_expire_after_their_own_expiry() {
+        let state = setup_state().await;
+        let mut config = RustbinConfig::default().cleanup;
+        config.bin_expiry_hours = 4;
+        let two_hours_ago = Utc::now() - Duration::hours(2);
+        for bin_id in ["default-bin", "short-bin", "long-bin"] {
+            insert_bin(&state, bin_id, two_hours_ago).await;
         }
+        state.storage.insert_requests("long-bin", &[], 100, &(Utc::now() - Duration::hours(5)).to_rfc3339()).await.unwrap();
+        let expiry_hours = |hours| crate::models::BinLimits { expiry_hours: Some(hours), ..Default::default() };
+        state.storage.set_bin_limits("short-bin", Some(&expiry_hours(1))).await.unwrap();
+        state.storage.set_bin_limits("long-bin", Some(&expiry_hours(6))).await.unwrap();
 
-        // Verify that one bin was deleted and one was kept alive
-        assert_eq!(deleted_count, 1, "Should delete bin without WebSocket connections");
-        assert_eq!(kept_alive_count, 1, "Should keep bin with active WebSocket connections");
-
-        // Verify the bin with connection still exists
-        let bin_exists = sqlx::query_scalar::<_, i64>(
-            "SELECT COUNT(*) FROM bins WHERE id = ?"
-        )
-        .bind(bin_id_with_connection)
-        .fetch_one(&pool)
-        .await
-        .unwrap();
-        assert_eq!(bin_exists, 1, "Bin with active WebSocket should still exist");
-
-        // Verify the bin without connection was deleted
-        let bin_exists = sqlx::query_scalar::<_, i64>(
-            "SELECT COUNT(*) FROM bins WHERE id = ?"
-        )
-        .bind(bin_id_without_connection)
-        .fetch_one(&pool)
-        .await
+        let outcome = run_cleanup_pass(&state, &config, &mut HashSet::new()).await;
+        assert_eq!(outcome.deleted, 1);
+        assert!(!bin_exists(&state, "short-bin").await);
+        assert!(bin_exists(&state, "default-bin").await && bin_exists(&state, "long-bin").await);
+    }
+
+    #[tokio::test]
+    async fn test_deleted_bins_are_purged_after_retention() {
+        let state = setup_state().await;
+        let config = RustbinConfig::default().cleanup;
+        insert_bin(&state, "recently-deleted", Utc::now()).await;
+        insert_bin(&state, "long-deleted", Utc::now()).await;
+        let deleted_at = |hours| (Utc::now() - Duration::hours(hours)).to_rfc3339();
+        state.storage.soft_delete_bin("recently-deleted", &deleted_at(1)).await.unwrap();
+        state.storage.soft_delete_bin("long-deleted", &deleted_at(25)).await.unwrap();
+
+        let outcome = run_cleanup_pass(&state, &config, &mut HashSet::new()).await;
+        assert_eq!(outcome.purged, 1);
+        assert!(state.storage.restore_bin("recently-deleted", &Utc::now().to_rfc3339()).await.unwrap());
+        assert!(!state.storage.restore_bin("long-deleted", &Utc::now().to_rfc3339()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_expired_bins_are_archived_before_deletion() {
+        let state = setup_state().await;
+        let dir = tempfile::tempdir().unwrap();
+        let expired = (Utc::now() - Duration::hours(2)).to_rfc3339();
+        insert_bin(&state, "expired-bin", Utc::now()).await;
+        let request = serde_json::from_value(serde_json::json!({
+            "method": "POST",
+            "headers": "[]",
+            "body": "evidence",
+            "timestamp": expired,
+            "request_id": uuid::Uuid::new_v4(),
+            "content_type_mismatch": false,
+        }))
         .unwrap();
-        assert_eq!(bin_exists, 0, "Bin without WebSocket should be deleted");
+        state.storage.insert_requests("expired-bin", &[request], 100, &expired).await.unwrap();
+
+        // A file where the directory should be: archiving fails and the bin is kept
+        let blocked = dir.path().join("blocked");
+        std::fs::write(&blocked, b"").unwrap();
+        let mut config = RustbinConfig::default().cleanup;
+        config.archive_directory = Some(blocked.to_string_lossy().into_owned());
+        let outcome = run_cleanup_pass(&state, &config, &mut HashSet::new()).await;
+        assert_eq!((outcome.archived, outcome.deleted), (0, 0));
+        assert!(bin_exists(&state, "expired-bin").await);
+
+        let archives = dir.path().join("archives");
+        config.archive_directory = Some(archives.to_string_lossy().into_owned());
+        let outcome = run_cleanup_pass(&state, &config, &mut HashSet::new()).await;
+        assert_eq!((outcome.archived, outcome.deleted), (1, 1));
+        assert!(!bin_exists(&state, "expired-bin").await);
+        assert_eq!(std::fs::read_dir(&archives).unwrap().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_requests_are_deleted_after_their_retention() {
+        let state = setup_state().await;
+        let request = |minutes_ago: i64| -> crate::models::LoggedRequest {
+            serde_json::from_value(serde_json::json!({
+                "method": "POST",
+                "headers": "[]",
+                "body": format!("{} minutes old", minutes_ago),
+                "timestamp": (Utc::now() - Duration::minutes(minutes_ago)).to_rfc3339(),
+                "request_id": uuid::Uuid::new_v4(),
+                "content_type_mismatch": false,
+            }))
+            .unwrap()
+        };
+        for bin_id in ["default-bin", "short-bin", "forever-bin"] {
+            insert_bin(&state, bin_id, Utc::now()).await;
+            let requests = [request(300), request(90), request(5)];
+            state.storage.insert_requests(bin_id, &requests, 100, &Utc::now().to_rfc3339()).await.unwrap();
+            if bin_id == "default-bin" {
+                state.storage.set_request_pinned(bin_id, requests[0].request_id, true).await.unwrap();
+            }
+        }
+        let retention_hours = |hours| crate::models::BinLimits { retention_hours: Some(hours), ..Default::default() };
+        state.storage.set_bin_limits("short-bin", Some(&retention_hours(1))).await.unwrap();
+        state.storage.set_bin_limits("forever-bin", Some(&retention_hours(0))).await.unwrap();
+
+        let mut config = RustbinConfig::default().cleanup;
+        config.request_retention_hours = 2;
+        let outcome = run_cleanup_pass(&state, &config, &mut HashSet::new()).await;
+        assert_eq!(outcome.expired_requests, 2);
+        assert_eq!(outcome.deleted, 0, "bins themselves stay");
+
+        let state = &state;
+        let bodies = |bin_id: &'static str| async move {
+            let page = PageRequest { after: None, limit: 10, offset: 0 };
+            let (requests, _) = state.storage.list_requests(bin_id, &RequestFilter::default(), page).await.unwrap();
+            requests.into_iter().filter_map(|request| request.body).collect::<Vec<_>>()
+        };
+        // The pinned 300 minute old request outlives the retention
+        assert_eq!(bodies("default-bin").await, ["300 minutes old", "90 minutes old", "5 minutes old"]);
+        assert_eq!(bodies("short-bin").await, ["5 minutes old"]);
+        assert_eq!(bodies("forever-bin").await.len(), 3);
+    }
+
+    async fn fill_bin(state: &AppState, bin_id: &str, count: usize) -> Vec<uuid::Uuid> {
+        insert_bin(state, bin_id, Utc::now()).await;
+        let requests: Vec<crate::models::LoggedRequest> = (0..count)
+            .map(|_| {
+                serde_json::from_value(serde_json::json!({
+                    "method": "POST",
+                    "headers": "[]",
+                    "body": "x".repeat(4096),
+                    "timestamp": Utc::now().to_rfc3339(),
+                    "request_id": uuid::Uuid::new_v4(),
+                    "content_type_mismatch": false,
+                }))
+                .unwrap()
+            })
+            .collect();
+        state.storage.insert_requests(bin_id, &requests, 1000, &Utc::now().to_rfc3339()).await.unwrap();
+        requests.iter().map(|request| request.request_id).collect()
+    }
+
+    #[tokio::test]
+    async fn test_oldest_requests_are_evicted_over_the_storage_cap() {
+        let state = setup_state().await;
+        let ids = fill_bin(&state, "big-bin", 300).await;
+        state.storage.set_request_pinned("big-bin", ids[0], true).await.unwrap();
+        let full_size = state.storage.storage_size().await.unwrap();
+
+        let mut config = RustbinConfig::default().cleanup;
+        config.max_storage_bytes = (full_size / 2) as u64;
+        let outcome = run_cleanup_pass(&state, &config, &mut HashSet::new()).await;
+
+        assert!(outcome.evicted >= 100 && outcome.evicted < 300, "evicted {}", outcome.evicted);
+        assert!(state.storage.storage_size().await.unwrap() <= full_size / 2);
+        assert_eq!(state.storage_quota.evicted(), outcome.evicted as u64);
+        // Pinned requests stay, and the newest go last
+        assert!(state.storage.get_request("big-bin", ids[0]).await.unwrap().is_some());
+        assert!(state.storage.get_request("big-bin", ids[1]).await.unwrap().is_none());
+        assert!(state.storage.get_request("big-bin", ids[299]).await.unwrap().is_some());
+        assert!(!state.storage_quota.is_full());
+    }
+
+    #[tokio::test]
+    async fn test_captures_are_refused_over_the_storage_cap_when_rejecting() {
+        let state = setup_state().await;
+        fill_bin(&state, "big-bin", 10).await;
+
+        let mut config = RustbinConfig::default().cleanup;
+        config.max_storage_bytes = 1;
+        config.storage_full_policy = StorageFullPolicy::Reject;
+        let outcome = run_cleanup_pass(&state, &config, &mut HashSet::new()).await;
+        assert_eq!(outcome.evicted, 0);
+        assert!(state.storage_quota.is_full());
+
+        config.max_storage_bytes = 0;
+        run_cleanup_pass(&state, &config, &mut HashSet::new()).await;
+        assert!(!state.storage_quota.is_full());
     }
 }
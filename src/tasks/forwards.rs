@@ -0,0 +1,22 @@
+use tokio::time::{sleep, Duration};
+use tracing::info;
+
+use crate::config::ForwardingConfig;
+use crate::state::AppState;
+
+/// Periodically retry forward deliveries whose backoff has elapsed
+pub async fn start_forward_retry_task(state: AppState, config: &ForwardingConfig) {
+    if !state.replay.enabled {
+        return;
+    }
+    let interval = Duration::from_millis(config.retry_interval_ms.max(1));
+    tokio::spawn(async move {
+        loop {
+            let retried = state.forwarder.retry_due().await;
+            if retried > 0 {
+                info!(retried, "Retried forward deliveries");
+            }
+            sleep(interval).await;
+        }
+    });
+}
@@ -1,24 +1,37 @@
-use governor::clock::QuantaInstant;
-use governor::middleware::NoOpMiddleware;
 use std::sync::Arc;
 use std::time::Duration;
-use tower_governor::governor::GovernorConfig;
-use tower_governor::key_extractor::PeerIpKeyExtractor;
 
+use crate::bin_rate_limit::BinRateLimiter;
 use crate::config::RateLimitingConfig;
+use crate::rate_limit::RateLimits;
 
 // Prevent unbounded memory growth, and evict stale IPs.
-pub async fn start_rate_limit_cleanup(
-    conf: &Arc<GovernorConfig<PeerIpKeyExtractor, NoOpMiddleware<QuantaInstant>>>,
-    config: &RateLimitingConfig,
-) {
-    let governor_limiter = conf.limiter().clone();
+pub async fn start_rate_limit_cleanup(limits: &Arc<RateLimits>, config: &RateLimitingConfig) {
+    let governor_limiters: Vec<_> = limits.local_configs().iter().map(|conf| conf.limiter().clone()).collect();
     let interval = Duration::from_secs(config.cleanup_interval_seconds);
     std::thread::spawn(move || {
         loop {
             std::thread::sleep(interval);
-            tracing::info!("rate limiting storage size: {}", governor_limiter.len());
-            governor_limiter.retain_recent();
+            let size: usize = governor_limiters.iter().map(|limiter| limiter.len()).sum();
+            tracing::info!("rate limiting storage size: {}", size);
+            for limiter in &governor_limiters {
+                limiter.retain_recent();
+            }
+        }
+    });
+}
+
+// Same for the per-bin buckets, which only need to outlive a quiet spell.
+pub async fn start_bin_rate_limit_cleanup(limiter: Arc<BinRateLimiter>, config: &RateLimitingConfig) {
+    let interval = Duration::from_secs(config.cleanup_interval_seconds);
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(interval);
+            if limiter.is_empty() {
+                continue;
+            }
+            tracing::info!("bin rate limiting storage size: {}", limiter.len());
+            limiter.prune();
         }
     });
 }
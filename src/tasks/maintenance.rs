@@ -0,0 +1,94 @@
+use chrono::Utc;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use crate::config::DatabaseConfig;
+use crate::state::AppState;
+
+/// Run database maintenance on `[database] maintenance_schedule`. The schedule was
+/// validated when the state was built, so an unparsable one is only logged here.
+pub async fn start_maintenance_task(state: AppState, config: &DatabaseConfig) {
+    let schedule = match config.maintenance_schedule() {
+        Ok(Some(schedule)) => schedule,
+        Ok(None) => return,
+        Err(err) => {
+            warn!(%err, "Database maintenance is disabled");
+            return;
+        }
+    };
+    tokio::spawn(async move {
+        while let Some(next) = schedule.next_after(Utc::now()) {
+            let wait = (next - Utc::now()).to_std().unwrap_or_default();
+            sleep(wait).await;
+            run_maintenance(&state).await;
+        }
+        warn!("Database maintenance schedule never fires again");
+    });
+}
+
+/// Reclaim free pages and refresh the planner's statistics, logging what it freed
+pub async fn run_maintenance(state: &AppState) {
+    let started = std::time::Instant::now();
+    match state.storage.run_maintenance().await {
+        Ok(outcome) => info!(
+            reclaimed_pages = outcome.reclaimed_pages,
+            reclaimed_bytes = outcome.reclaimed_pages * outcome.page_size,
+            rebuilt = outcome.rebuilt,
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "Finished database maintenance"
+        ),
+        Err(err) => warn!(%err, "Database maintenance failed"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::RustbinConfig;
+    use crate::models::LoggedRequest;
+    use crate::state::AppState;
+    use crate::storage::NewBin;
+    use chrono::Utc;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn fill_and_clear(state: &AppState, bin_id: &str) {
+        state
+            .storage
+            .create_bin(NewBin { id: bin_id.to_string(), last_updated: Utc::now().to_rfc3339(), ..Default::default() })
+            .await
+            .unwrap();
+        let requests: Vec<LoggedRequest> = (0..200)
+            .map(|_| {
+                serde_json::from_value(serde_json::json!({
+                    "method": "POST",
+                    "headers": "[]",
+                    "body": "x".repeat(4096),
+                    "timestamp": Utc::now().to_rfc3339(),
+                    "request_id": uuid::Uuid::new_v4(),
+                    "content_type_mismatch": false,
+                }))
+                .unwrap()
+            })
+            .collect();
+        state.storage.insert_requests(bin_id, &requests, 1000, &Utc::now().to_rfc3339()).await.unwrap();
+        state.storage.clear_bin(bin_id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_gives_freed_pages_back() {
+        // Created without `auto_vacuum`, like databases from before it was set
+        let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await.unwrap();
+        crate::storage::sqlite::MIGRATOR.run(&pool).await.unwrap();
+        let state = AppState::from_pool(pool, &RustbinConfig::default());
+
+        fill_and_clear(&state, "first").await;
+        let outcome = state.storage.run_maintenance().await.unwrap();
+        assert!(outcome.rebuilt);
+        assert!(outcome.reclaimed_pages > 0);
+
+        // Once rebuilt, an incremental vacuum is enough
+        fill_and_clear(&state, "second").await;
+        let outcome = state.storage.run_maintenance().await.unwrap();
+        assert!(!outcome.rebuilt);
+        assert!(outcome.reclaimed_pages * outcome.page_size >= 200 * 4096);
+    }
+}
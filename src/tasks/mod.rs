@@ -1,2 +1,7 @@
+pub mod archive;
 pub mod cleanup;
-pub mod limit;
\ No newline at end of file
+pub mod forwards;
+pub mod limit;
+pub mod maintenance;
+pub mod reload;
+pub mod tls;
@@ -0,0 +1,140 @@
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+use tokio::time::{sleep, Duration};
+use tracing::{info, warn};
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+use crate::config::RustbinConfig;
+use crate::state::AppState;
+
+/// Swaps the log filter installed in `main`
+pub type LogFilterHandle = reload::Handle<EnvFilter, Registry>;
+
+/// Saving a file often takes several writes; wait for them to settle before reading it
+const SETTLE_DELAY: Duration = Duration::from_millis(250);
+
+/// Watch the configuration file and apply the settings that can change at runtime
+/// whenever it is saved. `log_filter` is `None` when `RUST_LOG` overrides the file.
+pub async fn start_config_reload_task(path: &str, state: AppState, log_filter: Option<LogFilterHandle>) {
+    let path = PathBuf::from(path);
+    let Some(file_name) = path.file_name().map(|name| name.to_os_string()) else {
+        return;
+    };
+    // Watch the directory, since editors and deploy tools often replace the file
+    // instead of writing to it, which ends a watch on the file itself
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    let (changes, mut changed) = mpsc::unbounded_channel();
+    let watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        let Ok(event) = event else { return };
+        // Reading the file ourselves shows up as access events
+        let writes = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_));
+        if writes && event.paths.iter().any(|changed| changed.file_name() == Some(file_name.as_os_str())) {
+            let _ = changes.send(());
+        }
+    });
+    let mut watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(err) => {
+            warn!(%err, "Cannot watch the configuration file, changes need a restart");
+            return;
+        }
+    };
+    if let Err(err) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+        warn!(%err, dir = %dir.display(), "Cannot watch the configuration file, changes need a restart");
+        return;
+    }
+
+    tokio::spawn(async move {
+        // Events stop when the watcher is dropped
+        let _watcher = watcher;
+        while changed.recv().await.is_some() {
+            sleep(SETTLE_DELAY).await;
+            while changed.try_recv().is_ok() {}
+            reload_config(&path, &state, log_filter.as_ref());
+        }
+    });
+}
+
+/// Load the file again and apply it. An unreadable or invalid file keeps the current
+/// settings, as does a missing one, which is likely being replaced.
+pub fn reload_config(path: &Path, state: &AppState, log_filter: Option<&LogFilterHandle>) {
+    if !path.exists() {
+        return;
+    }
+    let config = match RustbinConfig::load(path) {
+        Ok(config) => config,
+        Err(err) => {
+            warn!(path = %path.display(), %err, "Ignoring invalid configuration, keeping the current one");
+            return;
+        }
+    };
+
+    state.reload(&config);
+    if let Some(handle) = log_filter {
+        match EnvFilter::try_new(&config.logging.filter) {
+            Ok(filter) => {
+                if let Err(err) = handle.reload(filter) {
+                    warn!(%err, "Failed to apply the new log filter");
+                }
+            }
+            Err(err) => warn!(filter = %config.logging.filter, %err, "Ignoring invalid log filter"),
+        }
+    }
+    info!(path = %path.display(), "Reloaded configuration");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    fn write_config(path: &Path, max_body_size: usize, bin_expiry_hours: i64) {
+        let mut config = RustbinConfig::default();
+        config.limits.max_body_size = max_body_size;
+        config.cleanup.bin_expiry_hours = bin_expiry_hours;
+        config.save_to_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn reload_applies_limits_and_cleanup_and_keeps_them_on_errors() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("rustbin.toml");
+        let state = AppState::with_storage(Arc::new(MemoryStorage::new()), &RustbinConfig::default());
+
+        write_config(&path, 2048, 12);
+        reload_config(&path, &state, None);
+        assert_eq!(state.limits.load().max_body_size, 2048);
+        assert_eq!(state.cleanup.load().bin_expiry_hours, 12);
+
+        std::fs::write(&path, "[limits\nbroken").unwrap();
+        reload_config(&path, &state, None);
+        std::fs::remove_file(&path).unwrap();
+        reload_config(&path, &state, None);
+        assert_eq!(state.limits.load().max_body_size, 2048);
+    }
+
+    #[tokio::test]
+    async fn watcher_picks_up_saved_changes() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("rustbin.toml");
+        write_config(&path, 1024, 1);
+        let state = AppState::with_storage(Arc::new(MemoryStorage::new()), &RustbinConfig::default());
+        start_config_reload_task(path.to_str().unwrap(), state.clone(), None).await;
+
+        write_config(&path, 4096, 1);
+        for _ in 0..50 {
+            if state.limits.load().max_body_size == 4096 {
+                return;
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+        panic!("configuration change was not applied");
+    }
+}
@@ -0,0 +1,93 @@
+use axum_server::tls_rustls::RustlsConfig;
+use std::io;
+use std::time::SystemTime;
+use tokio::time::{interval, Duration, MissedTickBehavior};
+use tracing::{info, warn};
+
+use crate::config::TlsConfig;
+
+/// Load the certificate and key named in `[server.tls]`
+pub async fn load_tls_config(config: &TlsConfig) -> io::Result<RustlsConfig> {
+    RustlsConfig::from_pem_file(&config.cert_path, &config.key_path).await
+}
+
+/// Load the certificate and key again whenever either file changes, so renewed
+/// certificates are picked up without a restart. Connections keep the certificate they
+/// were accepted with.
+pub async fn start_tls_reload_task(tls: RustlsConfig, config: &TlsConfig) {
+    if config.reload_interval_seconds == 0 {
+        return;
+    }
+    let config = config.clone();
+    let mut ticks = interval(Duration::from_secs(config.reload_interval_seconds));
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    tokio::spawn(async move {
+        let mut loaded = modified(&config);
+        loop {
+            ticks.tick().await;
+            let current = modified(&config);
+            if current == loaded {
+                continue;
+            }
+            match tls.reload_from_pem_file(&config.cert_path, &config.key_path).await {
+                Ok(()) => {
+                    info!(cert_path = %config.cert_path, "Reloaded TLS certificate");
+                    loaded = current;
+                }
+                // Often a renewal caught halfway, with only one of the files written yet
+                Err(err) => warn!(cert_path = %config.cert_path, %err, "Failed to reload TLS certificate, keeping the current one"),
+            }
+        }
+    });
+}
+
+/// Modification times of the certificate and key files
+fn modified(config: &TlsConfig) -> Option<(SystemTime, SystemTime)> {
+    let cert = std::fs::metadata(&config.cert_path).and_then(|meta| meta.modified()).ok()?;
+    let key = std::fs::metadata(&config.key_path).and_then(|meta| meta.modified()).ok()?;
+    Some((cert, key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Router};
+    use std::net::SocketAddr;
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tls");
+
+    fn fixture_config(dir: &str) -> TlsConfig {
+        TlsConfig {
+            cert_path: format!("{}/cert.pem", dir),
+            key_path: format!("{}/key.pem", dir),
+            reload_interval_seconds: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn serves_https_with_the_configured_certificate() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let tls = load_tls_config(&fixture_config(FIXTURES)).await.unwrap();
+
+        let handle = axum_server::Handle::new();
+        let app = Router::new().route("/", get(|| async { "secure" }));
+        let server = axum_server::bind_rustls(SocketAddr::from(([127, 0, 0, 1], 0)), tls)
+            .handle(handle.clone())
+            .serve(app.into_make_service());
+        tokio::spawn(server);
+        let addr = handle.listening().await.unwrap();
+
+        let client = reqwest::Client::builder().danger_accept_invalid_certs(true).build().unwrap();
+        let body = client.get(format!("https://{}/", addr)).send().await.unwrap().text().await.unwrap();
+        assert_eq!(body, "secure");
+        handle.shutdown();
+    }
+
+    #[tokio::test]
+    async fn reports_missing_files() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        assert!(load_tls_config(&fixture_config("/nonexistent")).await.is_err());
+        assert_eq!(modified(&fixture_config("/nonexistent")), None);
+        assert!(modified(&fixture_config(FIXTURES)).is_some());
+    }
+}
@@ -0,0 +1,256 @@
+//! `rustbin tunnel`: follow a bin's WebSocket and pass every capture on to a local server,
+//! reporting what it answered back to the bin. Webhooks sent to a public rustbin reach a
+//! handler running on a laptop without exposing it.
+
+use futures::StreamExt;
+use reqwest::Url;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::{self, pki_types::ServerName};
+use tokio_rustls::TlsConnector;
+use tokio_tungstenite::tungstenite::{self, client::IntoClientRequest, Message};
+use tokio_tungstenite::WebSocketStream;
+use tracing::{info, warn};
+
+use crate::config::ReplayConfig;
+use crate::export::replay_url;
+use crate::models::{LoggedRequest, ReportedDelivery, RequestNotification, WsEvent, WsMessage};
+use crate::replay::{parse_target, Replayer};
+
+pub const USAGE: &str = "usage: rustbin tunnel <bin-id> --to <url> [--server <url>] [--token <token>]";
+
+const DEFAULT_SERVER: &str = "http://localhost:3000";
+/// Bytes of the local server's response body reported back to the bin
+const MAX_RESPONSE_BYTES: usize = 1024 * 1024;
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Where `rustbin tunnel` listens and where it delivers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TunnelOptions {
+    /// The rustbin server holding the bin, e.g. "https://rustb.in"
+    pub server: String,
+    pub bin_id: String,
+    /// Local server captures are passed on to; the captured sub-path and query are appended
+    pub to: String,
+    /// Token of a private bin
+    pub token: Option<String>,
+}
+
+impl TunnelOptions {
+    /// Parse the arguments following `rustbin tunnel`
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut bin_id = None;
+        let mut to = None;
+        let mut server = None;
+        let mut token = None;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let slot = match arg.as_str() {
+                "--to" => &mut to,
+                "--server" => &mut server,
+                "--token" => &mut token,
+                flag if flag.starts_with("--") => return Err(format!("unknown option `{}`", flag)),
+                _ if bin_id.is_none() => {
+                    bin_id = Some(arg.clone());
+                    continue;
+                }
+                _ => return Err(format!("unexpected argument `{}`", arg)),
+            };
+            *slot = Some(args.next().ok_or_else(|| format!("`{}` needs a value", arg))?.clone());
+        }
+
+        let options = TunnelOptions {
+            server: server.unwrap_or_else(|| DEFAULT_SERVER.to_string()).trim_end_matches('/').to_string(),
+            bin_id: bin_id.ok_or("missing bin id")?,
+            to: to.ok_or("missing `--to`")?,
+            token,
+        };
+        parse_target(&options.to).map_err(|err| format!("`--to`: {}", err))?;
+        parse_target(&options.server).map_err(|err| format!("`--server`: {}", err))?;
+        Ok(options)
+    }
+
+    /// The bin's WebSocket, skipping the history sent on connect
+    fn websocket_url(&self) -> Url {
+        let mut url = Url::parse(&self.server).expect("server was checked when parsing");
+        let scheme = if url.scheme() == "https" { "wss" } else { "ws" };
+        url.set_scheme(scheme).expect("ws and wss are valid schemes");
+        url.set_path(&format!("{}/bin/{}/ws", url.path().trim_end_matches('/'), self.bin_id));
+        url.query_pairs_mut().append_pair("history", "0");
+        if let Some(token) = &self.token {
+            url.query_pairs_mut().append_pair("token", token);
+        }
+        url
+    }
+}
+
+#[derive(Debug)]
+pub enum TunnelError {
+    /// The server refused the WebSocket, e.g. an unknown bin or a missing token
+    Rejected(u16),
+    /// The server couldn't be reached or the connection broke
+    Connection(String),
+}
+
+impl fmt::Display for TunnelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TunnelError::Rejected(status) => write!(f, "server refused the tunnel with status {}", status),
+            TunnelError::Connection(message) => write!(f, "tunnel connection failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for TunnelError {}
+
+/// Either a plain or a TLS connection to the server
+trait Io: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Io for T {}
+
+struct Tunnel {
+    options: TunnelOptions,
+    replayer: Replayer,
+    client: reqwest::Client,
+}
+
+/// Pass captures on until the bin is deleted, reconnecting with backoff when the
+/// connection drops. Captures made while disconnected are not delivered.
+pub async fn run(options: TunnelOptions) -> Result<(), TunnelError> {
+    let tunnel = Arc::new(Tunnel {
+        replayer: Replayer::new(&ReplayConfig::default(), MAX_RESPONSE_BYTES),
+        client: reqwest::Client::new(),
+        options,
+    });
+    let mut delay = Duration::from_secs(1);
+    loop {
+        match tunnel.clone().relay().await {
+            Ok(()) => {
+                info!(bin_id = %tunnel.options.bin_id, "Bin was deleted, closing the tunnel");
+                return Ok(());
+            }
+            Err(TunnelError::Rejected(status)) => return Err(TunnelError::Rejected(status)),
+            Err(err) => warn!(%err, retry_in = ?delay, "Tunnel disconnected"),
+        }
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+    }
+}
+
+impl Tunnel {
+    /// Deliver captures until the bin is deleted (`Ok`) or the connection ends (`Err`)
+    async fn relay(self: Arc<Self>) -> Result<(), TunnelError> {
+        let mut socket = self.connect().await?;
+        info!(bin_id = %self.options.bin_id, to = %self.options.to, "Tunnel open");
+        while let Some(frame) = socket.next().await {
+            let Message::Text(text) = frame.map_err(|err| TunnelError::Connection(err.to_string()))? else {
+                continue;
+            };
+            match serde_json::from_str::<WsMessage>(&text).map(|message| message.event) {
+                Ok(WsEvent::Request(notification)) => {
+                    tokio::spawn(self.clone().deliver(*notification));
+                }
+                Ok(WsEvent::BinDeleted { .. }) => return Ok(()),
+                // Other events and newer message types don't concern the tunnel
+                _ => {}
+            }
+        }
+        Err(TunnelError::Connection("server closed the connection".to_string()))
+    }
+
+    async fn connect(&self) -> Result<WebSocketStream<Box<dyn Io>>, TunnelError> {
+        let url = self.options.websocket_url();
+        let host = url.host_str().unwrap_or_default().to_string();
+        let port = url.port_or_known_default().unwrap_or(80);
+        let tcp = TcpStream::connect((host.as_str(), port))
+            .await
+            .map_err(|err| TunnelError::Connection(err.to_string()))?;
+        let stream: Box<dyn Io> = if url.scheme() == "wss" {
+            Box::new(tls_connector()?.connect(server_name(&host)?, tcp).await.map_err(|err| TunnelError::Connection(err.to_string()))?)
+        } else {
+            Box::new(tcp)
+        };
+
+        let request = url.as_str().into_client_request().map_err(|err| TunnelError::Connection(err.to_string()))?;
+        match tokio_tungstenite::client_async(request, stream).await {
+            Ok((socket, _)) => Ok(socket),
+            Err(tungstenite::Error::Http(response)) => Err(TunnelError::Rejected(response.status().as_u16())),
+            Err(err) => Err(TunnelError::Connection(err.to_string())),
+        }
+    }
+
+    /// Send one capture to the local server and report how it went
+    async fn deliver(self: Arc<Self>, notification: RequestNotification) {
+        let request = LoggedRequest::from(notification);
+        let url = replay_url(&request, &self.options.to);
+        let started = Instant::now();
+        let outcome = self.replayer.deliver(&request, &url, MAX_RESPONSE_BYTES).await;
+        let latency_ms = started.elapsed().as_millis() as u64;
+        match &outcome {
+            Ok(response) => info!(method = %request.method, %url, status = response.status, latency_ms, "Delivered"),
+            Err(err) => warn!(method = %request.method, %url, %err, "Delivery failed"),
+        }
+
+        let (response, error) = match outcome {
+            Ok(response) => (Some(response), None),
+            Err(err) => (None, Some(err.to_string())),
+        };
+        let report = ReportedDelivery { url, response, error, latency_ms: Some(latency_ms) };
+        let endpoint = format!("{}/bin/{}/request/{}/deliveries", self.options.server, self.options.bin_id, request.request_id);
+        let mut post = self.client.post(endpoint).json(&report);
+        if let Some(token) = &self.options.token {
+            post = post.bearer_auth(token);
+        }
+        match post.send().await {
+            Ok(answer) if answer.status().is_success() => {}
+            Ok(answer) => warn!(request_id = %request.request_id, status = answer.status().as_u16(), "Server refused the delivery report"),
+            Err(err) => warn!(request_id = %request.request_id, %err, "Failed to report delivery"),
+        }
+    }
+}
+
+/// TLS with the bundled web PKI roots, so the tunnel works without system certificates
+fn tls_connector() -> Result<TlsConnector, TunnelError> {
+    let roots = rustls::RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    let config = rustls::ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(|err| TunnelError::Connection(err.to_string()))?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Ok(TlsConnector::from(Arc::new(config)))
+}
+
+fn server_name(host: &str) -> Result<ServerName<'static>, TunnelError> {
+    ServerName::try_from(host.to_string()).map_err(|err| TunnelError::Connection(format!("`{}`: {}", host, err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_tunnel_arguments() {
+        let options = TunnelOptions::parse(&args("abc --to http://localhost:8080/ --server https://rustb.in/ --token t&1")).unwrap();
+        assert_eq!(options.bin_id, "abc");
+        assert_eq!(options.server, "https://rustb.in");
+        assert_eq!(options.websocket_url().as_str(), "wss://rustb.in/bin/abc/ws?history=0&token=t%261");
+
+        let options = TunnelOptions::parse(&args("--to http://localhost:8080 abc")).unwrap();
+        assert_eq!(options.server, DEFAULT_SERVER);
+        assert_eq!(options.websocket_url().as_str(), "ws://localhost:3000/bin/abc/ws?history=0");
+
+        assert!(TunnelOptions::parse(&args("abc")).is_err());
+        assert!(TunnelOptions::parse(&args("--to http://localhost:8080")).is_err());
+        assert!(TunnelOptions::parse(&args("abc --to")).is_err());
+        assert!(TunnelOptions::parse(&args("abc --to ftp://localhost")).is_err());
+        assert!(TunnelOptions::parse(&args("abc def --to http://localhost")).is_err());
+        assert!(TunnelOptions::parse(&args("abc --to http://localhost --verbose")).is_err());
+    }
+}
@@ -0,0 +1,42 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+pub const ENCODING_UTF8: &str = "utf8";
+pub const ENCODING_BASE64: &str = "base64";
+
+/// Encode a body for storage as text: valid UTF-8 is kept verbatim, anything
+/// else is base64 encoded so binary payloads survive untouched.
+pub fn encode_body(bytes: &[u8]) -> (String, &'static str) {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => (text.to_string(), ENCODING_UTF8),
+        Err(_) => (STANDARD.encode(bytes), ENCODING_BASE64),
+    }
+}
+
+/// Recover the original bytes of a stored body.
+pub fn decode_body(body: &str, encoding: Option<&str>) -> Result<Vec<u8>, base64::DecodeError> {
+    match encoding {
+        Some(ENCODING_BASE64) => STANDARD.decode(body),
+        _ => Ok(body.as_bytes().to_vec()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_bodies_are_stored_verbatim() {
+        let (body, encoding) = encode_body("héllo".as_bytes());
+        assert_eq!(body, "héllo");
+        assert_eq!(encoding, ENCODING_UTF8);
+        assert_eq!(decode_body(&body, Some(encoding)).unwrap(), "héllo".as_bytes());
+    }
+
+    #[test]
+    fn binary_bodies_round_trip() {
+        let original = vec![0x08, 0x96, 0x01, 0xff, 0xfe, 0x00];
+        let (body, encoding) = encode_body(&original);
+        assert_eq!(encoding, ENCODING_BASE64);
+        assert_eq!(decode_body(&body, Some(encoding)).unwrap(), original);
+    }
+}
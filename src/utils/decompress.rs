@@ -0,0 +1,99 @@
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use std::io::Read;
+
+#[derive(Debug)]
+pub enum DecompressError {
+    /// The `Content-Encoding` isn't one we know how to decode
+    Unsupported(String),
+    /// The decoded body would exceed the configured limit
+    TooLarge,
+    /// The body isn't valid for the declared encoding
+    Corrupt(std::io::Error),
+}
+
+impl std::fmt::Display for DecompressError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecompressError::Unsupported(encoding) => write!(f, "unsupported content encoding `{}`", encoding),
+            DecompressError::TooLarge => write!(f, "decompressed body exceeds size limit"),
+            DecompressError::Corrupt(err) => write!(f, "corrupt compressed body: {}", err),
+        }
+    }
+}
+
+/// Decode a body according to its `Content-Encoding` header, reading at most `limit` bytes.
+///
+/// Encodings listed as `gzip, br` were applied in that order, so they are undone in reverse.
+pub fn decompress_body(content_encoding: &str, bytes: &[u8], limit: usize) -> Result<Vec<u8>, DecompressError> {
+    let mut body = bytes.to_vec();
+    for encoding in content_encoding.rsplit(',').map(|e| e.trim().to_ascii_lowercase()) {
+        body = match encoding.as_str() {
+            "" | "identity" => body,
+            "gzip" | "x-gzip" => read_limited(GzDecoder::new(body.as_slice()), limit)?,
+            // "deflate" should be zlib wrapped, but plenty of senders use raw deflate
+            "deflate" => read_limited(ZlibDecoder::new(body.as_slice()), limit)
+                .or_else(|_| read_limited(DeflateDecoder::new(body.as_slice()), limit))?,
+            "br" => read_limited(brotli::Decompressor::new(body.as_slice(), 4096), limit)?,
+            other => return Err(DecompressError::Unsupported(other.to_string())),
+        };
+    }
+    Ok(body)
+}
+
+fn read_limited(reader: impl Read, limit: usize) -> Result<Vec<u8>, DecompressError> {
+    let mut decoded = Vec::new();
+    reader
+        .take(limit as u64 + 1)
+        .read_to_end(&mut decoded)
+        .map_err(DecompressError::Corrupt)?;
+    if decoded.len() > limit {
+        return Err(DecompressError::TooLarge);
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn decodes_gzip() {
+        let compressed = gzip(br#"{"hello": "world"}"#);
+        assert_eq!(decompress_body("gzip", &compressed, 1024).unwrap(), br#"{"hello": "world"}"#);
+    }
+
+    #[test]
+    fn decodes_brotli() {
+        let mut compressed = Vec::new();
+        brotli::CompressorWriter::new(&mut compressed, 4096, 5, 22)
+            .write_all(b"brotli body")
+            .unwrap();
+        assert_eq!(decompress_body("br", &compressed, 1024).unwrap(), b"brotli body");
+    }
+
+    #[test]
+    fn decodes_stacked_encodings() {
+        let compressed = gzip(&gzip(b"twice"));
+        assert_eq!(decompress_body("gzip, gzip", &compressed, 1024).unwrap(), b"twice");
+    }
+
+    #[test]
+    fn enforces_limit() {
+        let compressed = gzip(&vec![b'a'; 10_000]);
+        assert!(matches!(decompress_body("gzip", &compressed, 100), Err(DecompressError::TooLarge)));
+    }
+
+    #[test]
+    fn rejects_unknown_and_corrupt_bodies() {
+        assert!(matches!(decompress_body("zstd", b"x", 100), Err(DecompressError::Unsupported(_))));
+        assert!(matches!(decompress_body("gzip", b"not gzip", 100), Err(DecompressError::Corrupt(_))));
+    }
+}
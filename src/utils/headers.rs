@@ -0,0 +1,126 @@
+use axum::http::HeaderMap;
+use std::collections::HashMap;
+
+/// Serialize headers as an ordered list of `[name, value]` pairs, keeping
+/// repeated headers such as `Set-Cookie` or `X-Forwarded-For`.
+pub fn headers_to_json(headers: &HeaderMap) -> String {
+    let pairs: Vec<(String, String)> = headers
+        .iter()
+        .map(|(k, v)| (k.to_string(), String::from_utf8_lossy(v.as_bytes()).to_string()))
+        .collect();
+    serde_json::to_string(&pairs).unwrap_or_else(|_| "[]".to_string())
+}
+
+/// Parse stored headers back into ordered pairs.
+///
+/// Rows captured before headers were stored as pairs hold a JSON object instead.
+pub fn parse_stored_headers(headers_json: &str) -> Vec<(String, String)> {
+    if let Ok(pairs) = serde_json::from_str::<Vec<(String, String)>>(headers_json) {
+        return pairs;
+    }
+    serde_json::from_str::<HashMap<String, String>>(headers_json)
+        .map(|map| map.into_iter().collect())
+        .unwrap_or_default()
+}
+
+/// First value of a stored header (case-insensitive)
+pub fn stored_header(headers_json: &str, name: &str) -> Option<String> {
+    parse_stored_headers(headers_json)
+        .into_iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value)
+}
+
+/// The first of `offered` that `Accept` ranks highest, or `None` when it accepts none of
+/// them. A missing header accepts anything. Exact types beat `type/*`, which beats `*/*`;
+/// ties go to the earlier offer.
+pub fn preferred_media_type<'a>(headers: &HeaderMap, offered: &[&'a str]) -> Option<&'a str> {
+    let Some(accept) = headers.get(axum::http::header::ACCEPT).and_then(|value| value.to_str().ok()) else {
+        return offered.first().copied();
+    };
+    let ranges: Vec<(String, f32)> = accept
+        .split(',')
+        .filter_map(|range| {
+            let mut parts = range.split(';');
+            let media_type = parts.next()?.trim().to_ascii_lowercase();
+            let quality = parts
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (!media_type.is_empty()).then_some((media_type, quality))
+        })
+        .collect();
+
+    let quality = |offer: &str| {
+        let family = offer.split('/').next().unwrap_or_default();
+        // The most specific matching range decides
+        ranges
+            .iter()
+            .filter_map(|(range, quality)| {
+                let specificity = if range == offer {
+                    2
+                } else if range.strip_suffix("/*") == Some(family) {
+                    1
+                } else if range == "*/*" {
+                    0
+                } else {
+                    return None;
+                };
+                Some((specificity, *quality))
+            })
+            .max_by_key(|(specificity, _)| *specificity)
+            .map_or(0.0, |(_, quality)| quality)
+    };
+    let mut best: Option<(&str, f32)> = None;
+    for offer in offered {
+        let quality = quality(offer);
+        if quality > 0.0 && best.is_none_or(|(_, best)| quality > best) {
+            best = Some((offer, quality));
+        }
+    }
+    best.map(|(offer, _)| offer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn keeps_repeated_headers_in_order() {
+        let mut headers = HeaderMap::new();
+        headers.append("set-cookie", HeaderValue::from_static("a=1"));
+        headers.append("x-forwarded-for", HeaderValue::from_static("10.0.0.1"));
+        headers.append("set-cookie", HeaderValue::from_static("b=2"));
+
+        let json = headers_to_json(&headers);
+        let pairs = parse_stored_headers(&json);
+        let cookies: Vec<_> = pairs.iter().filter(|(k, _)| k == "set-cookie").map(|(_, v)| v.as_str()).collect();
+        assert_eq!(cookies, vec!["a=1", "b=2"]);
+        assert_eq!(stored_header(&json, "X-Forwarded-For").as_deref(), Some("10.0.0.1"));
+    }
+
+    #[test]
+    fn reads_legacy_object_headers() {
+        let json = r#"{"content-type": "application/json"}"#;
+        assert_eq!(stored_header(json, "Content-Type").as_deref(), Some("application/json"));
+    }
+
+    #[test]
+    fn picks_the_preferred_media_type() {
+        let offered = ["application/json", "text/html", "text/csv"];
+        let accept = |value: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert("accept", HeaderValue::from_static(value));
+            preferred_media_type(&headers, &offered)
+        };
+        assert_eq!(preferred_media_type(&HeaderMap::new(), &offered), Some("application/json"));
+        assert_eq!(accept("*/*"), Some("application/json"));
+        // What browsers send when opening a page
+        assert_eq!(accept("text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8"), Some("text/html"));
+        assert_eq!(accept("text/csv"), Some("text/csv"));
+        assert_eq!(accept("text/*;q=0.5, application/json;q=0.4"), Some("text/html"));
+        assert_eq!(accept("text/csv;q=0, text/*"), Some("text/html"));
+        assert_eq!(accept("image/png"), None);
+    }
+}
@@ -1 +1,8 @@
-pub mod uuid;
\ No newline at end of file
+pub mod body;
+pub mod decompress;
+pub mod headers;
+pub mod schedule;
+pub mod sniff;
+pub mod template;
+pub mod token;
+pub mod uuid;
@@ -0,0 +1,189 @@
+//! Cron-style schedules for periodic jobs such as `[database] maintenance_schedule`.
+
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc};
+use std::fmt;
+use std::str::FromStr;
+
+/// How far ahead to look for the next match before concluding there is none
+/// (e.g. `0 0 30 2 *`)
+const MAX_LOOKAHEAD_DAYS: i64 = 5 * 366;
+
+/// A five-field cron expression (`minute hour day-of-month month day-of-week`),
+/// evaluated in UTC. Fields take `*`, numbers, ranges (`1-5`), lists (`1,15`) and steps
+/// (`*/15`, `0-30/10`); day-of-week counts from Sunday as 0 (7 is Sunday too). The
+/// shorthands `@hourly`, `@daily`, `@weekly` and `@monthly` are accepted as well.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    /// Whether either day field was restricted; when both are, a day matching
+    /// either one fires, as in cron
+    day_of_month_restricted: bool,
+    day_of_week_restricted: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleError(String);
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid schedule: {}", self.0)
+    }
+}
+
+impl std::error::Error for ScheduleError {}
+
+impl FromStr for Schedule {
+    type Err = ScheduleError;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let expression = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minutes, hours, days_of_month, months, days_of_week] = fields[..] else {
+            return Err(ScheduleError(format!("expected 5 fields, got {}", fields.len())));
+        };
+
+        // Sunday may be written as 7; fold it onto 0
+        let mut days_of_week_mask = parse_field(days_of_week, 0, 7)?;
+        if days_of_week_mask & (1 << 7) != 0 {
+            days_of_week_mask = (days_of_week_mask | 1) & !(1 << 7);
+        }
+        Ok(Schedule {
+            minutes: parse_field(minutes, 0, 59)?,
+            hours: parse_field(hours, 0, 23)?,
+            days_of_month: parse_field(days_of_month, 1, 31)?,
+            months: parse_field(months, 1, 12)?,
+            days_of_week: days_of_week_mask,
+            day_of_month_restricted: days_of_month != "*",
+            day_of_week_restricted: days_of_week != "*",
+        })
+    }
+}
+
+impl Schedule {
+    /// The first minute strictly after `after` that the schedule fires on, or `None`
+    /// if it never does within the next few years
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let limit = after + Duration::days(MAX_LOOKAHEAD_DAYS);
+        let mut time = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        while time <= limit {
+            if !has(self.months, time.month()) {
+                // First minute of the next month
+                let (year, month) = if time.month() == 12 { (time.year() + 1, 1) } else { (time.year(), time.month() + 1) };
+                time = time.with_day(1)?.with_hour(0)?.with_minute(0)?.with_month(month)?.with_year(year)?;
+            } else if !self.matches_day(&time) {
+                time = time.with_hour(0)?.with_minute(0)? + Duration::days(1);
+            } else if !has(self.hours, time.hour()) {
+                time = time.with_minute(0)? + Duration::hours(1);
+            } else if !has(self.minutes, time.minute()) {
+                time += Duration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+
+    fn matches_day(&self, time: &DateTime<Utc>) -> bool {
+        let day_of_month = has(self.days_of_month, time.day());
+        let day_of_week = has(self.days_of_week, time.weekday().num_days_from_sunday());
+        if self.day_of_month_restricted && self.day_of_week_restricted {
+            day_of_month || day_of_week
+        } else {
+            day_of_month && day_of_week
+        }
+    }
+}
+
+fn has(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+/// Parse one field into a bit mask of the values it allows
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, ScheduleError> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = step.parse::<u32>().ok().filter(|step| *step > 0);
+                (range, step.ok_or_else(|| ScheduleError(format!("bad step in `{}`", part)))?)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_value(start, min, max)?, parse_value(end, min, max)?)
+        } else {
+            let value = parse_value(range, min, max)?;
+            // `5/10` means every 10 from 5 on, like `5-<max>/10`
+            (value, if step > 1 { max } else { value })
+        };
+        if start > end {
+            return Err(ScheduleError(format!("range `{}` runs backwards", range)));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+fn parse_value(value: &str, min: u32, max: u32) -> Result<u32, ScheduleError> {
+    value
+        .parse::<u32>()
+        .ok()
+        .filter(|value| (min..=max).contains(value))
+        .ok_or_else(|| ScheduleError(format!("`{}` is not between {} and {}", value, min, max)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0).unwrap()
+    }
+
+    fn next(expression: &str, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        expression.parse::<Schedule>().unwrap().next_after(after)
+    }
+
+    #[test]
+    fn finds_the_next_matching_minute() {
+        let now = at(2024, 3, 10, 14, 7);
+        assert_eq!(next("* * * * *", now), Some(at(2024, 3, 10, 14, 8)));
+        assert_eq!(next("*/15 * * * *", now), Some(at(2024, 3, 10, 14, 15)));
+        assert_eq!(next("0 3 * * *", now), Some(at(2024, 3, 11, 3, 0)));
+        assert_eq!(next("@monthly", now), Some(at(2024, 4, 1, 0, 0)));
+        assert_eq!(next("30 1 * 1 *", now), Some(at(2025, 1, 1, 1, 30)));
+        // 2024-03-10 is a Sunday
+        assert_eq!(next("0 4 * * 7", now), Some(at(2024, 3, 17, 4, 0)));
+        assert_eq!(next("0 4 * * 1-5", now), Some(at(2024, 3, 11, 4, 0)));
+    }
+
+    #[test]
+    fn either_restricted_day_field_matches() {
+        // The 15th or any Monday, whichever comes first
+        assert_eq!(next("0 0 15 * 1", at(2024, 3, 10, 14, 7)), Some(at(2024, 3, 11, 0, 0)));
+        assert_eq!(next("0 0 29 2 *", at(2024, 3, 1, 0, 0)), Some(at(2028, 2, 29, 0, 0)));
+        assert_eq!(next("0 0 30 2 *", at(2024, 3, 1, 0, 0)), None);
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        for expression in ["", "* * * *", "60 * * * *", "* 24 * * *", "* * 0 * *", "*/0 * * * *", "5-1 * * * *", "daily"] {
+            assert!(expression.parse::<Schedule>().is_err(), "{expression:?} should be rejected");
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+
+/// Body format detected by inspecting the payload bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BodyFormat {
+    Empty,
+    Json,
+    Xml,
+    Form,
+    Text,
+    Binary,
+}
+
+impl BodyFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BodyFormat::Empty => "empty",
+            BodyFormat::Json => "json",
+            BodyFormat::Xml => "xml",
+            BodyFormat::Form => "form",
+            BodyFormat::Text => "text",
+            BodyFormat::Binary => "binary",
+        }
+    }
+}
+
+/// Guess the format of a request body from its contents alone.
+pub fn sniff_body(bytes: &[u8]) -> BodyFormat {
+    let Ok(text) = std::str::from_utf8(bytes) else {
+        return BodyFormat::Binary;
+    };
+    let trimmed = text.trim();
+
+    if trimmed.is_empty() {
+        BodyFormat::Empty
+    } else if (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(trimmed).is_ok()
+    {
+        BodyFormat::Json
+    } else if trimmed.starts_with('<') && trimmed.ends_with('>') {
+        BodyFormat::Xml
+    } else if looks_like_form(trimmed) {
+        BodyFormat::Form
+    } else if text.chars().any(|c| c.is_control() && !matches!(c, '\n' | '\r' | '\t')) {
+        BodyFormat::Binary
+    } else {
+        BodyFormat::Text
+    }
+}
+
+fn looks_like_form(text: &str) -> bool {
+    text.split('&').all(|pair| {
+        let mut parts = pair.splitn(2, '=');
+        let key = parts.next().unwrap_or("");
+        let has_value = parts.next().is_some();
+        has_value
+            && !key.is_empty()
+            && pair
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-._~%+=!*'(),;:@/?[]".contains(c))
+    })
+}
+
+/// Map a `Content-Type` header to the body format it promises, if it is specific enough.
+pub fn declared_format(content_type: &str) -> Option<BodyFormat> {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+
+    if mime == "application/json" || mime.ends_with("+json") {
+        Some(BodyFormat::Json)
+    } else if mime == "application/xml" || mime == "text/xml" || mime.ends_with("+xml") {
+        Some(BodyFormat::Xml)
+    } else if mime == "application/x-www-form-urlencoded" {
+        Some(BodyFormat::Form)
+    } else if mime.starts_with("text/") {
+        Some(BodyFormat::Text)
+    } else {
+        None
+    }
+}
+
+/// Whether the declared `Content-Type` disagrees with what the body actually contains.
+///
+/// Empty bodies and generic or missing content types never count as a mismatch.
+/// `text/*` accepts any textual body and only flags binary payloads.
+pub fn is_content_type_mismatch(content_type: Option<&str>, detected: BodyFormat) -> bool {
+    if detected == BodyFormat::Empty {
+        return false;
+    }
+    match content_type.and_then(declared_format) {
+        Some(BodyFormat::Text) => detected == BodyFormat::Binary,
+        Some(declared) => declared != detected,
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniffs_common_formats() {
+        assert_eq!(sniff_body(b""), BodyFormat::Empty);
+        assert_eq!(sniff_body(br#"{"a": 1}"#), BodyFormat::Json);
+        assert_eq!(sniff_body(b"[1, 2, 3]"), BodyFormat::Json);
+        assert_eq!(sniff_body(b"<root><a>1</a></root>"), BodyFormat::Xml);
+        assert_eq!(sniff_body(b"a=1&b=hello+world"), BodyFormat::Form);
+        assert_eq!(sniff_body(b"just some text"), BodyFormat::Text);
+        assert_eq!(sniff_body(&[0xff, 0xfe, 0x00]), BodyFormat::Binary);
+        assert_eq!(sniff_body(b"\x00\x01\x02"), BodyFormat::Binary);
+    }
+
+    #[test]
+    fn broken_json_is_not_json() {
+        assert_eq!(sniff_body(b"{not json"), BodyFormat::Text);
+    }
+
+    #[test]
+    fn detects_mismatches() {
+        assert!(is_content_type_mismatch(Some("application/json"), BodyFormat::Form));
+        assert!(is_content_type_mismatch(Some("application/x-www-form-urlencoded"), BodyFormat::Json));
+        assert!(is_content_type_mismatch(Some("text/plain"), BodyFormat::Binary));
+        assert!(!is_content_type_mismatch(Some("application/json; charset=utf-8"), BodyFormat::Json));
+        assert!(!is_content_type_mismatch(Some("application/vnd.api+json"), BodyFormat::Json));
+        assert!(!is_content_type_mismatch(Some("text/plain"), BodyFormat::Json));
+        assert!(!is_content_type_mismatch(Some("application/octet-stream"), BodyFormat::Json));
+        assert!(!is_content_type_mismatch(None, BodyFormat::Json));
+        assert!(!is_content_type_mismatch(Some("application/json"), BodyFormat::Empty));
+    }
+}
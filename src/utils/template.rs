@@ -0,0 +1,52 @@
+use handlebars::{no_escape, Handlebars, RenderError, TemplateError};
+use serde_json::Value;
+
+/// Handlebars registry for response templates. Output is sent verbatim, so HTML
+/// escaping is turned off (a JSON body must not come back as `&quot;`).
+fn registry() -> Handlebars<'static> {
+    let mut handlebars = Handlebars::new();
+    handlebars.register_escape_fn(no_escape);
+    handlebars
+}
+
+/// Check that a template compiles without rendering it.
+pub fn validate_template(template: &str) -> Result<(), TemplateError> {
+    handlebars::Template::compile(template).map(|_| ())
+}
+
+/// Render a template against `context`. Missing variables render as empty strings.
+pub fn render_template(template: &str, context: &Value) -> Result<String, RenderError> {
+    registry().render_template(template, context)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn renders_request_variables() {
+        let context = json!({
+            "method": "POST",
+            "headers": { "x-request-id": "abc-123" },
+            "body": { "json": { "order_id": 42 } },
+        });
+        let rendered = render_template(
+            r#"{"method": "{{method}}", "id": "{{headers.x-request-id}}", "order": {{body.json.order_id}}}"#,
+            &context,
+        )
+        .unwrap();
+        assert_eq!(rendered, r#"{"method": "POST", "id": "abc-123", "order": 42}"#);
+    }
+
+    #[test]
+    fn missing_variables_render_empty() {
+        assert_eq!(render_template("[{{headers.nope}}]", &json!({})).unwrap(), "[]");
+    }
+
+    #[test]
+    fn rejects_broken_templates() {
+        assert!(validate_template("{{#if method}}unterminated").is_err());
+        assert!(validate_template("plain text").is_ok());
+    }
+}
@@ -0,0 +1,135 @@
+//! Per-bin callbacks: events about one bin, signed with the callback's own secret and
+//! delivered with the same retries as the configured `[webhooks]` endpoints.
+
+use std::sync::Arc;
+use tracing::{error, warn};
+
+use super::{BinEvent, WebhookDispatcher};
+use crate::config::WebhookEndpointConfig;
+use crate::models::{Callback, LoggedRequest, RequestNotification};
+use crate::outbound::OutboundGuard;
+use crate::replay::parse_target;
+use crate::storage::Storage;
+
+/// Events a callback can subscribe to
+pub const CALLBACK_EVENTS: [BinEvent; 3] = [BinEvent::RequestCaptured, BinEvent::Expiring, BinEvent::Deleted];
+
+pub fn is_callback_event(name: &str) -> bool {
+    CALLBACK_EVENTS.iter().any(|event| event.as_str() == name)
+}
+
+pub struct CallbackDispatcher {
+    storage: Arc<dyn Storage>,
+    webhooks: Arc<WebhookDispatcher>,
+    guard: Arc<OutboundGuard>,
+}
+
+impl CallbackDispatcher {
+    pub fn new(storage: Arc<dyn Storage>, webhooks: Arc<WebhookDispatcher>, guard: Arc<OutboundGuard>) -> Self {
+        CallbackDispatcher { storage, webhooks, guard }
+    }
+
+    /// Look up the bin's callbacks and send `event` to those that want it. Never blocks the caller.
+    pub fn emit(&self, event: BinEvent, bin_id: &str, data: serde_json::Value) {
+        let storage = self.storage.clone();
+        let webhooks = self.webhooks.clone();
+        let guard = self.guard.clone();
+        let bin_id = bin_id.to_string();
+        tokio::spawn(async move {
+            match storage.list_callbacks(&bin_id).await {
+                Ok(callbacks) => {
+                    let callbacks = permitted(&guard, &bin_id, callbacks).await;
+                    webhooks.send(targets(&callbacks, event), event, &bin_id, data);
+                }
+                Err(err) => error!(%bin_id, %err, "Failed to load bin callbacks"),
+            }
+        });
+    }
+
+    /// `request.captured` to the bin's `callbacks`, with the request as WebSocket clients get it
+    pub fn request_captured(&self, callbacks: &[Callback], bin_id: &str, request: &LoggedRequest) {
+        if callbacks.is_empty() {
+            return;
+        }
+        let data = serde_json::to_value(RequestNotification::from(request.clone())).unwrap_or_default();
+        self.send_to(callbacks, BinEvent::RequestCaptured, bin_id, data);
+    }
+
+    /// The bin's callbacks, for a caller about to delete them along with the bin; empty
+    /// when they can't be loaded
+    pub async fn load(&self, bin_id: &str) -> Vec<Callback> {
+        self.storage.list_callbacks(bin_id).await.unwrap_or_else(|err| {
+            error!(%bin_id, %err, "Failed to load bin callbacks");
+            Vec::new()
+        })
+    }
+
+    /// Send `event` to callbacks fetched with [`CallbackDispatcher::load`] or taken from the
+    /// bin's settings
+    pub fn send_to(&self, callbacks: &[Callback], event: BinEvent, bin_id: &str, data: serde_json::Value) {
+        let webhooks = self.webhooks.clone();
+        let guard = self.guard.clone();
+        let callbacks = callbacks.to_vec();
+        let bin_id = bin_id.to_string();
+        tokio::spawn(async move {
+            let callbacks = permitted(&guard, &bin_id, callbacks).await;
+            webhooks.send(targets(&callbacks, event), event, &bin_id, data);
+        });
+    }
+}
+
+/// `callbacks` without those `guard` refuses to send to; their URLs were checked when
+/// they were added, but the addresses behind a host name can change since
+async fn permitted(guard: &OutboundGuard, bin_id: &str, callbacks: Vec<Callback>) -> Vec<Callback> {
+    let mut permitted = Vec::with_capacity(callbacks.len());
+    for callback in callbacks {
+        let checked = match parse_target(&callback.url) {
+            Ok(url) => guard.check(&url).await,
+            Err(err) => Err(err.to_string()),
+        };
+        match checked {
+            Ok(()) => permitted.push(callback),
+            Err(err) => warn!(%bin_id, callback_id = %callback.callback_id, %err, "Refusing to send to callback"),
+        }
+    }
+    permitted
+}
+
+fn targets(callbacks: &[Callback], event: BinEvent) -> Vec<WebhookEndpointConfig> {
+    callbacks
+        .iter()
+        .filter(|callback| callback.events.is_empty() || callback.events.iter().any(|name| name == event.as_str()))
+        .map(|callback| WebhookEndpointConfig {
+            url: callback.url.clone(),
+            secret: Some(callback.secret.clone()),
+            events: callback.events.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn callback(events: &[&str]) -> Callback {
+        Callback {
+            callback_id: "cb".to_string(),
+            url: "http://localhost/hook".to_string(),
+            events: events.iter().map(|event| event.to_string()).collect(),
+            secret: "s3cret".to_string(),
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn filters_callbacks_by_event() {
+        let callbacks = [callback(&[]), callback(&["bin.deleted"])];
+        assert_eq!(targets(&callbacks, BinEvent::RequestCaptured).len(), 1);
+        let deleted = targets(&callbacks, BinEvent::Deleted);
+        assert_eq!(deleted.len(), 2);
+        assert_eq!(deleted[0].secret.as_deref(), Some("s3cret"));
+
+        assert!(is_callback_event("request.captured"));
+        assert!(!is_callback_event("bin.created"));
+    }
+}
@@ -0,0 +1,255 @@
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use std::time::Duration;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::config::{WebhookEndpointConfig, WebhooksConfig};
+
+pub mod callbacks;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Bin lifecycle events delivered to configured endpoints and per-bin callbacks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinEvent {
+    Created,
+    Expiring,
+    Expired,
+    LimitReached,
+    /// Only sent to a bin's own callbacks
+    RequestCaptured,
+    /// Only sent to a bin's own callbacks, whether the bin was deleted or expired
+    Deleted,
+}
+
+impl BinEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BinEvent::Created => "bin.created",
+            BinEvent::Expiring => "bin.expiring",
+            BinEvent::Expired => "bin.expired",
+            BinEvent::LimitReached => "bin.limit_reached",
+            BinEvent::RequestCaptured => "request.captured",
+            BinEvent::Deleted => "bin.deleted",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct EventPayload<'a> {
+    pub event: &'a str,
+    pub bin_id: &'a str,
+    pub timestamp: String,
+    pub data: serde_json::Value,
+}
+
+/// Compute the hex encoded HMAC-SHA256 signature of `"{timestamp}.{body}"`.
+pub fn sign_payload(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn endpoint_accepts(endpoint: &WebhookEndpointConfig, event: BinEvent) -> bool {
+    endpoint.events.is_empty() || endpoint.events.iter().any(|e| e == event.as_str())
+}
+
+/// Sends signed lifecycle events to every configured endpoint in the background.
+pub struct WebhookDispatcher {
+    client: reqwest::Client,
+    endpoints: Vec<WebhookEndpointConfig>,
+    max_attempts: u32,
+    retry_backoff: Duration,
+}
+
+impl WebhookDispatcher {
+    pub fn new(config: &WebhooksConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_seconds))
+            .build()
+            .expect("Failed to build webhook HTTP client");
+
+        Self {
+            client,
+            endpoints: config.endpoints.clone(),
+            max_attempts: config.max_attempts.max(1),
+            retry_backoff: Duration::from_millis(config.retry_backoff_ms),
+        }
+    }
+
+    /// Queue delivery of `event` to all interested endpoints. Never blocks the caller.
+    pub fn emit(&self, event: BinEvent, bin_id: &str, data: serde_json::Value) {
+        let targets = self
+            .endpoints
+            .iter()
+            .filter(|endpoint| endpoint_accepts(endpoint, event))
+            .cloned()
+            .collect();
+        self.send(targets, event, bin_id, data);
+    }
+
+    /// Deliver `event` to `targets` with the configured retries, whatever events they asked for
+    pub(crate) fn send(&self, targets: Vec<WebhookEndpointConfig>, event: BinEvent, bin_id: &str, data: serde_json::Value) {
+        if targets.is_empty() {
+            return;
+        }
+
+        let body = serde_json::to_string(&EventPayload {
+            event: event.as_str(),
+            bin_id,
+            timestamp: Utc::now().to_rfc3339(),
+            data,
+        })
+        .unwrap_or_else(|_| "{}".to_string());
+
+        for endpoint in targets {
+            let client = self.client.clone();
+            let body = body.clone();
+            let max_attempts = self.max_attempts;
+            let retry_backoff = self.retry_backoff;
+            tokio::spawn(async move {
+                deliver(client, endpoint, event, body, max_attempts, retry_backoff).await;
+            });
+        }
+    }
+}
+
+async fn deliver(
+    client: reqwest::Client,
+    endpoint: WebhookEndpointConfig,
+    event: BinEvent,
+    body: String,
+    max_attempts: u32,
+    retry_backoff: Duration,
+) {
+    let delivery_id = Uuid::new_v4();
+    let mut backoff = retry_backoff;
+
+    for attempt in 1..=max_attempts {
+        let timestamp = Utc::now().timestamp();
+        let mut request = client
+            .post(&endpoint.url)
+            .header("content-type", "application/json")
+            .header("x-rustbin-event", event.as_str())
+            .header("x-rustbin-delivery", delivery_id.to_string())
+            .header("x-rustbin-timestamp", timestamp.to_string());
+        if let Some(secret) = &endpoint.secret {
+            request = request.header(
+                "x-rustbin-signature",
+                format!("sha256={}", sign_payload(secret, timestamp, &body)),
+            );
+        }
+
+        match request.body(body.clone()).send().await {
+            Ok(response) if response.status().is_success() => {
+                info!(url = %endpoint.url, event = event.as_str(), attempt, "Webhook delivered");
+                return;
+            }
+            Ok(response) => {
+                warn!(url = %endpoint.url, event = event.as_str(), attempt, status = %response.status(), "Webhook rejected");
+            }
+            Err(err) => {
+                warn!(url = %endpoint.url, event = event.as_str(), attempt, %err, "Webhook delivery failed");
+            }
+        }
+
+        if attempt < max_attempts {
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+
+    warn!(url = %endpoint.url, event = event.as_str(), %delivery_id, "Giving up on webhook delivery");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::State, http::{HeaderMap, StatusCode}, routing::post, Router};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_sign_payload_is_deterministic() {
+        let a = sign_payload("secret", 1700000000, r#"{"event":"bin.created"}"#);
+        let b = sign_payload("secret", 1700000000, r#"{"event":"bin.created"}"#);
+        let c = sign_payload("other", 1700000000, r#"{"event":"bin.created"}"#);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(a.len(), 64);
+    }
+
+    #[test]
+    fn test_endpoint_event_filter() {
+        let mut endpoint = WebhookEndpointConfig {
+            url: "http://localhost".to_string(),
+            secret: None,
+            events: Vec::new(),
+        };
+        assert!(endpoint_accepts(&endpoint, BinEvent::Expired));
+
+        endpoint.events = vec!["bin.created".to_string()];
+        assert!(endpoint_accepts(&endpoint, BinEvent::Created));
+        assert!(!endpoint_accepts(&endpoint, BinEvent::Expired));
+    }
+
+    #[tokio::test]
+    async fn test_delivery_is_signed_and_retried() {
+        type Received = Arc<Mutex<Vec<(HeaderMap, String)>>>;
+        let received: Received = Arc::new(Mutex::new(Vec::new()));
+
+        // Fail the first attempt so the dispatcher has to retry
+        async fn receive(State(received): State<Received>, headers: HeaderMap, body: String) -> StatusCode {
+            let mut received = received.lock().unwrap();
+            received.push((headers, body));
+            if received.len() == 1 {
+                StatusCode::INTERNAL_SERVER_ERROR
+            } else {
+                StatusCode::OK
+            }
+        }
+
+        let app = Router::new()
+            .route("/hook", post(receive))
+            .with_state(received.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let dispatcher = WebhookDispatcher::new(&WebhooksConfig {
+            endpoints: vec![WebhookEndpointConfig {
+                url: format!("http://{}/hook", addr),
+                secret: Some("topsecret".to_string()),
+                events: Vec::new(),
+            }],
+            max_attempts: 3,
+            retry_backoff_ms: 10,
+            timeout_seconds: 5,
+        });
+        dispatcher.emit(BinEvent::Created, "bin-1", serde_json::json!({}));
+
+        for _ in 0..100 {
+            if received.lock().unwrap().len() >= 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        let (headers, body) = &received[1];
+        let payload: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(payload["event"], "bin.created");
+        assert_eq!(payload["bin_id"], "bin-1");
+
+        let timestamp: i64 = headers["x-rustbin-timestamp"].to_str().unwrap().parse().unwrap();
+        let expected = format!("sha256={}", sign_payload("topsecret", timestamp, body));
+        assert_eq!(headers["x-rustbin-signature"], expected.as_str());
+        assert_eq!(headers["x-rustbin-event"], "bin.created");
+    }
+}
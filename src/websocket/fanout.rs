@@ -0,0 +1,249 @@
+//! Relaying WebSocket messages between instances through Redis pub/sub, so clients see
+//! captures handled by any replica (`[websocket] fanout = "redis"`).
+
+use dashmap::DashMap;
+use futures::StreamExt;
+use redis::{AsyncCommands, RedisError};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::{interval, sleep, timeout, Duration, MissedTickBehavior};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::config::RedisConfig;
+use crate::redis::{timed_out, RedisClient};
+
+/// Wait this long before reconnecting after the subscription or a publish failed
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Ping the subscription this often, so a connection that died without closing is noticed
+const PING_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Messages waiting to be published; newer ones are dropped while Redis is slow
+const PUBLISH_QUEUE_SIZE: usize = 10_000;
+
+#[derive(Serialize, Deserialize)]
+struct Envelope<'a> {
+    /// Instances skip their own messages, which they delivered locally already
+    origin: Cow<'a, str>,
+    bin_id: Cow<'a, str>,
+    message: Cow<'a, str>,
+}
+
+pub struct Fanout {
+    outgoing: mpsc::Sender<(String, String)>,
+}
+
+impl Fanout {
+    /// Start publishing and subscribing on `{key_prefix}ws`. Must run inside a Tokio runtime.
+    pub fn start(config: &RedisConfig, bin_channels: Arc<DashMap<String, broadcast::Sender<String>>>) -> Result<Self, String> {
+        let client = Arc::new(RedisClient::new(config)?);
+        let channel = format!("{}ws", config.key_prefix);
+        let origin = Uuid::new_v4().to_string();
+        let (outgoing, queued) = mpsc::channel(PUBLISH_QUEUE_SIZE);
+
+        tokio::spawn(publish(client.clone(), channel.clone(), origin.clone(), queued));
+        tokio::spawn(subscribe(client, channel, origin, bin_channels));
+        Ok(Fanout { outgoing })
+    }
+
+    /// Hand `message` to the other instances; local clients get it from the caller
+    pub fn publish(&self, bin_id: &str, message: &str) {
+        if self.outgoing.try_send((bin_id.to_string(), message.to_string())).is_err() {
+            warn!(%bin_id, "WebSocket fanout is falling behind, dropping a message for other instances");
+        }
+    }
+}
+
+async fn publish(client: Arc<RedisClient>, channel: String, origin: String, mut queued: mpsc::Receiver<(String, String)>) {
+    while let Some((bin_id, message)) = queued.recv().await {
+        let envelope = Envelope { origin: origin.as_str().into(), bin_id: bin_id.as_str().into(), message: message.as_str().into() };
+        let payload = serde_json::to_string(&envelope).unwrap_or_default();
+        let published = match client.connection().await {
+            Ok(mut connection) => connection.publish::<_, _, i64>(&channel, payload).await,
+            Err(err) => Err(err),
+        };
+        if let Err(err) = published {
+            warn!(%bin_id, %err, "Failed to publish WebSocket message to other instances");
+        }
+    }
+}
+
+async fn subscribe(
+    client: Arc<RedisClient>,
+    channel: String,
+    origin: String,
+    bin_channels: Arc<DashMap<String, broadcast::Sender<String>>>,
+) {
+    loop {
+        let mut pubsub = match client.pubsub().await {
+            Ok(pubsub) => pubsub,
+            Err(err) => {
+                warn!(%err, "Cannot reach Redis for WebSocket fanout, retrying");
+                sleep(RECONNECT_DELAY).await;
+                continue;
+            }
+        };
+        if let Err(err) = timeout(client.timeout(), pubsub.subscribe(&channel)).await.unwrap_or_else(|_| Err(timed_out())) {
+            warn!(%err, "Failed to subscribe to WebSocket fanout, retrying");
+            sleep(RECONNECT_DELAY).await;
+            continue;
+        }
+        info!(%channel, "Receiving WebSocket messages from other instances");
+
+        // Messages published while reconnecting are lost; clients catch up from storage
+        let err = receive(pubsub, client.timeout(), &origin, &bin_channels).await;
+        warn!(%err, "Lost the WebSocket fanout subscription, reconnecting");
+        sleep(RECONNECT_DELAY).await;
+    }
+}
+
+/// Deliver the subscription's messages until its connection closes or stops answering pings
+async fn receive(
+    pubsub: redis::aio::PubSub,
+    ping_timeout: Duration,
+    origin: &str,
+    bin_channels: &DashMap<String, broadcast::Sender<String>>,
+) -> RedisError {
+    let (mut sink, mut messages) = pubsub.split();
+    let mut pings = interval(PING_INTERVAL);
+    pings.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    pings.tick().await;
+    loop {
+        tokio::select! {
+            message = messages.next() => match message {
+                Some(message) => deliver(message.get_payload_bytes(), origin, bin_channels),
+                None => return RedisError::from(std::io::Error::from(std::io::ErrorKind::UnexpectedEof)),
+            },
+            _ = pings.tick() => {
+                if let Err(err) = timeout(ping_timeout, sink.ping::<redis::Value>()).await.unwrap_or_else(|_| Err(timed_out())) {
+                    return err;
+                }
+            }
+        }
+    }
+}
+
+/// Pass a message from another instance to the bin's local clients, if it has any
+fn deliver(payload: &[u8], origin: &str, bin_channels: &DashMap<String, broadcast::Sender<String>>) {
+    let Ok(envelope) = serde_json::from_slice::<Envelope>(payload) else {
+        warn!("Ignoring malformed WebSocket fanout message");
+        return;
+    };
+    if envelope.origin == origin {
+        return;
+    }
+    if let Some(sender) = bin_channels.get(envelope.bin_id.as_ref()) {
+        let _ = sender.send(envelope.message.into_owned());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::tcp::OwnedWriteHalf;
+    use tokio::net::TcpListener;
+
+    type Writer = Arc<tokio::sync::Mutex<OwnedWriteHalf>>;
+    type Subscribers = Arc<tokio::sync::Mutex<Vec<Writer>>>;
+
+    /// Just enough of a Redis server for pub/sub on one channel
+    async fn fake_broker() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let subscribers = Subscribers::default();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let (reader, writer) = stream.into_split();
+                tokio::spawn(serve(BufReader::new(reader), Arc::new(writer.into()), subscribers.clone()));
+            }
+        });
+        address
+    }
+
+    async fn serve(mut reader: BufReader<tokio::net::tcp::OwnedReadHalf>, writer: Writer, subscribers: Subscribers) {
+        let mut line = String::new();
+        while reader.read_line(&mut line).await.unwrap_or(0) > 0 {
+            let count: usize = line.trim()[1..].parse().unwrap();
+            let mut args = Vec::new();
+            for _ in 0..count {
+                line.clear();
+                reader.read_line(&mut line).await.unwrap();
+                let mut arg = vec![0; line.trim()[1..].parse::<usize>().unwrap() + 2];
+                reader.read_exact(&mut arg).await.unwrap();
+                arg.truncate(arg.len() - 2);
+                args.push(String::from_utf8(arg).unwrap());
+            }
+            line.clear();
+            match args[0].as_str() {
+                "SUBSCRIBE" => {
+                    let reply = format!("*3\r\n$9\r\nsubscribe\r\n${}\r\n{}\r\n:1\r\n", args[1].len(), args[1]);
+                    writer.lock().await.write_all(reply.as_bytes()).await.unwrap();
+                    subscribers.lock().await.push(writer.clone());
+                }
+                "PUBLISH" => {
+                    let message = format!(
+                        "*3\r\n$7\r\nmessage\r\n${}\r\n{}\r\n${}\r\n{}\r\n",
+                        args[1].len(), args[1], args[2].len(), args[2]
+                    );
+                    let subscribers = subscribers.lock().await;
+                    for subscriber in subscribers.iter() {
+                        subscriber.lock().await.write_all(message.as_bytes()).await.unwrap();
+                    }
+                    let reply = format!(":{}\r\n", subscribers.len());
+                    writer.lock().await.write_all(reply.as_bytes()).await.unwrap();
+                }
+                // Connection setup, e.g. `CLIENT SETINFO`
+                _ => writer.lock().await.write_all(b"+OK\r\n").await.unwrap(),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn relays_messages_between_instances() {
+        let config = RedisConfig { url: format!("redis://{}", fake_broker().await), key_prefix: "test:".to_string(), timeout_ms: 1000 };
+        let channels = || Arc::new(DashMap::<String, broadcast::Sender<String>>::new());
+        let (here, there) = (channels(), channels());
+        let (sender, mut received_there) = broadcast::channel(4);
+        there.insert("bin".to_string(), sender);
+        let (sender, mut received_here) = broadcast::channel(4);
+        here.insert("bin".to_string(), sender);
+
+        let fanout_here = Fanout::start(&config, here).unwrap();
+        let _fanout_there = Fanout::start(&config, there).unwrap();
+        // Publish until both subscriptions are up; earlier messages are lost
+        let mut received = None;
+        for _ in 0..50 {
+            fanout_here.publish("bin", r#"{"type":"ping"}"#);
+            if let Ok(Ok(message)) = tokio::time::timeout(Duration::from_millis(100), received_there.recv()).await {
+                received = Some(message);
+                break;
+            }
+        }
+        assert_eq!(received.as_deref(), Some(r#"{"type":"ping"}"#));
+        // Our own messages don't come back
+        assert!(received_here.try_recv().is_err());
+    }
+
+    #[test]
+    fn delivers_messages_from_other_instances_only() {
+        let bin_channels = DashMap::new();
+        let (sender, mut receiver) = broadcast::channel(4);
+        bin_channels.insert("bin".to_string(), sender);
+
+        let envelope = |origin: &'static str, bin_id: &'static str| {
+            serde_json::to_vec(&Envelope { origin: origin.into(), bin_id: bin_id.into(), message: r#"{"type":"ping"}"#.into() }).unwrap()
+        };
+        deliver(&envelope("us", "bin"), "us", &bin_channels);
+        deliver(&envelope("them", "other-bin"), "us", &bin_channels);
+        deliver(b"not json", "us", &bin_channels);
+        assert!(receiver.try_recv().is_err());
+
+        deliver(&envelope("them", "bin"), "us", &bin_channels);
+        assert_eq!(receiver.try_recv().unwrap(), r#"{"type":"ping"}"#);
+    }
+}
@@ -0,0 +1,98 @@
+//! Per-connection filters clients set by sending a subscription message.
+
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// What a client wants to hear about, e.g. `{"methods":["POST"],"path_prefix":"/stripe"}`.
+/// Omitted or empty fields match everything, so `{}` clears the filter.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Subscription {
+    /// HTTP methods to forward, compared case-insensitively
+    #[serde(default)]
+    pub methods: Vec<String>,
+    /// Only requests whose path below the bin starts with this
+    #[serde(default)]
+    pub path_prefix: Option<String>,
+}
+
+impl Subscription {
+    /// Whether `notification` should be sent. Only requests are filtered; lifecycle
+    /// events always go through.
+    pub fn matches(&self, notification: &Notification) -> bool {
+        notification.request().is_none_or(|request| self.matches_request(request))
+    }
+
+    fn matches_request(&self, request: &NotifiedRequest) -> bool {
+        let method_ok = self.methods.is_empty()
+            || request
+                .method
+                .as_deref()
+                .is_some_and(|method| self.methods.iter().any(|wanted| wanted.eq_ignore_ascii_case(method)));
+        let path_ok = self.path_prefix.as_deref().is_none_or(|prefix| {
+            request.path.as_deref().unwrap_or("/").starts_with(prefix)
+        });
+        method_ok && path_ok
+    }
+}
+
+/// The parts of a broadcast message that filtering and deduplication look at
+#[derive(Debug, Default, Deserialize)]
+pub struct Notification {
+    #[serde(rename = "type", default)]
+    pub kind: String,
+    #[serde(default)]
+    data: NotifiedRequest,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct NotifiedRequest {
+    pub request_id: Option<Uuid>,
+    pub method: Option<String>,
+    pub path: Option<String>,
+}
+
+impl Notification {
+    /// Read a broadcast message; anything unparseable yields an empty notification
+    pub fn parse(msg: &str) -> Self {
+        serde_json::from_str(msg).unwrap_or_default()
+    }
+
+    /// The captured request, for `request` messages
+    pub fn request(&self) -> Option<&NotifiedRequest> {
+        (self.kind == "request").then_some(&self.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, path: &str) -> Notification {
+        Notification {
+            kind: "request".to_string(),
+            data: NotifiedRequest { request_id: None, method: Some(method.to_string()), path: Some(path.to_string()) },
+        }
+    }
+
+    #[test]
+    fn empty_subscription_matches_everything() {
+        let subscription: Subscription = serde_json::from_str("{}").unwrap();
+        assert!(subscription.matches(&request("GET", "/")));
+        assert!(subscription.matches(&Notification::parse("not json")));
+    }
+
+    #[test]
+    fn filters_on_method_and_path_prefix() {
+        let subscription: Subscription = serde_json::from_str(r#"{"methods":["post"],"path_prefix":"/stripe"}"#).unwrap();
+        assert!(subscription.matches(&request("POST", "/stripe/events")));
+        assert!(!subscription.matches(&request("GET", "/stripe/events")));
+        assert!(!subscription.matches(&request("POST", "/github")));
+        assert!(subscription.matches(&Notification::parse(r#"{"type":"requests_cleared","data":{"count":3}}"#)));
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        assert!(serde_json::from_str::<Subscription>(r#"{"method":"POST"}"#).is_err());
+    }
+}
@@ -1,34 +1,200 @@
+pub mod fanout;
+pub mod filter;
+
 use axum::{
-    extract::{ws::{WebSocketUpgrade, Message, WebSocket}, Path, State},
+    extract::{ws::{WebSocketUpgrade, Message, WebSocket}, Path, Query, State},
     response::IntoResponse,
 };
 
+use dashmap::DashMap;
+use serde::Deserialize;
+use futures::stream::SplitSink;
+use futures::{SinkExt, StreamExt};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::broadcast;
+use tokio::time::{interval_at, sleep_until, Instant};
+use tracing::{info, warn};
+use uuid::Uuid;
+use crate::models::{WsEvent, WsMessage};
 use crate::state::AppState;
+use filter::{Notification, Subscription};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct WsQuery {
+    /// Stored requests to send on connect, up to `[websocket] history`; `0` skips them
+    pub history: Option<i64>,
+}
 
 pub async fn ws_handler(
     Path(bin_id): Path<String>,
     State(state): State<AppState>,
+    Query(query): Query<WsQuery>,
     ws: WebSocketUpgrade,
 ) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_socket(socket, bin_id, state))
+    let history = query.history.map_or(state.websocket.history, |history| history.clamp(0, state.websocket.history));
+    ws.on_upgrade(move |socket| handle_socket(socket, bin_id, state, history))
 }
 
-async fn handle_socket(mut socket: WebSocket, bin_id: String, state: AppState) {
-    let sender = state
+async fn handle_socket(socket: WebSocket, bin_id: String, state: AppState, history: i64) {
+    // Subscribe while holding the entry so a client disconnecting at the same time
+    // can't remove the channel between creating and joining it
+    let receiver = state
         .bin_channels
         .entry(bin_id.clone())
         .or_insert_with(|| {
             let (tx, _) = broadcast::channel(1000);
             tx
         })
-        .clone();
+        .subscribe();
+
+    relay(socket, receiver, &bin_id, &state, history).await;
+
+    // The receiver is gone by now; drop the channel once nobody else listens either
+    state.bin_channels.remove_if(&bin_id, |_, sender| sender.receiver_count() == 0);
+}
+
+/// Send history and then live messages to the client until either side goes away
+async fn relay(socket: WebSocket, mut receiver: broadcast::Receiver<String>, bin_id: &str, state: &AppState, history: i64) {
+    // Subscribed before loading the history so nothing captured in between is missed
+    let (mut outgoing, mut incoming) = socket.split();
+
+    let mut replayed = match send_history(&mut outgoing, bin_id, state, history).await {
+        Some(replayed) => replayed,
+        None => return,
+    };
+
+    // Ping on a fixed schedule and give up on clients that don't answer in time, so
+    // half-open connections don't hold on to the bin's channel
+    let pinging = state.websocket.ping_interval_ms > 0;
+    let ping_interval = Duration::from_millis(state.websocket.ping_interval_ms.max(1));
+    let pong_timeout = Duration::from_millis(state.websocket.pong_timeout_ms);
+    let mut pings = interval_at(Instant::now() + ping_interval, ping_interval);
+    let mut pong_deadline: Option<Instant> = None;
+    let mut subscription = Subscription::default();
+
+    loop {
+        tokio::select! {
+            msg = receiver.recv() => {
+                let Ok(msg) = msg else { break };
+                let notification = Notification::parse(&msg);
+                // Requests captured while the history was loading arrive twice
+                let replayed_id = notification.request().and_then(|request| request.request_id);
+                if replayed_id.is_some_and(|id| replayed.remove(&id)) || !subscription.matches(&notification) {
+                    continue;
+                }
+                if outgoing.send(Message::Text(msg)).await.is_err() {
+                    break;
+                }
+                if notification.kind == "bin_deleted" {
+                    let _ = outgoing.send(Message::Close(None)).await;
+                    break;
+                }
+            }
+            frame = incoming.next() => match frame {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(frame)) => {
+                    // Any frame shows the client is alive, a pong in particular
+                    pong_deadline = None;
+                    if let Message::Text(text) = frame {
+                        match serde_json::from_str::<Subscription>(&text) {
+                            Ok(update) => subscription = update,
+                            Err(err) => {
+                                let error = WsMessage::new(WsEvent::Error { message: format!("Invalid subscription: {}", err) });
+                                if outgoing.send(Message::Text(error.to_json())).await.is_err() {
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+            _ = pings.tick(), if pinging => {
+                if outgoing.send(Message::Ping(Vec::new())).await.is_err() {
+                    break;
+                }
+                pong_deadline.get_or_insert(Instant::now() + pong_timeout);
+            }
+            _ = sleep_until(pong_deadline.unwrap_or_else(Instant::now)), if pong_deadline.is_some() => {
+                info!(%bin_id, "Closing WebSocket that stopped answering pings");
+                break;
+            }
+        }
+    }
+}
+
+/// A subscription to a bin's channel, dropping the channel with the last subscriber
+/// like WebSocket clients do
+pub struct Listener {
+    receiver: Option<broadcast::Receiver<String>>,
+    bin_id: String,
+    bin_channels: Arc<DashMap<String, broadcast::Sender<String>>>,
+}
 
-    let mut receiver = sender.subscribe();
+impl Listener {
+    pub fn subscribe(state: &AppState, bin_id: &str) -> Self {
+        let receiver = state
+            .bin_channels
+            .entry(bin_id.to_string())
+            .or_insert_with(|| broadcast::channel(1000).0)
+            .subscribe();
+        Listener { receiver: Some(receiver), bin_id: bin_id.to_string(), bin_channels: Arc::clone(&state.bin_channels) }
+    }
+
+    pub async fn recv(&mut self) -> Result<String, broadcast::error::RecvError> {
+        match self.receiver.as_mut() {
+            Some(receiver) => receiver.recv().await,
+            None => Err(broadcast::error::RecvError::Closed),
+        }
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        self.receiver = None;
+        self.bin_channels.remove_if(&self.bin_id, |_, sender| sender.receiver_count() == 0);
+    }
+}
 
-    while let Ok(msg) = receiver.recv().await {
-        if socket.send(Message::Text(msg)).await.is_err() {
-            break;
+/// Send `event` to everyone watching the bin, on other instances too with a fanout
+pub fn broadcast(state: &AppState, bin_id: &str, event: WsEvent) {
+    let message = WsMessage::new(event).to_json();
+    if let Some(fanout) = &state.fanout {
+        fanout.publish(bin_id, &message);
+    }
+    if let Some(sender) = state.bin_channels.get(bin_id) {
+        let _ = sender.send(message);
+    }
+}
+
+/// Send the bin's `history` most recent requests, oldest first. Returns their ids, or
+/// `None` when the client went away.
+async fn send_history(
+    outgoing: &mut SplitSink<WebSocket, Message>,
+    bin_id: &str,
+    state: &AppState,
+    history: i64,
+) -> Option<HashSet<Uuid>> {
+    let mut replayed = HashSet::new();
+    if history <= 0 {
+        return Some(replayed);
+    }
+
+    let requests = match state.storage.recent_requests(bin_id, history).await {
+        Ok(requests) => requests,
+        Err(err) => {
+            warn!(%bin_id, %err, "Failed to load WebSocket history");
+            return Some(replayed);
+        }
+    };
+    for request in requests {
+        let request_id = request.request_id;
+        let msg = WsMessage::new(WsEvent::Request(Box::new(request.into())));
+        if outgoing.send(Message::Text(msg.to_json())).await.is_err() {
+            return None;
         }
+        replayed.insert(request_id);
     }
+    Some(replayed)
 }
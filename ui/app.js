@@ -0,0 +1,345 @@
+// rustbin web UI: a bin picker at `/` and a live request list at `/ui/bin/:id`.
+// Talks to the same REST and WebSocket endpoints as any other client.
+'use strict';
+
+const HISTORY = 100;
+const RECENT_BINS = 10;
+// `[server] base_path`, filled in by the server; empty at the root
+const BASE = document.documentElement.dataset.base;
+
+const $ = (id) => document.getElementById(id);
+
+function show(element, text) {
+  element.textContent = text;
+  element.hidden = !text;
+}
+
+// Errors are problem+json documents; fall back to the status for anything else
+async function problem(response) {
+  try {
+    return (await response.json()).detail;
+  } catch {
+    return `${response.status} ${response.statusText}`;
+  }
+}
+
+function recentBins() {
+  try {
+    return JSON.parse(localStorage.getItem('rustbin.recent') || '[]');
+  } catch {
+    return [];
+  }
+}
+
+function remember(binId) {
+  const bins = [binId, ...recentBins().filter((id) => id !== binId)].slice(0, RECENT_BINS);
+  localStorage.setItem('rustbin.recent', JSON.stringify(bins));
+}
+
+function tokenFor(binId) {
+  return new URLSearchParams(location.search).get('token') || localStorage.getItem(`rustbin.token.${binId}`);
+}
+
+// ---- Home ----
+
+function home() {
+  $('home').hidden = false;
+  $('api-key').value = localStorage.getItem('rustbin.api-key') || '';
+
+  const bins = recentBins();
+  $('recent').hidden = bins.length === 0;
+  for (const binId of bins) {
+    const link = document.createElement('a');
+    link.href = `${BASE}/ui/bin/${encodeURIComponent(binId)}`;
+    link.textContent = binId;
+    const item = document.createElement('li');
+    item.append(link);
+    $('recent-list').append(item);
+  }
+
+  $('create-form').addEventListener('submit', async (event) => {
+    event.preventDefault();
+    const apiKey = $('api-key').value.trim();
+    const headers = { 'Content-Type': 'application/json' };
+    if (apiKey) headers['X-Api-Key'] = apiKey;
+    // Keep every bin made from this browser under one creator, for GET /my/bins
+    const creatorToken = localStorage.getItem('rustbin.creator-token');
+    if (creatorToken) headers['X-Creator-Token'] = creatorToken;
+    const response = await fetch(`${BASE}/create`, {
+      method: 'POST',
+      headers,
+      body: JSON.stringify({ private: $('private').checked }),
+    });
+    if (!response.ok) {
+      show($('home-error'), `Could not create a bin: ${await problem(response)}`);
+      return;
+    }
+    if (apiKey) localStorage.setItem('rustbin.api-key', apiKey);
+    const created = await response.json();
+    if (created.token) localStorage.setItem(`rustbin.token.${created.bin_id}`, created.token);
+    if (created.creator_token) localStorage.setItem('rustbin.creator-token', created.creator_token);
+    location.href = `${BASE}/ui/bin/${encodeURIComponent(created.bin_id)}`;
+  });
+
+  $('open-form').addEventListener('submit', (event) => {
+    event.preventDefault();
+    location.href = `${BASE}/ui/bin/${encodeURIComponent($('open-id').value.trim())}`;
+  });
+}
+
+// ---- Bin view ----
+
+class BinView {
+  constructor(binId) {
+    this.binId = binId;
+    this.token = tokenFor(binId);
+    this.requests = new Map();
+    this.selected = null;
+    this.socket = null;
+  }
+
+  api(path, options = {}) {
+    const headers = { ...(options.headers || {}) };
+    if (this.token) headers.Authorization = `Bearer ${this.token}`;
+    return fetch(`${BASE}/bin/${encodeURIComponent(this.binId)}${path}`, { ...options, headers });
+  }
+
+  async start() {
+    $('bin').hidden = false;
+    document.title = `rustbin · ${this.binId}`;
+    const url = `${location.origin}${BASE}/bin/${this.binId}`;
+    $('bin-url').textContent = url;
+    $('copy-url').addEventListener('click', () => navigator.clipboard.writeText(url));
+    $('clear').addEventListener('click', () => this.clear());
+    $('token-form').addEventListener('submit', (event) => {
+      event.preventDefault();
+      this.token = $('token').value.trim();
+      $('token-form').hidden = true;
+      this.load();
+    });
+    await this.load();
+  }
+
+  async load() {
+    // Inspect pages oldest first, so count first and fetch the last page
+    let response = await this.api('/inspect?limit=1');
+    if (response.status === 401) {
+      $('token-form').hidden = false;
+      return;
+    }
+    if (!response.ok) {
+      show($('bin-error'), response.status === 404 ? 'This bin does not exist.' : `Could not load the bin: ${response.status}`);
+      return;
+    }
+    if (this.token) localStorage.setItem(`rustbin.token.${this.binId}`, this.token);
+    remember(this.binId);
+
+    const total = Number(response.headers.get('X-Total-Count') || 0);
+    response = await this.api(`/inspect?limit=${HISTORY}&offset=${Math.max(0, total - HISTORY)}`);
+    if (response.ok) {
+      for (const request of await response.json()) {
+        this.add(normalize(request));
+      }
+    }
+    this.connect();
+  }
+
+  connect() {
+    const scheme = location.protocol === 'https:' ? 'wss' : 'ws';
+    const query = this.token ? `?token=${encodeURIComponent(this.token)}` : '';
+    const socket = new WebSocket(`${scheme}://${location.host}${BASE}/bin/${encodeURIComponent(this.binId)}/ws${query}`);
+    this.socket = socket;
+    socket.onopen = () => status('live', 'Live');
+    socket.onmessage = (event) => this.receive(JSON.parse(event.data));
+    socket.onclose = () => {
+      if (this.socket !== socket) return;
+      status('offline', 'Reconnecting…');
+      setTimeout(() => this.connect(), 2000);
+    };
+  }
+
+  receive(message) {
+    switch (message.type) {
+      case 'request':
+        this.add(message.data);
+        break;
+      case 'request_deleted':
+        this.remove(message.data.request_id);
+        break;
+      case 'requests_cleared':
+        this.reset();
+        break;
+      case 'bin_expiring':
+        status('warning', `Expires ${message.data.expires_at ? new Date(message.data.expires_at).toLocaleString() : 'soon'}`);
+        break;
+      case 'bin_deleted':
+        this.socket = null;
+        status('offline', message.data.expired ? 'Bin expired' : 'Bin deleted');
+        break;
+    }
+  }
+
+  add(request) {
+    // History replayed on connect repeats what inspect returned
+    if (this.requests.has(request.request_id)) return;
+    this.requests.set(request.request_id, request);
+
+    const item = document.createElement('li');
+    item.dataset.id = request.request_id;
+    const method = document.createElement('span');
+    method.className = `method method-${request.method.toLowerCase()}`;
+    method.textContent = request.method;
+    const path = document.createElement('span');
+    path.className = 'path';
+    path.textContent = (request.path || '/') + (request.query_string ? `?${request.query_string}` : '');
+    const time = document.createElement('time');
+    time.textContent = new Date(request.timestamp).toLocaleTimeString();
+    item.append(method, path, time);
+    item.addEventListener('click', () => this.select(request.request_id));
+    $('requests').prepend(item);
+
+    this.updateCount();
+    if (!this.selected) this.select(request.request_id);
+  }
+
+  remove(requestId) {
+    this.requests.delete(requestId);
+    $('requests').querySelector(`[data-id="${requestId}"]`)?.remove();
+    if (this.selected === requestId) this.showEmpty();
+    this.updateCount();
+  }
+
+  reset() {
+    this.requests.clear();
+    $('requests').replaceChildren();
+    this.showEmpty();
+    this.updateCount();
+  }
+
+  async clear() {
+    if (!confirm('Delete every request in this bin?')) return;
+    const response = await this.api('/clear', { method: 'DELETE' });
+    if (response.ok) this.reset();
+  }
+
+  updateCount() {
+    const count = this.requests.size;
+    $('count').textContent = `${count} request${count === 1 ? '' : 's'}`;
+  }
+
+  showEmpty() {
+    this.selected = null;
+    const empty = document.createElement('p');
+    empty.className = 'empty';
+    empty.textContent = 'Waiting for requests…';
+    $('detail').replaceChildren(empty);
+  }
+
+  select(requestId) {
+    this.selected = requestId;
+    for (const item of $('requests').children) {
+      item.classList.toggle('selected', item.dataset.id === requestId);
+    }
+    $('detail').replaceChildren(...renderRequest(this.requests.get(requestId)));
+  }
+}
+
+// Inspect returns stored requests, WebSocket messages carry parsed headers and query params
+function normalize(request) {
+  let headers = request.headers;
+  if (typeof headers === 'string') {
+    try {
+      headers = JSON.parse(headers);
+    } catch {
+      headers = [];
+    }
+  }
+  let queryParams = request.query_params;
+  if (typeof queryParams === 'string') {
+    try {
+      queryParams = JSON.parse(queryParams);
+    } catch {
+      queryParams = null;
+    }
+  }
+  return { ...request, headers, query_params: queryParams };
+}
+
+function renderRequest(request) {
+  const nodes = [];
+  const title = document.createElement('h2');
+  title.textContent = `${request.method} ${request.path || '/'}`;
+  nodes.push(title);
+
+  const facts = [new Date(request.timestamp).toLocaleString()];
+  if (request.source_ip) facts.push(`from ${request.source_ip}`);
+  if (request.signature_valid === true) facts.push('signature valid');
+  if (request.signature_valid === false) facts.push('signature invalid');
+  if (request.content_type_mismatch) facts.push('body does not match Content-Type');
+  const meta = document.createElement('p');
+  meta.className = 'meta';
+  meta.textContent = facts.join(' · ');
+  nodes.push(meta);
+
+  if (request.query_params && Object.keys(request.query_params).length) {
+    nodes.push(section('Query', table(Object.entries(request.query_params))));
+  }
+  if (request.fields && Object.keys(request.fields).length) {
+    nodes.push(section('Fields', table(Object.entries(request.fields))));
+  }
+  nodes.push(section('Headers', table(request.headers)));
+  nodes.push(section('Body', body(request)));
+  return nodes;
+}
+
+function section(name, content) {
+  const element = document.createElement('section');
+  const heading = document.createElement('h3');
+  heading.textContent = name;
+  element.append(heading, content);
+  return element;
+}
+
+function table(rows) {
+  const element = document.createElement('table');
+  for (const [name, value] of rows) {
+    const row = element.insertRow();
+    row.insertCell().textContent = name;
+    row.insertCell().textContent = typeof value === 'string' ? value : JSON.stringify(value);
+  }
+  return element;
+}
+
+function body(request) {
+  const pre = document.createElement('pre');
+  if (!request.body) {
+    pre.className = 'empty';
+    pre.textContent = '(empty)';
+  } else if (request.body_encoding === 'base64') {
+    pre.textContent = `(binary, ${Math.floor((request.body.length * 3) / 4)} bytes base64-encoded)`;
+  } else {
+    pre.textContent = pretty(request.body);
+  }
+  return pre;
+}
+
+function pretty(text) {
+  try {
+    return JSON.stringify(JSON.parse(text), null, 2);
+  } catch {
+    return text;
+  }
+}
+
+function status(kind, text) {
+  const element = $('status');
+  element.className = `status status-${kind}`;
+  show(element, text);
+}
+
+const match = location.pathname.slice(BASE.length).match(/^\/ui\/bin\/([^/]+)\/?$/);
+if (match) {
+  new BinView(decodeURIComponent(match[1])).start();
+} else {
+  home();
+}